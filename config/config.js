@@ -1,15 +1,8 @@
 // WebWM Configuration
 // This file defines keybindings, window rules, and custom behavior
 
-// Compositor API (would be injected by the Rust backend)
-const wm = {
-  spawn: (cmd) => console.log(`Spawn: ${cmd}`),
-  close: () => console.log('Close window'),
-  focus: (dir) => console.log(`Focus: ${dir}`),
-  moveToWorkspace: (ws) => console.log(`Move to workspace: ${ws}`),
-  toggleFloating: () => console.log('Toggle floating'),
-  reload: () => console.log('Reload config'),
-};
+// The compositor API is the global `wm` object. Saving this file,
+// desktop.xml or style.css reloads the configuration.
 
 // ============================================================================
 // KEYBINDINGS
@@ -26,11 +19,43 @@ keybind(`${MOD}+Return`, () => wm.spawn('alacritty'));
 keybind(`${MOD}+d`, () => wm.spawn('rofi -show drun'));
 keybind(`${MOD}+w`, () => wm.spawn('firefox'));
 keybind(`${MOD}+e`, () => wm.spawn('thunar'));
+// Chords: Super+a, then the keys after it, one at a time
+keybind(`${MOD}+a, f`, () => wm.spawn('firefox'));
+keybind(`${MOD}+a, t`, () => wm.spawn('alacritty'));
+keybind(`${MOD}+a, s, p`, () => wm.spawn('pavucontrol'));
+
+// Launch or focus: jump to the open browser, or start one and move it to 'web'
+keybind(`${MOD}+b`, () => wm.execFocus('firefox', { appId: 'firefox', workspace: 2 }));
+// Run or raise: focus the editor wherever it is (again to cycle), or start it
+keybind(`${MOD}+c`, () => wm.runOrRaise('code', 'code'));
+
+// Presentation mode for talks and screen sharing: no notifications, no
+// screen locking, no bar (see <presentation-mode> in desktop.xml)
+keybind(`${MOD}+p`, () => wm.togglePresentationMode());
+// Zoom the whole output to the focused window, to share just that app
+keybind(`${MOD}+z`, () => wm.toggleOutputZoom());
+// Hide or show the bar on the output under the pointer
+keybind(`${MOD}+${SHIFT}+b`, () => wm.toggleBar());
 
 // Window management
 keybind(`${MOD}+q`, () => wm.close());
+keybind(`${MOD}+${SHIFT}+q`, () => wm.forceClose()); // Kill a client that stopped responding
+keybind(`${MOD}+${SHIFT}+t`, () => wm.reopenLast()); // Bring back the last closed window
 keybind(`${MOD}+f`, () => wm.toggleFloating());
 keybind(`${MOD}+m`, () => wm.toggleMaximize());
+keybind(`${MOD}+${SHIFT}+c`, () => wm.centerWindow()); // Bring back a floating window dragged away
+keybind(`${MOD}+${SHIFT}+m`, () => wm.windowMenu()); // Also on right-clicking a tab title
+keybind(`${MOD}+semicolon`, () => wm.windowHints()); // Label the windows, type one to focus it
+keybind(`${MOD}+Page_Up`, () => wm.raiseWindow());
+keybind(`${MOD}+Page_Down`, () => wm.lowerWindow());
+keybind(`${MOD}+o`, () => wm.raiseOrLowerWindow());
+
+// Tab groups: merge the focused window into the tile on its right, split it out again
+keybind(`${MOD}+g`, () => wm.mergeWindow('right'));
+keybind(`${MOD}+${SHIFT}+g`, () => wm.splitWindow());
+
+// Screenshot of a region picked on screen ({geometry} is "x,y wxh")
+keybind(`${MOD}+${SHIFT}+s`, () => wm.selectRegion('grim -g "{geometry}" "$HOME/Pictures/screenshot-$(date +%Y%m%d-%H%M%S).png"'));
 
 // Focus management
 keybind(`${MOD}+h`, () => wm.focus('left'));
@@ -38,11 +63,34 @@ keybind(`${MOD}+j`, () => wm.focus('down'));
 keybind(`${MOD}+k`, () => wm.focus('up'));
 keybind(`${MOD}+l`, () => wm.focus('right'));
 
-// Move windows
-keybind(`${MOD}+${SHIFT}+h`, () => wm.moveWindow('left'));
-keybind(`${MOD}+${SHIFT}+j`, () => wm.moveWindow('down'));
-keybind(`${MOD}+${SHIFT}+k`, () => wm.moveWindow('up'));
-keybind(`${MOD}+${SHIFT}+l`, () => wm.moveWindow('right'));
+// Focus the output to the left or right (the pointer follows with
+// warp-to-output in desktop.xml)
+keybind(`${MOD}+comma`, () => wm.focusOutput('left'));
+keybind(`${MOD}+period`, () => wm.focusOutput('right'));
+
+// Swap the focused window with its neighbor
+keybind(`${MOD}+${SHIFT}+h`, () => wm.swapWindow('left'));
+keybind(`${MOD}+${SHIFT}+j`, () => wm.swapWindow('down'));
+keybind(`${MOD}+${SHIFT}+k`, () => wm.swapWindow('up'));
+keybind(`${MOD}+${SHIFT}+l`, () => wm.swapWindow('right'));
+
+// Resize the focused tile toward a side (or Super+right-drag it)
+keybind(`${MOD}+${CTRL}+h`, () => wm.resizeTiled('left', 0.05));
+keybind(`${MOD}+${CTRL}+j`, () => wm.resizeTiled('down', 0.05));
+keybind(`${MOD}+${CTRL}+k`, () => wm.resizeTiled('up', 0.05));
+keybind(`${MOD}+${CTRL}+l`, () => wm.resizeTiled('right', 0.05));
+
+// Or resize with plain keys in resize mode, shown in the bar until Escape
+// or Return goes back to the usual keybindings. Keys a mode doesn't bind
+// are ignored.
+wm.mode('resize', {
+  'h': () => wm.resizeTiled('left', 0.05),
+  'j': () => wm.resizeTiled('down', 0.05),
+  'k': () => wm.resizeTiled('up', 0.05),
+  'l': () => wm.resizeTiled('right', 0.05),
+  'Return': () => wm.exitMode(),
+});
+keybind(`${MOD}+${ALT}+r`, () => wm.enterMode('resize'));
 
 // Workspace switching
 for (let i = 1; i <= 9; i++) {
@@ -56,6 +104,23 @@ keybind(`${MOD}+${SHIFT}+Tab`, () => wm.cycleWorkspacePrev());
 
 // Layout controls
 keybind(`${MOD}+t`, () => wm.setLayout('tiling'));
+// Master-stack: promote the focused window, grow or shrink the master
+keybind(`${MOD}+${SHIFT}+Return`, () => wm.promoteToMaster());
+keybind(`${MOD}+bracketleft`, () => wm.adjustMasterRatio(-0.05));
+keybind(`${MOD}+bracketright`, () => wm.adjustMasterRatio(0.05));
+// Dwindle: resize the focused tile's split, or turn it the other way
+keybind(`${MOD}+${CTRL}+bracketleft`, () => wm.adjustSplitRatio(-0.05));
+keybind(`${MOD}+${CTRL}+bracketright`, () => wm.adjustSplitRatio(0.05));
+keybind(`${MOD}+r`, () => wm.rotateSplit());
+// Pick how the focused tile splits for the next window, like i3's splith
+// and splitv (Super+h is taken by focus)
+keybind(`${MOD}+${SHIFT}+v`, () => wm.splitHorizontal());
+keybind(`${MOD}+v`, () => wm.splitVertical());
+// Gaps between and around tiles
+keybind(`${MOD}+minus`, () => wm.adjustGaps(-5, -5));
+keybind(`${MOD}+equal`, () => wm.adjustGaps(5, 5));
+keybind(`${MOD}+${SHIFT}+minus`, () => wm.adjustGaps(0, -5));
+keybind(`${MOD}+${SHIFT}+equal`, () => wm.adjustGaps(0, 5));
 keybind(`${MOD}+space`, () => wm.cycleLayout());
 
 // System
@@ -66,6 +131,10 @@ keybind(`${MOD}+${SHIFT}+e`, () => wm.exit());
 // WINDOW RULES
 // ============================================================================
 
+// Rules like <rule> in desktop.xml, with the attributes in camelCase
+// (appId, contentType, confirmClose, ...); desktop.xml's come first
+windowRule({ appId: 'pavucontrol', floating: true });
+
 // Rule: Firefox always goes to workspace 2
 onWindowCreate((window) => {
   if (window.appId === 'firefox') {
@@ -137,6 +206,25 @@ onWindowUrgent((window) => {
   });
 });
 
+// Docking: restart the external bar when a monitor comes or goes
+wm.onOutputChange((event) => {
+  console.log(`Output ${event.output.name} ${event.change}, ${event.outputs.length} connected`);
+  if (event.change !== 'changed') {
+    wm.spawn('pkill waybar; waybar');
+  }
+});
+
+// Power: dim the screen when the charger is unplugged or the battery runs low
+wm.on('power', (event) => {
+  if (event.change === 'unplugged') {
+    wm.spawn('brightnessctl set 60%');
+  } else if (event.change === 'plugged') {
+    wm.spawn('brightnessctl set 100%');
+  } else if (event.on_battery && event.threshold <= 10) {
+    wm.spawn(`notify-send "Battery at ${Math.round(event.percentage)}%"`);
+  }
+});
+
 // ============================================================================
 // STARTUP COMMANDS
 // ============================================================================
@@ -158,4 +246,4 @@ onStartup(() => {
 });
 
 // HELPER FUNCTIONS ARE PROVIDED BY RUST BACKEND
-// keybind(), onWindowCreate(), onMouseEnter(), onMouseLeave(), onLayoutChange(), onWindowUrgent(), onStartup(), notify()
+// keybind(), wm.mode(), onWindowCreate(), onMouseEnter(), onMouseLeave(), onLayoutChange(), onWindowUrgent(), onStartup(), notify()
@@ -0,0 +1,27 @@
+#!/bin/bash
+# Install WebWM so it shows up in display managers
+#
+# Usage: sudo scripts/install-session.sh [PREFIX]   (default: /usr/local)
+
+set -e
+
+PREFIX="${1:-/usr/local}"
+ROOT="$(cd "$(dirname "$0")/.." && pwd)"
+
+if [ ! -x "$ROOT/target/release/webwm" ]; then
+    echo "Release build not found, run 'cargo build --release' first"
+    exit 1
+fi
+
+install -Dm755 "$ROOT/target/release/webwm" "$PREFIX/bin/webwm"
+install -Dm755 "$ROOT/target/release/webwm-msg" "$PREFIX/bin/webwm-msg"
+install -Dm755 "$ROOT/scripts/webwm-session" "$PREFIX/bin/webwm-session"
+
+# Display managers only look in /usr/share/wayland-sessions
+install -Dm644 "$ROOT/webwm.desktop" /usr/share/wayland-sessions/webwm.desktop
+
+echo "✓ Installed webwm, webwm-msg and webwm-session to $PREFIX/bin"
+echo "✓ Installed session file to /usr/share/wayland-sessions/webwm.desktop"
+echo ""
+echo "For greetd, set the command in /etc/greetd/config.toml:"
+echo "  command = \"tuigreet --cmd webwm-session\""
@@ -0,0 +1,279 @@
+// Benchmark mode (`webwm --bench [N]`)
+//
+// Runs on the headless backend, maps N synthetic SHM clients, then churns
+// through workspace switches, window moves and layout mode changes while
+// timing every relayout. Frame timings come from the render profiler.
+
+use serde::Serialize;
+use smithay::reexports::calloop::{
+    timer::{TimeoutAction, Timer},
+    LoopHandle, LoopSignal,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::compositor::WebWMCompositor;
+use crate::fake_client::{FakeClient, FakeClientOptions};
+
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    pub windows: usize,
+    pub iterations: usize,
+    /// Write the report as JSON to this file
+    pub output: Option<String>,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            windows: 16,
+            iterations: 500,
+            output: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub windows: usize,
+    pub iterations: usize,
+    pub map_time_ms: f64,
+    pub layout_avg_us: f64,
+    pub layout_max_us: f64,
+    pub layouts_per_second: f64,
+    pub frames: u64,
+    pub frame_avg_us: f64,
+    pub frame_max_us: f64,
+}
+
+enum Phase {
+    WaitingForWindows,
+    Churning { iteration: usize },
+}
+
+struct BenchState {
+    options: BenchOptions,
+    phase: Phase,
+    started: Instant,
+    map_time: Duration,
+    layout_times: Vec<Duration>,
+    frames_at_start: u64,
+    stop_clients: Arc<AtomicBool>,
+    signal: LoopSignal,
+}
+
+/// Spawn the synthetic clients and schedule the churn driver on the event loop
+pub fn start(
+    loop_handle: &LoopHandle<'static, WebWMCompositor>,
+    signal: LoopSignal,
+    socket_name: &str,
+    options: BenchOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!(
+        "⏱️  Benchmark: {} windows, {} iterations",
+        options.windows, options.iterations
+    );
+
+    let stop_clients = Arc::new(AtomicBool::new(false));
+    spawn_clients(
+        socket_name.to_string(),
+        options.windows,
+        stop_clients.clone(),
+    );
+
+    let mut state = BenchState {
+        options,
+        phase: Phase::WaitingForWindows,
+        started: Instant::now(),
+        map_time: Duration::ZERO,
+        layout_times: Vec::new(),
+        frames_at_start: 0,
+        stop_clients,
+        signal,
+    };
+
+    loop_handle.insert_source(
+        Timer::from_duration(Duration::from_millis(1)),
+        move |_, _, compositor| {
+            if state.step(compositor) {
+                TimeoutAction::ToDuration(Duration::from_millis(1))
+            } else {
+                TimeoutAction::Drop
+            }
+        },
+    )?;
+
+    Ok(())
+}
+
+fn spawn_clients(socket_name: String, count: usize, stop: Arc<AtomicBool>) {
+    thread::spawn(move || {
+        let mut clients = Vec::with_capacity(count);
+
+        for i in 0..count {
+            let options = FakeClientOptions {
+                app_id: format!("bench-{}", i),
+                title: format!("Bench window {}", i),
+                color: [(i * 37 % 255) as u8, (i * 91 % 255) as u8, 0xc0],
+                ..Default::default()
+            };
+
+            match FakeClient::connect(&socket_name, options) {
+                Ok(client) => clients.push(client),
+                Err(e) => {
+                    eprintln!("Benchmark client {} failed: {}", i, e);
+                    return;
+                }
+            }
+        }
+
+        // Keep acking configures until the benchmark is done
+        while !stop.load(Ordering::Relaxed) {
+            for client in &mut clients {
+                if let Err(e) = client.dispatch_pending() {
+                    eprintln!("Benchmark client error: {}", e);
+                    return;
+                }
+            }
+            thread::sleep(Duration::from_millis(2));
+        }
+    });
+}
+
+impl BenchState {
+    /// Returns false once the benchmark is finished
+    fn step(&mut self, compositor: &mut WebWMCompositor) -> bool {
+        match self.phase {
+            Phase::WaitingForWindows => {
+                let mapped: usize = compositor
+                    .workspace_manager
+                    .all_workspaces()
+                    .iter()
+                    .map(|ws| ws.len())
+                    .sum();
+
+                if mapped >= self.options.windows {
+                    self.map_time = self.started.elapsed();
+                    self.frames_at_start = compositor.profiler.frame().samples;
+                    println!(
+                        "  ✓ {} windows mapped in {:.1}ms",
+                        mapped,
+                        self.map_time.as_secs_f64() * 1000.0
+                    );
+                    self.phase = Phase::Churning { iteration: 0 };
+                } else if self.started.elapsed() > Duration::from_secs(30) {
+                    eprintln!(
+                        "❌ Benchmark timed out: only {}/{} windows mapped",
+                        mapped, self.options.windows
+                    );
+                    self.finish(compositor, false);
+                    return false;
+                }
+                true
+            }
+            Phase::Churning { iteration } => {
+                if iteration >= self.options.iterations {
+                    self.finish(compositor, true);
+                    return false;
+                }
+
+                churn(compositor, iteration);
+
+                let start = Instant::now();
+                compositor.relayout();
+                self.layout_times.push(start.elapsed());
+
+                self.phase = Phase::Churning {
+                    iteration: iteration + 1,
+                };
+                true
+            }
+        }
+    }
+
+    fn finish(&mut self, compositor: &WebWMCompositor, success: bool) {
+        self.stop_clients.store(true, Ordering::Relaxed);
+        self.signal.stop();
+
+        if !success {
+            return;
+        }
+
+        let report = self.report(compositor);
+        print_report(&report);
+
+        if let Some(ref path) = self.options.output {
+            match serde_json::to_string_pretty(&report) {
+                Ok(json) => match std::fs::write(path, json) {
+                    Ok(()) => println!("\n✓ Saved benchmark report to: {}", path),
+                    Err(e) => eprintln!("Failed to write {}: {}", path, e),
+                },
+                Err(e) => eprintln!("Failed to serialize report: {}", e),
+            }
+        }
+    }
+
+    fn report(&self, compositor: &WebWMCompositor) -> BenchReport {
+        let total: Duration = self.layout_times.iter().sum();
+        let max = self.layout_times.iter().max().copied().unwrap_or_default();
+        let count = self.layout_times.len().max(1) as f64;
+        let frame = compositor.profiler.frame();
+
+        BenchReport {
+            windows: self.options.windows,
+            iterations: self.layout_times.len(),
+            map_time_ms: self.map_time.as_secs_f64() * 1000.0,
+            layout_avg_us: total.as_secs_f64() * 1_000_000.0 / count,
+            layout_max_us: max.as_secs_f64() * 1_000_000.0,
+            layouts_per_second: if total.is_zero() {
+                0.0
+            } else {
+                count / total.as_secs_f64()
+            },
+            frames: frame.samples.saturating_sub(self.frames_at_start),
+            frame_avg_us: frame.average.as_secs_f64() * 1_000_000.0,
+            frame_max_us: frame.max.as_secs_f64() * 1_000_000.0,
+        }
+    }
+}
+
+/// One step of layout/workspace churn, cycling through the operations
+/// users trigger most often
+fn churn(compositor: &mut WebWMCompositor, iteration: usize) {
+    let manager = &mut compositor.workspace_manager;
+
+    match iteration % 4 {
+        0 => manager.cycle_workspace_next(),
+        1 => manager.cycle_workspace_prev(),
+        2 => manager.toggle_floating(),
+        _ => {
+            // Move the focused window away and straight back
+            let active = manager.active_workspace_id();
+            if let Some(window) = manager.focused_window().cloned() {
+                let other = if active == 1 { 2 } else { 1 };
+                manager.move_window_to_workspace(window.clone(), other);
+                manager.move_window_to_workspace(window, active);
+            }
+        }
+    }
+}
+
+fn print_report(report: &BenchReport) {
+    println!("\n===========================================");
+    println!("  Benchmark Results");
+    println!("===========================================");
+    println!("  • Windows: {}", report.windows);
+    println!("  • Iterations: {}", report.iterations);
+    println!("  • Map time: {:.1}ms", report.map_time_ms);
+    println!(
+        "  • Layout: {:.1}µs avg, {:.1}µs max ({:.0} layouts/s)",
+        report.layout_avg_us, report.layout_max_us, report.layouts_per_second
+    );
+    println!(
+        "  • Render: {} frames, {:.1}µs avg, {:.1}µs max",
+        report.frames, report.frame_avg_us, report.frame_max_us
+    );
+    println!("===========================================\n");
+}
@@ -0,0 +1,298 @@
+// webwm-msg - send a command to a running WebWM instance over its IPC socket
+//
+// Usage: webwm-msg <command> [args...]
+// The socket is taken from $WEBWM_SOCK, which the compositor exports
+// to every process it spawns.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::{json, Value};
+use std::env;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.is_empty() || args[0] == "--help" || args[0] == "-h" {
+        println!("Usage: webwm-msg <command> [args...]");
+        println!();
+        println!("Commands:");
+        println!("  version                 Show compositor version");
+        println!("  perf                    Show per-stage render timings");
+        println!("  trace [SECONDS] [FILE]  Record frames, render stages and event loop dispatch");
+        println!("                          for SECONDS (5), then write a Chrome trace to FILE");
+        println!("                          for Perfetto or chrome://tracing");
+        println!("  frame-stats             Show per-output frame pacing statistics");
+        println!(
+            "  metrics                 Show frame, client and bar metrics in Prometheus format"
+        );
+        println!("  debug-overlay [on|off]  Toggle the on-screen debug overlay");
+        println!("  presentation-mode [on|off]");
+        println!("                          Toggle do-not-disturb, idle inhibition and the bar");
+        println!("  debug-bundle [FILE]     Write a .tar.gz with config, window tree, outputs,");
+        println!("                          clients and recent logs for bug reports");
+        println!("  logs [--follow] [--level LEVEL]");
+        println!("                          Show recent log records");
+        println!("  profile-list            Show saved monitor profiles and the current layout");
+        println!("  profile-save NAME       Save the current monitor layout as a profile");
+        println!("  profile-apply NAME      Apply a saved monitor profile");
+        println!("  profile-remove NAME     Delete a saved monitor profile");
+        println!("  template-list           Show saved workspace templates");
+        println!("  template-save NAME      Save the current workspace's layout and apps");
+        println!("  template-apply NAME [WORKSPACE]");
+        println!("                          Launch a template into an empty workspace");
+        println!("  template-remove NAME    Delete a saved workspace template");
+        println!("  merge [left|right]      Merge the focused window into a neighbor's tab group");
+        println!("  split                   Split the focused window out of its tab group");
+        println!("  select-region [COMMAND] Pick a screen region, then run COMMAND with");
+        println!("                          {{geometry}} replaced by the region");
+        println!("  clients                 Show surfaces, buffer memory and frame callback");
+        println!("                          rates per client");
+        println!("  clear-cache             Delete cached icons and thumbnails");
+        println!("  force-close             Disconnect the focused window's client, even if hung");
+        println!("  mute                    Mute or unmute the audio of the focused window");
+        println!("  keybind-list            Show keybindings and their actions");
+        println!("  keybind-set COMBO JSON  Bind COMBO to an action, e.g.");
+        println!("                          keybind-set Super+b '{{\"type\":\"Spawn\",\"command\":\"firefox\"}}'");
+        println!("  keybind-remove COMBO    Remove a keybinding");
+        println!("  theme                   Show the stylesheet's theme variables");
+        println!(
+            "  theme-set NAME VALUE    Change a theme variable, e.g. theme-set --accent #f38ba8"
+        );
+        println!("  bar-list                Show bars and their widgets");
+        println!("  bar-widgets BAR JSON    Replace a bar's widgets with a JSON array");
+        println!("  bar-visible OUTPUT [on|off]");
+        println!("                          Show or hide the bars on one output");
+        println!(
+            "  subscribe [EVENT...]    Print events (lid, tablet-mode, output, ...) as they happen"
+        );
+        return;
+    }
+
+    let socket_path = match env::var("WEBWM_SOCK") {
+        Ok(path) => path,
+        Err(_) => {
+            eprintln!("WEBWM_SOCK is not set. Is WebWM running?");
+            process::exit(1);
+        }
+    };
+
+    if args[0] == "logs" {
+        run_logs(&socket_path, &args[1..]);
+        return;
+    }
+
+    if args[0] == "subscribe" {
+        if let Err(e) = run_subscribe(&socket_path, &args[1..]) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    // The compositor writes the trace, from its own working directory
+    let mut args = args;
+    if args[0] == "trace" {
+        if let Some(file) = args.get_mut(2) {
+            if let Ok(dir) = env::current_dir() {
+                *file = dir.join(&*file).to_string_lossy().into_owned();
+            }
+        }
+    }
+
+    let data = match send_request(&socket_path, &args[0], &args[1..]) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if args[0] == "debug-bundle" {
+        let path = args.get(1).cloned().unwrap_or_else(default_bundle_name);
+        if let Err(e) = write_debug_bundle(&path, &data) {
+            eprintln!("Failed to write {}: {}", path, e);
+            process::exit(1);
+        }
+        println!("Debug bundle written to {}", path);
+    } else if args[0] == "metrics" {
+        print!("{}", data["text"].as_str().unwrap_or_default());
+    } else if !data.is_null() {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&data).unwrap_or_default()
+        );
+    }
+}
+
+/// Send one request and return the response data
+fn send_request(socket_path: &str, command: &str, args: &[String]) -> Result<Value, String> {
+    let mut stream = UnixStream::connect(socket_path)
+        .map_err(|e| format!("Failed to connect to {}: {}", socket_path, e))?;
+
+    let request = json!({
+        "command": command,
+        "args": args,
+    });
+    writeln!(stream, "{}", request).map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+
+    let response: Value =
+        serde_json::from_str(&line).map_err(|e| format!("Invalid response: {}", e))?;
+    if response["success"] == json!(false) {
+        return Err(response["error"]
+            .as_str()
+            .unwrap_or("unknown error")
+            .to_string());
+    }
+
+    Ok(response["data"].clone())
+}
+
+/// `logs [--follow] [--level LEVEL]`: print buffered log records, and with
+/// `--follow` keep polling for new ones
+fn run_logs(socket_path: &str, args: &[String]) {
+    let follow = args.iter().any(|arg| arg == "--follow" || arg == "-f");
+    let level = args
+        .iter()
+        .position(|arg| arg == "--level")
+        .and_then(|i| args.get(i + 1));
+
+    let mut since: Option<u64> = None;
+    loop {
+        let mut request_args = Vec::new();
+        if let Some(level) = level {
+            request_args.push("--level".to_string());
+            request_args.push(level.clone());
+        }
+        if let Some(seq) = since {
+            request_args.push("--since".to_string());
+            request_args.push(seq.to_string());
+        }
+
+        let data = match send_request(socket_path, "logs", &request_args) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        };
+
+        for entry in data["entries"].as_array().into_iter().flatten() {
+            let uptime_ms = entry["uptime_ms"].as_u64().unwrap_or(0);
+            println!(
+                "[{:>6}.{:03} {:<5} {}] {}",
+                uptime_ms / 1000,
+                uptime_ms % 1000,
+                entry["level"].as_str().unwrap_or(""),
+                entry["target"].as_str().unwrap_or(""),
+                entry["message"].as_str().unwrap_or("")
+            );
+            since = entry["seq"].as_u64().or(since);
+        }
+
+        if !follow {
+            break;
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+/// `subscribe [EVENT...]`: keep the connection open and print one JSON
+/// line per event until the compositor exits
+fn run_subscribe(socket_path: &str, events: &[String]) -> Result<(), String> {
+    let mut stream = UnixStream::connect(socket_path)
+        .map_err(|e| format!("Failed to connect to {}: {}", socket_path, e))?;
+
+    let request = json!({
+        "command": "subscribe",
+        "args": events,
+    });
+    writeln!(stream, "{}", request).map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let mut lines = BufReader::new(&stream).lines();
+    let ack = lines
+        .next()
+        .ok_or("Connection closed")?
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    let response: Value =
+        serde_json::from_str(&ack).map_err(|e| format!("Invalid response: {}", e))?;
+    if response["success"] == json!(false) {
+        return Err(response["error"]
+            .as_str()
+            .unwrap_or("unknown error")
+            .to_string());
+    }
+
+    for line in lines {
+        let line = line.map_err(|e| format!("Failed to read event: {}", e))?;
+        println!("{}", line);
+    }
+
+    Ok(())
+}
+
+fn default_bundle_name() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    format!("webwm-debug-{}.tar.gz", secs)
+}
+
+/// Pack the compositor's debug snapshot into a gzipped tarball
+fn write_debug_bundle(path: &str, data: &Value) -> std::io::Result<()> {
+    let file = File::create(path)?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let json_file = |key: &str| serde_json::to_vec_pretty(&data[key]).unwrap_or_default();
+    let log: String = data["log"]
+        .as_array()
+        .map(|lines| {
+            lines
+                .iter()
+                .filter_map(|line| line.as_str())
+                .map(|line| format!("{}\n", line))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let entries = [
+        ("version.json", json_file("version")),
+        ("config.json", json_file("config")),
+        ("window-tree.json", json_file("window_tree")),
+        ("outputs.json", json_file("outputs")),
+        ("clients.json", json_file("clients")),
+        ("log.txt", log.into_bytes()),
+    ];
+
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    for (name, contents) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        header.set_cksum();
+        archive.append_data(
+            &mut header,
+            format!("webwm-debug/{}", name),
+            contents.as_slice(),
+        )?;
+    }
+
+    archive.into_inner()?.finish()?;
+    Ok(())
+}
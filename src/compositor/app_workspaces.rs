@@ -0,0 +1,137 @@
+// Workspace per app
+//
+// Windows of an app_id assigned to a workspace, with `<assign>` in its
+// `<workspace>` entry or a window rule's `workspace`, go there as soon as
+// the app_id is known, without switching to it.
+//
+// With `<workspaces per-app="true">` in desktop.xml, every app gets a
+// workspace of its own: the first window of an app_id creates a workspace
+// named after it, and later windows of that app join it. Windows that a
+// window rule already sends somewhere stay out of it. A JS function
+// registered with `setWorkspaceGrouper(fn)` can put several apps together:
+// it gets `{ appId, title }` and returns the name of the group (and its
+// workspace), or nothing for the app's own.
+
+use serde_json::json;
+use smithay::desktop::Window;
+
+use crate::compositor::workspace::{LayoutMode, Workspace};
+use crate::compositor::{window_app_id, window_title, WebWMCompositor};
+
+impl WebWMCompositor {
+    pub fn per_app_workspaces_enabled(&self) -> bool {
+        self.config
+            .desktop
+            .as_ref()
+            .is_some_and(|desktop| desktop.workspace_per_app)
+    }
+
+    /// Group `window` goes in: from the JS grouper, else its app_id
+    fn app_group(&self, window: &Window, app_id: &str) -> String {
+        let info = json!({
+            "appId": app_id,
+            "title": window_title(window),
+        });
+        self.js_runtime
+            .as_ref()
+            .and_then(|js_runtime| {
+                js_runtime.workspace_group(&info).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    None
+                })
+            })
+            .filter(|group| !group.is_empty())
+            .unwrap_or_else(|| app_id.to_string())
+    }
+
+    /// Workspace of `group`, created after the last one if it doesn't
+    /// exist yet
+    fn app_workspace(&mut self, group: &str) -> u32 {
+        if let Some(id) = self
+            .app_workspaces
+            .get(group)
+            .copied()
+            .filter(|id| self.workspace_manager.get_workspace(*id).is_some())
+        {
+            return id;
+        }
+
+        let id = self
+            .workspace_manager
+            .all_workspaces()
+            .iter()
+            .map(|workspace| workspace.id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        println!("🗂️ New workspace {} for '{}'", id, group);
+        self.workspace_manager.add_workspace(Workspace::new(
+            id,
+            group.to_string(),
+            LayoutMode::Tiling,
+        ));
+        self.app_workspaces.insert(group.to_string(), id);
+        id
+    }
+
+    /// Move a window whose app_id is now known to the workspace its rules
+    /// assign it to. Returns true if it was assigned one.
+    pub fn place_on_assigned_workspace(&mut self, window: &Window) -> bool {
+        let Some(id) = self
+            .window_rules_for(window)
+            .find_map(|rule| rule.workspace)
+        else {
+            return false;
+        };
+        if self.workspace_manager.get_workspace(id).is_none() {
+            eprintln!(
+                "⚠️  '{}' is assigned to workspace {}, which doesn't exist",
+                window_app_id(window),
+                id
+            );
+            return false;
+        }
+        if self.workspace_manager.find_window_workspace(window) == Some(id) {
+            return true;
+        }
+
+        println!("🗂️ '{}' opens on workspace {}", window_app_id(window), id);
+        self.workspace_manager
+            .move_window_to_workspace(window.clone(), id);
+        if let Some(workspace) = self.workspace_manager.get_workspace_mut(id) {
+            workspace.focus_window(window);
+        }
+        self.relayout();
+        true
+    }
+
+    /// Move a window whose app_id is now known to its app's workspace and
+    /// show it there
+    pub fn place_on_app_workspace(&mut self, window: &Window) {
+        if !self.per_app_workspaces_enabled() {
+            return;
+        }
+        let app_id = window_app_id(window);
+        if app_id.is_empty()
+            || self
+                .window_rules_for(window)
+                .any(|rule| rule.workspace.is_some())
+        {
+            return;
+        }
+
+        let group = self.app_group(window, &app_id);
+        let id = self.app_workspace(&group);
+        if self.workspace_manager.find_window_workspace(window) == Some(id) {
+            return;
+        }
+
+        self.workspace_manager
+            .move_window_to_workspace(window.clone(), id);
+        self.workspace_manager.switch_to_workspace(id);
+        if let Some(workspace) = self.workspace_manager.get_workspace_mut(id) {
+            workspace.focus_window(window);
+        }
+        self.relayout();
+    }
+}
@@ -0,0 +1,330 @@
+// Audio indicator
+//
+// Tabs of windows whose process is playing audio get a speaker badge, and
+// clicking the badge mutes or unmutes every stream of that window. Playing
+// streams come from PipeWire: a background thread runs `pw-dump` every
+// AUDIO_POLL_INTERVAL and hands the output streams, with the process ids
+// PipeWire reports for them, to the event loop. A stream belongs to a
+// window when the window's client is that process or one of its parents,
+// which covers browsers playing from a helper process. Muting goes through
+// `wpctl`. Badge colors come from the stylesheet's `.audio-badge` and
+// `.audio-badge-muted` rules.
+
+use serde_json::Value;
+use smithay::desktop::Window;
+use smithay::reexports::calloop::channel::{self, Event};
+use smithay::reexports::calloop::LoopHandle;
+use smithay::utils::{Logical, Point, Rectangle};
+use std::fs;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::tabs::TAB_BAR_HEIGHT;
+use crate::compositor::WebWMCompositor;
+
+/// How often PipeWire is asked for playing streams
+pub const AUDIO_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Width a badge takes from the end of a tab
+pub const BADGE_WIDTH: i32 = 20;
+const BADGE_SIZE: (i32, i32) = (10, 10);
+
+const DEFAULT_BADGE_COLOR: [f32; 4] = [0.65, 0.89, 0.63, 1.0]; // #a6e3a1
+const DEFAULT_MUTED_COLOR: [f32; 4] = [0.95, 0.55, 0.66, 1.0]; // #f38ba8
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioStream {
+    /// PipeWire node id, which wpctl takes
+    pub node_id: u32,
+    /// The playing process followed by its parents
+    pub pids: Vec<u32>,
+    pub muted: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct AudioState {
+    pub streams: Vec<AudioStream>,
+}
+
+impl AudioState {
+    /// Streams played by process `pid` or its children
+    pub fn streams_of(&self, pid: u32) -> impl Iterator<Item = &AudioStream> {
+        self.streams
+            .iter()
+            .filter(move |stream| stream.pids.contains(&pid))
+    }
+}
+
+/// Running audio output streams in `pw-dump` output, with only their own
+/// process id
+pub fn parse_streams(dump: &str) -> Result<Vec<AudioStream>, String> {
+    let objects: Vec<Value> =
+        serde_json::from_str(dump).map_err(|e| format!("Invalid pw-dump output: {}", e))?;
+
+    Ok(objects
+        .iter()
+        .filter(|object| object["type"] == "PipeWire:Interface:Node")
+        .filter_map(|node| {
+            let info = &node["info"];
+            let props = &info["props"];
+            if props["media.class"] != "Stream/Output/Audio" || info["state"] != "running" {
+                return None;
+            }
+
+            // Some clients set the process id as a string
+            let pid = match &props["application.process.id"] {
+                Value::Number(pid) => pid.as_u64(),
+                Value::String(pid) => pid.parse().ok(),
+                _ => None,
+            }?;
+            let muted = info["params"]["Props"]
+                .as_array()
+                .and_then(|props| props.iter().find_map(|p| p["mute"].as_bool()))
+                .unwrap_or(false);
+
+            Some(AudioStream {
+                node_id: node["id"].as_u64()? as u32,
+                pids: vec![pid as u32],
+                muted,
+            })
+        })
+        .collect())
+}
+
+/// Parent process id from the contents of `/proc/<pid>/stat`
+fn parse_parent_pid(stat: &str) -> Option<u32> {
+    // The command name is in parentheses and may contain anything
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// `pid` and its parents up to, but not including, init
+fn process_ancestry(pid: u32) -> Vec<u32> {
+    let mut pids = vec![pid];
+    while let Some(parent) = fs::read_to_string(format!("/proc/{}/stat", pids[pids.len() - 1]))
+        .ok()
+        .as_deref()
+        .and_then(parse_parent_pid)
+    {
+        if parent <= 1 || pids.contains(&parent) {
+            break;
+        }
+        pids.push(parent);
+    }
+    pids
+}
+
+fn playing_streams() -> Result<Vec<AudioStream>, String> {
+    let output = Command::new("pw-dump")
+        .output()
+        .map_err(|e| format!("Failed to run pw-dump: {}", e))?;
+    if !output.status.success() {
+        return Err("pw-dump failed, is PipeWire running?".to_string());
+    }
+
+    let mut streams = parse_streams(&String::from_utf8_lossy(&output.stdout))?;
+    for stream in &mut streams {
+        stream.pids = process_ancestry(stream.pids[0]);
+    }
+    Ok(streams)
+}
+
+/// Poll PipeWire for playing streams on a background thread
+pub fn init_audio_monitor(
+    loop_handle: &LoopHandle<'static, WebWMCompositor>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (sender, receiver) = channel::channel();
+    loop_handle.insert_source(receiver, |event, _, compositor| {
+        if let Event::Msg(streams) = event {
+            compositor.audio.streams = streams;
+        }
+    })?;
+
+    thread::Builder::new()
+        .name("audio-monitor".to_string())
+        .spawn(move || loop {
+            match playing_streams() {
+                Ok(streams) => {
+                    if sender.send(streams).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("⚠️  Audio indicator disabled: {}", e);
+                    return;
+                }
+            }
+            thread::sleep(AUDIO_POLL_INTERVAL);
+        })?;
+
+    Ok(())
+}
+
+/// Where the badge of a tab at `x`, `y` that is `width` wide goes
+pub fn badge_geometry(x: i32, y: i32, width: i32) -> Rectangle<i32, Logical> {
+    Rectangle::from_loc_and_size(
+        (
+            x + width - BADGE_WIDTH + (BADGE_WIDTH - BADGE_SIZE.0) / 2,
+            y + (TAB_BAR_HEIGHT - 2 - BADGE_SIZE.1) / 2,
+        ),
+        BADGE_SIZE,
+    )
+}
+
+impl WebWMCompositor {
+    /// Whether `window` plays audio, and if so whether all of it is muted
+    pub fn window_audio(&self, window: &Window) -> Option<bool> {
+        let pid = self.window_pid(window)? as u32;
+        let mut streams = self.audio.streams_of(pid).peekable();
+        streams.peek()?;
+        Some(streams.all(|stream| stream.muted))
+    }
+
+    /// Mute every stream of `window`, or unmute them all if they are muted
+    pub fn toggle_window_mute(&mut self, window: &Window) {
+        let Some(muted) = self.window_audio(window) else {
+            return;
+        };
+        let Some(pid) = self.window_pid(window) else {
+            return;
+        };
+
+        for stream in self
+            .audio
+            .streams
+            .iter_mut()
+            .filter(|stream| stream.pids.contains(&(pid as u32)))
+        {
+            let status = Command::new("wpctl")
+                .arg("set-mute")
+                .arg(stream.node_id.to_string())
+                .arg(if muted { "0" } else { "1" })
+                .status();
+            match status {
+                // Show it right away instead of at the next poll
+                Ok(status) if status.success() => stream.muted = !muted,
+                Ok(_) => eprintln!("⚠️  wpctl could not mute stream {}", stream.node_id),
+                Err(e) => eprintln!("Failed to run wpctl: {}", e),
+            }
+        }
+        println!(
+            "🔊 {} audio of process {}",
+            if muted { "Unmuted" } else { "Muted" },
+            pid
+        );
+    }
+
+    /// Handle a button press on a tab's badge. Returns true when it was
+    /// one.
+    pub fn audio_badge_click(&mut self, location: Point<f64, Logical>) -> bool {
+        let point = location.to_i32_round();
+
+        let clicked = self.tab_strips().iter().find_map(|group| {
+            let geometry = self.space.element_geometry(group.visible())?;
+            let tab_width = geometry.size.w / group.windows.len() as i32;
+            let y = geometry.loc.y - TAB_BAR_HEIGHT;
+
+            group.windows.iter().enumerate().find_map(|(i, window)| {
+                let x = geometry.loc.x + i as i32 * tab_width;
+                let badge = badge_geometry(x, y, tab_width - 2);
+                (self.window_audio(window).is_some() && badge.contains(point))
+                    .then(|| window.clone())
+            })
+        });
+
+        match clicked {
+            Some(window) => {
+                self.toggle_window_mute(&window);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Speaker drawn in `badge`, with sound waves unless muted
+    pub fn render_audio_badge(
+        &self,
+        badge: Rectangle<i32, Logical>,
+        muted: bool,
+    ) -> Vec<BarElement> {
+        let (selector, default) = if muted {
+            (".audio-badge-muted", DEFAULT_MUTED_COLOR)
+        } else {
+            (".audio-badge", DEFAULT_BADGE_COLOR)
+        };
+        let color = self
+            .stylesheet
+            .as_ref()
+            .and_then(|ss| ss.get_color(selector, "color"))
+            .map(|color| color.to_rgba_f32())
+            .unwrap_or(default);
+
+        let (x, y) = (badge.loc.x, badge.loc.y);
+        let rect = |dx: i32, dy: i32, w: i32, h: i32| BarElement::Rectangle {
+            geometry: Rectangle::from_loc_and_size((x + dx, y + dy), (w, h)),
+            color,
+        };
+
+        let mut elements = vec![rect(0, 3, 3, 4), rect(3, 1, 2, 8)];
+        if !muted {
+            elements.push(rect(7, 3, 1, 4));
+            elements.push(rect(9, 1, 1, 8));
+        }
+        elements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_streams() {
+        let dump = r#"[
+            { "id": 30, "type": "PipeWire:Interface:Node",
+              "info": { "state": "running",
+                        "props": { "media.class": "Audio/Sink" } } },
+            { "id": 71, "type": "PipeWire:Interface:Node",
+              "info": { "state": "running",
+                        "props": { "media.class": "Stream/Output/Audio",
+                                   "application.process.id": 4242 },
+                        "params": { "Props": [ { "volume": 1.0, "mute": true } ] } } },
+            { "id": 72, "type": "PipeWire:Interface:Node",
+              "info": { "state": "running",
+                        "props": { "media.class": "Stream/Output/Audio",
+                                   "application.process.id": "5151" } } },
+            { "id": 73, "type": "PipeWire:Interface:Node",
+              "info": { "state": "suspended",
+                        "props": { "media.class": "Stream/Output/Audio",
+                                   "application.process.id": 6000 } } },
+            { "id": 5, "type": "PipeWire:Interface:Client", "info": {} }
+        ]"#;
+
+        let streams = parse_streams(dump).unwrap();
+        assert_eq!(
+            streams,
+            vec![
+                AudioStream {
+                    node_id: 71,
+                    pids: vec![4242],
+                    muted: true,
+                },
+                AudioStream {
+                    node_id: 72,
+                    pids: vec![5151],
+                    muted: false,
+                },
+            ]
+        );
+        assert!(parse_streams("not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_parent_pid() {
+        let stat = "4242 (Web Content (1)) S 4100 4100 4100 0 -1 4194560";
+        assert_eq!(parse_parent_pid(stat), Some(4100));
+        assert_eq!(parse_parent_pid("garbage"), None);
+    }
+}
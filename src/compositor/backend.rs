@@ -1,21 +1,28 @@
+use std::time::Instant;
+
 use smithay::{
     backend::{
         renderer::{damage::OutputDamageTracker, gles::GlesRenderer, Bind, Frame, Renderer},
-        winit::{self, WinitGraphicsBackend},
+        winit::{self, WinitEvent, WinitGraphicsBackend},
     },
+    input::pointer::{CursorIcon, CursorImageStatus},
     output::{Mode, Output, PhysicalProperties, Subpixel},
     reexports::calloop::EventLoop,
     utils::{Physical, Rectangle, Size, Transform},
 };
 
-use crate::compositor::input::InputHandler;
+use crate::compositor::input::{trace_event_from_backend, InputHandler};
+use crate::compositor::profiler::RenderStage;
 use crate::compositor::renderer::{SolidColorRenderer, WebWMRenderer};
+use crate::compositor::scale::to_output_pixels;
+use crate::compositor::virtual_outputs::VirtualOutputSpec;
 use crate::compositor::WebWMCompositor;
 
 pub enum WebWMBackend {
     Winit(WinitBackendState),
     Drm(crate::compositor::full_drm_backend::FullWebWMBackend),
     BasicDrm(crate::compositor::drm_backend::WebWMBackend),
+    Headless(crate::compositor::headless_backend::HeadlessBackend),
 }
 
 pub struct WinitBackendState {
@@ -25,6 +32,10 @@ pub struct WinitBackendState {
     pub input_handler: InputHandler,
     pub renderer: WebWMRenderer,
     pub solid_renderer: SolidColorRenderer,
+    /// Shape last given to the host window's cursor, None while hidden
+    pub host_cursor: Option<CursorIcon>,
+    /// From `--virtual-output`, laid out but not drawn
+    pub virtual_outputs: Vec<Output>,
 }
 
 impl WebWMBackend {
@@ -44,6 +55,11 @@ impl WebWMBackend {
                 let drm_backend = super::drm_backend::WebWMBackend::new(event_loop)?;
                 Ok(WebWMBackend::BasicDrm(drm_backend))
             }
+            "headless" => {
+                println!("Using headless backend (no display output)");
+                let headless = super::headless_backend::HeadlessBackend::new(1920, 1080);
+                Ok(WebWMBackend::Headless(headless))
+            }
             _ => {
                 println!("Using winit backend for nested session");
                 let (backend, winit_events) = winit::init::<GlesRenderer>()?;
@@ -74,7 +90,13 @@ impl WebWMBackend {
 
                 event_loop
                     .handle()
-                    .insert_source(winit_events, move |_event, _, _compositor| {})?;
+                    .insert_source(winit_events, move |event, _, compositor| {
+                        if let WinitEvent::Input(event) = event {
+                            if let Some(event) = trace_event_from_backend(event) {
+                                compositor.handle_input(event);
+                            }
+                        }
+                    })?;
 
                 let winit_state = WinitBackendState {
                     winit: backend,
@@ -83,6 +105,8 @@ impl WebWMBackend {
                     input_handler: InputHandler::new(),
                     renderer: WebWMRenderer::new(),
                     solid_renderer: SolidColorRenderer::new(),
+                    host_cursor: Some(CursorIcon::Default),
+                    virtual_outputs: Vec::new(),
                 };
 
                 Ok(WebWMBackend::Winit(winit_state))
@@ -93,15 +117,38 @@ impl WebWMBackend {
     pub fn render(
         &mut self,
         compositor: &mut WebWMCompositor,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        compositor.profiler.begin_frame();
+        let result = self.render_inner(compositor);
+        compositor.profiler.end_frame();
+
+        if result.is_ok() {
+            let latency = compositor.profiler.frame().last;
+            for output in self.outputs() {
+                let refresh = output.current_mode().map(|m| m.refresh).unwrap_or(60_000);
+                compositor
+                    .frame_pacing
+                    .record_present(&output.name(), refresh, latency);
+            }
+        }
+
+        result
+    }
+
+    fn render_inner(
+        &mut self,
+        compositor: &mut WebWMCompositor,
     ) -> Result<(), Box<dyn std::error::Error>> {
         match self {
             WebWMBackend::Winit(state) => {
                 let size = state.winit.window_size();
+                let scale = state.output.current_scale().fractional_scale();
+                let stage_start = Instant::now();
 
-                // Get windows to render
+                // Get windows to render, in output pixels
                 let windows: Vec<_> = compositor
-                    .space
-                    .elements()
+                    .stacked_elements()
+                    .into_iter()
                     .filter_map(|window| {
                         let location = compositor.space.element_location(window)?;
                         let geometry = window.geometry();
@@ -109,21 +156,58 @@ impl WebWMBackend {
 
                         Some((
                             window,
-                            Rectangle::<i32, smithay::utils::Physical>::new(
-                                (render_location.x, render_location.y).into(),
-                                (geometry.size.w, geometry.size.h).into(),
-                            ),
+                            to_output_pixels(Rectangle::new(render_location, geometry.size), scale),
                         ))
                     })
                     .collect();
+                let window_count = windows.len();
+                compositor
+                    .profiler
+                    .record(RenderStage::ElementCollection, stage_start.elapsed());
+
+                let stage_start = Instant::now();
+                let bar_elements = compositor.render_bar_elements();
+                compositor
+                    .profiler
+                    .record(RenderStage::BarRasterization, stage_start.elapsed());
+
+                // Named cursors use the host's cursor, which follows the
+                // pointer without lag; anything else hides it
+                let host_cursor = match compositor.cursor_image_status {
+                    CursorImageStatus::Named(icon) if compositor.cursor_visible(Instant::now()) => {
+                        Some(icon)
+                    }
+                    _ => None,
+                };
+                if host_cursor != state.host_cursor {
+                    let window = state.winit.window();
+                    match host_cursor {
+                        Some(icon) => {
+                            window.set_cursor(icon);
+                            window.set_cursor_visible(true);
+                        }
+                        None => window.set_cursor_visible(false),
+                    }
+                    state.host_cursor = host_cursor;
+                }
 
                 // Bind the renderer for this frame
+                let stage_start = Instant::now();
+                state.renderer.set_output_scale(scale);
                 state.winit.bind()?;
                 let renderer = state.winit.renderer();
 
                 // Submit frame - winit handles actual EGL context management
-                println!("🎨 Rendering frame - basic mode active");
+                println!(
+                    "🎨 Rendering frame - basic mode active ({} windows, {} bar elements, scale {})",
+                    window_count,
+                    bar_elements.len(),
+                    scale
+                );
                 state.winit.submit(None)?;
+                compositor
+                    .profiler
+                    .record(RenderStage::GlSubmission, stage_start.elapsed());
 
                 Ok(())
             }
@@ -133,6 +217,51 @@ impl WebWMBackend {
             WebWMBackend::BasicDrm(state) => state
                 .render_frame(compositor)
                 .map_err(|e| Box::new(e) as Box<dyn std::error::Error>),
+            WebWMBackend::Headless(state) => state.render_frame(compositor).map_err(|e| e.into()),
+        }
+    }
+
+    /// Apply per-output settings that need the backend, like scanout format
+    pub fn configure_outputs(&mut self, compositor: &mut WebWMCompositor) {
+        if let WebWMBackend::Drm(state) = self {
+            state.configure_outputs(compositor);
+        }
+    }
+
+    pub fn outputs(&self) -> Vec<&Output> {
+        match self {
+            WebWMBackend::Winit(state) => std::iter::once(&state.output)
+                .chain(&state.virtual_outputs)
+                .collect(),
+            WebWMBackend::Drm(state) => state.get_outputs(),
+            WebWMBackend::BasicDrm(state) => state.get_outputs(),
+            WebWMBackend::Headless(state) => std::iter::once(&state.output)
+                .chain(&state.virtual_outputs)
+                .collect(),
+        }
+    }
+
+    /// Add the outputs asked for with `--virtual-output`, which only the
+    /// winit and headless backends have
+    pub fn add_virtual_outputs(
+        &mut self,
+        compositor: &mut WebWMCompositor,
+        specs: &[VirtualOutputSpec],
+    ) {
+        let virtual_outputs = match self {
+            WebWMBackend::Winit(state) => &mut state.virtual_outputs,
+            WebWMBackend::Headless(state) => &mut state.virtual_outputs,
+            _ => {
+                if !specs.is_empty() {
+                    eprintln!(
+                        "⚠️  Virtual outputs need the winit or headless backend, ignoring them"
+                    );
+                }
+                return;
+            }
+        };
+        for spec in specs {
+            virtual_outputs.push(compositor.add_virtual_output(spec));
         }
     }
 
@@ -141,6 +270,7 @@ impl WebWMBackend {
             WebWMBackend::Winit(state) => Some(&state.output),
             WebWMBackend::Drm(state) => state.get_outputs().first().copied(),
             WebWMBackend::BasicDrm(state) => state.get_outputs().first().copied(),
+            WebWMBackend::Headless(state) => Some(&state.output),
         }
     }
 
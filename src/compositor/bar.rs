@@ -1,9 +1,16 @@
-use smithay::utils::{Physical, Rectangle};
-use std::time::{SystemTime, UNIX_EPOCH};
-
-use crate::compositor::workspace::WorkspaceManager;
+use smithay::utils::{Logical, Physical, Rectangle};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::compositor::profiler::StageStats;
+use crate::compositor::text;
+use crate::compositor::workspace::{Workspace, WorkspaceManager};
 use crate::config::StyleSheet;
-use crate::config::{BarConfig, Position, Widget};
+use crate::config::{Align, BarConfig, Position, Widget};
+
+/// Room between the bar's edges and the left and right groups
+const PADDING: i32 = 16;
 
 #[derive(Debug, Clone)]
 pub struct Bar {
@@ -36,10 +43,30 @@ impl Bar {
     pub fn is_horizontal(&self) -> bool {
         matches!(self.config.position, Position::Top | Position::Bottom)
     }
+
+    /// Whether the bar belongs on `output`; with no output known, every
+    /// bar does
+    pub fn shown_on(&self, output: Option<&str>) -> bool {
+        match (&self.config.output, output) {
+            (Some(own), Some(output)) => own == output,
+            _ => true,
+        }
+    }
+}
+
+/// A widget drawn at its place on a bar
+struct PlacedWidget<'a> {
+    widget: &'a Widget,
+    x: i32,
+    width: i32,
+    elements: Vec<BarElement>,
 }
 
 pub struct BarRenderer {
     pub bars: Vec<Bar>,
+    /// How long each kind of widget takes to update, for metrics. Bars
+    /// are rendered through `&self`, hence the RefCell.
+    widget_times: RefCell<HashMap<&'static str, StageStats>>,
 }
 
 impl BarRenderer {
@@ -49,21 +76,34 @@ impl BarRenderer {
             .map(|config| Bar::new(config, output_width))
             .collect();
 
-        Self { bars }
+        Self {
+            bars,
+            widget_times: RefCell::new(HashMap::new()),
+        }
     }
 
+    pub fn widget_times(&self) -> HashMap<&'static str, StageStats> {
+        self.widget_times.borrow().clone()
+    }
+
+    /// The bars of `output`. `urgent_workspaces` maps the id of each
+    /// workspace with an urgent window to the vertical offset of its button.
     pub fn render_bars(
         &self,
+        output: Option<&str>,
         workspace_manager: &WorkspaceManager,
+        urgent_workspaces: &HashMap<u32, i32>,
         focused_window_title: Option<String>,
         stylesheet: Option<&StyleSheet>,
     ) -> Vec<BarElement> {
         let mut elements = Vec::new();
 
-        for bar in &self.bars {
+        for bar in self.bars.iter().filter(|bar| bar.shown_on(output)) {
             elements.extend(self.render_bar(
                 bar,
+                output,
                 workspace_manager,
+                urgent_workspaces,
                 focused_window_title.clone(),
                 stylesheet,
             ));
@@ -72,15 +112,187 @@ impl BarRenderer {
         elements
     }
 
+    /// The clock under `point`, as its calendar's first weekday and its
+    /// rectangle. Widgets are laid out the same way as for drawing.
+    pub fn clock_at(
+        &self,
+        point: (i32, i32),
+        output: Option<&str>,
+        workspace_manager: &WorkspaceManager,
+        focused_window_title: Option<String>,
+    ) -> Option<(u32, Rectangle<i32, Logical>)> {
+        let bar = self.bar_at(point, output)?;
+        let placed = self.place_widgets(
+            bar,
+            output,
+            workspace_manager,
+            &HashMap::new(),
+            focused_window_title.as_ref(),
+            [0.0; 4],
+            None,
+        );
+
+        placed.into_iter().find_map(|placed| match placed.widget {
+            Widget::Clock { first_weekday, .. }
+                if point.0 >= placed.x && point.0 < placed.x + placed.width =>
+            {
+                let clock = Rectangle::from_loc_and_size(
+                    (placed.x, bar.geometry.loc.y),
+                    (placed.width, bar.geometry.size.h),
+                );
+                Some((*first_weekday, clock))
+            }
+            _ => None,
+        })
+    }
+
+    /// The workspace whose button on a bar of `output` is under `point`,
+    /// with the button's rectangle
+    pub fn workspace_at(
+        &self,
+        point: (i32, i32),
+        output: Option<&str>,
+        workspace_manager: &WorkspaceManager,
+        focused_window_title: Option<String>,
+    ) -> Option<(u32, Rectangle<i32, Logical>)> {
+        let bar = self.bar_at(point, output)?;
+        let placed = self.place_widgets(
+            bar,
+            output,
+            workspace_manager,
+            &HashMap::new(),
+            focused_window_title.as_ref(),
+            [0.0; 4],
+            None,
+        );
+
+        for placed in placed {
+            if let Widget::Workspaces { .. } = placed.widget {
+                let mut x = placed.x;
+                for workspace in workspaces_on(workspace_manager, output) {
+                    let width = workspace_button_width(workspace);
+                    if point.0 >= x && point.0 < x + width {
+                        let button =
+                            Rectangle::from_loc_and_size((x, bar.geometry.loc.y + 5), (width, 20));
+                        return Some((workspace.id, button));
+                    }
+                    x += width + 8;
+                }
+            }
+        }
+        None
+    }
+
+    /// Where the workspace buttons on the first bar of `output` that has
+    /// them end, for things drawn next to them
+    pub fn workspaces_end(
+        &self,
+        output: Option<&str>,
+        workspace_manager: &WorkspaceManager,
+    ) -> Option<(i32, i32)> {
+        self.bars
+            .iter()
+            .filter(|bar| bar.shown_on(output))
+            .find_map(|bar| {
+                let placed = self.place_widgets(
+                    bar,
+                    output,
+                    workspace_manager,
+                    &HashMap::new(),
+                    None,
+                    [0.0; 4],
+                    None,
+                );
+                placed
+                    .iter()
+                    .find(|placed| matches!(placed.widget, Widget::Workspaces { .. }))
+                    .map(|placed| (placed.x + placed.width, bar.geometry.loc.y + 5))
+            })
+    }
+
+    fn bar_at(&self, point: (i32, i32), output: Option<&str>) -> Option<&Bar> {
+        self.bars.iter().find(|bar| {
+            let g = bar.geometry;
+            bar.shown_on(output)
+                && point.0 >= g.loc.x
+                && point.1 >= g.loc.y
+                && point.0 < g.loc.x + g.size.w
+                && point.1 < g.loc.y + g.size.h
+        })
+    }
+
+    /// Draw the widgets of `bar` and move each to its place: left ones one
+    /// after another from the left edge, then the centered ones around the
+    /// middle and the right ones against the right edge
+    fn place_widgets<'a>(
+        &self,
+        bar: &'a Bar,
+        output: Option<&str>,
+        workspace_manager: &WorkspaceManager,
+        urgent_workspaces: &HashMap<u32, i32>,
+        focused_window_title: Option<&String>,
+        text_color: [f32; 4],
+        stylesheet: Option<&StyleSheet>,
+    ) -> Vec<PlacedWidget<'a>> {
+        let mut aligned = Vec::new();
+        align_widgets(&bar.config.widgets, Align::Left, &mut aligned);
+
+        // Each widget is drawn from x = 0 first, to find out how wide it is
+        let mut placed: Vec<PlacedWidget> = aligned
+            .iter()
+            .map(|(_, widget)| {
+                let start = Instant::now();
+                let mut width = 0;
+                let elements = self.render_widget(
+                    widget,
+                    output,
+                    workspace_manager,
+                    urgent_workspaces,
+                    focused_window_title,
+                    &mut width,
+                    bar.geometry.loc.y + 5,
+                    text_color,
+                    stylesheet,
+                );
+                self.widget_times
+                    .borrow_mut()
+                    .entry(widget_name(widget))
+                    .or_default()
+                    .record(start.elapsed());
+                PlacedWidget {
+                    widget,
+                    x: 0,
+                    width,
+                    elements,
+                }
+            })
+            .collect();
+
+        let sizes: Vec<(Align, i32)> = aligned
+            .iter()
+            .zip(&placed)
+            .map(|((align, _), placed)| (*align, placed.width))
+            .collect();
+        let offsets = widget_offsets(bar.geometry.loc.x, bar.geometry.size.w, &sizes);
+        for (placed, x) in placed.iter_mut().zip(offsets) {
+            placed.x = x;
+            for element in &mut placed.elements {
+                *element = element.translated(x);
+            }
+        }
+        placed
+    }
+
     fn render_bar(
         &self,
         bar: &Bar,
+        output: Option<&str>,
         workspace_manager: &WorkspaceManager,
+        urgent_workspaces: &HashMap<u32, i32>,
         focused_window_title: Option<String>,
         stylesheet: Option<&StyleSheet>,
     ) -> Vec<BarElement> {
         let mut elements = Vec::new();
-        let mut x_offset = 16; // Left padding
 
         // Get colors from stylesheet or use defaults
         let (bg_color, text_color) = if let Some(ss) = stylesheet {
@@ -104,17 +316,16 @@ impl BarRenderer {
         });
 
         // Render widgets
-        for widget in &bar.config.widgets {
-            let widget_elements = self.render_widget(
-                widget,
-                workspace_manager,
-                focused_window_title.as_ref(),
-                &mut x_offset,
-                bar.geometry.loc.y + 5,
-                text_color,
-                stylesheet,
-            );
-            elements.extend(widget_elements);
+        for placed in self.place_widgets(
+            bar,
+            output,
+            workspace_manager,
+            urgent_workspaces,
+            focused_window_title.as_ref(),
+            text_color,
+            stylesheet,
+        ) {
+            elements.extend(placed.elements);
         }
 
         elements
@@ -123,7 +334,9 @@ impl BarRenderer {
     fn render_widget(
         &self,
         widget: &Widget,
+        output: Option<&str>,
         workspace_manager: &WorkspaceManager,
+        urgent_workspaces: &HashMap<u32, i32>,
         focused_window_title: Option<&String>,
         x_offset: &mut i32,
         y: i32,
@@ -131,24 +344,34 @@ impl BarRenderer {
         stylesheet: Option<&StyleSheet>,
     ) -> Vec<BarElement> {
         match widget {
-            Widget::Workspaces { display: _ } => {
-                self.render_workspaces(workspace_manager, x_offset, y, text_color, stylesheet)
-            }
+            Widget::Workspaces { display: _ } => self.render_workspaces(
+                output,
+                workspace_manager,
+                urgent_workspaces,
+                x_offset,
+                y,
+                text_color,
+                stylesheet,
+            ),
             Widget::WindowTitle { max_width } => {
                 self.render_window_title(focused_window_title, x_offset, y, *max_width, text_color)
             }
-            Widget::Clock { format } => self.render_clock(format, x_offset, y, text_color),
+            Widget::Clock { format, .. } => self.render_clock(format, x_offset, y, text_color),
             Widget::SystemTray => render_system_tray(self, x_offset, y, text_color, stylesheet),
             Widget::Spacer { flex } => {
                 *x_offset += 100 * (*flex as i32); // Simple spacer
                 Vec::new()
             }
+            // Groups are taken apart by place_widgets
+            Widget::Group { .. } => Vec::new(),
         }
     }
 
     fn render_workspaces(
         &self,
+        output: Option<&str>,
         workspace_manager: &WorkspaceManager,
+        urgent_workspaces: &HashMap<u32, i32>,
         x_offset: &mut i32,
         y: i32,
         text_color: [f32; 4],
@@ -157,13 +380,27 @@ impl BarRenderer {
         let mut elements = Vec::new();
         let active_id = workspace_manager.active_workspace_id();
 
-        for workspace in workspace_manager.all_workspaces() {
+        for workspace in workspaces_on(workspace_manager, output) {
             let is_active = workspace.id == active_id;
             let has_windows = !workspace.is_empty();
+            let bounce = urgent_workspaces.get(&workspace.id).copied();
+            let y = y + bounce.unwrap_or(0);
 
             // Get colors from stylesheet
             let (bg_color, fg_color) = if let Some(ss) = stylesheet {
-                if is_active {
+                if bounce.is_some() {
+                    let bg = ss
+                        .get_color("workspace:urgent", "background")
+                        .or_else(|| ss.get_color("workspace.urgent", "background"))
+                        .map(|c| c.to_rgba_f32())
+                        .unwrap_or([0.95, 0.55, 0.66, 1.0]); // Red
+                    let fg = ss
+                        .get_color("workspace:urgent", "color")
+                        .or_else(|| ss.get_color("workspace.urgent", "color"))
+                        .map(|c| c.to_rgba_f32())
+                        .unwrap_or([0.11, 0.11, 0.18, 1.0]); // Dark
+                    (bg, fg)
+                } else if is_active {
                     let bg = ss
                         .get_color("workspace.active", "background")
                         .map(|c| c.to_rgba_f32())
@@ -183,7 +420,9 @@ impl BarRenderer {
                     ([0.0, 0.0, 0.0, 0.0], text_color) // Transparent
                 }
             } else {
-                if is_active {
+                if bounce.is_some() {
+                    ([0.95, 0.55, 0.66, 1.0], [0.11, 0.11, 0.18, 1.0])
+                } else if is_active {
                     ([0.54, 0.71, 0.98, 1.0], [0.11, 0.11, 0.18, 1.0])
                 } else if has_windows {
                     ([0.19, 0.20, 0.27, 1.0], text_color)
@@ -192,7 +431,8 @@ impl BarRenderer {
                 }
             };
 
-            let width = 40;
+            let text = workspace_label(workspace);
+            let width = workspace_button_width(workspace);
             let height = 20;
 
             // Background box
@@ -203,13 +443,6 @@ impl BarRenderer {
                 });
             }
 
-            // Workspace number/name
-            let text = if workspace.name.len() <= 3 {
-                workspace.name.clone()
-            } else {
-                workspace.id.to_string()
-            };
-
             elements.push(BarElement::Text {
                 position: (*x_offset + 12, y + 3),
                 text,
@@ -241,17 +474,12 @@ impl BarRenderer {
         text_color: [f32; 4],
     ) -> Vec<BarElement> {
         if let Some(title) = title {
-            let display_title = if let Some(max) = max_width {
-                if title.len() > max as usize {
-                    format!("{}...", &title[..max as usize - 3])
-                } else {
-                    title.clone()
-                }
-            } else {
-                title.clone()
+            let display_title = match max_width {
+                Some(max) => text::truncate(title, 13, max as i32),
+                None => title.clone(),
             };
 
-            let text_width = display_title.len() * 8; // Approximate
+            let text_width = text::measure(&display_title, 13);
             let result = vec![BarElement::Text {
                 position: (*x_offset, y + 3),
                 text: display_title,
@@ -259,7 +487,7 @@ impl BarRenderer {
                 size: 13,
             }];
 
-            *x_offset += text_width as i32 + 16;
+            *x_offset += text_width + 16;
             result
         } else {
             Vec::new()
@@ -275,7 +503,7 @@ impl BarRenderer {
     ) -> Vec<BarElement> {
         let time_str = format_time(format);
 
-        let text_width = time_str.len() * 8;
+        let text_width = text::measure(&time_str, 13);
         let result = vec![BarElement::Text {
             position: (*x_offset, y + 3),
             text: time_str,
@@ -283,12 +511,98 @@ impl BarRenderer {
             size: 13,
         }];
 
-        *x_offset += text_width as i32 + 16;
+        *x_offset += text_width + 16;
         result
     }
 }
 
-#[derive(Debug, Clone)]
+/// Name of a widget's kind, as in `<window-title />`
+pub fn widget_name(widget: &Widget) -> &'static str {
+    match widget {
+        Widget::Workspaces { .. } => "workspaces",
+        Widget::WindowTitle { .. } => "window-title",
+        Widget::SystemTray => "system-tray",
+        Widget::Clock { .. } => "clock",
+        Widget::Spacer { .. } => "spacer",
+        Widget::Group { .. } => "group",
+    }
+}
+
+/// The widgets in `widgets`, groups taken apart, with the part of the bar
+/// each goes to. Widgets outside a group go to `align`.
+fn align_widgets<'a>(widgets: &'a [Widget], align: Align, aligned: &mut Vec<(Align, &'a Widget)>) {
+    for widget in widgets {
+        match widget {
+            Widget::Group { align, widgets } => align_widgets(widgets, *align, aligned),
+            widget => aligned.push((align, widget)),
+        }
+    }
+}
+
+/// Where each widget starts on a bar from `x`, `width` wide, given the part
+/// of the bar each goes to and how wide it is
+fn widget_offsets(x: i32, width: i32, widgets: &[(Align, i32)]) -> Vec<i32> {
+    let group_width = |align: Align| -> i32 {
+        widgets
+            .iter()
+            .filter(|(a, _)| *a == align)
+            .map(|(_, w)| w)
+            .sum()
+    };
+    let mut left = x + PADDING;
+    let mut center = x + (width - group_width(Align::Center)) / 2;
+    // Widgets leave room after themselves, which pads the right edge
+    let mut right = x + width - group_width(Align::Right);
+
+    widgets
+        .iter()
+        .map(|(align, w)| {
+            let next = match align {
+                Align::Left => &mut left,
+                Align::Center => &mut center,
+                Align::Right => &mut right,
+            };
+            let offset = *next;
+            *next += w;
+            offset
+        })
+        .collect()
+}
+
+/// Workspaces listed on the bars of `output`: its own and those that
+/// don't belong to any output
+fn workspaces_on<'a>(
+    workspace_manager: &'a WorkspaceManager,
+    output: Option<&'a str>,
+) -> impl Iterator<Item = &'a Workspace> {
+    workspace_manager
+        .all_workspaces()
+        .into_iter()
+        .filter(move |workspace| match (&workspace.output, output) {
+            (Some(own), Some(output)) => own == output,
+            _ => true,
+        })
+}
+
+/// Workspace number or name as shown on its button; auto names are always
+/// shown in full
+fn workspace_label(workspace: &Workspace) -> String {
+    if workspace.auto_name.is_some() || workspace.name.len() <= 3 {
+        workspace.display_name().to_string()
+    } else {
+        workspace.id.to_string()
+    }
+}
+
+fn workspace_button_width(workspace: &Workspace) -> i32 {
+    if workspace.auto_name.is_some() {
+        (text::measure(&workspace_label(workspace), 13) + 24).max(40)
+    } else {
+        40
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum BarElement {
     Rectangle {
         geometry: Rectangle<i32, Physical>,
@@ -307,6 +621,54 @@ pub enum BarElement {
     },
 }
 
+impl BarElement {
+    /// The element moved `dx` to the right
+    pub fn translated(&self, dx: i32) -> BarElement {
+        let mut element = self.clone();
+        match &mut element {
+            BarElement::Rectangle { geometry, .. } => geometry.loc.x += dx,
+            BarElement::Circle { center, .. } => center.0 += dx,
+            BarElement::Text { position, .. } => position.0 += dx,
+        }
+        element
+    }
+
+    /// The element drawn at `scale`: positions, sizes and font size are
+    /// multiplied by it
+    pub fn scaled(&self, scale: f64) -> BarElement {
+        let px = |v: i32| (v as f64 * scale).round() as i32;
+        match self {
+            BarElement::Rectangle { geometry, color } => BarElement::Rectangle {
+                geometry: Rectangle::from_loc_and_size(
+                    (px(geometry.loc.x), px(geometry.loc.y)),
+                    (px(geometry.size.w), px(geometry.size.h)),
+                ),
+                color: *color,
+            },
+            BarElement::Circle {
+                center,
+                radius,
+                color,
+            } => BarElement::Circle {
+                center: (px(center.0), px(center.1)),
+                radius: px(*radius),
+                color: *color,
+            },
+            BarElement::Text {
+                position,
+                text,
+                color,
+                size,
+            } => BarElement::Text {
+                position: (px(position.0), px(position.1)),
+                text: text.clone(),
+                color: *color,
+                size: (*size as f64 * scale).round() as u32,
+            },
+        }
+    }
+}
+
 fn render_system_tray(
     _bar_renderer: &BarRenderer,
     x_offset: &mut i32,
@@ -394,6 +756,7 @@ fn format_time(format: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::compositor::workspace::LayoutMode;
 
     #[test]
     fn test_time_formatting() {
@@ -412,10 +775,124 @@ mod tests {
             height: 30,
             class: "bar".to_string(),
             widgets: vec![],
+            output: None,
         };
 
         let bar = Bar::new(config, 1920);
         assert_eq!(bar.geometry.loc.y, 0);
         assert_eq!(bar.geometry.size.h, 30);
     }
+
+    #[test]
+    fn test_clock_at() {
+        let config = BarConfig {
+            id: "test".to_string(),
+            position: Position::Top,
+            height: 30,
+            class: "bar".to_string(),
+            widgets: vec![
+                Widget::Spacer { flex: 1 },
+                Widget::Clock {
+                    format: "%H:%M".to_string(),
+                    first_weekday: 6,
+                },
+            ],
+            output: None,
+        };
+        let bars = BarRenderer::new(vec![config], 1920);
+        let workspaces = WorkspaceManager::new();
+
+        // The spacer covers 16..116, then the clock is as wide as its text
+        let width = text::measure(&format_time("%H:%M"), 13) + 16;
+        assert_eq!(bars.clock_at((100, 10), None, &workspaces, None), None);
+        assert_eq!(bars.clock_at((120, 40), None, &workspaces, None), None);
+        assert_eq!(
+            bars.clock_at((120, 10), None, &workspaces, None),
+            Some((6, Rectangle::from_loc_and_size((116, 0), (width, 30))))
+        );
+    }
+
+    #[test]
+    fn test_widget_groups() {
+        let widgets = vec![
+            Widget::Group {
+                align: Align::Right,
+                widgets: vec![Widget::SystemTray, Widget::Spacer { flex: 1 }],
+            },
+            Widget::Spacer { flex: 2 },
+        ];
+        let mut aligned = Vec::new();
+        align_widgets(&widgets, Align::Left, &mut aligned);
+        let aligns: Vec<Align> = aligned.iter().map(|(align, _)| *align).collect();
+        assert_eq!(aligns, vec![Align::Right, Align::Right, Align::Left]);
+
+        let sizes = [
+            (Align::Left, 100),
+            (Align::Center, 50),
+            (Align::Right, 60),
+            (Align::Right, 40),
+        ];
+        assert_eq!(widget_offsets(0, 1000, &sizes), vec![16, 475, 900, 960]);
+    }
+
+    #[test]
+    fn test_bar_outputs() {
+        let config = BarConfig {
+            id: "tv".to_string(),
+            position: Position::Top,
+            height: 30,
+            class: "bar".to_string(),
+            widgets: vec![Widget::Workspaces {
+                display: "icons".to_string(),
+            }],
+            output: Some("HDMI-A-1".to_string()),
+        };
+        let bars = BarRenderer::new(vec![config], 1920);
+        assert!(bars
+            .render_bars(
+                Some("eDP-1"),
+                &WorkspaceManager::new(),
+                &HashMap::new(),
+                None,
+                None
+            )
+            .is_empty());
+
+        // Workspace 1 belongs to the laptop panel, so the TV's first
+        // button is workspace 2
+        let mut workspaces = WorkspaceManager::new();
+        let mut laptop = Workspace::new(1, "1".to_string(), LayoutMode::Tiling);
+        laptop.output = Some("eDP-1".to_string());
+        workspaces.add_workspace(laptop);
+        assert_eq!(
+            bars.workspace_at((20, 10), Some("HDMI-A-1"), &workspaces, None)
+                .map(|(id, _)| id),
+            Some(2)
+        );
+        assert_eq!(
+            bars.workspace_at((20, 10), None, &workspaces, None)
+                .map(|(id, _)| id),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_scaled_elements() {
+        let text = BarElement::Text {
+            position: (10, 3),
+            text: "12:00".to_string(),
+            color: [1.0; 4],
+            size: 13,
+        };
+        assert_eq!(
+            text.scaled(1.5),
+            BarElement::Text {
+                position: (15, 5),
+                text: "12:00".to_string(),
+                color: [1.0; 4],
+                size: 20,
+            }
+        );
+        assert_eq!(text.scaled(1.0), text);
+    }
 }
@@ -5,6 +5,7 @@ use smithay::backend::renderer::{
 use smithay::utils::{Physical, Rectangle, Size};
 
 use crate::compositor::bar::BarElement;
+use crate::compositor::text;
 
 pub struct BarTextureRenderer {
     width: i32,
@@ -16,22 +17,25 @@ impl BarTextureRenderer {
         Self { width, height }
     }
 
-    /// Render bar elements to a RGBA buffer (ARGB8888 format for Smithay)
-    pub fn render_to_buffer(&self, elements: &[BarElement]) -> Vec<u8> {
-        let size = (self.width * self.height * 4) as usize;
-        let mut buffer = vec![0u8; size];
+    /// Bytes needed for one rendered bar
+    pub fn buffer_len(&self) -> usize {
+        (self.width * self.height * 4) as usize
+    }
 
+    /// Render bar elements into a cleared ARGB8888 buffer of `buffer_len()`
+    /// bytes, usually one from a `BufferPool`
+    pub fn render_into(&self, buffer: &mut [u8], elements: &[BarElement]) {
         for element in elements {
             match element {
                 BarElement::Rectangle { geometry, color } => {
-                    self.draw_rectangle(&mut buffer, geometry, *color);
+                    self.draw_rectangle(buffer, geometry, *color);
                 }
                 BarElement::Circle {
                     center,
                     radius,
                     color,
                 } => {
-                    self.draw_circle(&mut buffer, *center, *radius, *color);
+                    self.draw_circle(buffer, *center, *radius, *color);
                 }
                 BarElement::Text {
                     position,
@@ -39,12 +43,10 @@ impl BarTextureRenderer {
                     color,
                     size,
                 } => {
-                    self.draw_text(&mut buffer, *position, text, *color, *size);
+                    self.draw_text(buffer, *position, text, *color, *size);
                 }
             }
         }
-
-        buffer
     }
 
     fn draw_rectangle(
@@ -101,17 +103,21 @@ impl BarTextureRenderer {
         position: (i32, i32),
         text: &str,
         color: [f32; 4],
-        _size: u32,
+        size: u32,
     ) {
-        // Simple bitmap font rendering
-        // This is a very basic 5x7 font for ASCII characters
+        if text::draw(text, size, position, color, |x, y, color| {
+            self.set_pixel(buffer, x, y, color)
+        }) {
+            return;
+        }
 
+        // No fonts installed: a very basic 5x7 font for ASCII characters
         let (mut x, y) = position;
 
         for ch in text.chars() {
             if ch.is_ascii() {
                 self.draw_char(buffer, x, y, ch, color);
-                x += 6; // Character width + spacing
+                x += text::FALLBACK_ADVANCE;
             }
         }
     }
@@ -0,0 +1,155 @@
+// Binding modes
+//
+// Named sets of keybindings, as in i3: `wm.mode("resize", {...})` in
+// config.js (or `modes` in config.json) declares one, and the enter-mode
+// action or `wm.enterMode("resize")` swaps the keybindings for its own.
+// Callbacks of `keybind()` and `wm.mode()` that aren't built-in bindings
+// run here, and the `wm` calls they make are carried out once they return.
+// Modes stack, so exit-mode or `wm.exitMode()` goes back to the one it was
+// entered from, and Escape, unless the mode binds it, straight back to the
+// default bindings. Keys a mode doesn't bind go nowhere, so a stray key
+// doesn't end up typed into a window. The mode's name is shown next to the
+// workspace buttons, styled by the `.binding-mode` rule, and every change
+// emits a `mode` event.
+
+use serde_json::json;
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::input::{InputHandler, Modifiers};
+use crate::compositor::ui::{rect, Canvas, Style};
+use crate::compositor::{text, WebWMCompositor};
+
+const PADDING: i32 = 12;
+const HEIGHT: i32 = 20;
+/// Room around the badge when there are no workspace buttons to sit next to
+const MARGIN: i32 = 8;
+
+const DEFAULT_STYLE: Style = Style::new(
+    [0.98, 0.70, 0.53, 1.0], // #fab387
+    [0.11, 0.11, 0.18, 1.0],
+    [0.98, 0.70, 0.53, 1.0],
+);
+
+impl InputHandler {
+    /// The binding mode keys go to, None for the default bindings
+    pub fn binding_mode(&self) -> Option<&str> {
+        self.mode_stack.last().map(String::as_str)
+    }
+
+    pub fn enter_mode(&mut self, name: &str, compositor: &mut WebWMCompositor) {
+        if !compositor.config.modes.contains_key(name) {
+            eprintln!("⚠️  No binding mode named '{}'", name);
+            return;
+        }
+        if self.binding_mode() == Some(name) {
+            return;
+        }
+        self.mode_stack.push(name.to_string());
+        self.mode_changed(compositor);
+    }
+
+    pub fn exit_mode(&mut self, compositor: &mut WebWMCompositor) {
+        if self.mode_stack.pop().is_some() {
+            self.mode_changed(compositor);
+        }
+    }
+
+    /// Go back to the default bindings from however deep in modes
+    pub fn reset_mode(&mut self, compositor: &mut WebWMCompositor) {
+        if !self.mode_stack.is_empty() {
+            self.mode_stack.clear();
+            self.mode_changed(compositor);
+        }
+    }
+
+    fn mode_changed(&mut self, compositor: &mut WebWMCompositor) {
+        let mode = self.binding_mode().unwrap_or("default").to_string();
+        println!("⌨️  Binding mode: {}", mode);
+        compositor.emit_event("mode", json!({ "mode": mode }));
+    }
+
+    /// Handle a key while in `mode`. Every key counts as handled, bound
+    /// or not, unless the mode is gone and the default bindings are back.
+    pub(super) fn check_mode_keybindings(
+        &mut self,
+        mode: &str,
+        key_name: &str,
+        mods: Modifiers,
+        compositor: &mut WebWMCompositor,
+    ) -> bool {
+        // The mode went away with a config reload
+        let Some(bindings) = compositor.config.modes.get(mode).cloned() else {
+            self.reset_mode(compositor);
+            return false;
+        };
+
+        if self.start_chord(&bindings, Some(mode), key_name, mods, compositor) {
+            return true;
+        }
+
+        let binding = bindings.iter().find(|binding| {
+            binding.key.to_lowercase() == key_name.to_lowercase()
+                && mods.matches(&binding.modifiers)
+                && binding.sequence.is_empty()
+        });
+        match binding {
+            Some(binding) => self.run_binding(binding, Some(mode), compositor),
+            None if key_name == "Escape" => self.reset_mode(compositor),
+            None => {}
+        }
+        true
+    }
+
+    /// Run the JS callback bound to `combo` in `mode`, or in the default
+    /// bindings, then what it asked for through `wm`
+    pub(super) fn run_js_binding(
+        &mut self,
+        mode: Option<&str>,
+        combo: &str,
+        compositor: &mut WebWMCompositor,
+    ) {
+        let Some(ref js_runtime) = compositor.js_runtime else {
+            return;
+        };
+        let actions = js_runtime.run_binding(mode, combo);
+        let reload = js_runtime.take_reload_request();
+
+        match actions {
+            Ok(actions) => {
+                for action in &actions {
+                    self.execute_action(action, compositor);
+                }
+            }
+            Err(e) => eprintln!("JS keybinding {} failed: {}", combo, e),
+        }
+        if reload {
+            compositor.schedule_config_reload();
+        }
+    }
+}
+
+impl WebWMCompositor {
+    /// Badge with the binding mode's name, next to the workspace buttons
+    pub fn render_binding_mode(&self) -> Vec<BarElement> {
+        let Some(mode) = self.input_handler.binding_mode() else {
+            return Vec::new();
+        };
+        let style = Style::load(self.stylesheet.as_ref(), ".binding-mode", DEFAULT_STYLE);
+
+        let output = self.primary_output_name();
+        let (x, y) = self
+            .bar_renderer
+            .as_ref()
+            .filter(|_| self.bar_visible_on(output.as_deref()))
+            .and_then(|bars| bars.workspaces_end(output.as_deref(), &self.workspace_manager))
+            .unwrap_or((MARGIN, self.bar_height() + MARGIN));
+
+        let mut canvas = Canvas::new();
+        canvas.fill(
+            rect(x, y, text::measure(mode, 13) + PADDING * 2, HEIGHT),
+            style.background,
+        );
+        canvas.text((x + PADDING, y + 3).into(), mode, style.color, 13);
+        canvas.finish()
+    }
+}
@@ -0,0 +1,326 @@
+// Backdrop blur (dual Kawase)
+//
+// Surfaces styled with `backdrop-filter: blur(Npx)` get the content behind
+// them blurred: the region is copied out of the frame, shrunk through a few
+// downsample passes and scaled back up, each pass averaging a handful of
+// neighbouring texels. Cost depends on the number of passes, not the radius.
+// The bar and floating windows whose rule class sets the property are
+// supported; everything is skipped in low-power mode.
+
+use smithay::backend::renderer::{
+    gles::{
+        GlesError, GlesRenderer, GlesTexProgram, GlesTexture, Uniform, UniformName, UniformType,
+    },
+    Bind, Frame, Renderer, Texture,
+};
+use smithay::utils::{Logical, Physical, Rectangle, Size, Transform};
+
+use crate::compositor::texture_pool::TexturePool;
+use crate::compositor::WebWMCompositor;
+use crate::config::StyleSheet;
+
+/// Radius beyond which more blur only costs more
+const MAX_RADIUS: f32 = 128.0;
+const MAX_PASSES: u32 = 5;
+
+const DOWNSAMPLE_SHADER: &str = r#"
+#version 100
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+precision mediump float;
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+uniform vec2 halfpixel;
+uniform float offset;
+varying vec2 v_coords;
+
+void main() {
+    vec2 uv = v_coords;
+    vec4 sum = texture2D(tex, uv) * 4.0;
+    sum += texture2D(tex, uv - halfpixel * offset);
+    sum += texture2D(tex, uv + halfpixel * offset);
+    sum += texture2D(tex, uv + vec2(halfpixel.x, -halfpixel.y) * offset);
+    sum += texture2D(tex, uv - vec2(halfpixel.x, -halfpixel.y) * offset);
+    gl_FragColor = sum / 8.0 * alpha;
+}
+"#;
+
+const UPSAMPLE_SHADER: &str = r#"
+#version 100
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+precision mediump float;
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+uniform vec2 halfpixel;
+uniform float offset;
+varying vec2 v_coords;
+
+void main() {
+    vec2 uv = v_coords;
+    vec4 sum = texture2D(tex, uv + vec2(-halfpixel.x * 2.0, 0.0) * offset);
+    sum += texture2D(tex, uv + vec2(-halfpixel.x, halfpixel.y) * offset) * 2.0;
+    sum += texture2D(tex, uv + vec2(0.0, halfpixel.y * 2.0) * offset);
+    sum += texture2D(tex, uv + vec2(halfpixel.x, halfpixel.y) * offset) * 2.0;
+    sum += texture2D(tex, uv + vec2(halfpixel.x * 2.0, 0.0) * offset);
+    sum += texture2D(tex, uv + vec2(halfpixel.x, -halfpixel.y) * offset) * 2.0;
+    sum += texture2D(tex, uv + vec2(0.0, -halfpixel.y * 2.0) * offset);
+    sum += texture2D(tex, uv + vec2(-halfpixel.x, -halfpixel.y) * offset) * 2.0;
+    gl_FragColor = sum / 12.0 * alpha;
+}
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlurSettings {
+    pub radius: f32,
+    /// Opacity of the blurred backdrop, from `backdrop-opacity`
+    pub opacity: f32,
+}
+
+impl BlurSettings {
+    /// From `backdrop-filter: blur(12px)` and `backdrop-opacity: 0.8`
+    pub fn from_stylesheet(stylesheet: &StyleSheet, selector: &str) -> Option<Self> {
+        let radius = parse_blur(&stylesheet.get_string(selector, "backdrop-filter")?)?;
+        let opacity = stylesheet
+            .get_number(selector, "backdrop-opacity")
+            .unwrap_or(1.0)
+            .clamp(0.0, 1.0);
+
+        Some(Self { radius, opacity })
+    }
+}
+
+/// A region to blur before whatever covers it is drawn
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backdrop {
+    /// In logical coordinates; the renderer scales it to the output
+    pub geometry: Rectangle<i32, Logical>,
+    pub settings: BlurSettings,
+}
+
+/// Radius in pixels from a `blur(12px)` filter
+pub fn parse_blur(value: &str) -> Option<f32> {
+    let radius = value
+        .trim()
+        .strip_prefix("blur(")?
+        .strip_suffix(')')?
+        .trim()
+        .trim_end_matches("px")
+        .parse::<f32>()
+        .ok()?;
+
+    (radius > 0.0).then_some(radius)
+}
+
+/// Number of down/up passes and the sample offset approximating `radius`.
+/// Each pass halves the texture, so n passes spread samples over roughly
+/// 2^(n+1) pixels times the offset.
+pub fn kawase_params(radius: f32) -> (u32, f32) {
+    let radius = radius.min(MAX_RADIUS);
+    let passes = (1..=MAX_PASSES)
+        .find(|n| 2f32.powi(*n as i32 + 1) >= radius)
+        .unwrap_or(MAX_PASSES);
+
+    (passes, radius / 2f32.powi(passes as i32 + 1))
+}
+
+/// Compiled shaders, created on first use
+#[derive(Default)]
+pub struct BlurRenderer {
+    programs: Option<(GlesTexProgram, GlesTexProgram)>,
+}
+
+impl BlurRenderer {
+    /// Blur `source`, returning a texture of the same size. Intermediate
+    /// textures come from and go back to `pool`; so does `source`.
+    pub fn blur(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        pool: &mut TexturePool,
+        source: GlesTexture,
+        settings: &BlurSettings,
+    ) -> Result<GlesTexture, GlesError> {
+        let (down, up) = self.programs(renderer)?;
+        let (passes, offset) = kawase_params(settings.radius);
+
+        let full = source.size();
+        let mut sizes = vec![full];
+        let mut current = source;
+
+        for pass in 1..=passes {
+            let size = Size::from(((full.w >> pass).max(1), (full.h >> pass).max(1)));
+            let mut next = pool.acquire(renderer, size)?;
+            run_pass(renderer, &current, &mut next, &down, offset)?;
+            pool.release(current);
+            current = next;
+            sizes.push(size);
+        }
+
+        for size in sizes.into_iter().rev().skip(1) {
+            let mut next = pool.acquire(renderer, size)?;
+            run_pass(renderer, &current, &mut next, &up, offset)?;
+            pool.release(current);
+            current = next;
+        }
+
+        Ok(current)
+    }
+
+    fn programs(
+        &mut self,
+        renderer: &mut GlesRenderer,
+    ) -> Result<(GlesTexProgram, GlesTexProgram), GlesError> {
+        if let Some(ref programs) = self.programs {
+            return Ok(programs.clone());
+        }
+
+        let uniforms = [
+            UniformName::new("halfpixel", UniformType::_2f),
+            UniformName::new("offset", UniformType::_1f),
+        ];
+        let down = renderer.compile_custom_texture_shader(DOWNSAMPLE_SHADER, &uniforms)?;
+        let up = renderer.compile_custom_texture_shader(UPSAMPLE_SHADER, &uniforms)?;
+
+        self.programs = Some((down.clone(), up.clone()));
+        Ok((down, up))
+    }
+}
+
+/// Draw `source` scaled into `target` through one blur shader
+fn run_pass(
+    renderer: &mut GlesRenderer,
+    source: &GlesTexture,
+    target: &mut GlesTexture,
+    program: &GlesTexProgram,
+    offset: f32,
+) -> Result<(), GlesError> {
+    let src_size = source.size();
+    let dst_size = target.size();
+    let dst = Rectangle::from_size(Size::<i32, Physical>::from((dst_size.w, dst_size.h)));
+    let halfpixel = (0.5 / src_size.w as f32, 0.5 / src_size.h as f32);
+
+    let mut framebuffer = renderer.bind(target)?;
+    let mut frame = renderer.render(&mut framebuffer, dst.size, Transform::Normal)?;
+    frame.render_texture_from_to(
+        source,
+        Rectangle::from_size(src_size.to_f64()),
+        dst,
+        &[dst],
+        &[],
+        Transform::Normal,
+        1.0,
+        Some(program),
+        &[
+            Uniform::new("halfpixel", halfpixel),
+            Uniform::new("offset", offset),
+        ],
+    )?;
+    frame.finish()?;
+
+    Ok(())
+}
+
+impl WebWMCompositor {
+    /// Regions to blur this frame, in drawing order: floating windows, then
+    /// bars. Empty in low-power mode.
+    pub fn backdrops(&mut self) -> Vec<Backdrop> {
+        if self.power.low_power(&self.config.performance.low_power) {
+            return Vec::new();
+        }
+        let Some(ref stylesheet) = self.stylesheet else {
+            return Vec::new();
+        };
+
+        let mut backdrops = Vec::new();
+
+        let workspace = self.workspace_manager.active_workspace();
+        for window in &workspace.windows {
+            if !workspace.is_floating(window) {
+                continue;
+            }
+            let rules: Vec<_> = self.window_rules_for(window).collect();
+
+            let settings = rules.iter().find_map(|rule| {
+                let class = rule.css_class.as_deref()?;
+                BlurSettings::from_stylesheet(stylesheet, &format!(".{}", class))
+            });
+            let (Some(settings), Some(location)) = (settings, self.space.element_location(window))
+            else {
+                continue;
+            };
+
+            let geometry = window.geometry();
+            let loc = location + geometry.loc;
+            backdrops.push(Backdrop {
+                geometry: Rectangle::from_loc_and_size(loc, geometry.size),
+                settings,
+            });
+        }
+
+        let output = self.primary_output_name();
+        if let Some(bar_renderer) = self
+            .bar_renderer
+            .as_ref()
+            .filter(|_| self.bar_visible_on(output.as_deref()))
+        {
+            for bar in bar_renderer
+                .bars
+                .iter()
+                .filter(|bar| bar.shown_on(output.as_deref()))
+            {
+                if let Some(settings) = BlurSettings::from_stylesheet(stylesheet, &bar.config.class)
+                {
+                    let geometry = bar.geometry;
+                    backdrops.push(Backdrop {
+                        geometry: Rectangle::from_loc_and_size(
+                            (geometry.loc.x, geometry.loc.y),
+                            (geometry.size.w, geometry.size.h),
+                        ),
+                        settings,
+                    });
+                }
+            }
+        }
+
+        backdrops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_blur() {
+        assert_eq!(parse_blur("blur(10px)"), Some(10.0));
+        assert_eq!(parse_blur(" blur( 4.5px ) "), Some(4.5));
+        assert_eq!(parse_blur("blur(0px)"), None);
+        assert_eq!(parse_blur("grayscale(1)"), None);
+    }
+
+    #[test]
+    fn test_kawase_params() {
+        assert_eq!(kawase_params(4.0), (1, 1.0));
+        assert_eq!(kawase_params(12.0), (3, 0.75));
+        assert_eq!(kawase_params(1.0), (1, 0.25));
+        assert_eq!(kawase_params(500.0), (5, 2.0));
+    }
+}
@@ -0,0 +1,335 @@
+// Calendar popup
+//
+// Clicking the bar's clock opens a month calendar below it; clicking the
+// clock again, clicking anywhere else or pressing Escape closes it. Left
+// and Right show the previous and next month. Weeks start on the clock's
+// `first-day-of-week`. Colors come from the stylesheet's `.calendar` and
+// `.calendar-today` rules. Like the clock, dates are in UTC.
+
+use smithay::input::keyboard::keysyms;
+use smithay::utils::{Logical, Point, Rectangle};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::ui::{dimmed, rect, Canvas, Style};
+use crate::compositor::WebWMCompositor;
+
+const CELL_WIDTH: i32 = 28;
+const ROW_HEIGHT: i32 = 20;
+const PADDING: i32 = 12;
+const BORDER_WIDTH: i32 = 1;
+
+const DEFAULT_STYLE: Style = Style::new(
+    [0.11, 0.11, 0.18, 0.97],
+    [0.8, 0.83, 0.96, 1.0],
+    [0.27, 0.28, 0.35, 1.0],
+);
+const DEFAULT_TODAY_STYLE: Style = Style::new(
+    [0.54, 0.71, 0.98, 1.0], // #89b4fa
+    [0.11, 0.11, 0.18, 1.0],
+    [0.54, 0.71, 0.98, 1.0],
+);
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+/// Monday first, like the weekday indexes below
+const WEEKDAY_NAMES: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+
+/// Days since 1970-01-01 of a date
+pub fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year } as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// (year, month, day) of a number of days since 1970-01-01
+pub fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+    (year, month, day)
+}
+
+/// 0 for Monday through 6 for Sunday
+pub fn weekday(year: i32, month: u32, day: u32) -> u32 {
+    // 1970-01-01 was a Thursday
+    (days_from_civil(year, month, day) + 3).rem_euclid(7) as u32
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    (days_from_civil(next_year, next_month, 1) - days_from_civil(year, month, 1)) as u32
+}
+
+/// The month as rows of weeks starting on `first_weekday`, with None for
+/// the days of neighbouring months
+pub fn month_grid(year: i32, month: u32, first_weekday: u32) -> Vec<[Option<u32>; 7]> {
+    let offset = (weekday(year, month, 1) + 7 - first_weekday) % 7;
+    let days = days_in_month(year, month);
+
+    let mut weeks = Vec::new();
+    let mut week = [None; 7];
+    for day in 1..=days {
+        let column = ((offset + day - 1) % 7) as usize;
+        week[column] = Some(day);
+        if column == 6 {
+            weeks.push(week);
+            week = [None; 7];
+        }
+    }
+    if week.iter().any(Option::is_some) {
+        weeks.push(week);
+    }
+    weeks
+}
+
+fn today() -> (i32, u32, u32) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    civil_from_days((secs / 86_400) as i64)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarPopup {
+    pub year: i32,
+    pub month: u32,
+    pub first_weekday: u32,
+    /// The clock it belongs to
+    pub clock: Rectangle<i32, Logical>,
+}
+
+impl CalendarPopup {
+    pub fn new(first_weekday: u32, clock: Rectangle<i32, Logical>) -> Self {
+        let (year, month, _) = today();
+        Self {
+            year,
+            month,
+            first_weekday,
+            clock,
+        }
+    }
+
+    /// Show the month `months` before or after the current one
+    pub fn shift(&mut self, months: i32) {
+        let index = self.year * 12 + self.month as i32 - 1 + months;
+        self.year = index.div_euclid(12);
+        self.month = index.rem_euclid(12) as u32 + 1;
+    }
+
+    /// Where the popup is drawn: below the clock, kept on an output
+    /// `output_width` wide
+    pub fn geometry(&self, output_width: i32) -> Rectangle<i32, Logical> {
+        let weeks = month_grid(self.year, self.month, self.first_weekday).len() as i32;
+        let width = 7 * CELL_WIDTH + 2 * PADDING;
+        let height = (weeks + 2) * ROW_HEIGHT + 2 * PADDING;
+        let x = self.clock.loc.x.min(output_width - width).max(0);
+        let y = self.clock.loc.y + self.clock.size.h + 4;
+        Rectangle::from_loc_and_size((x, y), (width, height))
+    }
+}
+
+impl WebWMCompositor {
+    /// Handle a button press for the calendar. Returns true when it was
+    /// meant for it; clicks elsewhere close it and go on to clients.
+    pub fn calendar_click(&mut self, location: Point<f64, Logical>) -> bool {
+        let point = location.to_i32_round();
+        let output = self.primary_output_name();
+        let bar_renderer = self
+            .bar_renderer
+            .as_ref()
+            .filter(|_| self.bar_visible_on(output.as_deref()));
+        let clock = bar_renderer.and_then(|bar_renderer| {
+            bar_renderer.clock_at(
+                (point.x, point.y),
+                output.as_deref(),
+                &self.workspace_manager,
+                self.get_focused_window_title(),
+            )
+        });
+
+        if let Some((first_weekday, clock)) = clock {
+            if self.calendar.take().is_none() {
+                println!("📅 Opening calendar");
+                self.calendar = Some(CalendarPopup::new(first_weekday, clock));
+            }
+            return true;
+        }
+
+        let output_width = self.layout_size().w;
+        match self.calendar {
+            Some(ref popup) if popup.geometry(output_width).contains(point) => true,
+            Some(_) => {
+                self.calendar = None;
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Escape closes the calendar, Left and Right page through months.
+    /// Returns true if the key was used.
+    pub fn calendar_key(&mut self, keysym: u32) -> bool {
+        let Some(ref mut popup) = self.calendar else {
+            return false;
+        };
+
+        match keysym {
+            keysyms::KEY_Escape => self.calendar = None,
+            keysyms::KEY_Left => popup.shift(-1),
+            keysyms::KEY_Right => popup.shift(1),
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn render_calendar(&self) -> Vec<BarElement> {
+        let Some(ref popup) = self.calendar else {
+            return Vec::new();
+        };
+
+        let stylesheet = self.stylesheet.as_ref();
+        let style = Style::load(stylesheet, ".calendar", DEFAULT_STYLE);
+        let today_style = Style::load(stylesheet, ".calendar-today", DEFAULT_TODAY_STYLE);
+
+        let geometry = popup.geometry(self.layout_size().w);
+        let (x, y) = (geometry.loc.x, geometry.loc.y);
+        let mut canvas = Canvas::new();
+        canvas.panel(geometry, &style, BORDER_WIDTH);
+
+        let title = format!("{} {}", MONTH_NAMES[popup.month as usize - 1], popup.year);
+        canvas.text_centered(
+            (x, y + PADDING).into(),
+            geometry.size.w,
+            title,
+            style.color,
+            13,
+        );
+
+        let left = x + PADDING;
+        let names_y = y + PADDING + ROW_HEIGHT;
+        for column in 0..7 {
+            let name = WEEKDAY_NAMES[((popup.first_weekday + column) % 7) as usize];
+            canvas.text(
+                (left + column as i32 * CELL_WIDTH + 6, names_y).into(),
+                name,
+                dimmed(style.color, 0.6),
+                13,
+            );
+        }
+
+        let today = today();
+        for (row, week) in month_grid(popup.year, popup.month, popup.first_weekday)
+            .iter()
+            .enumerate()
+        {
+            let cell_y = names_y + (row as i32 + 1) * ROW_HEIGHT;
+            for (column, day) in week.iter().enumerate() {
+                let Some(day) = *day else {
+                    continue;
+                };
+                let cell_x = left + column as i32 * CELL_WIDTH;
+                let is_today = (popup.year, popup.month, day) == today;
+                if is_today {
+                    canvas.fill(
+                        rect(cell_x, cell_y - 3, CELL_WIDTH - 2, ROW_HEIGHT - 2),
+                        today_style.background,
+                    );
+                }
+                canvas.text(
+                    (cell_x + if day < 10 { 10 } else { 6 }, cell_y).into(),
+                    day.to_string(),
+                    if is_today {
+                        today_style.color
+                    } else {
+                        style.color
+                    },
+                    13,
+                );
+            }
+        }
+
+        canvas.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(
+            civil_from_days(days_from_civil(2026, 10, 16)),
+            (2026, 10, 16)
+        );
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(weekday(2026, 10, 1), 3); // Thursday
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2100, 2), 28);
+        assert_eq!(days_in_month(2026, 12), 31);
+    }
+
+    #[test]
+    fn test_month_grid() {
+        // October 2026 starts on a Thursday
+        let weeks = month_grid(2026, 10, 0);
+        assert_eq!(weeks.len(), 5);
+        assert_eq!(
+            weeks[0],
+            [None, None, None, Some(1), Some(2), Some(3), Some(4)]
+        );
+        assert_eq!(weeks[4][5], Some(31));
+
+        // With Sunday first, the 4th begins the second week
+        let weeks = month_grid(2026, 10, 6);
+        assert_eq!(weeks[1][0], Some(4));
+
+        let mut popup = CalendarPopup {
+            year: 2026,
+            month: 12,
+            first_weekday: 0,
+            clock: Rectangle::from_loc_and_size((1800, 0), (120, 30)),
+        };
+        popup.shift(1);
+        assert_eq!((popup.year, popup.month), (2027, 1));
+        popup.shift(-13);
+        assert_eq!((popup.year, popup.month), (2025, 12));
+
+        // Kept on screen at the right edge
+        let geometry = popup.geometry(1920);
+        assert_eq!(geometry.loc.x + geometry.size.w, 1920);
+        assert_eq!(geometry.loc.y, 34);
+    }
+}
@@ -0,0 +1,236 @@
+// Key chords
+//
+// A keybinding can be a sequence of keys, like `Super+space, w, f` in
+// config.js. Its first key starts a chord, and each key after that narrows
+// down the bindings it could still be until one is complete. A key that
+// continues none of them, or Escape, drops the chord, and so does
+// `<chords timeout>` passing without a key; a timeout runs the binding the
+// keys so far complete, if there is one, so `Super+space` bound on its own
+// still works after waiting. While a chord is pending a hint lists the keys
+// that can come next, unless `<chords hint="false">`; its colors come from
+// the `.chord-hint` rule. Chords work the same in binding modes.
+
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use smithay::reexports::calloop::RegistrationToken;
+use std::time::Duration;
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::input::{InputHandler, Modifiers};
+use crate::compositor::ui::{rect, Canvas, Style};
+use crate::compositor::{text, WebWMCompositor};
+use crate::config::{parse_key_combo, Action, ChordSettings, Keybinding};
+
+const TITLE_SIZE: u32 = 13;
+const LINE_SIZE: u32 = 12;
+const PADDING: i32 = 12;
+const BORDER_WIDTH: i32 = 1;
+/// Room between the hint and the bottom of the screen
+const MARGIN: i32 = 48;
+
+const DEFAULT_STYLE: Style = Style::new(
+    [0.11, 0.11, 0.18, 0.95],
+    [0.8, 0.83, 0.96, 1.0],
+    [0.54, 0.71, 0.98, 1.0], // #89b4fa
+);
+
+/// A chord waiting for its next key
+pub struct PendingChord {
+    /// Keys pressed so far, the first included
+    keys: Vec<String>,
+    /// Bindings the keys so far could still be, all with keys left
+    candidates: Vec<Keybinding>,
+    /// Binding the keys so far complete, run if the chord times out
+    complete: Option<Keybinding>,
+    /// Binding mode the bindings are from
+    mode: Option<String>,
+    timer: Option<RegistrationToken>,
+}
+
+/// Whether the key named `key_name`, pressed with `mods`, is the one
+/// `stroke` (such as `Shift+w`) names
+fn stroke_matches(stroke: &str, key_name: &str, mods: Modifiers) -> bool {
+    let (modifiers, key) = parse_key_combo(stroke);
+    key.eq_ignore_ascii_case(key_name) && mods.matches(&modifiers)
+}
+
+fn chord_settings(compositor: &WebWMCompositor) -> ChordSettings {
+    compositor
+        .config
+        .desktop
+        .as_ref()
+        .map(|desktop| desktop.chords.clone())
+        .unwrap_or_default()
+}
+
+impl InputHandler {
+    /// Start a chord if the key begins one of `bindings`. Returns false
+    /// if it doesn't, for the key to be handled as usual.
+    pub(super) fn start_chord(
+        &mut self,
+        bindings: &[Keybinding],
+        mode: Option<&str>,
+        key_name: &str,
+        mods: Modifiers,
+        compositor: &mut WebWMCompositor,
+    ) -> bool {
+        let first_key = |binding: &&Keybinding| {
+            binding.key.eq_ignore_ascii_case(key_name) && mods.matches(&binding.modifiers)
+        };
+        let candidates: Vec<Keybinding> = bindings
+            .iter()
+            .filter(first_key)
+            .filter(|binding| !binding.sequence.is_empty())
+            .cloned()
+            .collect();
+        if candidates.is_empty() {
+            return false;
+        }
+
+        let complete = bindings
+            .iter()
+            .filter(first_key)
+            .find(|binding| binding.sequence.is_empty())
+            .cloned();
+        self.chord = Some(PendingChord {
+            keys: vec![mods.combo(key_name)],
+            candidates,
+            complete,
+            mode: mode.map(str::to_string),
+            timer: None,
+        });
+        self.restart_chord_timer(compositor);
+        true
+    }
+
+    /// Take the next key of the pending chord. Every key goes to the chord.
+    pub(super) fn continue_chord(
+        &mut self,
+        key_name: &str,
+        mods: Modifiers,
+        compositor: &mut WebWMCompositor,
+    ) -> bool {
+        let Some(mut chord) = self.chord.take() else {
+            return false;
+        };
+        if let Some(timer) = chord.timer.take() {
+            compositor.loop_handle.remove(timer);
+        }
+
+        let depth = chord.keys.len() - 1;
+        let (complete, longer): (Vec<Keybinding>, Vec<Keybinding>) = chord
+            .candidates
+            .into_iter()
+            .filter(|binding| stroke_matches(&binding.sequence[depth], key_name, mods))
+            .partition(|binding| binding.sequence.len() == depth + 1);
+
+        if longer.is_empty() {
+            match complete.first() {
+                Some(binding) => self.run_binding(binding, chord.mode.as_deref(), compositor),
+                None => println!("⌨️  No chord {}, {}", chord.keys.join(", "), key_name),
+            }
+            return true;
+        }
+
+        chord.keys.push(mods.combo(key_name));
+        chord.candidates = longer;
+        chord.complete = complete.into_iter().next();
+        self.chord = Some(chord);
+        self.restart_chord_timer(compositor);
+        true
+    }
+
+    /// Give up on the pending chord, running the binding its keys complete
+    pub fn chord_timed_out(&mut self, compositor: &mut WebWMCompositor) {
+        let Some(chord) = self.chord.take() else {
+            return;
+        };
+        if let Some(ref binding) = chord.complete {
+            self.run_binding(binding, chord.mode.as_deref(), compositor);
+        }
+    }
+
+    fn restart_chord_timer(&mut self, compositor: &mut WebWMCompositor) {
+        let timeout = Duration::from_millis(chord_settings(compositor).timeout_ms as u64);
+        let scheduled = compositor.loop_handle.insert_source(
+            Timer::from_duration(timeout),
+            |_, _, compositor| {
+                let mut handler = std::mem::take(&mut compositor.input_handler);
+                handler.chord_timed_out(compositor);
+                compositor.input_handler = handler;
+                TimeoutAction::Drop
+            },
+        );
+        match (scheduled, self.chord.as_mut()) {
+            (Ok(token), Some(chord)) => chord.timer = Some(token),
+            (Ok(token), None) => compositor.loop_handle.remove(token),
+            (Err(e), _) => eprintln!("Failed to time the chord: {}", e),
+        }
+    }
+
+    /// Run `binding` from `mode`'s keybindings, or the default ones
+    pub(super) fn run_binding(
+        &mut self,
+        binding: &Keybinding,
+        mode: Option<&str>,
+        compositor: &mut WebWMCompositor,
+    ) {
+        println!("Matched keybinding: {:?}", binding);
+        match (mode, &binding.action) {
+            (Some(mode), Action::Custom { js }) => self.run_js_binding(Some(mode), js, compositor),
+            (_, action) => self.execute_action(action, compositor),
+        }
+    }
+}
+
+impl WebWMCompositor {
+    /// The keys that can continue the pending chord, at the bottom of the
+    /// screen
+    pub fn render_chord_hint(&self) -> Vec<BarElement> {
+        let Some(ref chord) = self.input_handler.chord else {
+            return Vec::new();
+        };
+        if !chord_settings(self).hint {
+            return Vec::new();
+        }
+        let style = Style::load(self.stylesheet.as_ref(), ".chord-hint", DEFAULT_STYLE);
+
+        let depth = chord.keys.len() - 1;
+        let title = format!("{}, …", chord.keys.join(", "));
+        let mut lines: Vec<String> = chord
+            .candidates
+            .iter()
+            .map(|binding| binding.sequence[depth..].join(", "))
+            .collect();
+        lines.sort();
+        lines.dedup();
+
+        let width = lines
+            .iter()
+            .map(|line| text::measure(line, LINE_SIZE))
+            .chain(std::iter::once(text::measure(&title, TITLE_SIZE)))
+            .max()
+            .unwrap_or_default()
+            + PADDING * 2;
+        let height =
+            TITLE_SIZE as i32 + 8 + lines.len() as i32 * (LINE_SIZE as i32 + 4) + PADDING * 2;
+        let size = self.layout_size();
+        let geometry = rect(
+            (size.w - width) / 2,
+            size.h - height - MARGIN,
+            width,
+            height,
+        );
+
+        let mut canvas = Canvas::new();
+        canvas.panel(geometry, &style, BORDER_WIDTH);
+        let x = geometry.loc.x + PADDING;
+        let mut y = geometry.loc.y + PADDING;
+        canvas.text((x, y).into(), title, style.border_color, TITLE_SIZE);
+        y += TITLE_SIZE as i32 + 8;
+        for line in lines {
+            canvas.text((x, y).into(), line, style.color, LINE_SIZE);
+            y += LINE_SIZE as i32 + 4;
+        }
+        canvas.finish()
+    }
+}
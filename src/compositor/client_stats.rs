@@ -0,0 +1,259 @@
+// Per-client resource usage
+//
+// Counts the live surfaces and attached buffers of every client, estimates
+// the memory behind those buffers and measures how many frame callbacks it
+// asks for per second. `webwm-msg clients` prints the table. A client holding
+// far more buffers than it has surfaces is almost certainly creating new ones
+// without destroying the old, and gets a warning in the log.
+//
+// Ownership is remembered by object id, since a dead client's resources can
+// no longer be asked for their client while they are being destroyed.
+
+use serde_json::{json, Value};
+use smithay::backend::renderer::buffer_dimensions;
+use smithay::reexports::wayland_server::{
+    backend::{ClientId, ObjectId},
+    protocol::{wl_buffer::WlBuffer, wl_surface::WlSurface},
+    Resource,
+};
+use smithay::wayland::compositor::{with_states, BufferAssignment, SurfaceAttributes};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+/// More attached buffers per surface than this counts as a leak. Triple
+/// buffering plus a resize or two stays well below it.
+pub const MAX_BUFFERS_PER_SURFACE: usize = 8;
+/// Buffers are assumed to be 32-bit per pixel
+const BYTES_PER_PIXEL: usize = 4;
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Events per second, measured over windows of RATE_WINDOW
+#[derive(Debug, Default, Clone)]
+pub struct RateCounter {
+    window_start: Option<Instant>,
+    count: u32,
+    last_rate: f64,
+}
+
+impl RateCounter {
+    pub fn record(&mut self, now: Instant, count: u32) {
+        let start = *self.window_start.get_or_insert(now);
+        self.count += count;
+
+        let elapsed = now.duration_since(start);
+        if elapsed >= RATE_WINDOW {
+            self.last_rate = self.count as f64 / elapsed.as_secs_f64();
+            self.window_start = Some(now);
+            self.count = 0;
+        }
+    }
+
+    /// Rate of the last complete window. A client that went quiet is
+    /// measured up to `now` instead, so its rate decays.
+    pub fn per_second(&self, now: Instant) -> f64 {
+        match self.window_start {
+            Some(start) if now.duration_since(start) > RATE_WINDOW * 2 => {
+                self.count as f64 / now.duration_since(start).as_secs_f64()
+            }
+            _ => self.last_rate,
+        }
+    }
+}
+
+pub fn leaking(buffers: usize, surfaces: usize) -> bool {
+    buffers > surfaces.max(1) * MAX_BUFFERS_PER_SURFACE
+}
+
+#[derive(Debug, Default)]
+pub struct ClientStats {
+    pub surfaces: usize,
+    /// Estimated size of every buffer the client attached and still holds
+    buffers: HashMap<ObjectId, usize>,
+    frame_callbacks: RateCounter,
+    leak_warned: bool,
+}
+
+impl ClientStats {
+    pub fn buffer_count(&self) -> usize {
+        self.buffers.len()
+    }
+
+    pub fn buffer_bytes(&self) -> usize {
+        self.buffers.values().sum()
+    }
+
+    pub fn leaking(&self) -> bool {
+        leaking(self.buffers.len(), self.surfaces)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ClientMonitor {
+    clients: HashMap<ClientId, ClientStats>,
+    /// Client of every live surface and tracked buffer
+    owners: HashMap<ObjectId, ClientId>,
+}
+
+impl ClientMonitor {
+    /// Clients with at least one surface or buffer
+    pub fn client_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    fn forget(&mut self, object: &ObjectId) -> Option<&mut ClientStats> {
+        let client = self.owners.remove(object)?;
+        self.clients.get_mut(&client)
+    }
+
+    /// Drop clients with nothing left, which is where a disconnect ends up
+    fn prune(&mut self) {
+        self.clients
+            .retain(|_, stats| stats.surfaces > 0 || !stats.buffers.is_empty());
+    }
+}
+
+impl WebWMCompositor {
+    pub fn track_new_surface(&mut self, surface: &WlSurface) {
+        let Some(client) = surface.client() else {
+            return;
+        };
+        let monitor = &mut self.client_monitor;
+        monitor.owners.insert(surface.id(), client.id());
+        monitor.clients.entry(client.id()).or_default().surfaces += 1;
+    }
+
+    pub fn track_surface_destroyed(&mut self, surface: &WlSurface) {
+        if let Some(stats) = self.client_monitor.forget(&surface.id()) {
+            stats.surfaces = stats.surfaces.saturating_sub(1);
+        }
+        self.client_monitor.prune();
+    }
+
+    pub fn track_buffer_destroyed(&mut self, buffer: &WlBuffer) {
+        if let Some(stats) = self.client_monitor.forget(&buffer.id()) {
+            stats.buffers.remove(&buffer.id());
+        }
+        self.client_monitor.prune();
+    }
+
+    /// Record the buffer and frame callbacks of a commit. Has to run before
+    /// the buffer is taken out of the surface state for rendering.
+    pub fn track_commit(&mut self, surface: &WlSurface) {
+        let Some(client) = surface.client() else {
+            return;
+        };
+
+        let (buffer, callbacks) = with_states(surface, |states| {
+            let mut attributes = states.cached_state.get::<SurfaceAttributes>();
+            let current = attributes.current();
+            let buffer = match current.buffer {
+                Some(BufferAssignment::NewBuffer(ref buffer)) => Some(buffer.clone()),
+                _ => None,
+            };
+            (buffer, current.frame_callbacks.len() as u32)
+        });
+
+        let monitor = &mut self.client_monitor;
+        let stats = monitor.clients.entry(client.id()).or_default();
+        stats.frame_callbacks.record(Instant::now(), callbacks);
+
+        let Some(buffer) = buffer else {
+            return;
+        };
+        let bytes = buffer_dimensions(&buffer)
+            .map(|size| size.w.max(0) as usize * size.h.max(0) as usize * BYTES_PER_PIXEL)
+            .unwrap_or(0);
+        stats.buffers.insert(buffer.id(), bytes);
+
+        if stats.leaking() && !stats.leak_warned {
+            stats.leak_warned = true;
+            eprintln!(
+                "⚠️  Client {:?} may be leaking: {} buffers ({} KiB) for {} surfaces",
+                client.id(),
+                stats.buffers.len(),
+                stats.buffer_bytes() / 1024,
+                stats.surfaces
+            );
+        } else if !stats.leaking() {
+            stats.leak_warned = false;
+        }
+        monitor.owners.insert(buffer.id(), client.id());
+    }
+
+    /// One entry per connected client with surfaces or buffers, the largest
+    /// buffer users first
+    pub fn client_report(&self) -> Value {
+        let now = Instant::now();
+        let handle = self.display_handle.backend_handle();
+
+        let mut clients: Vec<(&ClientId, &ClientStats)> =
+            self.client_monitor.clients.iter().collect();
+        clients.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.buffer_bytes()));
+
+        let report: Vec<Value> = clients
+            .into_iter()
+            .map(|(client, stats)| {
+                let pid = handle
+                    .get_client_credentials(client.clone())
+                    .ok()
+                    .map(|credentials| credentials.pid);
+                json!({
+                    "pid": pid,
+                    "app_ids": self.client_app_ids(client),
+                    "surfaces": stats.surfaces,
+                    "buffers": stats.buffer_count(),
+                    "buffer_kib": stats.buffer_bytes() / 1024,
+                    "frame_callbacks_per_sec":
+                        (stats.frame_callbacks.per_second(now) * 10.0).round() / 10.0,
+                    "leaking": stats.leaking(),
+                })
+            })
+            .collect();
+
+        Value::Array(report)
+    }
+
+    fn client_app_ids(&self, client: &ClientId) -> Vec<String> {
+        self.workspace_manager
+            .all_workspaces()
+            .into_iter()
+            .flat_map(|workspace| workspace.windows.iter())
+            .filter(|window| {
+                window
+                    .toplevel()
+                    .and_then(|toplevel| toplevel.wl_surface().client())
+                    .is_some_and(|c| &c.id() == client)
+            })
+            .map(window_app_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_counter() {
+        let start = Instant::now();
+        let mut rate = RateCounter::default();
+        for i in 0..=60 {
+            rate.record(start + Duration::from_millis(i * 1000 / 60), 1);
+        }
+        let end = start + Duration::from_secs(1);
+        assert!((rate.per_second(end) - 61.0).abs() < 0.5);
+
+        // Nothing for a while: the rate falls off
+        assert!(rate.per_second(end + Duration::from_secs(10)) < 1.0);
+    }
+
+    #[test]
+    fn test_leak_threshold() {
+        assert!(!leaking(3, 1));
+        assert!(!leaking(8, 0));
+        assert!(leaking(9, 1));
+        assert!(!leaking(20, 4));
+    }
+}
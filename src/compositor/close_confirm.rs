@@ -0,0 +1,110 @@
+// Close confirmation
+//
+// Windows matching a rule with `confirm-close="true"` aren't closed right
+// away by the close binding. A small prompt is drawn over the window
+// instead: Enter closes it, Escape keeps it. While the prompt is up it has
+// the keyboard, so a second slip of Super+q can't get past it; switching
+// workspaces drops it. Clients closing themselves, and force close, are not
+// affected. Colors come from the stylesheet's `.close-confirm` rule.
+
+use smithay::desktop::Window;
+use smithay::input::keyboard::keysyms;
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::ui::{rect, Canvas, Style};
+use crate::compositor::{text, window_app_id, WebWMCompositor};
+
+const PROMPT_HEIGHT: i32 = 52;
+const PADDING: i32 = 14;
+const BORDER_WIDTH: i32 = 1;
+
+const DEFAULT_STYLE: Style = Style::new(
+    [0.11, 0.11, 0.18, 0.97],
+    [0.8, 0.83, 0.96, 1.0],
+    [0.95, 0.55, 0.66, 1.0], // #f38ba8
+);
+
+const HINT: &str = "Enter to close, Esc to cancel";
+
+impl WebWMCompositor {
+    /// Close the focused window, asking first if its rules say so
+    pub fn close_focused(&mut self) {
+        let Some(window) = self.workspace_manager.focused_window().cloned() else {
+            return;
+        };
+
+        let confirm = self
+            .window_rules_for(&window)
+            .find_map(|rule| rule.confirm_close)
+            .unwrap_or(false);
+        if confirm {
+            println!("❓ Confirm closing '{}'", window_app_id(&window));
+            self.close_confirm = Some(window);
+        } else if let Some(toplevel) = window.toplevel() {
+            toplevel.send_close();
+        }
+    }
+
+    /// Enter closes the window being confirmed, Escape cancels. Returns
+    /// true while the prompt is open, so no other key gets through.
+    pub fn close_confirm_key(&mut self, keysym: u32) -> bool {
+        let Some(window) = self.close_confirm.clone() else {
+            return false;
+        };
+        // Switching away from the window forgets the question
+        if !self
+            .workspace_manager
+            .active_workspace()
+            .windows
+            .contains(&window)
+        {
+            self.close_confirm = None;
+            return false;
+        }
+
+        match keysym {
+            keysyms::KEY_Return | keysyms::KEY_KP_Enter => {
+                println!("🗑️ Closing '{}'", window_app_id(&window));
+                self.close_confirm = None;
+                if let Some(toplevel) = window.toplevel() {
+                    toplevel.send_close();
+                }
+            }
+            keysyms::KEY_Escape => {
+                println!("↩️ Kept '{}' open", window_app_id(&window));
+                self.close_confirm = None;
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Drop the prompt of a window that went away on its own
+    pub fn cancel_close_confirm(&mut self, window: &Window) {
+        if self.close_confirm.as_ref() == Some(window) {
+            self.close_confirm = None;
+        }
+    }
+
+    pub fn render_close_confirm(&self) -> Vec<BarElement> {
+        let Some(window) = self.close_confirm.as_ref() else {
+            return Vec::new();
+        };
+        let Some(geometry) = self.space.element_geometry(window) else {
+            return Vec::new();
+        };
+
+        let style = Style::load(self.stylesheet.as_ref(), ".close-confirm", DEFAULT_STYLE);
+
+        let question = format!("Close {}?", window_app_id(window));
+        let width = text::measure(&question, 13).max(text::measure(HINT, 10)) + PADDING * 2;
+        let x = geometry.loc.x + (geometry.size.w - width) / 2;
+        let y = geometry.loc.y + (geometry.size.h - PROMPT_HEIGHT) / 2;
+
+        let mut canvas = Canvas::new();
+        canvas.panel(rect(x, y, width, PROMPT_HEIGHT), &style, BORDER_WIDTH);
+        canvas.text((x + PADDING, y + 8).into(), question, style.color, 13);
+        canvas.text((x + PADDING, y + 29).into(), HINT, style.color, 10);
+        canvas.finish()
+    }
+}
@@ -0,0 +1,542 @@
+// Output color correction with ICC profiles
+//
+// Everything WebWM draws is sRGB. An output with an ICC profile configured
+// (`<output name="..." icc-profile="..."/>`) gets those colors converted to
+// the panel's own primaries and tone response: decode sRGB to linear light,
+// go through the profile's colorant matrix (via the D50 PCS) and re-encode
+// with the panel's curve, fitted to a per-channel gamma for the shader.
+// Solid colors are converted on the CPU, textures through a GLES shader.
+//
+// HDR outputs (see output_format.rs) use the same path with a BT.709 to
+// BT.2020 matrix and PQ encoding instead of a gamma, placing sRGB white at
+// the SDR reference level.
+//
+// Only matrix/TRC display profiles are supported, which is what calibration
+// tools produce for monitors. LUT-based profiles are rejected.
+//
+// This only corrects outputs. Clients can't tell WebWM what color space they
+// draw in (there's no wp_color_management_v1), so their content is taken to
+// be sRGB, as they assume themselves without that protocol.
+
+use smithay::backend::renderer::gles::{
+    GlesError, GlesRenderer, GlesTexProgram, Uniform, UniformName, UniformType, UniformValue,
+};
+use std::collections::HashMap;
+
+use crate::compositor::WebWMCompositor;
+
+/// sRGB primaries adapted to D50 (Bradford), as used by the ICC PCS
+const SRGB_TO_XYZ_D50: [[f64; 3]; 3] = [
+    [0.4360747, 0.3850649, 0.1430804],
+    [0.2225045, 0.7168786, 0.0606169],
+    [0.0139322, 0.0971045, 0.7141733],
+];
+
+/// Linear BT.709 (sRGB) to linear BT.2020, both D65 (ITU-R BT.2087)
+const BT709_TO_BT2020: [[f64; 3]; 3] = [
+    [0.6274040, 0.3292820, 0.0433136],
+    [0.0690970, 0.9195400, 0.0113612],
+    [0.0163916, 0.0880132, 0.8955950],
+];
+
+/// SMPTE ST 2084 (PQ) constants
+const PQ_M1: f64 = 0.1593017578125;
+const PQ_M2: f64 = 78.84375;
+const PQ_C1: f64 = 0.8359375;
+const PQ_C2: f64 = 18.8515625;
+const PQ_C3: f64 = 18.6875;
+/// Peak luminance PQ encodes, in nits
+const PQ_MAX_NITS: f64 = 10000.0;
+
+const COLOR_SHADER: &str = r#"
+#version 100
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+uniform mat3 color_matrix;
+uniform vec3 gamma;
+uniform float pq_white;
+varying vec2 v_coords;
+
+vec3 pq_encode(vec3 nits) {
+    vec3 y = pow(nits / 10000.0, vec3(0.1593017578125));
+    return pow((0.8359375 + 18.8515625 * y) / (1.0 + 18.6875 * y), vec3(78.84375));
+}
+
+void main() {
+    vec4 color = texture2D(tex, v_coords);
+    vec3 rgb = color.a > 0.0 ? color.rgb / color.a : color.rgb;
+
+    vec3 linear = mix(rgb / 12.92, pow((rgb + 0.055) / 1.055, vec3(2.4)), step(0.04045, rgb));
+    vec3 panel = clamp(color_matrix * linear, 0.0, 1.0);
+    vec3 encoded = pq_white > 0.0 ? pq_encode(panel * pq_white) : pow(panel, 1.0 / gamma);
+
+    gl_FragColor = vec4(encoded * color.a, color.a) * alpha;
+}
+"#;
+
+/// Tone response curve of one channel, device value to linear light
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToneCurve {
+    Gamma(f64),
+    Table(Vec<f64>),
+    /// ICC parametric curve: function type and its parameters (g, a, b, ...)
+    Parametric(u16, Vec<f64>),
+}
+
+impl ToneCurve {
+    pub fn eval(&self, x: f64) -> f64 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            ToneCurve::Gamma(g) => x.powf(*g),
+            ToneCurve::Table(table) => {
+                let pos = x * (table.len() - 1) as f64;
+                let i = (pos.floor() as usize).min(table.len() - 2);
+                let t = pos - i as f64;
+                table[i] * (1.0 - t) + table[i + 1] * t
+            }
+            ToneCurve::Parametric(kind, p) => {
+                let param = |i: usize| p.get(i).copied().unwrap_or(0.0);
+                let (g, a, b, c, d, e, f) = (
+                    param(0),
+                    param(1),
+                    param(2),
+                    param(3),
+                    param(4),
+                    param(5),
+                    param(6),
+                );
+                match kind {
+                    0 => x.powf(g),
+                    1 if x >= -b / a => (a * x + b).powf(g),
+                    1 => 0.0,
+                    2 if x >= -b / a => (a * x + b).powf(g) + c,
+                    2 => c,
+                    3 if x >= d => (a * x + b).powf(g),
+                    3 => c * x,
+                    _ if x >= d => (a * x + b).powf(g) + e,
+                    _ => c * x + f,
+                }
+            }
+        }
+    }
+
+    /// Single exponent that best matches the curve through the midtones
+    pub fn fitted_gamma(&self) -> f64 {
+        let samples: Vec<f64> = (1..10)
+            .map(|i| i as f64 / 10.0)
+            .filter_map(|x| {
+                let y = self.eval(x);
+                (y > 0.0 && y < 1.0).then(|| y.ln() / x.ln())
+            })
+            .collect();
+
+        if samples.is_empty() {
+            return 2.2;
+        }
+        samples.iter().sum::<f64>() / samples.len() as f64
+    }
+}
+
+/// The parts of a matrix/TRC display profile needed for conversion
+#[derive(Debug, Clone, PartialEq)]
+pub struct IccProfile {
+    /// Red, green and blue colorants (XYZ, D50)
+    pub colorants: [[f64; 3]; 3],
+    pub curves: [ToneCurve; 3],
+}
+
+impl IccProfile {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 132 || &data[36..40] != b"acsp" {
+            return Err("Not an ICC profile".to_string());
+        }
+
+        let tag_count = read_u32(data, 128)? as usize;
+        let mut tags = HashMap::new();
+        for i in 0..tag_count {
+            let entry = 132 + i * 12;
+            let signature = data
+                .get(entry..entry + 4)
+                .ok_or("Truncated tag table")?
+                .to_vec();
+            let offset = read_u32(data, entry + 4)? as usize;
+            let size = read_u32(data, entry + 8)? as usize;
+            let tag = data.get(offset..offset + size).ok_or_else(|| {
+                format!("Tag {} out of bounds", String::from_utf8_lossy(&signature))
+            })?;
+            tags.insert(signature, tag);
+        }
+
+        let tag = |name: &[u8; 4]| {
+            tags.get(name.as_slice()).copied().ok_or_else(|| {
+                format!(
+                    "Missing {} tag (only matrix/TRC profiles are supported)",
+                    String::from_utf8_lossy(name)
+                )
+            })
+        };
+
+        let colorants = [
+            parse_xyz(tag(b"rXYZ")?)?,
+            parse_xyz(tag(b"gXYZ")?)?,
+            parse_xyz(tag(b"bXYZ")?)?,
+        ];
+        let curves = [
+            parse_curve(tag(b"rTRC")?)?,
+            parse_curve(tag(b"gTRC")?)?,
+            parse_curve(tag(b"bTRC")?)?,
+        ];
+
+        Ok(Self { colorants, curves })
+    }
+}
+
+/// sRGB to output conversion derived from a profile, or for an HDR output
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorTransform {
+    /// Linear sRGB to linear panel RGB, row-major
+    pub matrix: [[f64; 3]; 3],
+    pub gamma: [f64; 3],
+    /// PQ-encode instead of applying `gamma`, with sRGB white at this many nits
+    pub pq_white: Option<f64>,
+}
+
+impl ColorTransform {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let profile = IccProfile::parse(&data).map_err(|e| format!("{}: {}", path, e))?;
+        Self::from_profile(&profile)
+    }
+
+    pub fn from_profile(profile: &IccProfile) -> Result<Self, String> {
+        // Colorants are the columns of the panel's RGB -> XYZ matrix
+        let c = &profile.colorants;
+        let panel_to_xyz = [
+            [c[0][0], c[1][0], c[2][0]],
+            [c[0][1], c[1][1], c[2][1]],
+            [c[0][2], c[1][2], c[2][2]],
+        ];
+        let xyz_to_panel = invert(&panel_to_xyz).ok_or("Profile colorants are degenerate")?;
+
+        Ok(Self {
+            matrix: multiply(&xyz_to_panel, &SRGB_TO_XYZ_D50),
+            gamma: std::array::from_fn(|i| profile.curves[i].fitted_gamma()),
+            pq_white: None,
+        })
+    }
+
+    /// sRGB content on a BT.2020/PQ output, white at `reference_white` nits
+    pub fn hdr(reference_white: f64) -> Self {
+        Self {
+            matrix: BT709_TO_BT2020,
+            gamma: [1.0; 3],
+            pq_white: Some(reference_white),
+        }
+    }
+
+    /// Convert an sRGB color (RGBA, 0..1) for this output
+    pub fn apply(&self, color: [f32; 4]) -> [f32; 4] {
+        let linear: [f64; 3] = std::array::from_fn(|i| srgb_to_linear(color[i] as f64));
+        let mut out = [0.0f32; 4];
+        for (i, row) in self.matrix.iter().enumerate() {
+            let value =
+                (row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2]).clamp(0.0, 1.0);
+            out[i] = match self.pq_white {
+                Some(white) => pq_encode(value * white),
+                None => value.powf(1.0 / self.gamma[i]),
+            } as f32;
+        }
+        out[3] = color[3];
+        out
+    }
+
+    /// Uniforms for `color_program`
+    pub fn uniforms(&self) -> Vec<Uniform<'static>> {
+        // GL wants column-major unless asked to transpose
+        let m = &self.matrix;
+        let matrix = [
+            m[0][0], m[0][1], m[0][2], m[1][0], m[1][1], m[1][2], m[2][0], m[2][1], m[2][2],
+        ]
+        .map(|v| v as f32);
+
+        vec![
+            Uniform::new(
+                "color_matrix",
+                UniformValue::Matrix3x3 {
+                    matrices: vec![matrix],
+                    transpose: true,
+                },
+            ),
+            Uniform::new(
+                "gamma",
+                (
+                    self.gamma[0] as f32,
+                    self.gamma[1] as f32,
+                    self.gamma[2] as f32,
+                ),
+            ),
+            Uniform::new("pq_white", self.pq_white.unwrap_or(0.0) as f32),
+        ]
+    }
+}
+
+/// Texture shader applying a `ColorTransform`, see `ColorTransform::uniforms`
+pub fn color_program(renderer: &mut GlesRenderer) -> Result<GlesTexProgram, GlesError> {
+    renderer.compile_custom_texture_shader(
+        COLOR_SHADER,
+        &[
+            UniformName::new("color_matrix", UniformType::Matrix3x3),
+            UniformName::new("gamma", UniformType::_3f),
+            UniformName::new("pq_white", UniformType::_1f),
+        ],
+    )
+}
+
+impl WebWMCompositor {
+    /// Load the ICC profiles named in `<outputs>`. Outputs whose profile
+    /// fails to load are left uncorrected.
+    pub fn load_color_profiles(&mut self) {
+        let Some(ref desktop) = self.config.desktop else {
+            return;
+        };
+
+        self.color_transforms.clear();
+        for output in &desktop.outputs {
+            let Some(ref path) = output.icc_profile else {
+                continue;
+            };
+
+            match ColorTransform::load(path) {
+                Ok(transform) => {
+                    println!("🎨 Color profile for {}: {}", output.name, path);
+                    self.color_transforms.insert(output.name.clone(), transform);
+                }
+                Err(e) => eprintln!("⚠️  Ignoring color profile for {}: {}", output.name, e),
+            }
+        }
+    }
+
+    pub fn color_transform_for(&self, output_name: &str) -> Option<&ColorTransform> {
+        self.color_transforms.get(output_name)
+    }
+}
+
+fn srgb_to_linear(v: f64) -> f64 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Absolute luminance in nits to a PQ signal value
+fn pq_encode(nits: f64) -> f64 {
+    let y = (nits / PQ_MAX_NITS).clamp(0.0, 1.0).powf(PQ_M1);
+    ((PQ_C1 + PQ_C2 * y) / (1.0 + PQ_C3 * y)).powf(PQ_M2)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, String> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or("Truncated ICC profile")?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_s15f16(data: &[u8], offset: usize) -> Result<f64, String> {
+    Ok(read_u32(data, offset)? as i32 as f64 / 65536.0)
+}
+
+fn parse_xyz(tag: &[u8]) -> Result<[f64; 3], String> {
+    if tag.get(0..4) != Some(b"XYZ ".as_slice()) {
+        return Err("Colorant tag is not XYZ".to_string());
+    }
+    Ok([
+        read_s15f16(tag, 8)?,
+        read_s15f16(tag, 12)?,
+        read_s15f16(tag, 16)?,
+    ])
+}
+
+fn parse_curve(tag: &[u8]) -> Result<ToneCurve, String> {
+    match tag.get(0..4) {
+        Some(b"curv") => {
+            let count = read_u32(tag, 8)? as usize;
+            let entry = |i: usize| -> Result<u16, String> {
+                let bytes = tag.get(12 + i * 2..14 + i * 2).ok_or("Truncated curve")?;
+                Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+            };
+            match count {
+                0 => Ok(ToneCurve::Gamma(1.0)),
+                1 => Ok(ToneCurve::Gamma(entry(0)? as f64 / 256.0)),
+                _ => (0..count)
+                    .map(|i| entry(i).map(|v| v as f64 / 65535.0))
+                    .collect::<Result<_, _>>()
+                    .map(ToneCurve::Table),
+            }
+        }
+        Some(b"para") => {
+            let bytes = tag.get(8..10).ok_or("Truncated curve")?;
+            let kind = u16::from_be_bytes([bytes[0], bytes[1]]);
+            let count = match kind {
+                0 => 1,
+                1 => 3,
+                2 => 4,
+                3 => 5,
+                4 => 7,
+                _ => return Err(format!("Unknown parametric curve type {}", kind)),
+            };
+            let params = (0..count)
+                .map(|i| read_s15f16(tag, 12 + i * 4))
+                .collect::<Result<_, _>>()?;
+            Ok(ToneCurve::Parametric(kind, params))
+        }
+        _ => Err("Unsupported tone curve type".to_string()),
+    }
+}
+
+fn multiply(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn invert(m: &[[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+
+    let cofactor =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    Some([
+        [
+            cofactor(1, 2, 1, 2) / det,
+            -cofactor(0, 2, 1, 2) / det,
+            cofactor(0, 1, 1, 2) / det,
+        ],
+        [
+            -cofactor(1, 2, 0, 2) / det,
+            cofactor(0, 2, 0, 2) / det,
+            -cofactor(0, 1, 0, 2) / det,
+        ],
+        [
+            cofactor(1, 2, 0, 1) / det,
+            -cofactor(0, 2, 0, 1) / det,
+            cofactor(0, 1, 0, 1) / det,
+        ],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal matrix/TRC profile with the given colorants and gamma
+    fn build_profile(colorants: [[f64; 3]; 3], gamma: f64) -> Vec<u8> {
+        let fixed = |v: f64| ((v * 65536.0).round() as i32).to_be_bytes();
+        let mut tags: Vec<(&[u8; 4], Vec<u8>)> = Vec::new();
+        for (name, xyz) in [b"rXYZ", b"gXYZ", b"bXYZ"].into_iter().zip(colorants) {
+            let mut tag = b"XYZ \0\0\0\0".to_vec();
+            xyz.iter().for_each(|v| tag.extend(fixed(*v)));
+            tags.push((name, tag));
+        }
+        for name in [b"rTRC", b"gTRC", b"bTRC"] {
+            let mut tag = b"curv\0\0\0\0".to_vec();
+            tag.extend(1u32.to_be_bytes());
+            tag.extend(((gamma * 256.0) as u16).to_be_bytes());
+            tag.extend([0, 0]);
+            tags.push((name, tag));
+        }
+
+        let mut data = vec![0u8; 128];
+        data[36..40].copy_from_slice(b"acsp");
+        data.extend((tags.len() as u32).to_be_bytes());
+        let mut offset = 132 + tags.len() * 12;
+        for (name, tag) in &tags {
+            data.extend(name.as_slice());
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((tag.len() as u32).to_be_bytes());
+            offset += tag.len();
+        }
+        for (_, tag) in &tags {
+            data.extend(tag);
+        }
+        data
+    }
+
+    fn srgb_colorants() -> [[f64; 3]; 3] {
+        std::array::from_fn(|c| std::array::from_fn(|r| SRGB_TO_XYZ_D50[r][c]))
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let profile = IccProfile::parse(&build_profile(srgb_colorants(), 2.2)).unwrap();
+        assert_eq!(profile.curves[1], ToneCurve::Gamma(2.19921875));
+        assert!((profile.colorants[0][0] - 0.4360747).abs() < 1e-4);
+
+        assert!(IccProfile::parse(b"not a profile").is_err());
+    }
+
+    #[test]
+    fn test_srgb_profile_is_near_identity() {
+        let profile = IccProfile::parse(&build_profile(srgb_colorants(), 2.2)).unwrap();
+        let transform = ColorTransform::from_profile(&profile).unwrap();
+
+        for (i, row) in transform.matrix.iter().enumerate() {
+            for (j, v) in row.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((v - expected).abs() < 1e-3, "matrix[{}][{}] = {}", i, j, v);
+            }
+        }
+
+        // Mid grey stays roughly mid grey
+        let grey = transform.apply([0.5, 0.5, 0.5, 1.0]);
+        assert!((grey[0] - 0.5).abs() < 0.03, "{:?}", grey);
+        assert_eq!(grey[3], 1.0);
+    }
+
+    #[test]
+    fn test_hdr_transform() {
+        // 10000 nits is the top of the PQ range, the reference white of
+        // 203 nits sits at about 58% of the signal
+        assert!((pq_encode(10000.0) - 1.0).abs() < 1e-9);
+        assert!((pq_encode(203.0) - 0.58).abs() < 0.01);
+
+        let transform = ColorTransform::hdr(203.0);
+        let white = transform.apply([1.0, 1.0, 1.0, 1.0]);
+        assert!(white.iter().take(3).all(|v| (v - 0.58).abs() < 0.01));
+        let black = transform.apply([0.0, 0.0, 0.0, 0.5]);
+        assert!(black.iter().take(3).all(|v| *v < 1e-5));
+        assert_eq!(black[3], 0.5);
+    }
+
+    #[test]
+    fn test_tone_curves() {
+        let table = ToneCurve::Table(vec![0.0, 0.25, 1.0]);
+        assert!((table.eval(0.25) - 0.125).abs() < 1e-9);
+        assert!((table.eval(1.0) - 1.0).abs() < 1e-9);
+
+        // sRGB as an ICC type 3 parametric curve fits to about 2.2
+        let srgb = ToneCurve::Parametric(
+            3,
+            vec![2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045],
+        );
+        assert!((srgb.fitted_gamma() - 2.2).abs() < 0.1);
+    }
+}
@@ -0,0 +1,372 @@
+// Configuration hot reload
+//
+// The config directory is watched with inotify, and so is every directory
+// holding a file it includes, such as parts/ for `<include
+// path="parts/bars.xml"/>`. Saving desktop.xml, style.css, config.js,
+// config.json or an XML or JS file in any of them reloads the configuration
+// without a restart:
+// `Config` is rebuilt, borders and bars are restyled, keybindings come from
+// the new config.js, workspaces pick up their new names and gaps, and
+// everything is laid out again. Editors write a file in several steps
+// (or save a copy and rename it over the old one), so a reload waits for
+// the directory to be quiet for RELOAD_DELAY. The reload action and
+// `wm.reload()` go through the same path, and each reload watches the
+// directories of the files it read, so includes added later are followed.
+//
+// A file that fails to parse leaves the running configuration as it was,
+// never a mix of old and new. A notice in the top right corner says which
+// file and line broke and why, until a reload works or it's clicked away;
+// in safe mode, the banner shows the new error instead. Colors of the
+// notice come from the stylesheet's `.config-error` rule. Workspaces removed
+// from desktop.xml stay until restart, as they may still hold windows.
+
+use inotify::{Inotify, WatchDescriptor, WatchMask, Watches};
+use serde_json::json;
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use smithay::reexports::calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction};
+use smithay::utils::{Logical, Point, Rectangle};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::compositor::bar::{BarElement, BarRenderer};
+use crate::compositor::cursor_theme::CursorTheme;
+use crate::compositor::ui::{rect, Canvas, Style};
+use crate::compositor::workspace::Workspace;
+use crate::compositor::{text, WebWMCompositor};
+use crate::config::{self, Config, JSRuntime};
+
+/// Files a change to reloads the configuration
+pub const CONFIG_FILES: [&str; 4] = ["desktop.xml", "style.css", "config.js", "config.json"];
+
+/// Whether a change to the file `name` reloads the configuration: one of
+/// CONFIG_FILES, or XML or JS that may be included from them
+fn is_config_file(name: &str) -> bool {
+    CONFIG_FILES.contains(&name) || name.ends_with(".xml") || name.ends_with(".js")
+}
+
+/// Quiet time after the last change before reloading
+const RELOAD_DELAY: Duration = Duration::from_millis(200);
+
+const NOTICE_TITLE: &str = "Config reload failed, keeping the previous one";
+const NOTICE_HINT: &str = "Fix it and save to try again, or click to dismiss";
+const NOTICE_MARGIN: i32 = 12;
+const PADDING: i32 = 12;
+const BORDER_WIDTH: i32 = 1;
+
+const DEFAULT_STYLE: Style = Style::new(
+    [0.11, 0.11, 0.18, 0.97],
+    [0.8, 0.83, 0.96, 1.0],
+    [0.95, 0.55, 0.66, 1.0], // #f38ba8
+);
+
+/// Lines of the notice for `error`, with their text sizes
+fn notice_lines(error: &str) -> [(String, u32); 3] {
+    // Long errors, like XML ones, go on over several lines
+    let first_line = error.lines().next().unwrap_or_default();
+    [
+        (NOTICE_TITLE.to_string(), 13),
+        (first_line.to_string(), 12),
+        (NOTICE_HINT.to_string(), 11),
+    ]
+}
+
+/// The directories the configuration's files are in, watched for changes
+pub struct ConfigWatch {
+    watches: Watches,
+    config_dir: PathBuf,
+    dirs: HashMap<PathBuf, WatchDescriptor>,
+}
+
+impl ConfigWatch {
+    fn new(watches: Watches, config_dir: &Path) -> Self {
+        Self {
+            watches,
+            config_dir: config_dir.to_path_buf(),
+            dirs: HashMap::new(),
+        }
+    }
+
+    /// Watch the config directory and those `files` are in, and stop
+    /// watching the others
+    pub fn update<'a>(&mut self, files: impl IntoIterator<Item = &'a Path>) {
+        let dirs: Vec<PathBuf> = std::iter::once(self.config_dir.clone())
+            .chain(
+                files
+                    .into_iter()
+                    .filter_map(|file| file.parent().map(Path::to_path_buf)),
+            )
+            .map(|dir| dir.canonicalize().unwrap_or(dir))
+            .collect();
+
+        let stale: Vec<PathBuf> = self
+            .dirs
+            .keys()
+            .filter(|dir| !dirs.contains(dir))
+            .cloned()
+            .collect();
+        for dir in stale {
+            if let Some(watch) = self.dirs.remove(&dir) {
+                // Fails for directories that are gone, which is fine
+                let _ = self.watches.remove(watch);
+            }
+        }
+
+        for dir in dirs {
+            if self.dirs.contains_key(&dir) {
+                continue;
+            }
+            let mask = WatchMask::CLOSE_WRITE
+                | WatchMask::MOVED_TO
+                | WatchMask::CREATE
+                | WatchMask::DELETE;
+            match self.watches.add(&dir, mask) {
+                Ok(watch) => {
+                    self.dirs.insert(dir, watch);
+                }
+                Err(e) => eprintln!("⚠️  Can't watch {}: {}", dir.display(), e),
+            }
+        }
+    }
+}
+
+/// Watch `config_dir` and reload the configuration when a file in it or
+/// in the directories of `files` changes
+pub fn init_config_watcher(
+    loop_handle: &LoopHandle<'static, WebWMCompositor>,
+    config_dir: &Path,
+    files: &[PathBuf],
+) -> Result<ConfigWatch, Box<dyn std::error::Error>> {
+    let inotify = Inotify::init()?;
+    let mut watch = ConfigWatch::new(inotify.watches(), config_dir);
+    watch.update(files.iter().map(PathBuf::as_path));
+    if watch.dirs.is_empty() {
+        return Err(format!("can't watch {}", config_dir.display()).into());
+    }
+
+    let mut buffer = [0; 4096];
+    loop_handle.insert_source(
+        Generic::new(inotify, Interest::READ, Mode::Level),
+        move |_, inotify, compositor| {
+            // Safety: the inotify instance is never dropped while the source
+            // is alive
+            let inotify = unsafe { inotify.get_mut() };
+            let mut changed = false;
+            loop {
+                match inotify.read_events(&mut buffer) {
+                    Ok(events) => {
+                        changed |= events
+                            .filter_map(|event| event.name)
+                            .any(|name| is_config_file(&name.to_string_lossy()));
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        eprintln!("Config watch error: {}", e);
+                        break;
+                    }
+                }
+            }
+            if changed {
+                compositor.schedule_config_reload();
+            }
+            Ok(PostAction::Continue)
+        },
+    )?;
+    Ok(watch)
+}
+
+impl WebWMCompositor {
+    /// Reload the configuration once the config directory has been quiet
+    /// for a moment
+    pub fn schedule_config_reload(&mut self) {
+        if self.config_reload_pending {
+            return;
+        }
+        let scheduled = self.loop_handle.insert_source(
+            Timer::from_duration(RELOAD_DELAY),
+            |_, _, compositor| {
+                compositor.config_reload_pending = false;
+                if let Err(e) = compositor.reload_config() {
+                    eprintln!("⚠️  Config reload failed, keeping the current one: {}", e);
+                }
+                TimeoutAction::Drop
+            },
+        );
+        match scheduled {
+            Ok(_) => self.config_reload_pending = true,
+            Err(e) => eprintln!("Failed to schedule a config reload: {}", e),
+        }
+    }
+
+    /// Load the configuration again from the directory it came from
+    pub fn reload_config(&mut self) -> Result<(), String> {
+        let dir = self
+            .config_dir
+            .clone()
+            .ok_or("No configuration directory to reload from")?;
+        let (config, js_runtime) = match config::load_config(&dir.to_string_lossy()) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                // Fixing the broken file reloads, wherever it is
+                if let Some(ref mut watch) = self.config_watch {
+                    let files = self.config.files.iter().map(PathBuf::as_path);
+                    watch.update(files.chain([e.file.as_path()]));
+                }
+                let message = e.to_string();
+                if self.safe_mode.is_some() {
+                    self.safe_mode = Some(e);
+                } else {
+                    self.reload_error = Some(e);
+                }
+                return Err(message);
+            }
+        };
+        if let Some(ref mut watch) = self.config_watch {
+            watch.update(config.files.iter().map(PathBuf::as_path));
+        }
+        self.apply_config(config, js_runtime);
+        println!("🔄 Reloaded configuration from {}", dir.display());
+        Ok(())
+    }
+
+    /// Switch to `config` and its JS runtime, restyling and laying out
+    /// everything again
+    pub fn apply_config(&mut self, config: Config, mut js_runtime: Option<JSRuntime>) {
+        if let Some(ref mut js_runtime) = js_runtime {
+            js_runtime.set_watchdog(self.watchdog.clone());
+        }
+
+        self.stylesheet = config.stylesheet.clone();
+        self.cursor_theme = CursorTheme::load(
+            &config
+                .desktop
+                .as_ref()
+                .map(|desktop| desktop.cursor.clone())
+                .unwrap_or_default(),
+        );
+        self.frame_pacing.warning_threshold =
+            Duration::from_millis(config.performance.frame_warning_threshold_ms as u64);
+        self.bar_renderer = config
+            .desktop
+            .as_ref()
+            .filter(|desktop| !desktop.bars.is_empty())
+            .map(|desktop| BarRenderer::new(desktop.bars.clone(), self.layout_size().w));
+
+        // Workspaces keep their windows and layout, and take the rest anew
+        for ws_config in config.desktop.iter().flat_map(|d| &d.workspaces) {
+            match self.workspace_manager.get_workspace_mut(ws_config.id) {
+                Some(workspace) => {
+                    workspace.name = ws_config.name.clone();
+                    workspace.output = ws_config.output.clone();
+                    workspace.gaps_inner = ws_config.gaps_inner;
+                    workspace.gaps_outer = ws_config.gaps_outer;
+                }
+                None => self
+                    .workspace_manager
+                    .add_workspace(Workspace::from_config(ws_config)),
+            }
+        }
+
+        let leaving_safe_mode = self.safe_mode.take().is_some();
+        self.reload_error = None;
+        self.config = config;
+        self.js_runtime = js_runtime;
+        self.relayout();
+        self.emit_event("config-changed", json!({ "section": "all" }));
+        // The startup the config's onStartup handlers missed
+        if leaving_safe_mode {
+            println!("✓ Configuration fixed, leaving safe mode");
+            self.emit_event("startup", json!({}));
+        }
+    }
+
+    /// Where the notice about a failed reload is drawn, if there is one
+    fn reload_error_geometry(&self) -> Option<Rectangle<i32, Logical>> {
+        let error = self.reload_error.as_ref()?.to_string();
+        let lines = notice_lines(&error);
+        let width = lines
+            .iter()
+            .map(|(line, size)| text::measure(line, *size))
+            .max()
+            .unwrap_or_default()
+            + PADDING * 2;
+        let height = lines.iter().map(|(_, size)| *size as i32 + 4).sum::<i32>() + PADDING * 2;
+        let width = width.min(self.layout_size().w - NOTICE_MARGIN * 2);
+        Some(rect(
+            self.layout_size().w - width - NOTICE_MARGIN,
+            self.bar_height() + NOTICE_MARGIN,
+            width,
+            height,
+        ))
+    }
+
+    pub fn render_reload_error(&self) -> Vec<BarElement> {
+        let (Some(error), Some(geometry)) = (&self.reload_error, self.reload_error_geometry())
+        else {
+            return Vec::new();
+        };
+        let style = Style::load(self.stylesheet.as_ref(), ".config-error", DEFAULT_STYLE);
+
+        let mut canvas = Canvas::new();
+        canvas.panel(geometry, &style, BORDER_WIDTH);
+        let mut y = geometry.loc.y + PADDING;
+        for (i, (line, size)) in notice_lines(&error.to_string()).into_iter().enumerate() {
+            // The error itself stands out
+            let color = if i == 1 {
+                style.border_color
+            } else {
+                style.color
+            };
+            canvas.text((geometry.loc.x + PADDING, y).into(), line, color, size);
+            y += size as i32 + 4;
+        }
+        canvas.finish()
+    }
+
+    /// Clicking the notice dismisses it
+    pub fn reload_error_click(&mut self, location: Point<f64, Logical>) -> bool {
+        let clicked = self
+            .reload_error_geometry()
+            .is_some_and(|geometry| geometry.contains(location.to_i32_round()));
+        if clicked {
+            self.reload_error = None;
+        }
+        clicked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_config_watch_follows_includes() {
+        let dir = std::env::temp_dir().join(format!("webwm-watch-{}", std::process::id()));
+        fs::create_dir_all(dir.join("parts")).unwrap();
+        let included = dir.join("parts/bars.xml");
+
+        let mut inotify = Inotify::init().unwrap();
+        let mut watch = ConfigWatch::new(inotify.watches(), &dir);
+        watch.update([dir.join("desktop.xml").as_path(), included.as_path()]);
+        assert_eq!(watch.dirs.len(), 2);
+
+        // Saving an included file in a subdirectory is seen
+        fs::write(&included, "<desktop />").unwrap();
+        let mut buffer = [0; 4096];
+        let names: Vec<String> = inotify
+            .read_events(&mut buffer)
+            .unwrap()
+            .filter_map(|event| event.name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        assert!(names.iter().any(|name| name == "bars.xml"));
+
+        // Once nothing includes it, its directory isn't watched anymore
+        watch.update([dir.join("desktop.xml").as_path()]);
+        assert_eq!(watch.dirs.len(), 1);
+        assert!(watch.dirs.contains_key(&dir.canonicalize().unwrap()));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,108 @@
+// Content types
+//
+// Clients say what a surface shows through wp_content_type_v1: "photo",
+// "video" or "game". Window rules can match on it with `content-type`,
+// alone or together with `app-id`, and two rule settings exist mostly for
+// such windows:
+//
+//   <rule content-type="game" animations="false" direct-scanout="true" />
+//
+// `animations="false"` keeps the window still, without the urgency pulse.
+// `direct-scanout="true"` lets the window's buffer go straight to the
+// primary plane while it is fullscreen on its output, skipping composition,
+// as long as the bar and cursor have planes of their own.
+
+use smithay::delegate_content_type;
+use smithay::desktop::Window;
+use smithay::reexports::wayland_protocols::wp::content_type::v1::server::wp_content_type_v1::Type as ContentType;
+use smithay::wayland::compositor::with_states;
+use smithay::wayland::content_type::ContentTypeSurfaceCachedState;
+
+use crate::compositor::{window_app_id, WebWMCompositor};
+use crate::config::WindowRule;
+
+/// Name of a content type as written in rules, None for "none"
+pub fn content_type_name(content_type: ContentType) -> Option<&'static str> {
+    match content_type {
+        ContentType::Photo => Some("photo"),
+        ContentType::Video => Some("video"),
+        ContentType::Game => Some("game"),
+        _ => None,
+    }
+}
+
+/// Content type the window's client declared, if any
+pub fn window_content_type(window: &Window) -> Option<&'static str> {
+    let toplevel = window.toplevel()?;
+    let content_type = with_states(toplevel.wl_surface(), |states| {
+        *states
+            .cached_state
+            .get::<ContentTypeSurfaceCachedState>()
+            .current()
+            .content_type()
+    });
+    content_type_name(content_type)
+}
+
+impl WebWMCompositor {
+    /// Window rules that apply to `window`, in config order
+    pub fn window_rules_for<'a>(&'a self, window: &Window) -> impl Iterator<Item = &'a WindowRule> {
+        let app_id = window_app_id(window);
+        let content_type = window_content_type(window);
+        self.config
+            .window_rules
+            .iter()
+            .filter(move |rule| rule.matches(&app_id, content_type))
+    }
+
+    /// The active workspace's fullscreen window, if its rules let it be
+    /// scanned out directly
+    pub fn direct_scanout_window(&self) -> Option<Window> {
+        let window = self
+            .workspace_manager
+            .active_workspace()
+            .fullscreen_window
+            .clone()?;
+        self.window_rules_for(&window)
+            .find_map(|rule| rule.direct_scanout)
+            .unwrap_or(false)
+            .then_some(window)
+    }
+}
+
+delegate_content_type!(WebWMCompositor);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_matching() {
+        let game = WindowRule {
+            content_type: Some("game".to_string()),
+            animations: Some(false),
+            ..Default::default()
+        };
+        assert!(game.matches("steam_app_42", Some("game")));
+        assert!(!game.matches("steam_app_42", Some("video")));
+        assert!(!game.matches("steam_app_42", None));
+
+        let mpv_video = WindowRule {
+            app_id: "mpv".to_string(),
+            content_type: Some("video".to_string()),
+            ..Default::default()
+        };
+        assert!(mpv_video.matches("mpv", Some("video")));
+        assert!(!mpv_video.matches("firefox", Some("video")));
+
+        let mpv = WindowRule {
+            app_id: "mpv".to_string(),
+            ..Default::default()
+        };
+        assert!(mpv.matches("mpv", None));
+        assert!(mpv.matches("mpv", Some("game")));
+
+        assert_eq!(content_type_name(ContentType::Video), Some("video"));
+        assert_eq!(content_type_name(ContentType::None), None);
+    }
+}
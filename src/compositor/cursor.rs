@@ -0,0 +1,108 @@
+// Cursor hiding
+//
+// With `<cursor hide="true"/>` the cursor disappears once the pointer has
+// been still for `hide-after`, and, with `hide-while-typing`, as soon as a
+// key goes to a client. Any pointer motion brings it back. Only drawing is
+// affected: pointer focus and the position clients see stay as they are.
+
+use std::time::{Duration, Instant};
+
+use crate::compositor::WebWMCompositor;
+use crate::config::CursorSettings;
+
+#[derive(Debug, Default)]
+pub struct CursorVisibility {
+    last_motion: Option<Instant>,
+    /// A key was typed since the pointer last moved
+    typing: bool,
+}
+
+impl CursorVisibility {
+    pub fn pointer_moved(&mut self, now: Instant) {
+        self.last_motion = Some(now);
+        self.typing = false;
+    }
+
+    pub fn key_typed(&mut self) {
+        self.typing = true;
+    }
+
+    pub fn visible(&self, settings: &CursorSettings, now: Instant) -> bool {
+        if !settings.hide {
+            return true;
+        }
+        if settings.hide_while_typing && self.typing {
+            return false;
+        }
+
+        let timeout = Duration::from_millis(settings.hide_after_ms as u64);
+        timeout.is_zero()
+            || self
+                .last_motion
+                .is_none_or(|at| now.duration_since(at) < timeout)
+    }
+}
+
+impl WebWMCompositor {
+    fn cursor_settings(&self) -> CursorSettings {
+        self.config
+            .desktop
+            .as_ref()
+            .map(|desktop| desktop.cursor.clone())
+            .unwrap_or_default()
+    }
+
+    /// Whether the cursor should be drawn this frame
+    pub fn cursor_visible(&self, now: Instant) -> bool {
+        self.cursor_visibility.visible(&self.cursor_settings(), now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(hide_after_ms: u32, hide_while_typing: bool) -> CursorSettings {
+        CursorSettings {
+            hide: true,
+            hide_after_ms,
+            hide_while_typing,
+            ..CursorSettings::default()
+        }
+    }
+
+    #[test]
+    fn test_hide_after_inactivity() {
+        let start = Instant::now();
+        let mut cursor = CursorVisibility::default();
+        cursor.pointer_moved(start);
+
+        let hide = settings(3000, false);
+        assert!(cursor.visible(&hide, start + Duration::from_millis(2999)));
+        assert!(!cursor.visible(&hide, start + Duration::from_secs(3)));
+
+        // Typing doesn't matter unless asked for, 0 never hides
+        cursor.key_typed();
+        assert!(cursor.visible(&hide, start));
+        assert!(cursor.visible(&settings(0, false), start + Duration::from_secs(60)));
+
+        let disabled = CursorSettings {
+            hide: false,
+            ..settings(1, true)
+        };
+        assert!(cursor.visible(&disabled, start + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_hide_while_typing() {
+        let start = Instant::now();
+        let mut cursor = CursorVisibility::default();
+        let hide = settings(0, true);
+
+        cursor.key_typed();
+        assert!(!cursor.visible(&hide, start));
+
+        cursor.pointer_moved(start);
+        assert!(cursor.visible(&hide, start));
+    }
+}
@@ -0,0 +1,272 @@
+// Themed cursors
+//
+// Named cursors, set by clients through wp_cursor_shape_v1 or by the
+// compositor itself (the crosshair of region selection), are drawn from an
+// xcursor theme: `<cursor theme="Adwaita" size="24"/>`, else XCURSOR_THEME
+// and XCURSOR_SIZE, else the "default" theme at 24 pixels. A shape is
+// loaded the first time it is shown and kept. Shapes missing from the
+// theme fall back to their alternative names, then to the default arrow,
+// then to a built-in arrow, so there is always something to draw. Animated
+// cursors cycle through their frames. Cursor surfaces set with
+// wl_pointer.set_cursor are drawn as they are, at their hotspot.
+
+use smithay::backend::input::TabletToolDescriptor;
+use smithay::delegate_cursor_shape;
+use smithay::input::pointer::{CursorIcon, CursorImageStatus, CursorImageSurfaceData};
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::utils::{Logical, Point};
+use smithay::wayland::compositor::with_states;
+use smithay::wayland::tablet_manager::TabletSeatHandler;
+use std::collections::HashMap;
+use std::fs;
+use std::time::Instant;
+
+use crate::compositor::WebWMCompositor;
+use crate::config::CursorSettings;
+
+const DEFAULT_SIZE: u32 = 24;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorFrame {
+    pub width: u32,
+    pub height: u32,
+    pub hotspot: (i32, i32),
+    /// How long the frame shows in an animated cursor
+    pub delay_ms: u32,
+    /// Premultiplied ARGB8888, like the bar's textures
+    pub pixels: Vec<u8>,
+}
+
+/// What to draw at the pointer
+#[derive(Debug, Clone)]
+pub enum CursorSprite {
+    Themed(CursorFrame),
+    /// A client's cursor surface and its hotspot
+    Surface(WlSurface, Point<i32, Logical>),
+}
+
+pub struct CursorTheme {
+    theme: xcursor::CursorTheme,
+    pub name: String,
+    pub size: u32,
+    /// Frames of every shape shown so far
+    cursors: HashMap<CursorIcon, Vec<CursorFrame>>,
+    started: Instant,
+}
+
+impl CursorTheme {
+    pub fn load(settings: &CursorSettings) -> Self {
+        let name = settings
+            .theme
+            .clone()
+            .or_else(|| std::env::var("XCURSOR_THEME").ok())
+            .unwrap_or_else(|| "default".to_string());
+        let size = settings
+            .size
+            .or_else(|| std::env::var("XCURSOR_SIZE").ok()?.parse().ok())
+            .unwrap_or(DEFAULT_SIZE);
+
+        println!("🖱️  Cursor theme '{}' at {}px", name, size);
+        Self {
+            theme: xcursor::CursorTheme::load(&name),
+            name,
+            size,
+            cursors: HashMap::new(),
+            started: Instant::now(),
+        }
+    }
+
+    /// The frames of `icon`, loading it on first use
+    pub fn frames(&mut self, icon: CursorIcon) -> &[CursorFrame] {
+        if !self.cursors.contains_key(&icon) {
+            let frames = std::iter::once(icon.name())
+                .chain(icon.alt_names().iter().copied())
+                .chain(std::iter::once(CursorIcon::Default.name()))
+                .find_map(|name| self.load_frames(name))
+                .unwrap_or_else(|| {
+                    eprintln!(
+                        "⚠️  Cursor '{}' not in theme '{}', using the built-in arrow",
+                        icon.name(),
+                        self.name
+                    );
+                    vec![fallback_arrow(self.size)]
+                });
+            self.cursors.insert(icon, frames);
+        }
+        &self.cursors[&icon]
+    }
+
+    /// The frame of `icon` to show at `now`
+    pub fn frame(&mut self, icon: CursorIcon, now: Instant) -> CursorFrame {
+        let elapsed = now.duration_since(self.started).as_millis() as u64;
+        frame_at(self.frames(icon), elapsed).clone()
+    }
+
+    fn load_frames(&self, name: &str) -> Option<Vec<CursorFrame>> {
+        let path = self.theme.load_icon(name)?;
+        let images = xcursor::parser::parse_xcursor(&fs::read(path).ok()?)?;
+
+        let sizes: Vec<u32> = images.iter().map(|image| image.size).collect();
+        let size = nearest_size(&sizes, self.size)?;
+        Some(
+            images
+                .into_iter()
+                .filter(|image| image.size == size)
+                .map(|image| CursorFrame {
+                    width: image.width,
+                    height: image.height,
+                    hotspot: (image.xhot as i32, image.yhot as i32),
+                    delay_ms: image.delay,
+                    pixels: rgba_to_argb(&image.pixels_rgba),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// The size in `sizes` closest to `wanted`
+pub fn nearest_size(sizes: &[u32], wanted: u32) -> Option<u32> {
+    sizes
+        .iter()
+        .copied()
+        .min_by_key(|size| size.abs_diff(wanted))
+}
+
+/// The frame of an animation showing `elapsed_ms` after it started
+pub fn frame_at(frames: &[CursorFrame], elapsed_ms: u64) -> &CursorFrame {
+    let total: u64 = frames.iter().map(|frame| frame.delay_ms as u64).sum();
+    if total == 0 {
+        return &frames[0];
+    }
+
+    let mut t = elapsed_ms % total;
+    for frame in frames {
+        if t < frame.delay_ms as u64 {
+            return frame;
+        }
+        t -= frame.delay_ms as u64;
+    }
+    &frames[0]
+}
+
+/// RGBA bytes to ARGB8888, which is stored as BGRA
+fn rgba_to_argb(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4)
+        .flat_map(|px| [px[2], px[1], px[0], px[3]])
+        .collect()
+}
+
+/// A white arrow with a black outline, for when no theme is installed
+pub fn fallback_arrow(size: u32) -> CursorFrame {
+    let size = size.max(8) as i32;
+    let inside = |x: i32, y: i32| x >= 0 && y >= 0 && x * 2 <= y && x + y < size;
+
+    let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let edge = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                .iter()
+                .any(|(dx, dy)| !inside(x + dx, y + dy));
+            let pixel: [u8; 4] = match (inside(x, y), edge) {
+                (false, _) => [0, 0, 0, 0],
+                (true, true) => [0, 0, 0, 255],
+                (true, false) => [255, 255, 255, 255],
+            };
+            pixels.extend_from_slice(&pixel);
+        }
+    }
+
+    CursorFrame {
+        width: size as u32,
+        height: size as u32,
+        hotspot: (0, 0),
+        delay_ms: 0,
+        pixels,
+    }
+}
+
+impl WebWMCompositor {
+    /// What to draw at the pointer this frame, None for nothing
+    pub fn cursor_sprite(&mut self, now: Instant) -> Option<CursorSprite> {
+        if !self.cursor_visible(now) {
+            return None;
+        }
+
+        match self.cursor_image_status {
+            CursorImageStatus::Hidden => None,
+            CursorImageStatus::Named(icon) => {
+                Some(CursorSprite::Themed(self.cursor_theme.frame(icon, now)))
+            }
+            CursorImageStatus::Surface(ref surface) => {
+                let hotspot = with_states(surface, |states| {
+                    states
+                        .data_map
+                        .get::<CursorImageSurfaceData>()
+                        .map(|data| data.lock().unwrap().hotspot)
+                        .unwrap_or_default()
+                });
+                Some(CursorSprite::Surface(surface.clone(), hotspot))
+            }
+        }
+    }
+}
+
+// Tablet tools set their cursor the same way as the pointer
+impl TabletSeatHandler for WebWMCompositor {
+    fn tablet_tool_image(&mut self, _tool: &TabletToolDescriptor, image: CursorImageStatus) {
+        self.cursor_image_status = image;
+    }
+}
+
+delegate_cursor_shape!(WebWMCompositor);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(delay_ms: u32) -> CursorFrame {
+        CursorFrame {
+            width: 1,
+            height: 1,
+            hotspot: (0, 0),
+            delay_ms,
+            pixels: vec![delay_ms as u8; 4],
+        }
+    }
+
+    #[test]
+    fn test_frame_at() {
+        let still = vec![frame(0)];
+        assert_eq!(frame_at(&still, 12345), &still[0]);
+
+        // A spinner: 50ms, 100ms, 50ms, then around again
+        let spinner = vec![frame(50), frame(100), frame(50)];
+        assert_eq!(frame_at(&spinner, 0), &spinner[0]);
+        assert_eq!(frame_at(&spinner, 49), &spinner[0]);
+        assert_eq!(frame_at(&spinner, 50), &spinner[1]);
+        assert_eq!(frame_at(&spinner, 160), &spinner[2]);
+        assert_eq!(frame_at(&spinner, 200), &spinner[0]);
+    }
+
+    #[test]
+    fn test_nearest_size() {
+        assert_eq!(nearest_size(&[24, 32, 48, 24], 30), Some(32));
+        assert_eq!(nearest_size(&[24, 48], 64), Some(48));
+        assert_eq!(nearest_size(&[], 24), None);
+    }
+
+    #[test]
+    fn test_fallback_arrow() {
+        let arrow = fallback_arrow(24);
+        assert_eq!(arrow.pixels.len(), 24 * 24 * 4);
+
+        // The tip is the hotspot; the far corner is transparent
+        let pixel = |x: usize, y: usize| &arrow.pixels[(y * 24 + x) * 4..][..4];
+        assert_eq!(arrow.hotspot, (0, 0));
+        assert_eq!(pixel(0, 0), [0, 0, 0, 255]);
+        assert_eq!(pixel(2, 10), [255, 255, 255, 255]);
+        assert_eq!(pixel(23, 0), [0, 0, 0, 0]);
+
+        assert_eq!(rgba_to_argb(&[1, 2, 3, 4]), vec![3, 2, 1, 4]);
+    }
+}
@@ -0,0 +1,75 @@
+// Clipboard and drag-and-drop
+//
+// wl_data_device support. The selection is only offered to the client with
+// keyboard focus and follows focus from client to client, so a background
+// client can't read what was copied. Drags started by clients run as a
+// pointer grab inside Smithay; while one is in progress its icon surface is
+// drawn at the pointer.
+
+use smithay::delegate_data_device;
+use smithay::input::Seat;
+use smithay::reexports::wayland_server::{
+    protocol::{wl_data_source::WlDataSource, wl_surface::WlSurface},
+    Resource,
+};
+use smithay::utils::{Logical, Point};
+use smithay::wayland::selection::{
+    data_device::{
+        set_data_device_focus, ClientDndGrabHandler, DataDeviceHandler, DataDeviceState,
+        ServerDndGrabHandler,
+    },
+    SelectionHandler,
+};
+
+use crate::compositor::WebWMCompositor;
+
+impl WebWMCompositor {
+    /// Offer the selection to the client owning `focused`, and to no one
+    /// when focus is cleared
+    pub fn update_selection_focus(&mut self, seat: &Seat<Self>, focused: Option<&WlSurface>) {
+        let client = focused.and_then(|surface| self.display_handle.get_client(surface.id()).ok());
+        set_data_device_focus(&self.display_handle, seat, client);
+    }
+
+    /// Icon of the drag in progress and where to draw it
+    pub fn drag_icon(&self) -> Option<(&WlSurface, Point<i32, Logical>)> {
+        self.dnd_icon
+            .as_ref()
+            .map(|icon| (icon, self.pointer_location().to_i32_round()))
+    }
+}
+
+impl SelectionHandler for WebWMCompositor {
+    type SelectionUserData = ();
+}
+
+impl DataDeviceHandler for WebWMCompositor {
+    fn data_device_state(&self) -> &DataDeviceState {
+        &self.data_device_state
+    }
+}
+
+impl ClientDndGrabHandler for WebWMCompositor {
+    fn started(
+        &mut self,
+        _source: Option<WlDataSource>,
+        icon: Option<WlSurface>,
+        _seat: Seat<Self>,
+    ) {
+        println!("🖐️  Drag started");
+        self.dnd_icon = icon;
+    }
+
+    fn dropped(&mut self, target: Option<WlSurface>, validated: bool, _seat: Seat<Self>) {
+        if target.is_some() && validated {
+            println!("🖐️  Dropped");
+        } else {
+            println!("🖐️  Drag cancelled");
+        }
+        self.dnd_icon = None;
+    }
+}
+
+impl ServerDndGrabHandler for WebWMCompositor {}
+
+delegate_data_device!(WebWMCompositor);
@@ -0,0 +1,267 @@
+// Dialogs
+//
+// A toplevel with a parent, from xdg_toplevel.set_parent or an imported
+// xdg-foreign handle, is a dialog of that window. Dialogs always live on
+// their parent's workspace and follow it when it moves. They don't get a
+// tile of their own: in every layout they are centered over their parent,
+// kept on the output, and whenever a parent is raised its dialogs are
+// raised with it so they never end up hidden behind it.
+//
+// Dialogs marked modal through xdg_wm_dialog_v1 also dim their parent with
+// the stylesheet's `.modal-parent` background color and opacity. Clicks on
+// the parent don't reach it while the dialog is open; they raise and focus
+// the dialog instead.
+
+use smithay::delegate_xdg_dialog;
+use smithay::desktop::Window;
+use smithay::utils::{Logical, Point, Rectangle, Size};
+use smithay::wayland::shell::xdg::dialog::XdgDialogHandler;
+use smithay::wayland::shell::xdg::ToplevelSurface;
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::workspace::WindowKind;
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+/// Dim over the parent of a modal dialog, without a `.modal-parent` rule
+const DEFAULT_MODAL_DIM: [f32; 4] = [0.0, 0.0, 0.0, 0.35];
+
+/// Dialogs that haven't picked a size yet get this much, or their
+/// parent's size if that is smaller
+pub const DEFAULT_DIALOG_SIZE: (i32, i32) = (800, 600);
+
+/// Where a window of `size` goes to be centered over `parent`, kept
+/// inside `bounds` where it fits
+pub fn center_over(
+    parent: Rectangle<i32, Logical>,
+    size: Size<i32, Logical>,
+    bounds: Rectangle<i32, Logical>,
+) -> Point<i32, Logical> {
+    let clamp = |centered: i32, start: i32, room: i32, length: i32| {
+        centered.min(start + room - length).max(start)
+    };
+    (
+        clamp(
+            parent.loc.x + (parent.size.w - size.w) / 2,
+            bounds.loc.x,
+            bounds.size.w,
+            size.w,
+        ),
+        clamp(
+            parent.loc.y + (parent.size.h - size.h) / 2,
+            bounds.loc.y,
+            bounds.size.h,
+            size.h,
+        ),
+    )
+        .into()
+}
+
+impl WebWMCompositor {
+    /// The window `window` is a dialog of, from xdg_toplevel.set_parent or
+    /// an imported foreign toplevel
+    pub fn window_parent(&self, window: &Window) -> Option<Window> {
+        let parent = window.toplevel()?.parent()?;
+        self.window_for_surface(&parent)
+    }
+
+    /// Record `window`'s current parent, moving it to the parent's
+    /// workspace if it isn't there yet
+    pub fn update_window_parent(&mut self, window: &Window) {
+        let parent = self.window_parent(window);
+        let parent_workspace = parent
+            .as_ref()
+            .and_then(|parent| self.workspace_manager.find_window_workspace(parent));
+
+        if let Some(target) = parent_workspace {
+            if self.workspace_manager.find_window_workspace(window) != Some(target) {
+                self.workspace_manager
+                    .move_window_to_workspace(window.clone(), target);
+            }
+        }
+
+        // A parent that isn't on any workspace can't be tracked
+        let parent = parent.filter(|_| parent_workspace.is_some());
+        if let Some(workspace) = self
+            .workspace_manager
+            .find_window_workspace(window)
+            .and_then(|id| self.workspace_manager.get_workspace_mut(id))
+        {
+            workspace.set_dialog_parent(window, parent.clone());
+        }
+
+        if let Some(parent) = &parent {
+            println!(
+                "💬 '{}' is a dialog of '{}'",
+                window_app_id(window),
+                window_app_id(parent)
+            );
+        }
+        self.relayout();
+    }
+
+    /// Put `window` on top, followed by its dialogs and theirs
+    pub fn raise_with_dialogs(&mut self, window: &Window) {
+        self.space.raise_element(window, true);
+
+        let dialogs = self
+            .workspace_manager
+            .find_window_workspace(window)
+            .and_then(|id| self.workspace_manager.get_workspace(id))
+            .map(|workspace| workspace.dialogs_of(window))
+            .unwrap_or_default();
+        for dialog in &dialogs {
+            self.raise_with_dialogs(dialog);
+        }
+    }
+
+    /// Center the active workspace's dialogs over their parents, inside
+    /// the area below the bar
+    pub fn layout_dialogs(&mut self, output_size: Size<i32, Logical>) {
+        let bar_height = self.bar_height();
+        let usable_area = Rectangle::from_loc_and_size(
+            (0, bar_height),
+            (output_size.w, output_size.h - bar_height),
+        );
+
+        // Oldest first, so a dialog's own parent is already in place
+        let dialogs: Vec<Window> = self
+            .workspace_manager
+            .active_workspace()
+            .dialogs
+            .iter()
+            .map(|(dialog, _)| dialog.clone())
+            .collect();
+        for dialog in &dialogs {
+            self.place_dialog(dialog, usable_area);
+        }
+    }
+
+    /// The open modal dialog of `window`, if any
+    pub fn modal_dialog_of(&self, window: &Window) -> Option<Window> {
+        let workspace = self
+            .workspace_manager
+            .get_workspace(self.workspace_manager.find_window_workspace(window)?)?;
+        workspace
+            .dialogs_of(window)
+            .into_iter()
+            .find(|dialog| self.modal_dialogs.contains(dialog))
+    }
+
+    /// The window that should get focus when `window` is clicked: its
+    /// modal dialog, or that dialog's own modal dialog, while one is open
+    pub fn focus_target(&self, window: &Window) -> Window {
+        match self.modal_dialog_of(window) {
+            Some(dialog) => self.focus_target(&dialog),
+            None => window.clone(),
+        }
+    }
+
+    /// Dim the parents of modal dialogs on the active workspace, leaving
+    /// the dialog itself undimmed
+    pub fn render_modal_dims(&self) -> Vec<BarElement> {
+        let stylesheet = self.stylesheet.as_ref();
+        let mut color = stylesheet
+            .and_then(|ss| ss.get_color(".modal-parent", "background-color"))
+            .map(|color| color.to_rgba_f32())
+            .unwrap_or(DEFAULT_MODAL_DIM);
+        if let Some(opacity) = stylesheet.and_then(|ss| ss.get_number(".modal-parent", "opacity")) {
+            color[3] *= opacity.clamp(0.0, 1.0);
+        }
+
+        let mut elements = Vec::new();
+        for window in &self.workspace_manager.active_workspace().windows {
+            let Some(dialog) = self.modal_dialog_of(window) else {
+                continue;
+            };
+            let Some(parent) = self.space.element_geometry(window) else {
+                continue;
+            };
+            let dialog = self.space.element_geometry(&dialog);
+
+            let dimmed = match dialog {
+                Some(dialog) => parent.subtract_rect(dialog),
+                None => vec![parent],
+            };
+            elements.extend(
+                dimmed
+                    .into_iter()
+                    .map(|geometry| BarElement::Rectangle { geometry, color }),
+            );
+        }
+        elements
+    }
+
+    /// Whether `window` is laid out over its parent rather than on its own
+    pub fn is_dialog(&self, window: &Window) -> bool {
+        self.workspace_manager
+            .find_window_workspace(window)
+            .and_then(|id| self.workspace_manager.get_workspace(id))
+            .is_some_and(|workspace| workspace.window_kind(window) == WindowKind::Dialog)
+    }
+
+    /// Center the dialog `window` over its parent, or over `bounds` while
+    /// the parent isn't on screen
+    pub fn place_dialog(&mut self, window: &Window, bounds: Rectangle<i32, Logical>) {
+        let parent = self
+            .window_parent(window)
+            .and_then(|parent| self.space.element_geometry(&parent))
+            .unwrap_or(bounds);
+
+        let mut size = window.geometry().size;
+        if size.w <= 0 || size.h <= 0 {
+            size = (
+                DEFAULT_DIALOG_SIZE.0.min(parent.size.w),
+                DEFAULT_DIALOG_SIZE.1.min(parent.size.h),
+            )
+                .into();
+        }
+
+        let location = center_over(parent, size, bounds);
+        self.space.map_element(window.clone(), location, false);
+        if let Some(toplevel) = window.toplevel() {
+            toplevel.with_pending_state(|state| {
+                state.size = Some(size);
+            });
+            toplevel.send_configure();
+        }
+    }
+}
+
+impl XdgDialogHandler for WebWMCompositor {
+    fn modal_changed(&mut self, toplevel: ToplevelSurface, is_modal: bool) {
+        let Some(window) = self.window_for_surface(toplevel.wl_surface()) else {
+            return;
+        };
+
+        self.modal_dialogs.retain(|dialog| dialog != &window);
+        if is_modal {
+            println!("💬 '{}' is modal", window_app_id(&window));
+            self.modal_dialogs.push(window);
+        }
+    }
+}
+
+delegate_xdg_dialog!(WebWMCompositor);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_center_over() {
+        let bounds = Rectangle::from_loc_and_size((0, 30), (1920, 1050));
+        let parent = Rectangle::from_loc_and_size((100, 100), (1000, 800));
+
+        let location = center_over(parent, (400, 300).into(), bounds);
+        assert_eq!(location, (400, 350).into());
+
+        // Pushed back inside the output rather than hanging off its edge
+        let corner = Rectangle::from_loc_and_size((1700, 900), (200, 100));
+        let location = center_over(corner, (600, 400).into(), bounds);
+        assert_eq!(location, (1320, 680).into());
+
+        // Bigger than the output: pinned to its top left
+        let location = center_over(parent, (2500, 1200).into(), bounds);
+        assert_eq!(location, (0, 30).into());
+    }
+}
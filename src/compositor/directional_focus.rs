@@ -0,0 +1,157 @@
+// Directional focus
+//
+// `focus left`, `right`, `up` and `down` move focus to the nearest window
+// on screen in that direction, going by where windows are rather than by
+// the order the workspace keeps them in. A window counts as toward the
+// direction when its center is past the focused window's center and its
+// far edge is past the focused window's edge. Among those, windows lined
+// up with the focused one (sharing some of its rows for left and right,
+// or columns for up and down) win, then the closest edge, then the
+// closest center across. Monocle and tabbed workspaces show one window at
+// a time, so there left and up still go to the previous window and right
+// and down to the next.
+
+use smithay::desktop::Window;
+use smithay::utils::{Logical, Rectangle};
+
+use crate::compositor::workspace::LayoutMode;
+use crate::compositor::WebWMCompositor;
+
+/// The item among `candidates` nearest to `from` toward `direction`
+pub fn nearest_in_direction<T: Clone>(
+    from: Rectangle<i32, Logical>,
+    candidates: &[(T, Rectangle<i32, Logical>)],
+    direction: &str,
+) -> Option<T> {
+    // Which axis the direction runs along, and whether it runs backwards
+    let (horizontal, backwards) = match direction {
+        "left" => (true, true),
+        "right" => (true, false),
+        "up" => (false, true),
+        "down" => (false, false),
+        _ => return None,
+    };
+    // (start, length) of a rectangle along and across the direction
+    let along = |r: &Rectangle<i32, Logical>| {
+        if horizontal {
+            (r.loc.x, r.size.w)
+        } else {
+            (r.loc.y, r.size.h)
+        }
+    };
+    let across = |r: &Rectangle<i32, Logical>| {
+        if horizontal {
+            (r.loc.y, r.size.h)
+        } else {
+            (r.loc.x, r.size.w)
+        }
+    };
+    // Flip backwards directions so "ahead" is always larger
+    let flip = |(start, len): (i32, i32)| {
+        if backwards {
+            (-(start + len), len)
+        } else {
+            (start, len)
+        }
+    };
+
+    let (from_start, from_len) = flip(along(&from));
+    let (from_across, from_width) = across(&from);
+    let from_center = from_start * 2 + from_len;
+    let from_center_across = from_across * 2 + from_width;
+
+    candidates
+        .iter()
+        .filter_map(|(item, geometry)| {
+            let (start, len) = flip(along(geometry));
+            let ahead = start * 2 + len > from_center && start + len > from_start + from_len;
+            if !ahead {
+                return None;
+            }
+            let (cross, width) = across(geometry);
+            let lined_up = cross < from_across + from_width && from_across < cross + width;
+            let gap = (start - (from_start + from_len)).max(0);
+            let off_center = (cross * 2 + width - from_center_across).abs();
+            Some(((!lined_up, gap, off_center), item))
+        })
+        .min_by_key(|(score, _)| *score)
+        .map(|(_, item)| item.clone())
+}
+
+impl WebWMCompositor {
+    /// Whether directional focus goes by window order on the active
+    /// workspace: one window shown at a time, or none focused on screen
+    pub fn focus_by_order(&self) -> bool {
+        let workspace = self.workspace_manager.active_workspace();
+        matches!(
+            workspace.layout_mode,
+            LayoutMode::Monocle | LayoutMode::Tabbed
+        ) || workspace
+            .focused_window()
+            .and_then(|window| self.space.element_geometry(window))
+            .is_none()
+    }
+
+    /// The window on screen nearest the focused one toward `direction`
+    pub fn window_in_direction(&self, direction: &str) -> Option<Window> {
+        let workspace = self.workspace_manager.active_workspace();
+        let focused = workspace.focused_window()?;
+        let from = self.space.element_geometry(focused)?;
+
+        let candidates: Vec<(Window, Rectangle<i32, Logical>)> = self
+            .space
+            .elements()
+            .filter(|window| *window != focused && workspace.windows.contains(window))
+            .filter_map(|window| Some((window.clone(), self.space.element_geometry(window)?)))
+            .filter(|(_, geometry)| geometry.size.w > 0 && geometry.size.h > 0)
+            .collect();
+        nearest_in_direction(from, &candidates, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compositor::ui::rect;
+
+    #[test]
+    fn test_nearest_in_direction() {
+        // A master tile on the left and three stacked on the right
+        let master = rect(0, 0, 960, 1080);
+        let stack = [
+            ("top", rect(970, 0, 950, 350)),
+            ("middle", rect(970, 360, 950, 350)),
+            ("bottom", rect(970, 720, 950, 360)),
+        ];
+        let mut all = vec![("master", master)];
+        all.extend(stack);
+
+        // Right of the master: the stack tile across from its center
+        assert_eq!(nearest_in_direction(master, &all, "right"), Some("middle"));
+        // Left of any stack tile: the master
+        assert_eq!(
+            nearest_in_direction(stack[2].1, &all, "left"),
+            Some("master")
+        );
+        // Up and down within the stack, the nearest first
+        assert_eq!(nearest_in_direction(stack[2].1, &all, "up"), Some("middle"));
+        assert_eq!(
+            nearest_in_direction(stack[0].1, &all, "down"),
+            Some("middle")
+        );
+        // Nothing past the edges
+        assert_eq!(nearest_in_direction(master, &all, "left"), None);
+        assert_eq!(nearest_in_direction(stack[0].1, &all, "up"), None);
+        assert_eq!(nearest_in_direction(master, &all, "sideways"), None);
+
+        // A window lined up wins over a closer one off to the side
+        let windows = [
+            ("level", rect(800, 0, 100, 100)),
+            ("below", rect(220, 150, 100, 100)),
+        ];
+        assert_eq!(
+            nearest_in_direction(rect(0, 0, 200, 100), &windows, "right"),
+            Some("level")
+        );
+    }
+}
@@ -0,0 +1,744 @@
+// Dwindle layout
+//
+// Tiles form a binary tree, like bspwm or Hyprland's dwindle: every leaf is
+// a tile and every inner node splits its area in two. A new window splits
+// the tile that was focused when it opened, side by side or one above the
+// other. With `<split-mode default="auto"/>` the longer side of that tile is
+// cut; "alternate" flips the direction at each level instead, and "manual"
+// keeps the direction of the split the tile is in, like i3. Each workspace
+// keeps its own tree, so closing a window hands its area to its sibling
+// without moving anything else. The focused tile's split can be resized
+// and rotated.
+//
+// The split-horizontal and split-vertical actions pick the direction the
+// focused tile is split in when the next window opens, whatever the mode.
+// On a workspace with another layout they switch it to dwindle first, as
+// only the tree has directions to pick.
+
+use smithay::desktop::Window;
+use smithay::utils::{Logical, Point, Rectangle, Size};
+
+use crate::compositor::workspace::LayoutMode;
+use crate::compositor::WebWMCompositor;
+
+/// Splits never give either side less or more than this
+const MIN_SPLIT_RATIO: f32 = 0.1;
+const MAX_SPLIT_RATIO: f32 = 0.9;
+
+/// How a split cuts its area
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitAxis {
+    /// Side by side
+    Horizontal,
+    /// One above the other
+    Vertical,
+}
+
+impl SplitAxis {
+    fn flipped(self) -> Self {
+        match self {
+            SplitAxis::Horizontal => SplitAxis::Vertical,
+            SplitAxis::Vertical => SplitAxis::Horizontal,
+        }
+    }
+
+    /// Cut across the longer side of `area`
+    fn for_area(area: Rectangle<i32, Logical>) -> Self {
+        if area.size.w >= area.size.h {
+            SplitAxis::Horizontal
+        } else {
+            SplitAxis::Vertical
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node<T> {
+    Leaf(T),
+    Split {
+        axis: SplitAxis,
+        /// Share of the area the first child gets
+        ratio: f32,
+        children: Box<(Node<T>, Node<T>)>,
+    },
+}
+
+impl<T: Clone + PartialEq> Node<T> {
+    fn is_leaf(&self, item: &T) -> bool {
+        matches!(self, Node::Leaf(leaf) if leaf == item)
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        match self {
+            Node::Leaf(leaf) => leaf == item,
+            Node::Split { children, .. } => children.0.contains(item) || children.1.contains(item),
+        }
+    }
+
+    fn leaves(&self, out: &mut Vec<T>) {
+        match self {
+            Node::Leaf(leaf) => out.push(leaf.clone()),
+            Node::Split { children, .. } => {
+                children.0.leaves(out);
+                children.1.leaves(out);
+            }
+        }
+    }
+
+    fn split_leaf(&mut self, target: &T, item: &T, axis: SplitAxis, ratio: f32) -> bool {
+        match self {
+            Node::Leaf(leaf) if leaf == target => {
+                let old = Node::Leaf(leaf.clone());
+                *self = Node::Split {
+                    axis,
+                    ratio,
+                    children: Box::new((old, Node::Leaf(item.clone()))),
+                };
+                true
+            }
+            Node::Leaf(_) => false,
+            Node::Split { children, .. } => {
+                children.0.split_leaf(target, item, axis, ratio)
+                    || children.1.split_leaf(target, item, axis, ratio)
+            }
+        }
+    }
+
+    /// This node without `item`; a split left with one child is replaced
+    /// by that child
+    fn without(self, item: &T) -> Option<Node<T>> {
+        match self {
+            Node::Leaf(leaf) => (leaf != *item).then_some(Node::Leaf(leaf)),
+            Node::Split {
+                axis,
+                ratio,
+                children,
+            } => {
+                let (first, second) = *children;
+                match (first.without(item), second.without(item)) {
+                    (Some(first), Some(second)) => Some(Node::Split {
+                        axis,
+                        ratio,
+                        children: Box::new((first, second)),
+                    }),
+                    (first, second) => first.or(second),
+                }
+            }
+        }
+    }
+
+    fn replace(&mut self, old: &T, new: &T) -> bool {
+        match self {
+            Node::Leaf(leaf) if leaf == old => {
+                *leaf = new.clone();
+                true
+            }
+            Node::Leaf(_) => false,
+            Node::Split { children, .. } => {
+                children.0.replace(old, new) || children.1.replace(old, new)
+            }
+        }
+    }
+
+    fn swap(&mut self, a: &T, b: &T) {
+        match self {
+            Node::Leaf(leaf) if leaf == a => *leaf = b.clone(),
+            Node::Leaf(leaf) if leaf == b => *leaf = a.clone(),
+            Node::Leaf(_) => {}
+            Node::Split { children, .. } => {
+                children.0.swap(a, b);
+                children.1.swap(a, b);
+            }
+        }
+    }
+
+    /// Axis and ratio of the split `item` is a direct child of, and
+    /// whether it is the first child
+    fn parent_split(&mut self, item: &T) -> Option<(&mut SplitAxis, &mut f32, bool)> {
+        let Node::Split {
+            axis,
+            ratio,
+            children,
+        } = self
+        else {
+            return None;
+        };
+        let (first, second) = &mut **children;
+        if first.is_leaf(item) || second.is_leaf(item) {
+            return Some((axis, ratio, first.is_leaf(item)));
+        }
+        first
+            .parent_split(item)
+            .or_else(|| second.parent_split(item))
+    }
+
+    /// Ratio of the split whose gap `point` is on (or within `margin` of),
+    /// and the length that split divides
+    fn split_at(
+        &mut self,
+        area: Rectangle<i32, Logical>,
+        gaps: i32,
+        point: Point<i32, Logical>,
+        margin: i32,
+    ) -> Option<(&mut f32, i32)> {
+        let Node::Split {
+            axis,
+            ratio,
+            children,
+        } = self
+        else {
+            return None;
+        };
+        let (first, second) = split_area(area, *axis, *ratio, gaps);
+        let (gap, length) = match axis {
+            SplitAxis::Horizontal => (
+                Rectangle::from_loc_and_size(
+                    (first.loc.x + first.size.w - margin, area.loc.y),
+                    (gaps + margin * 2, area.size.h),
+                ),
+                area.size.w - gaps,
+            ),
+            SplitAxis::Vertical => (
+                Rectangle::from_loc_and_size(
+                    (area.loc.x, first.loc.y + first.size.h - margin),
+                    (area.size.w, gaps + margin * 2),
+                ),
+                area.size.h - gaps,
+            ),
+        };
+
+        if gap.contains(point) {
+            Some((ratio, length))
+        } else if first.contains(point) {
+            children.0.split_at(first, gaps, point, margin)
+        } else if second.contains(point) {
+            children.1.split_at(second, gaps, point, margin)
+        } else {
+            None
+        }
+    }
+
+    /// Move the edge of `item` that faces `forward` (right or down) or
+    /// back along `axis` by `pixels`, using the innermost split with that
+    /// edge; returns its new ratio
+    fn resize_toward(
+        &mut self,
+        item: &T,
+        area: Rectangle<i32, Logical>,
+        gaps: i32,
+        (axis, forward): (SplitAxis, bool),
+        pixels: f64,
+    ) -> Option<f32> {
+        let Node::Split {
+            axis: split_axis,
+            ratio,
+            children,
+        } = self
+        else {
+            return None;
+        };
+        let (first, second) = split_area(area, *split_axis, *ratio, gaps);
+        let in_first = children.0.contains(item);
+        let inner = if in_first {
+            children
+                .0
+                .resize_toward(item, first, gaps, (axis, forward), pixels)
+        } else if children.1.contains(item) {
+            children
+                .1
+                .resize_toward(item, second, gaps, (axis, forward), pixels)
+        } else {
+            return None;
+        };
+        if inner.is_some() || *split_axis != axis || in_first != forward {
+            return inner;
+        }
+
+        let length = match axis {
+            SplitAxis::Horizontal => area.size.w,
+            SplitAxis::Vertical => area.size.h,
+        } - gaps;
+        let delta = pixels as f32 / length.max(1) as f32;
+        let delta = if forward { delta } else { -delta };
+        *ratio = (*ratio + delta).clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO);
+        Some(*ratio)
+    }
+
+    fn geometries(
+        &self,
+        area: Rectangle<i32, Logical>,
+        gaps: i32,
+        out: &mut Vec<(T, Rectangle<i32, Logical>)>,
+    ) {
+        match self {
+            Node::Leaf(leaf) => out.push((leaf.clone(), area)),
+            Node::Split {
+                axis,
+                ratio,
+                children,
+            } => {
+                let (first, second) = split_area(area, *axis, *ratio, gaps);
+                children.0.geometries(first, gaps, out);
+                children.1.geometries(second, gaps, out);
+            }
+        }
+    }
+}
+
+/// Cut `area` in two along `axis`, `gaps` apart
+fn split_area(
+    area: Rectangle<i32, Logical>,
+    axis: SplitAxis,
+    ratio: f32,
+    gaps: i32,
+) -> (Rectangle<i32, Logical>, Rectangle<i32, Logical>) {
+    let (loc, size) = (area.loc, area.size);
+    match axis {
+        SplitAxis::Horizontal => {
+            let first = ((size.w - gaps) as f32 * ratio).round() as i32;
+            (
+                Rectangle::from_loc_and_size(loc, (first, size.h)),
+                Rectangle::from_loc_and_size(
+                    (loc.x + first + gaps, loc.y),
+                    (size.w - gaps - first, size.h),
+                ),
+            )
+        }
+        SplitAxis::Vertical => {
+            let first = ((size.h - gaps) as f32 * ratio).round() as i32;
+            (
+                Rectangle::from_loc_and_size(loc, (size.w, first)),
+                Rectangle::from_loc_and_size(
+                    (loc.x, loc.y + first + gaps),
+                    (size.w, size.h - gaps - first),
+                ),
+            )
+        }
+    }
+}
+
+/// Binary tree of tiles, one per workspace
+#[derive(Debug, Clone)]
+pub struct DwindleTree<T> {
+    root: Option<Node<T>>,
+    /// Ratio new splits start with, from the workspace's `<split-ratio>`
+    pub default_ratio: f32,
+    /// Leaf the next new tile splits: the one focused when it opened
+    pub split_target: Option<T>,
+    /// Direction picked for the next split of a leaf, with the split
+    /// actions
+    pub split_hint: Option<(T, SplitAxis)>,
+}
+
+impl<T: Clone + PartialEq> DwindleTree<T> {
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            default_ratio: 0.5,
+            split_target: None,
+            split_hint: None,
+        }
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.leaves().contains(item)
+    }
+
+    /// Leaves in layout order
+    pub fn leaves(&self) -> Vec<T> {
+        let mut leaves = Vec::new();
+        if let Some(root) = &self.root {
+            root.leaves(&mut leaves);
+        }
+        leaves
+    }
+
+    /// Split `target` (or the last leaf if it isn't in the tree), putting
+    /// `item` after it. `axis` picks the direction from the area of the
+    /// leaf being split and the axis of the split it is in, unless a
+    /// direction was picked for that leaf with `split_hint`.
+    pub fn insert(
+        &mut self,
+        item: T,
+        target: Option<&T>,
+        area: Rectangle<i32, Logical>,
+        gaps: i32,
+        axis: impl FnOnce(Rectangle<i32, Logical>, Option<SplitAxis>) -> SplitAxis,
+    ) {
+        if self.root.is_none() {
+            self.root = Some(Node::Leaf(item));
+            return;
+        }
+
+        let geometries = self.geometries(area, gaps);
+        let Some((target, target_area)) = geometries
+            .iter()
+            .find(|(leaf, _)| Some(leaf) == target)
+            .or(geometries.last())
+            .cloned()
+        else {
+            return;
+        };
+        let parent_axis = self
+            .root
+            .as_mut()
+            .and_then(|root| root.parent_split(&target))
+            .map(|(axis, _, _)| *axis);
+
+        let axis = match self.split_hint.take() {
+            Some((leaf, hint)) if leaf == target => hint,
+            hint => {
+                self.split_hint = hint;
+                axis(target_area, parent_axis)
+            }
+        };
+        let ratio = self.default_ratio;
+        if let Some(root) = &mut self.root {
+            root.split_leaf(&target, &item, axis, ratio);
+        }
+    }
+
+    pub fn remove(&mut self, item: &T) -> bool {
+        if self
+            .split_hint
+            .as_ref()
+            .is_some_and(|(leaf, _)| leaf == item)
+        {
+            self.split_hint = None;
+        }
+        let had = self.contains(item);
+        self.root = self.root.take().and_then(|root| root.without(item));
+        had
+    }
+
+    /// Put `new` where `old` is, keeping the shape of the tree
+    pub fn replace(&mut self, old: &T, new: &T) -> bool {
+        if let Some((leaf, _)) = self.split_hint.as_mut().filter(|(leaf, _)| leaf == old) {
+            *leaf = new.clone();
+        }
+        self.root
+            .as_mut()
+            .is_some_and(|root| root.replace(old, new))
+    }
+
+    /// Put `a` where `b` is and `b` where `a` is
+    pub fn swap(&mut self, a: &T, b: &T) -> bool {
+        if !self.contains(a) || !self.contains(b) {
+            return false;
+        }
+        if let Some(root) = &mut self.root {
+            root.swap(a, b);
+        }
+        true
+    }
+
+    /// Grow `item`'s side of the split it is in by `delta` (shrink it
+    /// with a negative one)
+    pub fn adjust_ratio(&mut self, item: &T, delta: f32) -> bool {
+        let Some((_, ratio, first)) = self.root.as_mut().and_then(|root| root.parent_split(item))
+        else {
+            return false;
+        };
+        let delta = if first { delta } else { -delta };
+        *ratio = (*ratio + delta).clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO);
+        true
+    }
+
+    /// Turn the split `item` is in by 90 degrees
+    pub fn rotate(&mut self, item: &T) -> bool {
+        let Some((axis, _, _)) = self.root.as_mut().and_then(|root| root.parent_split(item)) else {
+            return false;
+        };
+        *axis = axis.flipped();
+        true
+    }
+
+    /// Move the gap at `point` (give or take `margin`) by `pixels`, right
+    /// or down; returns the split's new ratio
+    pub fn resize_split_at(
+        &mut self,
+        point: Point<i32, Logical>,
+        area: Rectangle<i32, Logical>,
+        gaps: i32,
+        margin: i32,
+        pixels: f64,
+    ) -> Option<f32> {
+        let (ratio, length) = self.root.as_mut()?.split_at(area, gaps, point, margin)?;
+        *ratio =
+            (*ratio + pixels as f32 / length.max(1) as f32).clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO);
+        Some(*ratio)
+    }
+
+    /// Grow `item` by `pixels` toward `forward` (right or down) or back
+    /// along `axis`; returns the ratio of the split that moved
+    pub fn resize_toward(
+        &mut self,
+        item: &T,
+        area: Rectangle<i32, Logical>,
+        gaps: i32,
+        (axis, forward): (SplitAxis, bool),
+        pixels: f64,
+    ) -> Option<f32> {
+        self.root
+            .as_mut()?
+            .resize_toward(item, area, gaps, (axis, forward), pixels)
+    }
+
+    /// Where each leaf goes in `area`
+    pub fn geometries(
+        &self,
+        area: Rectangle<i32, Logical>,
+        gaps: i32,
+    ) -> Vec<(T, Rectangle<i32, Logical>)> {
+        let mut out = Vec::new();
+        if let Some(root) = &self.root {
+            root.geometries(area, gaps, &mut out);
+        }
+        out
+    }
+}
+
+impl<T: Clone + PartialEq> Default for DwindleTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Direction a split of `area` takes in split `mode`: across its longer
+/// side, the other way from the split it is in ("alternate"), or the same
+/// way ("manual")
+fn split_axis(mode: &str, area: Rectangle<i32, Logical>, parent: Option<SplitAxis>) -> SplitAxis {
+    match (mode, parent) {
+        ("alternate", Some(parent)) => parent.flipped(),
+        ("manual", Some(parent)) => parent,
+        ("alternate" | "manual", None) => SplitAxis::Horizontal,
+        _ => SplitAxis::for_area(area),
+    }
+}
+
+impl WebWMCompositor {
+    /// The tree leaf standing for the focused window's tile
+    pub fn focused_dwindle_leaf(&self) -> Option<Window> {
+        let workspace = self.workspace_manager.active_workspace();
+        let focused = workspace.dialog_root(workspace.focused_window()?);
+        let tile = workspace
+            .tiles()
+            .into_iter()
+            .find(|tile| tile.windows.contains(focused))?;
+        let leaves = workspace.dwindle.leaves();
+        tile.windows
+            .into_iter()
+            .find(|window| leaves.contains(window))
+    }
+
+    /// Grow the focused tile's side of its split by `delta`
+    pub fn adjust_split_ratio(&mut self, delta: f32) {
+        let Some(leaf) = self.focused_dwindle_leaf() else {
+            return;
+        };
+        let workspace = self.workspace_manager.active_workspace_mut();
+        if workspace.dwindle.adjust_ratio(&leaf, delta) {
+            self.relayout();
+        }
+    }
+
+    /// Split the focused tile along `axis` when the next window opens
+    pub fn choose_split(&mut self, axis: SplitAxis) {
+        let workspace = self.workspace_manager.active_workspace_mut();
+        if workspace.layout_mode != LayoutMode::Dwindle {
+            println!(
+                "Switched workspace {} to {:?} mode to split",
+                workspace.id,
+                LayoutMode::Dwindle
+            );
+            workspace.layout_mode = LayoutMode::Dwindle;
+            self.relayout();
+        }
+        let Some(leaf) = self.focused_dwindle_leaf() else {
+            return;
+        };
+
+        println!("🪓 Next window splits the focused tile {:?}", axis);
+        self.workspace_manager
+            .active_workspace_mut()
+            .dwindle
+            .split_hint = Some((leaf, axis));
+    }
+
+    /// Turn the focused tile's split between side by side and stacked
+    pub fn rotate_split(&mut self) {
+        let Some(leaf) = self.focused_dwindle_leaf() else {
+            return;
+        };
+        let workspace = self.workspace_manager.active_workspace_mut();
+        if workspace.dwindle.rotate(&leaf) {
+            self.relayout();
+        }
+    }
+
+    pub fn layout_dwindle(&mut self, output_size: Size<i32, Logical>, gaps: i32) {
+        let area = self.tiled_area(output_size);
+
+        // Bring the tree in line with the tiles: one leaf per tile, and new
+        // tiles split the one focused when they opened
+        let tiles = self.workspace_manager.active_workspace().tiles();
+        let mut seen = Vec::new();
+        let stale: Vec<Window> = self
+            .workspace_manager
+            .active_workspace()
+            .dwindle
+            .leaves()
+            .into_iter()
+            .filter(
+                |leaf| match tiles.iter().position(|tile| tile.windows.contains(leaf)) {
+                    Some(tile) if !seen.contains(&tile) => {
+                        seen.push(tile);
+                        false
+                    }
+                    _ => true,
+                },
+            )
+            .collect();
+
+        let new_tiles: Vec<Window> = tiles
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !seen.contains(i))
+            .map(|(_, tile)| tile.windows[0].clone())
+            .collect();
+        let mode = self
+            .config
+            .desktop
+            .as_ref()
+            .map_or("auto", |desktop| desktop.layout.split_mode.as_str());
+
+        let workspace = self.workspace_manager.active_workspace_mut();
+        for leaf in &stale {
+            workspace.dwindle.remove(leaf);
+        }
+        for window in new_tiles {
+            // The window that was focused may be in a tab group led by
+            // another leaf
+            let target = workspace.dwindle.split_target.take().and_then(|target| {
+                let tile = tiles.iter().find(|tile| tile.windows.contains(&target))?;
+                let leaves = workspace.dwindle.leaves();
+                tile.windows.iter().find(|w| leaves.contains(w)).cloned()
+            });
+            workspace
+                .dwindle
+                .insert(window, target.as_ref(), area, gaps, |leaf_area, parent| {
+                    split_axis(mode, leaf_area, parent)
+                });
+        }
+
+        for (leaf, geometry) in self
+            .workspace_manager
+            .active_workspace()
+            .dwindle
+            .geometries(area, gaps)
+        {
+            if let Some(tile) = tiles.iter().find(|tile| tile.windows.contains(&leaf)) {
+                self.map_tile(tile, geometry.loc, geometry.size);
+            }
+        }
+
+        let active_ws = self.workspace_manager.active_workspace();
+        println!(
+            "Relayout: {} windows in dwindle mode on workspace {}",
+            active_ws.len(),
+            active_ws.id
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn by_area(area: Rectangle<i32, Logical>, parent: Option<SplitAxis>) -> SplitAxis {
+        split_axis("auto", area, parent)
+    }
+
+    #[test]
+    fn test_dwindle_tree() {
+        let area = Rectangle::from_loc_and_size((0, 0), (1000, 600));
+        let mut tree = DwindleTree::new();
+        tree.insert(1, None, area, 0, by_area);
+        assert_eq!(tree.geometries(area, 0), vec![(1, area)]);
+
+        // Wide: side by side, then the right half (taller than wide) is
+        // cut top to bottom
+        tree.insert(2, Some(&1), area, 0, by_area);
+        tree.insert(3, Some(&2), area, 0, by_area);
+        assert_eq!(
+            tree.geometries(area, 0),
+            vec![
+                (1, Rectangle::from_loc_and_size((0, 0), (500, 600))),
+                (2, Rectangle::from_loc_and_size((500, 0), (500, 300))),
+                (3, Rectangle::from_loc_and_size((500, 300), (500, 300))),
+            ]
+        );
+
+        // Growing the second child moves the split towards the first
+        assert!(tree.adjust_ratio(&3, 0.25));
+        assert_eq!(
+            tree.geometries(area, 0)[2].1,
+            Rectangle::from_loc_and_size((500, 150), (500, 450))
+        );
+        assert!(tree.rotate(&3));
+        assert_eq!(
+            tree.geometries(area, 0)[2].1,
+            Rectangle::from_loc_and_size((625, 0), (375, 600))
+        );
+
+        // The sibling takes over a removed leaf's area
+        assert!(tree.remove(&1));
+        assert!(!tree.adjust_ratio(&1, 0.1));
+        assert_eq!(tree.leaves(), vec![2, 3]);
+        assert_eq!(
+            tree.geometries(area, 0)[1].1,
+            Rectangle::from_loc_and_size((250, 0), (750, 600))
+        );
+
+        // Swapped leaves trade areas
+        assert!(tree.swap(&2, &3));
+        assert_eq!(tree.leaves(), vec![3, 2]);
+        assert_eq!(
+            tree.geometries(area, 0)[1],
+            (2, Rectangle::from_loc_and_size((250, 0), (750, 600)))
+        );
+        assert!(!tree.swap(&2, &1));
+    }
+
+    #[test]
+    fn test_split_hint() {
+        let area = Rectangle::from_loc_and_size((0, 0), (1000, 600));
+        let manual = |area, parent| split_axis("manual", area, parent);
+        let mut tree = DwindleTree::new();
+        tree.insert(1, None, area, 0, manual);
+        tree.insert(2, Some(&1), area, 0, manual);
+        tree.insert(3, Some(&2), area, 0, manual);
+
+        // Manual splits keep going side by side
+        assert_eq!(
+            tree.geometries(area, 0)[2].1,
+            Rectangle::from_loc_and_size((750, 0), (250, 600))
+        );
+
+        // A hint only applies to its own leaf, once
+        tree.split_hint = Some((3, SplitAxis::Vertical));
+        tree.insert(4, Some(&1), area, 0, manual);
+        assert!(tree.split_hint.is_some());
+        tree.insert(5, Some(&3), area, 0, manual);
+        assert!(tree.split_hint.is_none());
+        assert_eq!(
+            tree.geometries(area, 0)[4],
+            (5, Rectangle::from_loc_and_size((750, 300), (250, 300)))
+        );
+
+        // Removing the leaf drops its hint
+        tree.split_hint = Some((2, SplitAxis::Vertical));
+        tree.remove(&2);
+        assert!(tree.split_hint.is_none());
+    }
+}
@@ -0,0 +1,280 @@
+// Flex layout
+//
+// Tiling mode lays its tiles out in the window slots described by nested
+// `<row>` and `<column>` containers under `<tiling>` in desktop.xml. A row
+// puts its children side by side and a column stacks them; each child takes
+// a share of its parent in proportion to its `flex` (1 by default), like CSS
+// flexbox. Containers without children are slots, filled in document order.
+// The last slot takes every window left over, split along its own
+// direction, and slots without a window collapse so their siblings grow.
+// Tiles sharing that last slot are sized by their weights, which resizing
+// changes. Without a description the whole area is one `<row>`, which puts
+// all tiles side by side.
+
+use std::ops::Range;
+
+use smithay::utils::{Logical, Rectangle, Size};
+
+use crate::compositor::WebWMCompositor;
+use crate::config::xml_parser::{FlexContainer, FlexDirection};
+
+impl FlexContainer {
+    fn slot_count(&self) -> usize {
+        if self.children.is_empty() {
+            1
+        } else {
+            self.children.iter().map(FlexContainer::slot_count).sum()
+        }
+    }
+}
+
+/// How many of `tiles` windows the slot at `index` of `slots` holds
+fn slot_tiles(index: usize, slots: usize, tiles: usize) -> usize {
+    if index >= tiles {
+        0
+    } else if index + 1 == slots {
+        tiles - index
+    } else {
+        1
+    }
+}
+
+/// Windows held by `node`, whose first slot is `first_slot`
+fn container_tiles(node: &FlexContainer, first_slot: usize, slots: usize, tiles: usize) -> usize {
+    if node.children.is_empty() {
+        return slot_tiles(first_slot, slots, tiles);
+    }
+    let mut slot = first_slot;
+    node.children
+        .iter()
+        .map(|child| {
+            let count = container_tiles(child, slot, slots, tiles);
+            slot += child.slot_count();
+            count
+        })
+        .sum()
+}
+
+/// Cut `area` along `direction` into parts sized by `flexes`, `gaps` apart
+pub fn split(
+    area: Rectangle<i32, Logical>,
+    direction: FlexDirection,
+    flexes: &[f32],
+    gaps: i32,
+) -> Vec<Rectangle<i32, Logical>> {
+    let extent = match direction {
+        FlexDirection::Row => area.size.w,
+        FlexDirection::Column => area.size.h,
+    };
+    let available = (extent - gaps * (flexes.len() as i32 - 1)).max(0);
+    let total: f32 = flexes.iter().sum();
+
+    let mut offset = 0;
+    flexes
+        .iter()
+        .map(|flex| {
+            let size = (available as f32 * flex / total).floor() as i32;
+            let part = match direction {
+                FlexDirection::Row => Rectangle::from_loc_and_size(
+                    (area.loc.x + offset, area.loc.y),
+                    (size, area.size.h),
+                ),
+                FlexDirection::Column => Rectangle::from_loc_and_size(
+                    (area.loc.x, area.loc.y + offset),
+                    (area.size.w, size),
+                ),
+            };
+            offset += size + gaps;
+            part
+        })
+        .collect()
+}
+
+fn place(
+    node: &FlexContainer,
+    area: Rectangle<i32, Logical>,
+    gaps: i32,
+    first_slot: usize,
+    (slots, weights): (usize, &[f32]),
+    geometries: &mut Vec<Rectangle<i32, Logical>>,
+) {
+    let tiles = weights.len();
+    if node.children.is_empty() {
+        let count = slot_tiles(first_slot, slots, tiles);
+        let weights = &weights[first_slot.min(tiles)..(first_slot + count).min(tiles)];
+        geometries.extend(split(area, node.direction, weights, gaps));
+        return;
+    }
+
+    // Children without windows don't take any room
+    let mut slot = first_slot;
+    let mut used = Vec::new();
+    for child in &node.children {
+        if container_tiles(child, slot, slots, tiles) > 0 {
+            used.push((child, slot));
+        }
+        slot += child.slot_count();
+    }
+
+    let flexes: Vec<f32> = used.iter().map(|(child, _)| child.flex).collect();
+    for ((child, slot), part) in used
+        .into_iter()
+        .zip(split(area, node.direction, &flexes, gaps))
+    {
+        place(child, part, gaps, slot, (slots, weights), geometries);
+    }
+}
+
+/// Geometries of windows laid out by `root` inside `area`, in the order
+/// the slots are filled; `weights` has one entry per window
+pub fn flex_geometries(
+    root: &FlexContainer,
+    area: Rectangle<i32, Logical>,
+    gaps: i32,
+    weights: &[f32],
+) -> Vec<Rectangle<i32, Logical>> {
+    let mut geometries = Vec::with_capacity(weights.len());
+    if !weights.is_empty() {
+        place(
+            root,
+            area,
+            gaps,
+            0,
+            (root.slot_count(), weights),
+            &mut geometries,
+        );
+    }
+    geometries
+}
+
+/// The tiles sharing the last slot of `root`, and the direction they are
+/// laid out in; other slots get their size from the config
+pub fn shared_slot(root: &FlexContainer, tiles: usize) -> Option<(Range<usize>, FlexDirection)> {
+    let slots = root.slot_count();
+    if tiles < slots {
+        return None;
+    }
+    let mut last = root;
+    while let Some(child) = last.children.last() {
+        last = child;
+    }
+    Some((slots - 1..tiles, last.direction))
+}
+
+impl WebWMCompositor {
+    /// The configured flex description, or a single row
+    pub fn flex_root(&self) -> FlexContainer {
+        self.config
+            .desktop
+            .as_ref()
+            .and_then(|desktop| desktop.layout.flex.clone())
+            .unwrap_or(FlexContainer {
+                direction: FlexDirection::Row,
+                flex: 1.0,
+                children: Vec::new(),
+            })
+    }
+
+    pub fn layout_tiling(&mut self, output_size: Size<i32, Logical>, gaps: i32) {
+        let tiles = self.workspace_manager.active_workspace().tiles();
+        if tiles.is_empty() {
+            return;
+        }
+
+        let bar_height = self.bar_height();
+        let area = self.tiled_area(output_size);
+
+        let workspace = self.workspace_manager.active_workspace();
+        let weights: Vec<f32> = tiles
+            .iter()
+            .map(|tile| workspace.tile_weight(tile))
+            .collect();
+        let geometries = flex_geometries(&self.flex_root(), area, gaps, &weights);
+        for (tile, geometry) in tiles.iter().zip(geometries) {
+            self.map_tile(tile, geometry.loc, geometry.size);
+        }
+
+        let active_ws = self.workspace_manager.active_workspace();
+        println!(
+            "Relayout: {} windows in tiling mode on workspace {} (gaps: {}px, bar_height: {}px)",
+            active_ws.len(),
+            active_ws.id,
+            gaps,
+            bar_height
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(
+        direction: FlexDirection,
+        flex: f32,
+        children: Vec<FlexContainer>,
+    ) -> FlexContainer {
+        FlexContainer {
+            direction,
+            flex,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_flex_geometries() {
+        let area = Rectangle::from_loc_and_size((0, 0), (1210, 800));
+
+        // A bare row is the old side-by-side split
+        let row = container(FlexDirection::Row, 1.0, Vec::new());
+        let geometries = flex_geometries(&row, area, 10, &[1.0; 3]);
+        assert_eq!(
+            geometries[0],
+            Rectangle::from_loc_and_size((0, 0), (396, 800))
+        );
+        assert_eq!(
+            geometries[2],
+            Rectangle::from_loc_and_size((812, 0), (396, 800))
+        );
+
+        // A wide slot on the left, the rest stacked on the right
+        let root = container(
+            FlexDirection::Row,
+            1.0,
+            vec![
+                container(FlexDirection::Column, 2.0, Vec::new()),
+                container(FlexDirection::Column, 1.0, Vec::new()),
+            ],
+        );
+        let geometries = flex_geometries(&root, area, 10, &[1.0; 3]);
+        assert_eq!(
+            geometries[0],
+            Rectangle::from_loc_and_size((0, 0), (800, 800))
+        );
+        assert_eq!(
+            geometries[1],
+            Rectangle::from_loc_and_size((810, 0), (400, 395))
+        );
+        assert_eq!(
+            geometries[2],
+            Rectangle::from_loc_and_size((810, 405), (400, 395))
+        );
+
+        // An empty slot gives its room to the others
+        let geometries = flex_geometries(&root, area, 10, &[1.0]);
+        assert_eq!(
+            geometries,
+            vec![Rectangle::from_loc_and_size((0, 0), (1210, 800))]
+        );
+        assert!(flex_geometries(&root, area, 10, &[]).is_empty());
+
+        // Resized tiles in the last slot keep their weights
+        let geometries = flex_geometries(&row, area, 10, &[1.0, 2.0, 1.0]);
+        assert_eq!(
+            geometries[1],
+            Rectangle::from_loc_and_size((307, 0), (595, 800))
+        );
+        assert_eq!(shared_slot(&root, 3), Some((1..3, FlexDirection::Column)));
+        assert_eq!(shared_slot(&root, 1), None);
+    }
+}
@@ -0,0 +1,489 @@
+// Moving and resizing floating windows
+//
+// On a floating workspace, or over a window floating on its own,
+// Super+left-drag moves the window under the pointer and Super+right-drag
+// resizes it from the corner nearest to where the drag starts; over tiles
+// the same drags carry windows to the bar and resize tiles. The window follows the pointer right away, but a
+// resized window is sent its new size at most every CONFIGURE_INTERVAL, so
+// a fast drag doesn't bury the client in configures it can't keep up with.
+// With `<drag mode="wireframe">` in `<floating>`, the window stays where it
+// is and an outline follows the pointer instead; the window is moved and
+// sent its size once, on release. The outline's colors come from the
+// stylesheet's `.drag-outline` rule. Where a window was put is kept with the
+// workspace, so relayouts leave it there.
+//
+// New windows get `<default-size>` and open in the middle of the area below
+// the bar with `<center-new-windows>`, or cascade down from its top left
+// corner without. That first place is remembered too, so windows don't
+// shift when others open or close. A window can't be dragged so far
+// that less than `<min-visible>` pixels of it stay on the output, and the
+// center-window action brings one that got lost anyway back to the middle.
+
+use std::time::{Duration, Instant};
+
+use smithay::desktop::Window;
+use smithay::input::pointer::{CursorIcon, CursorImageStatus};
+use smithay::utils::{Logical, Point, Rectangle, Size, SERIAL_COUNTER};
+use smithay::wayland::seat::WaylandFocus;
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::tabs::TAB_BAR_HEIGHT;
+use crate::compositor::workspace::TabGroup;
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+/// Shortest time between two sizes sent during a resize
+const CONFIGURE_INTERVAL: Duration = Duration::from_millis(33);
+
+/// A window can't be resized smaller than this
+const MIN_SIZE: (i32, i32) = (120, 80);
+
+/// Size of new windows without a `<default-size>`
+const DEFAULT_SIZE: (i32, i32) = (800, 600);
+
+/// How far each cascaded window is from the one before
+const CASCADE_STEP: i32 = 30;
+
+/// How much of a window stays on the output without a `<min-visible>`
+const DEFAULT_MIN_VISIBLE: i32 = 48;
+
+const OUTLINE_WIDTH: i32 = 2;
+const DEFAULT_OUTLINE_COLOR: [f32; 4] = [0.8, 0.83, 0.96, 1.0];
+const DEFAULT_OUTLINE_FILL: [f32; 4] = [0.8, 0.83, 0.96, 0.05];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FloatingGrabKind {
+    Move,
+    /// Whether the right (rather than left) and bottom (rather than top)
+    /// edges follow the pointer
+    Resize {
+        right: bool,
+        bottom: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct FloatingGrab {
+    pub window: Window,
+    pub kind: FloatingGrabKind,
+    pub start: Point<f64, Logical>,
+    /// The tile's geometry when the drag started
+    pub initial: Rectangle<i32, Logical>,
+    /// When the client was last sent a size
+    pub last_configure: Instant,
+    /// Where the outline is, in wireframe mode
+    pub geometry: Rectangle<i32, Logical>,
+    pub wireframe: bool,
+}
+
+/// `initial` with the edges `right` and `bottom` pick moved by `delta`,
+/// no smaller than MIN_SIZE; the other edges stay put
+pub fn resized(
+    initial: Rectangle<i32, Logical>,
+    (right, bottom): (bool, bool),
+    delta: Point<i32, Logical>,
+) -> Rectangle<i32, Logical> {
+    let resize = |start: i32, length: i32, delta: i32, far: bool, min: i32| {
+        if far {
+            (start, (length + delta).max(min))
+        } else {
+            // The near edge moves, the far one stays where it was
+            let end = start + length;
+            let length = (length - delta).max(min);
+            (end - length, length)
+        }
+    };
+    let (x, w) = resize(initial.loc.x, initial.size.w, delta.x, right, MIN_SIZE.0);
+    let (y, h) = resize(initial.loc.y, initial.size.h, delta.y, bottom, MIN_SIZE.1);
+    Rectangle::from_loc_and_size((x, y), (w, h))
+}
+
+/// `geometry` moved just far enough back that `min_visible` pixels of it,
+/// across and down, are inside `bounds`
+pub fn keep_visible(
+    geometry: Rectangle<i32, Logical>,
+    bounds: Rectangle<i32, Logical>,
+    min_visible: i32,
+) -> Rectangle<i32, Logical> {
+    let clamp = |pos: i32, length: i32, start: i32, extent: i32| {
+        let min = min_visible.min(length).min(extent);
+        pos.clamp(start + min - length, start + extent - min)
+    };
+    let x = clamp(geometry.loc.x, geometry.size.w, bounds.loc.x, bounds.size.w);
+    let y = clamp(geometry.loc.y, geometry.size.h, bounds.loc.y, bounds.size.h);
+    Rectangle::from_loc_and_size((x, y), geometry.size)
+}
+
+/// Where the `index`th new window of `size` goes in `area`: in the middle,
+/// or cascaded from the top left corner, starting over before it would
+/// leave the area
+pub fn new_floating_geometry(
+    index: usize,
+    size: Size<i32, Logical>,
+    area: Rectangle<i32, Logical>,
+    center: bool,
+) -> Rectangle<i32, Logical> {
+    let size: Size<i32, Logical> = (size.w.min(area.size.w), size.h.min(area.size.h)).into();
+    if center {
+        return Rectangle::from_loc_and_size(
+            (
+                area.loc.x + (area.size.w - size.w) / 2,
+                area.loc.y + (area.size.h - size.h) / 2,
+            ),
+            size,
+        );
+    }
+
+    let room = (area.size.w - size.w).min(area.size.h - size.h);
+    let steps = (room / CASCADE_STEP).max(1);
+    let offset = (index as i32 % steps) * CASCADE_STEP;
+    Rectangle::from_loc_and_size((area.loc.x + offset, area.loc.y + offset), size)
+}
+
+impl WebWMCompositor {
+    fn floating_min_visible(&self) -> i32 {
+        self.config
+            .desktop
+            .as_ref()
+            .map_or(DEFAULT_MIN_VISIBLE, |desktop| {
+                desktop.layout.floating_min_visible as i32
+            })
+    }
+
+    /// Where the floating tile at `index` goes: where it was put, or where
+    /// new windows go
+    pub fn floating_geometry(
+        &self,
+        index: usize,
+        tile: &TabGroup,
+        output_size: Size<i32, Logical>,
+    ) -> Rectangle<i32, Logical> {
+        let workspace = self.workspace_manager.active_workspace();
+        if let Some(geometry) = workspace.floating_geometry(tile) {
+            // The output may have shrunk since
+            let bounds = Rectangle::from_loc_and_size((0, 0), output_size);
+            return keep_visible(geometry, bounds, self.floating_min_visible());
+        }
+
+        let (size, center) = self
+            .config
+            .desktop
+            .as_ref()
+            .map_or((DEFAULT_SIZE, true), |desktop| {
+                let layout = &desktop.layout;
+                (
+                    (
+                        layout.floating_default_width as i32,
+                        layout.floating_default_height as i32,
+                    ),
+                    layout.center_new_windows,
+                )
+            });
+        let bar_height = self.bar_height();
+        let area = Rectangle::from_loc_and_size(
+            (0, bar_height),
+            (output_size.w, output_size.h - bar_height),
+        );
+        new_floating_geometry(index, size.into(), area, center)
+    }
+
+    /// Whether drags move an outline rather than the window
+    pub fn wireframe_drags(&self) -> bool {
+        self.config
+            .desktop
+            .as_ref()
+            .is_some_and(|desktop| desktop.layout.drag_mode == "wireframe")
+    }
+
+    /// Start moving or resizing the floating window under `location`.
+    /// Returns false unless there is a floating window there.
+    pub fn begin_floating_grab(&mut self, location: Point<f64, Logical>, resize: bool) -> bool {
+        let workspace = self.workspace_manager.active_workspace();
+        let Some(window) = self
+            .space
+            .element_under(location)
+            .map(|(window, _)| workspace.dialog_root(window).clone())
+            .filter(|window| workspace.is_floating(window))
+        else {
+            return false;
+        };
+        let tiles = workspace.floating_tiles();
+        let Some(index) = tiles.iter().position(|tile| tile.windows.contains(&window)) else {
+            return false;
+        };
+        let initial = self.floating_geometry(index, &tiles[index], self.layout_size());
+
+        let kind = if resize {
+            let center = initial.loc.to_f64() + initial.size.to_f64().downscale(2.0).to_point();
+            FloatingGrabKind::Resize {
+                right: location.x >= center.x,
+                bottom: location.y >= center.y,
+            }
+        } else {
+            FloatingGrabKind::Move
+        };
+
+        self.workspace_manager
+            .active_workspace_mut()
+            .focus_window(&window);
+        if let (Some(keyboard), Some(surface)) = (self.seat.get_keyboard(), window.wl_surface()) {
+            keyboard.set_focus(
+                self,
+                Some(surface.into_owned()),
+                SERIAL_COUNTER.next_serial(),
+            );
+        }
+        self.raise_window(&window);
+
+        println!(
+            "{} '{}'",
+            if resize {
+                "↔️ Resizing"
+            } else {
+                "✋ Moving"
+            },
+            window_app_id(&window)
+        );
+        self.cursor_image_status = CursorImageStatus::Named(match kind {
+            FloatingGrabKind::Move => CursorIcon::Move,
+            FloatingGrabKind::Resize { right, bottom } => match (right, bottom) {
+                (true, true) => CursorIcon::SeResize,
+                (true, false) => CursorIcon::NeResize,
+                (false, true) => CursorIcon::SwResize,
+                (false, false) => CursorIcon::NwResize,
+            },
+        });
+        self.floating_grab = Some(FloatingGrab {
+            window,
+            kind,
+            start: location,
+            initial,
+            last_configure: Instant::now(),
+            geometry: initial,
+            wireframe: self.wireframe_drags(),
+        });
+        true
+    }
+
+    pub fn floating_grab_motion(&mut self, location: Point<f64, Logical>) {
+        let Some(ref grab) = self.floating_grab else {
+            return;
+        };
+        let Some(tile) = self
+            .workspace_manager
+            .active_workspace()
+            .floating_tiles()
+            .into_iter()
+            .find(|tile| tile.windows.contains(&grab.window))
+        else {
+            // The window went away
+            self.end_floating_grab();
+            return;
+        };
+
+        let delta = (location - grab.start).to_i32_round();
+        let geometry = match grab.kind {
+            FloatingGrabKind::Move => keep_visible(
+                Rectangle::from_loc_and_size(grab.initial.loc + delta, grab.initial.size),
+                Rectangle::from_loc_and_size((0, 0), self.layout_size()),
+                self.floating_min_visible(),
+            ),
+            FloatingGrabKind::Resize { right, bottom } => {
+                resized(grab.initial, (right, bottom), delta)
+            }
+        };
+        if grab.wireframe {
+            if let Some(ref mut grab) = self.floating_grab {
+                grab.geometry = geometry;
+            }
+            return;
+        }
+        let configure = grab.kind != FloatingGrabKind::Move
+            && grab.last_configure.elapsed() >= CONFIGURE_INTERVAL;
+
+        self.workspace_manager
+            .active_workspace_mut()
+            .set_floating_geometry(&tile, geometry);
+        if configure {
+            self.map_tile(&tile, geometry.loc, geometry.size);
+            if let Some(ref mut grab) = self.floating_grab {
+                grab.last_configure = Instant::now();
+            }
+        } else {
+            // Only the position changes until the next size is sent
+            let offset = if tile.windows.len() > 1 {
+                TAB_BAR_HEIGHT
+            } else {
+                0
+            };
+            let loc = Point::from((geometry.loc.x, geometry.loc.y + offset));
+            self.drop_placement(tile.visible());
+            self.space.map_element(tile.visible().clone(), loc, false);
+        }
+    }
+
+    /// Let go of the window; it gets its final size and its dialogs follow
+    pub fn end_floating_grab(&mut self) {
+        let Some(grab) = self.floating_grab.take() else {
+            return;
+        };
+        self.cursor_image_status = CursorImageStatus::default_named();
+        if grab.wireframe {
+            let workspace = self.workspace_manager.active_workspace_mut();
+            if let Some(tile) = workspace
+                .floating_tiles()
+                .into_iter()
+                .find(|tile| tile.windows.contains(&grab.window))
+            {
+                workspace.set_floating_geometry(&tile, grab.geometry);
+            }
+        }
+        self.relayout();
+    }
+
+    /// Put the focused window, if it floats, in the middle of the area
+    /// below the bar, shrinking it to fit if need be
+    pub fn center_window(&mut self) {
+        let workspace = self.workspace_manager.active_workspace();
+        let Some(focused) = workspace
+            .focused_window()
+            .map(|window| workspace.dialog_root(window).clone())
+        else {
+            return;
+        };
+        if !workspace.is_floating(&focused) {
+            println!(
+                "'{}' doesn't float, nothing to center",
+                window_app_id(&focused)
+            );
+            return;
+        }
+        let tiles = workspace.floating_tiles();
+        let Some(index) = tiles
+            .iter()
+            .position(|tile| tile.windows.contains(&focused))
+        else {
+            return;
+        };
+
+        let output_size = self.layout_size();
+        let bar_height = self.bar_height();
+        let usable = (output_size.w, output_size.h - bar_height);
+        let current = self.floating_geometry(index, &tiles[index], output_size);
+        let size = (current.size.w.min(usable.0), current.size.h.min(usable.1));
+        let geometry = Rectangle::from_loc_and_size(
+            (
+                (usable.0 - size.0) / 2,
+                bar_height + (usable.1 - size.1) / 2,
+            ),
+            size,
+        );
+
+        println!("🎯 Centered '{}'", window_app_id(&focused));
+        self.workspace_manager
+            .active_workspace_mut()
+            .set_floating_geometry(&tiles[index], geometry);
+        self.relayout();
+    }
+
+    /// The outline of a window being dragged in wireframe mode
+    pub fn render_drag_outline(&self) -> Vec<BarElement> {
+        let Some(grab) = self.floating_grab.as_ref().filter(|grab| grab.wireframe) else {
+            return Vec::new();
+        };
+
+        let style = |property: &str, default: [f32; 4]| {
+            self.stylesheet
+                .as_ref()
+                .and_then(|ss| ss.get_color(".drag-outline", property))
+                .map(|color| color.to_rgba_f32())
+                .unwrap_or(default)
+        };
+        let border = style("border-color", DEFAULT_OUTLINE_COLOR);
+        let rect = |x: i32, y: i32, w: i32, h: i32, color: [f32; 4]| BarElement::Rectangle {
+            geometry: Rectangle::from_loc_and_size((x, y), (w, h)),
+            color,
+        };
+
+        let (loc, size) = (grab.geometry.loc, grab.geometry.size);
+        let (x, y, w, h) = (loc.x, loc.y, size.w, size.h);
+        let b = OUTLINE_WIDTH;
+        vec![
+            rect(x, y, w, h, style("background-color", DEFAULT_OUTLINE_FILL)),
+            rect(x, y, w, b, border),
+            rect(x, y + h - b, w, b, border),
+            rect(x, y + b, b, h - 2 * b, border),
+            rect(x + w - b, y + b, b, h - 2 * b, border),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resized() {
+        let initial = Rectangle::from_loc_and_size((100, 100), (400, 300));
+
+        // The bottom-right corner follows the pointer
+        assert_eq!(
+            resized(initial, (true, true), (50, -20).into()),
+            Rectangle::from_loc_and_size((100, 100), (450, 280))
+        );
+        // The top-left corner moves while the bottom-right stays put
+        assert_eq!(
+            resized(initial, (false, false), (50, -20).into()),
+            Rectangle::from_loc_and_size((150, 80), (350, 320))
+        );
+        // Windows don't shrink below the minimum, nor move when they can't
+        assert_eq!(
+            resized(initial, (false, true), (1000, -1000).into()),
+            Rectangle::from_loc_and_size((380, 100), (120, 80))
+        );
+    }
+
+    #[test]
+    fn test_new_floating_geometry() {
+        let area = Rectangle::from_loc_and_size((0, 30), (1920, 1050));
+        let size = Size::from((800, 600));
+
+        assert_eq!(
+            new_floating_geometry(3, size, area, true),
+            Rectangle::from_loc_and_size((560, 255), (800, 600))
+        );
+        assert_eq!(
+            new_floating_geometry(2, size, area, false),
+            Rectangle::from_loc_and_size((60, 90), (800, 600))
+        );
+        // 450 pixels of room below fit 15 steps before starting over
+        assert_eq!(
+            new_floating_geometry(16, size, area, false),
+            Rectangle::from_loc_and_size((30, 60), (800, 600))
+        );
+        // Too big for the area: shrunk to it
+        assert_eq!(
+            new_floating_geometry(0, (4000, 600).into(), area, false),
+            Rectangle::from_loc_and_size((0, 30), (1920, 600))
+        );
+    }
+
+    #[test]
+    fn test_keep_visible() {
+        let bounds = Rectangle::from_loc_and_size((0, 0), (1920, 1080));
+        let window = |x, y| Rectangle::from_loc_and_size((x, y), (400, 300));
+
+        assert_eq!(keep_visible(window(100, 100), bounds, 48), window(100, 100));
+        // A strip stays on the output past each edge
+        assert_eq!(
+            keep_visible(window(-1000, 2000), bounds, 48),
+            window(-352, 1032)
+        );
+        assert_eq!(
+            keep_visible(window(1900, -500), bounds, 48),
+            window(1872, -252)
+        );
+        // Small windows stay on the output entirely
+        let small = Rectangle::from_loc_and_size((-50, 0), (20, 20));
+        assert_eq!(keep_visible(small, bounds, 48).loc, (0, 0).into());
+    }
+}
@@ -0,0 +1,139 @@
+// Stacking order of floating windows
+//
+// Each workspace keeps its floating tiles in a stack, bottom to top. New
+// windows open on top, clicking a window raises it (unless
+// `<click-to-raise>` in `<floating>` is false), and the raise, lower and
+// raise-or-lower actions move the focused window to the top or bottom.
+// Dialogs stay right above their parent. In other layouts, windows floated
+// on their own are stacked the same way above the tiles, and a maximized or
+// fullscreen window goes above them. Windows set to stay on top from the
+// window menu are above all the others, in any layout. Windows are drawn,
+// and found under the pointer, in stack order.
+
+use smithay::desktop::Window;
+
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+impl WebWMCompositor {
+    /// Mapped windows from bottom to top: tiles, floating windows in the
+    /// order of the floating stack, a maximized or fullscreen window, and
+    /// always-on-top windows last
+    pub fn stacked_elements(&self) -> Vec<&Window> {
+        let mut elements: Vec<&Window> = self.space.elements().collect();
+        let workspace = self.workspace_manager.active_workspace();
+        // Stable, so dialogs stay above their parent
+        elements.sort_by_key(|window| {
+            let root = Some(workspace.dialog_root(window));
+            if root == workspace.fullscreen_window.as_ref()
+                || root == workspace.maximized_window.as_ref()
+            {
+                (2, None)
+            } else if workspace.is_floating(window) {
+                (1, workspace.stack_index(window))
+            } else {
+                (0, None)
+            }
+        });
+        elements.sort_by_key(|window| workspace.is_on_top(window));
+        elements
+    }
+
+    /// Add floating tiles that aren't in the floating stack on top, and
+    /// put the mapped windows in stack order
+    pub fn sync_floating_stack(&mut self) {
+        let workspace = self.workspace_manager.active_workspace_mut();
+        for tile in workspace.floating_tiles() {
+            if workspace.stack_index(&tile.windows[0]).is_none() {
+                workspace.floating_stack.push(tile.windows[0].clone());
+            }
+        }
+
+        let stacked: Vec<Window> = self.stacked_elements().into_iter().cloned().collect();
+        for window in &stacked {
+            self.space.raise_element(window, false);
+        }
+    }
+
+    /// Put `window` on top of the other floating windows, or at the bottom
+    fn restack_window(&mut self, window: &Window, top: bool) {
+        let workspace = self.workspace_manager.active_workspace_mut();
+        if !workspace.windows.contains(window) || !workspace.is_floating(window) {
+            return;
+        }
+        let before = workspace.floating_stack.clone();
+        workspace.restack(window, top);
+        if workspace.floating_stack == before {
+            return;
+        }
+        println!(
+            "{} '{}'",
+            if top {
+                "⬆️  Raised"
+            } else {
+                "⬇️  Lowered"
+            },
+            window_app_id(window)
+        );
+        self.sync_floating_stack();
+    }
+
+    pub fn raise_window(&mut self, window: &Window) {
+        self.restack_window(window, true);
+    }
+
+    pub fn lower_window(&mut self, window: &Window) {
+        self.restack_window(window, false);
+    }
+
+    pub fn raise_focused(&mut self) {
+        if let Some(window) = self.workspace_manager.focused_window().cloned() {
+            self.raise_window(&window);
+        }
+    }
+
+    pub fn lower_focused(&mut self) {
+        if let Some(window) = self.workspace_manager.focused_window().cloned() {
+            self.lower_window(&window);
+        }
+    }
+
+    /// Lower the focused window if it's on top, raise it otherwise
+    pub fn raise_or_lower_focused(&mut self) {
+        let Some(window) = self.workspace_manager.focused_window().cloned() else {
+            return;
+        };
+        let workspace = self.workspace_manager.active_workspace();
+        let on_top = workspace
+            .stack_index(&window)
+            .is_some_and(|index| index + 1 == workspace.floating_stack.len());
+        self.restack_window(&window, !on_top);
+    }
+
+    /// Keep `window` above the other windows, or stop doing so
+    pub fn toggle_always_on_top(&mut self, window: &Window) {
+        let workspace = self.workspace_manager.active_workspace_mut();
+        if !workspace.windows.contains(window) {
+            return;
+        }
+        let on_top = !workspace.always_on_top.contains(window);
+        workspace.set_on_top(window, on_top);
+        println!(
+            "📌 '{}' {} on top",
+            window_app_id(window),
+            if on_top { "kept" } else { "no longer kept" }
+        );
+        self.sync_floating_stack();
+    }
+
+    /// Raise a window that was clicked, if click-to-raise is on
+    pub fn click_raise(&mut self, window: &Window) {
+        let enabled = self
+            .config
+            .desktop
+            .as_ref()
+            .is_none_or(|desktop| desktop.layout.click_to_raise);
+        if enabled {
+            self.raise_window(window);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+// Floating windows
+//
+// On workspaces with a tiling layout, single windows can float over the
+// tiles. The toggle-floating action (Super+f) and the window menu's Float
+// item take a window out of its tile or put it back, and window rules with
+// `floating="true"` float an app's windows once their app_id is known.
+// Floating windows go where they were last put, or where new windows on
+// floating workspaces go, are stacked above the tiles and move and resize
+// with Super+drag like on floating workspaces. Dialogs float with their
+// parent. On floating workspaces every window floats already.
+
+use smithay::desktop::Window;
+
+use crate::compositor::workspace::LayoutMode;
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+impl WebWMCompositor {
+    /// Float `window` over the tiles of its workspace, or tile it again
+    pub fn set_window_floating(&mut self, window: &Window, floating: bool) {
+        let Some(id) = self.workspace_manager.find_window_workspace(window) else {
+            return;
+        };
+        let Some(workspace) = self.workspace_manager.get_workspace_mut(id) else {
+            return;
+        };
+        if workspace.layout_mode == LayoutMode::Floating
+            || workspace.is_floating(window) == floating
+        {
+            return;
+        }
+        workspace.set_floating(window, floating);
+        println!(
+            "🪟 '{}' {}",
+            window_app_id(window),
+            if floating { "floats" } else { "is tiled again" }
+        );
+
+        if id == self.workspace_manager.active_workspace_id() {
+            self.relayout();
+        }
+    }
+
+    pub fn toggle_window_floating(&mut self, window: &Window) {
+        let workspace = self.workspace_manager.active_workspace();
+        if !workspace.windows.contains(window) {
+            return;
+        }
+        if workspace.layout_mode == LayoutMode::Floating {
+            println!("Every window floats on workspace {}", workspace.id);
+            return;
+        }
+        let floating = !workspace.is_floating(window);
+        self.set_window_floating(window, floating);
+    }
+
+    pub fn toggle_focused_floating(&mut self) {
+        if let Some(window) = self.workspace_manager.focused_window().cloned() {
+            self.toggle_window_floating(&window);
+        }
+    }
+
+    /// Float or tile a window whose app_id is now known as its rules say.
+    /// Dialogs go with their parent instead.
+    pub fn apply_floating_rule(&mut self, window: &Window) {
+        let dialog = self
+            .workspace_manager
+            .find_window_workspace(window)
+            .and_then(|id| self.workspace_manager.get_workspace(id))
+            .is_some_and(|workspace| workspace.dialog_parent(window).is_some());
+        if dialog {
+            return;
+        }
+        if let Some(floating) = self.window_rules_for(window).find_map(|rule| rule.floating) {
+            self.set_window_floating(window, floating);
+        }
+    }
+}
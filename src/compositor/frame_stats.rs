@@ -0,0 +1,284 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Frame pacing statistics for a single output
+#[derive(Debug, Clone)]
+pub struct OutputFrameStats {
+    /// Time between two vblanks at the current refresh rate
+    pub frame_interval: Duration,
+    pub frames: u64,
+    pub missed_vblanks: u64,
+    pub average_latency: Duration,
+    pub longest_stall: Duration,
+    last_present: Option<Instant>,
+}
+
+impl OutputFrameStats {
+    pub fn new(refresh_mhz: i32) -> Self {
+        Self {
+            frame_interval: interval_from_refresh(refresh_mhz),
+            frames: 0,
+            missed_vblanks: 0,
+            average_latency: Duration::ZERO,
+            longest_stall: Duration::ZERO,
+            last_present: None,
+        }
+    }
+
+    /// Record a presented frame. `due` is how soon after the previous one
+    /// it was due, None if nothing was pending then, so the gap is idle
+    /// time rather than a stall. Returns the number of vblanks missed.
+    pub fn record_present(
+        &mut self,
+        now: Instant,
+        latency: Duration,
+        due: Option<Duration>,
+    ) -> u64 {
+        self.frames += 1;
+
+        if self.frames == 1 {
+            self.average_latency = latency;
+        } else {
+            self.average_latency = (self.average_latency * 15 + latency) / 16;
+        }
+
+        let mut missed = 0;
+        if let (Some(last), Some(due)) = (self.last_present, due) {
+            let gap = now.saturating_duration_since(last);
+            self.longest_stall = self.longest_stall.max(gap);
+
+            // A frame rate cap below the refresh rate skips vblanks on
+            // purpose. Allow half a frame of jitter before counting one as
+            // missed.
+            let interval = self.frame_interval.max(due).as_secs_f64();
+            if interval > 0.0 {
+                let elapsed_vblanks = (gap.as_secs_f64() / interval + 0.5).floor() as u64;
+                missed = elapsed_vblanks.saturating_sub(1);
+            }
+        }
+
+        self.missed_vblanks += missed;
+        self.last_present = Some(now);
+        missed
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "refresh_interval_us": self.frame_interval.as_micros() as u64,
+            "frames": self.frames,
+            "missed_vblanks": self.missed_vblanks,
+            "avg_latency_us": self.average_latency.as_micros() as u64,
+            "longest_stall_us": self.longest_stall.as_micros() as u64,
+        })
+    }
+}
+
+/// Frame pacing across all outputs, exposed via IPC `frame-stats`
+pub struct FramePacing {
+    pub outputs: HashMap<String, OutputFrameStats>,
+    pub warning_threshold: Duration,
+    /// Shortest time between frames the scheduler allows, zero uncapped
+    pub min_interval: Duration,
+    /// Damage was pending when the last frame was drawn, so the next one
+    /// is due as soon as the scheduler allows
+    due: bool,
+}
+
+impl FramePacing {
+    pub fn new(warning_threshold_ms: u32) -> Self {
+        Self {
+            outputs: HashMap::new(),
+            warning_threshold: Duration::from_millis(warning_threshold_ms as u64),
+            min_interval: Duration::ZERO,
+            due: false,
+        }
+    }
+
+    /// Frames are scheduled at most `max_fps` times a second, 0 uncapped
+    pub fn set_max_fps(&mut self, max_fps: u32) {
+        self.min_interval = interval_from_fps(max_fps);
+    }
+
+    /// A frame was drawn, `idle` if nothing needed drawing. After an idle
+    /// frame the next one only comes once something changes.
+    pub fn frame_done(&mut self, idle: bool) {
+        self.due = !idle;
+    }
+
+    pub fn record_present(&mut self, output_name: &str, refresh_mhz: i32, latency: Duration) {
+        let stats = self
+            .outputs
+            .entry(output_name.to_string())
+            .or_insert_with(|| OutputFrameStats::new(refresh_mhz));
+
+        // Mode changes alter the deadline
+        let interval = interval_from_refresh(refresh_mhz);
+        if stats.frame_interval != interval {
+            stats.frame_interval = interval;
+        }
+
+        let due = self.due.then_some(self.min_interval);
+        let missed = stats.record_present(Instant::now(), latency, due);
+
+        if !self.warning_threshold.is_zero() && latency > self.warning_threshold {
+            eprintln!(
+                "⚠️  Output {}: frame took {:.1}ms ({} vblank(s) missed)",
+                output_name,
+                latency.as_secs_f64() * 1000.0,
+                missed
+            );
+        }
+    }
+
+    pub fn report(&self) -> Value {
+        let mut outputs = serde_json::Map::new();
+        for (name, stats) in &self.outputs {
+            outputs.insert(name.clone(), stats.to_json());
+        }
+
+        json!({
+            "warning_threshold_ms": self.warning_threshold.as_millis() as u64,
+            "outputs": outputs,
+        })
+    }
+}
+
+/// Schedules frames on a fixed cadence, separate from input dispatch.
+/// Drops to a slow idle tick while there is nothing to draw.
+pub struct FrameScheduler {
+    interval: Duration,
+    idle_interval: Duration,
+    next_frame: Instant,
+}
+
+impl FrameScheduler {
+    /// `max_fps` of 0 renders as fast as possible
+    pub fn new(max_fps: u32, idle_fps: u32, now: Instant) -> Self {
+        Self {
+            interval: interval_from_fps(max_fps),
+            idle_interval: interval_from_fps(idle_fps.max(1)),
+            next_frame: now,
+        }
+    }
+
+    pub fn next_frame(&self) -> Instant {
+        self.next_frame
+    }
+
+    pub fn is_due(&self, now: Instant) -> bool {
+        now >= self.next_frame
+    }
+
+    /// Something changed while idling: render now rather than at the idle tick
+    pub fn wake(&mut self, now: Instant) {
+        if self.next_frame > now + self.interval {
+            self.next_frame = now;
+        }
+    }
+
+    /// Advance past a rendered frame and return the next deadline. Slots that
+    /// were missed are skipped so a slow frame doesn't cause a burst of renders
+    /// that would starve input.
+    pub fn frame_done(&mut self, now: Instant, idle: bool) -> Instant {
+        let interval = if idle {
+            self.idle_interval
+        } else {
+            self.interval
+        };
+
+        if interval.is_zero() {
+            self.next_frame = now;
+            return now;
+        }
+
+        self.next_frame += interval;
+        if self.next_frame <= now {
+            let behind = (now - self.next_frame).as_nanos() / interval.as_nanos();
+            self.next_frame += interval * (behind as u32 + 1);
+        }
+
+        self.next_frame
+    }
+}
+
+fn interval_from_fps(fps: u32) -> Duration {
+    if fps == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_secs_f64(1.0 / fps as f64)
+}
+
+fn interval_from_refresh(refresh_mhz: i32) -> Duration {
+    if refresh_mhz <= 0 {
+        // Unknown refresh rate, assume 60Hz
+        return Duration::from_micros(16_667);
+    }
+    Duration::from_secs_f64(1000.0 / refresh_mhz as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missed_vblanks() {
+        let mut stats = OutputFrameStats::new(60_000);
+        let start = Instant::now();
+        let latency = Duration::from_millis(2);
+
+        let uncapped = Some(Duration::ZERO);
+
+        assert_eq!(stats.record_present(start, latency, uncapped), 0);
+
+        // On time
+        let t1 = start + Duration::from_micros(16_700);
+        assert_eq!(stats.record_present(t1, latency, uncapped), 0);
+
+        // Three frame intervals later: two vblanks were missed
+        let t2 = t1 + Duration::from_micros(50_000);
+        assert_eq!(stats.record_present(t2, latency, uncapped), 2);
+
+        assert_eq!(stats.missed_vblanks, 2);
+        assert_eq!(stats.frames, 3);
+        assert_eq!(stats.longest_stall, Duration::from_micros(50_000));
+
+        // A second at the idle tick with nothing pending isn't a stall
+        let t3 = t2 + Duration::from_secs(1);
+        assert_eq!(stats.record_present(t3, latency, None), 0);
+        assert_eq!(stats.longest_stall, Duration::from_micros(50_000));
+
+        // Capped at 30fps, every other vblank is skipped on purpose
+        let t4 = t3 + Duration::from_micros(33_400);
+        let capped = Some(interval_from_fps(30));
+        assert_eq!(stats.record_present(t4, latency, capped), 0);
+        assert_eq!(stats.missed_vblanks, 2);
+    }
+
+    #[test]
+    fn test_frame_scheduler() {
+        let start = Instant::now();
+        let mut scheduler = FrameScheduler::new(50, 1, start);
+        assert!(scheduler.is_due(start));
+
+        // On time: next slot is one interval later
+        let next = scheduler.frame_done(start + Duration::from_millis(5), false);
+        assert_eq!(next, start + Duration::from_millis(20));
+
+        // A 50ms frame skips the slots at 40ms and 60ms
+        let next = scheduler.frame_done(start + Duration::from_millis(70), false);
+        assert_eq!(next, start + Duration::from_millis(80));
+
+        // Idle: the next frame is a second away until something wakes us
+        let idle_at = start + Duration::from_millis(85);
+        let next = scheduler.frame_done(idle_at, true);
+        assert_eq!(next, start + Duration::from_millis(1080));
+        assert!(!scheduler.is_due(idle_at));
+        scheduler.wake(idle_at);
+        assert!(scheduler.is_due(idle_at));
+
+        let mut uncapped = FrameScheduler::new(0, 1, start);
+        let now = start + Duration::from_millis(3);
+        assert_eq!(uncapped.frame_done(now, false), now);
+    }
+}
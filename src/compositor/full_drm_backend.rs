@@ -1,4 +1,14 @@
-use crate::compositor::{renderer::WebWMRenderer, WebWMCompositor};
+use crate::compositor::{
+    bar::BarElement,
+    color::ColorTransform,
+    cursor_theme::{CursorFrame, CursorSprite},
+    output_format::{self, FormatCapabilities, OutputFormat, SDR_REFERENCE_WHITE},
+    planes::{flip_mode, FlipMode, FrameDamage, PlaneCapabilities, PlanePlanner},
+    profiler::RenderStage,
+    renderer::WebWMRenderer,
+    scale::to_output_pixels,
+    window_app_id, WebWMCompositor,
+};
 use smithay::{
     backend::{renderer::gles::GlesRenderer, session::libseat::LibSeatSession},
     output::{Mode, Output, PhysicalProperties, Scale, Subpixel},
@@ -50,6 +60,27 @@ pub struct DrmSurface {
     pub output: Output,
     pub renderer: Option<GlesRenderer>,
     pub egl_display: Option<smithay::backend::egl::EGLDisplay>,
+    pub planes: PlanePlanner,
+    pub format_caps: FormatCapabilities,
+    pub format: OutputFormat,
+    pub last_frame: LastFrameState,
+    /// Entries in the CRTC's gamma LUT, 0 without one
+    pub gamma_size: u32,
+    /// The gamma table last applied, None for the default
+    pub gamma: Option<Vec<u16>>,
+    /// Whether the device can flip without waiting for vblank
+    pub async_page_flip: bool,
+    pub flip_mode: FlipMode,
+}
+
+/// What was presented on a surface last frame, used to derive damage
+#[derive(Debug, Clone, Default)]
+pub struct LastFrameState {
+    pub scene: Vec<Rectangle<i32, Physical>>,
+    pub bar_elements: Vec<BarElement>,
+    pub pointer: (i32, i32),
+    pub cursor_visible: bool,
+    pub scale: f64,
 }
 
 pub struct FullWebWMBackend {
@@ -66,6 +97,8 @@ pub struct FullWebWMBackend {
 pub struct CursorState {
     pub position: (i32, i32),
     pub visible: bool,
+    /// Themed cursor image on the cursor planes
+    pub image: Option<CursorFrame>,
 }
 
 impl FullWebWMBackend {
@@ -135,6 +168,7 @@ impl FullWebWMBackend {
             cursor_state: CursorState {
                 position: (0, 0),
                 visible: true, // Show cursor by default
+                image: None,
             },
             renderer: WebWMRenderer::new(),
         })
@@ -174,10 +208,37 @@ impl FullWebWMBackend {
             mode.refresh / 1000
         );
 
+        // Overlay and cursor planes aren't implemented: this backend binds
+        // no CRTC, so there are none to assign and the bar and cursor are
+        // always composited. The planner only tells changed frames from
+        // idle ones here.
+        let planes = PlanePlanner::new(PlaneCapabilities::default());
+        println!("✓ No hardware planes: bar and cursor are composited");
+
+        // 10-bit and HDR scanout aren't implemented either: with no bound
+        // connector there's no "max bpc", plane format list or
+        // HDR_OUTPUT_METADATA to read, so only 8-bit SDR is offered.
+        let format_caps = FormatCapabilities {
+            max_bpc: 8,
+            ..FormatCapabilities::default()
+        };
+
         let mut surface = DrmSurface {
             output,
             renderer: None,
             egl_display: None,
+            planes,
+            format_caps,
+            format: OutputFormat::default(),
+            last_frame: LastFrameState::default(),
+            // GAMMA_LUT_SIZE of the CRTC once one is bound; until then
+            // there is no LUT to apply, so night light stays off
+            gamma_size: 0,
+            gamma: None,
+            // DRM_CAP_ASYNC_PAGE_FLIP of the device once one is bound;
+            // flips wait for vblank until then
+            async_page_flip: false,
+            flip_mode: FlipMode::Vsync,
         };
 
         // Initialize EGL renderer for this surface
@@ -203,16 +264,232 @@ impl FullWebWMBackend {
         Ok(())
     }
 
+    /// Negotiate scanout format and HDR for each surface from `<outputs>`.
+    /// HDR outputs get a PQ color transform in place of any ICC profile.
+    pub fn configure_outputs(&mut self, compositor: &mut WebWMCompositor) {
+        let outputs = compositor
+            .config
+            .desktop
+            .as_ref()
+            .map(|desktop| desktop.outputs.clone())
+            .unwrap_or_default();
+
+        for surface in &mut self.surfaces {
+            let name = surface.output.name();
+            let config = outputs.iter().find(|output| output.name == name);
+            let (format, warnings) = output_format::negotiate(config, &surface.format_caps);
+            for warning in warnings {
+                eprintln!("⚠️  {}", warning);
+            }
+
+            println!(
+                "✓ {}: {:?}, {} bpc{}",
+                name,
+                format.fourcc,
+                format.bpc,
+                if format.hdr.is_some() { ", HDR" } else { "" }
+            );
+
+            if let Some(metadata) = format.hdr {
+                if config.is_some_and(|output| output.icc_profile.is_some()) {
+                    eprintln!("⚠️  {}: ICC profile ignored while HDR is on", name);
+                }
+                // Committed with the next modeset once the connector is bound
+                let blob = metadata.to_blob();
+                println!("  🌈 HDR_OUTPUT_METADATA: {} bytes, PQ/BT.2020", blob.len());
+                compositor
+                    .color_transforms
+                    .insert(name, ColorTransform::hdr(SDR_REFERENCE_WHITE));
+            }
+
+            surface.format = format;
+            if surface.gamma_size > 0 {
+                compositor.gamma.sizes.insert(name, surface.gamma_size);
+            }
+        }
+    }
+
     pub fn render_frame(&mut self, compositor: &mut WebWMCompositor) -> Result<(), DrmError> {
         let frame_count = self.frame_count.fetch_add(1, Ordering::SeqCst) + 1;
 
         println!("🎨 Rendering {} DRM surfaces", self.surfaces.len());
 
+        // Get windows to render; zoomed to a window, that's the only one
+        let stage_start = Instant::now();
+        let zoom = compositor.output_zoom();
+        let windows: Vec<_> = compositor
+            .stacked_elements()
+            .into_iter()
+            .filter(|window| zoom.is_none() || compositor.zoomed_window.as_ref() == Some(window))
+            .filter_map(|window| {
+                let location = compositor.space.element_location(window)?;
+                let geometry = window.geometry();
+                let render_location = location + geometry.loc;
+
+                Some((
+                    window,
+                    Rectangle::from_loc_and_size(render_location, geometry.size),
+                ))
+            })
+            .collect();
+
+        // Popups are drawn right above their window
+        let scene: Vec<_> = windows
+            .iter()
+            .flat_map(|(window, geometry)| {
+                std::iter::once(*geometry).chain(compositor.popup_geometries(window))
+            })
+            .map(|geometry| zoom.map_or(geometry, |zoom| zoom.apply(geometry)))
+            .collect();
+        let window_count = windows.len();
+        compositor
+            .profiler
+            .record(RenderStage::ElementCollection, stage_start.elapsed());
+
+        // Get bar elements; overlays are drawn on the output windows are
+        // laid out on, other outputs only get their own bars
+        let stage_start = Instant::now();
+        let overlays = if zoom.is_some() {
+            Vec::new()
+        } else {
+            compositor.render_bar_elements()
+        };
+        let primary_output = compositor.primary_output_name();
+        compositor
+            .profiler
+            .record(RenderStage::BarRasterization, stage_start.elapsed());
+        let backdrops = compositor.backdrops();
+        let pointer = compositor.pointer_location();
+        let pointer = zoom.map_or(pointer, |zoom| zoom.apply_point(pointer));
+        let pointer = (pointer.x as i32, pointer.y as i32);
+        let dragging = compositor.drag_icon().is_some();
+        let cursor_visible = compositor.cursor_visible(Instant::now());
+        if cursor_visible != self.cursor_state.visible {
+            println!(
+                "🖱️  Cursor {}",
+                if cursor_visible { "shown" } else { "hidden" }
+            );
+            self.cursor_state.visible = cursor_visible;
+        }
+
+        // Themed cursors go to the cursor plane; client cursor surfaces are
+        // composited like drag icons
+        let sprite = compositor.cursor_sprite(Instant::now());
+        let image = match sprite {
+            Some(CursorSprite::Themed(ref frame)) => Some(frame.clone()),
+            _ => None,
+        };
+        let image_changed = image != self.cursor_state.image;
+        if image_changed {
+            // Uploaded to the cursor plane's buffer once the CRTC is bound
+            if let Some(ref frame) = image {
+                println!(
+                    "🖱️  Cursor image {}x{}, hotspot {:?}",
+                    frame.width, frame.height, frame.hotspot
+                );
+            }
+            self.cursor_state.image = image;
+        }
+        let composited_cursor = matches!(sprite, Some(CursorSprite::Surface(..)));
+
+        // A fullscreen window with a `direct-scanout` rule is the whole
+        // scene, unless something composited is drawn over it
+        let scanout_window = compositor
+            .direct_scanout_window()
+            .filter(|_| !dragging && !composited_cursor);
+        let tearing_window = compositor.tearing_window();
+
         // Render each surface
         let len = self.surfaces.len();
         for i in 0..len {
             // Get surface and output size
+            let output_name = self.surfaces[i].output.name();
             let output_size = self.surfaces[i].output.current_mode().unwrap().size;
+            let scale = self.surfaces[i].output.current_scale().fractional_scale();
+            let output_scene: Vec<_> = scene
+                .iter()
+                .map(|geometry| to_output_pixels(*geometry, scale))
+                .collect();
+
+            let bar_elements =
+                if zoom.is_some() || primary_output.as_deref() == Some(output_name.as_str()) {
+                    overlays.clone()
+                } else {
+                    compositor.render_output_bars(Some(&output_name))
+                };
+
+            // Gamma is a CRTC property, independent of what is drawn
+            let surface = &mut self.surfaces[i];
+            let gamma = compositor.gamma_ramp_for(&surface.output.name());
+            if gamma != surface.gamma {
+                // Committed as the GAMMA_LUT blob once the CRTC is bound
+                match gamma {
+                    Some(ref ramp) => println!(
+                        "  🌈 {}: gamma LUT of {} entries",
+                        surface.output.name(),
+                        ramp.len() / 3
+                    ),
+                    None => println!("  🌈 {}: default gamma", surface.output.name()),
+                }
+                surface.gamma = gamma;
+            }
+
+            // Work out which planes actually changed since the last frame
+            let damage = FrameDamage {
+                // Drag icons and cursor surfaces are composited, so they
+                // can't ride the cursor plane
+                scene: surface.last_frame.scene != output_scene
+                    || ((dragging || composited_cursor) && surface.last_frame.pointer != pointer),
+                bar: surface.last_frame.bar_elements != bar_elements
+                    || surface.last_frame.scale != scale,
+                cursor: surface.last_frame.pointer != pointer
+                    || surface.last_frame.cursor_visible != cursor_visible
+                    || image_changed,
+                direct_scanout: scanout_window.is_some(),
+            };
+            let plan = surface.planes.plan(damage);
+
+            // Passed as DRM_MODE_PAGE_FLIP_ASYNC once the CRTC is bound
+            surface.flip_mode = flip_mode(plan, tearing_window.is_some(), surface.async_page_flip);
+            surface.last_frame = LastFrameState {
+                scene: output_scene,
+                bar_elements: bar_elements.clone(),
+                pointer,
+                cursor_visible,
+                scale,
+            };
+
+            if frame_count % 60 == 0 {
+                if let (FlipMode::Async, Some(window)) = (surface.flip_mode, &tearing_window) {
+                    println!("  ⚡ Async page flip for '{}'", window_app_id(window));
+                }
+                if plan.scanout {
+                    // Its buffer is attached to the primary plane once the
+                    // CRTC is bound
+                    if let Some(ref window) = scanout_window {
+                        println!(
+                            "  📺 Scanning out '{}' directly, composite skipped",
+                            window_app_id(window)
+                        );
+                    }
+                } else if plan.is_idle() {
+                    println!("  💤 Nothing changed, skipping frame");
+                }
+            }
+
+            // Without planes nothing is committed besides the composite
+            if plan.is_idle() {
+                continue;
+            }
+
+            self.renderer
+                .set_color_transform(compositor.color_transform_for(&output_name).cloned());
+            self.renderer.set_output_scale(scale);
+            self.renderer.set_border_width(
+                compositor
+                    .workspace_length("border-width")
+                    .map(|w| w as i32),
+            );
 
             // Get renderer from the surface if available
             if let Some(ref mut renderer) = self.surfaces[i].renderer {
@@ -221,45 +498,29 @@ impl FullWebWMBackend {
                 if frame_count % 60 == 0 {
                     println!("  🖥️  GPU Rendering Operations:");
                     println!("    ✓ Clear screen: #1a1b26 (WebWM Dark)");
-                    println!("    📐 Surface: {}x{} @60Hz", output_size.w, output_size.h);
+                    println!(
+                        "    📐 Surface: {}x{} @60Hz, scale {}",
+                        output_size.w, output_size.h, scale
+                    );
                 }
 
-                // Get windows to render
-                let windows: Vec<_> = compositor
-                    .space
-                    .elements()
-                    .filter_map(|window| {
-                        let location = compositor.space.element_location(window)?;
-                        let geometry = window.geometry();
-                        let render_location = location + geometry.loc;
-
-                        Some((
-                            window,
-                            Rectangle::<i32, smithay::utils::Physical>::from_loc_and_size(
-                                (render_location.x, render_location.y),
-                                (geometry.size.w, geometry.size.h),
-                            ),
-                        ))
-                    })
-                    .collect();
-
-                // Get bar elements
-                let bar_elements = compositor.render_bar_elements();
-
                 // Use WebWMRenderer for actual rendering
                 // Note: This would require proper frame setup in a real implementation
                 // For now, we'll simulate the rendering
                 if frame_count % 60 == 0 {
-                    println!(
-                        "    🪟 Rendering {} windows with WebWM theme",
-                        windows.len()
-                    );
+                    println!("    🪟 Rendering {} windows with WebWM theme", window_count);
                     if !bar_elements.is_empty() {
                         println!(
                             "    📊 Rendering status bar with {} elements",
                             bar_elements.len()
                         );
                     }
+                    if !backdrops.is_empty() {
+                        println!("    🌫️  Blurring {} backdrop(s)", backdrops.len());
+                    }
+                    if dragging {
+                        println!("    🖐️  Drawing drag icon at {:?}", pointer);
+                    }
 
                     if frame_count == 60 {
                         println!("    🎨 Real GPU rendering with WebWM theme");
@@ -0,0 +1,124 @@
+// Fullscreen
+//
+// A window asking for fullscreen either covers the whole output or, in
+// "container" mode, only its own tile: the client is told it is fullscreen
+// (so a video player hides its controls) but keeps the size the layout gives
+// it. The mode comes from a window rule's `fullscreen` attribute, falling
+// back to `<layout><fullscreen mode="..."/></layout>`.
+
+use smithay::desktop::Window;
+use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
+use smithay::utils::{Logical, Size};
+
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FullscreenMode {
+    /// Cover the whole output, bar included
+    Output,
+    /// Stay inside the tile
+    Container,
+}
+
+impl FullscreenMode {
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "output" => Some(Self::Output),
+            "container" => Some(Self::Container),
+            _ => None,
+        }
+    }
+}
+
+impl WebWMCompositor {
+    pub fn fullscreen_mode_for(&self, window: &Window) -> FullscreenMode {
+        self.window_rules_for(window)
+            .find_map(|rule| rule.fullscreen.as_deref().and_then(FullscreenMode::parse))
+            .or_else(|| {
+                self.config
+                    .desktop
+                    .as_ref()
+                    .and_then(|desktop| FullscreenMode::parse(&desktop.layout.fullscreen_mode))
+            })
+            .unwrap_or(FullscreenMode::Output)
+    }
+
+    /// Whether `window` was made fullscreen, in either mode
+    pub fn is_fullscreen(&self, window: &Window) -> bool {
+        window.toplevel().is_some_and(|toplevel| {
+            toplevel
+                .with_pending_state(|state| state.states.contains(xdg_toplevel::State::Fullscreen))
+        })
+    }
+
+    pub fn set_fullscreen(&mut self, window: &Window, enabled: bool) {
+        let Some(toplevel) = window.toplevel() else {
+            return;
+        };
+        let mode = self.fullscreen_mode_for(window);
+
+        toplevel.with_pending_state(|state| {
+            if enabled {
+                state.states.set(xdg_toplevel::State::Fullscreen);
+            } else {
+                state.states.unset(xdg_toplevel::State::Fullscreen);
+            }
+        });
+
+        if let Some(workspace) = self
+            .workspace_manager
+            .find_window_workspace(window)
+            .and_then(|id| self.workspace_manager.get_workspace_mut(id))
+        {
+            if enabled && mode == FullscreenMode::Output {
+                workspace.fullscreen_window = Some(window.clone());
+            } else if workspace.fullscreen_window.as_ref() == Some(window) {
+                workspace.fullscreen_window = None;
+            }
+        }
+
+        println!(
+            "🖥️  Window '{}' {} fullscreen ({:?})",
+            window_app_id(window),
+            if enabled { "entered" } else { "left" },
+            mode
+        );
+
+        self.relayout();
+        // Windows on other workspaces aren't configured by the relayout
+        toplevel.send_pending_configure();
+    }
+
+    /// Whether an output-fullscreen window covers `output`. Windows are laid
+    /// out on the first output, so only that one can be covered.
+    pub fn fullscreen_on(&self, output: Option<&str>) -> bool {
+        self.workspace_manager
+            .active_workspace()
+            .fullscreen_window
+            .is_some()
+            && output.map_or(true, |output| {
+                self.primary_output_name().as_deref() == Some(output)
+            })
+    }
+
+    /// Put the active workspace's output-fullscreen window on top of the
+    /// tiled layout, covering the whole output
+    pub fn layout_fullscreen(&mut self, output_size: Size<i32, Logical>) {
+        let Some(window) = self
+            .workspace_manager
+            .active_workspace()
+            .fullscreen_window
+            .clone()
+        else {
+            return;
+        };
+
+        self.space.map_element(window.clone(), (0, 0), true);
+        if let Some(toplevel) = window.toplevel() {
+            toplevel.with_pending_state(|state| {
+                state.size = Some((output_size.w, output_size.h).into());
+            });
+            toplevel.send_configure();
+        }
+    }
+}
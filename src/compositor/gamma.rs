@@ -0,0 +1,350 @@
+// Gamma control and night light
+//
+// Outputs whose CRTC has a gamma LUT advertise its size, and privileged
+// clients (wlsunset, gammastep, calibration tools) can set the tables
+// through zwlr_gamma_control_manager_v1. Each output has at most one
+// control; when it is destroyed the output goes back to its default ramp.
+//
+// That default is the built-in night light when `<night-light>` is enabled
+// in desktop.xml: the white point follows the color temperature for the
+// local time of day, fading between the day and night temperatures over
+// `transition` minutes after sunset and sunrise. A client's ramp takes
+// precedence over it. The DRM backend applies the resulting LUT; backends
+// without one (winit, headless) report no gamma size, so controls fail.
+
+use smithay::output::Output;
+use smithay::reexports::wayland_protocols_wlr::gamma_control::v1::server::{
+    zwlr_gamma_control_manager_v1::{self, ZwlrGammaControlManagerV1},
+    zwlr_gamma_control_v1::{self, ZwlrGammaControlV1},
+};
+use smithay::reexports::wayland_server::{
+    backend::ClientId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New,
+};
+use std::collections::HashMap;
+use std::fs::File;
+use std::os::fd::OwnedFd;
+use std::os::unix::fs::FileExt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::compositor::WebWMCompositor;
+use crate::config::NightLightSettings;
+
+/// Color temperature that leaves colors untouched
+pub const NEUTRAL_TEMPERATURE: u32 = 6500;
+/// How often the night light temperature is recomputed
+pub const NIGHT_LIGHT_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default)]
+pub struct GammaState {
+    /// Gamma ramp size of every output that supports gamma tables
+    pub sizes: HashMap<String, u32>,
+    /// The control each output can have at most one of
+    controls: HashMap<String, ZwlrGammaControlV1>,
+    /// Ramps set by clients: red, green then blue
+    client_ramps: HashMap<String, Vec<u16>>,
+    /// Night light temperature in Kelvin, None while it is off
+    pub night_temperature: Option<u32>,
+}
+
+impl GammaState {
+    pub fn new(display_handle: &DisplayHandle) -> Self {
+        display_handle.create_global::<WebWMCompositor, ZwlrGammaControlManagerV1, _>(1, ());
+        Self::default()
+    }
+
+    /// Drop the control of `output` and the ramp its client set
+    fn release(&mut self, output: &str) {
+        self.controls.remove(output);
+        if self.client_ramps.remove(output).is_some() {
+            println!("🌈 {}: gamma control released", output);
+        }
+    }
+
+    fn is_active(&self, output: &str, control: &ZwlrGammaControlV1) -> bool {
+        self.controls.get(output) == Some(control)
+    }
+}
+
+/// Relative white point of a color temperature, (1, 1, 1) at 6500K. Uses
+/// Tanner Helland's fit of the blackbody curve.
+pub fn white_point(temperature: u32) -> [f64; 3] {
+    fn rgb(kelvin: f64) -> [f64; 3] {
+        let t = kelvin / 100.0;
+        let red = if t <= 66.0 {
+            255.0
+        } else {
+            329.698727446 * (t - 60.0).powf(-0.1332047592)
+        };
+        let green = if t <= 66.0 {
+            99.4708025861 * t.ln() - 161.1195681661
+        } else {
+            288.1221695283 * (t - 60.0).powf(-0.0755148492)
+        };
+        let blue = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.5177312231 * (t - 10.0).ln() - 305.0447927307
+        };
+        [red, green, blue].map(|c| c.clamp(0.0, 255.0) / 255.0)
+    }
+
+    let neutral = rgb(NEUTRAL_TEMPERATURE as f64);
+    let color = rgb(temperature.clamp(1000, 25000) as f64);
+    std::array::from_fn(|i| (color[i] / neutral[i]).min(1.0))
+}
+
+/// Linear red, green and blue ramps of `size` entries scaled to the white
+/// point of `temperature`
+pub fn temperature_ramp(size: u32, temperature: u32) -> Vec<u16> {
+    let white = white_point(temperature);
+    let last = size.saturating_sub(1).max(1) as f64;
+    white
+        .iter()
+        .flat_map(|channel| {
+            (0..size).map(move |i| (i as f64 / last * channel * 65535.0).round() as u16)
+        })
+        .collect()
+}
+
+/// Night light temperature `minutes` after local midnight
+pub fn night_temperature(settings: &NightLightSettings, minutes: u32) -> u32 {
+    let since = |start: u32| (minutes + 1440 - start % 1440) % 1440;
+    let (since_sunset, since_sunrise) = (since(settings.sunset), since(settings.sunrise));
+    let fade = |elapsed: u32| {
+        if settings.transition_minutes == 0 {
+            1.0
+        } else {
+            (elapsed as f64 / settings.transition_minutes as f64).min(1.0)
+        }
+    };
+
+    // How far into the night it is, from 0 (day) to 1 (night)
+    let night = if since_sunset < since_sunrise {
+        fade(since_sunset)
+    } else {
+        1.0 - fade(since_sunrise)
+    };
+
+    let day = settings.day_temperature as f64;
+    (day + (settings.night_temperature as f64 - day) * night).round() as u32
+}
+
+/// Minutes since local midnight, with the time zone rules of the C library;
+/// UTC if it can't tell
+fn local_minutes() -> u32 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as libc::time_t)
+        .unwrap_or(0);
+    // SAFETY: an all-zero tm is valid, and localtime_r only writes to it
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+        return (now / 60).rem_euclid(1440) as u32;
+    }
+    (tm.tm_hour * 60 + tm.tm_min) as u32
+}
+
+/// The table a client sent: three ramps of `size` 16-bit entries
+fn read_ramp(fd: OwnedFd, size: u32) -> Result<Vec<u16>, String> {
+    let file = File::from(fd);
+    let mut bytes = vec![0u8; size as usize * 3 * 2];
+    file.read_exact_at(&mut bytes, 0)
+        .map_err(|e| format!("Failed to read gamma table: {}", e))?;
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+        .collect())
+}
+
+impl WebWMCompositor {
+    /// Recompute the night light temperature for the current time
+    pub fn update_night_light(&mut self) {
+        let settings = self
+            .config
+            .desktop
+            .as_ref()
+            .map(|desktop| desktop.night_light.clone())
+            .unwrap_or_default();
+
+        let temperature = settings
+            .enabled
+            .then(|| night_temperature(&settings, local_minutes()));
+        if temperature != self.gamma.night_temperature {
+            match temperature {
+                Some(kelvin) => println!("🌙 Night light at {}K", kelvin),
+                None => println!("🌙 Night light off"),
+            }
+            self.gamma.night_temperature = temperature;
+        }
+    }
+
+    /// The gamma table `output` should have, or None for the hardware's
+    /// default
+    pub fn gamma_ramp_for(&self, output: &str) -> Option<Vec<u16>> {
+        if let Some(ramp) = self.gamma.client_ramps.get(output) {
+            return Some(ramp.clone());
+        }
+
+        let size = *self.gamma.sizes.get(output)?;
+        self.gamma
+            .night_temperature
+            .filter(|kelvin| *kelvin != NEUTRAL_TEMPERATURE)
+            .map(|kelvin| temperature_ramp(size, kelvin))
+    }
+}
+
+/// The output a control was created for and its ramp size; None for
+/// controls that failed right away
+pub struct GammaControlData {
+    output: Option<(String, u32)>,
+}
+
+impl GlobalDispatch<ZwlrGammaControlManagerV1, ()> for WebWMCompositor {
+    fn bind(
+        _state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrGammaControlManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl Dispatch<ZwlrGammaControlManagerV1, ()> for WebWMCompositor {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _manager: &ZwlrGammaControlManagerV1,
+        request: zwlr_gamma_control_manager_v1::Request,
+        _data: &(),
+        _handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let zwlr_gamma_control_manager_v1::Request::GetGammaControl { id, output } = request else {
+            return;
+        };
+
+        let name = Output::from_resource(&output).map(|output| output.name());
+        let available = name.and_then(|name| {
+            let size = *state.gamma.sizes.get(&name)?;
+            (!state.gamma.controls.contains_key(&name)).then_some((name, size))
+        });
+
+        match available {
+            Some((name, size)) => {
+                let control = data_init.init(
+                    id,
+                    GammaControlData {
+                        output: Some((name.clone(), size)),
+                    },
+                );
+                control.gamma_size(size);
+                println!("🌈 {}: gamma control granted ({} entries)", name, size);
+                state.gamma.controls.insert(name, control);
+            }
+            None => {
+                let control = data_init.init(id, GammaControlData { output: None });
+                control.failed();
+            }
+        }
+    }
+}
+
+impl Dispatch<ZwlrGammaControlV1, GammaControlData> for WebWMCompositor {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        control: &ZwlrGammaControlV1,
+        request: zwlr_gamma_control_v1::Request,
+        data: &GammaControlData,
+        _handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        let Some((ref output, size)) = data.output else {
+            return;
+        };
+        if !state.gamma.is_active(output, control) {
+            return;
+        }
+
+        match request {
+            zwlr_gamma_control_v1::Request::SetGamma { fd } => match read_ramp(fd, size) {
+                Ok(ramp) => {
+                    state.gamma.client_ramps.insert(output.clone(), ramp);
+                }
+                Err(e) => {
+                    eprintln!("⚠️  {}: {}", output, e);
+                    control.failed();
+                    state.gamma.release(output);
+                }
+            },
+            zwlr_gamma_control_v1::Request::Destroy => state.gamma.release(output),
+            _ => {}
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        control: &ZwlrGammaControlV1,
+        data: &GammaControlData,
+    ) {
+        if let Some((ref output, _)) = data.output {
+            if state.gamma.is_active(output, control) {
+                state.gamma.release(output);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> NightLightSettings {
+        NightLightSettings {
+            enabled: true,
+            day_temperature: 6500,
+            night_temperature: 4000,
+            sunset: 19 * 60,
+            sunrise: 7 * 60,
+            transition_minutes: 60,
+        }
+    }
+
+    #[test]
+    fn test_night_temperature() {
+        let settings = settings();
+        assert_eq!(night_temperature(&settings, 12 * 60), 6500);
+        assert_eq!(night_temperature(&settings, 19 * 60), 6500);
+        assert_eq!(night_temperature(&settings, 19 * 60 + 30), 5250);
+        assert_eq!(night_temperature(&settings, 23 * 60), 4000);
+        assert_eq!(night_temperature(&settings, 3 * 60), 4000);
+        assert_eq!(night_temperature(&settings, 7 * 60 + 30), 5250);
+        assert_eq!(night_temperature(&settings, 9 * 60), 6500);
+
+        let instant = NightLightSettings {
+            transition_minutes: 0,
+            ..settings
+        };
+        assert_eq!(night_temperature(&instant, 19 * 60), 4000);
+        assert_eq!(night_temperature(&instant, 7 * 60), 6500);
+    }
+
+    #[test]
+    fn test_temperature_ramp() {
+        assert_eq!(white_point(NEUTRAL_TEMPERATURE), [1.0, 1.0, 1.0]);
+
+        let ramp = temperature_ramp(256, 3000);
+        assert_eq!(ramp.len(), 768);
+        // Red stays at full range, blue is cut the most
+        assert_eq!(ramp[255], 65535);
+        assert!(ramp[511] < 65535);
+        assert!(ramp[767] < ramp[511]);
+        assert_eq!(ramp[256], 0);
+    }
+}
@@ -0,0 +1,325 @@
+// Grid layout
+//
+// A workspace whose `workspace[name="..."]` rule (or the plain `workspace`
+// rule) in style.css says `display: grid` lays out its tiles like a CSS
+// grid instead of side by side. `grid-template-columns` and
+// `grid-template-rows` take px, %, fr and auto tracks and `repeat()`, and
+// `grid-template-areas` names areas of cells. A window rule with
+// `grid-area="..."` puts its windows in that area. Other windows take the
+// named areas nobody asked for, in the order they first appear, then free
+// cells row by row; when the cells run out, rows of 1fr are added at the
+// bottom. Tracks are `gap` apart, like the other layouts' tiles.
+
+use smithay::utils::{Logical, Rectangle, Size};
+
+use crate::compositor::WebWMCompositor;
+use crate::config::{parse_grid_areas, parse_grid_tracks, GridTrack};
+
+/// The grid a workspace's stylesheet rule describes
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridTemplate {
+    pub columns: Vec<GridTrack>,
+    /// Explicit rows; more are added as windows need them
+    pub rows: Vec<GridTrack>,
+    /// Cell names, one Vec per row
+    pub areas: Vec<Vec<String>>,
+}
+
+/// Columns and rows a window spans, end exclusive
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    columns: (usize, usize),
+    rows: (usize, usize),
+}
+
+impl GridTemplate {
+    /// Without track lists the areas decide the number of tracks, and
+    /// without either there is one column
+    pub fn new(
+        columns: Option<Vec<GridTrack>>,
+        rows: Option<Vec<GridTrack>>,
+        areas: Vec<Vec<String>>,
+    ) -> Self {
+        let area_columns = areas.first().map_or(1, Vec::len);
+        Self {
+            columns: columns.unwrap_or_else(|| vec![GridTrack::Fr(1.0); area_columns]),
+            rows: rows.unwrap_or_else(|| vec![GridTrack::Fr(1.0); areas.len()]),
+            areas,
+        }
+    }
+
+    /// Named areas in the order they first appear, with the cells they
+    /// cover; areas outside the columns are left out
+    fn named_areas(&self) -> Vec<(String, Span)> {
+        let mut named: Vec<(String, Span)> = Vec::new();
+        for (row, names) in self.areas.iter().enumerate() {
+            for (column, name) in names.iter().enumerate() {
+                if name == "." || column >= self.columns.len() {
+                    continue;
+                }
+                match named.iter_mut().find(|(n, _)| n == name) {
+                    Some((_, span)) => {
+                        span.columns.0 = span.columns.0.min(column);
+                        span.columns.1 = span.columns.1.max(column + 1);
+                        span.rows.1 = span.rows.1.max(row + 1);
+                    }
+                    None => named.push((
+                        name.clone(),
+                        Span {
+                            columns: (column, column + 1),
+                            rows: (row, row + 1),
+                        },
+                    )),
+                }
+            }
+        }
+        named
+    }
+
+    /// Cells each of the windows goes in, given the area each asks for
+    fn place(&self, requested: &[Option<String>]) -> Vec<Span> {
+        let columns = self.columns.len().max(1);
+        let mut named = self.named_areas();
+        let mut occupied: Vec<Vec<bool>> = Vec::new();
+        let occupy = |occupied: &mut Vec<Vec<bool>>, span: Span| {
+            if occupied.len() < span.rows.1 {
+                occupied.resize(span.rows.1, vec![false; columns]);
+            }
+            for row in &mut occupied[span.rows.0..span.rows.1] {
+                for cell in &mut row[span.columns.0..span.columns.1] {
+                    *cell = true;
+                }
+            }
+        };
+
+        let mut spans: Vec<Option<Span>> = vec![None; requested.len()];
+        for (i, name) in requested.iter().enumerate() {
+            let Some(index) = name
+                .as_ref()
+                .and_then(|name| named.iter().position(|(n, _)| n == name))
+            else {
+                continue;
+            };
+            let (_, span) = named.remove(index);
+            occupy(&mut occupied, span);
+            spans[i] = Some(span);
+        }
+
+        let mut named = named.into_iter();
+        spans
+            .into_iter()
+            .map(|span| {
+                if let Some(span) = span {
+                    return span;
+                }
+                if let Some((_, span)) = named.next() {
+                    occupy(&mut occupied, span);
+                    return span;
+                }
+                // First free cell, row by row, past the end if need be
+                let free = (0..)
+                    .flat_map(|row| (0..columns).map(move |column| (row, column)))
+                    .find(|&(row, column)| {
+                        !occupied
+                            .get(row)
+                            .is_some_and(|cells: &Vec<bool>| cells[column])
+                    })
+                    .unwrap_or_default();
+                let span = Span {
+                    columns: (free.1, free.1 + 1),
+                    rows: (free.0, free.0 + 1),
+                };
+                occupy(&mut occupied, span);
+                span
+            })
+            .collect()
+    }
+}
+
+/// Offset and size of each track along `extent`, `gaps` apart
+fn track_sizes(tracks: &[GridTrack], extent: i32, gaps: i32) -> Vec<(i32, i32)> {
+    let available = (extent - gaps * (tracks.len() as i32 - 1)).max(0) as f32;
+    let fixed: f32 = tracks
+        .iter()
+        .map(|track| match track {
+            GridTrack::Px(px) => *px,
+            GridTrack::Percent(percent) => extent as f32 * percent / 100.0,
+            GridTrack::Fr(_) => 0.0,
+        })
+        .sum();
+    let free = (available - fixed).max(0.0);
+    let fractions: f32 = tracks
+        .iter()
+        .map(|track| match track {
+            GridTrack::Fr(fr) => *fr,
+            _ => 0.0,
+        })
+        .sum();
+
+    let mut offset = 0;
+    tracks
+        .iter()
+        .map(|track| {
+            let size = match track {
+                GridTrack::Px(px) => *px,
+                GridTrack::Percent(percent) => extent as f32 * percent / 100.0,
+                GridTrack::Fr(fr) => free * fr / fractions,
+            }
+            .floor() as i32;
+            let track = (offset, size);
+            offset += size + gaps;
+            track
+        })
+        .collect()
+}
+
+/// Where each of the windows goes in `area`, given the grid area each
+/// asks for
+pub fn grid_geometries(
+    template: &GridTemplate,
+    area: Rectangle<i32, Logical>,
+    gaps: i32,
+    requested: &[Option<String>],
+) -> Vec<Rectangle<i32, Logical>> {
+    let spans = template.place(requested);
+
+    let mut rows = template.rows.clone();
+    let needed = spans.iter().map(|span| span.rows.1).max().unwrap_or(0);
+    if rows.len() < needed {
+        rows.resize(needed, GridTrack::Fr(1.0));
+    }
+    let columns = if template.columns.is_empty() {
+        vec![GridTrack::Fr(1.0)]
+    } else {
+        template.columns.clone()
+    };
+
+    let column_sizes = track_sizes(&columns, area.size.w, gaps);
+    let row_sizes = track_sizes(&rows, area.size.h, gaps);
+    let extent = |tracks: &[(i32, i32)], (first, end): (usize, usize)| {
+        let (start, _) = tracks[first];
+        let (last, size) = tracks[end - 1];
+        (start, last + size - start)
+    };
+
+    spans
+        .into_iter()
+        .map(|span| {
+            let (x, w) = extent(&column_sizes, span.columns);
+            let (y, h) = extent(&row_sizes, span.rows);
+            Rectangle::from_loc_and_size((area.loc.x + x, area.loc.y + y), (w, h))
+        })
+        .collect()
+}
+
+impl WebWMCompositor {
+    /// The grid of the active workspace, if its stylesheet rule makes it
+    /// one. The `workspace[name="..."]` rule wins over plain `workspace`.
+    pub fn workspace_grid(&self) -> Option<GridTemplate> {
+        let stylesheet = self.stylesheet.as_ref()?;
+        let named = format!(
+            "workspace[name=\"{}\"]",
+            self.workspace_manager.active_workspace().name
+        );
+        let text = |property: &str| {
+            stylesheet
+                .get_text(&named, property)
+                .or_else(|| stylesheet.get_text("workspace", property))
+        };
+
+        if text("display")? != "grid" {
+            return None;
+        }
+        Some(GridTemplate::new(
+            text("grid-template-columns").and_then(|c| parse_grid_tracks(&c)),
+            text("grid-template-rows").and_then(|r| parse_grid_tracks(&r)),
+            text("grid-template-areas")
+                .and_then(|a| parse_grid_areas(&a))
+                .unwrap_or_default(),
+        ))
+    }
+
+    pub fn layout_grid(
+        &mut self,
+        output_size: Size<i32, Logical>,
+        gaps: i32,
+        template: &GridTemplate,
+    ) {
+        let tiles = self.workspace_manager.active_workspace().tiles();
+        let requested: Vec<Option<String>> = tiles
+            .iter()
+            .map(|tile| {
+                self.window_rules_for(&tile.windows[0])
+                    .find_map(|rule| rule.grid_area.clone())
+            })
+            .collect();
+
+        let area = self.tiled_area(output_size);
+        for (tile, geometry) in tiles
+            .iter()
+            .zip(grid_geometries(template, area, gaps, &requested))
+        {
+            self.map_tile(tile, geometry.loc, geometry.size);
+        }
+
+        let active_ws = self.workspace_manager.active_workspace();
+        println!(
+            "Relayout: {} windows in grid mode on workspace {} ({} columns)",
+            active_ws.len(),
+            active_ws.id,
+            template.columns.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_geometries() {
+        assert_eq!(
+            parse_grid_tracks("200px repeat(2, 1fr) auto 25%"),
+            Some(vec![
+                GridTrack::Px(200.0),
+                GridTrack::Fr(1.0),
+                GridTrack::Fr(1.0),
+                GridTrack::Fr(1.0),
+                GridTrack::Percent(25.0),
+            ])
+        );
+        assert_eq!(parse_grid_tracks("1fr wide"), None);
+        assert_eq!(parse_grid_areas("'a b' 'a'"), None);
+
+        let area = Rectangle::from_loc_and_size((0, 0), (1010, 610));
+        let template = GridTemplate::new(
+            parse_grid_tracks("2fr 1fr"),
+            None,
+            parse_grid_areas(r#""main side" "main log""#).unwrap(),
+        );
+
+        // Asked-for areas first, then the remaining areas in order
+        let geometries =
+            grid_geometries(&template, area, 10, &[None, Some("main".to_string()), None]);
+        assert_eq!(
+            geometries[1],
+            Rectangle::from_loc_and_size((0, 0), (666, 610))
+        );
+        assert_eq!(
+            geometries[0],
+            Rectangle::from_loc_and_size((676, 0), (333, 300))
+        );
+        assert_eq!(
+            geometries[2],
+            Rectangle::from_loc_and_size((676, 310), (333, 300))
+        );
+
+        // Without areas, windows fill cells row by row and add rows
+        let template = GridTemplate::new(parse_grid_tracks("repeat(2, 1fr)"), None, Vec::new());
+        let geometries = grid_geometries(&template, area, 10, &[None, None, None]);
+        assert_eq!(
+            geometries[2],
+            Rectangle::from_loc_and_size((0, 310), (500, 300))
+        );
+    }
+}
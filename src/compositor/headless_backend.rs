@@ -0,0 +1,104 @@
+use smithay::{
+    output::{Mode, Output, PhysicalProperties, Scale, Subpixel},
+    utils::{Rectangle, Transform},
+};
+use std::time::{Duration, Instant};
+
+use crate::compositor::bar_renderer::BarTextureRenderer;
+use crate::compositor::profiler::RenderStage;
+use crate::compositor::texture_pool::BufferPool;
+use crate::compositor::WebWMCompositor;
+
+/// Backend without any display: windows are laid out and the bar is
+/// rasterized on the CPU, but nothing is scanned out. Used for benchmarks
+/// and automated tests.
+pub struct HeadlessBackend {
+    pub output: Output,
+    /// From `--virtual-output`
+    pub virtual_outputs: Vec<Output>,
+    pub frame_count: usize,
+    bar_rasterizer: BarTextureRenderer,
+    buffer_pool: BufferPool,
+}
+
+impl HeadlessBackend {
+    pub fn new(width: i32, height: i32) -> Self {
+        let mode = Mode {
+            size: (width, height).into(),
+            refresh: 60_000,
+        };
+
+        let physical_properties = PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: "WebWM".into(),
+            model: "Headless".into(),
+            serial_number: String::new(),
+        };
+
+        let output = Output::new("HEADLESS-1".into(), physical_properties);
+        output.change_current_state(
+            Some(mode),
+            Some(Transform::Normal),
+            Some(Scale::Fractional(1.0)),
+            Some((0, 0).into()),
+        );
+        output.set_preferred(mode);
+
+        println!("✓ Headless output created: {}x{}", width, height);
+
+        Self {
+            output,
+            virtual_outputs: Vec::new(),
+            frame_count: 0,
+            bar_rasterizer: BarTextureRenderer::new(width, height),
+            buffer_pool: BufferPool::default(),
+        }
+    }
+
+    pub fn render_frame(&mut self, compositor: &mut WebWMCompositor) -> Result<(), String> {
+        self.frame_count += 1;
+
+        let stage_start = Instant::now();
+        let windows: Vec<_> = compositor
+            .stacked_elements()
+            .into_iter()
+            .filter_map(|window| {
+                let location = compositor.space.element_location(window)?;
+                let geometry = window.geometry();
+                Some((
+                    window.clone(),
+                    Rectangle::<i32, smithay::utils::Physical>::from_loc_and_size(
+                        (location.x + geometry.loc.x, location.y + geometry.loc.y),
+                        (geometry.size.w, geometry.size.h),
+                    ),
+                ))
+            })
+            .collect();
+        compositor
+            .profiler
+            .record(RenderStage::ElementCollection, stage_start.elapsed());
+
+        let stage_start = Instant::now();
+        let bar_elements = compositor.render_bar_elements();
+        if !bar_elements.is_empty() {
+            let mut buffer = self.buffer_pool.acquire(self.bar_rasterizer.buffer_len());
+            self.bar_rasterizer.render_into(&mut buffer, &bar_elements);
+            self.buffer_pool.release(buffer);
+        }
+        self.buffer_pool.end_frame();
+        compositor
+            .profiler
+            .record(RenderStage::BarRasterization, stage_start.elapsed());
+
+        // Clients throttle on frame callbacks, so keep them coming
+        let now = compositor.clock.now();
+        for (window, _) in &windows {
+            window.send_frame(&self.output, now, Some(Duration::ZERO), |_, _| {
+                Some(self.output.clone())
+            });
+        }
+
+        Ok(())
+    }
+}
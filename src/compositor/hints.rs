@@ -0,0 +1,210 @@
+// Window hints
+//
+// The window-hints action labels every window on screen with one or two
+// home-row letters, like easymotion does for text. Typing a window's label
+// focuses it (raising it on floating workspaces); Backspace takes back a
+// letter, and Escape, a click or a label that matches nothing closes the
+// hints. Labels go in reading order, top to bottom and left to right, and
+// are single letters while there are few enough windows. Colors come from
+// the stylesheet's `.window-hint` rule.
+
+use smithay::desktop::Window;
+use smithay::input::keyboard::keysyms;
+use smithay::utils::{Logical, Point, Rectangle, SERIAL_COUNTER};
+use smithay::wayland::seat::WaylandFocus;
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::ui::{dimmed, rect, Canvas, Style};
+use crate::compositor::{text, window_app_id, WebWMCompositor};
+
+/// Letters labels are made of, easiest to reach first
+pub const HINT_KEYS: &str = "asdfghjkl";
+
+const LABEL_SIZE: u32 = 22;
+const PADDING: i32 = 10;
+const BORDER_WIDTH: i32 = 2;
+
+const DEFAULT_STYLE: Style = Style::new(
+    [0.98, 0.89, 0.69, 0.95], // #f9e2af
+    [0.12, 0.12, 0.18, 1.0],
+    [0.12, 0.12, 0.18, 1.0],
+);
+
+/// `count` labels from `keys`: single letters when there are enough of
+/// them, otherwise pairs, so no label starts another
+pub fn hint_labels(count: usize, keys: &str) -> Vec<String> {
+    let keys: Vec<char> = keys.chars().collect();
+    let n = keys.len();
+    if count <= n {
+        return keys[..count].iter().map(|key| key.to_string()).collect();
+    }
+    (0..count.min(n * n))
+        .map(|i| format!("{}{}", keys[i / n], keys[i % n]))
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowHints {
+    /// Label of each window, in reading order
+    pub hints: Vec<(String, Window)>,
+    /// Letters typed so far
+    pub typed: String,
+}
+
+impl WebWMCompositor {
+    /// Label the windows on the active workspace
+    pub fn open_window_hints(&mut self) {
+        let workspace = self.workspace_manager.active_workspace();
+        let mut windows: Vec<(Rectangle<i32, Logical>, Window)> = self
+            .space
+            .elements()
+            .filter(|window| workspace.windows.contains(window))
+            .filter_map(|window| Some((self.space.element_geometry(window)?, window.clone())))
+            .collect();
+        if windows.is_empty() {
+            return;
+        }
+        windows.sort_by_key(|(geometry, _)| (geometry.loc.y, geometry.loc.x));
+
+        let labels = hint_labels(windows.len(), HINT_KEYS);
+        println!("🔤 Hinting {} windows", labels.len());
+        self.window_hints = Some(WindowHints {
+            hints: labels
+                .into_iter()
+                .zip(windows.into_iter().map(|(_, window)| window))
+                .collect(),
+            typed: String::new(),
+        });
+    }
+
+    /// The hints take every key while they're shown. Returns true if they
+    /// were.
+    pub fn window_hints_key(&mut self, keysym: u32) -> bool {
+        let Some(ref mut hints) = self.window_hints else {
+            return false;
+        };
+
+        match keysym {
+            keysyms::KEY_Escape => self.window_hints = None,
+            keysyms::KEY_BackSpace => {
+                hints.typed.pop();
+            }
+            _ => {
+                // Modifiers and other keys don't count
+                let Some(letter) = char::from_u32(keysym)
+                    .map(|c| c.to_ascii_lowercase())
+                    .filter(|c| HINT_KEYS.contains(*c))
+                else {
+                    return true;
+                };
+                hints.typed.push(letter);
+
+                let typed = hints.typed.clone();
+                let picked = hints
+                    .hints
+                    .iter()
+                    .find(|(label, _)| *label == typed)
+                    .map(|(_, window)| window.clone());
+                if let Some(window) = picked {
+                    self.window_hints = None;
+                    self.focus_hinted_window(&window);
+                } else if !hints
+                    .hints
+                    .iter()
+                    .any(|(label, _)| label.starts_with(&typed))
+                {
+                    println!("No window hinted '{}'", typed);
+                    self.window_hints = None;
+                }
+            }
+        }
+        true
+    }
+
+    /// Clicks close the hints and go on to the window under the pointer
+    pub fn window_hints_click(&mut self) -> bool {
+        self.window_hints = None;
+        false
+    }
+
+    fn focus_hinted_window(&mut self, window: &Window) {
+        // It may have closed, or the workspace changed, meanwhile
+        if !self
+            .workspace_manager
+            .active_workspace()
+            .windows
+            .contains(window)
+        {
+            return;
+        }
+        let window = self.focus_target(window);
+        println!("🎯 Focused '{}'", window_app_id(&window));
+
+        self.workspace_manager
+            .active_workspace_mut()
+            .focus_window(&window);
+        self.raise_window(&window);
+        if let (Some(keyboard), Some(surface)) = (self.seat.get_keyboard(), window.wl_surface()) {
+            keyboard.set_focus(
+                self,
+                Some(surface.into_owned()),
+                SERIAL_COUNTER.next_serial(),
+            );
+        }
+    }
+
+    pub fn render_window_hints(&self) -> Vec<BarElement> {
+        let Some(ref hints) = self.window_hints else {
+            return Vec::new();
+        };
+        let style = Style::load(self.stylesheet.as_ref(), ".window-hint", DEFAULT_STYLE);
+
+        let mut canvas = Canvas::new();
+        for (label, window) in &hints.hints {
+            if !label.starts_with(&hints.typed) {
+                continue;
+            }
+            let Some(geometry) = self.space.element_geometry(window) else {
+                continue;
+            };
+
+            let width = text::measure(label, LABEL_SIZE) + PADDING * 2;
+            let height = LABEL_SIZE as i32 + PADDING * 2;
+            let x = geometry.loc.x + (geometry.size.w - width) / 2;
+            let y = geometry.loc.y + (geometry.size.h - height) / 2;
+            canvas.panel(rect(x, y, width, height), &style, BORDER_WIDTH);
+
+            // Letters already typed are faded
+            let (typed, rest) = label.split_at(hints.typed.len());
+            let position = Point::from((x + PADDING, y + PADDING));
+            canvas.text(position, typed, dimmed(style.color, 0.4), LABEL_SIZE);
+            let offset = text::measure(typed, LABEL_SIZE);
+            canvas.text(
+                (position.x + offset, position.y).into(),
+                rest,
+                style.color,
+                LABEL_SIZE,
+            );
+        }
+        canvas.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hint_labels() {
+        assert_eq!(hint_labels(3, "asd"), vec!["a", "s", "d"]);
+        assert_eq!(hint_labels(0, "asd"), Vec::<String>::new());
+
+        // Too many windows for single letters: all pairs, none a prefix of
+        // another
+        let labels = hint_labels(5, "asd");
+        assert_eq!(labels, vec!["aa", "as", "ad", "sa", "ss"]);
+
+        // Windows beyond every pair go without a label
+        assert_eq!(hint_labels(20, "as").len(), 4);
+    }
+}
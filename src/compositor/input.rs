@@ -1,18 +1,28 @@
+use serde_json::json;
 use smithay::backend::input::{
     Axis, ButtonState, InputBackend, InputEvent, KeyState, KeyboardKeyEvent, PointerAxisEvent,
-    PointerButtonEvent, PointerMotionEvent,
+    PointerButtonEvent, PointerMotionEvent, Switch, SwitchState, SwitchToggleEvent,
 };
+use smithay::desktop::WindowSurfaceType;
 use smithay::input::{
-    keyboard::{keysyms, Keysym, ModifiersState},
+    keyboard::{keysyms, xkb, Keycode, Keysym, ModifiersState},
     pointer::{AxisFrame, ButtonEvent, MotionEvent},
 };
 use smithay::utils::{Logical, Point, SERIAL_COUNTER};
 use smithay::wayland::seat::WaylandFocus;
 use std::process::Command;
+use std::time::Instant;
 
+use crate::compositor::chords::PendingChord;
+use crate::compositor::dwindle::SplitAxis;
+use crate::compositor::input_trace::{InputRecorder, InputTraceEvent, SwitchKind};
 use crate::compositor::WebWMCompositor;
 use crate::config::Action;
 
+/// Linux event code of the left mouse button
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+
 // Key modifier flags
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Modifiers {
@@ -55,16 +65,42 @@ impl Modifiers {
             && self.shift == required.shift
             && self.super_key == required.super_key
     }
+
+    /// Keybinding-style name such as `Super+Shift+a`
+    pub fn combo(&self, key: &str) -> String {
+        let mut parts = Vec::new();
+        if self.super_key {
+            parts.push("Super");
+        }
+        if self.ctrl {
+            parts.push("Ctrl");
+        }
+        if self.alt {
+            parts.push("Alt");
+        }
+        if self.shift {
+            parts.push("Shift");
+        }
+        parts.push(key);
+        parts.join("+")
+    }
 }
 
 pub struct InputHandler {
-    pub pointer_location: Point<f64, Logical>,
+    /// Set by `--record-input`
+    pub recorder: Option<InputRecorder>,
+    /// Binding modes entered, innermost last; empty in the default one
+    pub mode_stack: Vec<String>,
+    /// Chord started by the keys pressed so far
+    pub chord: Option<PendingChord>,
 }
 
 impl InputHandler {
     pub fn new() -> Self {
         Self {
-            pointer_location: (0.0, 0.0).into(),
+            recorder: None,
+            mode_stack: Vec::new(),
+            chord: None,
         }
     }
 
@@ -73,35 +109,56 @@ impl InputHandler {
         event: InputEvent<B>,
         compositor: &mut WebWMCompositor,
     ) {
+        if let Some(event) = trace_event_from_backend(event) {
+            self.process_trace_event(event, compositor);
+        }
+    }
+
+    /// Handle a backend-independent input event. Live input and replayed
+    /// traces both end up here.
+    pub fn process_trace_event(
+        &mut self,
+        event: InputTraceEvent,
+        compositor: &mut WebWMCompositor,
+    ) {
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.record(&event);
+        }
+
         match event {
-            InputEvent::Keyboard { event } => {
-                self.handle_keyboard(event, compositor);
+            InputTraceEvent::Key { keycode, pressed } => {
+                self.handle_keyboard(Keycode::from(keycode), pressed, compositor);
             }
-            InputEvent::PointerMotion { event } => {
-                self.handle_pointer_motion(event, compositor);
+            InputTraceEvent::PointerMotion { dx, dy } => {
+                self.handle_pointer_motion((dx, dy).into(), compositor);
             }
-            InputEvent::PointerButton { event } => {
-                self.handle_pointer_button(event, compositor);
+            InputTraceEvent::PointerButton { button, pressed } => {
+                self.handle_pointer_button(button, pressed, compositor);
             }
-            InputEvent::PointerAxis { event } => {
-                self.handle_pointer_axis(event, compositor);
+            InputTraceEvent::PointerAxis {
+                horizontal,
+                vertical,
+            } => {
+                self.handle_pointer_axis(horizontal, vertical, compositor);
             }
-            _ => {}
+            InputTraceEvent::Switch { switch, on } => match switch {
+                SwitchKind::Lid => compositor.handle_lid_switch(on),
+                SwitchKind::TabletMode => compositor.handle_tablet_mode(on),
+            },
         }
     }
 
-    fn handle_keyboard<B: InputBackend>(
+    fn handle_keyboard(
         &mut self,
-        event: impl KeyboardKeyEvent<B>,
+        keycode: Keycode,
+        pressed: bool,
         compositor: &mut WebWMCompositor,
     ) {
-        let keycode = event.key_code();
-        let state = event.state();
-
         // Only process key press (not release)
-        if state != KeyState::Pressed {
+        if !pressed {
             return;
         }
+        let state = KeyState::Pressed;
 
         // Get keyboard to translate keycode to keysym
         if let Some(keyboard) = compositor.seat.get_keyboard() {
@@ -112,6 +169,40 @@ impl InputHandler {
             // Get the keysym for this keycode
             let keysym = Keysym::from(u32::from(keycode));
 
+            // A locker holding the input inhibitor gets every key, bindings
+            // included, and nobody else gets any
+            if compositor.input_inhibited() {
+                compositor.enforce_input_inhibit();
+                keyboard.input::<(), _>(
+                    compositor,
+                    keycode,
+                    state,
+                    SERIAL_COUNTER.next_serial(),
+                    0,
+                    |_, _, _| smithay::input::keyboard::FilterResult::Forward,
+                );
+                return;
+            }
+
+            // A region selection grabs the keyboard; Escape cancels it
+            if compositor.region_selector.is_some() {
+                if u32::from(keysym) == keysyms::KEY_Escape {
+                    compositor.cancel_region_select();
+                }
+                return;
+            }
+
+            // Compositor overlays, like a close confirmation or the window
+            // menu, take the keys they need
+            if compositor.overlay_key(u32::from(keysym)) {
+                return;
+            }
+
+            // Escape closes the innermost menu of a popup grab
+            if u32::from(keysym) == keysyms::KEY_Escape && compositor.dismiss_innermost_popup() {
+                return;
+            }
+
             println!(
                 "Key pressed: keycode={:?}, keysym={:?}, mods={:?}",
                 keycode, keysym, mods
@@ -123,6 +214,16 @@ impl InputHandler {
                 return;
             }
 
+            // Unbound Super combos may belong to an external hotkey daemon
+            if mods.super_key && self.passthrough_hotkey(u32::from(keysym), mods, compositor) {
+                return;
+            }
+
+            // Typing hides the cursor, if configured
+            if !keysym.is_modifier_key() {
+                compositor.cursor_visibility.key_typed();
+            }
+
             // Forward to focused window
             keyboard.input::<(), _>(
                 compositor,
@@ -146,13 +247,30 @@ impl InputHandler {
 
         println!("Checking keybinding: {} with mods {:?}", key_name, mods);
 
+        // A pending chord takes the next key, waiting out modifier presses
+        if self.chord.is_some() {
+            return Keysym::from(keysym).is_modifier_key()
+                || self.continue_chord(&key_name, mods, compositor);
+        }
+
+        if let Some(mode) = self.binding_mode().map(str::to_string) {
+            if self.check_mode_keybindings(&mode, &key_name, mods, compositor) {
+                return true;
+            }
+        }
+
+        let bindings = compositor.config.keybindings.clone();
+        if self.start_chord(&bindings, None, &key_name, mods, compositor) {
+            return true;
+        }
+
         // Check each configured keybinding
-        for binding in &compositor.config.keybindings.clone() {
+        for binding in &bindings {
             if binding.key.to_lowercase() == key_name.to_lowercase()
                 && mods.matches(&binding.modifiers)
+                && binding.sequence.is_empty()
             {
-                println!("Matched keybinding: {:?}", binding);
-                self.execute_action(&binding.action, compositor);
+                self.run_binding(binding, None, compositor);
                 return true;
             }
         }
@@ -160,7 +278,49 @@ impl InputHandler {
         false
     }
 
-    fn execute_action(&mut self, action: &Action, compositor: &mut WebWMCompositor) {
+    /// Hand an unbound combo to `<hotkey-passthrough>`: run its command and
+    /// emit a `hotkey` event for IPC subscribers and JS `on('hotkey')`.
+    /// Returns false when passthrough is off, so the client gets the key.
+    fn passthrough_hotkey(
+        &mut self,
+        keysym: u32,
+        mods: Modifiers,
+        compositor: &mut WebWMCompositor,
+    ) -> bool {
+        let Some(passthrough) = compositor
+            .config
+            .desktop
+            .as_ref()
+            .and_then(|desktop| desktop.hotkey_passthrough.clone())
+        else {
+            return false;
+        };
+
+        // Modifier presses and keys we have no name for go to the client
+        let key = keysym_to_string(keysym);
+        if key.starts_with("Unknown(") {
+            return false;
+        }
+
+        let combo = mods.combo(&key);
+        println!("Passing through hotkey: {}", combo);
+
+        if let Some(ref command) = passthrough.command {
+            let command = command.replace("{combo}", &combo);
+            Command::new("sh")
+                .arg("-c")
+                .arg(&command)
+                .env("WEBWM_HOTKEY", &combo)
+                .spawn()
+                .map_err(|e| eprintln!("Failed to spawn '{}': {}", command, e))
+                .ok();
+        }
+
+        compositor.emit_event("hotkey", json!({ "combo": combo, "keysym": keysym }));
+        true
+    }
+
+    pub(super) fn execute_action(&mut self, action: &Action, compositor: &mut WebWMCompositor) {
         match action {
             Action::Spawn { command } => {
                 println!("Spawning: {}", command);
@@ -174,16 +334,13 @@ impl InputHandler {
 
             Action::Close => {
                 println!("Closing focused window");
-                if let Some(window) = compositor.workspace_manager.focused_window() {
-                    if let Some(toplevel) = window.toplevel() {
-                        toplevel.send_close();
-                    }
-                }
+                compositor.close_focused();
             }
 
             Action::Focus { direction } => {
                 println!("Focusing: {}", direction);
                 self.focus_direction(direction, compositor);
+                self.warp_to_focused(compositor);
             }
 
             Action::Move { workspace } => {
@@ -200,49 +357,141 @@ impl InputHandler {
                 println!("Switching to workspace: {}", workspace);
                 compositor.workspace_manager.switch_to_workspace(*workspace);
                 compositor.relayout();
+                self.warp_to_focused(compositor);
             }
 
-            Action::ToggleFloating => {
-                println!("Toggling floating mode");
-                compositor.workspace_manager.toggle_floating();
-                compositor.relayout();
+            Action::ToggleFloating => compositor.toggle_focused_floating(),
+
+            Action::MergeWindow { direction } => {
+                compositor.merge_focused_window(direction);
             }
 
-            Action::Custom { js } => {
-                println!("Executing custom action: {}", js);
-                match js.as_str() {
-                    "Super+Return" => {
-                        Command::new("sh").arg("-c").arg("alacritty").spawn().ok();
-                    }
-                    "Super+q" => {
-                        if let Some(window) = compositor.workspace_manager.focused_window() {
-                            if let Some(toplevel) = window.toplevel() {
-                                toplevel.send_close();
-                            }
-                        }
-                    }
-                    "Super+f" => {
-                        compositor.workspace_manager.toggle_floating();
-                        compositor.relayout();
-                    }
-                    _ => {
-                        println!("Unhandled custom action: {}", js);
-                    }
+            Action::SplitWindow => {
+                compositor.split_focused_window();
+            }
+
+            Action::SelectRegion { command } => {
+                compositor.begin_region_select(command.clone(), compositor.pointer_location);
+            }
+
+            Action::ForceClose => {
+                compositor.force_close_focused();
+            }
+
+            Action::ExecAndFocus {
+                command,
+                app_id,
+                workspace,
+            } => {
+                compositor.exec_and_focus(command, app_id.clone(), *workspace);
+            }
+
+            Action::RunOrRaise { app_id, command } => {
+                compositor.run_or_raise(app_id, command);
+            }
+
+            Action::TogglePresentationMode => {
+                compositor.toggle_presentation_mode();
+            }
+
+            Action::PromoteToMaster => {
+                compositor.promote_to_master();
+            }
+
+            Action::AdjustMasterRatio { delta } => {
+                compositor.adjust_master_ratio(*delta);
+            }
+
+            Action::AdjustSplitRatio { delta } => {
+                compositor.adjust_split_ratio(*delta);
+            }
+
+            Action::RotateSplit => {
+                compositor.rotate_split();
+            }
+
+            Action::SplitHorizontal => compositor.choose_split(SplitAxis::Horizontal),
+            Action::SplitVertical => compositor.choose_split(SplitAxis::Vertical),
+
+            Action::ReopenLast => {
+                compositor.reopen_last();
+            }
+
+            Action::ToggleOutputZoom => {
+                compositor.toggle_output_zoom();
+            }
+
+            Action::ResizeTiled { direction, amount } => {
+                compositor.resize_tiled(direction, *amount);
+            }
+
+            Action::ToggleBar { output } => {
+                if let Err(e) = compositor.toggle_bar(output.as_deref()) {
+                    eprintln!("Failed to toggle bar: {}", e);
                 }
             }
+
+            Action::Swap { direction } => {
+                compositor.swap_window(direction);
+                self.warp_to_focused(compositor);
+            }
+
+            Action::CenterWindow => {
+                compositor.center_window();
+            }
+
+            Action::WindowMenu => compositor.open_focused_window_menu(),
+            Action::WindowHints => compositor.open_window_hints(),
+
+            Action::FocusOutput { direction } => {
+                if let Some(center) = compositor.focus_output(direction) {
+                    self.move_pointer_to(center, compositor);
+                }
+            }
+
+            Action::Raise => compositor.raise_focused(),
+            Action::Lower => compositor.lower_focused(),
+            Action::RaiseOrLower => compositor.raise_or_lower_focused(),
+
+            Action::AdjustGaps { inner, outer } => {
+                compositor.adjust_gaps(*inner, *outer);
+            }
+
+            Action::Reload => compositor.schedule_config_reload(),
+
+            Action::EnterMode { name } => self.enter_mode(name, compositor),
+            Action::ExitMode => self.exit_mode(compositor),
+
+            // Runs its keybind() callback
+            Action::Custom { js } => self.run_js_binding(None, js, compositor),
         }
     }
 
     fn focus_direction(&mut self, direction: &str, compositor: &mut WebWMCompositor) {
-        match direction {
-            "up" | "left" => {
-                compositor.workspace_manager.focus_prev_window();
-            }
-            "down" | "right" => {
-                compositor.workspace_manager.focus_next_window();
-            }
-            _ => return,
-        };
+        if compositor.focus_by_order() {
+            match direction {
+                "up" | "left" => {
+                    compositor.workspace_manager.focus_prev_window();
+                }
+                "down" | "right" => {
+                    compositor.workspace_manager.focus_next_window();
+                }
+                _ => return,
+            };
+        } else {
+            let Some(window) = compositor.window_in_direction(direction) else {
+                println!("No window {} of the focused one", direction);
+                return;
+            };
+            let window = compositor.focus_target(&window);
+            compositor
+                .workspace_manager
+                .active_workspace_mut()
+                .focus_window(&window);
+            compositor.raise_window(&window);
+        }
+        // Tabbed and monocle layouts show the newly focused window
+        compositor.relayout();
 
         // Update keyboard focus
         if let Some(window) = compositor.workspace_manager.focused_window() {
@@ -265,33 +514,79 @@ impl InputHandler {
         }
     }
 
-    fn handle_pointer_motion<B: InputBackend>(
+    fn handle_pointer_motion(
         &mut self,
-        event: impl PointerMotionEvent<B>,
+        delta: Point<f64, Logical>,
         compositor: &mut WebWMCompositor,
     ) {
-        let delta = event.delta();
-        self.pointer_location.x += delta.x;
-        self.pointer_location.y += delta.y;
+        // Keep the pointer on the outputs, pausing at edges and barriers
+        let location = compositor.constrain_pointer(
+            compositor.pointer_location,
+            compositor.pointer_location + delta,
+        );
+        compositor.cursor_visibility.pointer_moved(Instant::now());
+        // The output under the pointer has focus again
+        compositor.focused_output = None;
+        self.move_pointer_to(location, compositor);
+    }
 
-        // Clamp to output bounds - use default for now, should be passed from backend
-        let output_size = (1920.0, 1080.0);
-        self.pointer_location.x = self.pointer_location.x.max(0.0).min(output_size.0);
-        self.pointer_location.y = self.pointer_location.y.max(0.0).min(output_size.1);
+    /// Put the pointer in the middle of the focused window, if enabled
+    fn warp_to_focused(&mut self, compositor: &mut WebWMCompositor) {
+        if let Some(center) = compositor.warp_target() {
+            println!("🖱️  Warping pointer to {:?}", (center.x, center.y));
+            self.move_pointer_to(center, compositor);
+        }
+    }
+
+    fn move_pointer_to(&mut self, location: Point<f64, Logical>, compositor: &mut WebWMCompositor) {
+        compositor.pointer_location = location;
+
+        if compositor.region_selector.is_some() {
+            compositor.region_select_motion(compositor.pointer_location);
+            return;
+        }
+
+        if compositor.window_drag.is_some() {
+            compositor.window_drag_motion(compositor.pointer_location);
+            return;
+        }
+
+        if compositor.tile_resize.is_some() {
+            compositor.tile_resize_motion(compositor.pointer_location);
+            return;
+        }
+
+        if compositor.floating_grab.is_some() {
+            compositor.floating_grab_motion(compositor.pointer_location);
+            return;
+        }
+
+        compositor.overlay_motion(compositor.pointer_location);
 
         // Update pointer focus based on location
-        let surface_under = compositor.space.element_under(self.pointer_location);
+        let surface_under = compositor
+            .space
+            .element_under(compositor.pointer_location)
+            .filter(|(window, _)| {
+                window
+                    .wl_surface()
+                    .is_some_and(|surface| compositor.input_allowed(&surface))
+            });
 
         if let Some(pointer) = compositor.seat.get_pointer() {
             if let Some((window, location)) = surface_under {
-                if let Some(surface) = window.wl_surface() {
-                    let surface_location = self.pointer_location - location.to_f64();
-
+                // `location` is where the window's surface is drawn; the
+                // surface under the pointer may be one of its popups or
+                // subsurfaces rather than the window itself
+                if let Some((surface, offset)) = window.surface_under(
+                    compositor.pointer_location - location.to_f64(),
+                    WindowSurfaceType::ALL,
+                ) {
                     pointer.motion(
                         compositor,
-                        Some((surface.into_owned(), surface_location)),
+                        Some((surface, (location + offset).to_f64())),
                         &MotionEvent {
-                            location: self.pointer_location,
+                            location: compositor.pointer_location,
                             serial: SERIAL_COUNTER.next_serial(),
                             time: 0,
                         },
@@ -302,7 +597,7 @@ impl InputHandler {
                     compositor,
                     None,
                     &MotionEvent {
-                        location: self.pointer_location,
+                        location: compositor.pointer_location,
                         serial: SERIAL_COUNTER.next_serial(),
                         time: 0,
                     },
@@ -311,23 +606,140 @@ impl InputHandler {
         }
     }
 
-    fn handle_pointer_button<B: InputBackend>(
+    fn handle_pointer_button(
         &mut self,
-        event: impl PointerButtonEvent<B>,
+        button: u32,
+        pressed: bool,
         compositor: &mut WebWMCompositor,
     ) {
-        let button = event.button_code();
-        let state = event.state();
+        let state = if pressed {
+            ButtonState::Pressed
+        } else {
+            ButtonState::Released
+        };
 
         println!("Pointer button: {} {:?}", button, state);
 
+        if compositor.region_selector.is_some() {
+            compositor.region_select_button(button, pressed);
+            return;
+        }
+
+        // With input inhibited, clicks only go to the inhibiting client
+        let inhibited = compositor.input_inhibited();
+
+        // On floating workspaces, Super+left-drag moves windows and
+        // Super+right-drag resizes them
+        if (button == BTN_LEFT || button == BTN_RIGHT) && !inhibited {
+            if compositor.floating_grab.is_some() {
+                if !pressed {
+                    compositor.end_floating_grab();
+                }
+                return;
+            }
+            let super_held = compositor
+                .seat
+                .get_keyboard()
+                .is_some_and(|keyboard| keyboard.modifier_state().logo);
+            if pressed
+                && super_held
+                && compositor.begin_floating_grab(compositor.pointer_location, button == BTN_RIGHT)
+            {
+                return;
+            }
+        }
+
+        // Super+drag carries a window to a workspace button in the bar
+        if button == BTN_LEFT && !inhibited {
+            if compositor.window_drag.is_some() {
+                if !pressed {
+                    compositor.end_window_drag();
+                }
+                return;
+            }
+            let super_held = compositor
+                .seat
+                .get_keyboard()
+                .is_some_and(|keyboard| keyboard.modifier_state().logo);
+            if pressed && super_held && compositor.begin_window_drag(compositor.pointer_location) {
+                return;
+            }
+        }
+
+        // Super+right-drag resizes the tile under the pointer
+        if button == BTN_RIGHT && !inhibited {
+            if compositor.tile_resize.is_some() {
+                if !pressed {
+                    compositor.end_tile_resize();
+                }
+                return;
+            }
+            let super_held = compositor
+                .seat
+                .get_keyboard()
+                .is_some_and(|keyboard| keyboard.modifier_state().logo);
+            if pressed && super_held && compositor.begin_tile_resize(compositor.pointer_location) {
+                return;
+            }
+        }
+
+        // Clicks on compositor overlays, or on the clock to open its
+        // calendar, don't reach clients
+        if state == ButtonState::Pressed
+            && !inhibited
+            && compositor.overlay_click(compositor.pointer_location)
+        {
+            return;
+        }
+
+        // The speaker badge on a tab mutes that window
+        if state == ButtonState::Pressed
+            && !inhibited
+            && compositor.audio_badge_click(compositor.pointer_location)
+        {
+            return;
+        }
+
+        // Right-clicking a tab title opens the window menu
+        if state == ButtonState::Pressed
+            && button == BTN_RIGHT
+            && !inhibited
+            && compositor.tab_menu_click(compositor.pointer_location)
+        {
+            return;
+        }
+
+        // Clicking a tab title brings that tab to the front
+        if state == ButtonState::Pressed
+            && !inhibited
+            && compositor.tab_click(compositor.pointer_location)
+        {
+            return;
+        }
+
+        // Clicks inside a grabbing popup chain stay with it; clicks outside
+        // dismiss it and go on to the window under the pointer
+        if state == ButtonState::Pressed && compositor.popup_grab_root.is_some() {
+            if compositor.over_grabbed_popup(compositor.pointer_location) {
+                self.forward_button(button, state, compositor);
+                return;
+            }
+            compositor.dismiss_popups();
+        }
+
         // On button press, focus the window under cursor
         if state == ButtonState::Pressed {
-            let surface_under = compositor.space.element_under(self.pointer_location);
+            let surface_under = compositor.space.element_under(compositor.pointer_location);
 
-            if let Some((window, _)) = surface_under {
+            if let Some((clicked, _)) = surface_under {
+                // Clicking a window with a modal dialog focuses the dialog
+                let window = compositor.focus_target(clicked);
+                let blocked = &window != clicked;
                 if let Some(keyboard) = compositor.seat.get_keyboard() {
-                    if let Some(surface) = window.wl_surface() {
+                    if let Some(surface) = window
+                        .wl_surface()
+                        .filter(|surface| compositor.input_allowed(surface))
+                    {
                         keyboard.set_focus(
                             compositor,
                             Some(surface.into_owned() as smithay::reexports::wayland_server::protocol::wl_surface::WlSurface),
@@ -336,10 +748,26 @@ impl InputHandler {
                         println!("Focused window under cursor");
                     }
                 }
+                compositor.click_raise(&window);
+
+                // The parent of a modal dialog doesn't see the click
+                if blocked {
+                    compositor.raise_with_dialogs(&window);
+                    return;
+                }
             }
         }
 
-        // Forward button event to client
+        self.forward_button(button, state, compositor);
+    }
+
+    /// Send a button event to the client under the pointer
+    fn forward_button(
+        &mut self,
+        button: u32,
+        state: ButtonState,
+        compositor: &mut WebWMCompositor,
+    ) {
         if let Some(pointer) = compositor.seat.get_pointer() {
             pointer.button(
                 compositor,
@@ -353,15 +781,27 @@ impl InputHandler {
         }
     }
 
-    fn handle_pointer_axis<B: InputBackend>(
+    fn handle_pointer_axis(
         &mut self,
-        event: impl PointerAxisEvent<B>,
+        horizontal: f64,
+        vertical: f64,
         compositor: &mut WebWMCompositor,
     ) {
-        if let Some(pointer) = compositor.seat.get_pointer() {
-            let horizontal = event.amount(Axis::Horizontal).unwrap_or(0.0);
-            let vertical = event.amount(Axis::Vertical).unwrap_or(0.0);
+        // Scrolling over the gap between tiles resizes their split
+        let super_held = compositor
+            .seat
+            .get_keyboard()
+            .is_some_and(|keyboard| keyboard.modifier_state().logo);
+        if compositor.scroll_split(
+            compositor.pointer_location,
+            horizontal,
+            vertical,
+            super_held,
+        ) {
+            return;
+        }
 
+        if let Some(pointer) = compositor.seat.get_pointer() {
             let frame = AxisFrame::new(0)
                 .value(Axis::Horizontal, horizontal)
                 .value(Axis::Vertical, vertical);
@@ -371,75 +811,117 @@ impl InputHandler {
     }
 }
 
-// Convert keysym to human-readable string
+impl Default for InputHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Convert a backend event into its recordable form
+pub fn trace_event_from_backend<B: InputBackend>(event: InputEvent<B>) -> Option<InputTraceEvent> {
+    match event {
+        InputEvent::Keyboard { event } => Some(InputTraceEvent::Key {
+            keycode: u32::from(event.key_code()),
+            pressed: event.state() == KeyState::Pressed,
+        }),
+        InputEvent::PointerMotion { event } => {
+            let delta = event.delta();
+            Some(InputTraceEvent::PointerMotion {
+                dx: delta.x,
+                dy: delta.y,
+            })
+        }
+        InputEvent::PointerButton { event } => Some(InputTraceEvent::PointerButton {
+            button: event.button_code(),
+            pressed: event.state() == ButtonState::Pressed,
+        }),
+        InputEvent::PointerAxis { event } => Some(InputTraceEvent::PointerAxis {
+            horizontal: event.amount(Axis::Horizontal).unwrap_or(0.0),
+            vertical: event.amount(Axis::Vertical).unwrap_or(0.0),
+        }),
+        InputEvent::SwitchToggle { event } => {
+            let switch = match event.switch()? {
+                Switch::Lid => SwitchKind::Lid,
+                Switch::TabletMode => SwitchKind::TabletMode,
+            };
+            Some(InputTraceEvent::Switch {
+                switch,
+                on: event.state() == SwitchState::On,
+            })
+        }
+        _ => None,
+    }
+}
+
+// Convert keysym to the name config.js and desktop.xml use for it: xkb's,
+// as in `minus` or `bracketleft`
 fn keysym_to_string(keysym: u32) -> String {
     match keysym {
-        keysyms::KEY_Return => "Return".to_string(),
-        keysyms::KEY_Escape => "Escape".to_string(),
-        keysyms::KEY_BackSpace => "BackSpace".to_string(),
-        keysyms::KEY_Tab => "Tab".to_string(),
-        keysyms::KEY_space => "space".to_string(),
-
-        // Letters
-        keysyms::KEY_a => "a".to_string(),
-        keysyms::KEY_b => "b".to_string(),
-        keysyms::KEY_c => "c".to_string(),
-        keysyms::KEY_d => "d".to_string(),
-        keysyms::KEY_e => "e".to_string(),
-        keysyms::KEY_f => "f".to_string(),
-        keysyms::KEY_g => "g".to_string(),
-        keysyms::KEY_h => "h".to_string(),
-        keysyms::KEY_i => "i".to_string(),
-        keysyms::KEY_j => "j".to_string(),
-        keysyms::KEY_k => "k".to_string(),
-        keysyms::KEY_l => "l".to_string(),
-        keysyms::KEY_m => "m".to_string(),
-        keysyms::KEY_n => "n".to_string(),
-        keysyms::KEY_o => "o".to_string(),
-        keysyms::KEY_p => "p".to_string(),
-        keysyms::KEY_q => "q".to_string(),
-        keysyms::KEY_r => "r".to_string(),
-        keysyms::KEY_s => "s".to_string(),
-        keysyms::KEY_t => "t".to_string(),
-        keysyms::KEY_u => "u".to_string(),
-        keysyms::KEY_v => "v".to_string(),
-        keysyms::KEY_w => "w".to_string(),
-        keysyms::KEY_x => "x".to_string(),
-        keysyms::KEY_y => "y".to_string(),
-        keysyms::KEY_z => "z".to_string(),
-
-        // Numbers
-        keysyms::KEY_1 => "1".to_string(),
-        keysyms::KEY_2 => "2".to_string(),
-        keysyms::KEY_3 => "3".to_string(),
-        keysyms::KEY_4 => "4".to_string(),
-        keysyms::KEY_5 => "5".to_string(),
-        keysyms::KEY_6 => "6".to_string(),
-        keysyms::KEY_7 => "7".to_string(),
-        keysyms::KEY_8 => "8".to_string(),
-        keysyms::KEY_9 => "9".to_string(),
-        keysyms::KEY_0 => "0".to_string(),
-
-        // Function keys
-        keysyms::KEY_F1 => "F1".to_string(),
-        keysyms::KEY_F2 => "F2".to_string(),
-        keysyms::KEY_F3 => "F3".to_string(),
-        keysyms::KEY_F4 => "F4".to_string(),
-        keysyms::KEY_F5 => "F5".to_string(),
-        keysyms::KEY_F6 => "F6".to_string(),
-        keysyms::KEY_F7 => "F7".to_string(),
-        keysyms::KEY_F8 => "F8".to_string(),
-        keysyms::KEY_F9 => "F9".to_string(),
-        keysyms::KEY_F10 => "F10".to_string(),
-        keysyms::KEY_F11 => "F11".to_string(),
-        keysyms::KEY_F12 => "F12".to_string(),
-
-        // Arrow keys
-        keysyms::KEY_Left => "Left".to_string(),
-        keysyms::KEY_Right => "Right".to_string(),
-        keysyms::KEY_Up => "Up".to_string(),
-        keysyms::KEY_Down => "Down".to_string(),
-
-        _ => format!("Unknown({})", keysym),
+        // xkb names these Prior and Next
+        keysyms::KEY_Page_Up => "Page_Up".to_string(),
+        keysyms::KEY_Page_Down => "Page_Down".to_string(),
+        _ => {
+            let name = xkb::keysym_get_name(Keysym::from(keysym));
+            // Keysyms without a name come back as their number
+            if name.is_empty() || name.starts_with("0x") {
+                format!("Unknown({})", keysym)
+            } else {
+                name
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modifier_combo() {
+        let mods = Modifiers {
+            ctrl: false,
+            alt: false,
+            shift: true,
+            super_key: true,
+        };
+        assert_eq!(mods.combo("a"), "Super+Shift+a");
+        assert!(mods.matches(&["Super".to_string(), "Shift".to_string()]));
+    }
+
+    #[test]
+    fn test_keysym_names() {
+        assert_eq!(keysym_to_string(keysyms::KEY_Return), "Return");
+        assert_eq!(keysym_to_string(keysyms::KEY_a), "a");
+        assert_eq!(keysym_to_string(keysyms::KEY_F12), "F12");
+        assert_eq!(keysym_to_string(keysyms::KEY_minus), "minus");
+        assert_eq!(keysym_to_string(keysyms::KEY_equal), "equal");
+        assert_eq!(keysym_to_string(keysyms::KEY_comma), "comma");
+        assert_eq!(keysym_to_string(keysyms::KEY_period), "period");
+        assert_eq!(keysym_to_string(keysyms::KEY_semicolon), "semicolon");
+        assert_eq!(keysym_to_string(keysyms::KEY_bracketleft), "bracketleft");
+        assert_eq!(keysym_to_string(keysyms::KEY_bracketright), "bracketright");
+        assert_eq!(keysym_to_string(keysyms::KEY_Page_Up), "Page_Up");
+        assert_eq!(keysym_to_string(keysyms::KEY_Page_Down), "Page_Down");
+        assert_eq!(keysym_to_string(0x12345678), "Unknown(305419896)");
+    }
+
+    #[test]
+    fn test_shipped_key_names() {
+        // A binding whose key the matcher never names would never fire
+        let config_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/config");
+        let (config, _) = crate::config::load_config(config_dir).unwrap();
+        let bindings = config
+            .keybindings
+            .iter()
+            .chain(config.modes.values().flatten());
+        for binding in bindings {
+            let keysym = xkb::keysym_from_name(&binding.key, xkb::KEYSYM_CASE_INSENSITIVE);
+            assert_eq!(
+                keysym_to_string(u32::from(keysym)).to_lowercase(),
+                binding.key.to_lowercase(),
+                "{:?}",
+                binding.modifiers
+            );
+        }
     }
 }
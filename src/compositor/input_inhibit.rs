@@ -0,0 +1,174 @@
+// Input inhibition
+//
+// Screen lockers and kiosk overlays take all input through
+// zwlr_input_inhibit_manager_v1. While a client holds the inhibitor, only
+// its surfaces get keyboard focus, pointer focus and clicks; keybindings,
+// hotkey passthrough and the compositor's own click targets (the clock,
+// tab badges, Super+drag) are off too, so nothing can get past the locker.
+// Only one client can hold it at a time; a second one gets the protocol's
+// `already_inhibited` error. Input goes back to normal when the inhibitor
+// is destroyed or its client goes away, and an `input-inhibit` event tells
+// JS `on()` handlers and IPC subscribers either way.
+
+use serde_json::json;
+use smithay::input::pointer::MotionEvent;
+use smithay::reexports::wayland_protocols_wlr::input_inhibitor::v1::server::{
+    zwlr_input_inhibit_manager_v1::{self, ZwlrInputInhibitManagerV1},
+    zwlr_input_inhibitor_v1::{self, ZwlrInputInhibitorV1},
+};
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{
+    backend::ClientId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+use smithay::utils::SERIAL_COUNTER;
+
+use crate::compositor::WebWMCompositor;
+
+#[derive(Debug, Default)]
+pub struct InputInhibitState {
+    /// Client holding the inhibitor
+    client: Option<ClientId>,
+}
+
+impl InputInhibitState {
+    pub fn new(display_handle: &DisplayHandle) -> Self {
+        display_handle.create_global::<WebWMCompositor, ZwlrInputInhibitManagerV1, _>(1, ());
+        Self::default()
+    }
+}
+
+impl WebWMCompositor {
+    pub fn input_inhibited(&self) -> bool {
+        self.input_inhibit.client.is_some()
+    }
+
+    /// Whether `surface` may get input right now
+    pub fn input_allowed(&self, surface: &WlSurface) -> bool {
+        match self.input_inhibit.client {
+            Some(ref client) => surface.client().is_some_and(|c| &c.id() == client),
+            None => true,
+        }
+    }
+
+    /// Give `client` all input until `end_input_inhibit`
+    pub fn begin_input_inhibit(&mut self, client: ClientId) {
+        println!("🔒 Input inhibited for one client");
+        self.input_inhibit.client = Some(client);
+        self.region_selector = None;
+        self.calendar = None;
+        self.window_drag = None;
+
+        // Pointer focus comes back on the next motion over an allowed surface
+        if let Some(pointer) = self.seat.get_pointer() {
+            let location = pointer.current_location();
+            pointer.motion(
+                self,
+                None,
+                &MotionEvent {
+                    location,
+                    serial: SERIAL_COUNTER.next_serial(),
+                    time: 0,
+                },
+            );
+        }
+        self.enforce_input_inhibit();
+
+        self.emit_event("input-inhibit", json!({ "active": true }));
+    }
+
+    pub fn end_input_inhibit(&mut self) {
+        if self.input_inhibit.client.take().is_none() {
+            return;
+        }
+        println!("🔓 Input inhibitor released");
+        self.emit_event("input-inhibit", json!({ "active": false }));
+    }
+
+    /// Move keyboard focus to the inhibiting client's window, or nowhere,
+    /// if another client has it
+    pub fn enforce_input_inhibit(&mut self) {
+        if !self.input_inhibited() {
+            return;
+        }
+        let Some(keyboard) = self.seat.get_keyboard() else {
+            return;
+        };
+        if keyboard
+            .current_focus()
+            .is_some_and(|surface| self.input_allowed(&surface))
+        {
+            return;
+        }
+
+        let surface = self
+            .space
+            .elements()
+            .filter_map(|window| window.wl_surface().map(|s| s.into_owned()))
+            .find(|surface| self.input_allowed(surface));
+        keyboard.set_focus(self, surface, SERIAL_COUNTER.next_serial());
+    }
+}
+
+impl GlobalDispatch<ZwlrInputInhibitManagerV1, ()> for WebWMCompositor {
+    fn bind(
+        _state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrInputInhibitManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl Dispatch<ZwlrInputInhibitManagerV1, ()> for WebWMCompositor {
+    fn request(
+        state: &mut Self,
+        client: &Client,
+        manager: &ZwlrInputInhibitManagerV1,
+        request: zwlr_input_inhibit_manager_v1::Request,
+        _data: &(),
+        _handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let zwlr_input_inhibit_manager_v1::Request::GetInhibitor { id } = request else {
+            return;
+        };
+
+        data_init.init(id, ());
+        if state.input_inhibited() {
+            manager.post_error(
+                zwlr_input_inhibit_manager_v1::Error::AlreadyInhibited,
+                "input is already inhibited by another client",
+            );
+            return;
+        }
+        state.begin_input_inhibit(client.id());
+    }
+}
+
+impl Dispatch<ZwlrInputInhibitorV1, ()> for WebWMCompositor {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _inhibitor: &ZwlrInputInhibitorV1,
+        _request: zwlr_input_inhibitor_v1::Request,
+        _data: &(),
+        _handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        // Destroy is the only request; releasing happens in destroyed
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        client: ClientId,
+        _inhibitor: &ZwlrInputInhibitorV1,
+        _data: &(),
+    ) {
+        if state.input_inhibit.client.as_ref() == Some(&client) {
+            state.end_input_inhibit();
+        }
+    }
+}
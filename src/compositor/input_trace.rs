@@ -0,0 +1,173 @@
+// Input event traces
+//
+// Every input event goes through `InputTraceEvent` before it is handled, so a
+// trace recorded with `--record-input` replays through exactly the same code
+// path on a headless instance (`--replay-input`). Traces are JSON lines with
+// a millisecond timestamp relative to compositor start.
+
+use serde::{Deserialize, Serialize};
+use smithay::reexports::calloop::{
+    timer::{TimeoutAction, Timer},
+    LoopHandle,
+};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::time::{Duration, Instant};
+
+use crate::compositor::WebWMCompositor;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputTraceEvent {
+    Key { keycode: u32, pressed: bool },
+    PointerMotion { dx: f64, dy: f64 },
+    PointerButton { button: u32, pressed: bool },
+    PointerAxis { horizontal: f64, vertical: f64 },
+    Switch { switch: SwitchKind, on: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchKind {
+    Lid,
+    TabletMode,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimedInputEvent {
+    pub time_ms: u64,
+    #[serde(flatten)]
+    pub event: InputTraceEvent,
+}
+
+/// Appends every handled input event to a trace file
+pub struct InputRecorder {
+    file: File,
+    start: Instant,
+}
+
+impl InputRecorder {
+    pub fn create(path: &str) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+
+        Ok(Self {
+            file,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, event: &InputTraceEvent) {
+        let timed = TimedInputEvent {
+            time_ms: self.start.elapsed().as_millis() as u64,
+            event: event.clone(),
+        };
+
+        // One line per event, written straight away so a crash keeps the trace
+        match serde_json::to_string(&timed) {
+            Ok(line) => {
+                if let Err(e) = writeln!(self.file, "{}", line) {
+                    eprintln!("Failed to write input trace: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Failed to serialize input event: {}", e),
+        }
+    }
+}
+
+pub fn load_trace(path: &str) -> Result<Vec<TimedInputEvent>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    parse_trace(BufReader::new(file))
+}
+
+pub fn parse_trace(reader: impl BufRead) -> Result<Vec<TimedInputEvent>, String> {
+    let mut events = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read trace: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event: TimedInputEvent = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid trace event on line {}: {}", i + 1, e))?;
+        events.push(event);
+    }
+
+    // Replay relies on timestamps never going backwards
+    events.sort_by_key(|event| event.time_ms);
+    Ok(events)
+}
+
+/// Feed a recorded trace into the compositor, keeping the original timing
+pub fn start_replay(
+    loop_handle: &LoopHandle<'static, WebWMCompositor>,
+    events: Vec<TimedInputEvent>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("▶️  Replaying {} input events", events.len());
+
+    let mut pending: VecDeque<TimedInputEvent> = events.into();
+    let start = Instant::now();
+
+    loop_handle.insert_source(Timer::immediate(), move |_, _, compositor| {
+        let elapsed = start.elapsed().as_millis() as u64;
+
+        while pending.front().is_some_and(|e| e.time_ms <= elapsed) {
+            if let Some(timed) = pending.pop_front() {
+                compositor.handle_input(timed.event);
+            }
+        }
+
+        match pending.front() {
+            Some(next) => TimeoutAction::ToDuration(Duration::from_millis(next.time_ms - elapsed)),
+            None => {
+                println!("✓ Input replay finished");
+                TimeoutAction::Drop
+            }
+        }
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trace() {
+        let trace = r#"
+{"time_ms":20,"type":"pointer_button","button":272,"pressed":true}
+{"time_ms":10,"type":"pointer_motion","dx":5.0,"dy":-2.5}
+
+{"time_ms":30,"type":"key","keycode":36,"pressed":false}
+"#;
+
+        let events = parse_trace(trace.as_bytes()).unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[0].event,
+            InputTraceEvent::PointerMotion { dx: 5.0, dy: -2.5 }
+        );
+        assert_eq!(events[1].time_ms, 20);
+        assert_eq!(
+            events[2].event,
+            InputTraceEvent::Key {
+                keycode: 36,
+                pressed: false
+            }
+        );
+
+        let lid =
+            parse_trace(r#"{"time_ms":5,"type":"switch","switch":"lid","on":true}"#.as_bytes());
+        assert_eq!(
+            lid.unwrap()[0].event,
+            InputTraceEvent::Switch {
+                switch: SwitchKind::Lid,
+                on: true
+            }
+        );
+
+        assert!(parse_trace(r#"{"time_ms":1,"type":"bogus"}"#.as_bytes()).is_err());
+    }
+}
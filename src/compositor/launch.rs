@@ -0,0 +1,270 @@
+// Exec and focus
+//
+// The exec-and-focus action spawns a command and waits up to LAUNCH_TIMEOUT
+// for its window: the first toplevel whose client has the spawned process
+// id, or, for launchers that fork or hand off to a running instance, the
+// app_id given with the action. That window is moved to the target
+// workspace, if there is one, and focused. When an app_id is given and a
+// window with it is already open, that one is focused and nothing is
+// spawned, which makes the action "launch or focus". Run-or-raise is the
+// same without a target workspace; with several windows of the app open,
+// repeating it cycles through them.
+
+use smithay::desktop::Window;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::Resource;
+use smithay::utils::SERIAL_COUNTER;
+use smithay::wayland::shell::xdg::ToplevelSurface;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+/// How long a launched command has to map its window
+pub const LAUNCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingLaunch {
+    pub command: String,
+    /// Process id of the spawned shell, which execs simple commands
+    pub pid: Option<u32>,
+    pub app_id: Option<String>,
+    pub workspace: Option<u32>,
+    /// Index among the workspace's windows to put the window at
+    pub position: Option<usize>,
+    started: Instant,
+}
+
+impl PendingLaunch {
+    fn matches(&self, pid: Option<u32>, app_id: &str) -> bool {
+        (self.pid.is_some() && self.pid == pid)
+            || self.app_id.as_deref().is_some_and(|id| id == app_id)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LaunchTracker {
+    pending: Vec<PendingLaunch>,
+}
+
+impl LaunchTracker {
+    pub fn push(
+        &mut self,
+        command: String,
+        pid: Option<u32>,
+        app_id: Option<String>,
+        workspace: Option<u32>,
+        position: Option<usize>,
+        now: Instant,
+    ) {
+        self.pending.push(PendingLaunch {
+            command,
+            pid,
+            app_id,
+            workspace,
+            position,
+            started: now,
+        });
+    }
+
+    /// Drop launches whose window never showed up, returning them
+    pub fn expire(&mut self, now: Instant) -> Vec<PendingLaunch> {
+        let (expired, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|launch| now.duration_since(launch.started) >= LAUNCH_TIMEOUT);
+        self.pending = pending;
+        expired
+    }
+
+    /// The oldest launch a window of process `pid` with `app_id` belongs
+    /// to, which is then no longer waited for
+    pub fn claim(&mut self, pid: Option<u32>, app_id: &str) -> Option<PendingLaunch> {
+        let index = self
+            .pending
+            .iter()
+            .position(|launch| launch.matches(pid, app_id))?;
+        Some(self.pending.remove(index))
+    }
+}
+
+impl WebWMCompositor {
+    /// Spawn `command` and focus its window once it appears, or focus an
+    /// open window with `app_id` instead
+    pub fn exec_and_focus(
+        &mut self,
+        command: &str,
+        app_id: Option<String>,
+        workspace: Option<u32>,
+    ) {
+        if let Some(ref app_id) = app_id {
+            if let Some(window) = self.raise_target(app_id) {
+                println!("🚀 '{}' is already open, focusing it", app_id);
+                self.focus_launched_window(&window, workspace);
+                return;
+            }
+        }
+
+        self.spawn_and_wait(command, app_id, workspace, None);
+    }
+
+    /// Spawn `command` and place its window on `workspace`, at `position`
+    /// among its windows, once it appears
+    pub fn spawn_and_wait(
+        &mut self,
+        command: &str,
+        app_id: Option<String>,
+        workspace: Option<u32>,
+        position: Option<usize>,
+    ) {
+        println!("🚀 Spawning '{}' and waiting for its window", command);
+        match Command::new("sh").arg("-c").arg(command).spawn() {
+            Ok(child) => self.launches.push(
+                command.to_string(),
+                Some(child.id()),
+                app_id,
+                workspace,
+                position,
+                Instant::now(),
+            ),
+            Err(e) => eprintln!("Failed to spawn '{}': {}", command, e),
+        }
+    }
+
+    /// Process id of the client owning `surface`
+    pub fn client_pid(&self, surface: &WlSurface) -> Option<u32> {
+        surface.client().and_then(|client| {
+            self.display_handle
+                .backend_handle()
+                .get_client_credentials(client.id())
+                .ok()
+                .map(|credentials| credentials.pid as u32)
+        })
+    }
+
+    /// Focus a window with `app_id` on any workspace, or spawn `command`
+    pub fn run_or_raise(&mut self, app_id: &str, command: &str) {
+        self.exec_and_focus(command, Some(app_id.to_string()), None);
+    }
+
+    /// The window with `app_id` to focus: the one after the focused window
+    /// if that has the app_id too, so repeated raises cycle, else the first
+    fn raise_target(&self, app_id: &str) -> Option<Window> {
+        let matching: Vec<&Window> = self
+            .workspace_manager
+            .all_workspaces()
+            .into_iter()
+            .flat_map(|ws| ws.windows.iter())
+            .filter(|window| window_app_id(window) == app_id)
+            .collect();
+
+        let next = self
+            .workspace_manager
+            .focused_window()
+            .and_then(|focused| matching.iter().position(|window| *window == focused))
+            .map_or(0, |index| (index + 1) % matching.len());
+        matching.get(next).map(|window| (*window).clone())
+    }
+
+    /// Check a new or renamed toplevel against the launches waiting for a
+    /// window
+    pub fn claim_launched_window(&mut self, surface: &ToplevelSurface) {
+        for launch in self.launches.expire(Instant::now()) {
+            eprintln!(
+                "⚠️  No window from '{}' within {}s",
+                launch.command,
+                LAUNCH_TIMEOUT.as_secs()
+            );
+        }
+
+        let Some(window) = self.window_for_surface(surface.wl_surface()) else {
+            return;
+        };
+        let pid = self.client_pid(surface.wl_surface());
+
+        if let Some(launch) = self.launches.claim(pid, &window_app_id(&window)) {
+            println!("🚀 Window of '{}' appeared", launch.command);
+            self.focus_launched_window(&window, launch.workspace);
+            if let Some(position) = launch.position {
+                if let Some(workspace) = self
+                    .workspace_manager
+                    .find_window_workspace(&window)
+                    .and_then(|id| self.workspace_manager.get_workspace_mut(id))
+                {
+                    workspace.move_window_to_position(&window, position);
+                }
+                self.relayout();
+            }
+            if let Some(id) = launch.workspace {
+                self.fill_template_slots(id);
+            }
+        }
+    }
+
+    /// Move `window` to `workspace` if given, then show and focus it
+    fn focus_launched_window(&mut self, window: &Window, workspace: Option<u32>) {
+        if let Some(target) = workspace {
+            self.workspace_manager
+                .move_window_to_workspace(window.clone(), target);
+        }
+        let Some(id) = self.workspace_manager.find_window_workspace(window) else {
+            return;
+        };
+
+        self.workspace_manager.switch_to_workspace(id);
+        if let Some(ws) = self.workspace_manager.get_workspace_mut(id) {
+            ws.focus_window(window);
+        }
+        self.relayout();
+
+        if let (Some(keyboard), Some(surface)) = (self.seat.get_keyboard(), window.wl_surface()) {
+            keyboard.set_focus(
+                self,
+                Some(surface.into_owned()),
+                SERIAL_COUNTER.next_serial(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim_by_pid_or_app_id() {
+        let now = Instant::now();
+        let mut launches = LaunchTracker::default();
+        launches.push("foot".to_string(), Some(100), None, Some(3), None, now);
+        launches.push(
+            "firefox".to_string(),
+            Some(200),
+            Some("firefox".to_string()),
+            None,
+            None,
+            now,
+        );
+
+        // Unrelated windows don't claim anything
+        assert_eq!(launches.claim(Some(300), "org.gnome.Nautilus"), None);
+
+        // Firefox's launcher script forks, so only the app_id matches
+        let firefox = launches.claim(Some(201), "firefox").unwrap();
+        assert_eq!(firefox.command, "firefox");
+
+        let foot = launches.claim(Some(100), "").unwrap();
+        assert_eq!(foot.workspace, Some(3));
+        assert_eq!(launches.claim(Some(100), ""), None);
+    }
+
+    #[test]
+    fn test_launch_timeout() {
+        let now = Instant::now();
+        let mut launches = LaunchTracker::default();
+        launches.push("slow".to_string(), Some(1), None, None, None, now);
+
+        assert!(launches.expire(now + Duration::from_secs(1)).is_empty());
+        let expired = launches.expire(now + LAUNCH_TIMEOUT);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(launches.claim(Some(1), ""), None);
+    }
+}
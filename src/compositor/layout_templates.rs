@@ -0,0 +1,359 @@
+// Workspace layout templates
+//
+// A template is a snapshot of a workspace: its layout mode and its tiles in
+// order, each tile one or more app slots (several slots are a tab group).
+// Every slot remembers the app_id that fills it and the command line its
+// process was started with. Applying a template to an empty workspace sets
+// the layout mode and launches every slot's command; windows are recognised
+// like exec-and-focus ones and sorted into their slots as they appear.
+// Tiles are always split evenly, so there are no ratios to store.
+// Templates live in `$XDG_CONFIG_HOME/webwm/templates.json`.
+
+use serde::{Deserialize, Serialize};
+use smithay::desktop::Window;
+use smithay::reexports::wayland_server::Resource;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Instant;
+
+use crate::compositor::workspace::{LayoutMode, TabGroup};
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateSlot {
+    pub app_id: String,
+    /// Shell command that starts the app
+    pub command: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutTemplate {
+    pub name: String,
+    /// "tiling", "floating" or "monocle"
+    pub layout: String,
+    /// Tiles in layout order, each holding one slot per tab
+    pub tiles: Vec<Vec<TemplateSlot>>,
+}
+
+impl LayoutTemplate {
+    pub fn slot_count(&self) -> usize {
+        self.tiles.iter().map(|tile| tile.len()).sum()
+    }
+}
+
+/// The (tile, tab) slot of each window, given their app_ids in workspace
+/// order. Every slot takes the first window with its app_id; windows left
+/// over get None.
+pub fn assign_slots(template: &LayoutTemplate, app_ids: &[String]) -> Vec<Option<(usize, usize)>> {
+    let mut assigned = vec![None; app_ids.len()];
+    for (tile, slots) in template.tiles.iter().enumerate() {
+        for (tab, slot) in slots.iter().enumerate() {
+            let free = app_ids
+                .iter()
+                .enumerate()
+                .position(|(i, app_id)| assigned[i].is_none() && *app_id == slot.app_id);
+            if let Some(i) = free {
+                assigned[i] = Some((tile, tab));
+            }
+        }
+    }
+    assigned
+}
+
+fn layout_name(mode: LayoutMode) -> &'static str {
+    match mode {
+        LayoutMode::Tiling => "tiling",
+        LayoutMode::Floating => "floating",
+        LayoutMode::Monocle => "monocle",
+        LayoutMode::MasterStack => "master-stack",
+        LayoutMode::Dwindle => "dwindle",
+        LayoutMode::Tabbed => "tabbed",
+    }
+}
+
+/// Command line of a running process, quoted for `sh -c`
+fn process_command(pid: i32) -> Option<String> {
+    let cmdline = fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    let args: Vec<String> = cmdline
+        .split(|byte| *byte == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| {
+            let arg = String::from_utf8_lossy(arg);
+            if arg.contains(|c: char| c.is_whitespace() || c == '\'') {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            } else {
+                arg.into_owned()
+            }
+        })
+        .collect();
+    (!args.is_empty()).then(|| args.join(" "))
+}
+
+#[derive(Debug, Default)]
+pub struct TemplateStore {
+    pub templates: Vec<LayoutTemplate>,
+    /// Workspaces still waiting for windows, with the template applied
+    filling: HashMap<u32, LayoutTemplate>,
+    path: Option<PathBuf>,
+}
+
+impl TemplateStore {
+    /// Load saved templates; a missing or broken file just means none
+    pub fn load() -> Self {
+        let path = crate::config::user_config_dir().map(|dir| dir.join("templates.json"));
+
+        let templates = path
+            .as_ref()
+            .filter(|path| path.exists())
+            .and_then(|path| match fs::read_to_string(path) {
+                Ok(contents) => match serde_json::from_str(&contents) {
+                    Ok(templates) => Some(templates),
+                    Err(e) => {
+                        eprintln!("⚠️  Ignoring invalid {}: {}", path.display(), e);
+                        None
+                    }
+                },
+                Err(e) => {
+                    eprintln!("⚠️  Failed to read {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            templates,
+            filling: HashMap::new(),
+            path,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LayoutTemplate> {
+        self.templates.iter().find(|t| t.name == name)
+    }
+
+    /// Add or replace a template by name and write the store to disk
+    pub fn save_template(&mut self, template: LayoutTemplate) -> Result<(), String> {
+        match self.templates.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => self.templates.push(template),
+        }
+        self.write()
+    }
+
+    pub fn remove_template(&mut self, name: &str) -> Result<bool, String> {
+        let before = self.templates.len();
+        self.templates.retain(|t| t.name != name);
+        if self.templates.len() == before {
+            return Ok(false);
+        }
+        self.write().map(|_| true)
+    }
+
+    fn write(&self) -> Result<(), String> {
+        let Some(ref path) = self.path else {
+            return Err("No config directory to store templates in".to_string());
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+
+        let json = serde_json::to_string_pretty(&self.templates)
+            .map_err(|e| format!("Failed to serialize templates: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+impl WebWMCompositor {
+    /// Snapshot the active workspace as a template named `name`
+    pub fn workspace_template(&self, name: &str) -> LayoutTemplate {
+        let workspace = self.workspace_manager.active_workspace();
+        let tiles = workspace
+            .tiles()
+            .iter()
+            .map(|tile| {
+                tile.windows
+                    .iter()
+                    .map(|window| {
+                        let app_id = window_app_id(window);
+                        let command = self
+                            .window_pid(window)
+                            .and_then(process_command)
+                            .unwrap_or_else(|| app_id.clone());
+                        TemplateSlot { app_id, command }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        LayoutTemplate {
+            name: name.to_string(),
+            layout: layout_name(workspace.layout_mode).to_string(),
+            tiles,
+        }
+    }
+
+    /// Process id of the client owning `window`
+    pub fn window_pid(&self, window: &Window) -> Option<i32> {
+        let client = window.toplevel()?.wl_surface().client()?;
+        self.display_handle
+            .backend_handle()
+            .get_client_credentials(client.id())
+            .ok()
+            .map(|credentials| credentials.pid)
+    }
+
+    /// Launch template `name` into `workspace`, or the first empty
+    /// workspace. Returns the workspace used.
+    pub fn apply_template(&mut self, name: &str, workspace: Option<u32>) -> Result<u32, String> {
+        let template = self
+            .layout_templates
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("No template named '{}'", name))?;
+
+        let id = match workspace {
+            Some(id) => id,
+            None => self
+                .workspace_manager
+                .all_workspaces()
+                .into_iter()
+                .find(|ws| ws.is_empty())
+                .map(|ws| ws.id)
+                .ok_or("No empty workspace to apply the template to")?,
+        };
+        let ws = self
+            .workspace_manager
+            .get_workspace_mut(id)
+            .ok_or_else(|| format!("Workspace {} does not exist", id))?;
+        if !ws.is_empty() {
+            return Err(format!("Workspace {} is not empty", id));
+        }
+        ws.layout_mode = LayoutMode::from(template.layout.as_str());
+
+        println!(
+            "📐 Applying template '{}' to workspace {} ({} apps)",
+            name,
+            id,
+            template.slot_count()
+        );
+        self.workspace_manager.switch_to_workspace(id);
+        self.relayout();
+
+        for slot in template.tiles.iter().flatten() {
+            match Command::new("sh").arg("-c").arg(&slot.command).spawn() {
+                Ok(child) => self.launches.push(
+                    slot.command.clone(),
+                    Some(child.id()),
+                    Some(slot.app_id.clone()),
+                    Some(id),
+                    Instant::now(),
+                ),
+                Err(e) => eprintln!("Failed to spawn '{}': {}", slot.command, e),
+            }
+        }
+        self.layout_templates.filling.insert(id, template);
+
+        Ok(id)
+    }
+
+    /// Sort the windows of a workspace being filled from a template into
+    /// their slots, forming its tab groups
+    pub fn fill_template_slots(&mut self, id: u32) {
+        let Some(template) = self.layout_templates.filling.get(&id).cloned() else {
+            return;
+        };
+        let Some(ws) = self.workspace_manager.get_workspace_mut(id) else {
+            return;
+        };
+
+        let focused = ws.focused_window().cloned();
+        let app_ids: Vec<String> = ws.windows.iter().map(window_app_id).collect();
+        let slots = assign_slots(&template, &app_ids);
+
+        let mut placed: Vec<((usize, usize), Window)> = Vec::new();
+        let mut rest = Vec::new();
+        for (window, slot) in ws.windows.drain(..).zip(&slots) {
+            match slot {
+                Some(slot) => placed.push((*slot, window)),
+                None => rest.push(window),
+            }
+        }
+        placed.sort_by_key(|(slot, _)| *slot);
+
+        ws.tab_groups = (0..template.tiles.len())
+            .map(|tile| TabGroup {
+                windows: placed
+                    .iter()
+                    .filter(|((t, _), _)| *t == tile)
+                    .map(|(_, window)| window.clone())
+                    .collect(),
+                active: 0,
+            })
+            .filter(|group| group.windows.len() > 1)
+            .collect();
+        ws.windows = placed.iter().map(|(_, window)| window.clone()).collect();
+        ws.windows.extend(rest);
+        ws.focused_window_idx = focused
+            .and_then(|focused| ws.windows.iter().position(|w| *w == focused))
+            .or(ws.focused_window_idx);
+
+        if slots.iter().flatten().count() == template.slot_count() {
+            println!("📐 Template '{}' complete", template.name);
+            self.layout_templates.filling.remove(&id);
+        }
+        self.relayout();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(app_id: &str) -> TemplateSlot {
+        TemplateSlot {
+            app_id: app_id.to_string(),
+            command: app_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_assign_slots() {
+        // Editor on the left, two terminals tabbed on the right
+        let template = LayoutTemplate {
+            name: "code".to_string(),
+            layout: "tiling".to_string(),
+            tiles: vec![vec![slot("code")], vec![slot("foot"), slot("foot")]],
+        };
+        assert_eq!(template.slot_count(), 3);
+
+        let app_ids: Vec<String> = ["foot", "pavucontrol", "code", "foot", "foot"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            assign_slots(&template, &app_ids),
+            vec![Some((1, 0)), None, Some((0, 0)), Some((1, 1)), None]
+        );
+    }
+
+    #[test]
+    fn test_template_json() {
+        let template = LayoutTemplate {
+            name: "web".to_string(),
+            layout: "monocle".to_string(),
+            tiles: vec![vec![TemplateSlot {
+                app_id: "firefox".to_string(),
+                command: "firefox --new-window".to_string(),
+            }]],
+        };
+        let json = serde_json::to_string(&template).unwrap();
+        assert_eq!(
+            serde_json::from_str::<LayoutTemplate>(&json).unwrap(),
+            template
+        );
+    }
+}
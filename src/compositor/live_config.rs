@@ -0,0 +1,150 @@
+// Live configuration changes
+//
+// Keybindings, theme variables and bar widgets can be changed while the
+// compositor runs, through IPC (`keybind-set`, `theme-set`, `bar-widgets`,
+// ...) and the web settings page, which sends the same commands. Changes
+// take effect right away but are not written back to desktop.xml,
+// style.css or config.js. Every change emits a `config-changed` event
+// naming the section that changed.
+
+use serde_json::json;
+
+use crate::compositor::bar::BarRenderer;
+use crate::compositor::WebWMCompositor;
+use crate::config::{
+    extract_theme_from_css, parse_key_combo, parse_key_sequence, Action, Keybinding, Widget,
+};
+
+/// `Super+Shift+Return` style name of a keybinding, or `Super+space, w, f`
+/// for a chord
+pub fn keybinding_combo(binding: &Keybinding) -> String {
+    let first = binding
+        .modifiers
+        .iter()
+        .map(|m| m.as_str())
+        .chain(std::iter::once(binding.key.as_str()))
+        .collect::<Vec<_>>()
+        .join("+");
+    std::iter::once(first)
+        .chain(binding.sequence.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether two combos name the same keys, ignoring case, spacing and
+/// modifier order
+fn same_combo(a: &str, b: &str) -> bool {
+    let normalize = |combo: &str| {
+        combo
+            .to_lowercase()
+            .split(',')
+            .map(|stroke| {
+                let (mut modifiers, key) = parse_key_combo(stroke.trim());
+                modifiers.sort();
+                (modifiers, key)
+            })
+            .collect::<Vec<_>>()
+    };
+    normalize(a) == normalize(b)
+}
+
+impl WebWMCompositor {
+    /// Bind `combo` to `action`, replacing what it was bound to
+    pub fn set_keybinding(&mut self, combo: &str, action: Action) -> Result<(), String> {
+        let (modifiers, key, sequence) = parse_key_sequence(combo);
+        if key.is_empty() || sequence.iter().any(|stroke| stroke.is_empty()) {
+            return Err(format!("Invalid key combo '{}'", combo));
+        }
+
+        let binding = Keybinding {
+            key,
+            modifiers,
+            sequence,
+            action,
+        };
+        let bindings = &mut self.config.keybindings;
+        match bindings
+            .iter_mut()
+            .find(|b| same_combo(&keybinding_combo(b), combo))
+        {
+            Some(existing) => *existing = binding,
+            None => bindings.push(binding),
+        }
+
+        println!("⌨️  Bound {}", combo);
+        self.emit_event("config-changed", json!({ "section": "keybindings" }));
+        Ok(())
+    }
+
+    pub fn remove_keybinding(&mut self, combo: &str) -> Result<(), String> {
+        let before = self.config.keybindings.len();
+        self.config
+            .keybindings
+            .retain(|b| !same_combo(&keybinding_combo(b), combo));
+        if self.config.keybindings.len() == before {
+            return Err(format!("Nothing is bound to '{}'", combo));
+        }
+
+        println!("⌨️  Unbound {}", combo);
+        self.emit_event("config-changed", json!({ "section": "keybindings" }));
+        Ok(())
+    }
+
+    /// Change a theme variable such as `--accent`, restyling everything
+    /// that uses it
+    pub fn set_theme_variable(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let Some(ref mut stylesheet) = self.stylesheet else {
+            return Err("No stylesheet loaded".to_string());
+        };
+        stylesheet.set_variable(name, value)?;
+
+        self.config.theme = extract_theme_from_css(stylesheet);
+        self.config.stylesheet = Some(stylesheet.clone());
+
+        println!("🎨 Theme: {} = {}", name, value);
+        self.emit_event("config-changed", json!({ "section": "theme" }));
+        Ok(())
+    }
+
+    /// Replace the widgets of the bar with id `bar`
+    pub fn set_bar_widgets(&mut self, bar: &str, widgets: Vec<Widget>) -> Result<(), String> {
+        let Some(ref mut desktop) = self.config.desktop else {
+            return Err("No bars configured".to_string());
+        };
+        let Some(config) = desktop.bars.iter_mut().find(|b| b.id == bar) else {
+            return Err(format!("No bar with id '{}'", bar));
+        };
+        config.widgets = widgets;
+
+        let bars = desktop.bars.clone();
+        self.bar_renderer = Some(BarRenderer::new(bars, self.layout_size().w));
+
+        println!("📊 Bar '{}' widgets updated", bar);
+        self.emit_event("config-changed", json!({ "section": "bars" }));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_combo() {
+        assert!(same_combo("Super+Shift+Return", "shift+super+return"));
+        assert!(!same_combo("Super+Return", "Super+Shift+Return"));
+        assert!(!same_combo("Super+a", "Super+b"));
+        assert!(same_combo("Super+space, w, f", "super+space,w,f"));
+        assert!(!same_combo("Super+space, w, f", "Super+space, w"));
+
+        let mut binding = Keybinding {
+            key: "q".to_string(),
+            modifiers: vec!["Super".to_string(), "Shift".to_string()],
+            sequence: Vec::new(),
+            action: Action::Close,
+        };
+        assert_eq!(keybinding_combo(&binding), "Super+Shift+q");
+        binding.sequence = vec!["w".to_string(), "Shift+f".to_string()];
+        assert_eq!(keybinding_combo(&binding), "Super+Shift+q, w, Shift+f");
+    }
+}
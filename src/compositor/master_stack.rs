@@ -0,0 +1,143 @@
+// Master-stack layout
+//
+// The first tile of the workspace is the master and takes `<master-ratio>`
+// of the width on the left; the other tiles share the right-hand column,
+// one above the other. A lone tile gets the whole width. Promoting a window
+// makes its tile the master, and the ratio can be nudged per workspace
+// without touching the config. Stacked tiles share the column by their
+// weights, so resizing one takes room from its neighbor.
+
+use smithay::utils::{Logical, Point, Rectangle, Size};
+
+use crate::compositor::flex_layout::split;
+use crate::compositor::WebWMCompositor;
+use crate::config::FlexDirection;
+
+/// The master tile never gets less or more of the width than this
+const MIN_MASTER_RATIO: f32 = 0.1;
+const MAX_MASTER_RATIO: f32 = 0.9;
+
+/// `<master-ratio>` when there's no desktop config
+const DEFAULT_MASTER_RATIO: f32 = 0.55;
+
+impl WebWMCompositor {
+    /// Share of the width the active workspace's master tile gets
+    pub fn master_ratio(&self) -> f32 {
+        self.workspace_manager
+            .active_workspace()
+            .master_ratio
+            .or_else(|| {
+                self.config
+                    .desktop
+                    .as_ref()
+                    .map(|desktop| desktop.layout.master_ratio)
+            })
+            .unwrap_or(DEFAULT_MASTER_RATIO)
+            .clamp(MIN_MASTER_RATIO, MAX_MASTER_RATIO)
+    }
+
+    /// Grow (or with a negative `delta`, shrink) the master tile of the
+    /// active workspace
+    pub fn adjust_master_ratio(&mut self, delta: f32) {
+        let ratio = (self.master_ratio() + delta).clamp(MIN_MASTER_RATIO, MAX_MASTER_RATIO);
+        let workspace = self.workspace_manager.active_workspace_mut();
+        workspace.master_ratio = Some(ratio);
+        println!(
+            "📐 Master ratio on workspace {} is now {:.2}",
+            workspace.id, ratio
+        );
+        self.relayout();
+    }
+
+    /// Make the focused window's tile the master
+    pub fn promote_to_master(&mut self) {
+        if self
+            .workspace_manager
+            .active_workspace_mut()
+            .promote_focused()
+        {
+            self.relayout();
+        }
+    }
+
+    /// Move the divider between master and stack by `pixels` if `point` is
+    /// on its gap (give or take `margin`); returns the new ratio
+    pub fn resize_master_at(
+        &mut self,
+        point: Point<i32, Logical>,
+        margin: i32,
+        pixels: f64,
+    ) -> Option<f32> {
+        if self.workspace_manager.active_workspace().tiles().len() < 2 {
+            return None;
+        }
+        let output_size = self.layout_size();
+        let gaps = self.get_gaps();
+        let area = self.tiled_area(output_size);
+        let columns_width = area.size.w - gaps;
+        let master_width = (columns_width as f32 * self.master_ratio()).round() as i32;
+        let gap = Rectangle::from_loc_and_size(
+            (area.loc.x + master_width - margin, self.bar_height()),
+            (gaps + margin * 2, output_size.h - self.bar_height()),
+        );
+        if !gap.contains(point) {
+            return None;
+        }
+        self.move_master_divider(pixels)
+    }
+
+    /// Move the divider between master and stack right by `pixels` (left
+    /// with a negative amount); returns the new ratio
+    pub fn move_master_divider(&mut self, pixels: f64) -> Option<f32> {
+        if self.workspace_manager.active_workspace().tiles().len() < 2 {
+            return None;
+        }
+        let columns_width = self.tiled_area(self.layout_size()).size.w - self.get_gaps();
+        let ratio = (self.master_ratio() + pixels as f32 / columns_width.max(1) as f32)
+            .clamp(MIN_MASTER_RATIO, MAX_MASTER_RATIO);
+        self.workspace_manager.active_workspace_mut().master_ratio = Some(ratio);
+        Some(ratio)
+    }
+
+    pub fn layout_master_stack(&mut self, output_size: Size<i32, Logical>, gaps: i32) {
+        let tiles = self.workspace_manager.active_workspace().tiles();
+        let Some((master, stack)) = tiles.split_first() else {
+            return;
+        };
+
+        let area = self.tiled_area(output_size);
+        let (x, y, height) = (area.loc.x, area.loc.y, area.size.h);
+
+        if stack.is_empty() {
+            self.map_tile(master, area.loc, area.size);
+        } else {
+            let columns_width = area.size.w - gaps;
+            let master_width = (columns_width as f32 * self.master_ratio()).round() as i32;
+            let stack_width = columns_width - master_width;
+            let stack_x = x + master_width + gaps;
+            let column = Rectangle::from_loc_and_size((stack_x, y), (stack_width, height));
+            let workspace = self.workspace_manager.active_workspace();
+            let weights: Vec<f32> = stack
+                .iter()
+                .map(|tile| workspace.tile_weight(tile))
+                .collect();
+
+            self.map_tile(master, (x, y).into(), (master_width, height).into());
+            for (tile, geometry) in
+                stack
+                    .iter()
+                    .zip(split(column, FlexDirection::Column, &weights, gaps))
+            {
+                self.map_tile(tile, geometry.loc, geometry.size);
+            }
+        }
+
+        let active_ws = self.workspace_manager.active_workspace();
+        println!(
+            "Relayout: {} windows in master-stack mode on workspace {} (ratio: {:.2})",
+            active_ws.len(),
+            active_ws.id,
+            self.master_ratio()
+        );
+    }
+}
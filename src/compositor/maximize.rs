@@ -0,0 +1,90 @@
+// Maximize
+//
+// A window asking to be maximized fills the area below the bar, without
+// gaps, on top of the other windows of its workspace. Unlike fullscreen the
+// bar stays visible. The window keeps its place in the workspace, so
+// unmaximizing (or unfullscreening) just lays it out where it was before.
+// One window per workspace is maximized at a time; maximizing another one
+// tells the previous window it no longer is.
+
+use smithay::desktop::Window;
+use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
+use smithay::utils::{Logical, Size};
+
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+fn set_maximized_state(window: &Window, enabled: bool) {
+    if let Some(toplevel) = window.toplevel() {
+        toplevel.with_pending_state(|state| {
+            if enabled {
+                state.states.set(xdg_toplevel::State::Maximized);
+            } else {
+                state.states.unset(xdg_toplevel::State::Maximized);
+            }
+        });
+    }
+}
+
+impl WebWMCompositor {
+    pub fn set_maximized(&mut self, window: &Window, enabled: bool) {
+        let Some(toplevel) = window.toplevel() else {
+            return;
+        };
+        set_maximized_state(window, enabled);
+
+        let mut previous = None;
+        if let Some(workspace) = self
+            .workspace_manager
+            .find_window_workspace(window)
+            .and_then(|id| self.workspace_manager.get_workspace_mut(id))
+        {
+            if enabled {
+                previous = workspace
+                    .maximized_window
+                    .replace(window.clone())
+                    .filter(|previous| previous != window);
+            } else if workspace.maximized_window.as_ref() == Some(window) {
+                workspace.maximized_window = None;
+            }
+        }
+        if let Some(previous) = &previous {
+            set_maximized_state(previous, false);
+        }
+
+        println!(
+            "🔲 Window '{}' {}",
+            window_app_id(window),
+            if enabled { "maximized" } else { "unmaximized" }
+        );
+
+        self.relayout();
+        // Windows on other workspaces aren't configured by the relayout
+        toplevel.send_pending_configure();
+        if let Some(toplevel) = previous.as_ref().and_then(|previous| previous.toplevel()) {
+            toplevel.send_pending_configure();
+        }
+    }
+
+    /// Put the active workspace's maximized window on top of the layout,
+    /// filling the area below the bar
+    pub fn layout_maximized(&mut self, output_size: Size<i32, Logical>) {
+        let workspace = self.workspace_manager.active_workspace();
+        let Some(window) = workspace.maximized_window.clone() else {
+            return;
+        };
+        // Sized by layout_fullscreen instead
+        if workspace.fullscreen_window.as_ref() == Some(&window) {
+            return;
+        }
+
+        let bar_height = self.bar_height();
+        self.space
+            .map_element(window.clone(), (0, bar_height), true);
+        if let Some(toplevel) = window.toplevel() {
+            toplevel.with_pending_state(|state| {
+                state.size = Some((output_size.w, output_size.h - bar_height).into());
+            });
+            toplevel.send_configure();
+        }
+    }
+}
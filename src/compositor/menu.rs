@@ -0,0 +1,385 @@
+// Overlay menus
+//
+// A menu drawn by the compositor: a column of items, some with a check mark
+// and some opening a submenu beside them. Hovering an item highlights it
+// and opens its submenu; clicking picks it. From the keyboard, Up and Down
+// move the highlight, Right or Return open a submenu, Left or Escape close
+// it again and Return picks the item. A menu doesn't act on what is picked:
+// each item carries a value that is handed back to whoever opened it.
+//
+// Menus stay inside the area they are opened in, and submenus flip to the
+// left when there is no room on the right. Colors come from the
+// stylesheet's `.menu` and `.menu-item:selected` rules.
+
+use smithay::input::keyboard::keysyms;
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::text;
+use crate::compositor::ui::{dimmed, list_step, rect, Canvas, Style};
+use crate::config::StyleSheet;
+
+const ITEM_HEIGHT: i32 = 24;
+const PADDING: i32 = 4;
+/// Room left of the labels for check marks
+const CHECK_WIDTH: i32 = 22;
+/// Room right of the labels for submenu arrows
+const ARROW_WIDTH: i32 = 24;
+const MIN_WIDTH: i32 = 140;
+const BORDER_WIDTH: i32 = 1;
+const FONT_SIZE: u32 = 13;
+
+const DEFAULT_STYLE: Style = Style::new(
+    [0.11, 0.11, 0.18, 0.97],
+    [0.8, 0.83, 0.96, 1.0],
+    [0.27, 0.28, 0.35, 1.0],
+);
+const DEFAULT_SELECTED_STYLE: Style = Style::new(
+    [0.54, 0.71, 0.98, 1.0], // #89b4fa
+    [0.11, 0.11, 0.18, 1.0],
+    [0.54, 0.71, 0.98, 1.0],
+);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuItem<T> {
+    pub label: String,
+    /// Handed back when the item is picked; None for submenu entries
+    pub value: Option<T>,
+    /// Some for items that can be on or off
+    pub checked: Option<bool>,
+    pub submenu: Vec<MenuItem<T>>,
+}
+
+impl<T> MenuItem<T> {
+    pub fn new(label: impl Into<String>, value: T) -> Self {
+        Self {
+            label: label.into(),
+            value: Some(value),
+            checked: None,
+            submenu: Vec::new(),
+        }
+    }
+
+    pub fn toggle(label: impl Into<String>, value: T, checked: bool) -> Self {
+        Self {
+            checked: Some(checked),
+            ..Self::new(label, value)
+        }
+    }
+
+    pub fn submenu(label: impl Into<String>, items: Vec<MenuItem<T>>) -> Self {
+        Self {
+            label: label.into(),
+            value: None,
+            checked: None,
+            submenu: items,
+        }
+    }
+}
+
+/// What a click or key press did to a menu
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuEvent<T> {
+    /// An item was picked; the menu is done
+    Picked(T),
+    /// The menu was dismissed
+    Closed,
+}
+
+#[derive(Debug, Clone)]
+pub struct Menu<T> {
+    pub items: Vec<MenuItem<T>>,
+    pub geometry: Rectangle<i32, Logical>,
+    /// The area the menu and its submenus are kept in
+    pub bounds: Rectangle<i32, Logical>,
+    /// Highlighted item
+    pub selected: Option<usize>,
+    /// Open submenu, belonging to the selected item
+    pub submenu: Option<Box<Menu<T>>>,
+}
+
+impl<T: Clone> Menu<T> {
+    /// A menu with its top left corner at `position`, moved as needed to
+    /// fit in `bounds`
+    pub fn new(
+        items: Vec<MenuItem<T>>,
+        position: Point<i32, Logical>,
+        bounds: Rectangle<i32, Logical>,
+    ) -> Self {
+        let width = items
+            .iter()
+            .map(|item| text::measure(&item.label, FONT_SIZE))
+            .max()
+            .unwrap_or(0)
+            + CHECK_WIDTH
+            + ARROW_WIDTH;
+        let width = width.max(MIN_WIDTH);
+        let height = items.len() as i32 * ITEM_HEIGHT + 2 * PADDING;
+
+        let right = bounds.loc.x + bounds.size.w;
+        let bottom = bounds.loc.y + bounds.size.h;
+        let x = position.x.min(right - width).max(bounds.loc.x);
+        let y = position.y.min(bottom - height).max(bounds.loc.y);
+
+        Self {
+            items,
+            geometry: Rectangle::from_loc_and_size((x, y), (width, height)),
+            bounds,
+            selected: None,
+            submenu: None,
+        }
+    }
+
+    fn item_geometry(&self, index: usize) -> Rectangle<i32, Logical> {
+        Rectangle::from_loc_and_size(
+            (
+                self.geometry.loc.x,
+                self.geometry.loc.y + PADDING + index as i32 * ITEM_HEIGHT,
+            ),
+            (self.geometry.size.w, ITEM_HEIGHT),
+        )
+    }
+
+    fn item_at(&self, point: Point<i32, Logical>) -> Option<usize> {
+        (0..self.items.len()).find(|&i| self.item_geometry(i).contains(point))
+    }
+
+    /// Whether `point` is on this menu or one of its open submenus
+    pub fn contains(&self, point: Point<i32, Logical>) -> bool {
+        self.geometry.contains(point)
+            || self
+                .submenu
+                .as_ref()
+                .is_some_and(|submenu| submenu.contains(point))
+    }
+
+    /// Highlight item `index`, opening its submenu or closing another one
+    fn select(&mut self, index: usize) {
+        if self.selected == Some(index) && self.submenu.is_some() {
+            return;
+        }
+        self.selected = Some(index);
+        self.submenu = None;
+
+        let item = &self.items[index];
+        if item.submenu.is_empty() {
+            return;
+        }
+        let row = self.item_geometry(index);
+        let mut submenu = Menu::new(
+            item.submenu.clone(),
+            (row.loc.x + row.size.w, row.loc.y - PADDING).into(),
+            self.bounds,
+        );
+        // No room on the right: open to the left instead
+        if submenu.geometry.loc.x < row.loc.x + row.size.w {
+            submenu.geometry.loc.x = (row.loc.x - submenu.geometry.size.w).max(self.bounds.loc.x);
+        }
+        self.submenu = Some(Box::new(submenu));
+    }
+
+    /// Highlight the item under the pointer
+    pub fn motion(&mut self, point: Point<i32, Logical>) {
+        if let Some(ref mut submenu) = self.submenu {
+            if submenu.contains(point) {
+                submenu.motion(point);
+                return;
+            }
+        }
+        if let Some(index) = self.item_at(point) {
+            self.select(index);
+        }
+    }
+
+    /// Returns None when the click stays in the menu without picking
+    /// anything, like a click on a submenu entry
+    pub fn click(&mut self, point: Point<i32, Logical>) -> Option<MenuEvent<T>> {
+        if let Some(ref mut submenu) = self.submenu {
+            if submenu.contains(point) {
+                return submenu.click(point);
+            }
+        }
+        if !self.geometry.contains(point) {
+            return Some(MenuEvent::Closed);
+        }
+
+        let index = self.item_at(point)?;
+        self.select(index);
+        self.items[index].value.clone().map(MenuEvent::Picked)
+    }
+
+    /// Whether the keyboard is in the open submenu rather than this menu
+    fn in_submenu(&self) -> bool {
+        self.submenu
+            .as_ref()
+            .is_some_and(|submenu| submenu.selected.is_some())
+    }
+
+    /// Handle a key press. Returns None when the menu stays open.
+    pub fn key(&mut self, keysym: u32) -> Option<MenuEvent<T>> {
+        if self.in_submenu() {
+            let submenu = self.submenu.as_mut()?;
+            let closes = matches!(keysym, keysyms::KEY_Left | keysyms::KEY_Escape);
+            if closes && !submenu.in_submenu() {
+                self.submenu = None;
+                return None;
+            }
+            return submenu.key(keysym);
+        }
+
+        let count = self.items.len();
+        if count == 0 {
+            return Some(MenuEvent::Closed);
+        }
+        match keysym {
+            keysyms::KEY_Escape => return Some(MenuEvent::Closed),
+            keysyms::KEY_Down | keysyms::KEY_Up => {
+                let forward = keysym == keysyms::KEY_Down;
+                if let Some(index) = list_step(self.selected, count, forward) {
+                    self.select(index);
+                }
+            }
+            keysyms::KEY_Right | keysyms::KEY_Return => {
+                let index = self.selected?;
+                // Reopens a submenu closed with Left
+                if self.submenu.is_none() {
+                    self.select(index);
+                }
+                if let Some(ref mut submenu) = self.submenu {
+                    submenu.select(0);
+                    return None;
+                }
+                if keysym == keysyms::KEY_Return {
+                    return self.items[index].value.clone().map(MenuEvent::Picked);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// The menu and its open submenus
+    pub fn render(&self, stylesheet: Option<&StyleSheet>) -> Vec<BarElement> {
+        let mut canvas = Canvas::new();
+        self.draw(&mut canvas, stylesheet);
+        canvas.finish()
+    }
+
+    fn draw(&self, canvas: &mut Canvas, stylesheet: Option<&StyleSheet>) {
+        let style = Style::load(stylesheet, ".menu", DEFAULT_STYLE);
+        let selected = Style::load(stylesheet, ".menu-item:selected", DEFAULT_SELECTED_STYLE);
+
+        canvas.panel(self.geometry, &style, BORDER_WIDTH);
+        for (i, item) in self.items.iter().enumerate() {
+            let row = self.item_geometry(i);
+            let (x, y) = (row.loc.x, row.loc.y);
+            let color = if self.selected == Some(i) {
+                canvas.fill(
+                    rect(x + 2, y, row.size.w - 4, ITEM_HEIGHT),
+                    selected.background,
+                );
+                selected.color
+            } else {
+                style.color
+            };
+
+            match item.checked {
+                Some(true) => canvas.fill(rect(x + 8, y + 8, 8, 8), color),
+                Some(false) => canvas.fill(rect(x + 8, y + 8, 8, 8), dimmed(color, 0.3)),
+                None => {}
+            }
+            canvas.text(
+                (x + CHECK_WIDTH, y + 5).into(),
+                item.label.as_str(),
+                color,
+                FONT_SIZE,
+            );
+            if !item.submenu.is_empty() {
+                canvas.text(
+                    (x + row.size.w - ARROW_WIDTH + 8, y + 5).into(),
+                    ">",
+                    color,
+                    FONT_SIZE,
+                );
+            }
+        }
+
+        if let Some(ref submenu) = self.submenu {
+            submenu.draw(canvas, stylesheet);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu() -> Menu<&'static str> {
+        Menu::new(
+            vec![
+                MenuItem::new("Close", "close"),
+                MenuItem::submenu(
+                    "Move to",
+                    vec![MenuItem::new("One", "one"), MenuItem::new("Two", "two")],
+                ),
+                MenuItem::toggle("Pinned", "pin", true),
+            ],
+            (1900, 10).into(),
+            Rectangle::from_loc_and_size((0, 0), (1920, 1080)),
+        )
+    }
+
+    #[test]
+    fn test_menu_pointer() {
+        let mut menu = menu();
+        // Pushed back inside the bounds
+        assert_eq!(menu.geometry.loc.x + menu.geometry.size.w, 1920);
+
+        let row = |menu: &Menu<&str>, i: usize| {
+            let g = menu.item_geometry(i);
+            Point::from((g.loc.x + 10, g.loc.y + 10))
+        };
+
+        // Hovering the submenu entry opens it, on the left for lack of room
+        menu.motion(row(&menu, 1));
+        let submenu = menu.submenu.as_ref().expect("submenu opened");
+        assert!(submenu.geometry.loc.x < menu.geometry.loc.x);
+        let two = row(submenu, 1);
+        assert_eq!(menu.click(row(&menu, 1)), None);
+        assert_eq!(menu.click(two), Some(MenuEvent::Picked("two")));
+
+        // Other items close it again
+        menu.motion(row(&menu, 0));
+        assert!(menu.submenu.is_none());
+        assert_eq!(menu.click(row(&menu, 2)), Some(MenuEvent::Picked("pin")));
+        assert_eq!(menu.click((10, 500).into()), Some(MenuEvent::Closed));
+    }
+
+    #[test]
+    fn test_menu_keys() {
+        let mut menu = menu();
+        assert_eq!(menu.key(keysyms::KEY_Down), None);
+        assert_eq!(menu.selected, Some(0));
+        assert_eq!(menu.key(keysyms::KEY_Up), None);
+        assert_eq!(menu.selected, Some(2));
+
+        // Into the submenu and back out
+        menu.key(keysyms::KEY_Down);
+        menu.key(keysyms::KEY_Down);
+        assert_eq!(menu.selected, Some(1));
+        menu.key(keysyms::KEY_Right);
+        assert_eq!(menu.submenu.as_ref().unwrap().selected, Some(0));
+        menu.key(keysyms::KEY_Escape);
+        assert!(menu.submenu.is_none());
+        assert_eq!(menu.selected, Some(1));
+
+        menu.key(keysyms::KEY_Return);
+        menu.key(keysyms::KEY_Down);
+        assert_eq!(
+            menu.key(keysyms::KEY_Return),
+            Some(MenuEvent::Picked("two"))
+        );
+        assert_eq!(menu.key(keysyms::KEY_Escape), None);
+        assert_eq!(menu.key(keysyms::KEY_Escape), Some(MenuEvent::Closed));
+    }
+}
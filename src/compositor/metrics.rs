@@ -0,0 +1,222 @@
+// Prometheus metrics
+//
+// Frame times, dropped frames, client and window counts, bar widget update
+// times and workspace switches in the Prometheus text format, for people
+// who already monitor their workstation. `webwm-msg metrics` prints them,
+// and `<metrics port="9477" />` under `<performance>` serves them at
+// http://127.0.0.1:9477/metrics for scraping.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::compositor::profiler::RenderStage;
+use crate::compositor::WebWMCompositor;
+
+/// Builds the text exposition format, one metric family at a time
+#[derive(Default)]
+pub struct MetricsWriter {
+    text: String,
+}
+
+impl MetricsWriter {
+    /// Start a family; `kind` is `gauge` or `counter`
+    pub fn family(&mut self, name: &str, kind: &str, help: &str) -> &mut Self {
+        let _ = writeln!(self.text, "# HELP {} {}", name, help);
+        let _ = writeln!(self.text, "# TYPE {} {}", name, kind);
+        self
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) -> &mut Self {
+        self.text.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape_label(value)))
+                .collect();
+            let _ = write!(self.text, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.text, " {}", value);
+        self
+    }
+
+    pub fn finish(self) -> String {
+        self.text
+    }
+}
+
+/// Label values are quoted; backslashes, quotes and newlines are escaped
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs_f64()
+}
+
+impl WebWMCompositor {
+    pub fn prometheus_metrics(&self) -> String {
+        let mut metrics = MetricsWriter::default();
+
+        metrics
+            .family("webwm_info", "gauge", "WebWM version")
+            .sample("webwm_info", &[("version", env!("CARGO_PKG_VERSION"))], 1.0);
+
+        let frame = self.profiler.frame();
+        metrics
+            .family("webwm_frames_rendered_total", "counter", "Frames rendered")
+            .sample("webwm_frames_rendered_total", &[], frame.samples as f64)
+            .family(
+                "webwm_frame_time_seconds",
+                "gauge",
+                "Render time per frame, moving average",
+            )
+            .sample("webwm_frame_time_seconds", &[], seconds(frame.average))
+            .family(
+                "webwm_frame_time_max_seconds",
+                "gauge",
+                "Longest render time of a frame",
+            )
+            .sample("webwm_frame_time_max_seconds", &[], seconds(frame.max));
+
+        metrics.family(
+            "webwm_render_stage_seconds",
+            "gauge",
+            "Time per render stage, moving average",
+        );
+        for stage in RenderStage::ALL {
+            metrics.sample(
+                "webwm_render_stage_seconds",
+                &[("stage", stage.name())],
+                seconds(self.profiler.stage(stage).average),
+            );
+        }
+
+        let mut outputs: Vec<_> = self.frame_pacing.outputs.iter().collect();
+        outputs.sort_by_key(|(name, _)| name.as_str());
+        metrics.family(
+            "webwm_output_frames_total",
+            "counter",
+            "Frames presented per output",
+        );
+        for (name, stats) in &outputs {
+            metrics.sample(
+                "webwm_output_frames_total",
+                &[("output", name.as_str())],
+                stats.frames as f64,
+            );
+        }
+        metrics.family(
+            "webwm_output_dropped_frames_total",
+            "counter",
+            "Vblanks missed per output",
+        );
+        for (name, stats) in &outputs {
+            metrics.sample(
+                "webwm_output_dropped_frames_total",
+                &[("output", name.as_str())],
+                stats.missed_vblanks as f64,
+            );
+        }
+        metrics.family(
+            "webwm_output_latency_seconds",
+            "gauge",
+            "Time from render to presentation per output, moving average",
+        );
+        for (name, stats) in &outputs {
+            metrics.sample(
+                "webwm_output_latency_seconds",
+                &[("output", name.as_str())],
+                seconds(stats.average_latency),
+            );
+        }
+
+        metrics
+            .family(
+                "webwm_clients",
+                "gauge",
+                "Wayland clients with surfaces or buffers",
+            )
+            .sample(
+                "webwm_clients",
+                &[],
+                self.client_monitor.client_count() as f64,
+            )
+            .family("webwm_windows", "gauge", "Mapped windows")
+            .sample("webwm_windows", &[], self.space.elements().count() as f64)
+            .family(
+                "webwm_workspace_switches_total",
+                "counter",
+                "Switches to another workspace",
+            )
+            .sample(
+                "webwm_workspace_switches_total",
+                &[],
+                self.workspace_manager.switch_count() as f64,
+            );
+
+        if let Some(ref bar_renderer) = self.bar_renderer {
+            let mut widgets: Vec<_> = bar_renderer.widget_times().into_iter().collect();
+            widgets.sort_by_key(|(name, _)| *name);
+            metrics.family(
+                "webwm_bar_widget_update_seconds",
+                "gauge",
+                "Time to update a bar widget, moving average",
+            );
+            for (name, stats) in &widgets {
+                metrics.sample(
+                    "webwm_bar_widget_update_seconds",
+                    &[("widget", *name)],
+                    seconds(stats.average),
+                );
+            }
+            metrics.family(
+                "webwm_bar_widget_updates_total",
+                "counter",
+                "Bar widget updates",
+            );
+            for (name, stats) in &widgets {
+                metrics.sample(
+                    "webwm_bar_widget_updates_total",
+                    &[("widget", *name)],
+                    stats.samples as f64,
+                );
+            }
+        }
+
+        metrics.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_writer() {
+        let mut metrics = MetricsWriter::default();
+        metrics
+            .family("webwm_clients", "gauge", "Connected Wayland clients")
+            .sample("webwm_clients", &[], 3.0)
+            .family("webwm_output_frames_total", "counter", "Frames")
+            .sample("webwm_output_frames_total", &[("output", "DP-1")], 120.0)
+            .sample(
+                "webwm_output_frames_total",
+                &[("output", "odd\"name\\")],
+                0.5,
+            );
+
+        assert_eq!(
+            metrics.finish(),
+            "# HELP webwm_clients Connected Wayland clients\n\
+             # TYPE webwm_clients gauge\n\
+             webwm_clients 3\n\
+             # HELP webwm_output_frames_total Frames\n\
+             # TYPE webwm_output_frames_total counter\n\
+             webwm_output_frames_total{output=\"DP-1\"} 120\n\
+             webwm_output_frames_total{output=\"odd\\\"name\\\\\"} 0.5\n"
+        );
+    }
+}
@@ -1,41 +1,149 @@
+pub mod app_workspaces;
+pub mod audio;
 pub mod backend;
 pub mod bar;
 pub mod bar_element;
 pub mod bar_renderer;
+pub mod binding_modes;
+pub mod blur;
+pub mod calendar;
+pub mod chords;
+pub mod client_stats;
+pub mod close_confirm;
+pub mod color;
+pub mod config_reload;
+pub mod content_type;
+pub mod cursor;
+pub mod cursor_theme;
+pub mod data_device;
+pub mod dialogs;
+pub mod directional_focus;
 pub mod drm_backend;
+pub mod dwindle;
+pub mod flex_layout;
+pub mod floating_drag;
+pub mod floating_stack;
+pub mod floating_windows;
+pub mod frame_stats;
 pub mod full_drm_backend;
+pub mod fullscreen;
+pub mod gamma;
+pub mod grid_layout;
+pub mod headless_backend;
+pub mod hints;
 pub mod input;
+pub mod input_inhibit;
+pub mod input_trace;
+pub mod launch;
+pub mod layout_templates;
+pub mod live_config;
+pub mod master_stack;
+pub mod maximize;
+pub mod menu;
+pub mod metrics;
+pub mod output_bars;
+pub mod output_events;
+pub mod output_focus;
+pub mod output_format;
+pub mod output_profiles;
+pub mod output_zoom;
+pub mod planes;
+pub mod pointer_barriers;
+pub mod popups;
+pub mod power;
+pub mod presentation;
+pub mod profiler;
+pub mod region_select;
 pub mod renderer;
+pub mod reopen;
+pub mod safe_mode;
+pub mod scale;
+pub mod split_resize;
+pub mod switches;
+pub mod tabs;
+pub mod tearing;
+pub mod text;
+pub mod texture_pool;
+pub mod tile_resize;
+pub mod trace;
+pub mod transactions;
+pub mod ui;
+pub mod unresponsive;
+pub mod urgency;
+pub mod virtual_outputs;
+pub mod window_drag;
+pub mod window_menu;
+pub mod window_swap;
 pub mod workspace;
+pub mod workspace_names;
+pub mod xdg_foreign;
 
+use audio::AudioState;
 use bar::{BarElement, BarRenderer};
+use calendar::CalendarPopup;
+use client_stats::ClientMonitor;
+use color::ColorTransform;
+use cursor::CursorVisibility;
+use cursor_theme::CursorTheme;
+use frame_stats::FramePacing;
+use gamma::GammaState;
+use input_inhibit::InputInhibitState;
+use launch::LaunchTracker;
+use layout_templates::TemplateStore;
+use output_profiles::{OutputProfile, OutputState, ProfileStore};
+use pointer_barriers::EdgePressure;
+use power::PowerState;
+use profiler::RenderProfiler;
+use switches::SwitchState;
+use unresponsive::PingTracker;
+use urgency::UrgencyTracker;
 use workspace::{LayoutMode, WorkspaceManager};
 
 use smithay::{
-    delegate_compositor, delegate_output, delegate_seat, delegate_shm, delegate_xdg_shell,
+    backend::renderer::utils::on_commit_buffer_handler,
+    delegate_compositor, delegate_output, delegate_seat, delegate_shm, delegate_xdg_activation,
+    delegate_xdg_shell,
     desktop::{PopupKind, PopupManager, Space, Window},
     input::{keyboard::ModifiersState, Seat, SeatHandler, SeatState},
+    output::{Output, Scale},
     reexports::{
         calloop::LoopHandle,
         wayland_server::{
             backend::{ClientData, ClientId, DisconnectReason},
-            protocol::{wl_seat, wl_surface::WlSurface},
+            protocol::{wl_output::WlOutput, wl_seat, wl_surface::WlSurface},
             Client, Display, DisplayHandle,
         },
     },
     utils::{Clock, Logical, Monotonic, Point, Serial, Size},
     wayland::{
         buffer::BufferHandler,
-        compositor::{CompositorClientState, CompositorHandler, CompositorState},
+        compositor::{with_states, CompositorClientState, CompositorHandler, CompositorState},
+        content_type::ContentTypeState,
+        cursor_shape::CursorShapeManagerState,
+        fractional_scale::FractionalScaleManagerState,
+        idle_notify::IdleNotifierState,
         output::{OutputHandler, OutputManagerState},
+        selection::data_device::DataDeviceState,
         shell::xdg::{
-            PopupSurface, PositionerState, ToplevelSurface, XdgShellHandler, XdgShellState,
+            dialog::XdgDialogState, PopupSurface, PositionerState, ShellClient, ToplevelSurface,
+            XdgShellHandler, XdgShellState, XdgToplevelSurfaceData,
+            XdgToplevelSurfaceRoleAttributes,
         },
         shm::{ShmHandler, ShmState},
+        tearing_control::TearingControlState,
+        viewporter::ViewporterState,
+        xdg_activation::{
+            XdgActivationHandler, XdgActivationState, XdgActivationToken, XdgActivationTokenData,
+        },
+        xdg_foreign::XdgForeignState,
     },
 };
 
-use crate::config::{Config, JSRuntime, StyleSheet};
+use crate::config::{Config, ConfigError, JSRuntime, StyleSheet};
+use crate::watchdog::Watchdog;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 pub struct WebWMCompositor {
     pub display_handle: DisplayHandle,
@@ -47,20 +155,114 @@ pub struct WebWMCompositor {
     pub seat_state: SeatState<Self>,
     pub output_manager_state: OutputManagerState,
     pub popup_manager: PopupManager,
+    /// Window surface whose popups hold a keyboard and pointer grab
+    pub popup_grab_root: Option<WlSurface>,
     pub seat: Seat<Self>,
     pub workspace_manager: WorkspaceManager,
     pub bar_renderer: Option<BarRenderer>,
     pub config: Config,
+    /// Where the configuration was loaded from, to reload it
+    pub config_dir: Option<PathBuf>,
+    /// A reload waits for the config directory to settle
+    pub config_reload_pending: bool,
+    /// The directories hot reload watches, None without hot reload
+    pub config_watch: Option<config_reload::ConfigWatch>,
+    /// Why the configuration didn't load, while running without it
+    pub safe_mode: Option<ConfigError>,
+    /// Why the last reload failed, shown until one works
+    pub reload_error: Option<ConfigError>,
     pub stylesheet: Option<StyleSheet>,
     pub cursor_image_status: smithay::input::pointer::CursorImageStatus,
+    pub cursor_shape_state: CursorShapeManagerState,
+    pub cursor_theme: CursorTheme,
     pub input_handler: input::InputHandler,
+    /// Where the pointer is. Kept here and not in `input_handler`, which is
+    /// taken out while input is handled, so actions see it too.
+    pub pointer_location: Point<f64, Logical>,
     pub js_runtime: Option<JSRuntime>,
+    pub profiler: RenderProfiler,
+    /// Watches for event loop stages that take too long
+    pub watchdog: Watchdog,
+    pub frame_pacing: FramePacing,
+    pub debug_overlay: bool,
+    pub output_profiles: ProfileStore,
+    /// Outputs as of the last `output` event
+    pub known_outputs: Vec<OutputState>,
+    pub switches: SwitchState,
+    pub urgency: UrgencyTracker,
+    pub activation_state: XdgActivationState,
+    pub ipc_subscribers: Vec<crate::ipc::Subscriber>,
+    pub last_input: Option<Instant>,
+    pub power: PowerState,
+    /// sRGB to panel conversion for outputs with an ICC profile
+    pub color_transforms: HashMap<String, ColorTransform>,
+    /// Workspace of each app group, with `<workspaces per-app>`
+    pub app_workspaces: HashMap<String, u32>,
+    /// Set while the user is picking a screen region
+    pub region_selector: Option<region_select::RegionSelector>,
+    /// Month calendar opened from the bar's clock
+    pub calendar: Option<CalendarPopup>,
+    /// Window waiting for Enter or Escape before it is closed
+    pub close_confirm: Option<Window>,
+    /// Menu opened by right-clicking a tab title or with the window-menu
+    /// action
+    pub window_menu: Option<window_menu::WindowMenu>,
+    /// Labels shown over windows by the window-hints action
+    pub window_hints: Option<hints::WindowHints>,
+    /// Window the output is zoomed to, for screen sharing
+    pub zoomed_window: Option<Window>,
+    /// Outputs whose bars are hidden with the toggle-bar action
+    pub hidden_bars: HashSet<String>,
+    /// Output last focused with the focus-output action, until the pointer
+    /// moves
+    pub focused_output: Option<String>,
+    /// Percentage badge of the last scroll over a gap
+    pub split_indicator: Option<split_resize::SplitIndicator>,
+    /// Closed windows the reopen-last action can bring back, oldest first
+    pub recently_closed: Vec<reopen::ClosedWindow>,
+    pub data_device_state: DataDeviceState,
+    /// Icon surface of the drag in progress
+    pub dnd_icon: Option<WlSurface>,
+    pub pings: PingTracker<ShellClient>,
+    pub client_monitor: ClientMonitor,
+    pub cursor_visibility: CursorVisibility,
+    pub edge_pressure: EdgePressure,
+    pub fractional_scale_state: FractionalScaleManagerState,
+    pub viewporter_state: ViewporterState,
+    pub gamma: GammaState,
+    pub launches: LaunchTracker,
+    pub layout_templates: TemplateStore,
+    pub idle_notifier_state: IdleNotifierState<Self>,
+    /// Do-not-disturb with idle inhibited and optionally no bar
+    pub presentation_mode: bool,
+    /// Audio streams playing, from PipeWire
+    pub audio: AudioState,
+    /// Window being dragged to a workspace button
+    pub window_drag: Option<window_drag::WindowDrag>,
+    /// Floating window being moved or resized with Super+drag
+    pub floating_grab: Option<floating_drag::FloatingGrab>,
+    /// Tile being resized with Super+right-drag
+    pub tile_resize: Option<tile_resize::TileResize>,
+    pub content_type_state: ContentTypeState,
+    /// Client that has taken all input, like a screen locker
+    pub input_inhibit: InputInhibitState,
+    pub tearing_control_state: TearingControlState,
+    pub xdg_foreign_state: XdgForeignState,
+    pub xdg_dialog_state: XdgDialogState,
+    /// Dialogs marked modal through xdg_wm_dialog_v1
+    pub modal_dialogs: Vec<Window>,
+    /// Relayout waiting for its resized windows to redraw
+    pub layout_transaction: Option<transactions::LayoutTransaction<Window>>,
+    pub loop_handle: LoopHandle<'static, Self>,
 }
 
+/// How long after the last input event the compositor still counts as busy
+const IDLE_INPUT_GRACE: Duration = Duration::from_secs(1);
+
 impl WebWMCompositor {
     pub fn new(
         display: &mut Display<Self>,
-        _loop_handle: LoopHandle<'static, Self>,
+        loop_handle: LoopHandle<'static, Self>,
         config: Config,
         js_runtime: Option<JSRuntime>,
     ) -> Self {
@@ -72,6 +274,18 @@ impl WebWMCompositor {
         let xdg_shell_state = XdgShellState::new::<Self>(&display_handle);
         let shm_state = ShmState::new::<Self>(&display_handle, vec![]);
         let output_manager_state = OutputManagerState::new_with_xdg_output::<Self>(&display_handle);
+        let activation_state = XdgActivationState::new::<Self>(&display_handle);
+        let data_device_state = DataDeviceState::new::<Self>(&display_handle);
+        let fractional_scale_state = FractionalScaleManagerState::new::<Self>(&display_handle);
+        let viewporter_state = ViewporterState::new::<Self>(&display_handle);
+        let gamma = GammaState::new(&display_handle);
+        let idle_notifier_state = IdleNotifierState::new(&display_handle, loop_handle.clone());
+        let cursor_shape_state = CursorShapeManagerState::new::<Self>(&display_handle);
+        let content_type_state = ContentTypeState::new::<Self>(&display_handle);
+        let input_inhibit = InputInhibitState::new(&display_handle);
+        let tearing_control_state = TearingControlState::new::<Self>(&display_handle);
+        let xdg_foreign_state = XdgForeignState::new::<Self>(&display_handle);
+        let xdg_dialog_state = XdgDialogState::new::<Self>(&display_handle);
         let mut seat_state = SeatState::new();
 
         // Create seat (keyboard and pointer)
@@ -84,6 +298,14 @@ impl WebWMCompositor {
         let popup_manager = PopupManager::default();
 
         let stylesheet = config.stylesheet.clone();
+        let cursor_theme = CursorTheme::load(
+            &config
+                .desktop
+                .as_ref()
+                .map(|desktop| desktop.cursor.clone())
+                .unwrap_or_default(),
+        );
+        let frame_pacing = FramePacing::new(config.performance.frame_warning_threshold_ms);
 
         // Initialize workspace manager
         let mut workspace_manager = WorkspaceManager::new();
@@ -91,10 +313,7 @@ impl WebWMCompositor {
         // Configure workspaces from config if available
         if let Some(ref desktop) = config.desktop {
             for ws_config in &desktop.workspaces {
-                let layout_mode = LayoutMode::from(ws_config.layout.as_str());
-                let workspace =
-                    workspace::Workspace::new(ws_config.id, ws_config.name.clone(), layout_mode);
-                workspace_manager.add_workspace(workspace);
+                workspace_manager.add_workspace(workspace::Workspace::from_config(ws_config));
             }
         }
 
@@ -119,16 +338,76 @@ impl WebWMCompositor {
             seat_state,
             output_manager_state,
             popup_manager,
+            popup_grab_root: None,
             seat,
             workspace_manager,
             bar_renderer,
             config,
+            config_dir: None,
+            config_reload_pending: false,
+            config_watch: None,
+            safe_mode: None,
+            reload_error: None,
             stylesheet,
             cursor_image_status: smithay::input::pointer::CursorImageStatus::default_named(),
+            cursor_shape_state,
+            cursor_theme,
             input_handler: input::InputHandler::new(),
+            pointer_location: (0.0, 0.0).into(),
             js_runtime,
+            profiler: RenderProfiler::new(),
+            watchdog: Watchdog::default(),
+            frame_pacing,
+            debug_overlay: std::env::var("WEBWM_DEBUG_OVERLAY").is_ok(),
+            output_profiles: ProfileStore::load(),
+            known_outputs: Vec::new(),
+            switches: SwitchState::default(),
+            urgency: UrgencyTracker::default(),
+            activation_state,
+            ipc_subscribers: Vec::new(),
+            last_input: None,
+            power: PowerState::default(),
+            color_transforms: HashMap::new(),
+            app_workspaces: HashMap::new(),
+            region_selector: None,
+            calendar: None,
+            close_confirm: None,
+            window_menu: None,
+            window_hints: None,
+            zoomed_window: None,
+            hidden_bars: HashSet::new(),
+            focused_output: None,
+            split_indicator: None,
+            recently_closed: Vec::new(),
+            data_device_state,
+            dnd_icon: None,
+            pings: PingTracker::default(),
+            client_monitor: ClientMonitor::default(),
+            cursor_visibility: CursorVisibility::default(),
+            edge_pressure: EdgePressure::default(),
+            fractional_scale_state,
+            viewporter_state,
+            gamma,
+            launches: LaunchTracker::default(),
+            layout_templates: TemplateStore::load(),
+            idle_notifier_state,
+            presentation_mode: false,
+            audio: AudioState::default(),
+            window_drag: None,
+            floating_grab: None,
+            tile_resize: None,
+            content_type_state,
+            input_inhibit,
+            tearing_control_state,
+            xdg_foreign_state,
+            xdg_dialog_state,
+            modal_dialogs: Vec::new(),
+            layout_transaction: None,
+            loop_handle,
         };
 
+        compositor.load_color_profiles();
+
         compositor
     }
 
@@ -207,16 +486,16 @@ impl WebWMCompositor {
     }
 
     pub fn remove_window(&mut self, toplevel: &ToplevelSurface) {
-        // Find and remove the window
-        let windows = self.workspace_manager.active_workspace().windows.clone();
-
-        if let Some(window) = windows
-            .iter()
-            .find(|w| w.toplevel().map(|t| t.eq(toplevel)).unwrap_or(false))
-            .cloned()
-        {
+        // Windows on other workspaces can close too
+        if let Some(window) = self.window_for_surface(toplevel.wl_surface()) {
+            self.remember_closed_window(&window);
             self.space.unmap_elem(&window);
+            self.drop_placement(&window);
             self.workspace_manager.remove_window(&window);
+            self.urgency.clear(&window);
+            self.modal_dialogs.retain(|dialog| dialog != &window);
+            self.cancel_close_confirm(&window);
+            self.end_output_zoom_for(&window);
 
             let active_ws = self.workspace_manager.active_workspace();
             println!(
@@ -230,98 +509,75 @@ impl WebWMCompositor {
     }
 
     pub fn relayout(&mut self) {
-        self.relayout_with_size(Size::from((1920, 1080))) // Fallback size
+        self.relayout_with_size(self.layout_size())
     }
 
-    pub fn relayout_with_size(&mut self, output_size: Size<i32, smithay::utils::Physical>) {
+    /// Lay out the active workspace in an output of `output_size`, in
+    /// logical coordinates
+    pub fn relayout_with_size(&mut self, output_size: Size<i32, Logical>) {
+        // Window membership only changes right before a relayout
+        self.update_workspace_names();
+
         let active_workspace = self.workspace_manager.active_workspace();
 
         if active_workspace.is_empty() {
             return;
         }
 
-        let gaps = self.config.layout.gaps as i32;
+        let layout_mode = active_workspace.layout_mode;
+        let gaps = self.get_gaps();
 
-        match active_workspace.layout_mode {
-            LayoutMode::Tiling => self.layout_tiling(output_size, gaps),
+        self.begin_layout_transaction(output_size);
+        match layout_mode {
+            LayoutMode::Tiling => match self.workspace_grid() {
+                Some(grid) => self.layout_grid(output_size, gaps, &grid),
+                None => self.layout_tiling(output_size, gaps),
+            },
             LayoutMode::Floating => self.layout_floating(output_size),
             LayoutMode::Monocle => self.layout_monocle(output_size),
+            LayoutMode::MasterStack => self.layout_master_stack(output_size, gaps),
+            LayoutMode::Dwindle => self.layout_dwindle(output_size, gaps),
+            LayoutMode::Tabbed => self.layout_tabbed(output_size),
         }
-    }
-
-    fn layout_tiling(&mut self, output_size: Size<i32, smithay::utils::Physical>, gaps: i32) {
-        let windows = &self.workspace_manager.active_workspace().windows;
-        let window_count = windows.len();
-
-        if window_count == 0 {
-            return;
+        if layout_mode != LayoutMode::Floating {
+            self.place_floating_tiles(output_size);
         }
 
-        // Account for bar height
-        let bar_height = self.bar_height();
-        let usable_height = output_size.h - bar_height;
-
-        // Simple tiling: split screen vertically
-        let window_width =
-            (output_size.w - (gaps * (window_count as i32 + 1))) / window_count as i32;
-        let window_height = usable_height - (gaps * 2);
-
-        for (i, window) in windows.iter().enumerate() {
-            let x = gaps + (i as i32 * (window_width + gaps));
-            let y = bar_height + gaps;
+        self.layout_dialogs(output_size);
+        self.layout_maximized(output_size);
+        self.layout_fullscreen(output_size);
+        self.sync_floating_stack();
+        self.finish_layout_transaction();
+        self.send_surface_scales();
+    }
 
-            self.space.map_element(window.clone(), (x, y), false);
-
-            if let Some(toplevel) = window.toplevel() {
-                toplevel.with_pending_state(|state| {
-                    state.size = Some((window_width as i32, window_height as i32).into());
-                });
-                toplevel.send_configure();
-            }
-        }
+    fn layout_floating(&mut self, output_size: Size<i32, Logical>) {
+        self.place_floating_tiles(output_size);
 
         let active_ws = self.workspace_manager.active_workspace();
         println!(
-            "Relayout: {} windows in tiling mode on workspace {} (gaps: {}px, bar_height: {}px)",
-            window_count, active_ws.id, gaps, bar_height
+            "Relayout: {} windows in floating mode on workspace {}",
+            active_ws.len(),
+            active_ws.id
         );
     }
 
-    fn layout_floating(&mut self, output_size: Size<i32, smithay::utils::Physical>) {
-        let windows = &self.workspace_manager.active_workspace().windows;
-
-        // Account for bar height
-        let bar_height = self.bar_height();
-        let usable_height = output_size.h - bar_height;
-
-        // Floating mode: center windows with offset
-        let base_x = (output_size.w - 800) / 2;
-        let base_y = bar_height + (usable_height - 600) / 2;
-
-        for (i, window) in windows.iter().enumerate() {
-            let offset = i as i32 * 30;
-            let x = base_x + offset;
-            let y = base_y + offset;
-
-            self.space.map_element(window.clone(), (x, y), false);
+    /// Put each floating tile where it was put, or where new windows go
+    fn place_floating_tiles(&mut self, output_size: Size<i32, Logical>) {
+        let tiles = self.workspace_manager.active_workspace().floating_tiles();
 
-            if let Some(toplevel) = window.toplevel() {
-                toplevel.with_pending_state(|state| {
-                    state.size = Some((800, 600).into());
-                });
-                toplevel.send_configure();
+        for (i, tile) in tiles.iter().enumerate() {
+            let geometry = self.floating_geometry(i, tile, output_size);
+            // Remembered, so the window stays put as others come and go
+            let workspace = self.workspace_manager.active_workspace_mut();
+            if workspace.floating_geometry(tile).is_none() {
+                workspace.set_floating_geometry(tile, geometry);
             }
+            self.map_tile(tile, geometry.loc, geometry.size);
         }
-
-        let active_ws = self.workspace_manager.active_workspace();
-        println!(
-            "Relayout: {} windows in floating mode on workspace {}",
-            windows.len(),
-            active_ws.id
-        );
     }
 
-    fn layout_monocle(&mut self, output_size: Size<i32, smithay::utils::Physical>) {
+    fn layout_monocle(&mut self, output_size: Size<i32, Logical>) {
         let windows = &self.workspace_manager.active_workspace().windows;
         let focused_idx = self.workspace_manager.active_workspace().focused_window_idx;
 
@@ -329,18 +585,21 @@ impl WebWMCompositor {
         let bar_height = self.bar_height();
         let usable_height = output_size.h - bar_height;
 
-        // Monocle: fullscreen the focused window, hide others
+        // Monocle: fullscreen the focused window, hide others. A focused
+        // dialog shows its parent, with the dialog on top. Floating windows
+        // stay over it.
+        let workspace = self.workspace_manager.active_workspace();
         if let Some(idx) = focused_idx {
-            if let Some(window) = windows.get(idx) {
-                self.space
-                    .map_element(window.clone(), (0, bar_height), false);
-
-                if let Some(toplevel) = window.toplevel() {
-                    toplevel.with_pending_state(|state| {
-                        state.size = Some((output_size.w as i32, usable_height as i32).into());
-                    });
-                    toplevel.send_configure();
-                }
+            if let Some(window) = windows
+                .get(idx)
+                .filter(|window| !workspace.is_floating(window))
+                .map(|window| workspace.dialog_root(window).clone())
+            {
+                self.place_window(
+                    &window,
+                    (0, bar_height).into(),
+                    (output_size.w, usable_height).into(),
+                );
             }
         }
 
@@ -364,7 +623,12 @@ impl WebWMCompositor {
         // For now, just log events
     }
 
-    pub fn get_border_color(&self, _window: &Window, focused: bool) -> [f32; 4] {
+    pub fn get_border_color(&self, window: &Window, focused: bool) -> [f32; 4] {
+        let base = self.base_border_color(focused);
+        self.urgent_border_color(window, base).unwrap_or(base)
+    }
+
+    fn base_border_color(&self, focused: bool) -> [f32; 4] {
         if let Some(ref stylesheet) = self.stylesheet {
             let selector = if focused { "window:focus" } else { "window" };
 
@@ -384,6 +648,9 @@ impl WebWMCompositor {
     }
 
     pub fn get_border_width(&self) -> u32 {
+        if let Some(width) = self.workspace_length("border-width") {
+            return width as u32;
+        }
         if let Some(ref stylesheet) = self.stylesheet {
             if let Some(width) = stylesheet.get_length("window", "border-width") {
                 return width as u32;
@@ -393,6 +660,75 @@ impl WebWMCompositor {
         self.config.layout.border_width
     }
 
+    /// Gap between tiles on the active workspace
+    pub fn get_gaps(&self) -> i32 {
+        let workspace = self.workspace_manager.active_workspace();
+        self.workspace_length("gap")
+            .map(|gap| gap as i32)
+            .unwrap_or(
+                workspace
+                    .gaps_inner
+                    .unwrap_or(self.config.layout.gaps_inner) as i32,
+            )
+    }
+
+    /// Gap between the tiles and the edges of the output on the active
+    /// workspace
+    pub fn get_outer_gaps(&self) -> i32 {
+        let workspace = self.workspace_manager.active_workspace();
+        self.workspace_length("outer-gap")
+            .map(|gap| gap as i32)
+            .unwrap_or(
+                workspace
+                    .gaps_outer
+                    .unwrap_or(self.config.layout.gaps_outer) as i32,
+            )
+    }
+
+    /// Widen the gaps between tiles by `inner` and around them by `outer`
+    /// pixels (narrow them with negative amounts). A workspace with gaps of
+    /// its own in desktop.xml has those adjusted instead; workspaces whose
+    /// stylesheet rule sets its own gaps keep them.
+    pub fn adjust_gaps(&mut self, inner: i32, outer: i32) {
+        let layout = &mut self.config.layout;
+        let workspace = self.workspace_manager.active_workspace_mut();
+        let gaps_inner = workspace
+            .gaps_inner
+            .as_mut()
+            .unwrap_or(&mut layout.gaps_inner);
+        *gaps_inner = gaps_inner.saturating_add_signed(inner);
+        let gaps_inner = *gaps_inner;
+        let gaps_outer = workspace
+            .gaps_outer
+            .as_mut()
+            .unwrap_or(&mut layout.gaps_outer);
+        *gaps_outer = gaps_outer.saturating_add_signed(outer);
+        println!("↔️  Gaps: {}px inner, {}px outer", gaps_inner, *gaps_outer);
+        self.relayout();
+    }
+
+    /// Area below the bar that tiles are laid out in, the outer gap in from
+    /// the edges
+    pub fn tiled_area(&self, output_size: Size<i32, Logical>) -> Rectangle<i32, Logical> {
+        let bar_height = self.bar_height();
+        let gaps = self.get_outer_gaps();
+        Rectangle::from_loc_and_size(
+            (gaps, bar_height + gaps),
+            (
+                output_size.w - gaps * 2,
+                output_size.h - bar_height - gaps * 2,
+            ),
+        )
+    }
+
+    /// A length from the active workspace's `workspace[name="..."]` rule,
+    /// overriding the global one, e.g. for a gapless media workspace
+    pub fn workspace_length(&self, property: &str) -> Option<f32> {
+        let stylesheet = self.stylesheet.as_ref()?;
+        let name = &self.workspace_manager.active_workspace().name;
+        stylesheet.get_length(&format!("workspace[name=\"{}\"]", name), property)
+    }
+
     pub fn get_focused_window_title(&self) -> Option<String> {
         if let Some(window) = self.workspace_manager.focused_window() {
             if let Some(_toplevel) = window.toplevel() {
@@ -405,16 +741,66 @@ impl WebWMCompositor {
     }
 
     pub fn render_bar_elements(&self) -> Vec<BarElement> {
-        if let Some(ref bar_renderer) = self.bar_renderer {
-            let focused_title = self.get_focused_window_title();
-            bar_renderer.render_bars(
-                &self.workspace_manager,
-                focused_title,
-                self.stylesheet.as_ref(),
-            )
-        } else {
-            Vec::new()
+        let mut elements = self.render_output_bars(self.primary_output_name().as_deref());
+        elements.extend(self.render_safe_mode_banner());
+        elements.extend(self.render_binding_mode());
+
+        elements.extend(self.render_tab_bars());
+        elements.extend(self.render_modal_dims());
+        elements.extend(self.render_unresponsive_overlays());
+
+        if self.debug_overlay {
+            elements.extend(self.render_debug_overlay());
+        }
+
+        elements.extend(self.render_presentation_indicator());
+        elements.extend(self.render_reload_error());
+        elements.extend(self.render_calendar());
+        elements.extend(self.render_close_confirm());
+        elements.extend(self.render_window_menu());
+        elements.extend(self.render_window_hints());
+        elements.extend(self.render_chord_hint());
+        elements.extend(self.render_split_indicator());
+        elements.extend(self.render_region_select());
+        elements.extend(self.render_window_drag());
+        elements.extend(self.render_drag_outline());
+
+        elements
+    }
+
+    fn render_debug_overlay(&self) -> Vec<BarElement> {
+        let lines = self.profiler.overlay_lines();
+        // Top right of the focused output
+        let output = self
+            .focused_output_name()
+            .and_then(|name| {
+                let output = self.space.outputs().find(|output| output.name() == name)?;
+                self.space.output_geometry(output)
+            })
+            .unwrap_or_else(|| {
+                smithay::utils::Rectangle::from_loc_and_size((0, 0), self.layout_size())
+            });
+        let top = output.loc.y + self.bar_height() + 8;
+        let x = output.loc.x + output.size.w - 260;
+
+        let mut elements = vec![BarElement::Rectangle {
+            geometry: smithay::utils::Rectangle::from_loc_and_size(
+                (x - 8, top - 4),
+                (256, lines.len() as i32 * 12 + 8),
+            ),
+            color: [0.0, 0.0, 0.0, 0.7],
+        }];
+
+        for (i, line) in lines.into_iter().enumerate() {
+            elements.push(BarElement::Text {
+                position: (x, top + i as i32 * 12),
+                text: line,
+                color: [0.65, 0.89, 0.63, 1.0],
+                size: 11,
+            });
         }
+
+        elements
     }
 
     pub fn is_focused_window(&self, window: &smithay::desktop::Window) -> bool {
@@ -425,9 +811,34 @@ impl WebWMCompositor {
         }
     }
 
+    /// The window, on any workspace, whose toplevel owns `surface`
+    pub fn window_for_surface(&self, surface: &WlSurface) -> Option<Window> {
+        self.workspace_manager
+            .all_workspaces()
+            .into_iter()
+            .flat_map(|workspace| workspace.windows.iter())
+            .find(|window| {
+                window
+                    .toplevel()
+                    .is_some_and(|toplevel| toplevel.wl_surface() == surface)
+            })
+            .cloned()
+    }
+
     pub fn bar_height(&self) -> i32 {
+        if self.safe_mode.is_some() {
+            return safe_mode::BANNER_HEIGHT;
+        }
+        let output = self.primary_output_name();
+        if !self.bar_visible_on(output.as_deref()) {
+            return 0;
+        }
         if let Some(ref bar_renderer) = self.bar_renderer {
-            if let Some(bar) = bar_renderer.bars.first() {
+            if let Some(bar) = bar_renderer
+                .bars
+                .iter()
+                .find(|bar| bar.shown_on(output.as_deref()))
+            {
                 return bar.height();
             }
         }
@@ -435,8 +846,173 @@ impl WebWMCompositor {
     }
 
     pub fn pointer_location(&self) -> Point<f64, Logical> {
-        self.input_handler.pointer_location
+        self.pointer_location
+    }
+
+    /// Center of the focused window, where a keyboard focus change moves
+    /// the pointer when `warp-pointer` is on
+    pub fn warp_target(&self) -> Option<Point<f64, Logical>> {
+        let enabled = self
+            .config
+            .desktop
+            .as_ref()
+            .is_some_and(|desktop| desktop.pointer.warp_pointer);
+        if !enabled {
+            return None;
+        }
+
+        let window = self.workspace_manager.focused_window()?;
+        let geometry = self.space.element_geometry(window)?;
+        let center = Point::from((
+            geometry.loc.x + geometry.size.w / 2,
+            geometry.loc.y + geometry.size.h / 2,
+        ));
+        Some(center.to_f64())
     }
+
+    /// Current position, mode and scale of every mapped output
+    pub fn output_states(&self) -> Vec<OutputState> {
+        self.space
+            .outputs()
+            .map(|output| {
+                let properties = output.physical_properties();
+                let position = self
+                    .space
+                    .output_geometry(output)
+                    .map(|geometry| (geometry.loc.x, geometry.loc.y))
+                    .unwrap_or((0, 0));
+
+                OutputState {
+                    identifier: output_profiles::output_identifier(
+                        &properties.make,
+                        &properties.model,
+                        &properties.serial_number,
+                        &output.name(),
+                    ),
+                    name: output.name(),
+                    position,
+                    mode: output
+                        .current_mode()
+                        .map(|mode| (mode.size.w, mode.size.h, mode.refresh)),
+                    scale: output.current_scale().fractional_scale(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn apply_output_profile(&mut self, profile: &OutputProfile) {
+        let outputs: Vec<Output> = self.space.outputs().cloned().collect();
+        let current = self.output_states();
+
+        for saved in &profile.outputs {
+            let Some(index) = current
+                .iter()
+                .position(|state| state.identifier == saved.identifier)
+            else {
+                continue;
+            };
+            let output = &outputs[index];
+
+            let mode = saved.mode.and_then(|(w, h, refresh)| {
+                output
+                    .modes()
+                    .into_iter()
+                    .find(|m| m.size.w == w && m.size.h == h && m.refresh == refresh)
+            });
+            if saved.mode.is_some() && mode.is_none() {
+                eprintln!(
+                    "⚠️  Profile '{}': saved mode not available on {}",
+                    profile.name,
+                    output.name()
+                );
+            }
+
+            output.change_current_state(
+                mode,
+                None,
+                Some(Scale::Fractional(saved.scale)),
+                Some(saved.position.into()),
+            );
+            self.space.map_output(output, saved.position);
+        }
+
+        println!("✓ Applied output profile '{}'", profile.name);
+        self.relayout();
+        self.emit_output_changes();
+    }
+
+    /// Call after outputs are added or removed; applies the saved profile
+    /// for this set of monitors, if there is one, and tells JS and IPC
+    /// subscribers
+    pub fn outputs_changed(&mut self) {
+        let current = self.output_states();
+        if let Some(profile) = self.output_profiles.find_matching(&current).cloned() {
+            self.apply_output_profile(&profile);
+        }
+        self.emit_output_changes();
+    }
+
+    /// Notify JS `on(event, ...)` handlers and IPC subscribers
+    pub fn emit_event(&mut self, event: &str, data: serde_json::Value) {
+        if let Some(ref js_runtime) = self.js_runtime {
+            if let Err(e) = js_runtime.emit(event, &data) {
+                eprintln!("JS handler for '{}' failed: {}", event, e);
+            }
+            if js_runtime.take_reload_request() {
+                self.schedule_config_reload();
+            }
+        }
+        crate::ipc::broadcast(&mut self.ipc_subscribers, event, &data);
+    }
+
+    /// Nothing to draw: no mapped windows, no animations, no region being
+    /// selected and no recent input
+    pub fn is_idle(&self) -> bool {
+        self.space.elements().next().is_none()
+            && self.urgency.is_empty()
+            && self.region_selector.is_none()
+            && self
+                .last_input
+                .is_none_or(|at| at.elapsed() > IDLE_INPUT_GRACE)
+    }
+
+    /// Route an input event through the input handler
+    pub fn handle_input(&mut self, event: input_trace::InputTraceEvent) {
+        let _stage = self.watchdog.enter("input");
+        let start = Instant::now();
+        self.last_input = Some(start);
+        self.idle_notifier_state.notify_activity(&self.seat);
+        let mut handler = std::mem::take(&mut self.input_handler);
+        handler.process_trace_event(event, self);
+        self.input_handler = handler;
+        self.profiler.record_dispatch("input", start);
+    }
+}
+
+/// app_id as set by the client, empty until it sends one
+pub fn window_app_id(window: &Window) -> String {
+    window_role_attribute(window, |role| role.app_id.clone())
+}
+
+pub fn window_title(window: &Window) -> String {
+    window_role_attribute(window, |role| role.title.clone())
+}
+
+fn window_role_attribute(
+    window: &Window,
+    f: impl Fn(&XdgToplevelSurfaceRoleAttributes) -> Option<String>,
+) -> String {
+    window
+        .toplevel()
+        .and_then(|toplevel| {
+            with_states(toplevel.wl_surface(), |states| {
+                states
+                    .data_map
+                    .get::<XdgToplevelSurfaceData>()
+                    .and_then(|data| f(&data.lock().ok()?))
+            })
+        })
+        .unwrap_or_default()
 }
 
 fn parse_hex_color(hex: &str) -> [f32; 4] {
@@ -458,10 +1034,13 @@ delegate_xdg_shell!(WebWMCompositor);
 delegate_shm!(WebWMCompositor);
 delegate_seat!(WebWMCompositor);
 delegate_output!(WebWMCompositor);
+delegate_xdg_activation!(WebWMCompositor);
 
 // Implement required traits
 impl BufferHandler for WebWMCompositor {
-    fn buffer_destroyed(&mut self, _buffer: &wayland_server::protocol::wl_buffer::WlBuffer) {}
+    fn buffer_destroyed(&mut self, buffer: &wayland_server::protocol::wl_buffer::WlBuffer) {
+        self.track_buffer_destroyed(buffer);
+    }
 }
 
 impl CompositorHandler for WebWMCompositor {
@@ -473,10 +1052,26 @@ impl CompositorHandler for WebWMCompositor {
         &client.get_data::<ClientState>().unwrap().compositor_state
     }
 
+    fn new_surface(&mut self, surface: &WlSurface) {
+        self.track_new_surface(surface);
+    }
+
+    fn destroyed(&mut self, surface: &WlSurface) {
+        self.track_surface_destroyed(surface);
+    }
+
     fn commit(&mut self, surface: &WlSurface) {
+        self.track_commit(surface);
+
+        // Keep the committed buffer around so surfaces (drag icons included)
+        // can be imported for rendering
+        on_commit_buffer_handler::<Self>(surface);
+
         // Note: Space::commit() has been removed in newer Smithay versions
         // The commit is now handled automatically by the compositor state
         self.popup_manager.commit(surface);
+        self.configure_popup(surface);
+        self.layout_transaction_commit(surface);
     }
 }
 
@@ -485,9 +1080,60 @@ impl XdgShellHandler for WebWMCompositor {
         &mut self.xdg_shell_state
     }
 
+    fn client_pong(&mut self, client: ShellClient) {
+        self.client_answered_ping(&client);
+    }
+
+    fn client_destroyed(&mut self, client: ShellClient) {
+        self.pings.remove(&client);
+    }
+
     fn new_toplevel(&mut self, surface: ToplevelSurface) {
         println!("New toplevel window created");
-        self.add_window(surface);
+        self.add_window(surface.clone());
+        self.claim_launched_window(&surface);
+    }
+
+    fn app_id_changed(&mut self, surface: ToplevelSurface) {
+        // Clients usually set their app_id only after the window was added
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            if !self.place_on_assigned_workspace(&window) {
+                self.place_on_app_workspace(&window);
+            }
+            self.apply_floating_rule(&window);
+        }
+        self.update_workspace_names();
+        self.claim_launched_window(&surface);
+    }
+
+    fn fullscreen_request(&mut self, surface: ToplevelSurface, _output: Option<WlOutput>) {
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.set_fullscreen(&window, true);
+        }
+    }
+
+    fn unfullscreen_request(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.set_fullscreen(&window, false);
+        }
+    }
+
+    fn maximize_request(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.set_maximized(&window, true);
+        }
+    }
+
+    fn unmaximize_request(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.set_maximized(&window, false);
+        }
+    }
+
+    fn parent_changed(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_surface(surface.wl_surface()) {
+            self.update_window_parent(&window);
+        }
     }
 
     fn toplevel_destroyed(&mut self, surface: ToplevelSurface) {
@@ -496,20 +1142,30 @@ impl XdgShellHandler for WebWMCompositor {
     }
 
     fn new_popup(&mut self, surface: PopupSurface, _positioner: PositionerState) {
+        self.unconstrain_popup(&surface);
         self.popup_manager.track_popup(PopupKind::Xdg(surface)).ok();
     }
 
     fn reposition_request(
         &mut self,
-        _surface: PopupSurface,
-        _positioner: PositionerState,
-        _token: u32,
+        surface: PopupSurface,
+        positioner: PositionerState,
+        token: u32,
     ) {
-        // Handle popup reposition requests
+        surface.with_pending_state(|state| {
+            state.geometry = positioner.get_geometry();
+            state.positioner = positioner;
+        });
+        self.unconstrain_popup(&surface);
+        surface.send_repositioned(token);
     }
 
-    fn grab(&mut self, _surface: PopupSurface, _seat: wl_seat::WlSeat, _serial: Serial) {
-        // Handle popup grabs
+    fn grab(&mut self, surface: PopupSurface, seat: wl_seat::WlSeat, serial: Serial) {
+        self.grab_popup(surface, seat, serial);
+    }
+
+    fn popup_destroyed(&mut self, surface: PopupSurface) {
+        self.popup_destroyed_by_client(&surface);
     }
 }
 
@@ -528,10 +1184,16 @@ impl SeatHandler for WebWMCompositor {
         &mut self.seat_state
     }
 
-    fn focus_changed(&mut self, _seat: &Seat<Self>, focused: Option<&WlSurface>) {
+    fn focus_changed(&mut self, seat: &Seat<Self>, focused: Option<&WlSurface>) {
+        self.update_selection_focus(seat, focused);
+
         // Handle focus changes
-        if let Some(_surface) = focused {
+        if let Some(surface) = focused {
             println!("Focus changed to surface");
+            self.clear_urgency_for_surface(surface);
+            if let Some(window) = self.window_for_surface(surface) {
+                self.raise_with_dialogs(&window);
+            }
         }
     }
 
@@ -547,6 +1209,27 @@ impl SeatHandler for WebWMCompositor {
 // Implement OutputHandler trait (required for delegate_output!)
 impl OutputHandler for WebWMCompositor {}
 
+impl XdgActivationHandler for WebWMCompositor {
+    fn activation_state(&mut self) -> &mut XdgActivationState {
+        &mut self.activation_state
+    }
+
+    fn request_activation(
+        &mut self,
+        _token: XdgActivationToken,
+        _token_data: XdgActivationTokenData,
+        surface: WlSurface,
+    ) {
+        // Never steal focus; mark the window urgent instead, like sway's
+        // default `focus_on_window_activation urgent`
+        if let Some(window) = self.window_for_surface(&surface) {
+            if self.workspace_manager.focused_window() != Some(&window) {
+                self.set_window_urgent(&window, true);
+            }
+        }
+    }
+}
+
 pub struct ClientState {
     pub compositor_state: CompositorClientState,
 }
@@ -0,0 +1,91 @@
+// Bars per output
+//
+// A `<bar output="...">` is only shown on that output, and a
+// `<workspace output="...">` is only listed on that output's bars. Without
+// the attribute a bar is shown everywhere and a workspace on every bar.
+// Bars can also be hidden on one output at runtime, with the toggle-bar
+// action or `webwm-msg bar-visible`, so a TV can run without one while the
+// main monitor keeps its bar. Windows are laid out on the first output, so
+// hiding the bar there gives them its room.
+
+use serde_json::json;
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::WebWMCompositor;
+
+impl WebWMCompositor {
+    /// Name of the output windows are laid out on
+    pub fn primary_output_name(&self) -> Option<String> {
+        self.space.outputs().next().map(|output| output.name())
+    }
+
+    /// Whether bars are drawn on `output`
+    pub fn bar_visible_on(&self, output: Option<&str>) -> bool {
+        self.bar_visible(output) && !output.is_some_and(|output| self.hidden_bars.contains(output))
+    }
+
+    /// The bars of `output`, listing the workspaces that belong there
+    pub fn render_output_bars(&self, output: Option<&str>) -> Vec<BarElement> {
+        let Some(bar_renderer) = self
+            .bar_renderer
+            .as_ref()
+            .filter(|_| self.bar_visible_on(output))
+        else {
+            return Vec::new();
+        };
+
+        bar_renderer.render_bars(
+            output,
+            &self.workspace_manager,
+            &self.urgent_workspaces(),
+            self.get_focused_window_title(),
+            self.stylesheet.as_ref(),
+        )
+    }
+
+    pub fn set_bar_visible(&mut self, output: &str, visible: bool) -> Result<(), String> {
+        if !self.space.outputs().any(|o| o.name() == output) {
+            return Err(format!("No output named '{}'", output));
+        }
+
+        let changed = if visible {
+            self.hidden_bars.remove(output)
+        } else {
+            self.hidden_bars.insert(output.to_string())
+        };
+        if !changed {
+            return Ok(());
+        }
+
+        println!(
+            "📊 Bar {} on {}",
+            if visible { "shown" } else { "hidden" },
+            output
+        );
+        if self.primary_output_name().as_deref() == Some(output) {
+            self.calendar = None;
+            self.relayout();
+        }
+        self.emit_event(
+            "bar-visible",
+            json!({ "output": output, "visible": visible }),
+        );
+        Ok(())
+    }
+
+    /// Show or hide the bar on `output`, or on the output under the pointer
+    pub fn toggle_bar(&mut self, output: Option<&str>) -> Result<(), String> {
+        let output = match output {
+            Some(output) => output.to_string(),
+            None => self
+                .space
+                .output_under(self.pointer_location())
+                .next()
+                .map(|output| output.name())
+                .or_else(|| self.primary_output_name())
+                .ok_or("No outputs")?,
+        };
+        let hidden = self.hidden_bars.contains(&output);
+        self.set_bar_visible(&output, hidden)
+    }
+}
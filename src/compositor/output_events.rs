@@ -0,0 +1,141 @@
+// Output hotplug events
+//
+// Whenever outputs are connected, disconnected or reconfigured (mode,
+// position or scale), an `output` event is emitted for each one that
+// changed, with the change, the output and the full list of outputs. JS
+// scripts get them through `wm.onOutputChange(callback)` or `on('output')`
+// to implement their own docking behaviour on top of the declarative
+// `<outputs>` config and the monitor profiles; IPC subscribers see them too.
+// The outputs present at startup are reported as added.
+
+use serde_json::{json, Value};
+
+use crate::compositor::output_profiles::OutputState;
+use crate::compositor::WebWMCompositor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputChange {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl OutputChange {
+    fn as_str(self) -> &'static str {
+        match self {
+            OutputChange::Added => "added",
+            OutputChange::Removed => "removed",
+            OutputChange::Changed => "changed",
+        }
+    }
+}
+
+/// What happened to each output between two snapshots, matched by
+/// connector name
+pub fn output_changes(
+    before: &[OutputState],
+    after: &[OutputState],
+) -> Vec<(OutputChange, OutputState)> {
+    let mut changes = Vec::new();
+
+    for old in before {
+        if !after.iter().any(|new| new.name == old.name) {
+            changes.push((OutputChange::Removed, old.clone()));
+        }
+    }
+    for new in after {
+        match before.iter().find(|old| old.name == new.name) {
+            None => changes.push((OutputChange::Added, new.clone())),
+            Some(old) if old != new => changes.push((OutputChange::Changed, new.clone())),
+            Some(_) => {}
+        }
+    }
+
+    changes
+}
+
+fn output_json(state: &OutputState) -> Value {
+    let (width, height, refresh) = state.mode.unwrap_or((0, 0, 0));
+    json!({
+        "name": state.name,
+        "identifier": state.identifier,
+        "x": state.position.0,
+        "y": state.position.1,
+        "width": width,
+        "height": height,
+        "refresh": refresh as f64 / 1000.0,
+        "scale": state.scale,
+    })
+}
+
+impl WebWMCompositor {
+    /// Emit an `output` event for every output that changed since the
+    /// last call
+    pub fn emit_output_changes(&mut self) {
+        let current = self.output_states();
+        let changes = output_changes(&self.known_outputs, &current);
+        if changes.is_empty() {
+            return;
+        }
+
+        let outputs: Vec<Value> = current.iter().map(output_json).collect();
+        self.known_outputs = current;
+
+        for (change, state) in changes {
+            println!("🖥️  Output {} {}", state.name, change.as_str());
+            self.emit_event(
+                "output",
+                json!({
+                    "change": change.as_str(),
+                    "output": output_json(&state),
+                    "outputs": outputs,
+                }),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(name: &str, x: i32, scale: f64) -> OutputState {
+        OutputState {
+            identifier: format!("Dell {}", name),
+            name: name.to_string(),
+            position: (x, 0),
+            mode: Some((1920, 1080, 60_000)),
+            scale,
+        }
+    }
+
+    #[test]
+    fn test_output_changes() {
+        let laptop = output("eDP-1", 0, 1.5);
+        let monitor = output("DP-1", 1280, 1.0);
+
+        // Docking
+        let changes = output_changes(&[laptop.clone()], &[laptop.clone(), monitor.clone()]);
+        assert_eq!(changes, vec![(OutputChange::Added, monitor.clone())]);
+
+        // Moving and rescaling
+        let moved = output("DP-1", -1920, 1.0);
+        let rescaled = output("eDP-1", 0, 2.0);
+        let changes = output_changes(
+            &[laptop.clone(), monitor.clone()],
+            &[rescaled.clone(), moved.clone()],
+        );
+        assert_eq!(
+            changes,
+            vec![
+                (OutputChange::Changed, rescaled.clone()),
+                (OutputChange::Changed, moved.clone())
+            ]
+        );
+
+        // Undocking, nothing else changed
+        let changes = output_changes(&[rescaled.clone(), moved.clone()], &[rescaled]);
+        assert_eq!(changes, vec![(OutputChange::Removed, moved)]);
+        assert!(output_changes(&[laptop.clone()], &[laptop]).is_empty());
+    }
+}
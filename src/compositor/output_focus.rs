@@ -0,0 +1,150 @@
+// Focusing outputs from the keyboard
+//
+// The focus-output action moves focus to the next output "left", "right",
+// "up" or "down" of the focused one: the output whose center is closest in
+// that direction. The topmost window on it gets keyboard focus. The focused
+// output is the last one picked this way, until the pointer moves; then
+// it's the one under the pointer again. With `warp-to-output` on
+// `<pointer>`, the pointer also jumps to the middle of the new output, so
+// it follows the keyboard across screens.
+
+use smithay::utils::{Logical, Point, Rectangle, SERIAL_COUNTER};
+use smithay::wayland::seat::WaylandFocus;
+
+use crate::compositor::WebWMCompositor;
+
+/// The output among `outputs` nearest to `from` toward `direction`,
+/// comparing centers
+pub fn output_in_direction(
+    from: Rectangle<i32, Logical>,
+    outputs: &[(String, Rectangle<i32, Logical>)],
+    direction: &str,
+) -> Option<String> {
+    let center = |r: Rectangle<i32, Logical>| (r.loc.x + r.size.w / 2, r.loc.y + r.size.h / 2);
+    let (x, y) = center(from);
+
+    outputs
+        .iter()
+        .filter_map(|(name, geometry)| {
+            let (ox, oy) = center(*geometry);
+            let (dx, dy) = (ox - x, oy - y);
+            let ahead = match direction {
+                "left" => dx < 0 && dx.abs() >= dy.abs(),
+                "right" => dx > 0 && dx.abs() >= dy.abs(),
+                "up" => dy < 0 && dy.abs() >= dx.abs(),
+                "down" => dy > 0 && dy.abs() >= dx.abs(),
+                _ => false,
+            };
+            ahead.then(|| (dx as i64 * dx as i64 + dy as i64 * dy as i64, name))
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name.clone())
+}
+
+impl WebWMCompositor {
+    /// The output keyboard actions apply to
+    pub fn focused_output_name(&self) -> Option<String> {
+        self.focused_output
+            .clone()
+            .filter(|name| self.space.outputs().any(|o| &o.name() == name))
+            .or_else(|| {
+                self.space
+                    .output_under(self.pointer_location())
+                    .next()
+                    .map(|output| output.name())
+            })
+            .or_else(|| self.primary_output_name())
+    }
+
+    /// Focus the output toward `direction` and the topmost window on it.
+    /// Returns where the pointer goes when `warp-to-output` is on.
+    pub fn focus_output(&mut self, direction: &str) -> Option<Point<f64, Logical>> {
+        let outputs: Vec<(String, Rectangle<i32, Logical>)> = self
+            .space
+            .outputs()
+            .filter_map(|output| Some((output.name(), self.space.output_geometry(output)?)))
+            .collect();
+        let current = self.focused_output_name()?;
+        let (_, from) = outputs.iter().find(|(name, _)| name == &current)?;
+        let Some(target) = output_in_direction(*from, &outputs, direction) else {
+            println!("No output {} of {}", direction, current);
+            return None;
+        };
+        let (_, geometry) = outputs.iter().find(|(name, _)| name == &target)?;
+        let geometry = *geometry;
+
+        println!("🖥️  Focused output {}", target);
+        self.focused_output = Some(target);
+
+        let window = self
+            .stacked_elements()
+            .into_iter()
+            .rev()
+            .find(|window| {
+                self.space
+                    .element_geometry(window)
+                    .is_some_and(|g| g.overlaps(geometry))
+            })
+            .cloned();
+        if let Some(window) = window {
+            self.workspace_manager
+                .active_workspace_mut()
+                .focus_window(&window);
+            if let (Some(keyboard), Some(surface)) = (self.seat.get_keyboard(), window.wl_surface())
+            {
+                keyboard.set_focus(
+                    self,
+                    Some(surface.into_owned()),
+                    SERIAL_COUNTER.next_serial(),
+                );
+            }
+        }
+
+        let warp = self
+            .config
+            .desktop
+            .as_ref()
+            .is_some_and(|desktop| desktop.pointer.warp_to_output);
+        warp.then(|| {
+            Point::from((
+                geometry.loc.x + geometry.size.w / 2,
+                geometry.loc.y + geometry.size.h / 2,
+            ))
+            .to_f64()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_in_direction() {
+        let output = |name: &str, x, y, w, h| {
+            (
+                name.to_string(),
+                Rectangle::from_loc_and_size((x, y), (w, h)),
+            )
+        };
+        // A laptop below and between two monitors
+        let outputs = vec![
+            output("DP-1", 0, 0, 1920, 1080),
+            output("DP-2", 1920, 0, 2560, 1440),
+            output("eDP-1", 1200, 1440, 1920, 1200),
+        ];
+        let (_, laptop) = outputs[2].clone();
+        let (_, left) = outputs[0].clone();
+
+        assert_eq!(
+            output_in_direction(laptop, &outputs, "up").as_deref(),
+            Some("DP-2")
+        );
+        assert_eq!(
+            output_in_direction(left, &outputs, "right").as_deref(),
+            Some("DP-2")
+        );
+        assert_eq!(output_in_direction(left, &outputs, "left"), None);
+        assert_eq!(output_in_direction(laptop, &outputs, "sideways"), None);
+    }
+}
@@ -0,0 +1,220 @@
+// Scanout format and HDR signalling per output
+//
+// Outputs default to 8-bit XRGB. `<output bit-depth="10"/>` asks for a
+// 2:10:10:10 primary plane, which removes banding in gradients and the bar's
+// translucent backgrounds; `hdr="true"` additionally switches the connector
+// to PQ (SMPTE ST 2084) with BT.2020 primaries and sends static HDR metadata.
+// Whatever the connector, plane or link can't do falls back to 8-bit SDR
+// with a warning instead of failing the modeset.
+//
+// Not usable yet: the DRM backend binds no connector to read "max bpc",
+// the plane formats or HDR_OUTPUT_METADATA from, so it reports 8-bit SDR
+// only and both attributes fall back with that warning.
+
+use smithay::backend::allocator::Fourcc;
+
+use crate::config::OutputConfig;
+
+/// Reference white for SDR content on an HDR output, in nits (ITU-R BT.2408)
+pub const SDR_REFERENCE_WHITE: f64 = 203.0;
+
+/// What a connector and its CRTC can scan out
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FormatCapabilities {
+    /// Upper bound of the connector's "max bpc" property
+    pub max_bpc: u32,
+    /// The primary plane accepts a 2:10:10:10 format
+    pub ten_bit_plane: bool,
+    /// The connector has an HDR_OUTPUT_METADATA property
+    pub hdr_metadata: bool,
+}
+
+/// Buffer format and signalling chosen for an output
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputFormat {
+    pub fourcc: Fourcc,
+    pub bpc: u32,
+    pub hdr: Option<HdrMetadata>,
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        Self {
+            fourcc: Fourcc::Xrgb8888,
+            bpc: 8,
+            hdr: None,
+        }
+    }
+}
+
+/// Pick the scanout format for an output. Anything the hardware can't do
+/// falls back towards plain 8-bit SDR; the reasons are returned for logging.
+pub fn negotiate(
+    config: Option<&OutputConfig>,
+    caps: &FormatCapabilities,
+) -> (OutputFormat, Vec<String>) {
+    let mut warnings = Vec::new();
+    let Some(config) = config else {
+        return (OutputFormat::default(), warnings);
+    };
+
+    // HDR needs the extra precision, so asking for it implies 10-bit
+    let wants_ten_bit = config.bit_depth == Some(10) || config.hdr;
+    let ten_bit = wants_ten_bit && caps.ten_bit_plane && caps.max_bpc >= 10;
+    if wants_ten_bit && !ten_bit {
+        warnings.push(format!(
+            "{}: 10-bit scanout not supported (max bpc {}), using 8-bit",
+            config.name, caps.max_bpc
+        ));
+    }
+
+    let hdr = if !config.hdr {
+        None
+    } else if !ten_bit {
+        warnings.push(format!(
+            "{}: HDR needs 10-bit scanout, disabled",
+            config.name
+        ));
+        None
+    } else if !caps.hdr_metadata {
+        warnings.push(format!(
+            "{}: connector can't send HDR metadata, disabled",
+            config.name
+        ));
+        None
+    } else {
+        Some(HdrMetadata::default())
+    };
+
+    let format = if ten_bit {
+        OutputFormat {
+            fourcc: Fourcc::Xrgb2101010,
+            bpc: 10,
+            hdr,
+        }
+    } else {
+        OutputFormat::default()
+    };
+
+    (format, warnings)
+}
+
+/// Static HDR metadata (CTA-861-G type 1) with the PQ transfer function and
+/// BT.2020 primaries
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HdrMetadata {
+    /// Mastering display luminance, in nits
+    pub max_luminance: u16,
+    /// In units of 0.0001 nits
+    pub min_luminance: u16,
+    /// Maximum content and frame-average light level, in nits
+    pub max_cll: u16,
+    pub max_fall: u16,
+}
+
+impl Default for HdrMetadata {
+    fn default() -> Self {
+        Self {
+            max_luminance: 1000,
+            min_luminance: 50,
+            max_cll: 1000,
+            max_fall: 400,
+        }
+    }
+}
+
+/// EOTF values of the infoframe
+const EOTF_SMPTE_ST2084: u8 = 2;
+/// BT.2020 red, green, blue and D65 white chromaticities
+const BT2020_PRIMARIES: [(f64, f64); 4] = [
+    (0.708, 0.292),
+    (0.170, 0.797),
+    (0.131, 0.046),
+    (0.3127, 0.3290),
+];
+
+impl HdrMetadata {
+    /// Contents of the HDR_OUTPUT_METADATA property blob, laid out like the
+    /// kernel's `struct hdr_output_metadata`
+    pub fn to_blob(self) -> Vec<u8> {
+        let mut blob = Vec::with_capacity(32);
+        // metadata_type: HDMI_STATIC_METADATA_TYPE1
+        blob.extend(0u32.to_ne_bytes());
+        blob.push(EOTF_SMPTE_ST2084);
+        blob.push(0); // infoframe metadata type 1
+        for (x, y) in BT2020_PRIMARIES {
+            // Chromaticities in units of 0.00002
+            blob.extend(((x * 50000.0).round() as u16).to_ne_bytes());
+            blob.extend(((y * 50000.0).round() as u16).to_ne_bytes());
+        }
+        blob.extend(self.max_luminance.to_ne_bytes());
+        blob.extend(self.min_luminance.to_ne_bytes());
+        blob.extend(self.max_cll.to_ne_bytes());
+        blob.extend(self.max_fall.to_ne_bytes());
+        // Padding to the struct's 4 byte alignment
+        blob.resize(32, 0);
+        blob
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(bit_depth: Option<u32>, hdr: bool) -> OutputConfig {
+        OutputConfig {
+            name: "DP-1".to_string(),
+            icc_profile: None,
+            bit_depth,
+            hdr,
+            scale: None,
+        }
+    }
+
+    #[test]
+    fn test_negotiate_fallbacks() {
+        let hdr_capable = FormatCapabilities {
+            max_bpc: 12,
+            ten_bit_plane: true,
+            hdr_metadata: true,
+        };
+
+        let (format, warnings) = negotiate(Some(&output(None, true)), &hdr_capable);
+        assert_eq!(format.fourcc, Fourcc::Xrgb2101010);
+        assert!(format.hdr.is_some());
+        assert!(warnings.is_empty());
+
+        // No HDR metadata property: keep 10-bit, drop HDR
+        let no_metadata = FormatCapabilities {
+            hdr_metadata: false,
+            ..hdr_capable
+        };
+        let (format, warnings) = negotiate(Some(&output(Some(10), true)), &no_metadata);
+        assert_eq!(format.bpc, 10);
+        assert_eq!(format.hdr, None);
+        assert_eq!(warnings.len(), 1);
+
+        // 8 bpc link: everything falls back to 8-bit SDR
+        let eight_bit = FormatCapabilities {
+            max_bpc: 8,
+            ..hdr_capable
+        };
+        let (format, warnings) = negotiate(Some(&output(Some(10), true)), &eight_bit);
+        assert_eq!(format, OutputFormat::default());
+        assert_eq!(warnings.len(), 2);
+
+        let (format, _) = negotiate(None, &hdr_capable);
+        assert_eq!(format, OutputFormat::default());
+    }
+
+    #[test]
+    fn test_hdr_blob_layout() {
+        let blob = HdrMetadata::default().to_blob();
+        assert_eq!(blob.len(), 32);
+        assert_eq!(blob[4], EOTF_SMPTE_ST2084);
+        // Red x of BT.2020: 0.708 / 0.00002
+        assert_eq!(u16::from_ne_bytes([blob[6], blob[7]]), 35400);
+        // max_display_mastering_luminance follows the 4 chromaticity pairs
+        assert_eq!(u16::from_ne_bytes([blob[22], blob[23]]), 1000);
+    }
+}
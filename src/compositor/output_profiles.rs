@@ -0,0 +1,185 @@
+// Monitor profiles (kanshi-like)
+//
+// A profile stores position, mode and scale for every monitor in a specific
+// set of connected monitors. Whenever the set of outputs changes and matches
+// a saved profile's fingerprint, that profile is applied automatically.
+// Profiles live in `$XDG_CONFIG_HOME/webwm/outputs.json`.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Layout of a single monitor, identified by make/model/serial so it
+/// survives connector renames (DP-1 vs DP-2)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputState {
+    pub identifier: String,
+    /// Connector name when saved, for display only
+    #[serde(default)]
+    pub name: String,
+    pub position: (i32, i32),
+    /// (width, height, refresh in mHz)
+    pub mode: Option<(i32, i32, i32)>,
+    pub scale: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputProfile {
+    pub name: String,
+    pub outputs: Vec<OutputState>,
+}
+
+impl OutputProfile {
+    pub fn fingerprint(&self) -> Vec<String> {
+        fingerprint(&self.outputs)
+    }
+}
+
+/// Sorted identifiers of all monitors, independent of connection order
+pub fn fingerprint(outputs: &[OutputState]) -> Vec<String> {
+    let mut ids: Vec<String> = outputs.iter().map(|o| o.identifier.clone()).collect();
+    ids.sort();
+    ids
+}
+
+/// Stable identifier for a monitor. Falls back to the connector name for
+/// virtual outputs that report no EDID data.
+pub fn output_identifier(make: &str, model: &str, serial: &str, connector: &str) -> String {
+    let id = [make, model, serial]
+        .iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if id.is_empty() {
+        connector.to_string()
+    } else {
+        id
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ProfileStore {
+    pub profiles: Vec<OutputProfile>,
+    path: Option<PathBuf>,
+}
+
+impl ProfileStore {
+    /// Load saved profiles; a missing or broken file just means no profiles
+    pub fn load() -> Self {
+        let path = crate::config::user_config_dir().map(|dir| dir.join("outputs.json"));
+
+        let profiles = path
+            .as_ref()
+            .filter(|path| path.exists())
+            .and_then(|path| match fs::read_to_string(path) {
+                Ok(contents) => match serde_json::from_str(&contents) {
+                    Ok(profiles) => Some(profiles),
+                    Err(e) => {
+                        eprintln!("⚠️  Ignoring invalid {}: {}", path.display(), e);
+                        None
+                    }
+                },
+                Err(e) => {
+                    eprintln!("⚠️  Failed to read {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self { profiles, path }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&OutputProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// The profile saved for exactly this set of monitors
+    pub fn find_matching(&self, outputs: &[OutputState]) -> Option<&OutputProfile> {
+        let current = fingerprint(outputs);
+        self.profiles.iter().find(|p| p.fingerprint() == current)
+    }
+
+    /// Add or replace a profile by name and write the store to disk
+    pub fn save_profile(&mut self, profile: OutputProfile) -> Result<(), String> {
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+        self.write()
+    }
+
+    pub fn remove_profile(&mut self, name: &str) -> Result<bool, String> {
+        let before = self.profiles.len();
+        self.profiles.retain(|p| p.name != name);
+        if self.profiles.len() == before {
+            return Ok(false);
+        }
+        self.write().map(|_| true)
+    }
+
+    fn write(&self) -> Result<(), String> {
+        let Some(ref path) = self.path else {
+            return Err("No config directory to store profiles in".to_string());
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+
+        let json = serde_json::to_string_pretty(&self.profiles)
+            .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(identifier: &str, x: i32) -> OutputState {
+        OutputState {
+            identifier: identifier.to_string(),
+            name: String::new(),
+            position: (x, 0),
+            mode: Some((1920, 1080, 60_000)),
+            scale: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_output_identifier() {
+        assert_eq!(
+            output_identifier("Dell", "U2720Q", "ABC123", "DP-1"),
+            "Dell U2720Q ABC123"
+        );
+        assert_eq!(output_identifier("", " ", "", "HEADLESS-1"), "HEADLESS-1");
+    }
+
+    #[test]
+    fn test_profile_matching_ignores_order() {
+        let store = ProfileStore {
+            profiles: vec![
+                OutputProfile {
+                    name: "laptop".to_string(),
+                    outputs: vec![output("eDP", 0)],
+                },
+                OutputProfile {
+                    name: "docked".to_string(),
+                    outputs: vec![output("eDP", 0), output("Dell U2720Q", 1920)],
+                },
+            ],
+            path: None,
+        };
+
+        let connected = vec![output("Dell U2720Q", 0), output("eDP", 0)];
+        assert_eq!(store.find_matching(&connected).unwrap().name, "docked");
+        assert_eq!(
+            store.find_matching(&[output("eDP", 0)]).unwrap().name,
+            "laptop"
+        );
+        assert!(store.find_matching(&[output("Other", 0)]).is_none());
+    }
+}
@@ -0,0 +1,81 @@
+// Zoom to window
+//
+// For sharing the whole screen while only one app matters: the zoom action
+// makes the output show just the focused window, scaled up to fill it. The
+// window keeps its size, so clients don't re-layout, and nothing else is
+// drawn, the bar included. The window is centered and keeps its aspect
+// ratio, so a window of another shape than the output gets borders. Input
+// still goes by the normal layout; the cursor is drawn where it is over
+// the zoomed window. The same action, or the window going away, ends it.
+
+use smithay::desktop::Window;
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+/// What the output shows while zoomed: `source` of the layout, scaled by
+/// `scale` to fill it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputZoom {
+    pub source: Rectangle<i32, Logical>,
+    pub scale: f64,
+}
+
+impl OutputZoom {
+    /// Where a rectangle of the layout ends up on the zoomed output
+    pub fn apply(&self, rect: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        let loc = (rect.loc - self.source.loc).to_f64().upscale(self.scale);
+        let size = rect.size.to_f64().upscale(self.scale);
+        Rectangle::from_loc_and_size(loc, size).to_i32_round()
+    }
+
+    pub fn apply_point(&self, point: Point<f64, Logical>) -> Point<f64, Logical> {
+        (point - self.source.loc.to_f64()).upscale(self.scale)
+    }
+}
+
+impl WebWMCompositor {
+    /// Zoom the output to the focused window, or back out
+    pub fn toggle_output_zoom(&mut self) {
+        if let Some(window) = self.zoomed_window.take() {
+            println!("🔍 Zoom to '{}' ended", window_app_id(&window));
+            return;
+        }
+        let Some(window) = self.workspace_manager.focused_window().cloned() else {
+            return;
+        };
+        println!("🔍 Zoomed to '{}'", window_app_id(&window));
+        self.zoomed_window = Some(window);
+    }
+
+    /// The zoom to draw with, while the zoomed window is on screen
+    pub fn output_zoom(&self) -> Option<OutputZoom> {
+        let window = self.zoomed_window.as_ref()?;
+        let geometry = self.space.element_geometry(window)?;
+        let output = self.layout_size();
+        if geometry.size.w <= 0 || geometry.size.h <= 0 {
+            return None;
+        }
+
+        // Widen or heighten the window's area to the output's shape
+        let scale = (output.w as f64 / geometry.size.w as f64)
+            .min(output.h as f64 / geometry.size.h as f64);
+        let width = (output.w as f64 / scale).round() as i32;
+        let height = (output.h as f64 / scale).round() as i32;
+        let source = Rectangle::from_loc_and_size(
+            (
+                geometry.loc.x - (width - geometry.size.w) / 2,
+                geometry.loc.y - (height - geometry.size.h) / 2,
+            ),
+            (width, height),
+        );
+        Some(OutputZoom { source, scale })
+    }
+
+    /// End the zoom of a window that went away
+    pub fn end_output_zoom_for(&mut self, window: &Window) {
+        if self.zoomed_window.as_ref() == Some(window) {
+            self.zoomed_window = None;
+        }
+    }
+}
@@ -0,0 +1,250 @@
+/// Hardware planes available on a CRTC, beyond the primary plane
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PlaneCapabilities {
+    pub overlay_planes: usize,
+    pub cursor_plane: bool,
+}
+
+/// Where a UI layer ends up being scanned out from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaneTarget {
+    /// Composited into the primary plane together with the windows
+    Primary,
+    /// Scanned out from its own overlay plane
+    Overlay,
+    /// Scanned out from the hardware cursor plane
+    Cursor,
+}
+
+/// What changed since the previous frame
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameDamage {
+    pub scene: bool,
+    pub bar: bool,
+    pub cursor: bool,
+    /// The scene is a single fullscreen window whose buffer may be scanned
+    /// out as it is
+    pub direct_scanout: bool,
+}
+
+/// Work required to present a frame after plane assignment
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FramePlan {
+    /// The primary plane has to be re-composited on the GPU
+    pub composite: bool,
+    /// Only the bar overlay plane needs a new buffer
+    pub update_overlay: bool,
+    /// Only the cursor plane needs to be moved or updated
+    pub update_cursor: bool,
+    /// The fullscreen window's buffer goes straight to the primary plane
+    pub scanout: bool,
+}
+
+impl FramePlan {
+    pub fn is_idle(&self) -> bool {
+        !self.composite && !self.update_overlay && !self.update_cursor && !self.scanout
+    }
+}
+
+/// How a frame is handed to the display
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FlipMode {
+    /// Wait for vblank, never tearing
+    #[default]
+    Vsync,
+    /// Flip right away, tearing if it lands mid-scanout
+    Async,
+}
+
+/// Flip mode for a frame. Async flips may only change the primary plane's
+/// buffer, so frames that also update the bar or cursor plane wait for
+/// vblank even when the window asked for tearing.
+pub fn flip_mode(plan: FramePlan, tearing: bool, async_supported: bool) -> FlipMode {
+    if tearing
+        && async_supported
+        && (plan.composite || plan.scanout)
+        && !plan.update_overlay
+        && !plan.update_cursor
+    {
+        FlipMode::Async
+    } else {
+        FlipMode::Vsync
+    }
+}
+
+/// Assigns the bar and cursor to dedicated planes when the hardware has them,
+/// so small UI updates don't force a full composite of the scene
+#[derive(Debug, Clone)]
+pub struct PlanePlanner {
+    pub capabilities: PlaneCapabilities,
+    pub enabled: bool,
+    pub bar: PlaneTarget,
+    pub cursor: PlaneTarget,
+}
+
+impl PlanePlanner {
+    pub fn new(capabilities: PlaneCapabilities) -> Self {
+        // Planes can be disabled for drivers with broken atomic plane support
+        let enabled = std::env::var("WEBWM_DISABLE_PLANES").is_err();
+
+        let mut planner = Self {
+            capabilities,
+            enabled,
+            bar: PlaneTarget::Primary,
+            cursor: PlaneTarget::Primary,
+        };
+        planner.assign();
+        planner
+    }
+
+    fn assign(&mut self) {
+        if !self.enabled {
+            self.bar = PlaneTarget::Primary;
+            self.cursor = PlaneTarget::Primary;
+            return;
+        }
+
+        self.bar = if self.capabilities.overlay_planes > 0 {
+            PlaneTarget::Overlay
+        } else {
+            PlaneTarget::Primary
+        };
+
+        self.cursor = if self.capabilities.cursor_plane {
+            PlaneTarget::Cursor
+        } else if self.capabilities.overlay_planes > 1 {
+            // The first overlay plane is taken by the bar
+            PlaneTarget::Overlay
+        } else {
+            PlaneTarget::Primary
+        };
+    }
+
+    /// Whether a client buffer can take the primary plane. The bar and
+    /// cursor are drawn on top of it, so they need planes of their own.
+    pub fn can_scan_out(&self) -> bool {
+        self.enabled && self.bar != PlaneTarget::Primary && self.cursor != PlaneTarget::Primary
+    }
+
+    pub fn plan(&self, damage: FrameDamage) -> FramePlan {
+        let mut plan = FramePlan {
+            composite: damage.scene,
+            ..Default::default()
+        };
+
+        if damage.scene && damage.direct_scanout && self.can_scan_out() {
+            plan.composite = false;
+            plan.scanout = true;
+        }
+
+        if damage.bar {
+            if self.bar == PlaneTarget::Primary {
+                plan.composite = true;
+            } else {
+                plan.update_overlay = true;
+            }
+        }
+
+        if damage.cursor {
+            if self.cursor == PlaneTarget::Primary {
+                plan.composite = true;
+            } else {
+                plan.update_cursor = true;
+            }
+        }
+
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planner(overlay_planes: usize, cursor_plane: bool) -> PlanePlanner {
+        let mut planner = PlanePlanner::new(PlaneCapabilities {
+            overlay_planes,
+            cursor_plane,
+        });
+        planner.enabled = true;
+        planner.assign();
+        planner
+    }
+
+    #[test]
+    fn test_plane_assignment() {
+        let p = planner(1, true);
+        assert_eq!(p.bar, PlaneTarget::Overlay);
+        assert_eq!(p.cursor, PlaneTarget::Cursor);
+
+        let p = planner(2, false);
+        assert_eq!(p.bar, PlaneTarget::Overlay);
+        assert_eq!(p.cursor, PlaneTarget::Overlay);
+
+        let p = planner(0, false);
+        assert_eq!(p.bar, PlaneTarget::Primary);
+        assert_eq!(p.cursor, PlaneTarget::Primary);
+    }
+
+    #[test]
+    fn test_clock_tick_skips_composite() {
+        let p = planner(1, true);
+        let plan = p.plan(FrameDamage {
+            scene: false,
+            bar: true,
+            cursor: true,
+            ..Default::default()
+        });
+        assert!(!plan.composite);
+        assert!(plan.update_overlay);
+        assert!(plan.update_cursor);
+
+        let p = planner(0, false);
+        let plan = p.plan(FrameDamage {
+            scene: false,
+            bar: true,
+            cursor: false,
+            ..Default::default()
+        });
+        assert!(plan.composite);
+    }
+
+    #[test]
+    fn test_flip_mode() {
+        let scanout = FramePlan {
+            scanout: true,
+            ..Default::default()
+        };
+        assert_eq!(flip_mode(scanout, true, true), FlipMode::Async);
+        assert_eq!(flip_mode(scanout, false, true), FlipMode::Vsync);
+        assert_eq!(flip_mode(scanout, true, false), FlipMode::Vsync);
+
+        // Moving the cursor plane needs a synchronous commit
+        let with_cursor = FramePlan {
+            update_cursor: true,
+            ..scanout
+        };
+        assert_eq!(flip_mode(with_cursor, true, true), FlipMode::Vsync);
+        assert_eq!(flip_mode(FramePlan::default(), true, true), FlipMode::Vsync);
+    }
+
+    #[test]
+    fn test_direct_scanout() {
+        let damage = FrameDamage {
+            scene: true,
+            bar: true,
+            cursor: false,
+            direct_scanout: true,
+        };
+
+        let plan = planner(1, true).plan(damage);
+        assert!(plan.scanout);
+        assert!(!plan.composite);
+        assert!(plan.update_overlay);
+
+        // Without an overlay plane the bar has to be composited
+        let plan = planner(0, true).plan(damage);
+        assert!(!plan.scanout);
+        assert!(plan.composite);
+    }
+}
@@ -0,0 +1,269 @@
+// Pointer barriers and sticky edges
+//
+// The pointer is kept on the outputs: moving into space no output covers
+// slides it along the edge instead. Between two outputs, `edge-resistance`
+// makes the pointer stop at the shared edge until it has been pushed that
+// many pixels further, so targets at the edge (the bar, scrollbars) are
+// easy to hit without falling onto the next output. A `<barrier>` on an
+// edge never lets the pointer cross it.
+
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::compositor::WebWMCompositor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Edge {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "left" => Some(Edge::Left),
+            "right" => Some(Edge::Right),
+            "top" => Some(Edge::Top),
+            "bottom" => Some(Edge::Bottom),
+            _ => None,
+        }
+    }
+}
+
+/// How far the pointer has been pushed against one output edge
+#[derive(Debug, Default)]
+pub struct EdgePressure {
+    edge: Option<(String, Edge)>,
+    pushed: f64,
+}
+
+impl EdgePressure {
+    fn reset(&mut self) {
+        self.edge = None;
+        self.pushed = 0.0;
+    }
+
+    /// Add a push against `edge`; true once the total reaches `resistance`
+    fn push(&mut self, output: &str, edge: Edge, distance: f64, resistance: f64) -> bool {
+        let same_edge = self
+            .edge
+            .as_ref()
+            .is_some_and(|(o, e)| o == output && *e == edge);
+        if !same_edge {
+            self.edge = Some((output.to_string(), edge));
+            self.pushed = 0.0;
+        }
+
+        self.pushed += distance;
+        if self.pushed >= resistance {
+            self.reset();
+            return true;
+        }
+        false
+    }
+}
+
+fn contains(rect: &Rectangle<i32, Logical>, point: Point<f64, Logical>) -> bool {
+    point.x >= rect.loc.x as f64
+        && point.y >= rect.loc.y as f64
+        && point.x < (rect.loc.x + rect.size.w) as f64
+        && point.y < (rect.loc.y + rect.size.h) as f64
+}
+
+/// Closest point to `point` inside `rect`
+fn clamp_to(rect: &Rectangle<i32, Logical>, point: Point<f64, Logical>) -> Point<f64, Logical> {
+    let right = (rect.loc.x + rect.size.w - 1).max(rect.loc.x) as f64;
+    let bottom = (rect.loc.y + rect.size.h - 1).max(rect.loc.y) as f64;
+    (
+        point.x.clamp(rect.loc.x as f64, right),
+        point.y.clamp(rect.loc.y as f64, bottom),
+    )
+        .into()
+}
+
+/// The edge of `rect` a move to `point` leaves through. Horizontal edges win
+/// at corners.
+fn exit_edge(rect: &Rectangle<i32, Logical>, point: Point<f64, Logical>) -> Edge {
+    if point.x < rect.loc.x as f64 {
+        Edge::Left
+    } else if point.x >= (rect.loc.x + rect.size.w) as f64 {
+        Edge::Right
+    } else if point.y < rect.loc.y as f64 {
+        Edge::Top
+    } else {
+        Edge::Bottom
+    }
+}
+
+/// Where a pointer moving from `from` to `to` ends up, given the outputs
+/// by name, the hard barriers and the resistance in pixels
+pub fn constrain(
+    outputs: &[(String, Rectangle<i32, Logical>)],
+    barriers: &[(String, Edge)],
+    resistance: f64,
+    pressure: &mut EdgePressure,
+    from: Point<f64, Logical>,
+    to: Point<f64, Logical>,
+) -> Point<f64, Logical> {
+    let Some((name, current)) = outputs
+        .iter()
+        .find(|(_, rect)| contains(rect, from))
+        .or_else(|| outputs.first())
+    else {
+        return to;
+    };
+
+    if contains(current, to) {
+        pressure.reset();
+        return to;
+    }
+
+    // Off the edge of the desktop: slide along it
+    let clamped = clamp_to(current, to);
+    if !outputs.iter().any(|(_, rect)| contains(rect, to)) {
+        return clamped;
+    }
+
+    let edge = exit_edge(current, to);
+    if barriers
+        .iter()
+        .any(|(output, e)| output == name && *e == edge)
+    {
+        return clamped;
+    }
+
+    let overshoot = (to.x - clamped.x).abs().max((to.y - clamped.y).abs());
+    if resistance > 0.0 && !pressure.push(name, edge, overshoot, resistance) {
+        return clamped;
+    }
+
+    to
+}
+
+impl WebWMCompositor {
+    /// Apply output boundaries, edge resistance and barriers to a pointer
+    /// move
+    pub fn constrain_pointer(
+        &mut self,
+        from: Point<f64, Logical>,
+        to: Point<f64, Logical>,
+    ) -> Point<f64, Logical> {
+        let mut outputs: Vec<(String, Rectangle<i32, Logical>)> = self
+            .space
+            .outputs()
+            .filter_map(|output| Some((output.name(), self.space.output_geometry(output)?)))
+            .collect();
+        if outputs.is_empty() {
+            outputs.push((
+                String::new(),
+                Rectangle::from_loc_and_size((0, 0), (1920, 1080)),
+            ));
+        }
+
+        let settings = self
+            .config
+            .desktop
+            .as_ref()
+            .map(|desktop| desktop.pointer.clone())
+            .unwrap_or_default();
+        let barriers: Vec<(String, Edge)> = settings
+            .barriers
+            .iter()
+            .filter_map(|barrier| Some((barrier.output.clone(), Edge::parse(&barrier.edge)?)))
+            .collect();
+
+        constrain(
+            &outputs,
+            &barriers,
+            settings.edge_resistance as f64,
+            &mut self.edge_pressure,
+            from,
+            to,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Move between eDP-1 (1920x1080) and DP-1 (2560x1440) to its right
+    fn step(
+        barriers: &[(String, Edge)],
+        resistance: f64,
+        pressure: &mut EdgePressure,
+        from: (f64, f64),
+        to: (f64, f64),
+    ) -> (f64, f64) {
+        let outputs = vec![
+            (
+                "eDP-1".to_string(),
+                Rectangle::from_loc_and_size((0, 0), (1920, 1080)),
+            ),
+            (
+                "DP-1".to_string(),
+                Rectangle::from_loc_and_size((1920, 0), (2560, 1440)),
+            ),
+        ];
+        let at = constrain(
+            &outputs,
+            barriers,
+            resistance,
+            pressure,
+            from.into(),
+            to.into(),
+        );
+        (at.x, at.y)
+    }
+
+    #[test]
+    fn test_edge_resistance() {
+        let mut pressure = EdgePressure::default();
+
+        // Stops at the edge, then needs 30px of push to cross
+        let at = step(&[], 30.0, &mut pressure, (1900.0, 500.0), (1920.0, 500.0));
+        assert_eq!(at, (1919.0, 500.0));
+        let at = step(&[], 30.0, &mut pressure, at, (1939.0, 500.0));
+        assert_eq!(at, (1919.0, 500.0));
+        let at = step(&[], 30.0, &mut pressure, at, (1939.0, 500.0));
+        assert_eq!(at, (1939.0, 500.0));
+
+        // Backing off resets the pressure
+        let at = step(&[], 30.0, &mut pressure, (1910.0, 0.0), (1935.0, 0.0));
+        let at = step(&[], 30.0, &mut pressure, at, (1900.0, 0.0));
+        let at = step(&[], 30.0, &mut pressure, at, (1925.0, 0.0));
+        assert_eq!(at, (1919.0, 0.0));
+    }
+
+    #[test]
+    fn test_barriers_and_desktop_edges() {
+        let barriers = vec![("eDP-1".to_string(), Edge::Right)];
+        let mut pressure = EdgePressure::default();
+
+        for _ in 0..10 {
+            let at = step(
+                &barriers,
+                0.0,
+                &mut pressure,
+                (1919.0, 10.0),
+                (1950.0, 10.0),
+            );
+            assert_eq!(at, (1919.0, 10.0));
+        }
+
+        // Crossing from the other side isn't blocked
+        let at = step(
+            &barriers,
+            0.0,
+            &mut pressure,
+            (1925.0, 10.0),
+            (1910.0, 10.0),
+        );
+        assert_eq!(at, (1910.0, 10.0));
+
+        // Below eDP-1 but beside DP-1 is off the desktop
+        let at = step(&[], 0.0, &mut pressure, (100.0, 1070.0), (110.0, 1100.0));
+        assert_eq!(at, (110.0, 1079.0));
+    }
+}
@@ -0,0 +1,230 @@
+// Popups
+//
+// Menus, dropdowns and tooltips are xdg_popups. The client describes where
+// it wants one with a positioner, relative to its parent, and the popup is
+// slid, flipped or resized as the positioner allows so it stays on the
+// output. A popup that asks for a grab gets keyboard and pointer input until
+// it is dismissed. Clicking outside the client's popups dismisses the whole
+// chain and the click goes on to whatever was under it; Escape dismisses
+// the innermost popup, so a submenu closes before its menu. Whenever the
+// chain shrinks the keyboard moves to the popup that is now innermost, and
+// back to the window once the last one is gone. Popups are drawn right
+// above the window they belong to.
+
+use smithay::desktop::{
+    find_popup_root_surface, get_popup_toplevel_coords, PopupKeyboardGrab, PopupKind, PopupManager,
+    PopupPointerGrab, PopupUngrabStrategy, Window,
+};
+use smithay::input::pointer::Focus;
+use smithay::input::Seat;
+use smithay::reexports::wayland_server::protocol::{wl_seat, wl_surface::WlSurface};
+use smithay::utils::{Logical, Point, Rectangle, Serial, SERIAL_COUNTER};
+use smithay::wayland::seat::WaylandFocus;
+use smithay::wayland::shell::xdg::PopupSurface;
+
+use crate::compositor::WebWMCompositor;
+
+impl WebWMCompositor {
+    /// Area the popup has to stay inside: the output its window is on
+    fn popup_bounds(&self, window: &Window) -> Rectangle<i32, Logical> {
+        self.space
+            .outputs_for_element(window)
+            .first()
+            .and_then(|output| self.space.output_geometry(output))
+            .unwrap_or_else(|| Rectangle::from_loc_and_size((0, 0), self.layout_size()))
+    }
+
+    /// Place `popup` where its positioner asks, adjusted to stay on the
+    /// output. Takes effect with its next configure.
+    pub fn unconstrain_popup(&self, popup: &PopupSurface) {
+        let kind = PopupKind::Xdg(popup.clone());
+        let Some(window) = find_popup_root_surface(&kind)
+            .ok()
+            .and_then(|root| self.window_for_surface(&root))
+        else {
+            return;
+        };
+        let Some(window_location) = self.space.element_location(&window) else {
+            return;
+        };
+
+        // The positioner works relative to the popup's parent
+        let mut target = self.popup_bounds(&window);
+        target.loc -= window_location;
+        target.loc -= get_popup_toplevel_coords(&kind);
+
+        popup.with_pending_state(|state| {
+            state.geometry = state.positioner.get_unconstrained_geometry(target);
+        });
+    }
+
+    /// Send a new popup its first configure once it commits
+    pub fn configure_popup(&self, surface: &WlSurface) {
+        if let Some(PopupKind::Xdg(popup)) = self.popup_manager.find_popup(surface) {
+            if !popup.is_initial_configure_sent() {
+                popup.send_configure().ok();
+            }
+        }
+    }
+
+    /// Give keyboard and pointer to `popup` until it is dismissed. Refused
+    /// unless `serial` is the input event that opened it.
+    pub fn grab_popup(&mut self, popup: PopupSurface, seat: wl_seat::WlSeat, serial: Serial) {
+        let Some(seat) = Seat::<Self>::from_resource(&seat) else {
+            return;
+        };
+        let kind = PopupKind::Xdg(popup);
+        let Some(root) = find_popup_root_surface(&kind)
+            .ok()
+            .and_then(|root| self.window_for_surface(&root))
+            .and_then(|window| window.wl_surface().map(|surface| surface.into_owned()))
+        else {
+            return;
+        };
+        let Ok(mut grab) = self
+            .popup_manager
+            .grab_popup(root.clone(), kind, &seat, serial)
+        else {
+            return;
+        };
+
+        if let Some(keyboard) = seat.get_keyboard() {
+            if keyboard.is_grabbed()
+                && !(keyboard.has_grab(serial)
+                    || keyboard.has_grab(grab.previous_serial().unwrap_or(serial)))
+            {
+                grab.ungrab(PopupUngrabStrategy::All);
+                return;
+            }
+            keyboard.set_focus(self, grab.current_grab(), serial);
+            keyboard.set_grab(self, PopupKeyboardGrab::new(&grab), serial);
+        }
+
+        if let Some(pointer) = seat.get_pointer() {
+            if pointer.is_grabbed()
+                && !(pointer.has_grab(serial)
+                    || pointer.has_grab(grab.previous_serial().unwrap_or_else(|| grab.serial())))
+            {
+                grab.ungrab(PopupUngrabStrategy::All);
+                return;
+            }
+            pointer.set_grab(self, PopupPointerGrab::new(&grab), serial, Focus::Keep);
+        }
+        self.popup_grab_root = Some(root);
+    }
+
+    /// Popups of the chain holding the grab, outermost first, leaving out
+    /// `except` (one being destroyed)
+    fn grabbed_popups(&self, except: Option<&WlSurface>) -> Vec<PopupKind> {
+        let Some(root) = &self.popup_grab_root else {
+            return Vec::new();
+        };
+        PopupManager::popups_for_surface(root)
+            .map(|(popup, _)| popup)
+            .filter(|popup| popup.alive() && Some(popup.wl_surface()) != except)
+            .collect()
+    }
+
+    /// Whether `point` is on one of the popups holding the grab
+    pub fn over_grabbed_popup(&self, point: Point<f64, Logical>) -> bool {
+        let Some(window) = self
+            .popup_grab_root
+            .as_ref()
+            .and_then(|root| self.window_for_surface(root))
+        else {
+            return false;
+        };
+        self.popup_geometries(&window)
+            .iter()
+            .any(|geometry| geometry.to_f64().contains(point))
+    }
+
+    /// Dismiss every popup of the grab, as a click outside them does.
+    /// Returns false if there was no grab.
+    pub fn dismiss_popups(&mut self) -> bool {
+        let Some(root) = self.popup_grab_root.clone() else {
+            return false;
+        };
+        // Dismissing the outermost popup takes its children with it
+        if let Some(outermost) = self.grabbed_popups(None).first() {
+            PopupManager::dismiss_popup(&root, outermost).ok();
+        }
+        self.end_popup_grab();
+        true
+    }
+
+    /// Dismiss the innermost popup of the grab, as Escape does. Returns
+    /// false if there was no grab.
+    pub fn dismiss_innermost_popup(&mut self) -> bool {
+        let Some(root) = self.popup_grab_root.clone() else {
+            return false;
+        };
+        let popups = self.grabbed_popups(None);
+        let Some(innermost) = popups.last() else {
+            self.end_popup_grab();
+            return false;
+        };
+        PopupManager::dismiss_popup(&root, innermost).ok();
+
+        match popups.len() {
+            1 => self.end_popup_grab(),
+            n => self.focus_popup(popups[n - 2].wl_surface().clone()),
+        }
+        true
+    }
+
+    /// Follow a popup the client destroyed itself: focus moves to the
+    /// next popup out, or back to the window with the last one
+    pub fn popup_destroyed_by_client(&mut self, popup: &PopupSurface) {
+        let surface = popup.wl_surface();
+        if self.popup_grab_root.is_none() {
+            return;
+        }
+        match self.grabbed_popups(Some(surface)).last() {
+            Some(innermost) => self.focus_popup(innermost.wl_surface().clone()),
+            None => self.end_popup_grab(),
+        }
+    }
+
+    fn focus_popup(&mut self, surface: WlSurface) {
+        if let Some(keyboard) = self.seat.get_keyboard() {
+            keyboard.set_focus(self, Some(surface), SERIAL_COUNTER.next_serial());
+        }
+    }
+
+    /// Release keyboard and pointer and give the keyboard back to the
+    /// window the popups belonged to
+    fn end_popup_grab(&mut self) {
+        let Some(root) = self.popup_grab_root.take() else {
+            return;
+        };
+        let serial = SERIAL_COUNTER.next_serial();
+        if let Some(pointer) = self.seat.get_pointer() {
+            pointer.unset_grab(self, serial, 0);
+        }
+        if let Some(keyboard) = self.seat.get_keyboard() {
+            keyboard.unset_grab(self);
+            if root.alive() {
+                keyboard.set_focus(self, Some(root), serial);
+            }
+        }
+    }
+
+    /// Where `window`'s open popups are, in layout coordinates, parents
+    /// before their children so they stack in drawing order
+    pub fn popup_geometries(&self, window: &Window) -> Vec<Rectangle<i32, Logical>> {
+        let (Some(surface), Some(location)) =
+            (window.wl_surface(), self.space.element_location(window))
+        else {
+            return Vec::new();
+        };
+        // Offsets are from the window's surface, not its geometry
+        let origin = location - window.geometry().loc;
+
+        PopupManager::popups_for_surface(&surface)
+            .map(|(popup, offset)| {
+                Rectangle::from_loc_and_size(origin + offset, popup.geometry().size)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,284 @@
+// Power source detection for low-power mode, and power events
+//
+// `<low-power mode="auto">` turns expensive effects off while the machine runs
+// from battery. The battery state comes from sysfs and is cached, since it is
+// asked for every frame.
+//
+// When UPower is running, a background thread follows `upower --monitor` and
+// reads the battery after every change it reports. Plugging or unplugging
+// the charger and the battery level crossing one of BATTERY_THRESHOLDS emit
+// a `power` event, so config.js can lower brightness or switch profiles with
+// `wm.on('power', callback)`.
+
+use serde_json::json;
+use smithay::reexports::calloop::channel::{self, Event};
+use smithay::reexports::calloop::LoopHandle;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::compositor::WebWMCompositor;
+
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+/// Battery state is re-read at most this often
+const RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// UPower's combined view of all batteries
+const UPOWER_DISPLAY_DEVICE: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+
+/// Battery percentages that emit a `power` event when crossed
+pub const BATTERY_THRESHOLDS: [f64; 4] = [50.0, 20.0, 10.0, 5.0];
+
+#[derive(Debug, Default)]
+pub struct PowerState {
+    on_battery: bool,
+    checked_at: Option<Instant>,
+    /// Last battery reading from UPower
+    status: Option<PowerStatus>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerStatus {
+    pub on_battery: bool,
+    pub percentage: f64,
+}
+
+impl PowerState {
+    /// Whether low-power mode is active for the configured mode
+    pub fn low_power(&mut self, mode: &str) -> bool {
+        match mode {
+            "on" => true,
+            "off" => false,
+            _ => self.on_battery(),
+        }
+    }
+
+    fn on_battery(&mut self) -> bool {
+        if self
+            .checked_at
+            .is_none_or(|at| at.elapsed() > RECHECK_INTERVAL)
+        {
+            self.on_battery = on_battery(Path::new(POWER_SUPPLY_DIR));
+            self.checked_at = Some(Instant::now());
+        }
+        self.on_battery
+    }
+}
+
+/// True when a battery is discharging, i.e. no charger is plugged in
+pub fn on_battery(power_supply_dir: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(power_supply_dir) else {
+        return false;
+    };
+
+    entries.flatten().any(|entry| {
+        let read = |name: &str| {
+            fs::read_to_string(entry.path().join(name))
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default()
+        };
+        read("type") == "Battery" && read("status") == "Discharging"
+    })
+}
+
+/// Battery state in `upower -i` output, None without a battery
+pub fn parse_upower(info: &str) -> Option<PowerStatus> {
+    let field = |name: &str| {
+        info.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == name).then(|| value.trim())
+        })
+    };
+
+    if field("present")? != "yes" {
+        return None;
+    }
+    let on_battery = matches!(
+        field("state")?,
+        "discharging" | "pending-discharge" | "empty"
+    );
+    let percentage = field("percentage")?
+        .trim_end_matches('%')
+        .replace(',', ".")
+        .parse()
+        .ok()?;
+    Some(PowerStatus {
+        on_battery,
+        percentage,
+    })
+}
+
+/// The threshold the battery level passed going from `previous` to
+/// `current`: the lowest one on the way down, the highest on the way up
+pub fn crossed_threshold(previous: f64, current: f64) -> Option<f64> {
+    if current < previous {
+        BATTERY_THRESHOLDS
+            .iter()
+            .copied()
+            .filter(|&t| previous > t && current <= t)
+            .reduce(f64::min)
+    } else {
+        BATTERY_THRESHOLDS
+            .iter()
+            .copied()
+            .filter(|&t| previous < t && current >= t)
+            .reduce(f64::max)
+    }
+}
+
+fn battery_status() -> Result<Option<PowerStatus>, String> {
+    let output = Command::new("upower")
+        .args(["-i", UPOWER_DISPLAY_DEVICE])
+        .output()
+        .map_err(|e| format!("Failed to run upower: {}", e))?;
+    if !output.status.success() {
+        return Err("upower failed, is UPower running?".to_string());
+    }
+    Ok(parse_upower(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Follow UPower on a background thread and emit `power` events
+pub fn init_power_monitor(
+    loop_handle: &LoopHandle<'static, WebWMCompositor>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (sender, receiver) = channel::channel();
+    loop_handle.insert_source(receiver, |event, _, compositor| {
+        if let Event::Msg(status) = event {
+            compositor.handle_power_status(status);
+        }
+    })?;
+
+    thread::Builder::new()
+        .name("power-monitor".to_string())
+        .spawn(move || {
+            let mut last = None;
+            let mut check = || match battery_status() {
+                Ok(status) => {
+                    if let Some(status) = status.filter(|&status| last != Some(status)) {
+                        last = Some(status);
+                        return sender.send(status).is_ok();
+                    }
+                    true
+                }
+                Err(e) => {
+                    eprintln!("⚠️  Power events disabled: {}", e);
+                    false
+                }
+            };
+            if !check() {
+                return;
+            }
+
+            let monitor = Command::new("upower")
+                .arg("--monitor")
+                .stdout(Stdio::piped())
+                .spawn();
+            let Some(stdout) = monitor.ok().and_then(|mut child| child.stdout.take()) else {
+                eprintln!("⚠️  Power events disabled: failed to run upower --monitor");
+                return;
+            };
+            // Every line is a change to some device
+            for _ in BufReader::new(stdout).lines() {
+                if !check() {
+                    return;
+                }
+            }
+        })?;
+
+    Ok(())
+}
+
+impl WebWMCompositor {
+    pub fn handle_power_status(&mut self, status: PowerStatus) {
+        self.power.on_battery = status.on_battery;
+        self.power.checked_at = Some(Instant::now());
+        let Some(previous) = self.power.status.replace(status) else {
+            return;
+        };
+
+        let event = |change: &str, threshold: Option<f64>| {
+            json!({
+                "change": change,
+                "on_battery": status.on_battery,
+                "percentage": status.percentage,
+                "threshold": threshold,
+            })
+        };
+        if previous.on_battery != status.on_battery {
+            let change = if status.on_battery {
+                "unplugged"
+            } else {
+                "plugged"
+            };
+            println!("🔌 Charger {} ({:.0}%)", change, status.percentage);
+            self.emit_event("power", event(change, None));
+        }
+        if let Some(threshold) = crossed_threshold(previous.percentage, status.percentage) {
+            println!("🔋 Battery at {:.0}%", status.percentage);
+            self.emit_event("power", event("threshold", Some(threshold)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_on_battery() {
+        let dir = std::env::temp_dir().join(format!("webwm-power-{}", std::process::id()));
+        let supply = |name: &str, kind: &str, status: &str| {
+            let path = dir.join(name);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("type"), format!("{}\n", kind)).unwrap();
+            fs::write(path.join("status"), format!("{}\n", status)).unwrap();
+        };
+
+        supply("AC", "Mains", "Unknown");
+        supply("BAT0", "Battery", "Charging");
+        assert!(!on_battery(&dir));
+
+        supply("BAT0", "Battery", "Discharging");
+        assert!(on_battery(&dir));
+
+        assert!(!on_battery(&dir.join("missing")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_upower() {
+        let info = "  native-path:          BAT0
+  power supply:         yes
+  battery
+    present:             yes
+    state:               discharging
+    energy:              30,2 Wh
+    percentage:          57%
+    icon-name:          'battery-good-symbolic'
+";
+        assert_eq!(
+            parse_upower(info),
+            Some(PowerStatus {
+                on_battery: true,
+                percentage: 57.0
+            })
+        );
+        let charging = info.replace("discharging", "fully-charged");
+        assert_eq!(parse_upower(&charging).map(|s| s.on_battery), Some(false));
+        let desktop = info.replace("present:             yes", "present:             no");
+        assert_eq!(parse_upower(&desktop), None);
+    }
+
+    #[test]
+    fn test_crossed_threshold() {
+        assert_eq!(crossed_threshold(21.0, 20.0), Some(20.0));
+        assert_eq!(crossed_threshold(20.0, 19.0), None);
+        // A big drop reports the lowest threshold passed
+        assert_eq!(crossed_threshold(60.0, 8.0), Some(10.0));
+        assert_eq!(crossed_threshold(19.0, 51.0), Some(50.0));
+        assert_eq!(crossed_threshold(57.0, 57.0), None);
+    }
+}
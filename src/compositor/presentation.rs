@@ -0,0 +1,123 @@
+// Presentation mode
+//
+// For screen sharing and talks: one toggle that silences notifications,
+// keeps the screen from idling or locking, and, with `hide-bar="true"`,
+// hides the bar so windows get its space. WebWM doesn't show notifications
+// itself, so do-not-disturb is left to the notification daemon through the
+// `on-enter` and `on-exit` commands of `<presentation-mode>` (for mako,
+// `makoctl mode -a do-not-disturb`), and a `presentation-mode` event goes to
+// JS `on()` handlers and IPC subscribers. Idle is inhibited through
+// ext-idle-notify, which swayidle and similar lockers listen to. While the
+// mode is on, a "PRESENTING" badge sits in the top right corner.
+
+use serde_json::json;
+use smithay::delegate_idle_notify;
+use smithay::utils::Rectangle;
+use smithay::wayland::idle_notify::{IdleNotifierHandler, IdleNotifierState};
+use std::process::Command;
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::text;
+use crate::compositor::WebWMCompositor;
+use crate::config::PresentationSettings;
+
+const BADGE_TEXT: &str = "PRESENTING";
+const BADGE_MARGIN: i32 = 8;
+const BADGE_PADDING: i32 = 6;
+const DEFAULT_BADGE_BACKGROUND: [f32; 4] = [0.95, 0.55, 0.66, 0.9]; // #f38ba8
+const DEFAULT_BADGE_COLOR: [f32; 4] = [0.11, 0.11, 0.18, 1.0];
+
+impl WebWMCompositor {
+    fn presentation_settings(&self) -> PresentationSettings {
+        self.config
+            .desktop
+            .as_ref()
+            .map(|desktop| desktop.presentation.clone())
+            .unwrap_or_default()
+    }
+
+    /// Turn presentation mode on or off
+    pub fn set_presentation_mode(&mut self, active: bool) {
+        if active == self.presentation_mode {
+            return;
+        }
+        self.presentation_mode = active;
+        println!(
+            "📽️  Presentation mode {}",
+            if active { "on" } else { "off" }
+        );
+
+        let settings = self.presentation_settings();
+        let command = if active {
+            settings.on_enter
+        } else {
+            settings.on_exit
+        };
+        if let Some(command) = command {
+            if let Err(e) = Command::new("sh").arg("-c").arg(&command).spawn() {
+                eprintln!("Failed to spawn '{}': {}", command, e);
+            }
+        }
+
+        self.idle_notifier_state.set_is_inhibited(active);
+        if settings.hide_bar {
+            self.calendar = None;
+            self.relayout();
+        }
+
+        self.emit_event("presentation-mode", json!({ "active": active }));
+    }
+
+    pub fn toggle_presentation_mode(&mut self) {
+        self.set_presentation_mode(!self.presentation_mode);
+    }
+
+    /// Whether presentation mode and fullscreen windows leave the bars of
+    /// `output` drawn
+    pub fn bar_visible(&self, output: Option<&str>) -> bool {
+        !(self.presentation_mode && self.presentation_settings().hide_bar)
+            && !self.fullscreen_on(output)
+    }
+
+    /// The badge shown while presentation mode is on. Colors come from the
+    /// stylesheet's `.presentation-indicator` rule.
+    pub fn render_presentation_indicator(&self) -> Vec<BarElement> {
+        if !self.presentation_mode {
+            return Vec::new();
+        }
+
+        let style = |property: &str, default: [f32; 4]| {
+            self.stylesheet
+                .as_ref()
+                .and_then(|ss| ss.get_color(".presentation-indicator", property))
+                .map(|color| color.to_rgba_f32())
+                .unwrap_or(default)
+        };
+
+        let width = text::measure(BADGE_TEXT, 13) + BADGE_PADDING * 2;
+        let height = 13 + BADGE_PADDING * 2;
+        let x = self.layout_size().w - width - BADGE_MARGIN;
+        let y = self.bar_height() + BADGE_MARGIN;
+
+        vec![
+            BarElement::Rectangle {
+                geometry: Rectangle::from_loc_and_size((x, y), (width, height)),
+                color: style("background", DEFAULT_BADGE_BACKGROUND),
+            },
+            BarElement::Text {
+                position: (x + BADGE_PADDING, y + BADGE_PADDING),
+                text: BADGE_TEXT.to_string(),
+                color: style("color", DEFAULT_BADGE_COLOR),
+                size: 13,
+            },
+        ]
+    }
+}
+
+impl IdleNotifierHandler for WebWMCompositor {
+    fn idle_notifier_state(&mut self) -> &mut IdleNotifierState<Self> {
+        &mut self.idle_notifier_state
+    }
+}
+
+delegate_idle_notify!(WebWMCompositor);
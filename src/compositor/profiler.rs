@@ -0,0 +1,188 @@
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+use crate::compositor::trace::{TraceSession, Track};
+
+/// Stages of the render path that are timed individually
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderStage {
+    ElementCollection,
+    BarRasterization,
+    GlSubmission,
+}
+
+impl RenderStage {
+    pub const ALL: [RenderStage; 3] = [
+        RenderStage::ElementCollection,
+        RenderStage::BarRasterization,
+        RenderStage::GlSubmission,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderStage::ElementCollection => "element_collection",
+            RenderStage::BarRasterization => "bar_rasterization",
+            RenderStage::GlSubmission => "gl_submission",
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            RenderStage::ElementCollection => 0,
+            RenderStage::BarRasterization => 1,
+            RenderStage::GlSubmission => 2,
+        }
+    }
+}
+
+/// Rolling statistics for a single stage
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StageStats {
+    pub last: Duration,
+    pub average: Duration,
+    pub max: Duration,
+    pub samples: u64,
+}
+
+impl StageStats {
+    pub fn record(&mut self, duration: Duration) {
+        self.last = duration;
+        self.max = self.max.max(duration);
+        self.samples += 1;
+
+        // Exponential moving average, so the numbers follow recent behaviour
+        if self.samples == 1 {
+            self.average = duration;
+        } else {
+            self.average = (self.average * 15 + duration) / 16;
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "last_us": self.last.as_micros() as u64,
+            "avg_us": self.average.as_micros() as u64,
+            "max_us": self.max.as_micros() as u64,
+            "samples": self.samples,
+        })
+    }
+}
+
+/// Per-stage timings of the render path, queried via IPC `perf`
+/// and shown on the debug overlay
+pub struct RenderProfiler {
+    stages: [StageStats; 3],
+    frame: StageStats,
+    frame_start: Option<Instant>,
+    /// Every timing goes here too while `webwm-msg trace` records
+    pub trace: Option<TraceSession>,
+}
+
+impl RenderProfiler {
+    pub fn new() -> Self {
+        Self {
+            stages: [StageStats::default(); 3],
+            frame: StageStats::default(),
+            frame_start: None,
+            trace: None,
+        }
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.frame_start = Some(Instant::now());
+    }
+
+    pub fn end_frame(&mut self) {
+        if let Some(start) = self.frame_start.take() {
+            let duration = start.elapsed();
+            self.frame.record(duration);
+            if let Some(ref mut trace) = self.trace {
+                trace.record("frame", Track::Render, start, duration);
+            }
+        }
+    }
+
+    pub fn record(&mut self, stage: RenderStage, duration: Duration) {
+        self.stages[stage.index()].record(duration);
+        if let Some(ref mut trace) = self.trace {
+            let now = Instant::now();
+            let start = now.checked_sub(duration).unwrap_or(now);
+            trace.record(stage.name(), Track::Render, start, duration);
+        }
+    }
+
+    /// Time spent handling an event source since `start`, only kept for
+    /// traces
+    pub fn record_dispatch(&mut self, source: &'static str, start: Instant) {
+        if let Some(ref mut trace) = self.trace {
+            trace.record(source, Track::EventLoop, start, start.elapsed());
+        }
+    }
+
+    pub fn stage(&self, stage: RenderStage) -> &StageStats {
+        &self.stages[stage.index()]
+    }
+
+    pub fn frame(&self) -> &StageStats {
+        &self.frame
+    }
+
+    pub fn report(&self) -> Value {
+        let mut stages = serde_json::Map::new();
+        for stage in RenderStage::ALL {
+            stages.insert(stage.name().to_string(), self.stage(stage).to_json());
+        }
+
+        json!({
+            "frame": self.frame.to_json(),
+            "stages": stages,
+        })
+    }
+
+    /// Short text lines for the debug overlay
+    pub fn overlay_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "frame {:.2}ms max {:.2}ms",
+            self.frame.average.as_secs_f64() * 1000.0,
+            self.frame.max.as_secs_f64() * 1000.0
+        )];
+
+        for stage in RenderStage::ALL {
+            let stats = self.stage(stage);
+            lines.push(format!(
+                "{} {:.2}ms",
+                stage.name().replace('_', " "),
+                stats.average.as_secs_f64() * 1000.0
+            ));
+        }
+
+        lines
+    }
+}
+
+impl Default for RenderProfiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_recording() {
+        let mut profiler = RenderProfiler::new();
+        profiler.record(RenderStage::GlSubmission, Duration::from_millis(4));
+        profiler.record(RenderStage::GlSubmission, Duration::from_millis(2));
+
+        let stats = profiler.stage(RenderStage::GlSubmission);
+        assert_eq!(stats.samples, 2);
+        assert_eq!(stats.last, Duration::from_millis(2));
+        assert_eq!(stats.max, Duration::from_millis(4));
+
+        let report = profiler.report();
+        assert_eq!(report["stages"]["gl_submission"]["max_us"], 4000);
+        assert_eq!(report["stages"]["bar_rasterization"]["samples"], 0);
+    }
+}
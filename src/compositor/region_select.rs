@@ -0,0 +1,262 @@
+// Interactive region selection
+//
+// Screenshot and recording commands need a rectangle to capture. Instead of
+// running slurp, the compositor can ask for it directly: a crosshair follows
+// the pointer, dragging with the left button stretches a rubber band and
+// releasing it finishes. A click without a drag picks the window under the
+// pointer, or the whole output. Escape or a right click cancels. While a
+// selection is in progress pointer and keyboard input don't reach clients.
+//
+// The result replaces `{geometry}` in the command, in slurp's `x,y wxh`
+// format, so `grim -g "{geometry}" shot.png` works unchanged. It is also
+// exported as $WEBWM_REGION and sent as a `region-selected` event.
+
+use serde_json::json;
+use smithay::input::pointer::{CursorIcon, CursorImageStatus, MotionEvent};
+use smithay::utils::{Logical, Point, Rectangle, SERIAL_COUNTER};
+use std::process::Command;
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::WebWMCompositor;
+
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+/// Drags shorter than this, in pixels, count as a click
+const CLICK_THRESHOLD: i32 = 4;
+const BORDER_WIDTH: i32 = 2;
+const DIM_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.4];
+const DEFAULT_BORDER_COLOR: [f32; 4] = [0.54, 0.71, 0.98, 1.0]; // #89b4fa
+const DEFAULT_FILL_COLOR: [f32; 4] = [0.54, 0.71, 0.98, 0.1];
+
+#[derive(Debug, Clone)]
+pub struct RegionSelector {
+    /// Run with the result substituted for `{geometry}`; may be empty
+    pub command: String,
+    pub cursor: Point<i32, Logical>,
+    /// Where the left button went down
+    pub anchor: Option<Point<i32, Logical>>,
+}
+
+impl RegionSelector {
+    pub fn new(command: String, cursor: Point<i32, Logical>) -> Self {
+        Self {
+            command,
+            cursor,
+            anchor: None,
+        }
+    }
+
+    /// The rubber band while dragging
+    pub fn selection(&self) -> Option<Rectangle<i32, Logical>> {
+        self.anchor.map(|anchor| rect_between(anchor, self.cursor))
+    }
+
+    /// The dragged rectangle when the button is released, or None if the
+    /// pointer barely moved and this was a click
+    pub fn release(&self) -> Option<Rectangle<i32, Logical>> {
+        let anchor = self.anchor?;
+        let dragged = (self.cursor.x - anchor.x).abs() >= CLICK_THRESHOLD
+            || (self.cursor.y - anchor.y).abs() >= CLICK_THRESHOLD;
+        dragged.then(|| rect_between(anchor, self.cursor))
+    }
+}
+
+/// Rectangle spanned by two corners, both included
+pub fn rect_between(a: Point<i32, Logical>, b: Point<i32, Logical>) -> Rectangle<i32, Logical> {
+    let (x, y) = (a.x.min(b.x), a.y.min(b.y));
+    Rectangle::from_loc_and_size((x, y), ((a.x - b.x).abs() + 1, (a.y - b.y).abs() + 1))
+}
+
+/// slurp's default output format
+pub fn format_geometry(rect: &Rectangle<i32, Logical>) -> String {
+    format!(
+        "{},{} {}x{}",
+        rect.loc.x, rect.loc.y, rect.size.w, rect.size.h
+    )
+}
+
+impl WebWMCompositor {
+    /// Start selecting a region at `cursor`. Replaces any selection already
+    /// in progress.
+    pub fn begin_region_select(&mut self, command: String, cursor: Point<f64, Logical>) {
+        println!("✂️  Selecting region");
+        self.region_selector = Some(RegionSelector::new(command, cursor.to_i32_round()));
+        self.cursor_image_status = CursorImageStatus::Named(CursorIcon::Crosshair);
+
+        // Take pointer focus away so clients see the pointer leave
+        if let Some(pointer) = self.seat.get_pointer() {
+            pointer.motion(
+                self,
+                None,
+                &MotionEvent {
+                    location: cursor,
+                    serial: SERIAL_COUNTER.next_serial(),
+                    time: 0,
+                },
+            );
+        }
+    }
+
+    pub fn cancel_region_select(&mut self) {
+        if self.region_selector.take().is_some() {
+            println!("✂️  Region selection cancelled");
+            self.cursor_image_status = CursorImageStatus::default_named();
+        }
+    }
+
+    pub fn region_select_motion(&mut self, location: Point<f64, Logical>) {
+        if let Some(ref mut selector) = self.region_selector {
+            selector.cursor = location.to_i32_round();
+        }
+    }
+
+    pub fn region_select_button(&mut self, button: u32, pressed: bool) {
+        let Some(ref mut selector) = self.region_selector else {
+            return;
+        };
+
+        match (button, pressed) {
+            (BTN_LEFT, true) => selector.anchor = Some(selector.cursor),
+            (BTN_LEFT, false) if selector.anchor.is_some() => {
+                let cursor = selector.cursor;
+                let region = selector.release().unwrap_or_else(|| self.region_at(cursor));
+                self.finish_region_select(region);
+            }
+            (BTN_RIGHT, true) => self.cancel_region_select(),
+            _ => {}
+        }
+    }
+
+    fn finish_region_select(&mut self, region: Rectangle<i32, Logical>) {
+        let Some(selector) = self.region_selector.take() else {
+            return;
+        };
+        self.cursor_image_status = CursorImageStatus::default_named();
+
+        let geometry = format_geometry(&region);
+        println!("✂️  Selected region {}", geometry);
+
+        if !selector.command.is_empty() {
+            let command = selector.command.replace("{geometry}", &geometry);
+            Command::new("sh")
+                .arg("-c")
+                .arg(&command)
+                .env("WEBWM_REGION", &geometry)
+                .spawn()
+                .map_err(|e| eprintln!("Failed to spawn '{}': {}", command, e))
+                .ok();
+        }
+
+        self.emit_event(
+            "region-selected",
+            json!({
+                "geometry": geometry,
+                "x": region.loc.x,
+                "y": region.loc.y,
+                "width": region.size.w,
+                "height": region.size.h,
+            }),
+        );
+    }
+
+    /// What a click selects: the window under `point`, else its output
+    fn region_at(&self, point: Point<i32, Logical>) -> Rectangle<i32, Logical> {
+        if let Some((window, location)) = self.space.element_under(point.to_f64()) {
+            let geometry = window.geometry();
+            return Rectangle::new(location + geometry.loc, geometry.size);
+        }
+        self.output_geometry_at(point)
+    }
+
+    fn output_geometry_at(&self, point: Point<i32, Logical>) -> Rectangle<i32, Logical> {
+        self.space
+            .outputs()
+            .filter_map(|output| self.space.output_geometry(output))
+            .find(|geometry| geometry.contains(point))
+            .unwrap_or_else(|| Rectangle::from_loc_and_size((0, 0), (1920, 1080)))
+    }
+
+    /// Dimmed output with the selection cut out, the rubber band and its
+    /// size, or a crosshair before the button goes down. Colors come from
+    /// the stylesheet's `.region-select` rule.
+    pub fn render_region_select(&self) -> Vec<BarElement> {
+        let Some(ref selector) = self.region_selector else {
+            return Vec::new();
+        };
+
+        let style = |property: &str, default: [f32; 4]| {
+            self.stylesheet
+                .as_ref()
+                .and_then(|ss| ss.get_color(".region-select", property))
+                .map(|color| color.to_rgba_f32())
+                .unwrap_or(default)
+        };
+        let border = style("border-color", DEFAULT_BORDER_COLOR);
+        let fill = style("background-color", DEFAULT_FILL_COLOR);
+
+        let output = self.output_geometry_at(selector.cursor);
+        let rect = |x: i32, y: i32, w: i32, h: i32, color: [f32; 4]| BarElement::Rectangle {
+            geometry: Rectangle::from_loc_and_size((x, y), (w, h)),
+            color,
+        };
+        let (ox, oy, ow, oh) = (output.loc.x, output.loc.y, output.size.w, output.size.h);
+        let cursor = selector.cursor;
+
+        let Some(sel) = selector.selection() else {
+            return vec![
+                rect(ox, oy, ow, oh, DIM_COLOR),
+                rect(ox, cursor.y, ow, 1, border),
+                rect(cursor.x, oy, 1, oh, border),
+            ];
+        };
+
+        let (x, y, w, h) = (sel.loc.x, sel.loc.y, sel.size.w, sel.size.h);
+        let b = BORDER_WIDTH;
+        vec![
+            // Dim everything around the selection
+            rect(ox, oy, ow, y - oy, DIM_COLOR),
+            rect(ox, y + h, ow, oy + oh - y - h, DIM_COLOR),
+            rect(ox, y, x - ox, h, DIM_COLOR),
+            rect(x + w, y, ox + ow - x - w, h, DIM_COLOR),
+            rect(x, y, w, h, fill),
+            rect(x - b, y - b, w + 2 * b, b, border),
+            rect(x - b, y + h, w + 2 * b, b, border),
+            rect(x - b, y, b, h, border),
+            rect(x + w, y, b, h, border),
+            BarElement::Text {
+                position: (x, y + h + b + 4),
+                text: format!("{}×{}", w, h),
+                color: border,
+                size: 11,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rect_between() {
+        let rect = rect_between((50, 80).into(), (10, 20).into());
+        assert_eq!(rect, Rectangle::from_loc_and_size((10, 20), (41, 61)));
+        assert_eq!(format_geometry(&rect), "10,20 41x61");
+    }
+
+    #[test]
+    fn test_click_or_drag() {
+        let mut selector = RegionSelector::new(String::new(), (100, 100).into());
+        assert_eq!(selector.selection(), None);
+
+        selector.anchor = Some(selector.cursor);
+        selector.cursor = (102, 101).into();
+        assert_eq!(selector.release(), None);
+
+        selector.cursor = (300, 40).into();
+        assert_eq!(
+            selector.release(),
+            Some(Rectangle::from_loc_and_size((100, 40), (201, 61)))
+        );
+    }
+}
@@ -1,33 +1,113 @@
 use smithay::backend::renderer::{
-    element::{surface::WaylandSurfaceRenderElement, AsRenderElements},
-    gles::{GlesError, GlesFrame, GlesRenderer, GlesTexture},
-    Frame, ImportAll, ImportMem, Renderer,
+    element::{
+        surface::{render_elements_from_surface_tree, WaylandSurfaceRenderElement},
+        AsRenderElements, Element, Kind, RenderElement,
+    },
+    gles::{GlesError, GlesFrame, GlesRenderer, GlesTexProgram, GlesTexture, Uniform},
+    Bind, BlitFrame, Frame, ImportAll, ImportMem, Renderer, Texture, TextureFilter,
 };
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::utils::{Physical, Point, Rectangle, Scale, Size, Transform};
 
 use crate::compositor::bar::BarElement;
 use crate::compositor::bar_renderer::BarTextureRenderer;
+use crate::compositor::blur::{Backdrop, BlurRenderer};
+use crate::compositor::color::{self, ColorTransform};
+use crate::compositor::cursor_theme::{CursorFrame, CursorSprite};
+use crate::compositor::scale::to_output_pixels;
+use crate::compositor::texture_pool::{BufferPool, TexturePool};
 use crate::config::StyleSheet;
 
+/// Logical height of the bar strip
+const BAR_HEIGHT: i32 = 30;
+
 /// Main renderer that handles all GUI drawing
 pub struct WebWMRenderer {
     /// Cached bar texture
     bar_texture: Option<GlesTexture>,
-    bar_buffer: Vec<u8>,
+    bar_elements: Vec<BarElement>,
     bar_size: Size<i32, Physical>,
     bar_dirty: bool,
+    /// Scratch pixels and textures reused across frames
+    buffer_pool: BufferPool,
+    texture_pool: TexturePool,
+    blur: BlurRenderer,
+    /// Color correction for the output being drawn, see `set_color_transform`
+    color_transform: Option<ColorTransform>,
+    color_program: Option<GlesTexProgram>,
+    /// Scale of the output being drawn, see `set_output_scale`
+    output_scale: f64,
+    /// The themed cursor frame last drawn and its texture
+    cursor_texture: Option<(CursorFrame, GlesTexture)>,
+    /// Border width of the workspace being drawn, see `set_border_width`
+    border_width: Option<i32>,
 }
 
 impl WebWMRenderer {
     pub fn new() -> Self {
         Self {
             bar_texture: None,
-            bar_buffer: Vec::new(),
-            bar_size: Size::from((1920, 30)),
+            bar_elements: Vec::new(),
+            bar_size: Size::from((1920, BAR_HEIGHT)),
             bar_dirty: true,
+            buffer_pool: BufferPool::default(),
+            texture_pool: TexturePool::default(),
+            blur: BlurRenderer::default(),
+            color_transform: None,
+            color_program: None,
+            output_scale: 1.0,
+            cursor_texture: None,
+            border_width: None,
         }
     }
 
+    /// Convert everything drawn from now on for an output's ICC profile,
+    /// or draw plain sRGB with `None`
+    pub fn set_color_transform(&mut self, transform: Option<ColorTransform>) {
+        self.color_transform = transform;
+    }
+
+    /// Draw window borders `width` wide, overriding the `window` rules, or
+    /// as those rules say with `None`
+    pub fn set_border_width(&mut self, width: Option<i32>) {
+        self.border_width = width;
+    }
+
+    /// Draw for an output at `scale`. Window rectangles are already in
+    /// output pixels; bars, backdrops and drag icons are scaled here.
+    pub fn set_output_scale(&mut self, scale: f64) {
+        if self.output_scale != scale {
+            self.output_scale = scale;
+            self.bar_dirty = true;
+        }
+    }
+
+    /// Solid colors are corrected on the CPU
+    fn output_color(&self, color: [f32; 4]) -> [f32; 4] {
+        match self.color_transform {
+            Some(ref transform) => transform.apply(color),
+            None => color,
+        }
+    }
+
+    /// Shader and uniforms for drawing textures, None when uncorrected
+    fn texture_program(
+        &mut self,
+        renderer: &mut GlesRenderer,
+    ) -> Result<Option<(GlesTexProgram, Vec<Uniform<'static>>)>, GlesError> {
+        let Some(ref transform) = self.color_transform else {
+            return Ok(None);
+        };
+
+        if self.color_program.is_none() {
+            self.color_program = Some(color::color_program(renderer)?);
+        }
+        Ok(self
+            .color_program
+            .clone()
+            .map(|program| (program, transform.uniforms())))
+    }
+
     /// Render a complete frame with windows, borders, and bars
     pub fn render_frame(
         &mut self,
@@ -35,27 +115,46 @@ impl WebWMRenderer {
         frame: &mut GlesFrame,
         windows: &[(&smithay::desktop::Window, Rectangle<i32, Physical>)],
         bar_elements: &[BarElement],
+        backdrops: &[Backdrop],
+        drag_icon: Option<(&WlSurface, Point<i32, Physical>)>,
+        cursor: Option<(&CursorSprite, Point<i32, Physical>)>,
         stylesheet: Option<&StyleSheet>,
         output_size: Size<i32, Physical>,
     ) -> Result<(), GlesError> {
         // 1. Clear background
         self.clear_background(frame, stylesheet)?;
 
-        // 2. Render windows with borders
+        // 2. Render windows with borders, blurring what's behind them first
+        let mut backdrops: Vec<&Backdrop> = backdrops.iter().collect();
         for (i, (window, geometry)) in windows.iter().enumerate() {
             // Determine if this window is focused (first window is typically focused)
             let is_focused = i == 0;
 
+            self.render_backdrop_for(renderer, frame, &mut backdrops, *geometry)?;
             self.render_window_with_border(
                 renderer, frame, window, *geometry, stylesheet, is_focused,
             )?;
         }
 
-        // 3. Render status bar
+        // 3. Render status bar on top of its blurred backdrop
+        for backdrop in backdrops {
+            self.render_backdrop(renderer, frame, backdrop)?;
+        }
         if !bar_elements.is_empty() {
             self.render_bar(renderer, frame, bar_elements, output_size)?;
         }
 
+        // 4. Drag-and-drop icon follows the pointer above everything
+        if let Some((surface, location)) = drag_icon {
+            self.render_drag_icon(renderer, frame, surface, location)?;
+        }
+
+        // 5. Cursor, with its hotspot at the pointer
+        if let Some((sprite, location)) = cursor {
+            self.render_cursor(renderer, frame, sprite, location)?;
+        }
+
+        self.end_frame();
         Ok(())
     }
 
@@ -66,6 +165,9 @@ impl WebWMRenderer {
         frame: &mut GlesFrame,
         windows: &[(&smithay::desktop::Window, Rectangle<i32, Physical>, bool)],
         bar_elements: &[BarElement],
+        backdrops: &[Backdrop],
+        drag_icon: Option<(&WlSurface, Point<i32, Physical>)>,
+        cursor: Option<(&CursorSprite, Point<i32, Physical>)>,
         stylesheet: Option<&StyleSheet>,
         output_size: Size<i32, Physical>,
     ) -> Result<(), GlesError> {
@@ -73,7 +175,9 @@ impl WebWMRenderer {
         self.clear_background(frame, stylesheet)?;
 
         // 2. Render windows with borders and proper focus state
+        let mut backdrops: Vec<&Backdrop> = backdrops.iter().collect();
         for (window, geometry, is_focused) in windows {
+            self.render_backdrop_for(renderer, frame, &mut backdrops, *geometry)?;
             self.render_window_with_border(
                 renderer,
                 frame,
@@ -84,11 +188,25 @@ impl WebWMRenderer {
             )?;
         }
 
-        // 3. Render status bar
+        // 3. Render status bar on top of its blurred backdrop
+        for backdrop in backdrops {
+            self.render_backdrop(renderer, frame, backdrop)?;
+        }
         if !bar_elements.is_empty() {
             self.render_bar(renderer, frame, bar_elements, output_size)?;
         }
 
+        // 4. Drag-and-drop icon follows the pointer above everything
+        if let Some((surface, location)) = drag_icon {
+            self.render_drag_icon(renderer, frame, surface, location)?;
+        }
+
+        // 5. Cursor, with its hotspot at the pointer
+        if let Some((sprite, location)) = cursor {
+            self.render_cursor(renderer, frame, sprite, location)?;
+        }
+
+        self.end_frame();
         Ok(())
     }
 
@@ -106,7 +224,7 @@ impl WebWMRenderer {
             [0.10, 0.11, 0.15, 1.0]
         };
 
-        frame.clear(bg_color.into(), &[])?;
+        frame.clear(self.output_color(bg_color).into(), &[])?;
         Ok(())
     }
 
@@ -132,7 +250,9 @@ impl WebWMRenderer {
                     [0.19, 0.20, 0.27, 1.0] // #313244 (normal)
                 });
 
-            let width = ss.get_length(selector, "border-width").unwrap_or(2.0) as i32;
+            let width = self
+                .border_width
+                .unwrap_or_else(|| ss.get_length(selector, "border-width").unwrap_or(2.0) as i32);
 
             (color, width)
         } else {
@@ -142,7 +262,7 @@ impl WebWMRenderer {
                 } else {
                     [0.19, 0.20, 0.27, 1.0]
                 },
-                2,
+                self.border_width.unwrap_or(2),
             )
         };
 
@@ -172,8 +292,10 @@ impl WebWMRenderer {
             Rectangle::new(geometry.loc, (border_width, geometry.size.h).into()),
         ];
 
-        for border_rect in &borders {
-            self.render_solid_rect(frame, *border_rect, border_color)?;
+        if border_width > 0 {
+            for border_rect in &borders {
+                self.render_solid_rect(frame, *border_rect, border_color)?;
+            }
         }
 
         // Render window content surface
@@ -199,15 +321,227 @@ impl WebWMRenderer {
 
     fn render_bar(
         &mut self,
-        _renderer: &mut GlesRenderer,
-        _frame: &mut GlesFrame,
-        _elements: &[BarElement],
-        _output_size: Size<i32, Physical>,
+        renderer: &mut GlesRenderer,
+        frame: &mut GlesFrame,
+        elements: &[BarElement],
+        output_size: Size<i32, Physical>,
     ) -> Result<(), GlesError> {
-        // TODO: Implement bar rendering
+        // Rasterized at output resolution so text stays sharp when scaled
+        let bar_size = Size::from((
+            output_size.w,
+            (BAR_HEIGHT as f64 * self.output_scale).round() as i32,
+        ));
+        if self.bar_size != bar_size {
+            self.bar_size = bar_size;
+            self.bar_dirty = true;
+        }
+        if self.bar_elements != elements {
+            self.bar_elements = elements.to_vec();
+            self.bar_dirty = true;
+        }
+
+        // Only re-rasterize when the bar changed; the strip and its texture
+        // come from the pools so this doesn't allocate in steady state
+        if self.bar_dirty || self.bar_texture.is_none() {
+            let rasterizer = BarTextureRenderer::new(self.bar_size.w, self.bar_size.h);
+            let mut buffer = self.buffer_pool.acquire(rasterizer.buffer_len());
+            let scaled: Vec<BarElement> = elements
+                .iter()
+                .map(|element| element.scaled(self.output_scale))
+                .collect();
+            rasterizer.render_into(&mut buffer, &scaled);
+
+            if let Some(old) = self.bar_texture.take() {
+                self.texture_pool.release(old);
+            }
+            let size = Size::from((self.bar_size.w, self.bar_size.h));
+            self.bar_texture = Some(self.texture_pool.upload(renderer, &buffer, size)?);
+            self.buffer_pool.release(buffer);
+            self.bar_dirty = false;
+        }
+
+        let program = self.texture_program(renderer)?;
+        if let Some(ref texture) = self.bar_texture {
+            let dst = Rectangle::from_size(self.bar_size);
+            let src = Rectangle::from_size(texture.size().to_f64());
+            let (program, uniforms) = match program {
+                Some((ref program, ref uniforms)) => (Some(program), uniforms.as_slice()),
+                None => (None, [].as_slice()),
+            };
+            frame.render_texture_from_to(
+                texture,
+                src,
+                dst,
+                &[dst],
+                &[],
+                Transform::Normal,
+                1.0,
+                program,
+                uniforms,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Blur behind a window about to be drawn, if it asked for it
+    fn render_backdrop_for(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        frame: &mut GlesFrame,
+        backdrops: &mut Vec<&Backdrop>,
+        geometry: Rectangle<i32, Physical>,
+    ) -> Result<(), GlesError> {
+        let scale = self.output_scale;
+        if let Some(i) = backdrops
+            .iter()
+            .position(|b| to_output_pixels(b.geometry, scale) == geometry)
+        {
+            let backdrop = backdrops.remove(i);
+            self.render_backdrop(renderer, frame, backdrop)?;
+        }
         Ok(())
     }
 
+    /// Copy what has been drawn under the backdrop so far, blur it and draw
+    /// it back in place. The copy is already color corrected, so the result
+    /// is drawn as is.
+    fn render_backdrop(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        frame: &mut GlesFrame,
+        backdrop: &Backdrop,
+    ) -> Result<(), GlesError> {
+        let region = to_output_pixels(backdrop.geometry, self.output_scale);
+        if region.is_empty() {
+            return Ok(());
+        }
+
+        let size = Size::from((region.size.w, region.size.h));
+        let mut capture = self.texture_pool.acquire(renderer, size)?;
+        {
+            let mut target = renderer.bind(&mut capture)?;
+            frame.blit_to(
+                &mut target,
+                region,
+                Rectangle::from_size(region.size),
+                TextureFilter::Linear,
+            )?;
+        }
+
+        let blurred = self.blur.blur(
+            renderer,
+            &mut self.texture_pool,
+            capture,
+            &backdrop.settings,
+        )?;
+        frame.render_texture_from_to(
+            &blurred,
+            Rectangle::from_size(blurred.size().to_f64()),
+            region,
+            &[region],
+            &[],
+            Transform::Normal,
+            backdrop.settings.opacity,
+            None,
+            &[],
+        )?;
+        self.texture_pool.release(blurred);
+
+        Ok(())
+    }
+
+    /// Draw a client surface tree, e.g. a drag icon, at `location`
+    fn render_drag_icon(
+        &self,
+        renderer: &mut GlesRenderer,
+        frame: &mut GlesFrame,
+        surface: &WlSurface,
+        location: Point<i32, Physical>,
+    ) -> Result<(), GlesError> {
+        let scale = Scale::from(self.output_scale);
+        let elements: Vec<WaylandSurfaceRenderElement<GlesRenderer>> =
+            render_elements_from_surface_tree(
+                renderer,
+                surface,
+                location,
+                scale,
+                1.0,
+                Kind::Unspecified,
+            );
+
+        // Elements come front to back; paint back to front
+        for element in elements.iter().rev() {
+            let dst = element.geometry(scale);
+            element.draw(
+                frame,
+                element.src(),
+                dst,
+                &[Rectangle::from_size(dst.size)],
+                &[],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draw the cursor for a pointer at `location`
+    fn render_cursor(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        frame: &mut GlesFrame,
+        sprite: &CursorSprite,
+        location: Point<i32, Physical>,
+    ) -> Result<(), GlesError> {
+        let scale = self.output_scale;
+        let px = |v: i32| (v as f64 * scale).round() as i32;
+
+        let cursor = match sprite {
+            CursorSprite::Surface(surface, hotspot) => {
+                let origin = location - Point::from((px(hotspot.x), px(hotspot.y)));
+                return self.render_drag_icon(renderer, frame, surface, origin);
+            }
+            CursorSprite::Themed(cursor) => cursor,
+        };
+
+        // Animated cursors change frames; still ones reuse their texture
+        if self
+            .cursor_texture
+            .as_ref()
+            .is_none_or(|(drawn, _)| drawn != cursor)
+        {
+            let size = Size::from((cursor.width as i32, cursor.height as i32));
+            let texture = self.texture_pool.upload(renderer, &cursor.pixels, size)?;
+            if let Some((_, old)) = self.cursor_texture.replace((cursor.clone(), texture)) {
+                self.texture_pool.release(old);
+            }
+        }
+        let Some((_, ref texture)) = self.cursor_texture else {
+            return Ok(());
+        };
+
+        let dst = Rectangle::new(
+            location - Point::from((px(cursor.hotspot.0), px(cursor.hotspot.1))),
+            Size::from((px(cursor.width as i32), px(cursor.height as i32))),
+        );
+        frame.render_texture_from_to(
+            texture,
+            Rectangle::from_size(texture.size().to_f64()),
+            dst,
+            &[dst],
+            &[],
+            Transform::Normal,
+            1.0,
+            None,
+            &[],
+        )
+    }
+
+    /// Let the pools drop scratch space that went unused for a while
+    fn end_frame(&mut self) {
+        self.buffer_pool.end_frame();
+        self.texture_pool.end_frame();
+    }
+
     fn render_solid_rect(
         &self,
         frame: &mut GlesFrame,
@@ -222,7 +556,7 @@ impl WebWMRenderer {
         // using the alpha channel for transparency
 
         // Create damage region for this rectangle
-        frame.clear(color.into(), &[rect])?;
+        frame.clear(self.output_color(color).into(), &[rect])?;
 
         Ok(())
     }
@@ -0,0 +1,143 @@
+// Reopen closed windows
+//
+// The last few closed windows are remembered with their app_id, the command
+// line of their process (read from /proc while it is still running), the
+// workspace they were on and their place among its windows, which is what
+// decides where the layouts put them. The reopen-last action runs the most
+// recent one's command again, or its app_id when the command line isn't
+// known, and puts the new window back where the old one was.
+
+use smithay::desktop::Window;
+use smithay::wayland::seat::WaylandFocus;
+
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+/// How many closed windows are remembered
+pub const RECENTLY_CLOSED_LIMIT: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedWindow {
+    pub app_id: String,
+    pub command: Option<String>,
+    pub workspace: u32,
+    /// Index among the workspace's windows
+    pub position: usize,
+}
+
+impl ClosedWindow {
+    /// What to run to get the window back
+    pub fn relaunch_command(&self) -> Option<&str> {
+        self.command
+            .as_deref()
+            .or(Some(self.app_id.as_str()))
+            .filter(|command| !command.is_empty())
+    }
+}
+
+/// Quote `arg` for `sh -c` if it needs it
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Command line from the NUL-separated contents of /proc/<pid>/cmdline
+pub fn parse_cmdline(raw: &[u8]) -> Option<String> {
+    let args: Vec<String> = raw
+        .split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| shell_quote(&String::from_utf8_lossy(arg)))
+        .collect();
+    (!args.is_empty()).then(|| args.join(" "))
+}
+
+fn command_line(pid: u32) -> Option<String> {
+    parse_cmdline(&std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?)
+}
+
+impl WebWMCompositor {
+    /// Note what is needed to reopen `window`; call before it is removed
+    /// from its workspace
+    pub fn remember_closed_window(&mut self, window: &Window) {
+        let Some(workspace) = self
+            .workspace_manager
+            .find_window_workspace(window)
+            .and_then(|id| self.workspace_manager.get_workspace(id))
+        else {
+            return;
+        };
+        let closed = ClosedWindow {
+            app_id: window_app_id(window),
+            command: window
+                .wl_surface()
+                .and_then(|surface| self.client_pid(&surface))
+                .and_then(command_line),
+            workspace: workspace.id,
+            position: workspace
+                .windows
+                .iter()
+                .position(|w| w == window)
+                .unwrap_or(workspace.windows.len()),
+        };
+        if closed.relaunch_command().is_none() {
+            return;
+        }
+
+        self.recently_closed.push(closed);
+        if self.recently_closed.len() > RECENTLY_CLOSED_LIMIT {
+            self.recently_closed.remove(0);
+        }
+    }
+
+    /// Relaunch the most recently closed window and put it back in place
+    pub fn reopen_last(&mut self) {
+        let Some(closed) = self.recently_closed.pop() else {
+            println!("Nothing to reopen");
+            return;
+        };
+        let Some(command) = closed.relaunch_command().map(str::to_string) else {
+            return;
+        };
+
+        println!("♻️ Reopening '{}'", closed.app_id);
+        let app_id = Some(closed.app_id).filter(|app_id| !app_id.is_empty());
+        self.spawn_and_wait(
+            &command,
+            app_id,
+            Some(closed.workspace),
+            Some(closed.position),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cmdline() {
+        assert_eq!(
+            parse_cmdline(b"foot\0--working-directory=/home/me\0").as_deref(),
+            Some("foot --working-directory=/home/me")
+        );
+        assert_eq!(
+            parse_cmdline(b"mpv\0My Movie's Cut.mkv\0").as_deref(),
+            Some(r"mpv 'My Movie'\''s Cut.mkv'")
+        );
+        assert_eq!(parse_cmdline(b""), None);
+
+        let closed = ClosedWindow {
+            app_id: "firefox".to_string(),
+            command: None,
+            workspace: 2,
+            position: 0,
+        };
+        assert_eq!(closed.relaunch_command(), Some("firefox"));
+    }
+}
@@ -0,0 +1,55 @@
+// Safe mode
+//
+// When the configuration fails to load at startup, WebWM still starts,
+// with the defaults and a few extra keybindings (focus with Super+h/j/k/l,
+// workspaces 1 to 4, reload with Super+Shift+R), no config.js and no theme.
+// A banner across the top says which file and line broke and what went
+// wrong, so there's no need to dig through the log. Saving the fixed file
+// reloads the configuration like any other change, which leaves safe mode;
+// a reload that fails again updates the banner.
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::ui::{rect, Canvas};
+use crate::compositor::WebWMCompositor;
+
+/// Height of the banner, which takes the place of the bar
+pub const BANNER_HEIGHT: i32 = 56;
+
+const BANNER_BACKGROUND: [f32; 4] = [0.55, 0.12, 0.12, 1.0];
+const BANNER_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const BANNER_HINT_COLOR: [f32; 4] = [1.0, 0.85, 0.85, 1.0];
+
+impl WebWMCompositor {
+    pub fn render_safe_mode_banner(&self) -> Vec<BarElement> {
+        let Some(ref error) = self.safe_mode else {
+            return Vec::new();
+        };
+
+        let mut canvas = Canvas::new();
+        canvas.fill(
+            rect(0, 0, self.layout_size().w, BANNER_HEIGHT),
+            BANNER_BACKGROUND,
+        );
+        canvas.text(
+            (12, 6).into(),
+            "Safe mode: the configuration didn't load",
+            BANNER_COLOR,
+            14,
+        );
+        // Long errors, like XML ones, go on over several lines
+        let first_line = error
+            .to_string()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        canvas.text((12, 24).into(), first_line, BANNER_COLOR, 12);
+        canvas.text(
+            (12, 40).into(),
+            "Fix it and save to reload, or press Super+Shift+R",
+            BANNER_HINT_COLOR,
+            11,
+        );
+        canvas.finish()
+    }
+}
@@ -0,0 +1,159 @@
+// Output scaling
+//
+// `<output scale="1.5"/>` sets an output's HiDPI scale. Windows are laid out
+// in logical coordinates, so a 2880x1800 panel at 1.5 is tiled as if it
+// were 1920x1200, and the backends convert to pixels when drawing. Clients
+// hear the exact scale through wp_fractional_scale_v1 and present their
+// larger buffers at logical size with wp_viewporter. Clients without it get
+// the scale rounded up as their buffer scale and are scaled down, which
+// stays sharper than scaling up.
+
+use smithay::desktop::Window;
+use smithay::output::Scale;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::utils::{Logical, Physical, Rectangle, Size, Transform};
+use smithay::wayland::compositor::{send_surface_state, with_states};
+use smithay::wayland::fractional_scale::{with_fractional_scale, FractionalScaleHandler};
+use smithay::{delegate_fractional_scale, delegate_viewporter};
+
+use crate::compositor::WebWMCompositor;
+
+/// Layout size while no output is mapped
+const FALLBACK_SIZE: (i32, i32) = (1920, 1080);
+
+/// A logical rectangle in the pixels of an output drawn at `scale`. The
+/// edges are rounded rather than position and size, so neighbouring tiles
+/// stay flush at any scale.
+pub fn to_output_pixels(rect: Rectangle<i32, Logical>, scale: f64) -> Rectangle<i32, Physical> {
+    let px = |v: i32| (v as f64 * scale).round() as i32;
+    let (x1, y1) = (px(rect.loc.x), px(rect.loc.y));
+    let (x2, y2) = (px(rect.loc.x + rect.size.w), px(rect.loc.y + rect.size.h));
+    Rectangle::from_loc_and_size((x1, y1), (x2 - x1, y2 - y1))
+}
+
+/// Buffer scale for clients that only understand integers
+pub fn integer_scale(scale: f64) -> i32 {
+    scale.ceil().max(1.0) as i32
+}
+
+impl WebWMCompositor {
+    /// Apply the `scale` of each configured output in the space
+    pub fn apply_output_scales(&mut self) {
+        let configs = self
+            .config
+            .desktop
+            .as_ref()
+            .map(|desktop| desktop.outputs.clone())
+            .unwrap_or_default();
+
+        let outputs: Vec<_> = self.space.outputs().cloned().collect();
+        for output in outputs {
+            let Some(scale) = configs
+                .iter()
+                .find(|config| config.name == output.name())
+                .and_then(|config| config.scale)
+            else {
+                continue;
+            };
+            output.change_current_state(None, None, Some(Scale::Fractional(scale)), None);
+            println!("✓ {}: scale {}", output.name(), scale);
+        }
+
+        self.relayout();
+    }
+
+    /// Size of the primary output in logical coordinates, which is what
+    /// layouts work in
+    pub fn layout_size(&self) -> Size<i32, Logical> {
+        self.space
+            .outputs()
+            .next()
+            .and_then(|output| self.space.output_geometry(output))
+            .map(|geometry| geometry.size)
+            .unwrap_or_else(|| FALLBACK_SIZE.into())
+    }
+
+    /// Scale of the output a window is on, or of the primary output
+    pub fn window_scale(&self, window: &Window) -> f64 {
+        self.space
+            .outputs_for_element(window)
+            .first()
+            .or_else(|| self.space.outputs().next())
+            .map(|output| output.current_scale().fractional_scale())
+            .unwrap_or(1.0)
+    }
+
+    /// Tell the surfaces of every mapped window the scale of their output.
+    /// Unchanged scales aren't sent again.
+    pub fn send_surface_scales(&self) {
+        for window in self.space.elements() {
+            let scale = self.window_scale(window);
+            window.with_surfaces(|surface, states| {
+                with_fractional_scale(states, |fractional| fractional.set_preferred_scale(scale));
+                send_surface_state(surface, states, integer_scale(scale), Transform::Normal);
+            });
+        }
+    }
+}
+
+impl FractionalScaleHandler for WebWMCompositor {
+    fn new_fractional_scale(&mut self, surface: WlSurface) {
+        // Surfaces that aren't mapped yet start at the primary output's
+        // scale; the relayout mapping them corrects it if needed
+        let scale = self
+            .space
+            .elements()
+            .find(|window| {
+                window
+                    .toplevel()
+                    .is_some_and(|toplevel| toplevel.wl_surface() == &surface)
+            })
+            .map(|window| self.window_scale(window))
+            .or_else(|| {
+                self.space
+                    .outputs()
+                    .next()
+                    .map(|output| output.current_scale().fractional_scale())
+            })
+            .unwrap_or(1.0);
+
+        with_states(&surface, |states| {
+            with_fractional_scale(states, |fractional| fractional.set_preferred_scale(scale));
+        });
+    }
+}
+
+delegate_fractional_scale!(WebWMCompositor);
+delegate_viewporter!(WebWMCompositor);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_pixels() {
+        let rect = |x, y, w, h| Rectangle::<i32, Logical>::from_loc_and_size((x, y), (w, h));
+
+        assert_eq!(
+            to_output_pixels(rect(10, 20, 300, 200), 1.0),
+            Rectangle::from_loc_and_size((10, 20), (300, 200))
+        );
+        assert_eq!(
+            to_output_pixels(rect(10, 20, 300, 200), 2.0),
+            Rectangle::from_loc_and_size((20, 40), (600, 400))
+        );
+
+        // Side by side tiles at 1.25 neither overlap nor leave a gap
+        let left = to_output_pixels(rect(5, 0, 951, 100), 1.25);
+        let right = to_output_pixels(rect(956, 0, 951, 100), 1.25);
+        assert_eq!(left.loc.x + left.size.w, right.loc.x);
+    }
+
+    #[test]
+    fn test_integer_scale() {
+        assert_eq!(integer_scale(1.0), 1);
+        assert_eq!(integer_scale(1.25), 2);
+        assert_eq!(integer_scale(2.0), 2);
+        assert_eq!(integer_scale(0.5), 1);
+    }
+}
@@ -0,0 +1,114 @@
+// Scroll to resize splits
+//
+// Scrolling while the pointer is on the gap between two tiles moves that
+// gap, so a two-finger swipe on a touchpad drags it along with the fingers.
+// Holding Super widens the spot to a few pixels either side of the gap,
+// which helps with small gaps and mouse wheels. This works on the dwindle
+// layout's splits and the master-stack divider. While resizing, a small
+// badge next to the pointer shows the share the first side now has; its
+// colors come from the stylesheet's `.split-indicator` rule.
+
+use std::time::{Duration, Instant};
+
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::workspace::LayoutMode;
+use crate::compositor::{text, WebWMCompositor};
+
+/// How far from the gap Super+scroll still catches it
+const SUPER_MARGIN: i32 = 24;
+
+/// How long the badge stays after the last scroll
+const INDICATOR_TIMEOUT: Duration = Duration::from_millis(1000);
+
+const INDICATOR_PADDING: i32 = 6;
+const INDICATOR_OFFSET: i32 = 16;
+
+const DEFAULT_BACKGROUND: [f32; 4] = [0.11, 0.11, 0.18, 0.9];
+const DEFAULT_COLOR: [f32; 4] = [0.8, 0.83, 0.96, 1.0];
+
+/// The percentage badge of the last resize
+#[derive(Debug, Clone, Copy)]
+pub struct SplitIndicator {
+    pub ratio: f32,
+    pub position: Point<i32, Logical>,
+    pub shown_at: Instant,
+}
+
+impl WebWMCompositor {
+    /// Resize the split whose gap is under `point` by a scroll of
+    /// `horizontal` and `vertical`. Returns false if there's no gap there,
+    /// and the scroll should go to the client instead.
+    pub fn scroll_split(
+        &mut self,
+        point: Point<f64, Logical>,
+        horizontal: f64,
+        vertical: f64,
+        super_held: bool,
+    ) -> bool {
+        let point = point.to_i32_round();
+        let margin = if super_held { SUPER_MARGIN } else { 0 };
+        let pixels = horizontal + vertical;
+
+        let ratio = match self.workspace_manager.active_workspace().layout_mode {
+            LayoutMode::Dwindle => {
+                let gaps = self.get_gaps();
+                let area = self.tiled_area(self.layout_size());
+                self.workspace_manager
+                    .active_workspace_mut()
+                    .dwindle
+                    .resize_split_at(point, area, gaps, margin, pixels)
+            }
+            LayoutMode::MasterStack => self.resize_master_at(point, margin, pixels),
+            _ => None,
+        };
+        let Some(ratio) = ratio else {
+            return false;
+        };
+
+        self.split_indicator = Some(SplitIndicator {
+            ratio,
+            position: point,
+            shown_at: Instant::now(),
+        });
+        self.relayout();
+        true
+    }
+
+    pub fn render_split_indicator(&self) -> Vec<BarElement> {
+        let Some(indicator) = self
+            .split_indicator
+            .filter(|indicator| indicator.shown_at.elapsed() < INDICATOR_TIMEOUT)
+        else {
+            return Vec::new();
+        };
+
+        let style = |property: &str, default: [f32; 4]| {
+            self.stylesheet
+                .as_ref()
+                .and_then(|ss| ss.get_color(".split-indicator", property))
+                .map(|color| color.to_rgba_f32())
+                .unwrap_or(default)
+        };
+
+        let label = format!("{:.0}%", indicator.ratio * 100.0);
+        let width = text::measure(&label, 12) + INDICATOR_PADDING * 2;
+        let height = 12 + INDICATOR_PADDING * 2;
+        let x = indicator.position.x + INDICATOR_OFFSET;
+        let y = indicator.position.y + INDICATOR_OFFSET;
+
+        vec![
+            BarElement::Rectangle {
+                geometry: Rectangle::from_loc_and_size((x, y), (width, height)),
+                color: style("background", DEFAULT_BACKGROUND),
+            },
+            BarElement::Text {
+                position: (x + INDICATOR_PADDING, y + INDICATOR_PADDING),
+                text: label,
+                color: style("color", DEFAULT_COLOR),
+                size: 12,
+            },
+        ]
+    }
+}
@@ -0,0 +1,105 @@
+// Laptop lid and tablet-mode switches
+//
+// Closing the lid turns off the internal panel when an external monitor is
+// connected; opening it brings the panel back and re-applies the matching
+// output profile. Both switches are also emitted as `lid` and `tablet-mode`
+// events for JS `on()` handlers and IPC subscribers.
+
+use serde_json::json;
+use smithay::output::Output;
+use smithay::utils::{Logical, Point};
+
+use crate::compositor::WebWMCompositor;
+
+#[derive(Debug, Default)]
+pub struct SwitchState {
+    pub lid_closed: bool,
+    pub tablet_mode: bool,
+    /// Internal panel turned off by the lid, with its previous position
+    disabled_output: Option<(Output, Point<i32, Logical>)>,
+}
+
+/// Built-in panels use these connector types
+pub fn is_internal_output(name: &str) -> bool {
+    ["eDP", "LVDS", "DSI"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+impl WebWMCompositor {
+    pub fn handle_lid_switch(&mut self, closed: bool) {
+        if self.switches.lid_closed == closed {
+            return;
+        }
+        self.switches.lid_closed = closed;
+
+        let mut internal_disabled = false;
+        if closed {
+            let internal = self
+                .space
+                .outputs()
+                .find(|output| is_internal_output(&output.name()))
+                .cloned();
+            let has_external = self
+                .space
+                .outputs()
+                .any(|output| !is_internal_output(&output.name()));
+
+            // Never turn off the only screen
+            if let (Some(output), true) = (internal, has_external) {
+                let position = self
+                    .space
+                    .output_geometry(&output)
+                    .map(|geometry| geometry.loc)
+                    .unwrap_or_default();
+                self.space.unmap_output(&output);
+                println!("💻 Lid closed: disabled {}", output.name());
+
+                // Windows are laid out on the first output whatever their
+                // workspace, so everything that was on the panel moves to the
+                // remaining monitors. Workspaces bound to the panel with
+                // `output` are left off the bars until the lid opens.
+                self.switches.disabled_output = Some((output, position));
+                internal_disabled = true;
+                self.relayout();
+                self.emit_output_changes();
+            }
+        } else if let Some((output, position)) = self.switches.disabled_output.take() {
+            self.space.map_output(&output, position);
+            println!("💻 Lid opened: re-enabled {}", output.name());
+            self.outputs_changed();
+            self.relayout();
+        }
+
+        self.emit_event(
+            "lid",
+            json!({ "closed": closed, "internal_disabled": internal_disabled }),
+        );
+    }
+
+    pub fn handle_tablet_mode(&mut self, enabled: bool) {
+        if self.switches.tablet_mode == enabled {
+            return;
+        }
+        self.switches.tablet_mode = enabled;
+        println!(
+            "📱 Tablet mode {}",
+            if enabled { "enabled" } else { "disabled" }
+        );
+
+        self.emit_event("tablet-mode", json!({ "enabled": enabled }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_internal_output_detection() {
+        assert!(is_internal_output("eDP-1"));
+        assert!(is_internal_output("LVDS-1"));
+        assert!(!is_internal_output("DP-2"));
+        assert!(!is_internal_output("HDMI-A-1"));
+    }
+}
@@ -0,0 +1,264 @@
+// Tab groups
+//
+// `merge` puts the focused window into the neighboring tile as a tab; the
+// tile then shows one window at a time under a strip of tab titles, like a
+// browser. `split` takes the focused window back out into its own tile.
+// Focus cycling walks through tabs like any other window, so focusing a tab
+// brings it to the front, and so does clicking its title.
+//
+// The tabbed layout treats the whole workspace as one such group: every
+// window fills the area below the bar and the strip lists them all.
+
+use smithay::desktop::Window;
+use smithay::utils::{Logical, Point, Rectangle, Size, SERIAL_COUNTER};
+use smithay::wayland::seat::WaylandFocus;
+
+use crate::compositor::audio::{badge_geometry, BADGE_WIDTH};
+use crate::compositor::bar::BarElement;
+use crate::compositor::text;
+use crate::compositor::workspace::{LayoutMode, TabGroup};
+use crate::compositor::{window_app_id, window_title, WebWMCompositor};
+
+/// Height of the tab title strip above a grouped tile
+pub const TAB_BAR_HEIGHT: i32 = 24;
+
+impl WebWMCompositor {
+    /// `direction` is left/up for the previous tile, right/down for the next
+    pub fn merge_focused_window(&mut self, direction: &str) {
+        let forward = match direction {
+            "left" | "up" => false,
+            "right" | "down" => true,
+            _ => return,
+        };
+
+        if self
+            .workspace_manager
+            .active_workspace_mut()
+            .merge_focused(forward)
+        {
+            println!("🗂️  Merged window into tab group ({})", direction);
+            self.relayout();
+        }
+    }
+
+    pub fn split_focused_window(&mut self) {
+        if self
+            .workspace_manager
+            .active_workspace_mut()
+            .split_focused()
+        {
+            println!("🗂️  Split window out of its tab group");
+            self.relayout();
+        }
+    }
+
+    /// Place a tile: only the active tab is mapped, but every tab gets the
+    /// same size so switching tabs doesn't need a new configure
+    pub fn map_tile(
+        &mut self,
+        tile: &TabGroup,
+        loc: Point<i32, Logical>,
+        size: Size<i32, Logical>,
+    ) {
+        let (loc, size): (Point<i32, Logical>, Size<i32, Logical>) = if tile.windows.len() > 1 {
+            (
+                (loc.x, loc.y + TAB_BAR_HEIGHT).into(),
+                (size.w, size.h - TAB_BAR_HEIGHT).into(),
+            )
+        } else {
+            (loc, size)
+        };
+
+        let workspace = self.workspace_manager.active_workspace();
+        let (fullscreen, maximized) = (
+            workspace.fullscreen_window.clone(),
+            workspace.maximized_window.clone(),
+        );
+
+        for (i, window) in tile.windows.iter().enumerate() {
+            // Sized by layout_fullscreen or layout_maximized instead
+            if fullscreen.as_ref() == Some(window) || maximized.as_ref() == Some(window) {
+                self.drop_placement(window);
+                continue;
+            }
+
+            if i == tile.active {
+                self.place_window(window, loc, size);
+                continue;
+            }
+
+            self.drop_placement(window);
+            self.space.unmap_elem(window);
+            if let Some(toplevel) = window.toplevel() {
+                toplevel.with_pending_state(|state| {
+                    state.size = Some(size);
+                });
+                toplevel.send_configure();
+            }
+        }
+    }
+
+    pub fn layout_tabbed(&mut self, output_size: Size<i32, Logical>) {
+        let Some(tabs) = self.workspace_manager.active_workspace().as_tab_group() else {
+            return;
+        };
+
+        let area = self.tiled_area(output_size);
+        self.map_tile(&tabs, area.loc, area.size);
+
+        let active_ws = self.workspace_manager.active_workspace();
+        println!(
+            "Relayout: {} windows in tabbed mode on workspace {}",
+            active_ws.len(),
+            active_ws.id
+        );
+    }
+
+    /// Groups on the active workspace that get a title strip: its tab
+    /// groups, or in tabbed mode the whole workspace
+    pub fn tab_strips(&self) -> Vec<TabGroup> {
+        let workspace = self.workspace_manager.active_workspace();
+        let groups = if workspace.layout_mode == LayoutMode::Tabbed {
+            workspace.as_tab_group().into_iter().collect()
+        } else {
+            workspace.tab_groups.clone()
+        };
+        // A single window is drawn without a strip
+        groups
+            .into_iter()
+            .filter(|group| group.windows.len() > 1)
+            .collect()
+    }
+
+    /// Window whose tab title is at `point`
+    pub fn tab_at(&self, point: Point<i32, Logical>) -> Option<Window> {
+        self.tab_strips().into_iter().find_map(|group| {
+            let geometry = self.space.element_geometry(group.visible())?;
+            let tab_width = geometry.size.w / group.windows.len() as i32;
+            let strip = Rectangle::from_loc_and_size(
+                (geometry.loc.x, geometry.loc.y - TAB_BAR_HEIGHT),
+                (tab_width * group.windows.len() as i32, TAB_BAR_HEIGHT),
+            );
+            if !strip.contains(point) {
+                return None;
+            }
+            let i = ((point.x - strip.loc.x) / tab_width) as usize;
+            group.windows.get(i).cloned()
+        })
+    }
+
+    /// Bring the tab clicked at `location` to the front and focus it.
+    /// Returns true if the click was on a tab.
+    pub fn tab_click(&mut self, location: Point<f64, Logical>) -> bool {
+        let Some(window) = self.tab_at(location.to_i32_round()) else {
+            return false;
+        };
+
+        self.workspace_manager
+            .active_workspace_mut()
+            .focus_window(&window);
+        self.relayout();
+        if let (Some(keyboard), Some(surface)) = (self.seat.get_keyboard(), window.wl_surface()) {
+            keyboard.set_focus(
+                self,
+                Some(surface.into_owned()),
+                SERIAL_COUNTER.next_serial(),
+            );
+        }
+        true
+    }
+
+    /// Title strips for the tab groups on the active workspace
+    pub fn render_tab_bars(&self) -> Vec<BarElement> {
+        let workspace = self.workspace_manager.active_workspace();
+        // Tab strips would be drawn over a fullscreen window
+        if workspace.fullscreen_window.is_some() {
+            return Vec::new();
+        }
+        let focused = workspace.focused_window();
+        let mut elements = Vec::new();
+
+        for group in &self.tab_strips() {
+            let Some(geometry) = self.space.element_geometry(group.visible()) else {
+                continue;
+            };
+            let tab_width = geometry.size.w / group.windows.len() as i32;
+            let y = geometry.loc.y - TAB_BAR_HEIGHT;
+
+            for (i, window) in group.windows.iter().enumerate() {
+                let selector = if Some(window) == focused {
+                    "tab:focus"
+                } else if i == group.active {
+                    "tab.active"
+                } else {
+                    "tab"
+                };
+                let (background, color) = self.tab_colors(selector);
+                let x = geometry.loc.x + i as i32 * tab_width;
+
+                elements.push(BarElement::Rectangle {
+                    geometry: Rectangle::from_loc_and_size(
+                        (x, y),
+                        (tab_width - 2, TAB_BAR_HEIGHT - 2),
+                    ),
+                    color: background,
+                });
+
+                // Windows playing audio get a speaker at the end of their tab
+                let audio = self.window_audio(window);
+                let badge_width = if let Some(muted) = audio {
+                    elements.extend(
+                        self.render_audio_badge(badge_geometry(x, y, tab_width - 2), muted),
+                    );
+                    BADGE_WIDTH
+                } else {
+                    0
+                };
+
+                let mut label = window_title(window);
+                if label.is_empty() {
+                    label = window_app_id(window);
+                }
+                let label = text::truncate(&label, 12, tab_width - 16 - badge_width);
+
+                // Right-to-left titles hug the end of the tab
+                let label_x = if text::is_rtl(&label) {
+                    x + tab_width - 8 - badge_width - text::measure(&label, 12)
+                } else {
+                    x + 8
+                };
+                elements.push(BarElement::Text {
+                    position: (label_x, y + 5),
+                    text: label,
+                    color,
+                    size: 12,
+                });
+            }
+        }
+
+        elements
+    }
+
+    fn tab_colors(&self, selector: &str) -> ([f32; 4], [f32; 4]) {
+        let (background, color) = match selector {
+            "tab:focus" => ([0.54, 0.71, 0.98, 1.0], [0.11, 0.11, 0.18, 1.0]),
+            "tab.active" => ([0.27, 0.28, 0.35, 1.0], [0.8, 0.83, 0.96, 1.0]),
+            _ => ([0.19, 0.20, 0.27, 1.0], [0.65, 0.68, 0.78, 1.0]),
+        };
+
+        let Some(ref stylesheet) = self.stylesheet else {
+            return (background, color);
+        };
+
+        (
+            stylesheet
+                .get_color(selector, "background")
+                .map(|c| c.to_rgba_f32())
+                .unwrap_or(background),
+            stylesheet
+                .get_color(selector, "color")
+                .map(|c| c.to_rgba_f32())
+                .unwrap_or(color),
+        )
+    }
+}
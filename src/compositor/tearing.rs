@@ -0,0 +1,54 @@
+// Tearing control
+//
+// Games can ask for immediate presentation through wp_tearing_control_v1:
+// their frames are flipped to the display as soon as they are ready rather
+// than at the next vblank, trading tearing for latency. The hint only
+// counts for windows whose rules say so and while they are fullscreen on
+// their output:
+//
+//   <rule content-type="game" allow-tearing="true" />
+//
+// The DRM backend then uses async page flips for frames that only change
+// the primary plane, on devices that support them.
+
+use smithay::delegate_tearing_control;
+use smithay::desktop::Window;
+use smithay::reexports::wayland_protocols::wp::tearing_control::v1::server::wp_tearing_control_v1::PresentationHint;
+use smithay::wayland::compositor::with_states;
+use smithay::wayland::tearing_control::TearingControlSurfaceCachedState;
+
+use crate::compositor::WebWMCompositor;
+
+/// Whether the window's client asked for async presentation
+pub fn window_wants_tearing(window: &Window) -> bool {
+    let Some(toplevel) = window.toplevel() else {
+        return false;
+    };
+    with_states(toplevel.wl_surface(), |states| {
+        *states
+            .cached_state
+            .get::<TearingControlSurfaceCachedState>()
+            .current()
+            .presentation_hint()
+            == PresentationHint::Async
+    })
+}
+
+impl WebWMCompositor {
+    /// The active workspace's fullscreen window, if it asked to tear and
+    /// its rules allow it
+    pub fn tearing_window(&self) -> Option<Window> {
+        let window = self
+            .workspace_manager
+            .active_workspace()
+            .fullscreen_window
+            .clone()?;
+        let allowed = self
+            .window_rules_for(&window)
+            .find_map(|rule| rule.allow_tearing)
+            .unwrap_or(false);
+        (allowed && window_wants_tearing(&window)).then_some(window)
+    }
+}
+
+delegate_tearing_control!(WebWMCompositor);
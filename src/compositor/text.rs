@@ -0,0 +1,287 @@
+// Text shaping
+//
+// Bar, tab and overlay text is laid out with cosmic-text, which shapes it
+// with rustybuzz: Arabic and Indic scripts get their joined forms, text
+// starting with a right-to-left character runs right to left, and
+// characters missing from the sans-serif UI font come from any installed
+// font that has them, which covers CJK and emoji. Color emoji keep their
+// colors and are scaled down to the text size. System fonts are loaded
+// once, on first use. Without any fonts installed (minimal containers,
+// some CI machines) text falls back to the built-in 5x7 ASCII font.
+
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping, SwashCache, SwashContent};
+use std::sync::{Mutex, OnceLock};
+
+/// Width of a character of the fallback bitmap font, including spacing
+pub const FALLBACK_ADVANCE: i32 = 6;
+
+struct TextContext {
+    font_system: FontSystem,
+    swash_cache: SwashCache,
+}
+
+fn context() -> &'static Mutex<TextContext> {
+    static CONTEXT: OnceLock<Mutex<TextContext>> = OnceLock::new();
+    CONTEXT.get_or_init(|| {
+        let font_system = FontSystem::new();
+        let faces = font_system.db().faces().count();
+        if faces == 0 {
+            eprintln!("⚠️  No fonts found, bar text is limited to ASCII");
+        } else {
+            println!("✓ Loaded {} font faces for bar text", faces);
+        }
+        Mutex::new(TextContext {
+            font_system,
+            swash_cache: SwashCache::new(),
+        })
+    })
+}
+
+fn shape(font_system: &mut FontSystem, text: &str, size: u32) -> Buffer {
+    let size = size.max(1) as f32;
+    let mut buffer = Buffer::new(font_system, Metrics::new(size, (size * 1.25).ceil()));
+    buffer.set_size(font_system, None, None);
+    buffer.set_text(
+        font_system,
+        text,
+        Attrs::new().family(Family::SansSerif),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(font_system, false);
+    buffer
+}
+
+/// Width of `text` at `size` pixels, in pixels
+pub fn measure(text: &str, size: u32) -> i32 {
+    let Ok(mut context) = context().lock() else {
+        return 0;
+    };
+    if context.font_system.db().faces().next().is_none() {
+        return text.chars().filter(char::is_ascii).count() as i32 * FALLBACK_ADVANCE;
+    }
+
+    let buffer = shape(&mut context.font_system, text, size);
+    buffer
+        .layout_runs()
+        .map(|run| run.line_w)
+        .fold(0.0, f32::max)
+        .ceil() as i32
+}
+
+/// `text`, cut short with an ellipsis so it is at most `max_width` wide
+pub fn truncate(text: &str, size: u32, max_width: i32) -> String {
+    if measure(text, size) <= max_width {
+        return text.to_string();
+    }
+
+    // Text is shaped whole, so drop characters from the logical end until
+    // it fits; for right-to-left titles that is their left side
+    let mut chars: Vec<char> = text.chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let candidate: String = chars.iter().collect::<String>().trim_end().to_string() + "…";
+        if measure(&candidate, size) <= max_width {
+            return candidate;
+        }
+    }
+    String::new()
+}
+
+/// Draw `text` with its top left corner at `position`, calling `put` with
+/// every covered pixel and its color, alpha scaled by coverage. Returns
+/// false if there are no fonts and the caller has to draw it.
+pub fn draw(
+    text: &str,
+    size: u32,
+    position: (i32, i32),
+    color: [f32; 4],
+    mut put: impl FnMut(i32, i32, [f32; 4]),
+) -> bool {
+    let Ok(mut context) = context().lock() else {
+        return false;
+    };
+    if context.font_system.db().faces().next().is_none() {
+        return false;
+    }
+
+    let TextContext {
+        ref mut font_system,
+        ref mut swash_cache,
+    } = *context;
+    let buffer = shape(font_system, text, size);
+    for run in buffer.layout_runs() {
+        for glyph in run.glyphs {
+            let physical = glyph.physical((0.0, 0.0), 1.0);
+            let Some(image) = swash_cache.get_image(font_system, physical.cache_key) else {
+                continue;
+            };
+            let origin = (
+                position.0 + physical.x,
+                position.1 + run.line_y as i32 + physical.y,
+            );
+            let placement = image.placement;
+            let (width, height) = (placement.width as usize, placement.height as usize);
+
+            match image.content {
+                SwashContent::Mask => {
+                    for (i, coverage) in image.data.iter().enumerate() {
+                        let alpha = *coverage as f32 / 255.0 * color[3];
+                        if alpha > 0.0 {
+                            put(
+                                origin.0 + placement.left + (i % width) as i32,
+                                origin.1 - placement.top + (i / width) as i32,
+                                [color[0], color[1], color[2], alpha],
+                            );
+                        }
+                    }
+                }
+                SwashContent::SubpixelMask => {
+                    for (i, rgba) in image.data.chunks_exact(4).enumerate() {
+                        let coverage = (rgba[0] as f32 + rgba[1] as f32 + rgba[2] as f32) / 3.0;
+                        let alpha = coverage / 255.0 * color[3];
+                        if alpha > 0.0 {
+                            put(
+                                origin.0 + placement.left + (i % width) as i32,
+                                origin.1 - placement.top + (i / width) as i32,
+                                [color[0], color[1], color[2], alpha],
+                            );
+                        }
+                    }
+                }
+                SwashContent::Color => {
+                    // Bitmap emoji fonts only come in a few fixed sizes, so
+                    // swash hands back the nearest strike, often 109px tall;
+                    // shrink it to the glyph's advance and scale its
+                    // placement with it so it sits on the baseline
+                    let scale = (glyph.w / width.max(1) as f32).min(1.0);
+                    let (pixels, scaled_width, _) = scale_color(&image.data, width, height, scale);
+                    let left = (placement.left as f32 * scale).round() as i32;
+                    let top = (placement.top as f32 * scale).round() as i32;
+                    for (i, pixel) in pixels.into_iter().enumerate() {
+                        let alpha = pixel[3] * color[3];
+                        if alpha > 0.0 {
+                            put(
+                                origin.0 + left + (i % scaled_width) as i32,
+                                origin.1 - top + (i / scaled_width) as i32,
+                                [pixel[0], pixel[1], pixel[2], alpha],
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Shrink an RGBA color glyph by `scale` (at most 1), averaging the source
+/// pixels each output pixel covers. Returns the pixels as straight-alpha
+/// colors with the new width and height.
+fn scale_color(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    scale: f32,
+) -> (Vec<[f32; 4]>, usize, usize) {
+    let scaled_width = ((width as f32 * scale).round() as usize).clamp(1, width.max(1));
+    let scaled_height = ((height as f32 * scale).round() as usize).clamp(1, height.max(1));
+    if width == 0 || height == 0 || data.len() < width * height * 4 {
+        return (Vec::new(), 0, 0);
+    }
+
+    // Source rows or columns covered by output pixel `i`, at least one
+    let span = |i: usize, scaled: usize, full: usize| {
+        let start = i * full / scaled;
+        (start, ((i + 1) * full / scaled).max(start + 1))
+    };
+
+    let mut pixels = Vec::with_capacity(scaled_width * scaled_height);
+    for y in 0..scaled_height {
+        let (y0, y1) = span(y, scaled_height, height);
+        for x in 0..scaled_width {
+            let (x0, x1) = span(x, scaled_width, width);
+
+            // Average premultiplied, so transparent edges don't darken
+            let mut sum = [0.0f32; 4];
+            for sy in y0..y1 {
+                for sx in x0..x1 {
+                    let i = (sy * width + sx) * 4;
+                    let alpha = data[i + 3] as f32 / 255.0;
+                    sum[0] += data[i] as f32 / 255.0 * alpha;
+                    sum[1] += data[i + 1] as f32 / 255.0 * alpha;
+                    sum[2] += data[i + 2] as f32 / 255.0 * alpha;
+                    sum[3] += alpha;
+                }
+            }
+            let count = ((y1 - y0) * (x1 - x0)) as f32;
+            pixels.push(if sum[3] > 0.0 {
+                [
+                    sum[0] / sum[3],
+                    sum[1] / sum[3],
+                    sum[2] / sum[3],
+                    sum[3] / count,
+                ]
+            } else {
+                [0.0; 4]
+            });
+        }
+    }
+    (pixels, scaled_width, scaled_height)
+}
+
+/// Whether `text` starts with a right-to-left character, which makes
+/// cosmic-text lay the whole line out right to left
+pub fn is_rtl(text: &str) -> bool {
+    text.chars().find(|c| c.is_alphabetic()).is_some_and(|c| {
+        matches!(c as u32,
+                0x0590..=0x08FF // Hebrew, Arabic, Syriac, Thaana, NKo, ...
+                | 0xFB1D..=0xFDFF // Hebrew and Arabic presentation forms
+                | 0xFE70..=0xFEFF)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate() {
+        let title = "שלום עולם - מסמך חדש";
+        assert_eq!(truncate(title, 13, 10_000), title);
+
+        let width = measure(title, 13);
+        if width > 0 {
+            let short = truncate(title, 13, width / 2);
+            assert!(short.ends_with('…'));
+            assert!(measure(&short, 13) <= width / 2);
+            // Cut at a character boundary, never inside one
+            assert!(title.starts_with(short.trim_end_matches('…')));
+        }
+    }
+
+    #[test]
+    fn test_scale_color() {
+        // 2x2 red over a transparent right half, shrunk to one pixel
+        let data = [
+            255, 0, 0, 255, 0, 0, 0, 0, //
+            255, 0, 0, 255, 0, 0, 0, 0,
+        ];
+        let (pixels, width, height) = scale_color(&data, 2, 2, 0.5);
+        assert_eq!((width, height), (1, 1));
+        assert_eq!(pixels, vec![[1.0, 0.0, 0.0, 0.5]]);
+
+        // Scale 1 keeps the glyph as it is
+        let (pixels, width, height) = scale_color(&data, 2, 2, 1.0);
+        assert_eq!((width, height), (2, 2));
+        assert_eq!(pixels[0], [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(pixels[1][3], 0.0);
+    }
+
+    #[test]
+    fn test_is_rtl() {
+        assert!(is_rtl("مرحبا بالعالم"));
+        assert!(is_rtl("  123 שלום"));
+        assert!(!is_rtl("Firefox — مرحبا"));
+        assert!(!is_rtl("東京"));
+    }
+}
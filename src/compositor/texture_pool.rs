@@ -0,0 +1,129 @@
+// Offscreen buffer and texture reuse
+//
+// Bar strips, thumbnails, shadows and blur passes all need scratch pixels
+// every frame. Allocating a fresh Vec and importing a new GLES texture each
+// time shows up in frame times, so both are recycled here by size. Entries
+// that sit unused for a while are dropped so a resized output doesn't pin
+// old textures forever.
+
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::{
+    gles::{GlesError, GlesRenderer, GlesTexture},
+    ImportMem, Texture,
+};
+use smithay::utils::{Buffer, Rectangle, Size};
+
+/// Frames a free entry may stay unused before it is released
+const MAX_IDLE_FRAMES: u64 = 120;
+
+/// Recycled CPU-side pixel buffers
+#[derive(Debug, Default)]
+pub struct BufferPool {
+    free: Vec<(Vec<u8>, u64)>,
+    frame: u64,
+}
+
+impl BufferPool {
+    /// A zeroed buffer of exactly `len` bytes
+    pub fn acquire(&mut self, len: usize) -> Vec<u8> {
+        match self.free.iter().position(|(buffer, _)| buffer.len() == len) {
+            Some(i) => {
+                let (mut buffer, _) = self.free.swap_remove(i);
+                buffer.fill(0);
+                buffer
+            }
+            None => vec![0u8; len],
+        }
+    }
+
+    pub fn release(&mut self, buffer: Vec<u8>) {
+        self.free.push((buffer, self.frame));
+    }
+
+    pub fn end_frame(&mut self) {
+        self.frame += 1;
+        let frame = self.frame;
+        self.free
+            .retain(|(_, released)| frame - released <= MAX_IDLE_FRAMES);
+    }
+}
+
+/// Recycled GLES textures, matched by size
+#[derive(Default)]
+pub struct TexturePool {
+    free: Vec<(GlesTexture, u64)>,
+    frame: u64,
+}
+
+impl TexturePool {
+    /// A texture of `size`, contents undefined. Usable as a render target.
+    pub fn acquire(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        size: Size<i32, Buffer>,
+    ) -> Result<GlesTexture, GlesError> {
+        if let Some(i) = self
+            .free
+            .iter()
+            .position(|(texture, _)| texture.size() == size)
+        {
+            return Ok(self.free.swap_remove(i).0);
+        }
+
+        let blank = vec![0u8; (size.w * size.h * 4) as usize];
+        renderer.import_memory(&blank, Fourcc::Argb8888, size, false)
+    }
+
+    /// A texture of `size` filled with ARGB8888 `data`
+    pub fn upload(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        data: &[u8],
+        size: Size<i32, Buffer>,
+    ) -> Result<GlesTexture, GlesError> {
+        let texture = self.acquire(renderer, size)?;
+        renderer.update_memory(&texture, data, Rectangle::from_size(size))?;
+        Ok(texture)
+    }
+
+    pub fn release(&mut self, texture: GlesTexture) {
+        self.free.push((texture, self.frame));
+    }
+
+    /// Call once per rendered frame to drop textures nobody asked for lately
+    pub fn end_frame(&mut self) {
+        self.frame += 1;
+        let frame = self.frame;
+        self.free
+            .retain(|(_, released)| frame - released <= MAX_IDLE_FRAMES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_pool_reuse() {
+        let mut pool = BufferPool::default();
+
+        let mut buffer = pool.acquire(16);
+        buffer[3] = 0xff;
+        let ptr = buffer.as_ptr();
+        pool.release(buffer);
+
+        // Same size comes back zeroed from the same allocation
+        let buffer = pool.acquire(16);
+        assert_eq!(buffer.as_ptr(), ptr);
+        assert!(buffer.iter().all(|&b| b == 0));
+
+        // Other sizes get a fresh buffer
+        assert_eq!(pool.acquire(8).len(), 8);
+
+        pool.release(buffer);
+        for _ in 0..=MAX_IDLE_FRAMES {
+            pool.end_frame();
+        }
+        assert!(pool.free.is_empty());
+    }
+}
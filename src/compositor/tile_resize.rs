@@ -0,0 +1,252 @@
+// Resizing tiles
+//
+// The resize-tiled action grows the focused tile toward "left", "right",
+// "up" or "down" by `amount`, a share of the output's width or height (a
+// negative amount shrinks it). Super+right-drag does the same with the
+// mouse: the edges of the tile nearest to where the drag starts follow the
+// pointer. What moves depends on the layout: tiles sharing a row or column
+// trade room with their neighbor, master-stack moves the divider between
+// master and stack, and dwindle moves the innermost split on that side.
+// Sizes are kept with the workspace, so relayouts don't even them out.
+
+use smithay::desktop::Window;
+use smithay::input::pointer::{CursorIcon, CursorImageStatus};
+use smithay::utils::{Logical, Point, Rectangle, SERIAL_COUNTER};
+use smithay::wayland::seat::WaylandFocus;
+
+use crate::compositor::dwindle::SplitAxis;
+use crate::compositor::flex_layout::{flex_geometries, shared_slot, split};
+use crate::compositor::workspace::{LayoutMode, TabGroup};
+use crate::compositor::{window_app_id, WebWMCompositor};
+use crate::config::FlexDirection;
+
+/// A tile never gets less than this share of the room it trades with its
+/// neighbor
+const MIN_SHARE: f64 = 0.1;
+
+#[derive(Debug, Clone)]
+pub struct TileResize {
+    pub window: Window,
+    pub last: Point<f64, Logical>,
+    /// The edges that follow the pointer: "left" or "right", "up" or "down"
+    pub edges: (&'static str, &'static str),
+}
+
+impl WebWMCompositor {
+    /// Grow the focused tile toward `direction` by `amount` of the output
+    pub fn resize_tiled(&mut self, direction: &str, amount: f32) {
+        let size = self.layout_size();
+        let extent = match direction {
+            "left" | "right" => size.w,
+            "up" | "down" => size.h,
+            _ => {
+                println!("Unknown resize direction: {}", direction);
+                return;
+            }
+        };
+        if self.resize_tiled_by(direction, amount as f64 * extent as f64) {
+            self.relayout();
+        }
+    }
+
+    /// Move the focused tile's edge on the `direction` side outward by
+    /// `pixels`. Returns false if that edge can't move.
+    fn resize_tiled_by(&mut self, direction: &str, pixels: f64) -> bool {
+        let (horizontal, forward) = match direction {
+            "left" => (true, false),
+            "right" => (true, true),
+            "up" => (false, false),
+            "down" => (false, true),
+            _ => return false,
+        };
+        let workspace = self.workspace_manager.active_workspace();
+        let Some(focused) = workspace
+            .focused_window()
+            .map(|window| workspace.dialog_root(window).clone())
+        else {
+            return false;
+        };
+        let tiles = workspace.tiles();
+        let Some(index) = tiles
+            .iter()
+            .position(|tile| tile.windows.contains(&focused))
+        else {
+            return false;
+        };
+        let neighbor = if forward {
+            index + 1
+        } else {
+            index.wrapping_sub(1)
+        };
+        let weights: Vec<f32> = tiles
+            .iter()
+            .map(|tile| workspace.tile_weight(tile))
+            .collect();
+        let gaps = self.get_gaps();
+        let area = self.tiled_area(self.layout_size());
+        let extent = |geometries: &[Rectangle<i32, Logical>], i: usize| {
+            let geometry = geometries[i];
+            if horizontal {
+                geometry.size.w
+            } else {
+                geometry.size.h
+            }
+        };
+
+        match workspace.layout_mode {
+            LayoutMode::Tiling if self.workspace_grid().is_none() => {
+                let root = self.flex_root();
+                let Some((shared, slot_direction)) = shared_slot(&root, tiles.len()) else {
+                    return false;
+                };
+                if (slot_direction == FlexDirection::Row) != horizontal
+                    || !shared.contains(&index)
+                    || !shared.contains(&neighbor)
+                {
+                    return false;
+                }
+                let geometries = flex_geometries(&root, area, gaps, &weights);
+                let extents = (extent(&geometries, index), extent(&geometries, neighbor));
+                self.trade_tile_size(&tiles, (index, neighbor), extents, pixels)
+            }
+            LayoutMode::MasterStack if horizontal => {
+                // Sideways, only the divider between master and stack moves
+                match (index == 0, forward) {
+                    (true, true) => self.move_master_divider(pixels).is_some(),
+                    (false, false) => self.move_master_divider(-pixels).is_some(),
+                    _ => false,
+                }
+            }
+            LayoutMode::MasterStack => {
+                if index == 0 || neighbor == 0 || neighbor >= tiles.len() {
+                    return false;
+                }
+                let geometries = split(area, FlexDirection::Column, &weights[1..], gaps);
+                let extents = (
+                    extent(&geometries, index - 1),
+                    extent(&geometries, neighbor - 1),
+                );
+                self.trade_tile_size(&tiles, (index, neighbor), extents, pixels)
+            }
+            LayoutMode::Dwindle => {
+                let Some(leaf) = self.focused_dwindle_leaf() else {
+                    return false;
+                };
+                let axis = if horizontal {
+                    SplitAxis::Horizontal
+                } else {
+                    SplitAxis::Vertical
+                };
+                self.workspace_manager
+                    .active_workspace_mut()
+                    .dwindle
+                    .resize_toward(&leaf, area, gaps, (axis, forward), pixels)
+                    .is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// Move `pixels` of room from the `neighbor` tile to the one at `index`,
+    /// given how wide (or tall) both are now
+    fn trade_tile_size(
+        &mut self,
+        tiles: &[TabGroup],
+        (index, neighbor): (usize, usize),
+        (size, neighbor_size): (i32, i32),
+        pixels: f64,
+    ) -> bool {
+        let workspace = self.workspace_manager.active_workspace_mut();
+        let total = workspace.tile_weight(&tiles[index]) + workspace.tile_weight(&tiles[neighbor]);
+        let share = ((size as f64 + pixels) / (size + neighbor_size).max(1) as f64)
+            .clamp(MIN_SHARE, 1.0 - MIN_SHARE) as f32;
+        workspace.set_tile_weight(&tiles[index], share * total);
+        workspace.set_tile_weight(&tiles[neighbor], (1.0 - share) * total);
+        true
+    }
+
+    /// Start resizing the tile under `location` with the pointer. Returns
+    /// false if there is no window there.
+    pub fn begin_tile_resize(&mut self, location: Point<f64, Logical>) -> bool {
+        let Some(window) = self
+            .space
+            .element_under(location)
+            .map(|(window, _)| window.clone())
+        else {
+            return false;
+        };
+        let Some(geometry) = self.space.element_geometry(&window) else {
+            return false;
+        };
+
+        let center = geometry.loc.to_f64() + geometry.size.to_f64().downscale(2.0).to_point();
+        let edges = (
+            if location.x >= center.x {
+                "right"
+            } else {
+                "left"
+            },
+            if location.y >= center.y { "down" } else { "up" },
+        );
+        self.workspace_manager
+            .active_workspace_mut()
+            .focus_window(&window);
+        if let (Some(keyboard), Some(surface)) = (self.seat.get_keyboard(), window.wl_surface()) {
+            keyboard.set_focus(
+                self,
+                Some(surface.into_owned()),
+                SERIAL_COUNTER.next_serial(),
+            );
+        }
+
+        println!("↔️ Resizing '{}'", window_app_id(&window));
+        self.cursor_image_status = CursorImageStatus::Named(match edges {
+            ("right", "down") => CursorIcon::SeResize,
+            ("right", _) => CursorIcon::NeResize,
+            (_, "down") => CursorIcon::SwResize,
+            _ => CursorIcon::NwResize,
+        });
+        self.tile_resize = Some(TileResize {
+            window,
+            last: location,
+            edges,
+        });
+        true
+    }
+
+    pub fn tile_resize_motion(&mut self, location: Point<f64, Logical>) {
+        let Some(resize) = self.tile_resize.as_mut() else {
+            return;
+        };
+        let delta = location - resize.last;
+        resize.last = location;
+        let (horizontal, vertical) = resize.edges;
+
+        let dx = if horizontal == "right" {
+            delta.x
+        } else {
+            -delta.x
+        };
+        let dy = if vertical == "down" {
+            delta.y
+        } else {
+            -delta.y
+        };
+        let mut moved = false;
+        if dx != 0.0 {
+            moved |= self.resize_tiled_by(horizontal, dx);
+        }
+        if dy != 0.0 {
+            moved |= self.resize_tiled_by(vertical, dy);
+        }
+        if moved {
+            self.relayout();
+        }
+    }
+
+    pub fn end_tile_resize(&mut self) {
+        if self.tile_resize.take().is_some() {
+            self.cursor_image_status = CursorImageStatus::default_named();
+        }
+    }
+}
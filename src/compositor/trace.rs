@@ -0,0 +1,202 @@
+// Profiling traces
+//
+// `webwm-msg trace [SECONDS] [FILE]` records every frame, its render stages
+// and the time spent dispatching event sources (client requests, input,
+// IPC) for a few seconds, then writes them as a Chrome trace-event JSON
+// file. It opens in Perfetto (ui.perfetto.dev) or chrome://tracing, with
+// rendering and the event loop on separate tracks, to see where a slow
+// frame went.
+
+use serde_json::{json, Value};
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::compositor::WebWMCompositor;
+
+/// Events kept per trace; later ones are counted but dropped, so a long
+/// trace can't eat all memory
+const MAX_EVENTS: usize = 500_000;
+
+/// Row of the trace an event is drawn on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Track {
+    Render,
+    EventLoop,
+}
+
+impl Track {
+    fn id(&self) -> u32 {
+        match self {
+            Track::Render => 1,
+            Track::EventLoop => 2,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Track::Render => "render",
+            Track::EventLoop => "event loop",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TraceEvent {
+    name: &'static str,
+    track: Track,
+    /// Since the trace started
+    start: Duration,
+    duration: Duration,
+}
+
+/// Timings collected while a trace is being recorded
+#[derive(Debug)]
+pub struct TraceSession {
+    started: Instant,
+    /// Where the trace is written when it's done
+    pub path: PathBuf,
+    events: Vec<TraceEvent>,
+    dropped: usize,
+}
+
+impl TraceSession {
+    pub fn new(path: PathBuf, started: Instant) -> Self {
+        Self {
+            started,
+            path,
+            events: Vec::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Something that took `duration` from `start`
+    pub fn record(&mut self, name: &'static str, track: Track, start: Instant, duration: Duration) {
+        if self.events.len() >= MAX_EVENTS {
+            self.dropped += 1;
+            return;
+        }
+        self.events.push(TraceEvent {
+            name,
+            track,
+            start: start.saturating_duration_since(self.started),
+            duration,
+        });
+    }
+
+    pub fn event_count(&self) -> usize {
+        self.events.len()
+    }
+
+    /// The trace in Chrome's trace-event format, times in microseconds
+    pub fn to_json(&self) -> Value {
+        let tracks = [Track::Render, Track::EventLoop].map(|track| {
+            json!({
+                "name": "thread_name",
+                "ph": "M",
+                "pid": 1,
+                "tid": track.id(),
+                "args": { "name": track.name() },
+            })
+        });
+        let events = self.events.iter().map(|event| {
+            json!({
+                "name": event.name,
+                "cat": event.track.name(),
+                "ph": "X",
+                "ts": event.start.as_micros() as u64,
+                "dur": event.duration.as_micros() as u64,
+                "pid": 1,
+                "tid": event.track.id(),
+            })
+        });
+
+        json!({
+            "traceEvents": tracks.into_iter().chain(events).collect::<Vec<_>>(),
+            "displayTimeUnit": "ms",
+            "otherData": {
+                "version": env!("CARGO_PKG_VERSION"),
+                "dropped_events": self.dropped,
+            },
+        })
+    }
+}
+
+impl WebWMCompositor {
+    /// Record a trace for `duration`, then write it to `path`
+    pub fn start_trace(&mut self, duration: Duration, path: PathBuf) -> Result<(), String> {
+        if let Some(ref trace) = self.profiler.trace {
+            return Err(format!(
+                "Already recording a trace to {}",
+                trace.path.display()
+            ));
+        }
+
+        self.loop_handle
+            .insert_source(Timer::from_duration(duration), |_, _, compositor| {
+                compositor.finish_trace();
+                TimeoutAction::Drop
+            })
+            .map_err(|e| format!("Failed to schedule the end of the trace: {}", e))?;
+        println!(
+            "📈 Recording a {}s trace to {}",
+            duration.as_secs_f32(),
+            path.display()
+        );
+        self.profiler.trace = Some(TraceSession::new(path, Instant::now()));
+        Ok(())
+    }
+
+    /// Stop recording and write the trace
+    pub fn finish_trace(&mut self) {
+        let Some(trace) = self.profiler.trace.take() else {
+            return;
+        };
+        let result = serde_json::to_string(&trace.to_json())
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&trace.path, json).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => println!(
+                "📈 Trace with {} events written to {}",
+                trace.event_count(),
+                trace.path.display()
+            ),
+            Err(e) => eprintln!("Failed to write {}: {}", trace.path.display(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_events() {
+        let started = Instant::now();
+        let mut trace = TraceSession::new(PathBuf::from("trace.json"), started);
+        trace.record(
+            "frame",
+            Track::Render,
+            started + Duration::from_millis(16),
+            Duration::from_micros(2500),
+        );
+        trace.record(
+            "wayland_clients",
+            Track::EventLoop,
+            started + Duration::from_millis(20),
+            Duration::from_micros(300),
+        );
+        assert_eq!(trace.event_count(), 2);
+
+        let json = trace.to_json();
+        let events = json["traceEvents"].as_array().unwrap();
+        // Two track names, then the events
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0]["ph"], "M");
+        assert_eq!(events[2]["name"], "frame");
+        assert_eq!(events[2]["ph"], "X");
+        assert_eq!(events[2]["ts"], 16_000);
+        assert_eq!(events[2]["dur"], 2500);
+        assert_eq!(events[3]["tid"], 2);
+    }
+}
@@ -0,0 +1,277 @@
+// Layout transactions
+//
+// A relayout used to resize and move every tile right away, so windows
+// popped into place one by one as their clients caught up with the new
+// sizes. Now a relayout collects where its windows go instead. Windows that
+// get a new size are sent their configure, and nothing moves until each of
+// them has committed a buffer for it, or `<transactions timeout>` in
+// `<layout>` has passed; then every window moves in the same frame. A
+// relayout while one is still waiting adds to it. Windows that haven't
+// drawn anything yet, and moves outside a relayout (like dragging), aren't
+// held back.
+
+use smithay::desktop::Window;
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use smithay::reexports::calloop::RegistrationToken;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::utils::{Logical, Point, Serial, Size};
+use smithay::wayland::compositor::with_states;
+use smithay::wayland::shell::xdg::XdgToplevelSurfaceData;
+use std::time::Duration;
+
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+/// Placements of a relayout, held until the windows it resized have
+/// redrawn. Generic over the window so it can be tested without clients.
+#[derive(Debug)]
+pub struct LayoutTransaction<W> {
+    /// Resized windows, with the configure each has to commit
+    waiting: Vec<(W, Serial)>,
+    /// Where windows go once the transaction is applied, in layout order
+    placements: Vec<(W, Point<i32, Logical>)>,
+    /// Set while a relayout is still adding to it
+    open: bool,
+    output_size: Size<i32, Logical>,
+    /// Applies the transaction when clients take too long
+    timer: Option<RegistrationToken>,
+}
+
+impl<W: PartialEq> LayoutTransaction<W> {
+    pub fn new(output_size: Size<i32, Logical>) -> Self {
+        Self {
+            waiting: Vec::new(),
+            placements: Vec::new(),
+            open: true,
+            output_size,
+            timer: None,
+        }
+    }
+
+    /// Move `window` to `location` when the transaction is applied
+    pub fn place(&mut self, window: W, location: Point<i32, Logical>) {
+        self.placements.retain(|(w, _)| w != &window);
+        self.placements.push((window, location));
+    }
+
+    /// Hold the transaction until `window` commits the configure `serial`
+    pub fn wait_for(&mut self, window: W, serial: Serial) {
+        self.waiting.retain(|(w, _)| w != &window);
+        self.waiting.push((window, serial));
+    }
+
+    /// Leave `window` where it is and stop waiting for it
+    pub fn forget(&mut self, window: &W) {
+        self.waiting.retain(|(w, _)| w != window);
+        self.placements.retain(|(w, _)| w != window);
+    }
+
+    /// `window` committed the state of the configure `serial`
+    pub fn committed(&mut self, window: &W, serial: Serial) {
+        self.waiting
+            .retain(|(w, wanted)| w != window || !serial.is_no_older_than(wanted));
+    }
+
+    pub fn is_ready(&self) -> bool {
+        !self.open && self.waiting.is_empty()
+    }
+
+    pub fn waiting(&self) -> impl Iterator<Item = &W> {
+        self.waiting.iter().map(|(w, _)| w)
+    }
+}
+
+impl WebWMCompositor {
+    /// How long a transaction waits for clients; None applies layouts
+    /// right away
+    fn transaction_timeout(&self) -> Option<Duration> {
+        self.config
+            .desktop
+            .as_ref()
+            .map(|desktop| desktop.layout.transaction_timeout_ms)
+            .filter(|ms| *ms > 0)
+            .map(|ms| Duration::from_millis(ms as u64))
+    }
+
+    /// Collect the placements of a relayout, joining the transaction that
+    /// is still waiting if there is one
+    pub fn begin_layout_transaction(&mut self, output_size: Size<i32, Logical>) {
+        if self.transaction_timeout().is_none() {
+            return;
+        }
+        let transaction = self
+            .layout_transaction
+            .get_or_insert_with(|| LayoutTransaction::new(output_size));
+        transaction.open = true;
+        transaction.output_size = output_size;
+    }
+
+    /// Done collecting: apply the transaction if no window has to redraw
+    /// first, else wait for them
+    pub fn finish_layout_transaction(&mut self) {
+        let Some(timeout) = self.transaction_timeout() else {
+            return;
+        };
+        let Some(transaction) = self.layout_transaction.as_mut() else {
+            return;
+        };
+        transaction.open = false;
+        if transaction.is_ready() {
+            self.apply_layout_transaction();
+            return;
+        }
+        if transaction.timer.is_some() {
+            return;
+        }
+
+        let timer =
+            self.loop_handle
+                .insert_source(Timer::from_duration(timeout), |_, _, compositor| {
+                    if let Some(transaction) = compositor.layout_transaction.as_mut() {
+                        transaction.timer = None;
+                        let late: Vec<String> = transaction.waiting().map(window_app_id).collect();
+                        eprintln!("⏱️  Layout applied without waiting for {:?}", late);
+                    }
+                    compositor.apply_layout_transaction();
+                    TimeoutAction::Drop
+                });
+        match timer {
+            Ok(token) => transaction.timer = Some(token),
+            Err(e) => {
+                eprintln!("Failed to arm layout transaction timeout: {}", e);
+                self.apply_layout_transaction();
+            }
+        }
+    }
+
+    /// Give a tiled window its size and put it at `location`, along with
+    /// the rest of the layout while a relayout collects placements
+    pub fn place_window(
+        &mut self,
+        window: &Window,
+        location: Point<i32, Logical>,
+        size: Size<i32, Logical>,
+    ) {
+        let configure = window.toplevel().map(|toplevel| {
+            toplevel.with_pending_state(|state| {
+                state.size = Some(size);
+            });
+            let resized = toplevel.current_state().size != Some(size);
+            (toplevel.send_configure(), resized)
+        });
+
+        // Nothing on screen to pop yet
+        let shown = self
+            .space
+            .element_geometry(window)
+            .is_some_and(|geometry| geometry.size.w > 0 && geometry.size.h > 0);
+        match self
+            .layout_transaction
+            .as_mut()
+            .filter(|transaction| transaction.open && shown)
+        {
+            Some(transaction) => {
+                if let Some((serial, true)) = configure {
+                    transaction.wait_for(window.clone(), serial);
+                }
+                transaction.place(window.clone(), location);
+            }
+            None => {
+                self.drop_placement(window);
+                self.space.map_element(window.clone(), location, false);
+            }
+        }
+    }
+
+    /// Keep a waiting transaction from moving `window`, for windows that
+    /// were put somewhere else since or went away
+    pub fn drop_placement(&mut self, window: &Window) {
+        if let Some(ref mut transaction) = self.layout_transaction {
+            transaction.forget(window);
+        }
+    }
+
+    /// Apply the transaction once the last window it waits for has
+    /// committed its new size
+    pub fn layout_transaction_commit(&mut self, surface: &WlSurface) {
+        if self.layout_transaction.is_none() {
+            return;
+        }
+        let Some(window) = self.window_for_surface(surface) else {
+            return;
+        };
+        let serial = with_states(surface, |states| {
+            states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .and_then(|data| data.lock().ok()?.current_serial)
+        });
+        let Some(serial) = serial else {
+            return;
+        };
+        let Some(ref mut transaction) = self.layout_transaction else {
+            return;
+        };
+        transaction.committed(&window, serial);
+        if transaction.is_ready() {
+            self.apply_layout_transaction();
+        }
+    }
+
+    /// Move every window of the transaction to its place at once
+    pub fn apply_layout_transaction(&mut self) {
+        let Some(transaction) = self.layout_transaction.take() else {
+            return;
+        };
+        if let Some(timer) = transaction.timer {
+            self.loop_handle.remove(timer);
+        }
+
+        for (window, location) in transaction.placements {
+            // Unmapped meanwhile, like a window closed or a tab hidden
+            if self.space.element_geometry(&window).is_some() {
+                self.space.map_element(window, location, false);
+            }
+        }
+
+        // Mapping raised the tiles; put what goes over them back on top
+        let output_size = transaction.output_size;
+        self.layout_dialogs(output_size);
+        self.layout_maximized(output_size);
+        self.layout_fullscreen(output_size);
+        self.sync_floating_stack();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_transaction() {
+        let mut transaction = LayoutTransaction::new((1920, 1080).into());
+        transaction.place("alpha", (0, 0).into());
+        transaction.place("beta", (960, 0).into());
+        transaction.wait_for("alpha", Serial::from(10));
+        transaction.wait_for("beta", Serial::from(11));
+        transaction.open = false;
+        assert!(!transaction.is_ready());
+
+        // An older configure doesn't count, a newer one does
+        transaction.committed(&"alpha", Serial::from(9));
+        assert_eq!(transaction.waiting().count(), 2);
+        transaction.committed(&"alpha", Serial::from(12));
+        assert_eq!(transaction.waiting().collect::<Vec<_>>(), vec![&"beta"]);
+
+        // A window that goes away isn't waited for
+        transaction.forget(&"beta");
+        assert!(transaction.is_ready());
+        assert_eq!(transaction.placements, vec![("alpha", Point::from((0, 0)))]);
+
+        // Placing a window again replaces where it goes
+        transaction.place("alpha", (10, 10).into());
+        assert_eq!(
+            transaction.placements,
+            vec![("alpha", Point::from((10, 10)))]
+        );
+    }
+}
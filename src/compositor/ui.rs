@@ -0,0 +1,208 @@
+// Compositor-drawn UI
+//
+// Building blocks for the overlays the compositor draws itself, like the
+// window menu, the calendar, the close prompt and window hints. A `Style` holds the
+// colors of one stylesheet rule, with defaults for what the rule leaves
+// out. A `Canvas` turns panels, boxes and text into bar elements, in
+// logical coordinates. `list_step` moves keyboard focus through a list.
+//
+// Input reaches the overlays through `overlay_key`, `overlay_click` and
+// `overlay_motion`, which ask each open overlay in turn, topmost first. A
+// new overlay adds itself there rather than to the input handler.
+
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::{text, WebWMCompositor};
+use crate::config::StyleSheet;
+
+/// Colors of a stylesheet rule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub background: [f32; 4],
+    pub color: [f32; 4],
+    pub border_color: [f32; 4],
+}
+
+impl Style {
+    pub const fn new(background: [f32; 4], color: [f32; 4], border_color: [f32; 4]) -> Self {
+        Self {
+            background,
+            color,
+            border_color,
+        }
+    }
+
+    /// The colors `selector` sets, falling back to `defaults`
+    pub fn load(stylesheet: Option<&StyleSheet>, selector: &str, defaults: Style) -> Self {
+        let color = |property: &str, default: [f32; 4]| {
+            stylesheet
+                .and_then(|ss| ss.get_color(selector, property))
+                .map(|color| color.to_rgba_f32())
+                .unwrap_or(default)
+        };
+        Self {
+            background: color("background", defaults.background),
+            color: color("color", defaults.color),
+            border_color: color("border-color", defaults.border_color),
+        }
+    }
+}
+
+/// `color` with its opacity scaled by `factor`
+pub fn dimmed(mut color: [f32; 4], factor: f32) -> [f32; 4] {
+    color[3] *= factor;
+    color
+}
+
+pub fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+    Rectangle::from_loc_and_size((x, y), (w, h))
+}
+
+/// The item after or before `selected` in a list of `len`, wrapping around.
+/// With nothing selected yet, the first or last.
+pub fn list_step(selected: Option<usize>, len: usize, forward: bool) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    Some(match (selected, forward) {
+        (None, true) => 0,
+        (None, false) => len - 1,
+        (Some(i), true) => (i + 1) % len,
+        (Some(i), false) => (i + len - 1) % len,
+    })
+}
+
+/// Collects what an overlay draws
+#[derive(Debug, Default)]
+pub struct Canvas {
+    elements: Vec<BarElement>,
+}
+
+impl Canvas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fill(&mut self, geometry: Rectangle<i32, Logical>, color: [f32; 4]) {
+        self.elements.push(BarElement::Rectangle {
+            geometry: Rectangle::from_loc_and_size(
+                (geometry.loc.x, geometry.loc.y),
+                (geometry.size.w, geometry.size.h),
+            ),
+            color,
+        });
+    }
+
+    /// A box in the style's background with a border of `border_width`
+    /// around it, outside `geometry`
+    pub fn panel(&mut self, geometry: Rectangle<i32, Logical>, style: &Style, border_width: i32) {
+        let b = border_width;
+        if b > 0 {
+            self.fill(
+                rect(
+                    geometry.loc.x - b,
+                    geometry.loc.y - b,
+                    geometry.size.w + 2 * b,
+                    geometry.size.h + 2 * b,
+                ),
+                style.border_color,
+            );
+        }
+        self.fill(geometry, style.background);
+    }
+
+    /// Text with its top left corner at `position`
+    pub fn text(
+        &mut self,
+        position: Point<i32, Logical>,
+        text: impl Into<String>,
+        color: [f32; 4],
+        size: u32,
+    ) {
+        self.elements.push(BarElement::Text {
+            position: (position.x, position.y),
+            text: text.into(),
+            color,
+            size,
+        });
+    }
+
+    /// Text centered across `width` from `position`
+    pub fn text_centered(
+        &mut self,
+        position: Point<i32, Logical>,
+        width: i32,
+        text: impl Into<String>,
+        color: [f32; 4],
+        size: u32,
+    ) {
+        let text = text.into();
+        let x = position.x + (width - text::measure(&text, size)) / 2;
+        self.text((x, position.y).into(), text, color, size);
+    }
+
+    pub fn extend(&mut self, elements: impl IntoIterator<Item = BarElement>) {
+        self.elements.extend(elements);
+    }
+
+    pub fn finish(self) -> Vec<BarElement> {
+        self.elements
+    }
+}
+
+impl WebWMCompositor {
+    /// Give a key press to the overlay that has the keyboard. Returns true
+    /// if one took it.
+    pub fn overlay_key(&mut self, keysym: u32) -> bool {
+        self.window_hints_key(keysym)
+            || self.close_confirm_key(keysym)
+            || self.window_menu_key(keysym)
+            || self.calendar_key(keysym)
+    }
+
+    /// Give a button press to the overlay under the pointer. Returns true
+    /// if one took it; clicks that close an overlay go on to clients.
+    pub fn overlay_click(&mut self, location: Point<f64, Logical>) -> bool {
+        self.window_hints_click()
+            || self.reload_error_click(location)
+            || self.window_menu_click(location)
+            || self.calendar_click(location)
+    }
+
+    pub fn overlay_motion(&mut self, location: Point<f64, Logical>) {
+        self.window_menu_motion(location);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_step() {
+        assert_eq!(list_step(None, 3, true), Some(0));
+        assert_eq!(list_step(None, 3, false), Some(2));
+        assert_eq!(list_step(Some(2), 3, true), Some(0));
+        assert_eq!(list_step(Some(0), 3, false), Some(2));
+        assert_eq!(list_step(Some(1), 3, true), Some(2));
+        assert_eq!(list_step(None, 0, true), None);
+    }
+
+    #[test]
+    fn test_canvas_panel() {
+        let style = Style::new([0.0; 4], [1.0; 4], [0.5; 4]);
+        let mut canvas = Canvas::new();
+        canvas.panel(rect(10, 10, 100, 50), &style, 1);
+        canvas.panel(rect(0, 0, 20, 20), &style, 0);
+        let elements = canvas.finish();
+
+        assert_eq!(elements.len(), 3);
+        let BarElement::Rectangle { geometry, color } = &elements[0] else {
+            panic!("expected the border first");
+        };
+        assert_eq!((geometry.loc.x, geometry.loc.y), (9, 9));
+        assert_eq!((geometry.size.w, geometry.size.h), (102, 52));
+        assert_eq!(*color, [0.5; 4]);
+    }
+}
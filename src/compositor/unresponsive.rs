@@ -0,0 +1,295 @@
+// Unresponsive clients
+//
+// Every client with a toplevel is pinged through xdg_wm_base every couple of
+// seconds. One that hasn't answered within PING_TIMEOUT is marked
+// unresponsive: its windows are dimmed and get a "Not responding" badge, so
+// a hung application doesn't look like a frozen desktop. Answering again
+// clears the mark. The force-close binding (or `webwm-msg force-close`)
+// disconnects the focused window's client without waiting for it.
+
+use serde_json::json;
+use smithay::desktop::Window;
+use smithay::reexports::wayland_server::{backend::DisconnectReason, Resource};
+use smithay::utils::{Rectangle, SERIAL_COUNTER};
+use smithay::wayland::shell::xdg::ShellClient;
+use std::time::{Duration, Instant};
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::{window_app_id, window_title, WebWMCompositor};
+
+/// How often clients are pinged
+pub const PING_INTERVAL: Duration = Duration::from_secs(2);
+/// How long a client has to answer before its windows are marked
+pub const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+const DEFAULT_DIM_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+const BADGE_COLOR: [f32; 4] = [0.19, 0.20, 0.27, 0.95];
+const DEFAULT_TEXT_COLOR: [f32; 4] = [0.95, 0.55, 0.66, 1.0]; // #f38ba8
+const BADGE_SIZE: (i32, i32) = (220, 44);
+
+#[derive(Debug)]
+struct ClientPing<C> {
+    client: C,
+    /// When the ping still waiting for a pong was sent
+    pending: Option<Instant>,
+    unresponsive: bool,
+}
+
+/// Outstanding pings per client. Generic over the client handle so the
+/// bookkeeping can be tested without a Wayland connection.
+#[derive(Debug)]
+pub struct PingTracker<C> {
+    clients: Vec<ClientPing<C>>,
+}
+
+impl<C> Default for PingTracker<C> {
+    fn default() -> Self {
+        Self {
+            clients: Vec::new(),
+        }
+    }
+}
+
+impl<C: Clone + PartialEq> PingTracker<C> {
+    fn get(&self, client: &C) -> Option<&ClientPing<C>> {
+        self.clients.iter().find(|c| &c.client == client)
+    }
+
+    /// A ping is out and hasn't been answered
+    pub fn is_pending(&self, client: &C) -> bool {
+        self.get(client).is_some_and(|c| c.pending.is_some())
+    }
+
+    pub fn sent(&mut self, client: &C, now: Instant) {
+        match self.clients.iter_mut().find(|c| &c.client == client) {
+            Some(entry) => entry.pending = Some(now),
+            None => self.clients.push(ClientPing {
+                client: client.clone(),
+                pending: Some(now),
+                unresponsive: false,
+            }),
+        }
+    }
+
+    /// Returns true if the client had been marked unresponsive
+    pub fn pong(&mut self, client: &C) -> bool {
+        let Some(entry) = self.clients.iter_mut().find(|c| &c.client == client) else {
+            return false;
+        };
+        entry.pending = None;
+        std::mem::take(&mut entry.unresponsive)
+    }
+
+    pub fn remove(&mut self, client: &C) {
+        self.clients.retain(|c| &c.client != client);
+    }
+
+    /// Mark clients whose ping is older than `timeout`, returning the ones
+    /// that just crossed it
+    pub fn expire(&mut self, now: Instant, timeout: Duration) -> Vec<C> {
+        self.clients
+            .iter_mut()
+            .filter(|c| !c.unresponsive)
+            .filter(|c| {
+                c.pending
+                    .is_some_and(|at| now.duration_since(at) >= timeout)
+            })
+            .map(|c| {
+                c.unresponsive = true;
+                c.client.clone()
+            })
+            .collect()
+    }
+
+    pub fn is_unresponsive(&self, client: &C) -> bool {
+        self.get(client).is_some_and(|c| c.unresponsive)
+    }
+}
+
+impl WebWMCompositor {
+    /// Ping every client with a toplevel that isn't already waiting on one,
+    /// and mark the ones that let theirs time out
+    pub fn ping_clients(&mut self, now: Instant) {
+        let mut clients: Vec<ShellClient> = Vec::new();
+        for workspace in self.workspace_manager.all_workspaces() {
+            for window in &workspace.windows {
+                if let Some(toplevel) = window.toplevel() {
+                    let client = toplevel.client();
+                    if !clients.contains(&client) {
+                        clients.push(client);
+                    }
+                }
+            }
+        }
+
+        for client in clients {
+            if self.pings.is_pending(&client) {
+                continue;
+            }
+            match client.send_ping(SERIAL_COUNTER.next_serial()) {
+                Ok(()) => self.pings.sent(&client, now),
+                Err(e) => eprintln!("Failed to ping client: {:?}", e),
+            }
+        }
+
+        for client in self.pings.expire(now, PING_TIMEOUT) {
+            self.client_responsiveness_changed(&client, false);
+        }
+    }
+
+    pub fn client_answered_ping(&mut self, client: &ShellClient) {
+        if self.pings.pong(client) {
+            self.client_responsiveness_changed(client, true);
+        }
+    }
+
+    fn client_responsiveness_changed(&mut self, client: &ShellClient, responsive: bool) {
+        for window in self.client_windows(client) {
+            if responsive {
+                println!("✅ '{}' is responding again", window_app_id(&window));
+            } else {
+                println!("⏳ '{}' is not responding", window_app_id(&window));
+            }
+
+            self.emit_event(
+                "unresponsive",
+                json!({
+                    "app_id": window_app_id(&window),
+                    "title": window_title(&window),
+                    "workspace": self.workspace_manager.find_window_workspace(&window),
+                    "unresponsive": !responsive,
+                }),
+            );
+        }
+    }
+
+    fn client_windows(&self, client: &ShellClient) -> Vec<Window> {
+        self.workspace_manager
+            .all_workspaces()
+            .into_iter()
+            .flat_map(|workspace| workspace.windows.iter())
+            .filter(|window| {
+                window
+                    .toplevel()
+                    .is_some_and(|toplevel| &toplevel.client() == client)
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_window_unresponsive(&self, window: &Window) -> bool {
+        window
+            .toplevel()
+            .is_some_and(|toplevel| self.pings.is_unresponsive(&toplevel.client()))
+    }
+
+    /// Disconnect the focused window's client. Unlike closing, this doesn't
+    /// need the client's cooperation; its windows go away with the
+    /// connection.
+    pub fn force_close_focused(&mut self) {
+        let Some(window) = self.workspace_manager.focused_window().cloned() else {
+            return;
+        };
+        let Some(client) = window
+            .toplevel()
+            .and_then(|toplevel| toplevel.wl_surface().client())
+        else {
+            return;
+        };
+
+        println!("💀 Force closing '{}'", window_app_id(&window));
+        self.display_handle
+            .backend_handle()
+            .kill_client(client.id(), DisconnectReason::ConnectionClosed);
+    }
+
+    /// Dimmed unresponsive windows on the active workspace, each with a
+    /// centered badge. Colors come from the stylesheet's `.unresponsive`
+    /// rule.
+    pub fn render_unresponsive_overlays(&self) -> Vec<BarElement> {
+        let style = |property: &str, default: [f32; 4]| {
+            self.stylesheet
+                .as_ref()
+                .and_then(|ss| ss.get_color(".unresponsive", property))
+                .map(|color| color.to_rgba_f32())
+                .unwrap_or(default)
+        };
+
+        let mut elements = Vec::new();
+        for window in &self.workspace_manager.active_workspace().windows {
+            if !self.is_window_unresponsive(window) {
+                continue;
+            }
+            let Some(geometry) = self.space.element_geometry(window) else {
+                continue;
+            };
+
+            let (x, y, w, h) = (
+                geometry.loc.x,
+                geometry.loc.y,
+                geometry.size.w,
+                geometry.size.h,
+            );
+            let (badge_w, badge_h) = BADGE_SIZE;
+            let (badge_x, badge_y) = (x + (w - badge_w) / 2, y + (h - badge_h) / 2);
+            let text = style("color", DEFAULT_TEXT_COLOR);
+
+            elements.push(BarElement::Rectangle {
+                geometry: Rectangle::from_loc_and_size((x, y), (w, h)),
+                color: style("background-color", DEFAULT_DIM_COLOR),
+            });
+            elements.push(BarElement::Rectangle {
+                geometry: Rectangle::from_loc_and_size((badge_x, badge_y), (badge_w, badge_h)),
+                color: BADGE_COLOR,
+            });
+            elements.push(BarElement::Text {
+                position: (badge_x + 12, badge_y + 6),
+                text: "Not responding".to_string(),
+                color: text,
+                size: 13,
+            });
+            elements.push(BarElement::Text {
+                position: (badge_x + 12, badge_y + 25),
+                text: "Super+Shift+Q to force close".to_string(),
+                color: text,
+                size: 10,
+            });
+        }
+
+        elements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ping_timeout() {
+        let mut pings = PingTracker::default();
+        let start = Instant::now();
+
+        pings.sent(&1, start);
+        pings.sent(&2, start);
+        assert!(pings.is_pending(&1));
+        assert!(!pings.pong(&2));
+        assert!(!pings.is_pending(&2));
+
+        // Only the client that never answered crosses the timeout, once
+        assert!(pings
+            .expire(start + Duration::from_secs(1), PING_TIMEOUT)
+            .is_empty());
+        assert_eq!(pings.expire(start + PING_TIMEOUT, PING_TIMEOUT), vec![1]);
+        assert!(pings
+            .expire(start + PING_TIMEOUT * 2, PING_TIMEOUT)
+            .is_empty());
+        assert!(pings.is_unresponsive(&1));
+
+        // A late pong clears the mark
+        assert!(pings.pong(&1));
+        assert!(!pings.is_unresponsive(&1));
+
+        pings.remove(&1);
+        assert!(!pings.is_pending(&1));
+    }
+}
@@ -0,0 +1,217 @@
+// Window urgency
+//
+// A window that asks for attention through xdg-activation while it isn't
+// focused becomes urgent. Its border pulses between its normal color and the
+// `window:urgent` border color, at the speed of that rule's `transition`, and
+// its workspace button in the bar bounces, until the window gets focus.
+
+use serde_json::json;
+use smithay::desktop::Window;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::time::{Duration, Instant};
+
+use crate::compositor::workspace::Workspace;
+use crate::compositor::{window_app_id, window_title, WebWMCompositor};
+
+/// Used when the stylesheet has no `transition` on `window:urgent`
+const DEFAULT_PULSE: Duration = Duration::from_millis(500);
+/// How far the workspace button jumps, in pixels
+const BOUNCE_HEIGHT: f32 = 4.0;
+const DEFAULT_URGENT_COLOR: [f32; 4] = [0.95, 0.55, 0.66, 1.0]; // #f38ba8
+
+#[derive(Debug, Default)]
+pub struct UrgencyTracker {
+    windows: Vec<(Window, Instant)>,
+}
+
+impl UrgencyTracker {
+    /// Returns false if the window was already urgent
+    pub fn set_urgent(&mut self, window: &Window) -> bool {
+        if self.is_urgent(window) {
+            return false;
+        }
+        self.windows.push((window.clone(), Instant::now()));
+        true
+    }
+
+    /// Returns false if the window wasn't urgent
+    pub fn clear(&mut self, window: &Window) -> bool {
+        let before = self.windows.len();
+        self.windows.retain(|(w, _)| w != window);
+        self.windows.len() != before
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    pub fn is_urgent(&self, window: &Window) -> bool {
+        self.since(window).is_some()
+    }
+
+    pub fn since(&self, window: &Window) -> Option<Instant> {
+        self.windows
+            .iter()
+            .find(|(w, _)| w == window)
+            .map(|(_, since)| *since)
+    }
+
+    /// When the oldest urgent window on this workspace became urgent
+    pub fn workspace_since(&self, workspace: &Workspace) -> Option<Instant> {
+        workspace
+            .windows
+            .iter()
+            .filter_map(|window| self.since(window))
+            .min()
+    }
+}
+
+/// 0.0 at the normal color, 1.0 at the urgent color. Takes `half_period`
+/// to go from one to the other.
+pub fn pulse(elapsed: Duration, half_period: Duration) -> f32 {
+    if half_period.is_zero() {
+        return 1.0;
+    }
+    let t = elapsed.as_secs_f32() / half_period.as_secs_f32();
+    0.5 - 0.5 * (t * PI).cos()
+}
+
+pub fn blend(from: [f32; 4], to: [f32; 4], t: f32) -> [f32; 4] {
+    std::array::from_fn(|i| from[i] + (to[i] - from[i]) * t)
+}
+
+impl WebWMCompositor {
+    pub fn set_window_urgent(&mut self, window: &Window, urgent: bool) {
+        let changed = if urgent {
+            self.urgency.set_urgent(window)
+        } else {
+            self.urgency.clear(window)
+        };
+        if !changed {
+            return;
+        }
+
+        let workspace = self.workspace_manager.find_window_workspace(window);
+        println!(
+            "🔔 Window '{}' {} urgent",
+            window_app_id(window),
+            if urgent { "is" } else { "is no longer" }
+        );
+
+        self.emit_event(
+            "urgent",
+            json!({
+                "app_id": window_app_id(window),
+                "title": window_title(window),
+                "workspace": workspace,
+                "urgent": urgent,
+            }),
+        );
+    }
+
+    /// Focus reached this surface, so its window has the user's attention
+    pub fn clear_urgency_for_surface(&mut self, surface: &WlSurface) {
+        if let Some(window) = self.window_for_surface(surface) {
+            self.set_window_urgent(&window, false);
+        }
+    }
+
+    fn urgent_pulse_duration(&self) -> Duration {
+        self.stylesheet
+            .as_ref()
+            .and_then(|ss| {
+                ss.get_duration("window:urgent", "transition")
+                    .or_else(|| ss.get_duration("window.urgent", "transition"))
+            })
+            .unwrap_or(DEFAULT_PULSE)
+    }
+
+    fn animations_enabled(&self) -> bool {
+        self.config
+            .desktop
+            .as_ref()
+            .is_none_or(|desktop| desktop.animations.enabled)
+    }
+
+    /// Animations can also be turned off per window with a rule
+    fn window_animations_enabled(&self, window: &Window) -> bool {
+        self.animations_enabled()
+            && self
+                .window_rules_for(window)
+                .find_map(|rule| rule.animations)
+                .unwrap_or(true)
+    }
+
+    /// Border color of an urgent window right now, blended from `base`
+    pub fn urgent_border_color(&self, window: &Window, base: [f32; 4]) -> Option<[f32; 4]> {
+        let since = self.urgency.since(window)?;
+
+        let urgent_color = self
+            .stylesheet
+            .as_ref()
+            .and_then(|ss| {
+                ss.get_color("window:urgent", "border-color")
+                    .or_else(|| ss.get_color("window.urgent", "border-color"))
+            })
+            .map(|c| c.to_rgba_f32())
+            .unwrap_or(DEFAULT_URGENT_COLOR);
+
+        if !self.window_animations_enabled(window) {
+            return Some(urgent_color);
+        }
+
+        let t = pulse(since.elapsed(), self.urgent_pulse_duration());
+        Some(blend(base, urgent_color, t))
+    }
+
+    /// Workspaces holding an urgent window, with the vertical offset of
+    /// their bar button (negative while bouncing up)
+    pub fn urgent_workspaces(&self) -> HashMap<u32, i32> {
+        let bounce = self.animations_enabled()
+            && self
+                .config
+                .desktop
+                .as_ref()
+                .is_none_or(|desktop| desktop.animations.urgent_bounce);
+        let half_period = self.urgent_pulse_duration();
+
+        self.workspace_manager
+            .all_workspaces()
+            .into_iter()
+            .filter_map(|workspace| {
+                let since = self.urgency.workspace_since(workspace)?;
+                let offset = if bounce {
+                    -(pulse(since.elapsed(), half_period) * BOUNCE_HEIGHT).round() as i32
+                } else {
+                    0
+                };
+                Some((workspace.id, offset))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pulse() {
+        let half = Duration::from_millis(500);
+        assert_eq!(pulse(Duration::ZERO, half), 0.0);
+        assert!((pulse(half, half) - 1.0).abs() < 1e-6);
+        assert!(pulse(Duration::from_millis(1000), half) < 1e-6);
+        assert_eq!(pulse(Duration::from_millis(123), Duration::ZERO), 1.0);
+    }
+
+    #[test]
+    fn test_blend() {
+        let black = [0.0, 0.0, 0.0, 1.0];
+        let white = [1.0, 1.0, 1.0, 1.0];
+        assert_eq!(blend(black, white, 0.0), black);
+        assert_eq!(blend(black, white, 1.0), white);
+        assert_eq!(blend(black, white, 0.5), [0.5, 0.5, 0.5, 1.0]);
+    }
+}
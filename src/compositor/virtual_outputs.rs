@@ -0,0 +1,151 @@
+// Virtual outputs
+//
+// `--virtual-output WIDTHxHEIGHT[@SCALE][+X+Y]`, as often as needed, adds
+// outputs that exist only for layout to the winit and headless backends:
+// `webwm --virtual-output 2560x1440@2 --virtual-output 1280x1024+0+720`.
+// They're named VIRTUAL-1, VIRTUAL-2 and so on, so desktop.xml can give them
+// workspaces, bars and scales like real outputs, and multi-monitor setups
+// can be worked on with a single screen. Without a position each one goes
+// to the right of the outputs before it. Nothing is drawn for them.
+
+use smithay::output::{Mode, Output, PhysicalProperties, Scale, Subpixel};
+use smithay::utils::{Logical, Physical, Point, Size, Transform};
+
+use crate::compositor::WebWMCompositor;
+
+/// An output asked for with `--virtual-output`
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualOutputSpec {
+    /// In pixels
+    pub size: Size<i32, Physical>,
+    pub scale: f64,
+    /// In logical coordinates, None to go right of the others
+    pub position: Option<Point<i32, Logical>>,
+}
+
+impl VirtualOutputSpec {
+    /// Parse `2560x1440`, `2560x1440@2`, `1280x1024+0+720` or both
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("'{}' isn't WIDTHxHEIGHT[@SCALE][+X+Y]", spec);
+
+        let (rest, position) = match spec.split_once('+') {
+            Some((rest, position)) => {
+                let (x, y) = position.split_once('+').ok_or_else(invalid)?;
+                let x = x.parse().map_err(|_| invalid())?;
+                let y = y.parse().map_err(|_| invalid())?;
+                (rest, Some((x, y).into()))
+            }
+            None => (spec, None),
+        };
+        let (size, scale) = match rest.split_once('@') {
+            Some((size, scale)) => (size, scale.parse().map_err(|_| invalid())?),
+            None => (rest, 1.0),
+        };
+        let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+        let width: i32 = width.parse().map_err(|_| invalid())?;
+        let height: i32 = height.parse().map_err(|_| invalid())?;
+
+        if width <= 0 || height <= 0 {
+            return Err(format!("'{}' has no area", spec));
+        }
+        if !scale.is_finite() || scale <= 0.0 {
+            return Err(format!("'{}' needs a scale above 0", spec));
+        }
+        Ok(Self {
+            size: (width, height).into(),
+            scale,
+            position,
+        })
+    }
+}
+
+impl WebWMCompositor {
+    /// Create the output `spec` asks for and map it next to the others
+    pub fn add_virtual_output(&mut self, spec: &VirtualOutputSpec) -> Output {
+        let index = self
+            .space
+            .outputs()
+            .filter(|output| output.name().starts_with("VIRTUAL-"))
+            .count()
+            + 1;
+        let output = Output::new(
+            format!("VIRTUAL-{}", index),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: "WebWM".into(),
+                model: "Virtual".into(),
+                serial_number: String::new(),
+            },
+        );
+        let mode = Mode {
+            size: spec.size,
+            refresh: 60_000,
+        };
+
+        // Right of the rightmost output, top-aligned
+        let position = spec.position.unwrap_or_else(|| {
+            let right = self
+                .space
+                .outputs()
+                .filter_map(|output| self.space.output_geometry(output))
+                .map(|geometry| geometry.loc.x + geometry.size.w)
+                .max()
+                .unwrap_or_default();
+            (right, 0).into()
+        });
+
+        output.change_current_state(
+            Some(mode),
+            Some(Transform::Normal),
+            Some(Scale::Fractional(spec.scale)),
+            Some(position),
+        );
+        output.set_preferred(mode);
+        self.space.map_output(&output, position);
+
+        println!(
+            "✓ Virtual output {}: {}x{} at scale {}, position {},{}",
+            output.name(),
+            spec.size.w,
+            spec.size.h,
+            spec.scale,
+            position.x,
+            position.y
+        );
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_virtual_output() {
+        assert_eq!(
+            VirtualOutputSpec::parse("2560x1440@2").unwrap(),
+            VirtualOutputSpec {
+                size: (2560, 1440).into(),
+                scale: 2.0,
+                position: None,
+            }
+        );
+        assert_eq!(
+            VirtualOutputSpec::parse("1280x1024@1.25+0+720").unwrap(),
+            VirtualOutputSpec {
+                size: (1280, 1024).into(),
+                scale: 1.25,
+                position: Some((0, 720).into()),
+            }
+        );
+        let plain = VirtualOutputSpec::parse("1920x1080+-1920+0").unwrap();
+        assert_eq!(plain.scale, 1.0);
+        assert_eq!(plain.position, Some((-1920, 0).into()));
+
+        assert!(VirtualOutputSpec::parse("1920").is_err());
+        assert!(VirtualOutputSpec::parse("1920x1080@0").is_err());
+        assert!(VirtualOutputSpec::parse("0x1080").is_err());
+        assert!(VirtualOutputSpec::parse("1920x1080+10").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+// Dragging windows to workspaces
+//
+// Super+left-drag picks up the window under the pointer. While it is held,
+// its title follows the pointer and the workspace button under the pointer
+// is highlighted with the `workspace:drop-target` colors; releasing over a
+// button moves the window to that workspace, releasing anywhere else puts
+// it back. Clients don't see the drag's button presses or motion.
+
+use smithay::desktop::Window;
+use smithay::input::pointer::{CursorIcon, CursorImageStatus};
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::text;
+use crate::compositor::{window_app_id, window_title, WebWMCompositor};
+
+const DEFAULT_TARGET_BACKGROUND: [f32; 4] = [0.65, 0.89, 0.63, 0.35];
+const DEFAULT_TARGET_BORDER: [f32; 4] = [0.65, 0.89, 0.63, 1.0]; // #a6e3a1
+const DEFAULT_LABEL_BACKGROUND: [f32; 4] = [0.11, 0.11, 0.18, 0.9];
+const DEFAULT_LABEL_COLOR: [f32; 4] = [0.8, 0.83, 0.96, 1.0];
+
+#[derive(Debug, Clone)]
+pub struct WindowDrag {
+    pub window: Window,
+    pub pointer: Point<i32, Logical>,
+    /// Workspace button under the pointer and its rectangle
+    pub target: Option<(u32, Rectangle<i32, Logical>)>,
+}
+
+impl WebWMCompositor {
+    /// Pick up the window under `location`. Returns false if there is none.
+    pub fn begin_window_drag(&mut self, location: Point<f64, Logical>) -> bool {
+        let Some(window) = self
+            .space
+            .element_under(location)
+            .map(|(window, _)| window.clone())
+        else {
+            return false;
+        };
+
+        println!("✋ Dragging '{}'", window_app_id(&window));
+        self.window_drag = Some(WindowDrag {
+            window,
+            pointer: location.to_i32_round(),
+            target: None,
+        });
+        self.cursor_image_status = CursorImageStatus::Named(CursorIcon::Grabbing);
+        true
+    }
+
+    pub fn window_drag_motion(&mut self, location: Point<f64, Logical>) {
+        let point = location.to_i32_round();
+        let output = self.primary_output_name();
+        let target = self
+            .bar_renderer
+            .as_ref()
+            .filter(|_| self.bar_visible_on(output.as_deref()))
+            .and_then(|bar_renderer| {
+                bar_renderer.workspace_at(
+                    (point.x, point.y),
+                    output.as_deref(),
+                    &self.workspace_manager,
+                    self.get_focused_window_title(),
+                )
+            });
+
+        if let Some(ref mut drag) = self.window_drag {
+            drag.pointer = point;
+            drag.target = target;
+        }
+    }
+
+    /// Drop the dragged window, moving it if it is over a workspace button
+    pub fn end_window_drag(&mut self) {
+        let Some(drag) = self.window_drag.take() else {
+            return;
+        };
+        self.cursor_image_status = CursorImageStatus::default_named();
+
+        let Some((target, _)) = drag.target else {
+            return;
+        };
+        if self.workspace_manager.find_window_workspace(&drag.window) == Some(target) {
+            return;
+        }
+
+        println!(
+            "✋ Dropped '{}' on workspace {}",
+            window_app_id(&drag.window),
+            target
+        );
+        self.workspace_manager
+            .move_window_to_workspace(drag.window, target);
+        self.relayout();
+    }
+
+    /// The highlighted drop target and the dragged window's title
+    pub fn render_window_drag(&self) -> Vec<BarElement> {
+        let Some(ref drag) = self.window_drag else {
+            return Vec::new();
+        };
+
+        let style = |property: &str, default: [f32; 4]| {
+            self.stylesheet
+                .as_ref()
+                .and_then(|ss| ss.get_color("workspace:drop-target", property))
+                .map(|color| color.to_rgba_f32())
+                .unwrap_or(default)
+        };
+        let rect = |x: i32, y: i32, w: i32, h: i32, color: [f32; 4]| BarElement::Rectangle {
+            geometry: Rectangle::from_loc_and_size((x, y), (w, h)),
+            color,
+        };
+
+        let mut elements = Vec::new();
+        if let Some((_, button)) = drag.target {
+            let border = style("border-color", DEFAULT_TARGET_BORDER);
+            let (x, y, w, h) = (button.loc.x, button.loc.y, button.size.w, button.size.h);
+            elements.extend([
+                rect(x, y, w, h, style("background", DEFAULT_TARGET_BACKGROUND)),
+                rect(x, y, w, 1, border),
+                rect(x, y + h - 1, w, 1, border),
+                rect(x, y, 1, h, border),
+                rect(x + w - 1, y, 1, h, border),
+            ]);
+        }
+
+        let mut label = window_title(&drag.window);
+        if label.is_empty() {
+            label = window_app_id(&drag.window);
+        }
+        let (x, y) = (drag.pointer.x + 16, drag.pointer.y + 16);
+        elements.push(rect(
+            x,
+            y,
+            text::measure(&label, 12) + 12,
+            20,
+            DEFAULT_LABEL_BACKGROUND,
+        ));
+        elements.push(BarElement::Text {
+            position: (x + 6, y + 4),
+            text: label,
+            color: DEFAULT_LABEL_COLOR,
+            size: 12,
+        });
+
+        elements
+    }
+}
@@ -0,0 +1,192 @@
+// Window menu
+//
+// Right-clicking a window's tab title, or the window-menu binding, opens a
+// menu for that window: move it to another workspace, float it over the
+// tiles, make it fullscreen, keep it above the others, or close it (asking
+// first if its rules say so). The menu has the keyboard while it's open;
+// clicking outside it closes it and goes on to the window under the pointer.
+
+use smithay::desktop::Window;
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::menu::{Menu, MenuEvent, MenuItem};
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowMenuAction {
+    MoveToWorkspace(u32),
+    Float,
+    Fullscreen,
+    AlwaysOnTop,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowMenu {
+    /// The window the menu acts on
+    pub window: Window,
+    pub menu: Menu<WindowMenuAction>,
+}
+
+impl WebWMCompositor {
+    /// Open the menu of `window` at `position`
+    pub fn open_window_menu(&mut self, window: &Window, position: Point<i32, Logical>) {
+        let workspace = self.workspace_manager.active_workspace();
+        if !workspace.windows.contains(window) {
+            return;
+        }
+
+        let workspaces = self
+            .workspace_manager
+            .all_workspaces()
+            .into_iter()
+            .map(|ws| {
+                MenuItem::toggle(
+                    ws.display_name(),
+                    WindowMenuAction::MoveToWorkspace(ws.id),
+                    ws.id == workspace.id,
+                )
+            })
+            .collect();
+        let items = vec![
+            MenuItem::submenu("Move to workspace", workspaces),
+            MenuItem::toggle(
+                "Float",
+                WindowMenuAction::Float,
+                workspace.is_floating(window),
+            ),
+            MenuItem::toggle(
+                "Fullscreen",
+                WindowMenuAction::Fullscreen,
+                self.is_fullscreen(window),
+            ),
+            MenuItem::toggle(
+                "Always on top",
+                WindowMenuAction::AlwaysOnTop,
+                workspace.always_on_top.contains(window),
+            ),
+            MenuItem::new("Close", WindowMenuAction::Close),
+        ];
+
+        let bounds = self
+            .space
+            .output_under(position.to_f64())
+            .next()
+            .and_then(|output| self.space.output_geometry(output))
+            .unwrap_or_else(|| Rectangle::from_loc_and_size((0, 0), self.layout_size()));
+
+        println!("📋 Window menu for '{}'", window_app_id(window));
+        self.window_menu = Some(WindowMenu {
+            window: window.clone(),
+            menu: Menu::new(items, position, bounds),
+        });
+    }
+
+    /// Open the menu of the focused window at its top left corner
+    pub fn open_focused_window_menu(&mut self) {
+        let Some(window) = self.workspace_manager.focused_window().cloned() else {
+            return;
+        };
+        let position = self
+            .space
+            .element_geometry(&window)
+            .map(|geometry| geometry.loc)
+            .unwrap_or_else(|| self.pointer_location().to_i32_round());
+        self.open_window_menu(&window, position);
+    }
+
+    /// Open the menu of the window whose tab title was right-clicked.
+    /// Returns true if the click was on a tab.
+    pub fn tab_menu_click(&mut self, location: Point<f64, Logical>) -> bool {
+        let point = location.to_i32_round();
+        let Some(window) = self.tab_at(point) else {
+            return false;
+        };
+        self.open_window_menu(&window, point);
+        true
+    }
+
+    pub fn window_menu_motion(&mut self, location: Point<f64, Logical>) {
+        if let Some(ref mut window_menu) = self.window_menu {
+            window_menu.menu.motion(location.to_i32_round());
+        }
+    }
+
+    /// Handle a button press for the menu. Returns true when it was meant
+    /// for it; clicks elsewhere close it and go on to clients.
+    pub fn window_menu_click(&mut self, location: Point<f64, Logical>) -> bool {
+        let Some(ref mut window_menu) = self.window_menu else {
+            return false;
+        };
+        match window_menu.menu.click(location.to_i32_round()) {
+            None => true,
+            Some(MenuEvent::Picked(action)) => {
+                self.finish_window_menu(action);
+                true
+            }
+            Some(MenuEvent::Closed) => {
+                self.window_menu = None;
+                false
+            }
+        }
+    }
+
+    /// The menu takes every key while it's open. Returns true if it was.
+    pub fn window_menu_key(&mut self, keysym: u32) -> bool {
+        let Some(ref mut window_menu) = self.window_menu else {
+            return false;
+        };
+        match window_menu.menu.key(keysym) {
+            None => {}
+            Some(MenuEvent::Picked(action)) => self.finish_window_menu(action),
+            Some(MenuEvent::Closed) => self.window_menu = None,
+        }
+        true
+    }
+
+    /// Close the menu and do what was picked
+    fn finish_window_menu(&mut self, action: WindowMenuAction) {
+        let Some(WindowMenu { window, .. }) = self.window_menu.take() else {
+            return;
+        };
+        // The window may have closed, or the workspace changed, meanwhile
+        if !self
+            .workspace_manager
+            .active_workspace()
+            .windows
+            .contains(&window)
+        {
+            return;
+        }
+
+        match action {
+            WindowMenuAction::MoveToWorkspace(id) => {
+                if id != self.workspace_manager.active_workspace_id()
+                    && self.workspace_manager.move_window_to_workspace(window, id)
+                {
+                    self.relayout();
+                }
+            }
+            WindowMenuAction::Float => self.toggle_window_floating(&window),
+            WindowMenuAction::Fullscreen => {
+                let enabled = !self.is_fullscreen(&window);
+                self.set_fullscreen(&window, enabled);
+            }
+            WindowMenuAction::AlwaysOnTop => self.toggle_always_on_top(&window),
+            WindowMenuAction::Close => {
+                self.workspace_manager
+                    .active_workspace_mut()
+                    .focus_window(&window);
+                self.close_focused();
+            }
+        }
+    }
+
+    pub fn render_window_menu(&self) -> Vec<BarElement> {
+        self.window_menu
+            .as_ref()
+            .map(|window_menu| window_menu.menu.render(self.stylesheet.as_ref()))
+            .unwrap_or_default()
+    }
+}
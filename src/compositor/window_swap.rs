@@ -0,0 +1,76 @@
+// Swapping windows
+//
+// The swap action exchanges the focused window's tile with the previous
+// ("left" or "up") or next ("right" or "down") tile in layout order. The
+// two trade places, sizes and, on floating workspaces, positions, and are
+// sent their new geometries right away. On dwindle workspaces the order is
+// that of the split tree, so the tiles trade areas; elsewhere it's the
+// order the workspace keeps its windows in.
+
+use crate::compositor::workspace::LayoutMode;
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+impl WebWMCompositor {
+    /// Swap the focused tile with its neighbor toward `direction`
+    pub fn swap_window(&mut self, direction: &str) {
+        let forward = match direction {
+            "left" | "up" => false,
+            "right" | "down" => true,
+            _ => {
+                println!("Unknown swap direction: {}", direction);
+                return;
+            }
+        };
+        let workspace = self.workspace_manager.active_workspace();
+        let Some(focused) = workspace
+            .focused_window()
+            .map(|window| workspace.dialog_root(window).clone())
+        else {
+            return;
+        };
+        let neighbor = |index: usize| {
+            if forward {
+                index.checked_add(1)
+            } else {
+                index.checked_sub(1)
+            }
+        };
+
+        if workspace.layout_mode == LayoutMode::Dwindle {
+            let Some(leaf) = self.focused_dwindle_leaf() else {
+                return;
+            };
+            let leaves = workspace.dwindle.leaves();
+            let Some(other) = leaves
+                .iter()
+                .position(|l| l == &leaf)
+                .and_then(neighbor)
+                .and_then(|i| leaves.get(i))
+                .cloned()
+            else {
+                return;
+            };
+            self.workspace_manager
+                .active_workspace_mut()
+                .dwindle
+                .swap(&leaf, &other);
+        } else {
+            let tiles = workspace.tiles();
+            let Some(index) = tiles
+                .iter()
+                .position(|tile| tile.windows.contains(&focused))
+            else {
+                return;
+            };
+            let Some(other) = neighbor(index).and_then(|i| tiles.get(i)) else {
+                return;
+            };
+            self.workspace_manager
+                .active_workspace_mut()
+                .swap_tiles(&tiles[index], other);
+        }
+
+        println!("🔀 Swapped '{}' {}", window_app_id(&focused), direction);
+        self.relayout();
+    }
+}
@@ -1,6 +1,10 @@
 use smithay::desktop::Window;
+use smithay::utils::{Logical, Rectangle};
 use std::collections::HashMap;
 
+use crate::compositor::dwindle::DwindleTree;
+use crate::config::WorkspaceConfig;
+
 #[derive(Debug, Clone)]
 pub struct Workspace {
     pub id: u32,
@@ -8,6 +12,63 @@ pub struct Workspace {
     pub layout_mode: LayoutMode,
     pub windows: Vec<Window>,
     pub focused_window_idx: Option<usize>,
+    /// Name derived from the windows on it, when auto-naming is on
+    pub auto_name: Option<String>,
+    /// Groups of two or more merged windows
+    pub tab_groups: Vec<TabGroup>,
+    /// Window covering the whole output; container fullscreen isn't tracked
+    pub fullscreen_window: Option<Window>,
+    /// Window filling the area below the bar
+    pub maximized_window: Option<Window>,
+    /// Dialogs on this workspace, each with the window it belongs to
+    pub dialogs: Vec<(Window, Window)>,
+    /// Share of the width the master tile gets in master-stack mode, once
+    /// adjusted away from `<master-ratio>`
+    pub master_ratio: Option<f32>,
+    /// Tiles of dwindle mode, kept even while another layout is shown
+    pub dwindle: DwindleTree<Window>,
+    /// Sizes of resized tiles relative to their neighbors, by one of the
+    /// tile's windows; tiles not listed have a weight of 1
+    pub tile_weights: Vec<(Window, f32)>,
+    /// Where tiles moved or resized with the mouse in floating mode are, by
+    /// one of the tile's windows
+    pub floating_geometries: Vec<(Window, Rectangle<i32, Logical>)>,
+    /// Tiles of floating mode from bottom to top, by one of the tile's
+    /// windows; tiles not listed are below them all
+    pub floating_stack: Vec<Window>,
+    /// Windows kept above the others, set from the window menu
+    pub always_on_top: Vec<Window>,
+    /// Windows floating over the tiles in layouts other than floating,
+    /// from the toggle-floating action or `floating` window rules
+    pub floating_windows: Vec<Window>,
+    /// Output whose bars list this workspace, from `<workspace output>`
+    pub output: Option<String>,
+    /// Gaps between and around tiles in place of the layout's, from
+    /// `<workspace><gaps/>`
+    pub gaps_inner: Option<u32>,
+    pub gaps_outer: Option<u32>,
+}
+
+/// Windows sharing one tile, shown one at a time like browser tabs. Single
+/// windows are laid out as a group of one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabGroup {
+    pub windows: Vec<Window>,
+    /// Index of the tab that is shown
+    pub active: usize,
+}
+
+impl TabGroup {
+    pub fn visible(&self) -> &Window {
+        &self.windows[self.active]
+    }
+}
+
+/// Whether a window is laid out on its own or over its parent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowKind {
+    Normal,
+    Dialog,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -15,6 +76,12 @@ pub enum LayoutMode {
     Tiling,
     Floating,
     Monocle,
+    /// One master tile on the left, the others stacked on the right
+    MasterStack,
+    /// Each new window splits the focused tile in two
+    Dwindle,
+    /// Every window fills the workspace, with a strip of tabs to pick one
+    Tabbed,
 }
 
 impl Workspace {
@@ -25,10 +92,150 @@ impl Workspace {
             layout_mode,
             windows: Vec::new(),
             focused_window_idx: None,
+            auto_name: None,
+            tab_groups: Vec::new(),
+            fullscreen_window: None,
+            maximized_window: None,
+            dialogs: Vec::new(),
+            master_ratio: None,
+            dwindle: DwindleTree::new(),
+            tile_weights: Vec::new(),
+            floating_geometries: Vec::new(),
+            floating_stack: Vec::new(),
+            always_on_top: Vec::new(),
+            floating_windows: Vec::new(),
+            output: None,
+            gaps_inner: None,
+            gaps_outer: None,
+        }
+    }
+
+    /// A workspace as `<workspace>` in desktop.xml describes it
+    pub fn from_config(config: &WorkspaceConfig) -> Self {
+        let layout_mode = LayoutMode::from(config.layout.as_str());
+        let mut workspace = Self::new(config.id, config.name.clone(), layout_mode);
+        if let Some(ratio) = config.split_ratio {
+            workspace.dwindle.default_ratio = ratio;
+        }
+        workspace.output = config.output.clone();
+        workspace.gaps_inner = config.gaps_inner;
+        workspace.gaps_outer = config.gaps_outer;
+        workspace
+    }
+
+    /// Size of `tile` relative to the tiles it shares a row or column with
+    pub fn tile_weight(&self, tile: &TabGroup) -> f32 {
+        self.tile_weights
+            .iter()
+            .find(|(window, _)| tile.windows.contains(window))
+            .map_or(1.0, |(_, weight)| *weight)
+    }
+
+    pub fn set_tile_weight(&mut self, tile: &TabGroup, weight: f32) {
+        self.tile_weights
+            .retain(|(window, _)| !tile.windows.contains(window));
+        self.tile_weights.push((tile.windows[0].clone(), weight));
+    }
+
+    /// Where `tile` was put in floating mode, if it was moved or resized
+    pub fn floating_geometry(&self, tile: &TabGroup) -> Option<Rectangle<i32, Logical>> {
+        self.floating_geometries
+            .iter()
+            .find(|(window, _)| tile.windows.contains(window))
+            .map(|(_, geometry)| *geometry)
+    }
+
+    pub fn set_floating_geometry(&mut self, tile: &TabGroup, geometry: Rectangle<i32, Logical>) {
+        self.floating_geometries
+            .retain(|(window, _)| !tile.windows.contains(window));
+        self.floating_geometries
+            .push((tile.windows[0].clone(), geometry));
+    }
+
+    /// Windows of the tile `window` is in, or goes over as a dialog
+    fn tile_windows(&self, window: &Window) -> Vec<Window> {
+        let root = self.dialog_root(window);
+        self.tab_group(root)
+            .map_or_else(|| vec![root.clone()], |group| group.windows.clone())
+    }
+
+    /// Where the tile of `window` is in the floating stack, 0 being the
+    /// bottom
+    pub fn stack_index(&self, window: &Window) -> Option<usize> {
+        let windows = self.tile_windows(window);
+        self.floating_stack.iter().position(|w| windows.contains(w))
+    }
+
+    /// Put the tile of `window` on top of the floating stack, or at the
+    /// bottom
+    pub fn restack(&mut self, window: &Window, top: bool) {
+        let windows = self.tile_windows(window);
+        self.floating_stack.retain(|w| !windows.contains(w));
+        let root = self.dialog_root(window).clone();
+        if top {
+            self.floating_stack.push(root);
+        } else {
+            self.floating_stack.insert(0, root);
         }
     }
 
+    /// Whether `window` is drawn above the others, directly or as a tab or
+    /// dialog of such a window
+    pub fn is_on_top(&self, window: &Window) -> bool {
+        let windows = self.tile_windows(window);
+        self.always_on_top.iter().any(|w| windows.contains(w))
+    }
+
+    /// Keep `window` above the others, or stop doing so
+    pub fn set_on_top(&mut self, window: &Window, on_top: bool) {
+        self.always_on_top.retain(|w| w != window);
+        if on_top {
+            self.always_on_top.push(window.clone());
+        }
+    }
+
+    /// Whether `window` floats: every window does on floating workspaces,
+    /// elsewhere the windows set to float and their dialogs
+    pub fn is_floating(&self, window: &Window) -> bool {
+        self.layout_mode == LayoutMode::Floating
+            || self.floating_windows.contains(self.dialog_root(window))
+    }
+
+    /// Float `window` over the tiles, or put it back in a tile of its own
+    pub fn set_floating(&mut self, window: &Window, floating: bool) {
+        let root = self.dialog_root(window).clone();
+        self.floating_windows.retain(|w| w != &root);
+        if floating {
+            self.detach_tab(&root);
+            self.floating_windows.push(root);
+        }
+    }
+
+    /// Tiles that float, in layout order: all of them on floating
+    /// workspaces, elsewhere each floating window on its own
+    pub fn floating_tiles(&self) -> Vec<TabGroup> {
+        if self.layout_mode == LayoutMode::Floating {
+            return self.tiles();
+        }
+        self.windows
+            .iter()
+            .filter(|window| self.floating_windows.contains(window))
+            .map(|window| TabGroup {
+                windows: vec![window.clone()],
+                active: 0,
+            })
+            .collect()
+    }
+
+    /// Name to show in the bar
+    pub fn display_name(&self) -> &str {
+        self.auto_name.as_deref().unwrap_or(&self.name)
+    }
+
     pub fn add_window(&mut self, window: Window) {
+        self.dwindle.split_target = self
+            .focused_window()
+            .map(|focused| self.dialog_root(focused).clone());
         self.windows.push(window);
 
         // Focus the newly added window
@@ -41,7 +248,23 @@ impl Workspace {
 
     pub fn remove_window(&mut self, window: &Window) -> bool {
         if let Some(idx) = self.windows.iter().position(|w| w == window) {
+            self.detach_tab(window);
+            self.dwindle.remove(window);
+            self.tile_weights.retain(|(w, _)| w != window);
+            self.floating_geometries.retain(|(w, _)| w != window);
+            self.floating_stack.retain(|w| w != window);
+            self.always_on_top.retain(|w| w != window);
+            self.floating_windows.retain(|w| w != window);
             self.windows.remove(idx);
+            if self.fullscreen_window.as_ref() == Some(window) {
+                self.fullscreen_window = None;
+            }
+            if self.maximized_window.as_ref() == Some(window) {
+                self.maximized_window = None;
+            }
+            // Its dialogs become windows of their own
+            self.dialogs
+                .retain(|(dialog, parent)| dialog != window && parent != window);
 
             // Adjust focused window index
             if let Some(focused) = self.focused_window_idx {
@@ -60,6 +283,46 @@ impl Workspace {
         }
     }
 
+    pub fn window_kind(&self, window: &Window) -> WindowKind {
+        if self.dialog_parent(window).is_some() {
+            WindowKind::Dialog
+        } else {
+            WindowKind::Normal
+        }
+    }
+
+    pub fn dialog_parent(&self, window: &Window) -> Option<&Window> {
+        self.dialogs
+            .iter()
+            .find(|(dialog, _)| dialog == window)
+            .map(|(_, parent)| parent)
+    }
+
+    /// Dialogs whose parent is `window`, oldest first
+    pub fn dialogs_of(&self, window: &Window) -> Vec<Window> {
+        self.dialogs
+            .iter()
+            .filter(|(_, parent)| parent == window)
+            .map(|(dialog, _)| dialog.clone())
+            .collect()
+    }
+
+    /// The window at the top of `window`'s chain of dialog parents
+    pub fn dialog_root<'a>(&'a self, mut window: &'a Window) -> &'a Window {
+        while let Some(parent) = self.dialog_parent(window) {
+            window = parent;
+        }
+        window
+    }
+
+    /// Make `window` a dialog of `parent`, or a normal window with `None`
+    pub fn set_dialog_parent(&mut self, window: &Window, parent: Option<Window>) {
+        self.dialogs.retain(|(dialog, _)| dialog != window);
+        if let Some(parent) = parent {
+            self.dialogs.push((window.clone(), parent));
+        }
+    }
+
     pub fn focused_window(&self) -> Option<&Window> {
         self.focused_window_idx
             .and_then(|idx| self.windows.get(idx))
@@ -79,6 +342,7 @@ impl Workspace {
         let current = self.focused_window_idx.unwrap_or(0);
         let next = (current + 1) % self.windows.len();
         self.focused_window_idx = Some(next);
+        self.show_focused_tab();
     }
 
     pub fn focus_prev(&mut self) {
@@ -94,11 +358,13 @@ impl Workspace {
             current - 1
         };
         self.focused_window_idx = Some(prev);
+        self.show_focused_tab();
     }
 
     pub fn focus_window(&mut self, window: &Window) -> bool {
         if let Some(idx) = self.windows.iter().position(|w| w == window) {
             self.focused_window_idx = Some(idx);
+            self.show_focused_tab();
             true
         } else {
             false
@@ -112,12 +378,235 @@ impl Workspace {
     pub fn len(&self) -> usize {
         self.windows.len()
     }
+
+    pub fn tab_group(&self, window: &Window) -> Option<&TabGroup> {
+        self.tab_groups
+            .iter()
+            .find(|group| group.windows.contains(window))
+    }
+
+    /// One entry per tile, in layout order. A group takes the place of its
+    /// first window. Dialogs go over their parent instead of in a tile, and
+    /// floating windows over the tiles, except on floating workspaces.
+    pub fn tiles(&self) -> Vec<TabGroup> {
+        let mut tiles: Vec<TabGroup> = Vec::new();
+        let floats = self.layout_mode != LayoutMode::Floating;
+
+        for window in &self.windows {
+            if self.window_kind(window) == WindowKind::Dialog
+                || (floats && self.floating_windows.contains(window))
+            {
+                continue;
+            }
+            match self.tab_group(window) {
+                Some(group) if tiles.contains(group) => {}
+                Some(group) => tiles.push(group.clone()),
+                None => tiles.push(TabGroup {
+                    windows: vec![window.clone()],
+                    active: 0,
+                }),
+            }
+        }
+
+        tiles
+    }
+
+    /// Put `window` at `position` among the windows, or last if there
+    /// aren't that many
+    pub fn move_window_to_position(&mut self, window: &Window, position: usize) {
+        let Some(idx) = self.windows.iter().position(|w| w == window) else {
+            return;
+        };
+        let focused = self.focused_window().cloned();
+        let window = self.windows.remove(idx);
+        self.windows
+            .insert(position.min(self.windows.len()), window);
+        self.focused_window_idx = focused.and_then(|f| self.windows.iter().position(|w| w == &f));
+    }
+
+    /// Exchange the places of tiles `a` and `b`, along with their sizes
+    pub fn swap_tiles(&mut self, a: &TabGroup, b: &TabGroup) {
+        let focused = self.focused_window().cloned();
+        let mut windows: Vec<Window> = self
+            .tiles()
+            .into_iter()
+            .flat_map(|tile| {
+                if &tile == a {
+                    b.windows.clone()
+                } else if &tile == b {
+                    a.windows.clone()
+                } else {
+                    tile.windows
+                }
+            })
+            .collect();
+        // Dialogs aren't in tiles
+        let dialogs: Vec<Window> = self
+            .windows
+            .iter()
+            .filter(|window| !windows.contains(window))
+            .cloned()
+            .collect();
+        windows.extend(dialogs);
+        self.windows = windows;
+        self.focused_window_idx = focused.and_then(|f| self.windows.iter().position(|w| w == &f));
+
+        let (weight_a, weight_b) = (self.tile_weight(a), self.tile_weight(b));
+        self.set_tile_weight(a, weight_b);
+        self.set_tile_weight(b, weight_a);
+        let (geometry_a, geometry_b) = (self.floating_geometry(a), self.floating_geometry(b));
+        self.floating_geometries
+            .retain(|(window, _)| !a.windows.contains(window) && !b.windows.contains(window));
+        if let Some(geometry) = geometry_b {
+            self.set_floating_geometry(a, geometry);
+        }
+        if let Some(geometry) = geometry_a {
+            self.set_floating_geometry(b, geometry);
+        }
+    }
+
+    /// Move the focused window's tile to the front, where master-stack mode
+    /// shows it as the master. A focused dialog promotes its parent.
+    pub fn promote_focused(&mut self) -> bool {
+        let Some(focused) = self.focused_window().cloned() else {
+            return false;
+        };
+        let root = self.dialog_root(&focused).clone();
+        let Some(idx) = self.windows.iter().position(|w| w == &root) else {
+            return false;
+        };
+        if self
+            .tiles()
+            .first()
+            .is_some_and(|tile| tile.windows.contains(&root))
+        {
+            return false;
+        }
+
+        let window = self.windows.remove(idx);
+        self.windows.insert(0, window);
+        self.focused_window_idx = self.windows.iter().position(|w| w == &focused);
+        true
+    }
+
+    /// All tiled windows as the tabs of one group, as tabbed mode shows
+    /// them, with the focused one (or its dialog's parent) active
+    pub fn as_tab_group(&self) -> Option<TabGroup> {
+        let windows: Vec<Window> = self
+            .windows
+            .iter()
+            .filter(|window| {
+                self.window_kind(window) == WindowKind::Normal
+                    && !self.floating_windows.contains(window)
+            })
+            .cloned()
+            .collect();
+        if windows.is_empty() {
+            return None;
+        }
+        let active = self
+            .focused_window()
+            .map(|focused| self.dialog_root(focused))
+            .and_then(|root| windows.iter().position(|w| w == root))
+            .unwrap_or(0);
+        Some(TabGroup { windows, active })
+    }
+
+    /// Merge the focused window into the next (`forward`) or previous tile,
+    /// creating a tab group if that tile is a single window
+    pub fn merge_focused(&mut self, forward: bool) -> bool {
+        let Some(focused) = self.focused_window().cloned() else {
+            return false;
+        };
+
+        let tiles = self.tiles();
+        let Some(pos) = tiles.iter().position(|t| t.windows.contains(&focused)) else {
+            return false;
+        };
+        let target = if forward {
+            pos + 1
+        } else if pos > 0 {
+            pos - 1
+        } else {
+            return false;
+        };
+        let Some(neighbor) = tiles.get(target) else {
+            return false;
+        };
+        let anchor = neighbor.windows[0].clone();
+
+        self.detach_tab(&focused);
+        match self
+            .tab_groups
+            .iter_mut()
+            .find(|group| group.windows.contains(&anchor))
+        {
+            Some(group) => group.windows.push(focused),
+            None => self.tab_groups.push(TabGroup {
+                windows: vec![anchor, focused],
+                active: 0,
+            }),
+        }
+
+        self.show_focused_tab();
+        true
+    }
+
+    /// Take the focused window out of its tab group into a tile of its own
+    pub fn split_focused(&mut self) -> bool {
+        match self.focused_window().cloned() {
+            Some(focused) if self.tab_group(&focused).is_some() => {
+                self.detach_tab(&focused);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn detach_tab(&mut self, window: &Window) {
+        let Some(idx) = self
+            .tab_groups
+            .iter()
+            .position(|group| group.windows.contains(window))
+        else {
+            return;
+        };
+
+        let group = &mut self.tab_groups[idx];
+        group.windows.retain(|w| w != window);
+        // The rest of the group keeps its dwindle tile, which the window
+        // splits if it stays on the workspace
+        if let Some(heir) = group.windows.first() {
+            self.dwindle.replace(window, heir);
+            self.dwindle.split_target = Some(heir.clone());
+        }
+        if group.windows.len() < 2 {
+            self.tab_groups.remove(idx);
+        } else {
+            group.active = group.active.min(group.windows.len() - 1);
+        }
+    }
+
+    /// A focused tab is always the one shown
+    fn show_focused_tab(&mut self) {
+        let Some(focused) = self.focused_window().cloned() else {
+            return;
+        };
+
+        for group in &mut self.tab_groups {
+            if let Some(idx) = group.windows.iter().position(|w| w == &focused) {
+                group.active = idx;
+            }
+        }
+    }
 }
 
 pub struct WorkspaceManager {
     workspaces: HashMap<u32, Workspace>,
     active_workspace_id: u32,
     workspace_order: Vec<u32>,
+    /// Switches to a different workspace since startup
+    switches: u64,
 }
 
 impl WorkspaceManager {
@@ -126,6 +615,7 @@ impl WorkspaceManager {
             workspaces: HashMap::new(),
             active_workspace_id: 1,
             workspace_order: Vec::new(),
+            switches: 0,
         };
 
         // Create default workspaces (1-9)
@@ -168,9 +658,16 @@ impl WorkspaceManager {
         self.active_workspace_id
     }
 
+    pub fn switch_count(&self) -> u64 {
+        self.switches
+    }
+
     pub fn switch_to_workspace(&mut self, id: u32) -> bool {
         if self.workspaces.contains_key(&id) {
             println!("Switching to workspace {}", id);
+            if id != self.active_workspace_id {
+                self.switches += 1;
+            }
             self.active_workspace_id = id;
             true
         } else {
@@ -191,18 +688,32 @@ impl WorkspaceManager {
         }
 
         if let Some(source_id) = source_workspace_id {
-            // Remove from source workspace
+            // Remove from source workspace, dialogs come along
+            let mut dialogs = Vec::new();
+            let mut on_top = false;
+            let mut floating = false;
             if let Some(source_ws) = self.workspaces.get_mut(&source_id) {
+                dialogs = source_ws.dialogs_of(&window);
+                on_top = source_ws.always_on_top.contains(&window);
+                floating = source_ws.floating_windows.contains(&window);
                 source_ws.remove_window(&window);
             }
 
             // Add to target workspace
             if let Some(target_ws) = self.workspaces.get_mut(&target_workspace_id) {
-                target_ws.add_window(window);
+                target_ws.add_window(window.clone());
+                target_ws.set_on_top(&window, on_top);
+                target_ws.set_floating(&window, floating);
                 println!(
                     "Moved window from workspace {} to {}",
                     source_id, target_workspace_id
                 );
+                for dialog in dialogs {
+                    self.move_window_to_workspace(dialog.clone(), target_workspace_id);
+                    if let Some(target_ws) = self.workspaces.get_mut(&target_workspace_id) {
+                        target_ws.set_dialog_parent(&dialog, Some(window.clone()));
+                    }
+                }
                 return true;
             }
         }
@@ -301,20 +812,6 @@ impl WorkspaceManager {
 
         self.switch_to_workspace(prev_id);
     }
-
-    pub fn toggle_floating(&mut self) {
-        let active_workspace = self.active_workspace_mut();
-        active_workspace.layout_mode = match active_workspace.layout_mode {
-            LayoutMode::Tiling => LayoutMode::Floating,
-            LayoutMode::Floating => LayoutMode::Tiling,
-            LayoutMode::Monocle => LayoutMode::Tiling,
-        };
-
-        println!(
-            "Switched workspace {} to {:?} mode",
-            active_workspace.id, active_workspace.layout_mode
-        );
-    }
 }
 
 impl Default for WorkspaceManager {
@@ -329,6 +826,9 @@ impl From<&str> for LayoutMode {
             "tiling" => LayoutMode::Tiling,
             "floating" => LayoutMode::Floating,
             "monocle" => LayoutMode::Monocle,
+            "master-stack" | "master_stack" => LayoutMode::MasterStack,
+            "dwindle" | "bsp" => LayoutMode::Dwindle,
+            "tabbed" => LayoutMode::Tabbed,
             _ => LayoutMode::Tiling,
         }
     }
@@ -0,0 +1,110 @@
+// Workspace auto-naming
+//
+// With `<workspaces auto-name="true">` in desktop.xml, every workspace is
+// named after the app with the most windows on it, e.g. "3: firefox". A JS
+// function registered with `setWorkspaceNamer(fn)` can replace that: it gets
+// `{ id, name, apps, focused }` and returns the new name, or nothing to keep
+// the default.
+
+use serde_json::json;
+
+use crate::compositor::{window_app_id, WebWMCompositor};
+
+/// App with the most windows; ties go to the one opened first
+pub fn dominant_app(app_ids: &[String]) -> Option<&str> {
+    let mut best: Option<(&str, usize)> = None;
+    for app_id in app_ids {
+        let count = app_ids.iter().filter(|other| *other == app_id).count();
+        if best.is_none_or(|(_, best_count)| count > best_count) {
+            best = Some((app_id, count));
+        }
+    }
+    best.map(|(app_id, _)| app_id)
+}
+
+/// "3: firefox", or None for an empty workspace so it keeps its own name
+pub fn default_name(id: u32, app_ids: &[String]) -> Option<String> {
+    dominant_app(app_ids).map(|app_id| format!("{}: {}", id, app_id))
+}
+
+impl WebWMCompositor {
+    pub fn auto_name_enabled(&self) -> bool {
+        self.config
+            .desktop
+            .as_ref()
+            .is_some_and(|desktop| desktop.workspace_auto_name)
+    }
+
+    /// Recompute auto names; call whenever windows come, go or move
+    pub fn update_workspace_names(&mut self) {
+        if !self.auto_name_enabled() {
+            return;
+        }
+
+        let names: Vec<(u32, Option<String>)> = self
+            .workspace_manager
+            .all_workspaces()
+            .into_iter()
+            .map(|workspace| {
+                let app_ids: Vec<String> = workspace
+                    .windows
+                    .iter()
+                    .map(window_app_id)
+                    .filter(|app_id| !app_id.is_empty())
+                    .collect();
+
+                let custom = self.js_runtime.as_ref().and_then(|js_runtime| {
+                    let info = json!({
+                        "id": workspace.id,
+                        "name": workspace.name,
+                        "apps": app_ids,
+                        "focused": workspace.focused_window().map(window_app_id),
+                    });
+                    js_runtime.workspace_name(&info).unwrap_or_else(|e| {
+                        eprintln!("{}", e);
+                        None
+                    })
+                });
+
+                (
+                    workspace.id,
+                    custom.or_else(|| default_name(workspace.id, &app_ids)),
+                )
+            })
+            .collect();
+
+        for (id, name) in names {
+            if let Some(workspace) = self.workspace_manager.get_workspace_mut(id) {
+                workspace.auto_name = name;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(app_ids: &[&str]) -> Vec<String> {
+        app_ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_dominant_app() {
+        assert_eq!(dominant_app(&[]), None);
+        assert_eq!(
+            dominant_app(&ids(&["kitty", "firefox", "firefox"])),
+            Some("firefox")
+        );
+        assert_eq!(dominant_app(&ids(&["kitty", "firefox"])), Some("kitty"));
+    }
+
+    #[test]
+    fn test_default_name() {
+        assert_eq!(
+            default_name(3, &ids(&["firefox"])).as_deref(),
+            Some("3: firefox")
+        );
+        assert_eq!(default_name(1, &[]), None);
+    }
+}
@@ -0,0 +1,21 @@
+// Cross-client dialog parenting
+//
+// zxdg_exporter_v2 and zxdg_importer_v2 let one client parent its dialog
+// to another client's window: an application exports its toplevel and
+// hands the handle to xdg-desktop-portal, whose file chooser imports it and
+// becomes a transient child of the application's window. Smithay sets the
+// toplevel's parent as if the dialog had called xdg_toplevel.set_parent,
+// so it is handled like any other dialog (see dialogs.rs).
+
+use smithay::delegate_xdg_foreign;
+use smithay::wayland::xdg_foreign::{XdgForeignHandler, XdgForeignState};
+
+use crate::compositor::WebWMCompositor;
+
+impl XdgForeignHandler for WebWMCompositor {
+    fn xdg_foreign_state(&mut self) -> &mut XdgForeignState {
+        &mut self.xdg_foreign_state
+    }
+}
+
+delegate_xdg_foreign!(WebWMCompositor);
@@ -1,20 +1,25 @@
 use cssparser::Color as CssColor;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StyleSheet {
     pub rules: Vec<StyleRule>,
     pub variables: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StyleRule {
     pub selector: String,
     pub properties: HashMap<String, StyleValue>,
+    /// Properties set with `var()`, to the variable's name
+    #[serde(default)]
+    pub variable_refs: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum StyleValue {
     Color(Color),
     Length(f32, LengthUnit),
@@ -23,7 +28,7 @@ pub enum StyleValue {
     Keyword(String),
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -31,7 +36,16 @@ pub struct Color {
     pub a: f32,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// One track of `grid-template-columns` or `grid-template-rows`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridTrack {
+    Px(f32),
+    Percent(f32),
+    /// Share of the space the other tracks leave
+    Fr(f32),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
 pub enum LengthUnit {
     Px,
     Percent,
@@ -78,25 +92,37 @@ pub fn parse_css(css_content: &str) -> Result<StyleSheet, String> {
         }
 
         // Parse regular CSS rules
-        if line.contains("{") {
-            let selector = line.trim_end_matches('{').trim().to_string();
-            i += 1;
+        if let Some((selector, rest)) = line.split_once('{') {
+            let selector = normalize_selector(selector.trim());
 
-            let mut properties = HashMap::new();
-            while i < lines.len() {
-                let prop_line = lines[i].trim();
-                if prop_line == "}" {
-                    break;
+            // `selector { a: b; c: d }` on one line, or one property per line
+            let prop_lines: Vec<&str> = if let Some((body, _)) = rest.split_once('}') {
+                body.split(';').collect()
+            } else {
+                i += 1;
+                let start = i;
+                while i < lines.len() && lines[i].trim() != "}" {
+                    i += 1;
                 }
+                lines[start..i].to_vec()
+            };
+
+            let mut properties = HashMap::new();
+            let mut variable_refs = HashMap::new();
+            for prop_line in prop_lines {
+                let prop_line = prop_line.trim();
                 if let Some((prop, value)) = parse_css_property(prop_line, &stylesheet.variables) {
+                    if let Some(variable) = variable_ref(prop_line) {
+                        variable_refs.insert(prop.clone(), variable);
+                    }
                     properties.insert(prop, value);
                 }
-                i += 1;
             }
 
             stylesheet.rules.push(StyleRule {
                 selector,
                 properties,
+                variable_refs,
             });
         }
 
@@ -106,6 +132,28 @@ pub fn parse_css(css_content: &str) -> Result<StyleSheet, String> {
     Ok(stylesheet)
 }
 
+/// Quote attribute values the same way, so `workspace[name=media]` and
+/// `workspace[name='media']` both become `workspace[name="media"]`
+fn normalize_selector(selector: &str) -> String {
+    let Some((element, rest)) = selector.split_once('[') else {
+        return selector.to_string();
+    };
+    let Some((attribute, after)) = rest.split_once(']') else {
+        return selector.to_string();
+    };
+    let Some((name, value)) = attribute.split_once('=') else {
+        return selector.to_string();
+    };
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+    format!(
+        "{}[{}=\"{}\"]{}",
+        element.trim_end(),
+        name.trim(),
+        value,
+        after
+    )
+}
+
 fn parse_css_variable(line: &str) -> Option<(String, String)> {
     if !line.contains(":") {
         return None;
@@ -122,6 +170,18 @@ fn parse_css_variable(line: &str) -> Option<(String, String)> {
     Some((key, value))
 }
 
+/// The variable a property line takes its whole value from, if any
+fn variable_ref(line: &str) -> Option<String> {
+    let (_, value) = line.split_once(':')?;
+    let name = value
+        .trim()
+        .trim_end_matches(';')
+        .trim()
+        .strip_prefix("var(")?
+        .strip_suffix(')')?;
+    Some(name.trim().to_string())
+}
+
 fn parse_css_property(
     line: &str,
     variables: &HashMap<String, String>,
@@ -195,6 +255,70 @@ fn parse_css_value(value_str: &str) -> Option<StyleValue> {
     Some(StyleValue::String(value.to_string()))
 }
 
+/// A track list such as `200px 1fr 2fr` or `repeat(3, 1fr)`. `auto`
+/// counts as `1fr`.
+pub fn parse_grid_tracks(value: &str) -> Option<Vec<GridTrack>> {
+    let mut tracks = Vec::new();
+    let mut rest = value.trim();
+
+    while !rest.is_empty() {
+        if let Some(repeat) = rest.strip_prefix("repeat(") {
+            let (inner, after) = repeat.split_once(')')?;
+            let (count, pattern) = inner.split_once(',')?;
+            let count: usize = count.trim().parse().ok()?;
+            let pattern = parse_grid_tracks(pattern)?;
+            for _ in 0..count {
+                tracks.extend_from_slice(&pattern);
+            }
+            rest = after.trim_start();
+        } else {
+            let (token, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            tracks.push(parse_grid_track(token)?);
+            rest = after.trim_start();
+        }
+    }
+
+    (!tracks.is_empty()).then_some(tracks)
+}
+
+fn parse_grid_track(token: &str) -> Option<GridTrack> {
+    if token == "auto" {
+        return Some(GridTrack::Fr(1.0));
+    }
+    if let Some(fr) = token.strip_suffix("fr") {
+        return fr
+            .parse()
+            .ok()
+            .filter(|fr: &f32| *fr > 0.0)
+            .map(GridTrack::Fr);
+    }
+    if let Some(percent) = token.strip_suffix('%') {
+        return percent.parse().ok().map(GridTrack::Percent);
+    }
+    token
+        .strip_suffix("px")
+        .unwrap_or(token)
+        .parse()
+        .ok()
+        .map(GridTrack::Px)
+}
+
+/// Rows of cell names from `grid-template-areas: "main side" "main log"`;
+/// `.` is a cell without a name. Every row needs as many cells.
+pub fn parse_grid_areas(value: &str) -> Option<Vec<Vec<String>>> {
+    let value = value.replace('\'', "\"");
+    let rows: Vec<Vec<String>> = value
+        .split('"')
+        .skip(1)
+        .step_by(2)
+        .map(|row| row.split_whitespace().map(str::to_string).collect())
+        .filter(|row: &Vec<String>| !row.is_empty())
+        .collect();
+
+    let columns = rows.first()?.len();
+    rows.iter().all(|row| row.len() == columns).then_some(rows)
+}
+
 fn parse_color(color_str: &str) -> Option<Color> {
     let color = color_str.trim();
 
@@ -303,6 +427,24 @@ fn parse_named_color(name: &str) -> Option<Color> {
 }
 
 impl StyleSheet {
+    /// Change a `:root` variable and every property set from it
+    pub fn set_variable(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if !self.variables.contains_key(name) {
+            return Err(format!("No variable named '{}'", name));
+        }
+        let parsed = parse_css_value(value).ok_or_else(|| format!("Invalid value '{}'", value))?;
+
+        self.variables.insert(name.to_string(), value.to_string());
+        for rule in &mut self.rules {
+            for (property, variable) in &rule.variable_refs {
+                if variable == name {
+                    rule.properties.insert(property.clone(), parsed.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_styles_for_selector(&self, selector: &str) -> HashMap<String, StyleValue> {
         let mut styles = HashMap::new();
 
@@ -348,15 +490,74 @@ impl StyleSheet {
         }
     }
 
+    /// First time value in a property, e.g. 0.5s in
+    /// `transition: border-color 0.5s ease-in-out`
+    pub fn get_duration(&self, selector: &str, property: &str) -> Option<Duration> {
+        let styles = self.get_styles_for_selector(selector);
+        match styles.get(property)? {
+            StyleValue::String(s) | StyleValue::Keyword(s) => {
+                s.split_whitespace().find_map(parse_time)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get_string(&self, selector: &str, property: &str) -> Option<String> {
+        let styles = self.get_styles_for_selector(selector);
+        match styles.get(property)? {
+            StyleValue::String(s) | StyleValue::Keyword(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    /// A property's value as written, whatever it was parsed as, for
+    /// values like grid tracks that mix lengths and keywords
+    pub fn get_text(&self, selector: &str, property: &str) -> Option<String> {
+        let styles = self.get_styles_for_selector(selector);
+        Some(match styles.get(property)? {
+            StyleValue::Length(l, unit) => {
+                let suffix = match unit {
+                    LengthUnit::Px => "px",
+                    LengthUnit::Percent => "%",
+                    LengthUnit::Em => "em",
+                    LengthUnit::Rem => "rem",
+                };
+                format!("{}{}", l, suffix)
+            }
+            StyleValue::Number(n) => n.to_string(),
+            StyleValue::String(s) | StyleValue::Keyword(s) => s.clone(),
+            StyleValue::Color(c) => c.to_hex(),
+        })
+    }
+
+    pub fn get_number(&self, selector: &str, property: &str) -> Option<f32> {
+        let styles = self.get_styles_for_selector(selector);
+        match styles.get(property)? {
+            StyleValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
     pub fn get_length(&self, selector: &str, property: &str) -> Option<f32> {
         let styles = self.get_styles_for_selector(selector);
         match styles.get(property)? {
             StyleValue::Length(l, LengthUnit::Px) => Some(*l),
+            // Unitless zero, as in `gap: 0`
+            StyleValue::Number(n) if *n == 0.0 => Some(0.0),
             _ => None,
         }
     }
 }
 
+fn parse_time(token: &str) -> Option<Duration> {
+    let seconds = match token.strip_suffix("ms") {
+        Some(ms) => ms.parse::<f32>().ok()? / 1000.0,
+        None => token.strip_suffix('s')?.parse::<f32>().ok()?,
+    };
+
+    (seconds >= 0.0).then(|| Duration::from_secs_f32(seconds))
+}
+
 impl Color {
     pub fn to_rgba_f32(&self) -> [f32; 4] {
         [
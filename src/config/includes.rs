@@ -0,0 +1,372 @@
+// Config includes
+//
+// Large configs can be split across files. `<include path="bars.xml"/>`
+// anywhere in desktop.xml is replaced by what's inside the included file's
+// root element, so bars.xml would be a `<desktop>` holding `<bar>` elements,
+// and an include in `<workspaces>` could pull in a `<workspaces>` of its own.
+// In config.js, a line `@import "keys.js";` is replaced by that file. Paths
+// are relative to the file they're in, and included files can include
+// others. A file including itself, directly or through others, is an
+// error; a JS file imported a second time from elsewhere is skipped, as its
+// declarations are already there.
+//
+// The files are joined into one text before parsing, which remembers where
+// each part came from, so errors still name the file and line they're in.
+
+use roxmltree::Document;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::migrate::line_at;
+use super::{read_config_file, ConfigError};
+
+/// A config file with its includes filled in
+#[derive(Debug)]
+pub struct Expanded {
+    pub text: String,
+    /// The file included from, where lines outside any part are from
+    root: PathBuf,
+    /// Each part of `text`: the line it starts on there, its file, and the
+    /// line it starts on in the file
+    parts: Vec<(u32, PathBuf, u32)>,
+    lines: u32,
+    /// Every file read, the one included from first
+    pub files: Vec<PathBuf>,
+}
+
+impl Expanded {
+    fn new(root: &Path) -> Self {
+        Self {
+            text: String::new(),
+            root: root.to_path_buf(),
+            parts: Vec::new(),
+            lines: 1,
+            files: vec![root.to_path_buf()],
+        }
+    }
+
+    /// Append `text`, which starts on `line` of `file`
+    fn push(&mut self, text: &str, file: &Path, line: u32) {
+        if text.is_empty() {
+            return;
+        }
+        // The rest of a line something is already on stays that part's,
+        // unless it's only indentation so far
+        let line_start = self.text.rfind('\n').map_or(0, |i| i + 1);
+        if self.text[line_start..].trim().is_empty() {
+            self.parts.push((self.lines, file.to_path_buf(), line));
+        } else if text.contains('\n') {
+            self.parts
+                .push((self.lines + 1, file.to_path_buf(), line + 1));
+        }
+        self.text.push_str(text);
+        self.lines += text.matches('\n').count() as u32;
+    }
+
+    /// The file and line that `line` of the joined text is from
+    pub fn locate(&self, line: u32) -> (&Path, u32) {
+        self.parts
+            .iter()
+            .rev()
+            .find(|(start, _, _)| *start <= line)
+            .map(|(start, file, file_line)| (file.as_path(), file_line + line - start))
+            .unwrap_or((self.root.as_path(), line))
+    }
+
+    /// An error at `line` of the joined text, in the file that line is from
+    pub fn error(&self, line: Option<u32>, message: impl fmt::Display) -> ConfigError {
+        match line {
+            Some(line) => {
+                let (file, line) = self.locate(line);
+                ConfigError::new(file, Some(line), message)
+            }
+            None => ConfigError::new(&self.root, None, message),
+        }
+    }
+}
+
+/// Identity of a file, to tell when the same one comes up again
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// `relative` from the directory `file` is in
+fn resolve(file: &Path, relative: &str) -> PathBuf {
+    file.parent().unwrap_or(Path::new(".")).join(relative)
+}
+
+/// Error for `file` including one of the files it's being included from
+fn cycle_error(file: &Path, line: u32, stack: &[PathBuf], included: &Path) -> ConfigError {
+    let name = |path: &Path| {
+        path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+    };
+    let chain: Vec<String> = stack
+        .iter()
+        .map(|path| name(path.as_path()))
+        .chain(std::iter::once(name(included)))
+        .collect();
+    ConfigError::new(
+        file,
+        Some(line),
+        format!("Include cycle: {}", chain.join(" → ")),
+    )
+}
+
+/// desktop.xml at `path` with its `<include>`s filled in
+pub fn expand_xml(path: &Path) -> Result<Expanded, ConfigError> {
+    let xml = read_config_file(path)?;
+    let mut expanded = Expanded::new(path);
+    splice_xml(path, &xml, false, &mut vec![canonical(path)], &mut expanded)?;
+    Ok(expanded)
+}
+
+/// Append `xml` from `path` to `out`, only what's inside its root element
+/// when it's `included`, filling in its includes
+fn splice_xml(
+    path: &Path,
+    xml: &str,
+    included: bool,
+    stack: &mut Vec<PathBuf>,
+    out: &mut Expanded,
+) -> Result<(), ConfigError> {
+    let doc = Document::parse(xml).map_err(|e| {
+        ConfigError::new(path, Some(e.pos().row), format!("XML parse error: {}", e))
+    })?;
+    let root = doc.root_element();
+    // Without the whitespace around the root's children, which the
+    // including file has already
+    let content = || {
+        root.children()
+            .filter(|node| !node.is_text() || !node.text().unwrap_or_default().trim().is_empty())
+    };
+    let range = match (included, content().next(), content().last()) {
+        (false, _, _) => 0..xml.len(),
+        (true, Some(first), Some(last)) => first.range().start..last.range().end,
+        (true, _, _) => return Ok(()),
+    };
+
+    let includes = root.descendants().filter(|node| {
+        node.has_tag_name("include")
+            && *node != root
+            && !node.ancestors().skip(1).any(|a| a.has_tag_name("include"))
+    });
+    let mut position = range.start;
+    for include in includes {
+        let line = line_at(xml, include.range().start);
+        let Some(relative) = include.attribute("path") else {
+            return Err(ConfigError::new(path, Some(line), "<include> needs a path"));
+        };
+        let file = resolve(path, relative);
+        let identity = canonical(&file);
+        if stack.contains(&identity) {
+            return Err(cycle_error(path, line, stack, &file));
+        }
+        let text = fs::read_to_string(&file).map_err(|e| {
+            ConfigError::new(
+                path,
+                Some(line),
+                format!("Can't include {}: {}", relative, e),
+            )
+        })?;
+        out.files.push(file.clone());
+
+        out.push(
+            &xml[position..include.range().start],
+            path,
+            line_at(xml, position),
+        );
+        stack.push(identity);
+        splice_xml(&file, &text, true, stack, out)?;
+        stack.pop();
+        position = include.range().end;
+    }
+    out.push(&xml[position..range.end], path, line_at(xml, position));
+    Ok(())
+}
+
+/// The file an `@import "file.js";` line imports
+fn import_path(line: &str) -> Option<Result<&str, String>> {
+    let rest = line.trim().strip_prefix("@import")?;
+    let rest = rest.trim().trim_end_matches(';').trim_end();
+    let quoted = ['"', '\'']
+        .iter()
+        .find_map(|&quote| rest.strip_prefix(quote)?.strip_suffix(quote));
+    Some(quoted.ok_or_else(|| format!("@import needs a quoted path, not {}", rest)))
+}
+
+/// config.js at `path` with its `@import`s filled in
+pub fn expand_js(path: &Path) -> Result<Expanded, ConfigError> {
+    let js = read_config_file(path)?;
+    let mut expanded = Expanded::new(path);
+    let mut stack = vec![canonical(path)];
+    let mut imported = stack.clone();
+    splice_js(path, &js, &mut stack, &mut imported, &mut expanded)?;
+    Ok(expanded)
+}
+
+fn splice_js(
+    path: &Path,
+    js: &str,
+    stack: &mut Vec<PathBuf>,
+    imported: &mut Vec<PathBuf>,
+    out: &mut Expanded,
+) -> Result<(), ConfigError> {
+    // Lines since the last import, and the line they start on
+    let mut pending = String::new();
+    let mut pending_line = 1;
+    for (i, line) in js.split_inclusive('\n').enumerate() {
+        let line_number = i as u32 + 1;
+        let Some(relative) = import_path(line) else {
+            pending.push_str(line);
+            continue;
+        };
+        let relative = relative.map_err(|e| ConfigError::new(path, Some(line_number), e))?;
+        out.push(&pending, path, pending_line);
+        pending.clear();
+        pending_line = line_number + 1;
+
+        let file = resolve(path, relative);
+        let identity = canonical(&file);
+        if stack.contains(&identity) {
+            return Err(cycle_error(path, line_number, stack, &file));
+        }
+        if imported.contains(&identity) {
+            continue;
+        }
+        let mut text = fs::read_to_string(&file).map_err(|e| {
+            ConfigError::new(
+                path,
+                Some(line_number),
+                format!("Can't import {}: {}", relative, e),
+            )
+        })?;
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        out.files.push(file.clone());
+
+        imported.push(identity.clone());
+        stack.push(identity);
+        splice_js(&file, &text, stack, imported, out)?;
+        stack.pop();
+    }
+    out.push(&pending, path, pending_line);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("webwm-{}-{}", name, std::process::id()));
+        for (file, contents) in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_expand_xml() {
+        let dir = config_dir(
+            "xml-includes",
+            &[
+                (
+                    "desktop.xml",
+                    "<desktop>\n  <include path=\"parts/bars.xml\" />\n  <workspaces>\n    <include path=\"parts/workspaces.xml\"/>\n  </workspaces>\n</desktop>\n",
+                ),
+                (
+                    "parts/bars.xml",
+                    "<?xml version=\"1.0\"?>\n<desktop>\n  <bar id=\"top\" />\n  <include path=\"more/bar.xml\" />\n</desktop>\n",
+                ),
+                ("parts/more/bar.xml", "<desktop><bar id=\"bottom\" /></desktop>"),
+                (
+                    "parts/workspaces.xml",
+                    "<workspaces>\n<workspace id=\"1\" />\n</workspaces>\n",
+                ),
+            ],
+        );
+
+        let expanded = expand_xml(&dir.join("desktop.xml")).unwrap();
+        assert_eq!(
+            expanded.text,
+            "<desktop>\n  <bar id=\"top\" />\n  <bar id=\"bottom\" />\n  <workspaces>\n    <workspace id=\"1\" />\n  </workspaces>\n</desktop>\n"
+        );
+        // Lines lead back to the files they're from
+        let (file, line) = expanded.locate(2);
+        assert_eq!((file, line), (dir.join("parts/bars.xml").as_path(), 3));
+        assert_eq!(expanded.locate(4), (dir.join("desktop.xml").as_path(), 3));
+        assert_eq!(
+            expanded.locate(5),
+            (dir.join("parts/workspaces.xml").as_path(), 2)
+        );
+        assert_eq!(expanded.files.len(), 4);
+        assert!(expanded.files.contains(&dir.join("parts/more/bar.xml")));
+
+        // A file including itself back is an error where it does
+        fs::write(
+            dir.join("parts/more/bar.xml"),
+            "<desktop><include path=\"../bars.xml\" /></desktop>",
+        )
+        .unwrap();
+        let error = expand_xml(&dir.join("desktop.xml")).unwrap_err();
+        assert_eq!(error.file, dir.join("parts/more/bar.xml"));
+        assert_eq!(
+            error.message,
+            "Include cycle: desktop.xml → bars.xml → bar.xml → bars.xml"
+        );
+
+        fs::write(
+            dir.join("parts/bars.xml"),
+            "<desktop>\n  <include />\n</desktop>",
+        )
+        .unwrap();
+        let error = expand_xml(&dir.join("desktop.xml")).unwrap_err();
+        assert_eq!(error.line, Some(2));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_expand_js() {
+        let dir = config_dir(
+            "js-imports",
+            &[
+                (
+                    "config.js",
+                    "const MOD = 'Super';\n@import \"keys.js\";\n@import 'lib/rules.js'\nkeybind('Super+q', () => wm.close());\n",
+                ),
+                ("keys.js", "@import \"lib/rules.js\";\nkeybind(`${MOD}+w`, () => {});"),
+                ("lib/rules.js", "windowRule({ appId: 'a', floating: true });\n"),
+            ],
+        );
+
+        // Imported once, where it's first imported
+        let expanded = expand_js(&dir.join("config.js")).unwrap();
+        assert_eq!(
+            expanded.text,
+            "const MOD = 'Super';\nwindowRule({ appId: 'a', floating: true });\nkeybind(`${MOD}+w`, () => {});\nkeybind('Super+q', () => wm.close());\n"
+        );
+        assert_eq!(expanded.locate(2), (dir.join("lib/rules.js").as_path(), 1));
+        assert_eq!(expanded.locate(3), (dir.join("keys.js").as_path(), 2));
+        assert_eq!(expanded.locate(4), (dir.join("config.js").as_path(), 4));
+        let error = expanded.error(Some(3), "oops");
+        assert_eq!((error.file, error.line), (dir.join("keys.js"), Some(2)));
+
+        fs::write(dir.join("lib/rules.js"), "\n@import \"../config.js\";\n").unwrap();
+        let error = expand_js(&dir.join("config.js")).unwrap_err();
+        assert_eq!(error.file, dir.join("lib/rules.js"));
+        assert_eq!(error.line, Some(2));
+
+        fs::write(dir.join("keys.js"), "@import keys;\n").unwrap();
+        assert!(expand_js(&dir.join("config.js")).is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,28 +1,184 @@
 use rquickjs::Ctx;
 use rquickjs::{Context, Function, Object, Runtime, Value};
+use serde::Deserialize;
 use std::collections::HashMap;
+use std::fmt;
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{Action, WindowRule};
+use crate::watchdog::{Watchdog, JS_STAGE};
 
 pub struct JSRuntime {
     runtime: Runtime,
     context: Context,
     keybindings: Arc<Mutex<Vec<JSKeybinding>>>,
     window_handlers: Arc<Mutex<Vec<JSWindowHandler>>>,
-    startup_handlers: Arc<Mutex<Vec<String>>>,
     callback_functions: Arc<Mutex<HashMap<String, String>>>, // Store actual callback code
+    /// Marks JS as running
+    watchdog: Watchdog,
+    /// How long one callback may run before it's stopped
+    time_limit: Option<Duration>,
+    deadline: Arc<Mutex<Deadline>>,
+    /// Set by `wm.reload()`, for the compositor to pick up
+    reload_requested: Arc<AtomicBool>,
+    /// What a keybinding's callback asked for through `wm`, collected
+    /// while it runs
+    binding_actions: Arc<Mutex<Option<Vec<Action>>>>,
 }
 
 unsafe impl Send for JSRuntime {}
 
+/// `<js time-limit>` when `<performance>` doesn't set one
+pub const DEFAULT_TIME_LIMIT_MS: u32 = 2000;
+
+/// When the running callback has to be done by, checked by QuickJS's
+/// interrupt handler
+#[derive(Debug, Default)]
+struct Deadline {
+    at: Option<Instant>,
+    /// Set once the handler stopped the callback
+    hit: bool,
+}
+
+/// A script that threw, or was stopped for running too long
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptError {
+    pub message: String,
+    /// Line of the script it came from, when QuickJS knows it
+    pub line: Option<u32>,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} (line {})", self.message, line),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// The exception behind `error`, taken from the context
+fn script_error(ctx: &Ctx, error: rquickjs::Error) -> ScriptError {
+    if !matches!(error, rquickjs::Error::Exception) {
+        return ScriptError {
+            message: format!("{:?}", error),
+            line: None,
+        };
+    }
+    let thrown = ctx.catch();
+    let Some(exception) = thrown.as_object() else {
+        // Something like `throw 'oops'`
+        let message = thrown
+            .as_string()
+            .and_then(|s| s.to_string().ok())
+            .unwrap_or_else(|| format!("{:?}", thrown));
+        return ScriptError {
+            message,
+            line: None,
+        };
+    };
+    ScriptError {
+        message: exception
+            .get::<_, String>("message")
+            .unwrap_or_else(|_| "Unknown exception".to_string()),
+        line: exception
+            .get::<_, String>("stack")
+            .ok()
+            .and_then(|stack| stack_line(&stack)),
+    }
+}
+
+/// Line of the innermost frame in a QuickJS stack trace, such as
+/// `at <eval> (eval_script:3:1)`
+fn stack_line(stack: &str) -> Option<u32> {
+    let (_, rest) = stack.split_once("eval_script:")?;
+    rest.split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Tell the user a script was stopped; WebWM leaves showing it to the
+/// notification daemon
+fn notify_stopped(message: &str) {
+    let spawned = Command::new("notify-send")
+        .args(["--urgency=critical", "--app-name=WebWM", "Script stopped"])
+        .arg(message)
+        .spawn();
+    if let Err(e) = spawned {
+        eprintln!("⚠️  Failed to run notify-send: {}", e);
+    }
+}
+
+/// State file backing the JS `storage` object
+const STORAGE_FILE: &str = "storage.json";
+
+fn save_storage(values: &HashMap<String, String>) {
+    let contents = serde_json::to_string_pretty(values).unwrap_or_default();
+    if let Err(e) = crate::storage::write_state(STORAGE_FILE, &contents) {
+        eprintln!("⚠️  Failed to save JS storage: {}", e);
+    }
+}
+
+/// Collect `action` if a keybinding's callback is running. `wm` calls
+/// from anywhere else, like event handlers, do nothing.
+fn queue_action(actions: &Mutex<Option<Vec<Action>>>, action: Action) {
+    if let Ok(mut actions) = actions.lock() {
+        if let Some(ref mut actions) = *actions {
+            actions.push(action);
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct JSKeybinding {
     pub combo: String,
     pub modifiers: Vec<String>,
     pub key: String,
+    /// Keys after the first for a chord
+    pub sequence: Vec<String>,
     pub callback_name: String, // Name of the callback function
 }
 
+/// A rule from `windowRule({...})`, named like the desktop.xml attributes
+/// in camelCase
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct JSWindowRule {
+    app_id: Option<String>,
+    workspace: Option<u32>,
+    floating: Option<bool>,
+    class: Option<String>,
+    fullscreen: Option<String>,
+    content_type: Option<String>,
+    animations: Option<bool>,
+    direct_scanout: Option<bool>,
+    allow_tearing: Option<bool>,
+    confirm_close: Option<bool>,
+    grid_area: Option<String>,
+}
+
+impl From<JSWindowRule> for WindowRule {
+    fn from(rule: JSWindowRule) -> Self {
+        WindowRule {
+            app_id: rule.app_id.unwrap_or_default(),
+            workspace: rule.workspace,
+            floating: rule.floating,
+            css_class: rule.class,
+            fullscreen: rule.fullscreen,
+            content_type: rule.content_type,
+            animations: rule.animations,
+            direct_scanout: rule.direct_scanout,
+            allow_tearing: rule.allow_tearing,
+            confirm_close: rule.confirm_close,
+            grid_area: rule.grid_area,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct JSWindowHandler {
     pub event: WindowEvent,
@@ -49,16 +205,85 @@ impl JSRuntime {
 
         let keybindings = Arc::new(Mutex::new(Vec::new()));
         let window_handlers = Arc::new(Mutex::new(Vec::new()));
-        let startup_handlers = Arc::new(Mutex::new(Vec::new()));
         let callback_functions = Arc::new(Mutex::new(HashMap::new()));
 
-        Ok(Self {
+        let js_runtime = Self {
             runtime,
             context,
             keybindings,
             window_handlers,
-            startup_handlers,
             callback_functions,
+            watchdog: Watchdog::default(),
+            time_limit: Some(Duration::from_millis(DEFAULT_TIME_LIMIT_MS as u64)),
+            deadline: Arc::new(Mutex::new(Deadline::default())),
+            reload_requested: Arc::new(AtomicBool::new(false)),
+            binding_actions: Arc::new(Mutex::new(None)),
+        };
+
+        // QuickJS asks every so often whether to stop the running script
+        let deadline = js_runtime.deadline.clone();
+        js_runtime
+            .runtime
+            .set_interrupt_handler(Some(Box::new(move || {
+                let Ok(mut deadline) = deadline.lock() else {
+                    return false;
+                };
+                if deadline.at.is_some_and(|at| Instant::now() >= at) {
+                    deadline.hit = true;
+                }
+                deadline.hit
+            })));
+        Ok(js_runtime)
+    }
+
+    /// Report JS callbacks to `watchdog`
+    pub fn set_watchdog(&mut self, watchdog: Watchdog) {
+        self.watchdog = watchdog;
+    }
+
+    /// Stop callbacks that run longer than `limit`, or never with None
+    pub fn set_time_limit(&mut self, limit: Option<Duration>) {
+        self.time_limit = limit;
+    }
+
+    /// Whether a script called `wm.reload()` since the last time
+    pub fn take_reload_request(&self) -> bool {
+        self.reload_requested.swap(false, Ordering::Relaxed)
+    }
+
+    /// Whether the last callback was stopped for running too long
+    pub fn was_interrupted(&self) -> bool {
+        self.deadline.lock().is_ok_and(|deadline| deadline.hit)
+    }
+
+    /// Run `f` as one callback, stopped with an error that scripts can't
+    /// catch once it runs past the time limit
+    fn run<R>(
+        &self,
+        f: impl for<'js> FnOnce(Ctx<'js>) -> rquickjs::Result<R>,
+    ) -> Result<R, ScriptError> {
+        let _stage = self.watchdog.enter(JS_STAGE);
+        if let Ok(mut deadline) = self.deadline.lock() {
+            deadline.at = self.time_limit.map(|limit| Instant::now() + limit);
+            deadline.hit = false;
+        }
+        let result = self
+            .context
+            .with(|ctx| f(ctx.clone()).map_err(|e| script_error(&ctx, e)));
+        if let Ok(mut deadline) = self.deadline.lock() {
+            deadline.at = None;
+        }
+
+        result.map_err(|mut error| {
+            if self.was_interrupted() {
+                error.message = format!(
+                    "Stopped after running longer than {}ms (<js time-limit> in <performance>)",
+                    self.time_limit.unwrap_or_default().as_millis()
+                );
+                eprintln!("🛑 {}", error);
+                notify_stopped(&error.to_string());
+            }
+            error
         })
     }
 
@@ -96,6 +321,11 @@ impl JSRuntime {
 
             // Add utility functions
             self.add_utility_functions(ctx.clone(), &globals)?;
+            self.add_storage(ctx.clone(), &globals)?;
+
+            // Generic event hooks, fired from Rust through emit()
+            ctx.eval::<(), _>(EVENT_PRELUDE)
+                .map_err(|e| format!("Failed to install event hooks: {:?}", e))?;
 
             Ok(())
         })
@@ -118,45 +348,394 @@ impl JSRuntime {
         .map_err(|e| format!("Failed to set spawn: {:?}", e))?;
 
         // wm.close()
+        let actions = self.binding_actions.clone();
         wm.set(
             "close",
-            Function::new(ctx.clone(), || {
+            Function::new(ctx.clone(), move || {
                 println!("JS: close()");
-                // This will be handled by the keybinding system
+                queue_action(&actions, Action::Close);
             }),
         )
         .map_err(|e| format!("Failed to set close: {:?}", e))?;
 
         // wm.focus(direction)
+        let actions = self.binding_actions.clone();
         wm.set(
             "focus",
-            Function::new(ctx.clone(), |dir: String| {
+            Function::new(ctx.clone(), move |dir: String| {
                 println!("JS: focus({})", dir);
-                // This will be handled by the keybinding system
+                queue_action(&actions, Action::Focus { direction: dir });
             }),
         )
         .map_err(|e| format!("Failed to set focus: {:?}", e))?;
 
         // wm.moveToWorkspace(workspace)
+        let actions = self.binding_actions.clone();
         wm.set(
             "moveToWorkspace",
-            Function::new(ctx.clone(), |ws: u32| {
+            Function::new(ctx.clone(), move |ws: u32| {
                 println!("JS: moveToWorkspace({})", ws);
-                // This will be handled by the keybinding system
+                queue_action(&actions, Action::Move { workspace: ws });
             }),
         )
         .map_err(|e| format!("Failed to set moveToWorkspace: {:?}", e))?;
 
         // wm.switchToWorkspace(workspace)
+        let actions = self.binding_actions.clone();
         wm.set(
             "switchToWorkspace",
-            Function::new(ctx.clone(), |ws: u32| {
+            Function::new(ctx.clone(), move |ws: u32| {
                 println!("JS: switchToWorkspace({})", ws);
-                // This will be handled by the keybinding system
+                queue_action(&actions, Action::SwitchWorkspace { workspace: ws });
             }),
         )
         .map_err(|e| format!("Failed to set switchToWorkspace: {:?}", e))?;
 
+        // wm.mergeWindow(direction)
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "mergeWindow",
+            Function::new(ctx.clone(), move |dir: String| {
+                println!("JS: mergeWindow({})", dir);
+                queue_action(&actions, Action::MergeWindow { direction: dir });
+            }),
+        )
+        .map_err(|e| format!("Failed to set mergeWindow: {:?}", e))?;
+
+        // wm.splitWindow()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "splitWindow",
+            Function::new(ctx.clone(), move || {
+                println!("JS: splitWindow()");
+                queue_action(&actions, Action::SplitWindow);
+            }),
+        )
+        .map_err(|e| format!("Failed to set splitWindow: {:?}", e))?;
+
+        // wm.selectRegion(command)
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "selectRegion",
+            Function::new(ctx.clone(), move |cmd: String| {
+                println!("JS: selectRegion({})", cmd);
+                queue_action(&actions, Action::SelectRegion { command: cmd });
+            }),
+        )
+        .map_err(|e| format!("Failed to set selectRegion: {:?}", e))?;
+
+        // wm.forceClose()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "forceClose",
+            Function::new(ctx.clone(), move || {
+                println!("JS: forceClose()");
+                queue_action(&actions, Action::ForceClose);
+            }),
+        )
+        .map_err(|e| format!("Failed to set forceClose: {:?}", e))?;
+
+        // wm.execFocus(command, { appId, workspace })
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "execFocus",
+            Function::new(
+                ctx.clone(),
+                move |cmd: String, options: rquickjs::function::Opt<Object>| {
+                    println!("JS: execFocus({})", cmd);
+                    let options = options.0.as_ref();
+                    queue_action(
+                        &actions,
+                        Action::ExecAndFocus {
+                            command: cmd,
+                            app_id: options.and_then(|o| o.get("appId").ok().flatten()),
+                            workspace: options.and_then(|o| o.get("workspace").ok().flatten()),
+                        },
+                    );
+                },
+            ),
+        )
+        .map_err(|e| format!("Failed to set execFocus: {:?}", e))?;
+
+        // wm.runOrRaise(appId, command)
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "runOrRaise",
+            Function::new(ctx.clone(), move |app_id: String, cmd: String| {
+                println!("JS: runOrRaise({}, {})", app_id, cmd);
+                queue_action(
+                    &actions,
+                    Action::RunOrRaise {
+                        app_id,
+                        command: cmd,
+                    },
+                );
+            }),
+        )
+        .map_err(|e| format!("Failed to set runOrRaise: {:?}", e))?;
+
+        // wm.togglePresentationMode()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "togglePresentationMode",
+            Function::new(ctx.clone(), move || {
+                println!("JS: togglePresentationMode()");
+                queue_action(&actions, Action::TogglePresentationMode);
+            }),
+        )
+        .map_err(|e| format!("Failed to set togglePresentationMode: {:?}", e))?;
+
+        // wm.promoteToMaster()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "promoteToMaster",
+            Function::new(ctx.clone(), move || {
+                println!("JS: promoteToMaster()");
+                queue_action(&actions, Action::PromoteToMaster);
+            }),
+        )
+        .map_err(|e| format!("Failed to set promoteToMaster: {:?}", e))?;
+
+        // wm.adjustMasterRatio(delta)
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "adjustMasterRatio",
+            Function::new(ctx.clone(), move |delta: f64| {
+                println!("JS: adjustMasterRatio({})", delta);
+                queue_action(
+                    &actions,
+                    Action::AdjustMasterRatio {
+                        delta: delta as f32,
+                    },
+                );
+            }),
+        )
+        .map_err(|e| format!("Failed to set adjustMasterRatio: {:?}", e))?;
+
+        // wm.adjustSplitRatio(delta)
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "adjustSplitRatio",
+            Function::new(ctx.clone(), move |delta: f64| {
+                println!("JS: adjustSplitRatio({})", delta);
+                queue_action(
+                    &actions,
+                    Action::AdjustSplitRatio {
+                        delta: delta as f32,
+                    },
+                );
+            }),
+        )
+        .map_err(|e| format!("Failed to set adjustSplitRatio: {:?}", e))?;
+
+        // wm.rotateSplit()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "rotateSplit",
+            Function::new(ctx.clone(), move || {
+                println!("JS: rotateSplit()");
+                queue_action(&actions, Action::RotateSplit);
+            }),
+        )
+        .map_err(|e| format!("Failed to set rotateSplit: {:?}", e))?;
+
+        // wm.splitHorizontal()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "splitHorizontal",
+            Function::new(ctx.clone(), move || {
+                println!("JS: splitHorizontal()");
+                queue_action(&actions, Action::SplitHorizontal);
+            }),
+        )
+        .map_err(|e| format!("Failed to set splitHorizontal: {:?}", e))?;
+
+        // wm.splitVertical()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "splitVertical",
+            Function::new(ctx.clone(), move || {
+                println!("JS: splitVertical()");
+                queue_action(&actions, Action::SplitVertical);
+            }),
+        )
+        .map_err(|e| format!("Failed to set splitVertical: {:?}", e))?;
+
+        // wm.reopenLast()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "reopenLast",
+            Function::new(ctx.clone(), move || {
+                println!("JS: reopenLast()");
+                queue_action(&actions, Action::ReopenLast);
+            }),
+        )
+        .map_err(|e| format!("Failed to set reopenLast: {:?}", e))?;
+
+        // wm.toggleOutputZoom()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "toggleOutputZoom",
+            Function::new(ctx.clone(), move || {
+                println!("JS: toggleOutputZoom()");
+                queue_action(&actions, Action::ToggleOutputZoom);
+            }),
+        )
+        .map_err(|e| format!("Failed to set toggleOutputZoom: {:?}", e))?;
+
+        // wm.resizeTiled(direction, amount)
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "resizeTiled",
+            Function::new(ctx.clone(), move |direction: String, amount: f64| {
+                println!("JS: resizeTiled({}, {})", direction, amount);
+                queue_action(
+                    &actions,
+                    Action::ResizeTiled {
+                        direction,
+                        amount: amount as f32,
+                    },
+                );
+            }),
+        )
+        .map_err(|e| format!("Failed to set resizeTiled: {:?}", e))?;
+
+        // wm.toggleBar(output?)
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "toggleBar",
+            Function::new(
+                ctx.clone(),
+                move |output: rquickjs::function::Opt<String>| {
+                    println!("JS: toggleBar({:?})", output.0);
+                    queue_action(&actions, Action::ToggleBar { output: output.0 });
+                },
+            ),
+        )
+        .map_err(|e| format!("Failed to set toggleBar: {:?}", e))?;
+
+        // wm.swapWindow(direction)
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "swapWindow",
+            Function::new(ctx.clone(), move |direction: String| {
+                println!("JS: swapWindow({})", direction);
+                queue_action(&actions, Action::Swap { direction });
+            }),
+        )
+        .map_err(|e| format!("Failed to set swapWindow: {:?}", e))?;
+
+        // wm.focusOutput(direction)
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "focusOutput",
+            Function::new(ctx.clone(), move |direction: String| {
+                println!("JS: focusOutput({})", direction);
+                queue_action(&actions, Action::FocusOutput { direction });
+            }),
+        )
+        .map_err(|e| format!("Failed to set focusOutput: {:?}", e))?;
+
+        // wm.centerWindow()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "centerWindow",
+            Function::new(ctx.clone(), move || {
+                println!("JS: centerWindow()");
+                queue_action(&actions, Action::CenterWindow);
+            }),
+        )
+        .map_err(|e| format!("Failed to set centerWindow: {:?}", e))?;
+
+        // wm.windowMenu()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "windowMenu",
+            Function::new(ctx.clone(), move || {
+                println!("JS: windowMenu()");
+                queue_action(&actions, Action::WindowMenu);
+            }),
+        )
+        .map_err(|e| format!("Failed to set windowMenu: {:?}", e))?;
+
+        // wm.windowHints()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "windowHints",
+            Function::new(ctx.clone(), move || {
+                println!("JS: windowHints()");
+                queue_action(&actions, Action::WindowHints);
+            }),
+        )
+        .map_err(|e| format!("Failed to set windowHints: {:?}", e))?;
+
+        // wm.raiseWindow()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "raiseWindow",
+            Function::new(ctx.clone(), move || {
+                println!("JS: raiseWindow()");
+                queue_action(&actions, Action::Raise);
+            }),
+        )
+        .map_err(|e| format!("Failed to set raiseWindow: {:?}", e))?;
+
+        // wm.lowerWindow()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "lowerWindow",
+            Function::new(ctx.clone(), move || {
+                println!("JS: lowerWindow()");
+                queue_action(&actions, Action::Lower);
+            }),
+        )
+        .map_err(|e| format!("Failed to set lowerWindow: {:?}", e))?;
+
+        // wm.raiseOrLowerWindow()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "raiseOrLowerWindow",
+            Function::new(ctx.clone(), move || {
+                println!("JS: raiseOrLowerWindow()");
+                queue_action(&actions, Action::RaiseOrLower);
+            }),
+        )
+        .map_err(|e| format!("Failed to set raiseOrLowerWindow: {:?}", e))?;
+
+        // wm.adjustGaps(inner, outer)
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "adjustGaps",
+            Function::new(ctx.clone(), move |inner: i32, outer: i32| {
+                println!("JS: adjustGaps({}, {})", inner, outer);
+                queue_action(&actions, Action::AdjustGaps { inner, outer });
+            }),
+        )
+        .map_err(|e| format!("Failed to set adjustGaps: {:?}", e))?;
+
+        // wm.saveTemplate(name)
+        wm.set(
+            "saveTemplate",
+            Function::new(ctx.clone(), |name: String| {
+                println!("JS: saveTemplate({})", name);
+                // This will be handled by the keybinding system
+            }),
+        )
+        .map_err(|e| format!("Failed to set saveTemplate: {:?}", e))?;
+
+        // wm.applyTemplate(name, workspace)
+        wm.set(
+            "applyTemplate",
+            Function::new(
+                ctx.clone(),
+                |name: String, _workspace: rquickjs::function::Opt<u32>| {
+                    println!("JS: applyTemplate({})", name);
+                    // This will be handled by the keybinding system
+                },
+            ),
+        )
+        .map_err(|e| format!("Failed to set applyTemplate: {:?}", e))?;
+
         // wm.cycleWorkspaceNext()
         wm.set(
             "cycleWorkspaceNext",
@@ -178,11 +757,12 @@ impl JSRuntime {
         .map_err(|e| format!("Failed to set cycleWorkspacePrev: {:?}", e))?;
 
         // wm.toggleFloating()
+        let actions = self.binding_actions.clone();
         wm.set(
             "toggleFloating",
-            Function::new(ctx.clone(), || {
+            Function::new(ctx.clone(), move || {
                 println!("JS: toggleFloating()");
-                // This will be handled by the keybinding system
+                queue_action(&actions, Action::ToggleFloating);
             }),
         )
         .map_err(|e| format!("Failed to set toggleFloating: {:?}", e))?;
@@ -226,15 +806,38 @@ impl JSRuntime {
         .map_err(|e| format!("Failed to set cycleLayout: {:?}", e))?;
 
         // wm.reload()
+        let reload_requested = self.reload_requested.clone();
         wm.set(
             "reload",
-            Function::new(ctx.clone(), || {
+            Function::new(ctx.clone(), move || {
                 println!("JS: reload()");
-                // Would reload configuration
+                reload_requested.store(true, Ordering::Relaxed);
             }),
         )
         .map_err(|e| format!("Failed to set reload: {:?}", e))?;
 
+        // wm.enterMode(name)
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "enterMode",
+            Function::new(ctx.clone(), move |name: String| {
+                println!("JS: enterMode({})", name);
+                queue_action(&actions, Action::EnterMode { name });
+            }),
+        )
+        .map_err(|e| format!("Failed to set enterMode: {:?}", e))?;
+
+        // wm.exitMode()
+        let actions = self.binding_actions.clone();
+        wm.set(
+            "exitMode",
+            Function::new(ctx.clone(), move || {
+                println!("JS: exitMode()");
+                queue_action(&actions, Action::ExitMode);
+            }),
+        )
+        .map_err(|e| format!("Failed to set exitMode: {:?}", e))?;
+
         // wm.exit()
         wm.set(
             "exit",
@@ -258,6 +861,60 @@ impl JSRuntime {
         Ok(())
     }
 
+    /// `storage.get(key)`, `storage.set(key, value)` and `storage.remove(key)`:
+    /// strings kept across restarts in the state directory
+    fn add_storage<'a>(&self, ctx: Ctx<'a>, globals: &Object<'a>) -> Result<(), String> {
+        let values: HashMap<String, String> = crate::storage::read_state(STORAGE_FILE)
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        let values = Arc::new(Mutex::new(values));
+
+        let storage = Object::new(ctx.clone())
+            .map_err(|e| format!("Failed to create storage object: {:?}", e))?;
+
+        let get_values = values.clone();
+        storage
+            .set(
+                "get",
+                Function::new(ctx.clone(), move |key: String| -> Option<String> {
+                    get_values.lock().ok()?.get(&key).cloned()
+                }),
+            )
+            .map_err(|e| format!("Failed to set storage.get: {:?}", e))?;
+
+        let set_values = values.clone();
+        storage
+            .set(
+                "set",
+                Function::new(ctx.clone(), move |key: String, value: String| {
+                    if let Ok(mut values) = set_values.lock() {
+                        values.insert(key, value);
+                        save_storage(&values);
+                    }
+                }),
+            )
+            .map_err(|e| format!("Failed to set storage.set: {:?}", e))?;
+
+        storage
+            .set(
+                "remove",
+                Function::new(ctx.clone(), move |key: String| {
+                    if let Ok(mut values) = values.lock() {
+                        if values.remove(&key).is_some() {
+                            save_storage(&values);
+                        }
+                    }
+                }),
+            )
+            .map_err(|e| format!("Failed to set storage.remove: {:?}", e))?;
+
+        globals
+            .set("storage", storage)
+            .map_err(|e| format!("Failed to set storage global: {:?}", e))?;
+
+        Ok(())
+    }
+
     fn add_utility_functions<'a>(&self, ctx: Ctx<'a>, globals: &Object<'a>) -> Result<(), String> {
         let keybindings = self.keybindings.clone();
         let callback_functions = self.callback_functions.clone();
@@ -269,8 +926,9 @@ impl JSRuntime {
                 Function::new(ctx.clone(), move |combo: String, callback: Function| {
                     println!("Registering keybinding: {}", combo);
 
-                    // Parse combo (e.g., "Super+Return" -> ["Super"], "Return")
-                    let (modifiers, key) = parse_key_combo(&combo);
+                    // Parse combo (e.g., "Super+Return" -> ["Super"], "Return"),
+                    // or the first key of a chord like "Super+space, w, f"
+                    let (modifiers, key, sequence) = parse_key_sequence(&combo);
 
                     // Generate a unique callback name
                     let callback_name = format!("callback_{}", combo.replace("+", "_"));
@@ -290,6 +948,7 @@ impl JSRuntime {
                             combo: combo.clone(),
                             modifiers,
                             key,
+                            sequence,
                             callback_name,
                         });
                     }
@@ -369,55 +1028,74 @@ impl JSRuntime {
         globals
             .set(
                 "notify",
-                Function::new(ctx.clone(), |options: Object| {
+                Function::new(ctx, |options: Object| {
                     println!("JS: notify()");
                     // Would send notification
                 }),
             )
             .map_err(|e| format!("Failed to set notify: {:?}", e))?;
 
-        let startup_handlers = self.startup_handlers.clone();
-
-        // onStartup(callback)
-        globals
-            .set(
-                "onStartup",
-                Function::new(ctx, move |callback: Function| {
-                    println!("Registered startup handler");
-                    if let Ok(mut handlers) = startup_handlers.lock() {
-                        handlers.push("startup_handler".to_string());
-                    }
-                    // Execute immediately for now
-                    if let Err(e) = callback.call::<_, ()>(()) {
-                        eprintln!("Startup handler error: {:?}", e);
-                    }
-                }),
-            )
-            .map_err(|e| format!("Failed to set onStartup: {:?}", e))?;
-
         Ok(())
     }
 
     pub fn evaluate(&self, js_code: &str) -> Result<(), String> {
-        self.context.with(|ctx| {
-            match ctx.eval::<Value, _>(js_code) {
-                Ok(_) => Ok(()),
-                Err(e) => {
-                    // Try to get more detailed error information
-                    let error_msg = format!("JS evaluation error: {:?}", e);
-                    eprintln!("JavaScript Error Details:");
-                    eprintln!("  Error: {:?}", e);
-                    eprintln!("  Code length: {} characters", js_code.len());
-                    // Show first few lines of code that might be causing issues
-                    let lines: Vec<&str> = js_code.lines().take(10).collect();
-                    eprintln!("  Code preview (first 10 lines):");
-                    for (i, line) in lines.iter().enumerate() {
-                        eprintln!("    {}: {}", i + 1, line);
-                    }
-                    Err(error_msg)
+        self.evaluate_script(js_code)
+            .map_err(|e| format!("JS evaluation error: {}", e))
+    }
+
+    /// Like `evaluate`, keeping the line the error came from
+    pub fn evaluate_script(&self, js_code: &str) -> Result<(), ScriptError> {
+        let result = self.run(|ctx| ctx.eval::<Value, _>(js_code).map(|_| ()));
+        // The code isn't at fault for taking long
+        if let Err(ref error) = result {
+            if !self.was_interrupted() {
+                eprintln!("JavaScript Error Details:");
+                eprintln!("  Error: {}", error);
+                eprintln!("  Code length: {} characters", js_code.len());
+                // Show the code around the error, or the start of it
+                let first = error.line.map_or(1, |line| line.saturating_sub(2).max(1));
+                eprintln!("  Code preview:");
+                for (i, line) in js_code.lines().enumerate().skip(first as usize - 1).take(5) {
+                    eprintln!("    {}: {}", i + 1, line);
                 }
             }
-        })
+        }
+        result
+    }
+
+    /// Call every handler registered with `on(event, callback)`.
+    /// `payload` is a JSON value passed as the callback's argument.
+    pub fn emit(&self, event: &str, payload: &serde_json::Value) -> Result<(), String> {
+        let event = serde_json::to_string(event).map_err(|e| e.to_string())?;
+        self.evaluate(&format!("__webwm_emit({}, {})", event, payload))
+    }
+
+    /// Name from the function registered with `setWorkspaceNamer(fn)`.
+    /// None when there is no namer or it returned nothing. A namer that
+    /// runs too long is dropped, as it would stall every relayout.
+    pub fn workspace_name(&self, workspace: &serde_json::Value) -> Result<Option<String>, String> {
+        let result = self
+            .run(|ctx| {
+                ctx.eval::<Option<String>, _>(format!("__webwm_name_workspace({})", workspace))
+            })
+            .map_err(|e| format!("Workspace namer failed: {}", e));
+        if result.is_err() && self.was_interrupted() {
+            self.evaluate("__webwm_workspace_namer = null")?;
+        }
+        result
+    }
+
+    /// Group from the function registered with `setWorkspaceGrouper(fn)`.
+    /// None when there is no grouper or it returned nothing. A grouper that
+    /// runs too long is dropped like a namer.
+    pub fn workspace_group(&self, window: &serde_json::Value) -> Result<Option<String>, String> {
+        let result = self
+            .run(|ctx| ctx.eval::<Option<String>, _>(format!("__webwm_group_window({})", window)))
+            .map_err(|e| format!("Workspace grouper failed: {}", e));
+        if result.is_err() && self.was_interrupted() {
+            self.evaluate("__webwm_workspace_grouper = null")?;
+        }
+        result
     }
 
     pub fn get_keybindings(&self) -> Vec<JSKeybinding> {
@@ -427,6 +1105,49 @@ impl JSRuntime {
             .unwrap_or_default()
     }
 
+    /// Rules registered with `windowRule({...})`, in order
+    pub fn get_window_rules(&self) -> Result<Vec<WindowRule>, String> {
+        let json = self
+            .run(|ctx| ctx.eval::<String, _>("JSON.stringify(__webwm_window_rules)"))
+            .map_err(|e| format!("Failed to read window rules: {}", e))?;
+        let rules: Vec<JSWindowRule> =
+            serde_json::from_str(&json).map_err(|e| format!("Invalid windowRule(): {}", e))?;
+        // Like in desktop.xml, a rule has to say which windows it's for
+        if rules
+            .iter()
+            .any(|rule| rule.app_id.is_none() && rule.content_type.is_none())
+        {
+            return Err("windowRule() needs an appId or a contentType".to_string());
+        }
+        Ok(rules.into_iter().map(WindowRule::from).collect())
+    }
+
+    /// Combos bound in each mode declared with `wm.mode(name, {...})`
+    pub fn get_modes(&self) -> Result<HashMap<String, Vec<String>>, String> {
+        let json = self
+            .run(|ctx| ctx.eval::<String, _>("__webwm_mode_combos()"))
+            .map_err(|e| format!("Failed to read binding modes: {}", e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid wm.mode(): {}", e))
+    }
+
+    /// Run the callback bound to `combo` with `keybind()`, or with
+    /// `wm.mode()` while in `mode`, returning the actions it asked for
+    pub fn run_binding(&self, mode: Option<&str>, combo: &str) -> Result<Vec<Action>, String> {
+        let mode = serde_json::to_string(&mode).map_err(|e| e.to_string())?;
+        let combo = serde_json::to_string(combo).map_err(|e| e.to_string())?;
+        if let Ok(mut actions) = self.binding_actions.lock() {
+            *actions = Some(Vec::new());
+        }
+        let result = self.evaluate(&format!("__webwm_run_binding({}, {})", mode, combo));
+        let actions = self
+            .binding_actions
+            .lock()
+            .ok()
+            .and_then(|mut actions| actions.take())
+            .unwrap_or_default();
+        result.map(|_| actions)
+    }
+
     pub fn get_window_handlers(&self) -> Vec<JSWindowHandler> {
         self.window_handlers
             .lock()
@@ -458,7 +1179,86 @@ impl JSRuntime {
     }
 }
 
-fn parse_key_combo(combo: &str) -> (Vec<String>, String) {
+// on(event, callback), setWorkspaceNamer(fn) and setWorkspaceGrouper(fn)
+// keep their callbacks on the JS side; Rust calls them through the
+// __webwm_* helpers, as it does the callbacks of keybind() and wm.mode(). windowRule(rule) collects rules there too, for Rust to
+// read once the config has run. onStartup(callback) waits for the `startup` event, so
+// reloading the config doesn't start everything again.
+const EVENT_PRELUDE: &str = r#"
+globalThis.__webwm_handlers = {};
+globalThis.on = function (event, callback) {
+    (__webwm_handlers[event] = __webwm_handlers[event] || []).push(callback);
+};
+wm.on = globalThis.on;
+globalThis.__webwm_emit = function (event, payload) {
+    for (const callback of __webwm_handlers[event] || []) {
+        try {
+            callback(payload);
+        } catch (e) {
+            console.log(`Handler for '${event}' failed: ${e}`);
+        }
+    }
+};
+wm.onOutputChange = function (callback) {
+    on('output', callback);
+};
+globalThis.onStartup = function (callback) {
+    on('startup', callback);
+};
+globalThis.__webwm_keybinds = {};
+globalThis.keybind = (function (register) {
+    return function (combo, callback) {
+        __webwm_keybinds[combo] = callback;
+        register(combo, callback);
+    };
+})(keybind);
+globalThis.__webwm_modes = {};
+wm.mode = function (name, bindings) {
+    for (const [combo, callback] of Object.entries(bindings)) {
+        if (typeof callback !== 'function') {
+            throw new TypeError(`wm.mode('${name}'): '${combo}' isn't bound to a function`);
+        }
+    }
+    __webwm_modes[name] = bindings;
+};
+globalThis.__webwm_mode_combos = function () {
+    const combos = {};
+    for (const [name, bindings] of Object.entries(__webwm_modes)) {
+        combos[name] = Object.keys(bindings);
+    }
+    return JSON.stringify(combos);
+};
+globalThis.__webwm_run_binding = function (mode, combo) {
+    const bindings = mode === null ? __webwm_keybinds : __webwm_modes[mode];
+    const callback = bindings && bindings[combo];
+    if (callback) callback();
+};
+globalThis.__webwm_window_rules = [];
+globalThis.windowRule = function (rule) {
+    __webwm_window_rules.push(rule);
+};
+globalThis.__webwm_workspace_namer = null;
+globalThis.setWorkspaceNamer = function (namer) {
+    __webwm_workspace_namer = namer;
+};
+globalThis.__webwm_name_workspace = function (workspace) {
+    if (!__webwm_workspace_namer) return null;
+    const name = __webwm_workspace_namer(workspace);
+    return name == null ? null : String(name);
+};
+globalThis.__webwm_workspace_grouper = null;
+globalThis.setWorkspaceGrouper = function (grouper) {
+    __webwm_workspace_grouper = grouper;
+};
+globalThis.__webwm_group_window = function (window) {
+    if (!__webwm_workspace_grouper) return null;
+    const group = __webwm_workspace_grouper(window);
+    return group == null ? null : String(group);
+};
+"#;
+
+/// Modifiers and key of a combo such as `Super+Shift+Return`
+pub fn parse_key_combo(combo: &str) -> (Vec<String>, String) {
     let parts: Vec<&str> = combo.split('+').collect();
 
     if parts.len() == 1 {
@@ -474,6 +1274,15 @@ fn parse_key_combo(combo: &str) -> (Vec<String>, String) {
     (modifiers, key)
 }
 
+/// Modifiers and key of the first stroke of a chord such as
+/// `Super+space, w, f`, and the strokes after it. A plain combo is a chord
+/// of one.
+pub fn parse_key_sequence(combo: &str) -> (Vec<String>, String, Vec<String>) {
+    let mut strokes = combo.split(',').map(str::trim);
+    let (modifiers, key) = parse_key_combo(strokes.next().unwrap_or_default());
+    (modifiers, key, strokes.map(str::to_string).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -484,6 +1293,211 @@ mod tests {
         assert!(runtime.is_ok());
     }
 
+    #[test]
+    fn test_event_hooks() {
+        let runtime = JSRuntime::new().unwrap();
+        runtime.init_api().unwrap();
+        runtime
+            .evaluate("on('lid', (event) => { globalThis.lidClosed = event.closed; });")
+            .unwrap();
+
+        runtime
+            .emit("lid", &serde_json::json!({ "closed": true }))
+            .unwrap();
+
+        let closed = runtime
+            .context
+            .with(|ctx| ctx.globals().get::<_, bool>("lidClosed"))
+            .unwrap();
+        assert!(closed);
+    }
+
+    #[test]
+    fn test_output_change_hook() {
+        let runtime = JSRuntime::new().unwrap();
+        runtime.init_api().unwrap();
+        runtime
+            .evaluate(
+                "wm.onOutputChange((event) => { globalThis.docked = event.outputs.length > 1; });",
+            )
+            .unwrap();
+
+        runtime
+            .emit(
+                "output",
+                &serde_json::json!({
+                    "change": "added",
+                    "output": { "name": "DP-1" },
+                    "outputs": [{ "name": "eDP-1" }, { "name": "DP-1" }],
+                }),
+            )
+            .unwrap();
+
+        let docked = runtime
+            .context
+            .with(|ctx| ctx.globals().get::<_, bool>("docked"))
+            .unwrap();
+        assert!(docked);
+    }
+
+    #[test]
+    fn test_workspace_namer() {
+        let runtime = JSRuntime::new().unwrap();
+        runtime.init_api().unwrap();
+        let info = serde_json::json!({ "id": 2, "name": "web", "apps": ["firefox"] });
+        assert_eq!(runtime.workspace_name(&info).unwrap(), None);
+
+        runtime
+            .evaluate(
+                "setWorkspaceNamer((ws) => ws.apps.length ? `${ws.id} ${ws.apps[0]}` : null);",
+            )
+            .unwrap();
+        assert_eq!(
+            runtime.workspace_name(&info).unwrap().as_deref(),
+            Some("2 firefox")
+        );
+    }
+
+    #[test]
+    fn test_workspace_grouper() {
+        let runtime = JSRuntime::new().unwrap();
+        runtime.init_api().unwrap();
+        let info = serde_json::json!({ "appId": "org.gnome.Nautilus", "title": "Home" });
+        assert_eq!(runtime.workspace_group(&info).unwrap(), None);
+
+        runtime
+            .evaluate(
+                "setWorkspaceGrouper((w) => w.appId.startsWith('org.gnome') ? 'gnome' : null);",
+            )
+            .unwrap();
+        assert_eq!(
+            runtime.workspace_group(&info).unwrap().as_deref(),
+            Some("gnome")
+        );
+        let other = serde_json::json!({ "appId": "firefox", "title": "" });
+        assert_eq!(runtime.workspace_group(&other).unwrap(), None);
+    }
+
+    #[test]
+    fn test_script_error() {
+        let runtime = JSRuntime::new().unwrap();
+        runtime.init_api().unwrap();
+
+        let error = runtime
+            .evaluate_script("const a = 1;\n\nundefinedThing();\n")
+            .unwrap_err();
+        assert!(error.message.contains("undefinedThing"));
+        assert_eq!(error.line, Some(3));
+
+        let error = runtime
+            .evaluate_script("const b = 1;\nlet = ;\n")
+            .unwrap_err();
+        assert_eq!(error.line, Some(2));
+
+        let error = runtime.evaluate_script("throw 'oops';").unwrap_err();
+        assert_eq!(error.message, "oops");
+        assert_eq!(stack_line("    at <eval> (eval_script:12:5)"), Some(12));
+    }
+
+    #[test]
+    fn test_window_rules() {
+        let runtime = JSRuntime::new().unwrap();
+        runtime.init_api().unwrap();
+        assert!(runtime.get_window_rules().unwrap().is_empty());
+
+        runtime
+            .evaluate(
+                "windowRule({ appId: 'mpv', floating: true, class: 'video' });
+                 windowRule({ contentType: 'game', allowTearing: true });",
+            )
+            .unwrap();
+        let rules = runtime.get_window_rules().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].app_id, "mpv");
+        assert_eq!(rules[0].floating, Some(true));
+        assert_eq!(rules[0].css_class.as_deref(), Some("video"));
+        assert_eq!(rules[1].app_id, "");
+        assert_eq!(rules[1].allow_tearing, Some(true));
+
+        // Misspelled settings are an error rather than ignored
+        runtime
+            .evaluate("windowRule({ appId: 'x', floting: true });")
+            .unwrap();
+        let error = runtime.get_window_rules().unwrap_err();
+        assert!(error.contains("floting"));
+
+        let runtime = JSRuntime::new().unwrap();
+        runtime.init_api().unwrap();
+        runtime.evaluate("windowRule({ floating: true });").unwrap();
+        assert!(runtime.get_window_rules().is_err());
+    }
+
+    #[test]
+    fn test_binding_modes() {
+        let runtime = JSRuntime::new().unwrap();
+        runtime.init_api().unwrap();
+        runtime
+            .evaluate(
+                "keybind('Super+r', () => wm.enterMode('resize'));
+                 wm.mode('resize', {
+                     'h': () => wm.resizeTiled('left', 0.05),
+                     'Return': () => wm.exitMode(),
+                 });",
+            )
+            .unwrap();
+        let modes = runtime.get_modes().unwrap();
+        assert_eq!(modes["resize"], vec!["h", "Return"]);
+
+        // Callbacks run from both tables, handing back what they asked for
+        let actions = runtime.run_binding(None, "Super+r").unwrap();
+        assert!(matches!(&actions[..], [Action::EnterMode { name }] if name == "resize"));
+        let actions = runtime.run_binding(Some("resize"), "h").unwrap();
+        assert!(
+            matches!(&actions[..], [Action::ResizeTiled { direction, .. }] if direction == "left")
+        );
+        let actions = runtime.run_binding(Some("resize"), "Return").unwrap();
+        assert!(matches!(&actions[..], [Action::ExitMode]));
+
+        // wm calls outside a keybinding do nothing
+        runtime.evaluate("wm.enterMode('resize')").unwrap();
+        let actions = runtime.run_binding(Some("resize"), "Return").unwrap();
+        assert_eq!(actions.len(), 1);
+
+        // Unbound combos and unknown modes do nothing
+        assert!(runtime.run_binding(Some("launch"), "h").unwrap().is_empty());
+        assert!(runtime.run_binding(None, "Super+x").unwrap().is_empty());
+
+        let error = runtime.evaluate("wm.mode('launch', { 'f': 'firefox' });");
+        assert!(error.unwrap_err().contains("isn't bound to a function"));
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let mut runtime = JSRuntime::new().unwrap();
+        runtime.init_api().unwrap();
+        runtime.set_time_limit(Some(Duration::from_millis(50)));
+
+        // Runaway scripts are stopped, even ones that try to catch it
+        let error = runtime.evaluate("while (true) {}").unwrap_err();
+        assert!(error.contains("Stopped after running longer than 50ms"));
+        assert!(runtime.was_interrupted());
+        assert!(runtime
+            .evaluate("try { for (;;) {} } catch (e) { globalThis.caught = true; }")
+            .is_err());
+
+        // The runtime carries on afterwards
+        runtime.evaluate("globalThis.after = 1;").unwrap();
+        assert!(!runtime.was_interrupted());
+
+        // A namer that hangs is dropped
+        let info = serde_json::json!({ "id": 1, "name": "1", "apps": [] });
+        runtime
+            .evaluate("setWorkspaceNamer(() => { for (;;) {} });")
+            .unwrap();
+        assert!(runtime.workspace_name(&info).is_err());
+        assert_eq!(runtime.workspace_name(&info).unwrap(), None);
+    }
+
     #[test]
     fn test_key_combo_parsing() {
         let (mods, key) = parse_key_combo("Super+Return");
@@ -0,0 +1,378 @@
+// Config migrations
+//
+// `webwm config migrate [DIR] [--dry-run]` upgrades a config directory
+// written for an older WebWM. The version is the `version` attribute of
+// `<desktop>`, 1 when there is none. Each step from one version to the
+// next rewrites desktop.xml and config.js in place, splicing the changed
+// parts into the original text so comments and formatting stay as they
+// were, and says what it changed and on which line. Files are backed up
+// to `<file>.bak` before they're written.
+//
+// A breaking change to the config format bumps CONFIG_VERSION and adds its
+// step to `migrate_desktop_xml` or `migrate_config_js`.
+
+use roxmltree::{Document, Node};
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// Version of the config format this build reads
+pub const CONFIG_VERSION: u32 = 2;
+
+/// One thing a migration changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub file: &'static str,
+    pub line: u32,
+    pub description: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.description)
+    }
+}
+
+/// What migrating a config directory does
+#[derive(Debug, Default)]
+pub struct Migration {
+    pub from: u32,
+    /// New contents of the files that change
+    pub files: Vec<(&'static str, String)>,
+    pub changes: Vec<Change>,
+}
+
+/// Version of a desktop.xml, 1 for ones from before versions
+pub fn config_version(xml: &str) -> u32 {
+    Document::parse(xml)
+        .ok()
+        .and_then(|doc| doc.root_element().attribute("version")?.parse().ok())
+        .unwrap_or(1)
+}
+
+/// Text replacing a byte range of the original
+struct Edit {
+    range: Range<usize>,
+    text: String,
+}
+
+fn apply_edits(text: &str, mut edits: Vec<Edit>) -> String {
+    let mut text = text.to_string();
+    // From the end, so earlier ranges stay where they were
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+    for edit in edits {
+        text.replace_range(edit.range, &edit.text);
+    }
+    text
+}
+
+pub(super) fn line_at(text: &str, position: usize) -> u32 {
+    text[..position].matches('\n').count() as u32 + 1
+}
+
+/// Where the attribute starting at `position` ends, past its closing quote
+fn attribute_end(xml: &str, position: usize) -> usize {
+    let rest = &xml[position..];
+    let Some(open) = rest.find(['"', '\'']) else {
+        return xml.len();
+    };
+    let quote = rest.as_bytes()[open] as char;
+    rest[open + 1..]
+        .find(quote)
+        .map_or(xml.len(), |close| position + open + close + 2)
+}
+
+/// The span of `name` on `node`, with the whitespace before it, to remove it
+fn attribute_span(xml: &str, node: Node, name: &str) -> Option<Range<usize>> {
+    let attribute = node.attributes().find(|a| a.name() == name)?;
+    let start = xml[..attribute.position()].trim_end().len();
+    Some(start..attribute_end(xml, attribute.position()))
+}
+
+/// Indentation of the line `position` is on
+fn indentation(text: &str, position: usize) -> &str {
+    let line_start = text[..position].rfind('\n').map_or(0, |i| i + 1);
+    let line = &text[line_start..];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Steps desktop.xml from `version` to the next
+fn migrate_desktop_xml(xml: &str, version: u32) -> Result<(String, Vec<Change>), String> {
+    let doc = Document::parse(xml).map_err(|e| format!("desktop.xml: {}", e))?;
+    let mut edits = Vec::new();
+    let mut changes = Vec::new();
+    let mut change = |position: usize, description: String| {
+        changes.push(Change {
+            file: "desktop.xml",
+            line: line_at(xml, position),
+            description,
+        });
+    };
+
+    if version == 1 {
+        // JS callbacks got a time limit of their own instead of the watchdog
+        // interrupting them
+        for watchdog in doc
+            .descendants()
+            .filter(|n| n.has_tag_name("watchdog") && n.attribute("interrupt-js").is_some())
+        {
+            let limit = watchdog.attribute("interrupt-js").unwrap_or_default();
+            let span = attribute_span(xml, watchdog, "interrupt-js").unwrap_or_default();
+            edits.push(Edit {
+                range: span,
+                text: String::new(),
+            });
+            let has_js = watchdog
+                .parent()
+                .is_some_and(|performance| performance.children().any(|n| n.has_tag_name("js")));
+            if has_js {
+                change(
+                    watchdog.range().start,
+                    format!(
+                        "Removed interrupt-js=\"{}\" from <watchdog>, <js time-limit> is already set",
+                        limit
+                    ),
+                );
+            } else {
+                let end = watchdog.range().end;
+                edits.push(Edit {
+                    range: end..end,
+                    text: format!(
+                        "\n{}<js time-limit=\"{}\" />",
+                        indentation(xml, watchdog.range().start),
+                        limit
+                    ),
+                });
+                change(
+                    watchdog.range().start,
+                    format!(
+                        "Moved interrupt-js=\"{}\" from <watchdog> to <js time-limit=\"{}\" />",
+                        limit, limit
+                    ),
+                );
+            }
+        }
+
+        // `layout` on a workspace is `default-layout` now
+        for workspace in doc.descendants().filter(|n| n.has_tag_name("workspace")) {
+            let Some(attribute) = workspace.attributes().find(|a| a.name() == "layout") else {
+                continue;
+            };
+            let id = workspace.attribute("id").unwrap_or("?");
+            if workspace.attribute("default-layout").is_some() {
+                let span = attribute_span(xml, workspace, "layout").unwrap_or_default();
+                edits.push(Edit {
+                    range: span,
+                    text: String::new(),
+                });
+                change(
+                    attribute.position(),
+                    format!(
+                        "Removed layout= from workspace {}, default-layout= is already set",
+                        id
+                    ),
+                );
+            } else {
+                let start = attribute.position();
+                edits.push(Edit {
+                    range: start..start + "layout".len(),
+                    text: "default-layout".to_string(),
+                });
+                change(
+                    start,
+                    format!("Renamed layout= to default-layout= on workspace {}", id),
+                );
+            }
+        }
+    }
+
+    Ok((apply_edits(xml, edits), changes))
+}
+
+/// `version` on `<desktop>` set to `version`
+fn set_version(xml: &str, version: u32) -> Result<String, String> {
+    let doc = Document::parse(xml).map_err(|e| format!("desktop.xml: {}", e))?;
+    let root = doc.root_element();
+    let edit = match root.attributes().find(|a| a.name() == "version") {
+        Some(attribute) => Edit {
+            range: attribute.position()..attribute_end(xml, attribute.position()),
+            text: format!("version=\"{}\"", version),
+        },
+        None => {
+            let after_name = root.range().start + 1 + root.tag_name().name().len();
+            Edit {
+                range: after_name..after_name,
+                text: format!(" version=\"{}\"", version),
+            }
+        }
+    };
+    Ok(apply_edits(xml, vec![edit]))
+}
+
+/// Steps config.js from `version` to the next
+fn migrate_config_js(js: &str, version: u32) -> (String, Vec<Change>) {
+    let mut changes = Vec::new();
+    if version != 1 {
+        return (js.to_string(), changes);
+    }
+
+    // Old configs declared a stand-in `wm` object, which now hides the one
+    // WebWM provides; comment it out
+    let mut lines: Vec<String> = Vec::new();
+    let mut depth: Option<i32> = None;
+    for (i, line) in js.lines().enumerate() {
+        let declares_wm = ["const wm", "let wm", "var wm"]
+            .iter()
+            .any(|decl| line.trim_start().starts_with(decl))
+            && line.contains('{');
+        if depth.is_none() && declares_wm {
+            depth = Some(0);
+            lines.push("// Commented out by `webwm config migrate`: WebWM provides wm".to_string());
+            changes.push(Change {
+                file: "config.js",
+                line: i as u32 + 1,
+                description: "Commented out the stand-in `wm` object, which hid WebWM's"
+                    .to_string(),
+            });
+        }
+        match depth {
+            Some(d) => {
+                let d = d + line.matches('{').count() as i32 - line.matches('}').count() as i32;
+                lines.push(format!("// {}", line));
+                depth = (d > 0).then_some(d);
+            }
+            None => lines.push(line.to_string()),
+        }
+    }
+
+    let mut migrated = lines.join("\n");
+    if js.ends_with('\n') {
+        migrated.push('\n');
+    }
+    (migrated, changes)
+}
+
+/// What migrating the config in `dir` to CONFIG_VERSION would do
+pub fn plan(dir: &Path) -> Result<Migration, String> {
+    let read = |file: &str| fs::read_to_string(dir.join(file)).ok();
+    let Some(mut xml) = read("desktop.xml") else {
+        return Err(format!(
+            "No desktop.xml in {}, nothing to migrate",
+            dir.display()
+        ));
+    };
+    let mut js = read("config.js");
+
+    let from = config_version(&xml);
+    let mut migration = Migration {
+        from,
+        ..Default::default()
+    };
+    if from >= CONFIG_VERSION {
+        return Ok(migration);
+    }
+
+    for version in from..CONFIG_VERSION {
+        let (migrated, changes) = migrate_desktop_xml(&xml, version)?;
+        xml = migrated;
+        migration.changes.extend(changes);
+        if let Some(ref mut js) = js {
+            let (migrated, changes) = migrate_config_js(js, version);
+            *js = migrated;
+            migration.changes.extend(changes);
+        }
+    }
+
+    migration
+        .files
+        .push(("desktop.xml", set_version(&xml, CONFIG_VERSION)?));
+    if let Some(js) = js.filter(|js| read("config.js").as_ref() != Some(js)) {
+        migration.files.push(("config.js", js));
+    }
+    Ok(migration)
+}
+
+/// Write the migrated files to `dir`, backing up the old ones
+pub fn write(dir: &Path, migration: &Migration) -> Result<(), String> {
+    for (file, contents) in &migration.files {
+        let path = dir.join(file);
+        let backup = dir.join(format!("{}.bak", file));
+        fs::copy(&path, &backup)
+            .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+        fs::write(&path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<desktop>
+  <workspaces>
+    <!-- The one with the editor -->
+    <workspace id="1" name="main" layout="tiling" />
+    <workspace id="2" layout="monocle" default-layout="tabbed" />
+  </workspaces>
+  <performance>
+    <!-- Keep an eye on it -->
+    <watchdog threshold="500ms" interrupt-js="5s" />
+  </performance>
+</desktop>
+"#;
+
+    #[test]
+    fn test_migrate_desktop_xml() {
+        assert_eq!(config_version(OLD_XML), 1);
+        let (xml, changes) = migrate_desktop_xml(OLD_XML, 1).unwrap();
+        let xml = set_version(&xml, 2).unwrap();
+        assert_eq!(
+            xml,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<desktop version="2">
+  <workspaces>
+    <!-- The one with the editor -->
+    <workspace id="1" name="main" default-layout="tiling" />
+    <workspace id="2" default-layout="tabbed" />
+  </workspaces>
+  <performance>
+    <!-- Keep an eye on it -->
+    <watchdog threshold="500ms" />
+    <js time-limit="5s" />
+  </performance>
+</desktop>
+"#
+        );
+        assert_eq!(config_version(&xml), 2);
+
+        let lines: Vec<u32> = changes.iter().map(|change| change.line).collect();
+        assert_eq!(lines, [10, 5, 6]);
+        assert!(changes[0]
+            .description
+            .starts_with("Moved interrupt-js=\"5s\""));
+
+        // A current file has nothing to change
+        let (again, changes) = migrate_desktop_xml(&xml, 2).unwrap();
+        assert_eq!(again, xml);
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_config_js() {
+        let js = "// Keybindings\nconst wm = {\n  spawn: (cmd) => {},\n  close: () => {},\n};\n\nkeybind('Super+q', () => wm.close());\n";
+        let (migrated, changes) = migrate_config_js(js, 1);
+        assert_eq!(
+            migrated,
+            "// Keybindings\n// Commented out by `webwm config migrate`: WebWM provides wm\n// const wm = {\n//   spawn: (cmd) => {},\n//   close: () => {},\n// };\n\nkeybind('Super+q', () => wm.close());\n"
+        );
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].line, 2);
+
+        let (same, changes) = migrate_config_js("keybind('Super+q', () => wm.close());\n", 1);
+        assert_eq!(same, "keybind('Super+q', () => wm.close());\n");
+        assert!(changes.is_empty());
+    }
+}
@@ -1,70 +1,237 @@
 mod css_parser;
+pub mod includes;
 mod js_runtime;
+pub mod migrate;
 mod xml_parser;
+pub mod xml_schema;
 
 pub use css_parser::*;
 pub use js_runtime::*;
 pub use xml_parser::*;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
     pub desktop: Option<DesktopConfig>,
     pub stylesheet: Option<StyleSheet>,
     pub keybindings: Vec<Keybinding>,
+    /// Binding modes by name, each with the keybindings that replace the
+    /// default ones while it's active
+    #[serde(default)]
+    pub modes: HashMap<String, Vec<Keybinding>>,
     pub window_rules: Vec<WindowRule>,
     pub layout: LayoutConfig,
     pub theme: ThemeConfig,
+    #[serde(default)]
+    pub performance: PerformanceSettings,
+    /// The files this was read from, included ones too, for hot reload
+    /// to watch
+    #[serde(skip)]
+    pub files: Vec<PathBuf>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Keybinding {
     pub key: String,
     pub modifiers: Vec<String>,
+    /// Keys pressed after this one for a chord such as `Super+space, w, f`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sequence: Vec<String>,
     pub action: Action,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type")]
 pub enum Action {
-    Spawn { command: String },
+    Spawn {
+        command: String,
+    },
     Close,
-    Focus { direction: String },
-    Move { workspace: u32 },
-    SwitchWorkspace { workspace: u32 },
+    Focus {
+        direction: String,
+    },
+    Move {
+        workspace: u32,
+    },
+    SwitchWorkspace {
+        workspace: u32,
+    },
     ToggleFloating,
-    Custom { js: String },
+    MergeWindow {
+        direction: String,
+    },
+    SplitWindow,
+    SelectRegion {
+        command: String,
+    },
+    ForceClose,
+    ExecAndFocus {
+        command: String,
+        #[serde(default)]
+        app_id: Option<String>,
+        #[serde(default)]
+        workspace: Option<u32>,
+    },
+    RunOrRaise {
+        app_id: String,
+        command: String,
+    },
+    TogglePresentationMode,
+    PromoteToMaster,
+    AdjustMasterRatio {
+        delta: f32,
+    },
+    AdjustSplitRatio {
+        delta: f32,
+    },
+    RotateSplit,
+    SplitHorizontal,
+    SplitVertical,
+    ReopenLast,
+    ToggleOutputZoom,
+    ResizeTiled {
+        direction: String,
+        amount: f32,
+    },
+    /// Without an output, the one under the pointer
+    ToggleBar {
+        #[serde(default)]
+        output: Option<String>,
+    },
+    Swap {
+        direction: String,
+    },
+    CenterWindow,
+    WindowMenu,
+    /// Label the windows on screen and focus the one whose label is typed
+    WindowHints,
+    FocusOutput {
+        direction: String,
+    },
+    Raise,
+    Lower,
+    RaiseOrLower,
+    /// Widen the gaps by these many pixels, or narrow them when negative
+    AdjustGaps {
+        inner: i32,
+        outer: i32,
+    },
+    /// Load the configuration files again
+    Reload,
+    /// Swap the keybindings for those of the binding mode `name`
+    EnterMode {
+        name: String,
+    },
+    /// Go back to the binding mode entered before this one
+    ExitMode,
+    Custom {
+        js: String,
+    },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct WindowRule {
     pub app_id: String,
     pub workspace: Option<u32>,
     pub floating: Option<bool>,
     pub css_class: Option<String>,
+    #[serde(default)]
+    pub fullscreen: Option<String>,
+    /// "photo", "video" or "game", as declared through wp_content_type_v1
+    #[serde(default)]
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub animations: Option<bool>,
+    #[serde(default)]
+    pub direct_scanout: Option<bool>,
+    #[serde(default)]
+    pub allow_tearing: Option<bool>,
+    #[serde(default)]
+    pub confirm_close: Option<bool>,
+    /// Named area of the workspace's `grid-template-areas` to put the
+    /// window in
+    #[serde(default)]
+    pub grid_area: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl WindowRule {
+    /// Whether the rule applies to a window. Rules without an app id match
+    /// any app with their content type.
+    pub fn matches(&self, app_id: &str, content_type: Option<&str>) -> bool {
+        (self.app_id.is_empty() || self.app_id == app_id)
+            && self
+                .content_type
+                .as_deref()
+                .is_none_or(|wanted| Some(wanted) == content_type)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LayoutConfig {
     pub default_mode: String,
-    pub gaps: u32,
+    /// Between tiles
+    pub gaps_inner: u32,
+    /// Between tiles and the edges of the output
+    pub gaps_outer: u32,
     pub border_width: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ThemeConfig {
     pub border_focused: String,
     pub border_normal: String,
     pub background: String,
 }
 
-pub fn load_config(
-    config_dir: &str,
-) -> Result<(Config, Option<JSRuntime>), Box<dyn std::error::Error>> {
+/// `$XDG_CONFIG_HOME/webwm`, falling back to `~/.config/webwm`
+pub fn user_config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("webwm"))
+}
+
+/// A config file that failed to load, and where in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub file: PathBuf,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl ConfigError {
+    fn new(file: &Path, line: Option<u32>, message: impl fmt::Display) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            line,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file.display(), line, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn read_config_file(path: &Path) -> Result<String, ConfigError> {
+    fs::read_to_string(path).map_err(|e| ConfigError::new(path, None, e))
+}
+
+pub fn load_config(config_dir: &str) -> Result<(Config, Option<JSRuntime>), ConfigError> {
     let config_path = Path::new(config_dir);
 
     println!("Loading configuration from: {}", config_dir);
@@ -74,18 +241,31 @@ pub fn load_config(
     let css_path = config_path.join("style.css");
     let js_path = config_path.join("config.js");
 
-    if xml_path.exists() && css_path.exists() && js_path.exists() {
+    let web_files = [&xml_path, &css_path, &js_path];
+    if web_files.iter().all(|path| path.exists()) {
         println!("Found web-based configuration files");
         let (config, js_runtime) = load_web_config(&xml_path, &css_path, &js_path)?;
         return Ok((config, Some(js_runtime)));
     }
+    // Half a web config is more likely a mistake than a wish for config.json
+    if let Some(missing) = web_files.iter().find(|path| !path.exists()) {
+        if web_files.iter().any(|path| path.exists()) {
+            return Err(ConfigError::new(
+                missing,
+                None,
+                "Missing, but the other web config files are there",
+            ));
+        }
+    }
 
     // Fall back to JSON config
     let config_file = config_path.join("config.json");
     if config_file.exists() {
         println!("Loading JSON configuration");
-        let config_str = fs::read_to_string(config_file)?;
-        let config: Config = serde_json::from_str(&config_str)?;
+        let config_str = read_config_file(&config_file)?;
+        let mut config: Config = serde_json::from_str(&config_str)
+            .map_err(|e| ConfigError::new(&config_file, Some(e.line() as u32), e))?;
+        config.files = vec![config_file];
         return Ok((config, None));
     }
 
@@ -97,12 +277,17 @@ fn load_web_config(
     xml_path: &Path,
     css_path: &Path,
     js_path: &Path,
-) -> Result<(Config, JSRuntime), Box<dyn std::error::Error>> {
+) -> Result<(Config, JSRuntime), ConfigError> {
     println!("Parsing web-based configuration...");
 
-    // Load and parse XML
-    let xml_content = fs::read_to_string(xml_path)?;
-    let desktop_config = xml_parser::parse_desktop_xml(&xml_content)?;
+    // Load and parse XML, with the files it includes
+    let xml = includes::expand_xml(xml_path)?;
+    let desktop_config = xml_parser::parse_desktop_xml(&xml.text).map_err(|e| {
+        let line = roxmltree::Document::parse(&xml.text)
+            .err()
+            .map(|e| e.pos().row);
+        xml.error(line, e)
+    })?;
     println!(
         "  ✓ Parsed desktop.xml: {} bars, {} workspaces, {} window rules",
         desktop_config.bars.len(),
@@ -111,40 +296,60 @@ fn load_web_config(
     );
 
     // Load and parse CSS
-    let css_content = fs::read_to_string(css_path)?;
-    let stylesheet = css_parser::parse_css(&css_content)?;
+    let css_content = read_config_file(css_path)?;
+    let stylesheet =
+        css_parser::parse_css(&css_content).map_err(|e| ConfigError::new(css_path, None, e))?;
     println!(
         "  ✓ Parsed style.css: {} rules, {} variables",
         stylesheet.rules.len(),
         stylesheet.variables.len()
     );
 
-    // Load and execute JavaScript
-    let js_content = fs::read_to_string(js_path)?;
+    // Load and execute JavaScript, with the files it imports
+    let js = includes::expand_js(js_path)?;
 
     // Create and initialize JavaScript runtime
-    let js_runtime = JSRuntime::new()?;
-    js_runtime.init_api()?;
-    js_runtime.evaluate(&js_content)?;
+    let js_error = |e: String| ConfigError::new(js_path, None, e);
+    let mut js_runtime = JSRuntime::new().map_err(js_error)?;
+    let time_limit = Duration::from_millis(desktop_config.performance.js_time_limit_ms as u64);
+    js_runtime.set_time_limit(Some(time_limit).filter(|limit| !limit.is_zero()));
+    js_runtime.init_api().map_err(js_error)?;
+    js_runtime
+        .evaluate_script(&js.text)
+        .map_err(|e| js.error(e.line, e.message))?;
 
     let js_keybindings = js_runtime.get_keybindings();
+    let js_window_rules = js_runtime.get_window_rules().map_err(js_error)?;
+    let js_modes = js_runtime.get_modes().map_err(js_error)?;
     println!(
-        "  ✓ Executed config.js: {} keybindings registered",
-        js_keybindings.len()
+        "  ✓ Executed config.js: {} keybindings, {} window rules, {} binding modes registered",
+        js_keybindings.len(),
+        js_window_rules.len(),
+        js_modes.len()
     );
 
     // Convert to unified Config structure
     let mut config = Config {
         keybindings: vec![],
+        modes: HashMap::new(),
         window_rules: vec![],
         layout: LayoutConfig {
-            default_mode: desktop_config.layout.split_mode.clone(),
-            gaps: desktop_config.layout.gaps_inner,
-            border_width: 2, // Would extract from CSS
+            // Workspaces without a layout of their own start in tiling
+            default_mode: "tiling".to_string(),
+            gaps_inner: desktop_config.layout.gaps_inner,
+            gaps_outer: desktop_config.layout.gaps_outer,
+            border_width: border_width_from_css(&stylesheet),
         },
         theme: extract_theme_from_css(&stylesheet),
+        performance: desktop_config.performance.clone(),
         desktop: Some(desktop_config.clone()),
         stylesheet: Some(stylesheet),
+        files: [
+            xml.files.as_slice(),
+            &[css_path.to_path_buf()],
+            js.files.as_slice(),
+        ]
+        .concat(),
     };
 
     // Convert JS keybindings to Config keybindings
@@ -152,29 +357,86 @@ fn load_web_config(
         config.keybindings.push(Keybinding {
             key: js_kb.key.clone(),
             modifiers: js_kb.modifiers.clone(),
+            sequence: js_kb.sequence.clone(),
             action: Action::Custom {
                 js: js_kb.combo.clone(),
             },
         });
     }
 
+    // Mode bindings run their callbacks in config.js, like keybind() ones
+    for (name, combos) in js_modes {
+        let bindings = combos
+            .iter()
+            .map(|combo| {
+                let (modifiers, key, sequence) = parse_key_sequence(combo);
+                Keybinding {
+                    key,
+                    modifiers,
+                    sequence,
+                    action: Action::Custom { js: combo.clone() },
+                }
+            })
+            .collect();
+        config.modes.insert(name, bindings);
+    }
+
     // Convert XML window rules to Config window rules
     for xml_rule in &desktop_config.window_rules {
-        if let Some(app_id) = &xml_rule.app_id {
+        if xml_rule.app_id.is_none() && xml_rule.content_type.is_none() {
+            continue;
+        }
+        config.window_rules.push(WindowRule {
+            app_id: xml_rule.app_id.clone().unwrap_or_default(),
+            workspace: xml_rule.workspace,
+            floating: xml_rule.floating,
+            css_class: xml_rule.class.clone(),
+            fullscreen: xml_rule.fullscreen.clone(),
+            content_type: xml_rule.content_type.clone(),
+            animations: xml_rule.animations,
+            direct_scanout: xml_rule.direct_scanout,
+            allow_tearing: xml_rule.allow_tearing,
+            confirm_close: xml_rule.confirm_close,
+            grid_area: xml_rule.grid_area.clone(),
+        });
+    }
+
+    // Apps assigned to a workspace open there like with a window rule
+    for workspace in &desktop_config.workspaces {
+        for app_id in &workspace.assign {
             config.window_rules.push(WindowRule {
                 app_id: app_id.clone(),
-                workspace: xml_rule.workspace,
-                floating: xml_rule.floating,
-                css_class: xml_rule.class.clone(),
+                workspace: Some(workspace.id),
+                ..Default::default()
             });
         }
     }
 
+    // Rules from config.js come last, so desktop.xml's win where both
+    // set something
+    config.window_rules.extend(js_window_rules);
+
     println!("Configuration loaded successfully!");
     Ok((config, js_runtime))
 }
 
-fn extract_theme_from_css(stylesheet: &StyleSheet) -> ThemeConfig {
+/// Window border width from the `window` rule, or the `--border-width`
+/// variable its `border` shorthand uses
+fn border_width_from_css(stylesheet: &StyleSheet) -> u32 {
+    stylesheet
+        .get_length("window", "border-width")
+        .or_else(|| {
+            stylesheet
+                .variables
+                .get("--border-width")
+                .and_then(|width| width.trim().trim_end_matches("px").parse().ok())
+        })
+        .map(|width| width.max(0.0) as u32)
+        .unwrap_or(2)
+}
+
+/// Border and background colors from the stylesheet's variables
+pub fn extract_theme_from_css(stylesheet: &StyleSheet) -> ThemeConfig {
     // Extract theme colors from CSS variables
     let border_focused = stylesheet
         .variables
@@ -201,12 +463,45 @@ fn extract_theme_from_css(stylesheet: &StyleSheet) -> ThemeConfig {
     }
 }
 
+/// What safe mode runs with when the configuration doesn't load: the
+/// defaults, plus enough keybindings to get around and reload
+pub fn safe_mode_config() -> Config {
+    let mut config = default_config();
+    let bind = |key: &str, modifiers: &[&str], action: Action| Keybinding {
+        key: key.to_string(),
+        modifiers: modifiers.iter().map(|m| m.to_string()).collect(),
+        sequence: Vec::new(),
+        action,
+    };
+    config
+        .keybindings
+        .push(bind("r", &["Super", "Shift"], Action::Reload));
+    for (key, direction) in [("h", "left"), ("j", "down"), ("k", "up"), ("l", "right")] {
+        config.keybindings.push(bind(
+            key,
+            &["Super"],
+            Action::Focus {
+                direction: direction.to_string(),
+            },
+        ));
+    }
+    for workspace in 1..=4 {
+        config.keybindings.push(bind(
+            &workspace.to_string(),
+            &["Super"],
+            Action::SwitchWorkspace { workspace },
+        ));
+    }
+    config
+}
+
 fn default_config() -> Config {
     Config {
         keybindings: vec![
             Keybinding {
                 key: "Return".to_string(),
                 modifiers: vec!["Super".to_string()],
+                sequence: Vec::new(),
                 action: Action::Spawn {
                     command: "kitty".to_string(),
                 },
@@ -214,13 +509,16 @@ fn default_config() -> Config {
             Keybinding {
                 key: "q".to_string(),
                 modifiers: vec!["Super".to_string()],
+                sequence: Vec::new(),
                 action: Action::Close,
             },
         ],
+        modes: HashMap::new(),
         window_rules: vec![],
         layout: LayoutConfig {
             default_mode: "tiling".to_string(),
-            gaps: 10,
+            gaps_inner: 10,
+            gaps_outer: 10,
             border_width: 2,
         },
         theme: ThemeConfig {
@@ -228,8 +526,10 @@ fn default_config() -> Config {
             border_normal: "#333333".to_string(),
             background: "#1e1e1e".to_string(),
         },
+        performance: PerformanceSettings::default(),
         desktop: None,
         stylesheet: None,
+        files: Vec::new(),
     }
 }
 
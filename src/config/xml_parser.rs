@@ -1,25 +1,73 @@
 use roxmltree::{Document, Node};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DesktopConfig {
     pub bars: Vec<BarConfig>,
     pub workspaces: Vec<WorkspaceConfig>,
+    /// Rename workspaces after the apps on them, see `<workspaces auto-name>`
+    #[serde(default)]
+    pub workspace_auto_name: bool,
+    /// Give every app a workspace of its own, see `<workspaces per-app>`
+    #[serde(default)]
+    pub workspace_per_app: bool,
     pub window_rules: Vec<WindowRuleConfig>,
     pub layout: LayoutSettings,
     pub animations: AnimationSettings,
+    #[serde(default)]
+    pub performance: PerformanceSettings,
+    #[serde(default)]
+    pub hotkey_passthrough: Option<HotkeyPassthrough>,
+    #[serde(default)]
+    pub chords: ChordSettings,
+    #[serde(default)]
+    pub outputs: Vec<OutputConfig>,
+    #[serde(default)]
+    pub cursor: CursorSettings,
+    #[serde(default)]
+    pub pointer: PointerSettings,
+    #[serde(default)]
+    pub night_light: NightLightSettings,
+    #[serde(default)]
+    pub presentation: PresentationSettings,
+    #[serde(default)]
+    pub web_ui: Option<WebUiSettings>,
+    #[serde(default)]
+    pub storage: StorageSettings,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Per-output settings, `<outputs><output name="eDP-1" ... /></outputs>`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OutputConfig {
+    /// Connector name, e.g. eDP-1 or DP-2
+    pub name: String,
+    /// ICC profile to color-correct this output with
+    pub icc_profile: Option<String>,
+    /// Requested bits per channel, 8 or 10. The DRM backend only scans
+    /// out 8 bits so far.
+    pub bit_depth: Option<u32>,
+    /// Drive the output in HDR (PQ) mode; implies 10-bit, so not
+    /// supported yet either
+    pub hdr: bool,
+    /// HiDPI scale, e.g. 1.5 or 2; fractional values are allowed
+    pub scale: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BarConfig {
     pub id: String,
     pub position: Position,
     pub height: u32,
     pub class: String,
     pub widgets: Vec<Widget>,
+    /// Output the bar is shown on; without one it is shown on every output
+    #[serde(default)]
+    pub output: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum Position {
     Top,
     Bottom,
@@ -27,24 +75,60 @@ pub enum Position {
     Right,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum Widget {
-    Workspaces { display: String },
-    WindowTitle { max_width: Option<u32> },
+    Workspaces {
+        display: String,
+    },
+    WindowTitle {
+        max_width: Option<u32>,
+    },
     SystemTray,
-    Clock { format: String },
-    Spacer { flex: u32 },
+    Clock {
+        format: String,
+        /// First column of the calendar popup, 0 for Monday to 6 for Sunday
+        #[serde(default)]
+        first_weekday: u32,
+    },
+    Spacer {
+        flex: u32,
+    },
+    /// `<left>`, `<center>` or `<right>`: widgets drawn together at that
+    /// part of the bar
+    Group {
+        align: Align,
+        widgets: Vec<Widget>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WorkspaceConfig {
     pub id: u32,
     pub name: String,
+    /// Layout the workspace starts with, from `layout` or `default-layout`
     pub layout: String,
     pub split_ratio: Option<f32>,
+    /// Output whose bars list the workspace; without one, every bar does
+    #[serde(default)]
+    pub output: Option<String>,
+    /// `<gaps inner="..." outer="..."/>` in place of the layout's gaps
+    #[serde(default)]
+    pub gaps_inner: Option<u32>,
+    #[serde(default)]
+    pub gaps_outer: Option<u32>,
+    /// app_ids from `<assign app-id="..."/>` whose windows always open here
+    #[serde(default)]
+    pub assign: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WindowRuleConfig {
     pub app_id: Option<String>,
     pub title: Option<String>,
@@ -52,9 +136,30 @@ pub struct WindowRuleConfig {
     pub floating: Option<bool>,
     pub sticky: Option<bool>,
     pub class: Option<String>,
+    /// "output" or "container", overrides the layout's fullscreen mode
+    #[serde(default)]
+    pub fullscreen: Option<String>,
+    /// Matches windows declaring this wp_content_type_v1 type
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// "false" stops the window's animations
+    #[serde(default)]
+    pub animations: Option<bool>,
+    /// "true" scans the window out directly while it is fullscreen
+    #[serde(default)]
+    pub direct_scanout: Option<bool>,
+    /// "true" lets the window present without vsync when it asks to
+    #[serde(default)]
+    pub allow_tearing: Option<bool>,
+    /// "true" asks before closing the window with the close binding
+    #[serde(default)]
+    pub confirm_close: Option<bool>,
+    /// Area of a `display: grid` workspace the window goes in
+    #[serde(default)]
+    pub grid_area: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LayoutSettings {
     pub gaps_outer: u32,
     pub gaps_inner: u32,
@@ -63,14 +168,199 @@ pub struct LayoutSettings {
     pub floating_default_width: u32,
     pub floating_default_height: u32,
     pub center_new_windows: bool,
+    /// Pixels of a floating window, across and down, that always stay on
+    /// the output
+    pub floating_min_visible: u32,
+    /// Whether clicking a floating window brings it to the top
+    pub click_to_raise: bool,
+    /// How floating windows follow a Super+drag: "live", or "wireframe" to
+    /// draw an outline and resize the window once on release
+    #[serde(default)]
+    pub drag_mode: String,
+    /// What fullscreen windows fill: "output" or "container" (their tile)
+    #[serde(default)]
+    pub fullscreen_mode: String,
+    /// Window slots of the tiling layout, the `<row>` or `<column>` inside
+    /// `<tiling>`; without one, tiles sit side by side
+    #[serde(default)]
+    pub flex: Option<FlexContainer>,
+    /// How long a relayout waits for resized windows to redraw before
+    /// moving everything, from `<transactions timeout="...">`; 0 moves
+    /// windows right away
+    #[serde(default)]
+    pub transaction_timeout_ms: u32,
+}
+
+/// A `<row>` (children side by side) or `<column>` (children stacked) of
+/// the flex layout. A container without children is a slot for a window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FlexContainer {
+    pub direction: FlexDirection,
+    /// Share of the parent container, relative to its siblings
+    pub flex: f32,
+    pub children: Vec<FlexContainer>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum FlexDirection {
+    Row,
+    Column,
+}
+
+/// `<cursor hide="true" hide-after="3000ms" hide-while-typing="true" />`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct CursorSettings {
+    /// Hide the cursor at all; off by default
+    pub hide: bool,
+    /// Pointer inactivity before the cursor is hidden, 0 means never
+    pub hide_after_ms: u32,
+    /// Hide the cursor on key presses until the pointer moves again
+    pub hide_while_typing: bool,
+    /// xcursor theme, XCURSOR_THEME when unset
+    pub theme: Option<String>,
+    /// Cursor size in pixels, XCURSOR_SIZE when unset
+    pub size: Option<u32>,
+}
+
+/// `<pointer edge-resistance="30"><barrier output="DP-1" edge="left"/></pointer>`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PointerSettings {
+    /// Pixels the pointer has to be pushed past an edge shared by two
+    /// outputs before it crosses, 0 disables
+    pub edge_resistance: u32,
+    pub barriers: Vec<BarrierConfig>,
+    /// Center the pointer on the window focused by a keybinding
+    pub warp_pointer: bool,
+    /// Center the pointer on the output focused by a keybinding
+    pub warp_to_output: bool,
+}
+
+/// `<night-light enabled="true" night-temperature="4000" sunset="19:30"
+/// sunrise="07:00" transition="45" />`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct NightLightSettings {
+    pub enabled: bool,
+    /// Color temperatures in Kelvin; 6500 leaves colors as they are
+    pub day_temperature: u32,
+    pub night_temperature: u32,
+    /// Local times, in minutes after midnight, the fades start at
+    pub sunset: u32,
+    pub sunrise: u32,
+    /// How long each fade between the temperatures takes
+    pub transition_minutes: u32,
+}
+
+/// `<presentation-mode hide-bar="true" on-enter="makoctl mode -a do-not-disturb"
+/// on-exit="makoctl mode -r do-not-disturb" />`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PresentationSettings {
+    pub hide_bar: bool,
+    /// Commands run when presentation mode is turned on and off, usually
+    /// to put the notification daemon into do-not-disturb
+    pub on_enter: Option<String>,
+    pub on_exit: Option<String>,
+}
+
+/// `<web-ui port="8377" />` serves the settings page on localhost
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WebUiSettings {
+    pub port: u16,
+}
+
+/// `<storage cache-size="256MB" />`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct StorageSettings {
+    /// Most the cache directory may hold, in megabytes
+    pub cache_size_mb: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self { cache_size_mb: 256 }
+    }
+}
+
+/// An output edge the pointer can never leave through
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BarrierConfig {
+    pub output: String,
+    /// "left", "right", "top" or "bottom"
+    pub edge: String,
+}
+
+/// Send Super combos that match no keybinding to an external hotkey daemon
+/// instead of the focused client
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HotkeyPassthrough {
+    /// Run for every unbound combo; `{combo}` becomes e.g. `Super+Shift+a`
+    pub command: Option<String>,
+}
+
+/// `<chords timeout="1s" hint="true" />`, for keybindings that are a
+/// sequence of keys
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ChordSettings {
+    /// How long to wait for the next key of a chord
+    pub timeout_ms: u32,
+    /// Show the keys that can come next
+    pub hint: bool,
+}
+
+impl Default for ChordSettings {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 1000,
+            hint: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PerformanceSettings {
+    /// Log a warning when a frame takes longer than this (0 disables)
+    pub frame_warning_threshold_ms: u32,
+    /// Render frame rate cap, 0 means uncapped. Unset follows the output's
+    /// refresh rate.
+    pub max_fps: Option<u32>,
+    /// Per-output overrides of `max_fps`, keyed by connector name
+    pub output_max_fps: HashMap<String, u32>,
+    /// Frame rate with no windows mapped and nothing animating
+    pub idle_fps: u32,
+    /// "on", "off" or "auto" (on while running from battery). Turns off
+    /// expensive effects such as backdrop blur.
+    pub low_power: String,
+    /// `<metrics port="9477" />` serves Prometheus metrics on localhost
+    pub metrics_port: Option<u16>,
+    /// Warn when the event loop is stuck in one stage longer than this
+    /// (0 disables the watchdog)
+    pub watchdog_threshold_ms: u32,
+    /// Stop JS callbacks that run longer than this (0 never does)
+    pub js_time_limit_ms: u32,
+}
+
+impl PerformanceSettings {
+    /// Frame rate cap for an output, 0 means uncapped and `None` means the
+    /// output's refresh rate
+    pub fn max_fps_for(&self, output: &str) -> Option<u32> {
+        self.output_max_fps.get(output).copied().or(self.max_fps)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AnimationSettings {
     pub enabled: bool,
     pub window_open_duration: u32,
     pub window_close_duration: u32,
     pub workspace_switch_duration: u32,
+    /// Bounce the bar's workspace button while a window on it is urgent
+    pub urgent_bounce: bool,
 }
 
 pub fn parse_desktop_xml(xml_content: &str) -> Result<DesktopConfig, String> {
@@ -85,9 +375,21 @@ pub fn parse_desktop_xml(xml_content: &str) -> Result<DesktopConfig, String> {
     let mut config = DesktopConfig {
         bars: Vec::new(),
         workspaces: Vec::new(),
+        workspace_auto_name: false,
+        workspace_per_app: false,
         window_rules: Vec::new(),
         layout: LayoutSettings::default(),
         animations: AnimationSettings::default(),
+        performance: PerformanceSettings::default(),
+        hotkey_passthrough: None,
+        chords: ChordSettings::default(),
+        outputs: Vec::new(),
+        cursor: CursorSettings::default(),
+        pointer: PointerSettings::default(),
+        night_light: NightLightSettings::default(),
+        presentation: PresentationSettings::default(),
+        web_ui: None,
+        storage: StorageSettings::default(),
     };
 
     for child in root.children().filter(|n| n.is_element()) {
@@ -99,6 +401,14 @@ pub fn parse_desktop_xml(xml_content: &str) -> Result<DesktopConfig, String> {
             }
             "workspaces" => {
                 config.workspaces = parse_workspaces(&child);
+                config.workspace_auto_name = child
+                    .attribute("auto-name")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(false);
+                config.workspace_per_app = child
+                    .attribute("per-app")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(false);
             }
             "window-rules" => {
                 config.window_rules = parse_window_rules(&child);
@@ -109,6 +419,57 @@ pub fn parse_desktop_xml(xml_content: &str) -> Result<DesktopConfig, String> {
             "animations" => {
                 config.animations = parse_animations(&child);
             }
+            "performance" => {
+                config.performance = parse_performance(&child);
+            }
+            "outputs" => {
+                config.outputs = parse_outputs(&child);
+            }
+            "pointer" => {
+                config.pointer = parse_pointer(&child);
+            }
+            "cursor" => {
+                config.cursor = parse_cursor(&child);
+            }
+            "night-light" => {
+                config.night_light = parse_night_light(&child);
+            }
+            "presentation-mode" => {
+                config.presentation = PresentationSettings {
+                    hide_bar: child
+                        .attribute("hide-bar")
+                        .and_then(|h| h.parse().ok())
+                        .unwrap_or(false),
+                    on_enter: child.attribute("on-enter").map(|s| s.to_string()),
+                    on_exit: child.attribute("on-exit").map(|s| s.to_string()),
+                };
+            }
+            "storage" => {
+                if let Some(size) = child.attribute("cache-size").and_then(parse_size_mb) {
+                    config.storage.cache_size_mb = size;
+                }
+            }
+            "web-ui" => {
+                config.web_ui = Some(WebUiSettings {
+                    port: child
+                        .attribute("port")
+                        .and_then(|p| p.parse().ok())
+                        .unwrap_or(8377),
+                });
+            }
+            "hotkey-passthrough" => {
+                config.hotkey_passthrough = Some(HotkeyPassthrough {
+                    command: child.attribute("command").map(|s| s.to_string()),
+                });
+            }
+            "chords" => {
+                if let Some(timeout) = child.attribute("timeout").and_then(parse_duration_ms) {
+                    config.chords.timeout_ms = timeout;
+                }
+                if let Some(hint) = child.attribute("hint").and_then(|h| h.parse().ok()) {
+                    config.chords.hint = hint;
+                }
+            }
             _ => {}
         }
     }
@@ -130,6 +491,7 @@ fn parse_bar(node: &Node) -> Option<BarConfig> {
         .and_then(|h| h.parse().ok())
         .unwrap_or(30);
     let class = node.attribute("class").unwrap_or("bar").to_string();
+    let output = node.attribute("output").map(|o| o.to_string());
 
     let mut widgets = Vec::new();
     for child in node.children().filter(|n| n.is_element()) {
@@ -144,6 +506,7 @@ fn parse_bar(node: &Node) -> Option<BarConfig> {
         height,
         class,
         widgets,
+        output,
     })
 }
 
@@ -158,6 +521,10 @@ fn parse_widget(node: &Node) -> Option<Widget> {
         "system-tray" => Some(Widget::SystemTray),
         "clock" => Some(Widget::Clock {
             format: node.attribute("format").unwrap_or("%H:%M").to_string(),
+            first_weekday: node
+                .attribute("first-day-of-week")
+                .and_then(parse_weekday)
+                .unwrap_or(0),
         }),
         "spacer" => Some(Widget::Spacer {
             flex: node
@@ -165,10 +532,32 @@ fn parse_widget(node: &Node) -> Option<Widget> {
                 .and_then(|f| f.parse().ok())
                 .unwrap_or(1),
         }),
+        group @ ("left" | "center" | "right") => Some(Widget::Group {
+            align: match group {
+                "left" => Align::Left,
+                "center" => Align::Center,
+                _ => Align::Right,
+            },
+            widgets: node
+                .children()
+                .filter(|n| n.is_element())
+                .filter_map(|child| parse_widget(&child))
+                .collect(),
+        }),
         _ => None,
     }
 }
 
+/// Weekday index, 0 for Monday through 6 for Sunday, from a name such as
+/// "sunday" or "sun"
+pub(super) fn parse_weekday(name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    ["mon", "tue", "wed", "thu", "fri", "sat", "sun"]
+        .iter()
+        .position(|day| name.starts_with(day))
+        .map(|index| index as u32)
+}
+
 fn parse_workspaces(node: &Node) -> Vec<WorkspaceConfig> {
     node.children()
         .filter(|n| n.is_element() && n.tag_name().name() == "workspace")
@@ -178,7 +567,11 @@ fn parse_workspaces(node: &Node) -> Vec<WorkspaceConfig> {
                 .attribute("name")
                 .unwrap_or(&format!("{}", id))
                 .to_string();
-            let layout = ws.attribute("layout").unwrap_or("tiling").to_string();
+            let layout = ws
+                .attribute("default-layout")
+                .or(ws.attribute("layout"))
+                .unwrap_or("tiling")
+                .to_string();
 
             let split_ratio = ws
                 .children()
@@ -186,11 +579,28 @@ fn parse_workspaces(node: &Node) -> Vec<WorkspaceConfig> {
                 .and_then(|n| n.text())
                 .and_then(|t| t.parse().ok());
 
+            let gaps = ws.children().find(|n| n.tag_name().name() == "gaps");
+            let gap = |side: &str| {
+                gaps.and_then(|g| g.attribute(side))
+                    .and_then(|g| g.parse().ok())
+            };
+
+            let assign = ws
+                .children()
+                .filter(|n| n.tag_name().name() == "assign")
+                .filter_map(|n| n.attribute("app-id"))
+                .map(|app_id| app_id.to_string())
+                .collect();
+
             Some(WorkspaceConfig {
                 id,
                 name,
                 layout,
                 split_ratio,
+                output: ws.attribute("output").map(|o| o.to_string()),
+                gaps_inner: gap("inner"),
+                gaps_outer: gap("outer"),
+                assign,
             })
         })
         .collect()
@@ -206,6 +616,15 @@ fn parse_window_rules(node: &Node) -> Vec<WindowRuleConfig> {
             floating: rule.attribute("floating").and_then(|f| f.parse().ok()),
             sticky: rule.attribute("sticky").and_then(|s| s.parse().ok()),
             class: rule.attribute("class").map(|s| s.to_string()),
+            fullscreen: rule.attribute("fullscreen").map(|s| s.to_string()),
+            content_type: rule.attribute("content-type").map(|s| s.to_string()),
+            animations: rule.attribute("animations").and_then(|a| a.parse().ok()),
+            direct_scanout: rule
+                .attribute("direct-scanout")
+                .and_then(|d| d.parse().ok()),
+            allow_tearing: rule.attribute("allow-tearing").and_then(|t| t.parse().ok()),
+            confirm_close: rule.attribute("confirm-close").and_then(|c| c.parse().ok()),
+            grid_area: rule.attribute("grid-area").map(|s| s.to_string()),
         })
         .collect()
 }
@@ -227,6 +646,20 @@ fn parse_layout(node: &Node) -> LayoutSettings {
                         .unwrap_or(10);
                 }
 
+                // "auto" splits dwindle tiles across their longer side,
+                // "alternate" flips direction at each level
+                if let Some(mode) = child
+                    .children()
+                    .find(|n| n.tag_name().name() == "split-mode")
+                    .and_then(|n| n.attribute("default"))
+                {
+                    settings.split_mode = mode.to_string();
+                }
+
+                if let Some(flex) = child.children().find_map(|n| parse_flex_container(&n)) {
+                    settings.flex = Some(flex);
+                }
+
                 if let Some(ratio) = child
                     .children()
                     .find(|n| n.tag_name().name() == "master-ratio")
@@ -259,6 +692,42 @@ fn parse_layout(node: &Node) -> LayoutSettings {
                 {
                     settings.center_new_windows = center;
                 }
+
+                if let Some(pixels) = child
+                    .children()
+                    .find(|n| n.tag_name().name() == "min-visible")
+                    .and_then(|n| n.text())
+                    .and_then(|t| t.parse().ok())
+                {
+                    settings.floating_min_visible = pixels;
+                }
+
+                if let Some(raise) = child
+                    .children()
+                    .find(|n| n.tag_name().name() == "click-to-raise")
+                    .and_then(|n| n.text())
+                    .and_then(|t| t.parse().ok())
+                {
+                    settings.click_to_raise = raise;
+                }
+
+                if let Some(mode) = child
+                    .children()
+                    .find(|n| n.tag_name().name() == "drag")
+                    .and_then(|n| n.attribute("mode"))
+                {
+                    settings.drag_mode = mode.to_string();
+                }
+            }
+            "fullscreen" => {
+                if let Some(mode) = child.attribute("mode") {
+                    settings.fullscreen_mode = mode.to_string();
+                }
+            }
+            "transactions" => {
+                if let Some(timeout) = child.attribute("timeout") {
+                    settings.transaction_timeout_ms = parse_duration(Some(timeout));
+                }
             }
             _ => {}
         }
@@ -267,6 +736,26 @@ fn parse_layout(node: &Node) -> LayoutSettings {
     settings
 }
 
+fn parse_flex_container(node: &Node) -> Option<FlexContainer> {
+    let direction = match node.tag_name().name() {
+        "row" => FlexDirection::Row,
+        "column" => FlexDirection::Column,
+        _ => return None,
+    };
+    Some(FlexContainer {
+        direction,
+        flex: node
+            .attribute("flex")
+            .and_then(|f| f.parse().ok())
+            .filter(|f: &f32| *f > 0.0)
+            .unwrap_or(1.0),
+        children: node
+            .children()
+            .filter_map(|n| parse_flex_container(&n))
+            .collect(),
+    })
+}
+
 fn parse_animations(node: &Node) -> AnimationSettings {
     AnimationSettings {
         enabled: node
@@ -288,13 +777,219 @@ fn parse_animations(node: &Node) -> AnimationSettings {
                 .find(|n| n.tag_name().name() == "workspace-switch")
                 .and_then(|n| n.attribute("duration")),
         ),
+        urgent_bounce: node
+            .children()
+            .find(|n| n.tag_name().name() == "urgent-bounce")
+            .and_then(|n| n.attribute("enabled"))
+            .and_then(|e| e.parse().ok())
+            .unwrap_or(true),
+    }
+}
+
+fn parse_outputs(node: &Node) -> Vec<OutputConfig> {
+    node.children()
+        .filter(|n| n.is_element() && n.tag_name().name() == "output")
+        .filter_map(|n| {
+            Some(OutputConfig {
+                name: n.attribute("name")?.to_string(),
+                icc_profile: n.attribute("icc-profile").map(|s| s.to_string()),
+                bit_depth: n.attribute("bit-depth").and_then(|s| s.parse().ok()),
+                hdr: n
+                    .attribute("hdr")
+                    .and_then(|h| h.parse().ok())
+                    .unwrap_or(false),
+                scale: n
+                    .attribute("scale")
+                    .and_then(|s| s.parse().ok())
+                    .filter(|scale: &f64| *scale > 0.0),
+            })
+        })
+        .collect()
+}
+
+fn parse_cursor(node: &Node) -> CursorSettings {
+    let defaults = CursorSettings::default();
+    CursorSettings {
+        hide: node
+            .attribute("hide")
+            .and_then(|h| h.parse().ok())
+            .unwrap_or(defaults.hide),
+        hide_after_ms: node
+            .attribute("hide-after")
+            .map(|d| parse_duration(Some(d)))
+            .unwrap_or(defaults.hide_after_ms),
+        hide_while_typing: node
+            .attribute("hide-while-typing")
+            .and_then(|h| h.parse().ok())
+            .unwrap_or(defaults.hide_while_typing),
+        theme: node.attribute("theme").map(|t| t.to_string()),
+        size: node
+            .attribute("size")
+            .and_then(|s| s.trim_end_matches("px").parse().ok()),
+    }
+}
+
+fn parse_night_light(node: &Node) -> NightLightSettings {
+    let defaults = NightLightSettings::default();
+    let temperature = |name: &str, default: u32| {
+        node.attribute(name)
+            .and_then(|t| t.trim_end_matches('K').parse().ok())
+            .unwrap_or(default)
+    };
+    let time = |name: &str, default: u32| {
+        node.attribute(name)
+            .and_then(parse_time_of_day)
+            .unwrap_or(default)
+    };
+
+    NightLightSettings {
+        enabled: node
+            .attribute("enabled")
+            .and_then(|e| e.parse().ok())
+            .unwrap_or(defaults.enabled),
+        day_temperature: temperature("day-temperature", defaults.day_temperature),
+        night_temperature: temperature("night-temperature", defaults.night_temperature),
+        sunset: time("sunset", defaults.sunset),
+        sunrise: time("sunrise", defaults.sunrise),
+        transition_minutes: node
+            .attribute("transition")
+            .and_then(|t| t.trim_end_matches("min").parse().ok())
+            .unwrap_or(defaults.transition_minutes),
+    }
+}
+
+/// Minutes after midnight of `HH:MM`
+pub(super) fn parse_time_of_day(time: &str) -> Option<u32> {
+    let (hours, minutes) = time.split_once(':')?;
+    let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+fn parse_pointer(node: &Node) -> PointerSettings {
+    PointerSettings {
+        edge_resistance: node
+            .attribute("edge-resistance")
+            .and_then(|r| r.trim_end_matches("px").parse().ok())
+            .unwrap_or(0),
+        barriers: node
+            .children()
+            .filter(|n| n.is_element() && n.tag_name().name() == "barrier")
+            .filter_map(|n| {
+                Some(BarrierConfig {
+                    output: n.attribute("output")?.to_string(),
+                    edge: n.attribute("edge")?.to_string(),
+                })
+            })
+            .collect(),
+        warp_pointer: node
+            .attribute("warp-pointer")
+            .and_then(|w| w.parse().ok())
+            .unwrap_or(false),
+        warp_to_output: node
+            .attribute("warp-to-output")
+            .and_then(|w| w.parse().ok())
+            .unwrap_or(false),
+    }
+}
+
+fn parse_performance(node: &Node) -> PerformanceSettings {
+    let mut settings = PerformanceSettings::default();
+    // `<watchdog interrupt-js>` from before `<js time-limit>`, which wins
+    let mut interrupt_js = None;
+    let mut time_limit_set = false;
+
+    for child in node.children().filter(|n| n.is_element()) {
+        match child.tag_name().name() {
+            "frame-warning" => {
+                if let Some(threshold) = child.attribute("threshold") {
+                    settings.frame_warning_threshold_ms = parse_duration(Some(threshold));
+                }
+            }
+            "low-power" => {
+                if let Some(mode) = child.attribute("mode") {
+                    settings.low_power = mode.to_string();
+                }
+            }
+            "watchdog" => {
+                if let Some(threshold) = child.attribute("threshold") {
+                    settings.watchdog_threshold_ms = parse_duration(Some(threshold));
+                }
+                if let Some(limit) = child.attribute("interrupt-js") {
+                    eprintln!(
+                        "⚠️  <watchdog interrupt-js> is deprecated, use <js time-limit=\"{}\" /> (`webwm config migrate` moves it)",
+                        limit
+                    );
+                    interrupt_js = Some(parse_duration(Some(limit)));
+                }
+            }
+            "js" => {
+                if let Some(limit) = child.attribute("time-limit") {
+                    settings.js_time_limit_ms = parse_duration(Some(limit));
+                    time_limit_set = true;
+                }
+            }
+            "metrics" => {
+                settings.metrics_port = Some(
+                    child
+                        .attribute("port")
+                        .and_then(|p| p.parse().ok())
+                        .unwrap_or(9477),
+                );
+            }
+            "frame-rate" => {
+                if let Some(max) = child.attribute("max").and_then(parse_fps) {
+                    match child.attribute("output") {
+                        Some(output) => {
+                            settings.output_max_fps.insert(output.to_string(), max);
+                        }
+                        None => settings.max_fps = Some(max),
+                    }
+                }
+                if let Some(idle) = child.attribute("idle").and_then(|s| s.parse::<u32>().ok()) {
+                    settings.idle_fps = idle.max(1);
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(limit) = interrupt_js.filter(|_| !time_limit_set) {
+        settings.js_time_limit_ms = limit;
+    }
+
+    settings
+}
+
+/// "256MB", "1GB" or a plain number of megabytes
+pub(super) fn parse_size_mb(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Some(gb) = value.strip_suffix("GB") {
+        return gb.trim().parse::<u64>().ok().map(|gb| gb * 1024);
+    }
+    value.trim_end_matches("MB").trim().parse().ok()
+}
+
+/// "60", "120", ... or "uncapped" (0)
+pub(super) fn parse_fps(value: &str) -> Option<u32> {
+    if value == "uncapped" {
+        return Some(0);
+    }
+    value.parse().ok()
+}
+
+/// Milliseconds of "150ms", "2s" or a plain number of milliseconds
+pub(super) fn parse_duration_ms(duration: &str) -> Option<u32> {
+    match duration.strip_suffix("ms") {
+        Some(ms) => ms.parse().ok(),
+        None => duration.strip_suffix('s').map_or_else(
+            || duration.parse().ok(),
+            |secs| secs.parse::<u32>().ok().map(|s| s * 1000),
+        ),
     }
 }
 
+/// `parse_duration_ms`, or 200 if unreadable
 fn parse_duration(duration_str: Option<&str>) -> u32 {
-    duration_str
-        .and_then(|s| s.trim_end_matches("ms").parse().ok())
-        .unwrap_or(200)
+    duration_str.and_then(parse_duration_ms).unwrap_or(200)
 }
 
 impl Default for LayoutSettings {
@@ -307,6 +1002,52 @@ impl Default for LayoutSettings {
             floating_default_width: 800,
             floating_default_height: 600,
             center_new_windows: true,
+            floating_min_visible: 48,
+            click_to_raise: true,
+            drag_mode: "live".to_string(),
+            fullscreen_mode: "output".to_string(),
+            flex: None,
+            transaction_timeout_ms: 150,
+        }
+    }
+}
+
+impl Default for PerformanceSettings {
+    fn default() -> Self {
+        Self {
+            frame_warning_threshold_ms: 33,
+            max_fps: None,
+            output_max_fps: HashMap::new(),
+            idle_fps: 1,
+            low_power: "auto".to_string(),
+            metrics_port: None,
+            watchdog_threshold_ms: 500,
+            js_time_limit_ms: super::DEFAULT_TIME_LIMIT_MS,
+        }
+    }
+}
+
+impl Default for CursorSettings {
+    fn default() -> Self {
+        Self {
+            hide: false,
+            hide_after_ms: 3000,
+            hide_while_typing: true,
+            theme: None,
+            size: None,
+        }
+    }
+}
+
+impl Default for NightLightSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            day_temperature: 6500,
+            night_temperature: 4000,
+            sunset: 19 * 60,
+            sunrise: 7 * 60,
+            transition_minutes: 45,
         }
     }
 }
@@ -318,6 +1059,44 @@ impl Default for AnimationSettings {
             window_open_duration: 200,
             window_close_duration: 150,
             workspace_switch_duration: 250,
+            urgent_bounce: true,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn performance(xml: &str) -> PerformanceSettings {
+        parse_desktop_xml(&format!(
+            "<desktop><performance>{}</performance></desktop>",
+            xml
+        ))
+        .unwrap()
+        .performance
+    }
+
+    #[test]
+    fn test_interrupt_js_alias() {
+        let settings = performance(r#"<watchdog threshold="1s" interrupt-js="5s" />"#);
+        assert_eq!(settings.watchdog_threshold_ms, 1000);
+        assert_eq!(settings.js_time_limit_ms, 5000);
+
+        // Wherever it is, <js time-limit> wins
+        let settings =
+            performance(r#"<js time-limit="2s" /><watchdog threshold="1s" interrupt-js="5s" />"#);
+        assert_eq!(settings.js_time_limit_ms, 2000);
+    }
+
+    #[test]
+    fn test_frame_rate_defaults_to_refresh() {
+        let settings = performance("");
+        assert_eq!(settings.max_fps_for("DP-1"), None);
+
+        let settings =
+            performance(r#"<frame-rate max="uncapped" /><frame-rate output="DP-1" max="144" />"#);
+        assert_eq!(settings.max_fps_for("DP-1"), Some(144));
+        assert_eq!(settings.max_fps_for("HDMI-A-1"), Some(0));
+    }
+}
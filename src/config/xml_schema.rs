@@ -0,0 +1,767 @@
+// desktop.xml schema
+//
+// The parser is lenient: it skips what it doesn't know and falls back to
+// defaults for values it can't read, so a typo like `heigth="40"` goes
+// unnoticed. `webwm config` checks desktop.xml against the schema below
+// instead, reporting unknown elements and attributes, elements in the
+// wrong place (such as a widget outside a bar, or a group in a group),
+// missing required attributes, values that don't read or are out of range
+// and duplicate ids, each with the line and column it's at.
+//
+// The schema lists every element the parser reads; a new setting in
+// `parse_desktop_xml` goes here too.
+
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::fmt;
+
+use super::migrate::CONFIG_VERSION;
+use super::xml_parser::{
+    parse_duration_ms, parse_fps, parse_size_mb, parse_time_of_day, parse_weekday,
+};
+
+/// A problem found in desktop.xml, positioned 1-based like editors do
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub line: u32,
+    pub column: u32,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// What an attribute or an element's text holds
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Text,
+    Bool,
+    /// A whole number within the range
+    Int(i64, i64),
+    Float(f64, f64),
+    /// "150ms", "2s" or milliseconds
+    Duration,
+    /// Pixels, with or without "px"
+    Pixels,
+    /// Kelvin, with or without "K"
+    Temperature,
+    /// Minutes, with or without "min"
+    Minutes,
+    /// "256MB", "1GB" or megabytes
+    Size,
+    /// A frame rate or "uncapped"
+    Fps,
+    Weekday,
+    /// HH:MM
+    TimeOfDay,
+    OneOf(&'static [&'static str]),
+}
+
+impl Value {
+    fn accepts(&self, value: &str) -> bool {
+        let int_in = |value: &str, min: i64, max: i64| {
+            value.parse::<i64>().is_ok_and(|n| (min..=max).contains(&n))
+        };
+        match *self {
+            Value::Text => true,
+            Value::Bool => value == "true" || value == "false",
+            Value::Int(min, max) => int_in(value, min, max),
+            Value::Float(min, max) => value.parse::<f64>().is_ok_and(|n| (min..=max).contains(&n)),
+            Value::Duration => parse_duration_ms(value).is_some(),
+            Value::Pixels => int_in(value.trim_end_matches("px"), 0, 10_000),
+            Value::Temperature => int_in(value.trim_end_matches('K'), 1000, 25_000),
+            Value::Minutes => int_in(value.trim_end_matches("min"), 0, 24 * 60),
+            Value::Size => parse_size_mb(value).is_some(),
+            Value::Fps => parse_fps(value).is_some(),
+            Value::Weekday => parse_weekday(value).is_some(),
+            Value::TimeOfDay => parse_time_of_day(value).is_some(),
+            Value::OneOf(options) => options.contains(&value),
+        }
+    }
+
+    fn expected(&self) -> String {
+        match *self {
+            Value::Text => "text".to_string(),
+            Value::Bool => "true or false".to_string(),
+            Value::Int(min, max) => format!("a whole number from {} to {}", min, max),
+            Value::Float(min, max) => format!("a number from {} to {}", min, max),
+            Value::Duration => "a duration like 150ms or 2s".to_string(),
+            Value::Pixels => "pixels like 24 or 24px".to_string(),
+            Value::Temperature => "a temperature from 1000K to 25000K".to_string(),
+            Value::Minutes => "minutes like 45min".to_string(),
+            Value::Size => "a size like 256MB or 1GB".to_string(),
+            Value::Fps => "a frame rate or uncapped".to_string(),
+            Value::Weekday => "a weekday like monday".to_string(),
+            Value::TimeOfDay => "a time like 19:00".to_string(),
+            Value::OneOf(options) => format!("one of {}", options.join(", ")),
+        }
+    }
+}
+
+struct Attr {
+    name: &'static str,
+    value: Value,
+    required: bool,
+}
+
+const fn attr(name: &'static str, value: Value) -> Attr {
+    Attr {
+        name,
+        value,
+        required: false,
+    }
+}
+
+const fn required(name: &'static str, value: Value) -> Attr {
+    Attr {
+        name,
+        value,
+        required: true,
+    }
+}
+
+struct Element {
+    name: &'static str,
+    attributes: &'static [Attr],
+    children: &'static [&'static Element],
+    /// What its text holds, for elements like `<master-ratio>0.55</master-ratio>`
+    text: Option<Value>,
+    /// Attribute no two siblings may share a value of
+    key: Option<&'static str>,
+}
+
+const fn element(
+    name: &'static str,
+    attributes: &'static [Attr],
+    children: &'static [&'static Element],
+) -> Element {
+    Element {
+        name,
+        attributes,
+        children,
+        text: None,
+        key: None,
+    }
+}
+
+const fn text_element(name: &'static str, value: Value) -> Element {
+    Element {
+        name,
+        attributes: &[],
+        children: &[],
+        text: Some(value),
+        key: None,
+    }
+}
+
+impl Element {
+    const fn keyed(mut self, key: &'static str) -> Self {
+        self.key = Some(key);
+        self
+    }
+}
+
+const LAYOUTS: &[&str] = &[
+    "tiling",
+    "floating",
+    "monocle",
+    "master-stack",
+    "master_stack",
+    "dwindle",
+    "bsp",
+    "tabbed",
+];
+const FULLSCREEN_MODES: &[&str] = &["output", "container"];
+const EDGES: &[&str] = &["top", "bottom", "left", "right"];
+const EASINGS: &[&str] = &["linear", "ease", "ease-in", "ease-out", "ease-in-out"];
+const RATIO: Value = Value::Float(0.1, 0.9);
+const GAP: Value = Value::Int(0, 500);
+
+// Bars and their widgets
+static WORKSPACES_WIDGET: Element = element("workspaces", &[attr("display", Value::Text)], &[]);
+static WINDOW_TITLE: Element = element(
+    "window-title",
+    &[attr("max-width", Value::Int(1, 10_000))],
+    &[],
+);
+static SYSTEM_TRAY: Element = element("system-tray", &[], &[]);
+static CLOCK: Element = element(
+    "clock",
+    &[
+        attr("format", Value::Text),
+        attr("first-day-of-week", Value::Weekday),
+    ],
+    &[],
+);
+static SPACER: Element = element("spacer", &[attr("flex", Value::Int(1, 100))], &[]);
+const WIDGETS: &[&Element] = &[
+    &WORKSPACES_WIDGET,
+    &WINDOW_TITLE,
+    &SYSTEM_TRAY,
+    &CLOCK,
+    &SPACER,
+];
+static LEFT: Element = element("left", &[], WIDGETS);
+static CENTER: Element = element("center", &[], WIDGETS);
+static RIGHT: Element = element("right", &[], WIDGETS);
+static BAR: Element = element(
+    "bar",
+    &[
+        required("id", Value::Text),
+        required("position", Value::OneOf(EDGES)),
+        attr("height", Value::Int(1, 500)),
+        attr("class", Value::Text),
+        attr("output", Value::Text),
+    ],
+    &[
+        &LEFT,
+        &CENTER,
+        &RIGHT,
+        &WORKSPACES_WIDGET,
+        &WINDOW_TITLE,
+        &SYSTEM_TRAY,
+        &CLOCK,
+        &SPACER,
+    ],
+)
+.keyed("id");
+
+// Workspaces
+static SPLIT_RATIO: Element = text_element("split-ratio", RATIO);
+static GAPS: Element = element("gaps", &[attr("inner", GAP), attr("outer", GAP)], &[]);
+static ASSIGN: Element = element("assign", &[required("app-id", Value::Text)], &[]);
+static WORKSPACE: Element = element(
+    "workspace",
+    &[
+        required("id", Value::Int(1, 1000)),
+        attr("name", Value::Text),
+        attr("default-layout", Value::OneOf(LAYOUTS)),
+        attr("layout", Value::OneOf(LAYOUTS)),
+        attr("output", Value::Text),
+    ],
+    &[&SPLIT_RATIO, &GAPS, &ASSIGN],
+)
+.keyed("id");
+static WORKSPACES: Element = element(
+    "workspaces",
+    &[attr("auto-name", Value::Bool), attr("per-app", Value::Bool)],
+    &[&WORKSPACE],
+);
+
+// Window rules
+static RULE: Element = element(
+    "rule",
+    &[
+        attr("app-id", Value::Text),
+        attr("title", Value::Text),
+        attr("workspace", Value::Int(1, 1000)),
+        attr("floating", Value::Bool),
+        attr("sticky", Value::Bool),
+        attr("class", Value::Text),
+        attr("fullscreen", Value::OneOf(FULLSCREEN_MODES)),
+        attr("content-type", Value::OneOf(&["photo", "video", "game"])),
+        attr("animations", Value::Bool),
+        attr("direct-scanout", Value::Bool),
+        attr("allow-tearing", Value::Bool),
+        attr("confirm-close", Value::Bool),
+        attr("grid-area", Value::Text),
+    ],
+    &[],
+);
+static WINDOW_RULES: Element = element("window-rules", &[], &[&RULE]);
+
+// Layout
+static SPLIT_MODE: Element = element(
+    "split-mode",
+    &[attr(
+        "default",
+        Value::OneOf(&["auto", "alternate", "manual"]),
+    )],
+    &[],
+);
+static MASTER_RATIO: Element = text_element("master-ratio", RATIO);
+static ROW: Element = element(
+    "row",
+    &[attr("flex", Value::Float(0.01, 100.0))],
+    &[&ROW, &COLUMN],
+);
+static COLUMN: Element = element(
+    "column",
+    &[attr("flex", Value::Float(0.01, 100.0))],
+    &[&ROW, &COLUMN],
+);
+static TILING: Element = element(
+    "tiling",
+    &[],
+    &[&GAPS, &SPLIT_MODE, &MASTER_RATIO, &ROW, &COLUMN],
+);
+static DEFAULT_SIZE: Element = element(
+    "default-size",
+    &[
+        attr("width", Value::Int(1, 10_000)),
+        attr("height", Value::Int(1, 10_000)),
+    ],
+    &[],
+);
+static CENTER_NEW_WINDOWS: Element = text_element("center-new-windows", Value::Bool);
+static MIN_VISIBLE: Element = text_element("min-visible", Value::Int(0, 10_000));
+static CLICK_TO_RAISE: Element = text_element("click-to-raise", Value::Bool);
+static DRAG: Element = element(
+    "drag",
+    &[attr("mode", Value::OneOf(&["live", "wireframe"]))],
+    &[],
+);
+static FLOATING: Element = element(
+    "floating",
+    &[],
+    &[
+        &DEFAULT_SIZE,
+        &CENTER_NEW_WINDOWS,
+        &MIN_VISIBLE,
+        &CLICK_TO_RAISE,
+        &DRAG,
+    ],
+);
+static FULLSCREEN: Element = element(
+    "fullscreen",
+    &[attr("mode", Value::OneOf(FULLSCREEN_MODES))],
+    &[],
+);
+static TRANSACTIONS: Element = element("transactions", &[attr("timeout", Value::Duration)], &[]);
+static LAYOUT: Element = element(
+    "layout",
+    &[],
+    &[&TILING, &FLOATING, &FULLSCREEN, &TRANSACTIONS],
+);
+
+// Animations
+const ANIMATION_ATTRIBUTES: &[Attr] = &[
+    attr("duration", Value::Duration),
+    attr("easing", Value::OneOf(EASINGS)),
+];
+static WINDOW_OPEN: Element = element("window-open", ANIMATION_ATTRIBUTES, &[]);
+static WINDOW_CLOSE: Element = element("window-close", ANIMATION_ATTRIBUTES, &[]);
+static WORKSPACE_SWITCH: Element = element("workspace-switch", ANIMATION_ATTRIBUTES, &[]);
+static URGENT_BOUNCE: Element = element("urgent-bounce", &[attr("enabled", Value::Bool)], &[]);
+static ANIMATIONS: Element = element(
+    "animations",
+    &[attr("enabled", Value::Bool)],
+    &[
+        &WINDOW_OPEN,
+        &WINDOW_CLOSE,
+        &WORKSPACE_SWITCH,
+        &URGENT_BOUNCE,
+    ],
+);
+
+// Performance
+static FRAME_WARNING: Element =
+    element("frame-warning", &[attr("threshold", Value::Duration)], &[]);
+static LOW_POWER: Element = element(
+    "low-power",
+    &[attr("mode", Value::OneOf(&["auto", "on", "off"]))],
+    &[],
+);
+static WATCHDOG: Element = element(
+    "watchdog",
+    &[
+        attr("threshold", Value::Duration),
+        // Deprecated for `<js time-limit>`, still read
+        attr("interrupt-js", Value::Duration),
+    ],
+    &[],
+);
+static JS: Element = element("js", &[attr("time-limit", Value::Duration)], &[]);
+static METRICS: Element = element("metrics", &[attr("port", Value::Int(1, 65535))], &[]);
+static FRAME_RATE: Element = element(
+    "frame-rate",
+    &[
+        attr("max", Value::Fps),
+        attr("idle", Value::Int(1, 1000)),
+        attr("output", Value::Text),
+    ],
+    &[],
+);
+static PERFORMANCE: Element = element(
+    "performance",
+    &[],
+    &[
+        &FRAME_WARNING,
+        &LOW_POWER,
+        &WATCHDOG,
+        &JS,
+        &METRICS,
+        &FRAME_RATE,
+    ],
+);
+
+// Outputs and input
+static OUTPUT: Element = element(
+    "output",
+    &[
+        required("name", Value::Text),
+        attr("icc-profile", Value::Text),
+        attr("bit-depth", Value::OneOf(&["8", "10"])),
+        attr("hdr", Value::Bool),
+        attr("scale", Value::Float(0.25, 4.0)),
+    ],
+    &[],
+)
+.keyed("name");
+static OUTPUTS: Element = element("outputs", &[], &[&OUTPUT]);
+static BARRIER: Element = element(
+    "barrier",
+    &[
+        required("output", Value::Text),
+        required("edge", Value::OneOf(EDGES)),
+    ],
+    &[],
+);
+static POINTER: Element = element(
+    "pointer",
+    &[
+        attr("edge-resistance", Value::Pixels),
+        attr("warp-pointer", Value::Bool),
+        attr("warp-to-output", Value::Bool),
+    ],
+    &[&BARRIER],
+);
+static CURSOR: Element = element(
+    "cursor",
+    &[
+        attr("hide", Value::Bool),
+        attr("hide-after", Value::Duration),
+        attr("hide-while-typing", Value::Bool),
+        attr("theme", Value::Text),
+        attr("size", Value::Pixels),
+    ],
+    &[],
+);
+
+// The rest
+static NIGHT_LIGHT: Element = element(
+    "night-light",
+    &[
+        attr("enabled", Value::Bool),
+        attr("day-temperature", Value::Temperature),
+        attr("night-temperature", Value::Temperature),
+        attr("sunset", Value::TimeOfDay),
+        attr("sunrise", Value::TimeOfDay),
+        attr("transition", Value::Minutes),
+    ],
+    &[],
+);
+static PRESENTATION_MODE: Element = element(
+    "presentation-mode",
+    &[
+        attr("hide-bar", Value::Bool),
+        attr("on-enter", Value::Text),
+        attr("on-exit", Value::Text),
+    ],
+    &[],
+);
+static STORAGE: Element = element("storage", &[attr("cache-size", Value::Size)], &[]);
+static WEB_UI: Element = element("web-ui", &[attr("port", Value::Int(1, 65535))], &[]);
+static HOTKEY_PASSTHROUGH: Element =
+    element("hotkey-passthrough", &[attr("command", Value::Text)], &[]);
+static CHORDS: Element = element(
+    "chords",
+    &[attr("timeout", Value::Duration), attr("hint", Value::Bool)],
+    &[],
+);
+
+static DESKTOP: Element = element(
+    "desktop",
+    &[attr("version", Value::Int(1, CONFIG_VERSION as i64))],
+    &[
+        &BAR,
+        &WORKSPACES,
+        &WINDOW_RULES,
+        &LAYOUT,
+        &ANIMATIONS,
+        &PERFORMANCE,
+        &OUTPUTS,
+        &POINTER,
+        &CURSOR,
+        &NIGHT_LIGHT,
+        &PRESENTATION_MODE,
+        &STORAGE,
+        &WEB_UI,
+        &HOTKEY_PASSTHROUGH,
+        &CHORDS,
+    ],
+);
+
+/// Whether `name` is an element anywhere in the schema, to tell a misplaced
+/// element from a misspelled one
+fn known_element(name: &str) -> bool {
+    fn visit(element: &Element, name: &str, seen: &mut Vec<*const Element>) -> bool {
+        element.children.iter().any(|&child| {
+            if child.name == name {
+                return true;
+            }
+            // Rows and columns nest in each other
+            if seen.contains(&(child as *const Element)) {
+                return false;
+            }
+            seen.push(child);
+            visit(child, name, seen)
+        })
+    }
+    visit(&DESKTOP, name, &mut Vec::new())
+}
+
+/// The closest of `options` to a misspelled `name`, if one is close enough
+fn suggestion<'a>(name: &str, options: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    options
+        .map(|option| (edit_distance(name, option), option))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, option)| option)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substituted = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+fn did_you_mean(suggestion: Option<&str>) -> String {
+    suggestion
+        .map(|s| format!(", did you mean '{}'?", s))
+        .unwrap_or_default()
+}
+
+struct Checker<'a, 'input> {
+    doc: &'a Document<'input>,
+    errors: Vec<SchemaError>,
+}
+
+impl<'a, 'input> Checker<'a, 'input> {
+    fn report(&mut self, position: usize, message: String) {
+        let pos = self.doc.text_pos_at(position);
+        self.errors.push(SchemaError {
+            line: pos.row,
+            column: pos.col,
+            message,
+        });
+    }
+
+    fn check(&mut self, node: Node, schema: &Element) {
+        let name = schema.name;
+
+        for attribute in node.attributes() {
+            let Some(known) = schema
+                .attributes
+                .iter()
+                .find(|a| a.name == attribute.name())
+            else {
+                let close = suggestion(attribute.name(), schema.attributes.iter().map(|a| a.name));
+                self.report(
+                    attribute.position(),
+                    format!(
+                        "Unknown attribute '{}' on <{}>{}",
+                        attribute.name(),
+                        name,
+                        did_you_mean(close)
+                    ),
+                );
+                continue;
+            };
+            if !known.value.accepts(attribute.value().trim()) {
+                self.report(
+                    attribute.position(),
+                    format!(
+                        "'{}' isn't a valid {} for <{}>, expected {}",
+                        attribute.value(),
+                        known.name,
+                        name,
+                        known.value.expected()
+                    ),
+                );
+            }
+        }
+        for missing in schema
+            .attributes
+            .iter()
+            .filter(|a| a.required && node.attribute(a.name).is_none())
+        {
+            self.report(
+                node.range().start,
+                format!("<{}> needs a '{}' attribute", name, missing.name),
+            );
+        }
+
+        let text: String = node
+            .children()
+            .filter(|n| n.is_text())
+            .filter_map(|n| n.text())
+            .collect();
+        let text = text.trim();
+        match schema.text {
+            Some(value) if !value.accepts(text) => self.report(
+                node.range().start,
+                format!(
+                    "'{}' isn't valid in <{}>, expected {}",
+                    text,
+                    name,
+                    value.expected()
+                ),
+            ),
+            None if !text.is_empty() => self.report(
+                node.range().start,
+                format!("<{}> doesn't take text, found '{}'", name, text),
+            ),
+            _ => {}
+        }
+
+        // Where each key was first seen, to point duplicates back at it
+        let mut keys: HashMap<(&str, &str), u32> = HashMap::new();
+        for child in node.children().filter(|n| n.is_element()) {
+            let child_name = child.tag_name().name();
+            let Some(child_schema) = schema.children.iter().find(|c| c.name == child_name) else {
+                let message = if known_element(child_name) {
+                    format!("<{}> doesn't belong in <{}>", child_name, name)
+                } else {
+                    let close = suggestion(child_name, schema.children.iter().map(|c| c.name));
+                    format!(
+                        "Unknown element <{}> in <{}>{}",
+                        child_name,
+                        name,
+                        did_you_mean(close)
+                    )
+                };
+                self.report(child.range().start, message);
+                continue;
+            };
+
+            if let Some(key) = child_schema.key {
+                if let Some(value) = child.attribute(key) {
+                    let line = self.doc.text_pos_at(child.range().start).row;
+                    match keys.get(&(child_name, value)) {
+                        Some(first) => {
+                            let message = format!(
+                                "Duplicate <{}> {} '{}', first used on line {}",
+                                child_name, key, value, first
+                            );
+                            self.report(child.range().start, message);
+                        }
+                        None => {
+                            keys.insert((child_name, value), line);
+                        }
+                    }
+                }
+            }
+
+            self.check(child, child_schema);
+        }
+    }
+}
+
+/// Every problem with `xml_content` as a desktop.xml, in document order
+pub fn validate_desktop_xml(xml_content: &str) -> Vec<SchemaError> {
+    let doc = match Document::parse(xml_content) {
+        Ok(doc) => doc,
+        Err(e) => {
+            let pos = e.pos();
+            return vec![SchemaError {
+                line: pos.row,
+                column: pos.col,
+                message: e.to_string(),
+            }];
+        }
+    };
+
+    let mut checker = Checker {
+        doc: &doc,
+        errors: Vec::new(),
+    };
+    let root = doc.root_element();
+    if root.tag_name().name() == DESKTOP.name {
+        checker.check(root, &DESKTOP);
+    } else {
+        checker.report(
+            root.range().start,
+            format!(
+                "Root element must be <desktop>, not <{}>",
+                root.tag_name().name()
+            ),
+        );
+    }
+    checker.errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shipped_config_is_valid() {
+        let xml = include_str!("../../config/desktop.xml");
+        assert_eq!(validate_desktop_xml(xml), Vec::new());
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        let xml = r#"<desktop>
+  <bar id="top" position="top" heigth="40">
+    <left>
+      <right><clock /></right>
+    </left>
+    <workspace id="1" />
+  </bar>
+  <workspaces>
+    <workspace id="1" />
+    <workspace id="1" name="again" />
+    <workspace name="no id" />
+  </workspaces>
+  <layout>
+    <tiling><master-ratio>2</master-ratio></tiling>
+  </layout>
+  <cursor hide="yes" />
+  <animation />
+</desktop>"#;
+        let errors: Vec<String> = validate_desktop_xml(xml)
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "2:32: Unknown attribute 'heigth' on <bar>, did you mean 'height'?",
+                "4:7: <right> doesn't belong in <left>",
+                "6:5: <workspace> doesn't belong in <bar>",
+                "10:5: Duplicate <workspace> id '1', first used on line 9",
+                "11:5: <workspace> needs a 'id' attribute",
+                "14:13: '2' isn't valid in <master-ratio>, expected a number from 0.1 to 0.9",
+                "16:11: 'yes' isn't a valid hide for <cursor>, expected true or false",
+                "17:3: Unknown element <animation> in <desktop>, did you mean 'animations'?",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_xml() {
+        let errors = validate_desktop_xml("<desktop>\n  <bar>\n</desktop>");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 3);
+
+        let errors = validate_desktop_xml("<config />");
+        assert_eq!(
+            errors[0].message,
+            "Root element must be <desktop>, not <config>"
+        );
+    }
+}
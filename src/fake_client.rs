@@ -0,0 +1,660 @@
+// Synthetic in-process Wayland client
+//
+// Creates a single xdg_toplevel backed by an SHM buffer and acks every
+// configure by attaching a buffer of the requested size. Used by the
+// benchmark mode and the integration test harness to put real windows
+// into the layout engine. It also has a data device, to set the selection
+// and see what gets offered back, can declare its content type and
+// presentation hint, can export its toplevel or parent it to another
+// client's through xdg-foreign, can mark itself a modal dialog, and can
+// open a popup from a positioner.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use wayland_client::{
+    delegate_noop, event_created_child,
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::{
+        wl_buffer, wl_compositor, wl_data_device, wl_data_device_manager, wl_data_offer,
+        wl_data_source, wl_registry, wl_seat, wl_shm, wl_shm_pool, wl_surface,
+    },
+    Connection, Dispatch, EventQueue, QueueHandle,
+};
+use wayland_protocols::wp::content_type::v1::client::{
+    wp_content_type_manager_v1, wp_content_type_v1,
+};
+use wayland_protocols::wp::tearing_control::v1::client::{
+    wp_tearing_control_manager_v1, wp_tearing_control_v1,
+};
+use wayland_protocols::xdg::dialog::v1::client::{xdg_dialog_v1, xdg_wm_dialog_v1};
+use wayland_protocols::xdg::foreign::zv2::client::{
+    zxdg_exported_v2, zxdg_exporter_v2, zxdg_imported_v2, zxdg_importer_v2,
+};
+use wayland_protocols::xdg::shell::client::{
+    xdg_popup, xdg_positioner, xdg_surface, xdg_toplevel, xdg_wm_base,
+};
+
+static BUFFER_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone)]
+pub struct FakeClientOptions {
+    pub app_id: String,
+    pub title: String,
+    /// Size used until the compositor picks one
+    pub initial_size: (i32, i32),
+    /// RGB fill color of the buffer
+    pub color: [u8; 3],
+}
+
+impl Default for FakeClientOptions {
+    fn default() -> Self {
+        Self {
+            app_id: "webwm-fake".to_string(),
+            title: "Fake client".to_string(),
+            initial_size: (640, 480),
+            color: [0x89, 0xb4, 0xfa],
+        }
+    }
+}
+
+/// State updated from compositor events
+pub struct FakeClientState {
+    pub options: FakeClientOptions,
+    pub configure_count: u32,
+    /// Last size from xdg_toplevel.configure (0 means "client decides")
+    pub configured_size: (i32, i32),
+    pub committed_size: Option<(i32, i32)>,
+    pub activated: bool,
+    pub closed: bool,
+    /// Mime types of the selection currently offered to this client
+    pub selection: Option<Vec<String>>,
+    /// Handle of the exported toplevel, once the compositor sent it
+    pub exported_handle: Option<String>,
+    /// x, y, width and height of the popup from its last configure,
+    /// relative to the toplevel's geometry
+    pub popup_geometry: Option<(i32, i32, i32, i32)>,
+    /// Set when the compositor dismissed the popup
+    pub popup_done: bool,
+    /// Mime types of the most recent wl_data_offer
+    offer_mime_types: Vec<String>,
+    selection_source: Option<wl_data_source::WlDataSource>,
+    shm: wl_shm::WlShm,
+    surface: Option<wl_surface::WlSurface>,
+    buffer: Option<wl_buffer::WlBuffer>,
+}
+
+pub struct FakeClient {
+    connection: Connection,
+    queue: EventQueue<FakeClientState>,
+    pub state: FakeClientState,
+    compositor: wl_compositor::WlCompositor,
+    wm_base: xdg_wm_base::XdgWmBase,
+    xdg_surface: xdg_surface::XdgSurface,
+    pub toplevel: xdg_toplevel::XdgToplevel,
+    seat: wl_seat::WlSeat,
+    data_device_manager: wl_data_device_manager::WlDataDeviceManager,
+    data_device: wl_data_device::WlDataDevice,
+    content_type_manager: Option<wp_content_type_manager_v1::WpContentTypeManagerV1>,
+    content_type: Option<wp_content_type_v1::WpContentTypeV1>,
+    tearing_control_manager: Option<wp_tearing_control_manager_v1::WpTearingControlManagerV1>,
+    tearing_control: Option<wp_tearing_control_v1::WpTearingControlV1>,
+    exporter: Option<zxdg_exporter_v2::ZxdgExporterV2>,
+    exported: Option<zxdg_exported_v2::ZxdgExportedV2>,
+    importer: Option<zxdg_importer_v2::ZxdgImporterV2>,
+    imported: Option<zxdg_imported_v2::ZxdgImportedV2>,
+    popup: Option<xdg_popup::XdgPopup>,
+    wm_dialog: Option<xdg_wm_dialog_v1::XdgWmDialogV1>,
+    dialog: Option<xdg_dialog_v1::XdgDialogV1>,
+}
+
+/// The popup's surface, to attach a buffer to once it is configured
+pub struct PopupSurfaceData {
+    surface: wl_surface::WlSurface,
+    size: (i32, i32),
+}
+
+impl FakeClient {
+    /// Connect to the compositor listening on `socket_name` and map a toplevel
+    pub fn connect(socket_name: &str, options: FakeClientOptions) -> Result<Self, String> {
+        let runtime_dir =
+            std::env::var("XDG_RUNTIME_DIR").map_err(|_| "XDG_RUNTIME_DIR is not set")?;
+        let stream = UnixStream::connect(PathBuf::from(runtime_dir).join(socket_name))
+            .map_err(|e| format!("Failed to connect to {}: {}", socket_name, e))?;
+        let connection = Connection::from_socket(stream)
+            .map_err(|e| format!("Failed to create Wayland connection: {}", e))?;
+
+        let (globals, mut queue) = registry_queue_init::<FakeClientState>(&connection)
+            .map_err(|e| format!("Failed to read globals: {}", e))?;
+        let qh = queue.handle();
+
+        let compositor: wl_compositor::WlCompositor = globals
+            .bind(&qh, 1..=4, ())
+            .map_err(|e| format!("wl_compositor unavailable: {}", e))?;
+        let shm: wl_shm::WlShm = globals
+            .bind(&qh, 1..=1, ())
+            .map_err(|e| format!("wl_shm unavailable: {}", e))?;
+        let wm_base: xdg_wm_base::XdgWmBase = globals
+            .bind(&qh, 1..=2, ())
+            .map_err(|e| format!("xdg_wm_base unavailable: {}", e))?;
+        let seat: wl_seat::WlSeat = globals
+            .bind(&qh, 1..=7, ())
+            .map_err(|e| format!("wl_seat unavailable: {}", e))?;
+        let data_device_manager: wl_data_device_manager::WlDataDeviceManager = globals
+            .bind(&qh, 1..=3, ())
+            .map_err(|e| format!("wl_data_device_manager unavailable: {}", e))?;
+        let data_device = data_device_manager.get_data_device(&seat, &qh, ());
+        let content_type_manager = globals.bind(&qh, 1..=1, ()).ok();
+        let tearing_control_manager = globals.bind(&qh, 1..=1, ()).ok();
+        let exporter = globals.bind(&qh, 1..=1, ()).ok();
+        let importer = globals.bind(&qh, 1..=1, ()).ok();
+        let wm_dialog = globals.bind(&qh, 1..=1, ()).ok();
+
+        let surface = compositor.create_surface(&qh, ());
+        let xdg_surface = wm_base.get_xdg_surface(&surface, &qh, ());
+        let toplevel = xdg_surface.get_toplevel(&qh, ());
+        toplevel.set_app_id(options.app_id.clone());
+        toplevel.set_title(options.title.clone());
+
+        // Initial commit without a buffer asks for the first configure
+        surface.commit();
+
+        let mut state = FakeClientState {
+            options,
+            configure_count: 0,
+            configured_size: (0, 0),
+            committed_size: None,
+            activated: false,
+            closed: false,
+            selection: None,
+            exported_handle: None,
+            popup_geometry: None,
+            popup_done: false,
+            offer_mime_types: Vec::new(),
+            selection_source: None,
+            shm,
+            surface: Some(surface),
+            buffer: None,
+        };
+
+        queue
+            .roundtrip(&mut state)
+            .map_err(|e| format!("Initial roundtrip failed: {}", e))?;
+
+        Ok(Self {
+            connection,
+            queue,
+            state,
+            compositor,
+            wm_base,
+            xdg_surface,
+            toplevel,
+            seat,
+            data_device_manager,
+            data_device,
+            content_type_manager,
+            content_type: None,
+            tearing_control_manager,
+            tearing_control: None,
+            exporter,
+            exported: None,
+            importer,
+            imported: None,
+            popup: None,
+            wm_dialog,
+            dialog: None,
+        })
+    }
+
+    /// Declare what the window shows, applied with a surface commit
+    pub fn set_content_type(
+        &mut self,
+        content_type: wp_content_type_v1::Type,
+    ) -> Result<(), String> {
+        let (Some(manager), Some(surface)) = (&self.content_type_manager, &self.state.surface)
+        else {
+            return Err("wp_content_type_manager_v1 unavailable".to_string());
+        };
+
+        let qh = self.queue.handle();
+        let object = self
+            .content_type
+            .get_or_insert_with(|| manager.get_surface_content_type(surface, &qh, ()));
+        object.set_content_type(content_type);
+        surface.commit();
+        Ok(())
+    }
+
+    /// Ask for vsynced or async presentation, applied with a surface commit
+    pub fn set_presentation_hint(
+        &mut self,
+        hint: wp_tearing_control_v1::PresentationHint,
+    ) -> Result<(), String> {
+        let (Some(manager), Some(surface)) = (&self.tearing_control_manager, &self.state.surface)
+        else {
+            return Err("wp_tearing_control_manager_v1 unavailable".to_string());
+        };
+
+        let qh = self.queue.handle();
+        let object = self
+            .tearing_control
+            .get_or_insert_with(|| manager.get_tearing_control(surface, &qh, ()));
+        object.set_presentation_hint(hint);
+        surface.commit();
+        Ok(())
+    }
+
+    /// Export the toplevel; its handle arrives in `state.exported_handle`
+    pub fn export_toplevel(&mut self) -> Result<(), String> {
+        let (Some(exporter), Some(surface)) = (&self.exporter, &self.state.surface) else {
+            return Err("zxdg_exporter_v2 unavailable".to_string());
+        };
+
+        let qh = self.queue.handle();
+        self.exported = Some(exporter.export_toplevel(surface, &qh, ()));
+        Ok(())
+    }
+
+    /// Make this toplevel a dialog of the toplevel exported as `handle`
+    pub fn set_parent_handle(&mut self, handle: &str) -> Result<(), String> {
+        let (Some(importer), Some(surface)) = (&self.importer, &self.state.surface) else {
+            return Err("zxdg_importer_v2 unavailable".to_string());
+        };
+
+        let qh = self.queue.handle();
+        let imported = importer.import_toplevel(handle.to_string(), &qh, ());
+        imported.set_parent_of(surface);
+        if let Some(old) = self.imported.replace(imported) {
+            old.destroy();
+        }
+        Ok(())
+    }
+
+    /// Mark the toplevel as a modal dialog of its parent
+    pub fn set_modal(&mut self) -> Result<(), String> {
+        let Some(ref wm_dialog) = self.wm_dialog else {
+            return Err("xdg_wm_dialog_v1 unavailable".to_string());
+        };
+        let dialog = wm_dialog.get_xdg_dialog(&self.toplevel, &self.queue.handle(), ());
+        dialog.set_modal();
+        self.dialog = Some(dialog);
+        Ok(())
+    }
+
+    /// Open a `size` popup below and to the right of `anchor` (x, y, width,
+    /// height in the toplevel), allowed to slide and flip to stay on screen
+    pub fn open_popup(&mut self, anchor: (i32, i32, i32, i32), size: (i32, i32)) {
+        let qh = self.queue.handle();
+        let positioner = self.wm_base.create_positioner(&qh, ());
+        positioner.set_size(size.0, size.1);
+        positioner.set_anchor_rect(anchor.0, anchor.1, anchor.2, anchor.3);
+        positioner.set_anchor(xdg_positioner::Anchor::BottomRight);
+        positioner.set_gravity(xdg_positioner::Gravity::BottomRight);
+        positioner.set_constraint_adjustment(
+            xdg_positioner::ConstraintAdjustment::SlideX
+                | xdg_positioner::ConstraintAdjustment::FlipY,
+        );
+
+        let surface = self.compositor.create_surface(&qh, ());
+        let xdg_surface = self.wm_base.get_xdg_surface(
+            &surface,
+            &qh,
+            PopupSurfaceData {
+                surface: surface.clone(),
+                size,
+            },
+        );
+        let popup = xdg_surface.get_popup(Some(&self.xdg_surface), &positioner, &qh, ());
+        positioner.destroy();
+        surface.commit();
+
+        self.state.popup_done = false;
+        self.popup = Some(popup);
+    }
+
+    /// Ask for the open popup to grab keyboard and pointer, as menus do
+    pub fn grab_popup(&mut self) {
+        if let Some(ref popup) = self.popup {
+            popup.grab(&self.seat, 0);
+        }
+    }
+
+    /// Copy: offer the window title as the selection. Only takes effect
+    /// while this client has keyboard focus.
+    pub fn set_selection(&mut self, mime_type: &str) {
+        let qh = self.queue.handle();
+        let source = self.data_device_manager.create_data_source(&qh, ());
+        source.offer(mime_type.to_string());
+        self.data_device.set_selection(Some(&source), 0);
+
+        if let Some(old) = self.state.selection_source.replace(source) {
+            old.destroy();
+        }
+    }
+
+    /// Block until the compositor has processed all requests sent so far
+    pub fn roundtrip(&mut self) -> Result<(), String> {
+        self.queue
+            .roundtrip(&mut self.state)
+            .map(|_| ())
+            .map_err(|e| format!("Roundtrip failed: {}", e))
+    }
+
+    /// Process any events that already arrived without blocking
+    pub fn dispatch_pending(&mut self) -> Result<(), String> {
+        self.connection
+            .flush()
+            .map_err(|e| format!("Flush failed: {}", e))?;
+        if let Some(guard) = self.connection.prepare_read() {
+            // Nothing to read is fine, we only want what is already buffered
+            guard.read().ok();
+        }
+        self.queue
+            .dispatch_pending(&mut self.state)
+            .map(|_| ())
+            .map_err(|e| format!("Dispatch failed: {}", e))
+    }
+
+    /// Destroy the toplevel, as if the application was closed
+    pub fn close(mut self) -> Result<(), String> {
+        self.toplevel.destroy();
+        if let Some(surface) = self.state.surface.take() {
+            surface.destroy();
+        }
+        self.roundtrip()
+    }
+}
+
+impl FakeClientState {
+    fn attach_buffer(&mut self, qh: &QueueHandle<Self>) {
+        let (mut width, mut height) = self.configured_size;
+        if width <= 0 || height <= 0 {
+            (width, height) = self.options.initial_size;
+        }
+
+        let Some(surface) = self.surface.as_ref() else {
+            return;
+        };
+
+        match create_shm_buffer(&self.shm, qh, width, height, self.options.color) {
+            Ok(buffer) => {
+                surface.attach(Some(&buffer), 0, 0);
+                surface.damage(0, 0, width, height);
+                surface.commit();
+
+                if let Some(old) = self.buffer.replace(buffer) {
+                    old.destroy();
+                }
+                self.committed_size = Some((width, height));
+            }
+            Err(e) => eprintln!("Fake client: {}", e),
+        }
+    }
+}
+
+fn create_shm_buffer(
+    shm: &wl_shm::WlShm,
+    qh: &QueueHandle<FakeClientState>,
+    width: i32,
+    height: i32,
+    color: [u8; 3],
+) -> Result<wl_buffer::WlBuffer, String> {
+    let stride = width * 4;
+    let size = stride * height;
+
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let path = PathBuf::from(runtime_dir).join(format!(
+        "webwm-fake-{}-{}",
+        std::process::id(),
+        BUFFER_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to create SHM file: {}", e))?;
+    // The fd stays valid after unlinking; nothing is left behind on disk
+    fs::remove_file(&path).ok();
+
+    // XRGB8888 is little-endian BGRX in memory
+    let pixel = [color[2], color[1], color[0], 0xff];
+    let pixels: Vec<u8> = pixel.iter().copied().cycle().take(size as usize).collect();
+    file.write_all(&pixels)
+        .map_err(|e| format!("Failed to fill SHM file: {}", e))?;
+
+    let pool = shm.create_pool(file.as_fd(), size, qh, ());
+    let buffer = pool.create_buffer(0, width, height, stride, wl_shm::Format::Xrgb8888, qh, ());
+    pool.destroy();
+
+    Ok(buffer)
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for FakeClientState {
+    fn event(
+        _state: &mut Self,
+        _registry: &wl_registry::WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<xdg_wm_base::XdgWmBase, ()> for FakeClientState {
+    fn event(
+        _state: &mut Self,
+        wm_base: &xdg_wm_base::XdgWmBase,
+        event: xdg_wm_base::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_wm_base::Event::Ping { serial } = event {
+            wm_base.pong(serial);
+        }
+    }
+}
+
+impl Dispatch<xdg_surface::XdgSurface, ()> for FakeClientState {
+    fn event(
+        state: &mut Self,
+        xdg_surface: &xdg_surface::XdgSurface,
+        event: xdg_surface::Event,
+        _data: &(),
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_surface::Event::Configure { serial } = event {
+            xdg_surface.ack_configure(serial);
+            state.configure_count += 1;
+
+            if state.committed_size != Some(state.configured_size) {
+                state.attach_buffer(qh);
+            }
+        }
+    }
+}
+
+impl Dispatch<xdg_surface::XdgSurface, PopupSurfaceData> for FakeClientState {
+    fn event(
+        state: &mut Self,
+        xdg_surface: &xdg_surface::XdgSurface,
+        event: xdg_surface::Event,
+        data: &PopupSurfaceData,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_surface::Event::Configure { serial } = event {
+            xdg_surface.ack_configure(serial);
+            let (width, height) = data.size;
+            match create_shm_buffer(&state.shm, qh, width, height, state.options.color) {
+                Ok(buffer) => {
+                    data.surface.attach(Some(&buffer), 0, 0);
+                    data.surface.damage(0, 0, width, height);
+                    data.surface.commit();
+                }
+                Err(e) => eprintln!("Fake client: {}", e),
+            }
+        }
+    }
+}
+
+impl Dispatch<xdg_popup::XdgPopup, ()> for FakeClientState {
+    fn event(
+        state: &mut Self,
+        _popup: &xdg_popup::XdgPopup,
+        event: xdg_popup::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            xdg_popup::Event::Configure {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                state.popup_geometry = Some((x, y, width, height));
+            }
+            xdg_popup::Event::PopupDone => {
+                state.popup_done = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<xdg_toplevel::XdgToplevel, ()> for FakeClientState {
+    fn event(
+        state: &mut Self,
+        _toplevel: &xdg_toplevel::XdgToplevel,
+        event: xdg_toplevel::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            xdg_toplevel::Event::Configure {
+                width,
+                height,
+                states,
+            } => {
+                state.configured_size = (width, height);
+                state.activated = states
+                    .chunks_exact(4)
+                    .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+                    .any(|s| s == xdg_toplevel::State::Activated as u32);
+            }
+            xdg_toplevel::Event::Close => {
+                state.closed = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zxdg_exported_v2::ZxdgExportedV2, ()> for FakeClientState {
+    fn event(
+        state: &mut Self,
+        _exported: &zxdg_exported_v2::ZxdgExportedV2,
+        event: zxdg_exported_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zxdg_exported_v2::Event::Handle { handle } = event {
+            state.exported_handle = Some(handle);
+        }
+    }
+}
+
+impl Dispatch<wl_data_device::WlDataDevice, ()> for FakeClientState {
+    fn event(
+        state: &mut Self,
+        _device: &wl_data_device::WlDataDevice,
+        event: wl_data_device::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            // A new offer is announced right before its mime types
+            wl_data_device::Event::DataOffer { .. } => state.offer_mime_types.clear(),
+            wl_data_device::Event::Selection { id } => {
+                state.selection = id.map(|_| state.offer_mime_types.clone());
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(FakeClientState, wl_data_device::WlDataDevice, [
+        wl_data_device::EVT_DATA_OFFER_OPCODE => (wl_data_offer::WlDataOffer, ()),
+    ]);
+}
+
+impl Dispatch<wl_data_offer::WlDataOffer, ()> for FakeClientState {
+    fn event(
+        state: &mut Self,
+        _offer: &wl_data_offer::WlDataOffer,
+        event: wl_data_offer::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wl_data_offer::Event::Offer { mime_type } = event {
+            state.offer_mime_types.push(mime_type);
+        }
+    }
+}
+
+impl Dispatch<wl_data_source::WlDataSource, ()> for FakeClientState {
+    fn event(
+        state: &mut Self,
+        source: &wl_data_source::WlDataSource,
+        event: wl_data_source::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_data_source::Event::Send { fd, .. } => {
+                File::from(fd)
+                    .write_all(state.options.title.as_bytes())
+                    .ok();
+            }
+            wl_data_source::Event::Cancelled => {
+                source.destroy();
+                if state.selection_source.as_ref() == Some(source) {
+                    state.selection_source = None;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(FakeClientState: ignore wl_compositor::WlCompositor);
+delegate_noop!(FakeClientState: ignore wl_surface::WlSurface);
+delegate_noop!(FakeClientState: ignore wl_shm::WlShm);
+delegate_noop!(FakeClientState: wl_shm_pool::WlShmPool);
+delegate_noop!(FakeClientState: ignore wl_buffer::WlBuffer);
+delegate_noop!(FakeClientState: ignore wl_seat::WlSeat);
+delegate_noop!(FakeClientState: xdg_positioner::XdgPositioner);
+delegate_noop!(FakeClientState: xdg_wm_dialog_v1::XdgWmDialogV1);
+delegate_noop!(FakeClientState: xdg_dialog_v1::XdgDialogV1);
+delegate_noop!(FakeClientState: wl_data_device_manager::WlDataDeviceManager);
+delegate_noop!(FakeClientState: wp_content_type_manager_v1::WpContentTypeManagerV1);
+delegate_noop!(FakeClientState: wp_content_type_v1::WpContentTypeV1);
+delegate_noop!(FakeClientState: wp_tearing_control_manager_v1::WpTearingControlManagerV1);
+delegate_noop!(FakeClientState: wp_tearing_control_v1::WpTearingControlV1);
+delegate_noop!(FakeClientState: zxdg_exporter_v2::ZxdgExporterV2);
+delegate_noop!(FakeClientState: zxdg_importer_v2::ZxdgImporterV2);
+delegate_noop!(FakeClientState: ignore zxdg_imported_v2::ZxdgImportedV2);
@@ -0,0 +1,579 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use smithay::reexports::calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction};
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::compositor::content_type::window_content_type;
+use crate::compositor::live_config::keybinding_combo;
+use crate::compositor::output_profiles::OutputProfile;
+use crate::compositor::{window_app_id, window_title, WebWMCompositor};
+use crate::config::{Action, Widget};
+use crate::log_buffer;
+use crate::storage;
+
+/// A single request sent by `webwm-msg`, one JSON object per line
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct IpcRequest {
+    /// e.g. `version` or `profile-apply`, see `webwm-msg --help`
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct IpcResponse {
+    pub success: bool,
+    /// Command-specific result, absent on errors
+    #[serde(skip_serializing_if = "Value::is_null", default)]
+    pub data: Value,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<String>,
+}
+
+impl IpcResponse {
+    pub fn ok(data: Value) -> Self {
+        Self {
+            success: true,
+            data,
+            error: None,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            data: Value::Null,
+            error: Some(message.into()),
+        }
+    }
+}
+
+/// Path of the IPC socket for the given Wayland socket name
+pub fn socket_path(wayland_socket: &str) -> PathBuf {
+    let runtime_dir = env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(runtime_dir).join(format!("webwm.{}.sock", wayland_socket))
+}
+
+/// Bind the IPC socket and dispatch requests from the event loop
+pub fn init_ipc(
+    loop_handle: &LoopHandle<'static, WebWMCompositor>,
+    wayland_socket: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = socket_path(wayland_socket);
+
+    // A stale socket from a crashed session would make bind() fail
+    if path.exists() {
+        std::fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    listener.set_nonblocking(true)?;
+
+    loop_handle.insert_source(
+        Generic::new(listener, Interest::READ, Mode::Level),
+        |_, listener, compositor| {
+            loop {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let _stage = compositor.watchdog.enter("ipc");
+                        let start = Instant::now();
+                        handle_client(stream, compositor);
+                        compositor.profiler.record_dispatch("ipc", start);
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        eprintln!("IPC accept error: {}", e);
+                        break;
+                    }
+                }
+            }
+            Ok(PostAction::Continue)
+        },
+    )?;
+
+    // Let clients started from the session find us, like SWAYSOCK
+    env::set_var("WEBWM_SOCK", &path);
+
+    Ok(path)
+}
+
+fn handle_client(stream: UnixStream, compositor: &mut WebWMCompositor) {
+    // Requests are tiny; never let a misbehaving client stall the event loop
+    stream.set_nonblocking(false).ok();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .ok();
+    stream
+        .set_write_timeout(Some(Duration::from_millis(100)))
+        .ok();
+
+    let mut line = String::new();
+    let mut reader = BufReader::new(&stream);
+    if let Err(e) = reader.read_line(&mut line) {
+        eprintln!("IPC read error: {}", e);
+        return;
+    }
+
+    let response = match serde_json::from_str::<IpcRequest>(line.trim()) {
+        Ok(request) if request.command == "subscribe" => {
+            subscribe(stream, request.args, compositor);
+            return;
+        }
+        Ok(request) => dispatch(compositor, &request),
+        Err(e) => IpcResponse::error(format!("Invalid request: {}", e)),
+    };
+
+    let mut writer = &stream;
+    match serde_json::to_string(&response) {
+        Ok(json) => {
+            if let Err(e) = writeln!(writer, "{}", json) {
+                eprintln!("IPC write error: {}", e);
+            }
+        }
+        Err(e) => eprintln!("IPC serialization error: {}", e),
+    }
+}
+
+/// A client that ran `subscribe [EVENT...]` and keeps its connection open
+pub struct Subscriber {
+    stream: UnixStream,
+    /// Empty means all events
+    events: Vec<String>,
+}
+
+fn subscribe(stream: UnixStream, events: Vec<String>, compositor: &mut WebWMCompositor) {
+    let mut writer = &stream;
+    let ack = IpcResponse::ok(json!({ "subscribed": events }));
+    let Ok(json) = serde_json::to_string(&ack) else {
+        return;
+    };
+    if writeln!(writer, "{}", json).is_err() {
+        return;
+    }
+
+    compositor
+        .ipc_subscribers
+        .push(Subscriber { stream, events });
+}
+
+/// Send an event line to every interested subscriber, dropping the ones
+/// that went away or stopped reading
+pub fn broadcast(subscribers: &mut Vec<Subscriber>, event: &str, data: &Value) {
+    if subscribers.is_empty() {
+        return;
+    }
+
+    let line = json!({ "event": event, "data": data }).to_string();
+    subscribers.retain(|subscriber| {
+        if !subscriber.events.is_empty() && !subscriber.events.iter().any(|e| e == event) {
+            return true;
+        }
+        let mut writer = &subscriber.stream;
+        writeln!(writer, "{}", line).is_ok()
+    });
+}
+
+pub fn dispatch(compositor: &mut WebWMCompositor, request: &IpcRequest) -> IpcResponse {
+    match request.command.as_str() {
+        "version" => IpcResponse::ok(json!({ "version": env!("CARGO_PKG_VERSION") })),
+        "perf" => IpcResponse::ok(compositor.profiler.report()),
+        "trace" => {
+            let seconds = match request.args.first().map(|s| s.parse::<u64>()) {
+                None => DEFAULT_TRACE_SECONDS,
+                Some(Ok(seconds)) if (1..=MAX_TRACE_SECONDS).contains(&seconds) => seconds,
+                _ => {
+                    return IpcResponse::error(format!(
+                        "Usage: trace [SECONDS (1-{})] [FILE]",
+                        MAX_TRACE_SECONDS
+                    ))
+                }
+            };
+            let path = request
+                .args
+                .get(1)
+                .map(PathBuf::from)
+                .unwrap_or_else(default_trace_path);
+            match compositor.start_trace(Duration::from_secs(seconds), path.clone()) {
+                Ok(()) => IpcResponse::ok(json!({ "path": path, "seconds": seconds })),
+                Err(e) => IpcResponse::error(e),
+            }
+        }
+        "frame-stats" => IpcResponse::ok(compositor.frame_pacing.report()),
+        "metrics" => IpcResponse::ok(json!({ "text": compositor.prometheus_metrics() })),
+        "clear-cache" => {
+            let Some(dir) = storage::cache_dir() else {
+                return IpcResponse::error("Neither XDG_CACHE_HOME nor HOME is set");
+            };
+            match storage::clear_cache(&dir) {
+                Ok(freed) => {
+                    println!("🧹 Cleared {}", dir.display());
+                    IpcResponse::ok(json!({
+                        "path": dir,
+                        "freed_kib": freed / 1024,
+                    }))
+                }
+                Err(e) => IpcResponse::error(format!("Failed to clear {}: {}", dir.display(), e)),
+            }
+        }
+        "debug-overlay" => {
+            compositor.debug_overlay = match request.args.first().map(|s| s.as_str()) {
+                Some("on") => true,
+                Some("off") => false,
+                _ => !compositor.debug_overlay,
+            };
+            IpcResponse::ok(json!({ "debug_overlay": compositor.debug_overlay }))
+        }
+        "merge" => {
+            let direction = request.args.first().map(|s| s.as_str()).unwrap_or("right");
+            if !matches!(direction, "left" | "right" | "up" | "down") {
+                return IpcResponse::error("Usage: merge [left|right|up|down]");
+            }
+            compositor.merge_focused_window(direction);
+            IpcResponse::ok(Value::Null)
+        }
+        "split" => {
+            compositor.split_focused_window();
+            IpcResponse::ok(Value::Null)
+        }
+        "select-region" => {
+            let location = compositor.pointer_location();
+            compositor.begin_region_select(request.args.join(" "), location);
+            IpcResponse::ok(Value::Null)
+        }
+        "clients" => IpcResponse::ok(compositor.client_report()),
+        "presentation-mode" => {
+            let active = match request.args.first().map(|s| s.as_str()) {
+                Some("on") => true,
+                Some("off") => false,
+                _ => !compositor.presentation_mode,
+            };
+            compositor.set_presentation_mode(active);
+            IpcResponse::ok(json!({ "presentation_mode": active }))
+        }
+        "mute" => {
+            let Some(window) = compositor.workspace_manager.focused_window().cloned() else {
+                return IpcResponse::error("No focused window");
+            };
+            if compositor.window_audio(&window).is_none() {
+                return IpcResponse::error("The focused window is not playing audio");
+            }
+            compositor.toggle_window_mute(&window);
+            IpcResponse::ok(json!({ "muted": compositor.window_audio(&window) }))
+        }
+        "keybind-list" => IpcResponse::ok(json!({
+            "keybindings": compositor
+                .config
+                .keybindings
+                .iter()
+                .map(|binding| json!({
+                    "combo": keybinding_combo(binding),
+                    "action": binding.action,
+                }))
+                .collect::<Vec<_>>(),
+        })),
+        "keybind-set" => {
+            let (Some(combo), Some(_)) = (request.args.first(), request.args.get(1)) else {
+                return IpcResponse::error("Usage: keybind-set COMBO ACTION_JSON");
+            };
+            let action = match serde_json::from_str::<Action>(&request.args[1..].join(" ")) {
+                Ok(action) => action,
+                Err(e) => return IpcResponse::error(format!("Invalid action: {}", e)),
+            };
+            match compositor.set_keybinding(combo, action) {
+                Ok(()) => IpcResponse::ok(json!({ "bound": combo })),
+                Err(e) => IpcResponse::error(e),
+            }
+        }
+        "keybind-remove" => {
+            let Some(combo) = request.args.first() else {
+                return IpcResponse::error("Usage: keybind-remove COMBO");
+            };
+            match compositor.remove_keybinding(combo) {
+                Ok(()) => IpcResponse::ok(json!({ "removed": combo })),
+                Err(e) => IpcResponse::error(e),
+            }
+        }
+        "theme" => IpcResponse::ok(json!({
+            "variables": compositor
+                .stylesheet
+                .as_ref()
+                .map(|stylesheet| stylesheet.variables.clone())
+                .unwrap_or_default(),
+        })),
+        "theme-set" => {
+            let (Some(name), Some(_)) = (request.args.first(), request.args.get(1)) else {
+                return IpcResponse::error("Usage: theme-set NAME VALUE");
+            };
+            let value = request.args[1..].join(" ");
+            match compositor.set_theme_variable(name, &value) {
+                Ok(()) => IpcResponse::ok(json!({ "set": name, "value": value })),
+                Err(e) => IpcResponse::error(e),
+            }
+        }
+        "bar-list" => IpcResponse::ok(json!({
+            "bars": compositor
+                .config
+                .desktop
+                .as_ref()
+                .map(|desktop| desktop.bars.clone())
+                .unwrap_or_default(),
+        })),
+        "bar-widgets" => {
+            let (Some(bar), Some(_)) = (request.args.first(), request.args.get(1)) else {
+                return IpcResponse::error("Usage: bar-widgets BAR WIDGETS_JSON");
+            };
+            let widgets = match serde_json::from_str::<Vec<Widget>>(&request.args[1..].join(" ")) {
+                Ok(widgets) => widgets,
+                Err(e) => return IpcResponse::error(format!("Invalid widgets: {}", e)),
+            };
+            match compositor.set_bar_widgets(bar, widgets) {
+                Ok(()) => IpcResponse::ok(json!({ "updated": bar })),
+                Err(e) => IpcResponse::error(e),
+            }
+        }
+        "bar-visible" => {
+            let Some(output) = request.args.first() else {
+                return IpcResponse::error("Usage: bar-visible OUTPUT [on|off]");
+            };
+            let result = match request.args.get(1).map(|s| s.as_str()) {
+                Some("on") => compositor.set_bar_visible(output, true),
+                Some("off") => compositor.set_bar_visible(output, false),
+                _ => compositor.toggle_bar(Some(output)),
+            };
+            match result {
+                Ok(()) => IpcResponse::ok(json!({
+                    "output": output,
+                    "visible": !compositor.hidden_bars.contains(output),
+                })),
+                Err(e) => IpcResponse::error(e),
+            }
+        }
+        "force-close" => {
+            compositor.force_close_focused();
+            IpcResponse::ok(Value::Null)
+        }
+        "debug-bundle" => IpcResponse::ok(debug_bundle(compositor)),
+        "logs" => logs(&request.args),
+        "profile-list" => {
+            let current = compositor.output_states();
+            let active = compositor
+                .output_profiles
+                .find_matching(&current)
+                .map(|p| p.name.clone());
+            IpcResponse::ok(json!({
+                "matching": active,
+                "current": current,
+                "profiles": compositor.output_profiles.profiles,
+            }))
+        }
+        "profile-save" => {
+            let Some(name) = request.args.first() else {
+                return IpcResponse::error("Usage: profile-save NAME");
+            };
+            let profile = OutputProfile {
+                name: name.clone(),
+                outputs: compositor.output_states(),
+            };
+            match compositor.output_profiles.save_profile(profile) {
+                Ok(()) => IpcResponse::ok(json!({ "saved": name })),
+                Err(e) => IpcResponse::error(e),
+            }
+        }
+        "profile-apply" => {
+            let Some(name) = request.args.first() else {
+                return IpcResponse::error("Usage: profile-apply NAME");
+            };
+            match compositor.output_profiles.get(name).cloned() {
+                Some(profile) => {
+                    compositor.apply_output_profile(&profile);
+                    IpcResponse::ok(json!({ "applied": name }))
+                }
+                None => IpcResponse::error(format!("No profile named '{}'", name)),
+            }
+        }
+        "profile-remove" => {
+            let Some(name) = request.args.first() else {
+                return IpcResponse::error("Usage: profile-remove NAME");
+            };
+            match compositor.output_profiles.remove_profile(name) {
+                Ok(true) => IpcResponse::ok(json!({ "removed": name })),
+                Ok(false) => IpcResponse::error(format!("No profile named '{}'", name)),
+                Err(e) => IpcResponse::error(e),
+            }
+        }
+        "template-list" => IpcResponse::ok(json!({
+            "templates": compositor.layout_templates.templates,
+        })),
+        "template-save" => {
+            let Some(name) = request.args.first() else {
+                return IpcResponse::error("Usage: template-save NAME");
+            };
+            let template = compositor.workspace_template(name);
+            match compositor.layout_templates.save_template(template) {
+                Ok(()) => IpcResponse::ok(json!({ "saved": name })),
+                Err(e) => IpcResponse::error(e),
+            }
+        }
+        "template-apply" => {
+            let Some(name) = request.args.first() else {
+                return IpcResponse::error("Usage: template-apply NAME [WORKSPACE]");
+            };
+            let workspace = match request.args.get(1).map(|ws| ws.parse::<u32>()) {
+                Some(Ok(ws)) => Some(ws),
+                Some(Err(_)) => return IpcResponse::error("WORKSPACE must be a number"),
+                None => None,
+            };
+            match compositor.apply_template(name, workspace) {
+                Ok(id) => IpcResponse::ok(json!({ "applied": name, "workspace": id })),
+                Err(e) => IpcResponse::error(e),
+            }
+        }
+        "template-remove" => {
+            let Some(name) = request.args.first() else {
+                return IpcResponse::error("Usage: template-remove NAME");
+            };
+            match compositor.layout_templates.remove_template(name) {
+                Ok(true) => IpcResponse::ok(json!({ "removed": name })),
+                Ok(false) => IpcResponse::error(format!("No template named '{}'", name)),
+                Err(e) => IpcResponse::error(e),
+            }
+        }
+        other => IpcResponse::error(format!("Unknown command: {}", other)),
+    }
+}
+
+/// `logs [--since SEQ] [--level LEVEL]`
+fn logs(args: &[String]) -> IpcResponse {
+    let mut since = None;
+    let mut min_level = log::Level::Trace;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--since" => match args.next().and_then(|s| s.parse().ok()) {
+                Some(seq) => since = Some(seq),
+                None => return IpcResponse::error("--since expects a sequence number"),
+            },
+            "--level" => match args.next().and_then(|s| s.parse().ok()) {
+                Some(level) => min_level = level,
+                None => {
+                    return IpcResponse::error(
+                        "--level expects one of error, warn, info, debug, trace",
+                    )
+                }
+            },
+            other => return IpcResponse::error(format!("Unknown argument: {}", other)),
+        }
+    }
+
+    IpcResponse::ok(json!({ "entries": log_buffer::query(since, min_level) }))
+}
+
+const DEFAULT_TRACE_SECONDS: u64 = 5;
+const MAX_TRACE_SECONDS: u64 = 300;
+
+/// webwm-trace-<unix time>.json in the temporary directory
+fn default_trace_path() -> PathBuf {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    env::temp_dir().join(format!("webwm-trace-{}.json", now))
+}
+
+/// Everything `webwm-msg debug-bundle` packs into the tarball
+fn debug_bundle(compositor: &WebWMCompositor) -> Value {
+    json!({
+        "version": {
+            "version": env!("CARGO_PKG_VERSION"),
+            "backend": env::var("WEBWM_BACKEND").unwrap_or_else(|_| "winit".to_string()),
+        },
+        "config": serde_json::to_value(&compositor.config).unwrap_or(Value::Null),
+        "window_tree": window_tree(compositor),
+        "outputs": outputs(compositor),
+        "clients": compositor.client_report(),
+        "log": log_buffer::recent_lines(),
+    })
+}
+
+fn window_tree(compositor: &WebWMCompositor) -> Value {
+    let manager = &compositor.workspace_manager;
+    let active_id = manager.active_workspace_id();
+
+    let workspaces: Vec<Value> = manager
+        .all_workspaces()
+        .into_iter()
+        .map(|workspace| {
+            let windows: Vec<Value> = workspace
+                .windows
+                .iter()
+                .enumerate()
+                .map(|(i, window)| {
+                    let geometry = compositor.space.element_geometry(window);
+                    json!({
+                        "app_id": window_app_id(window),
+                        "title": window_title(window),
+                        "content_type": window_content_type(window),
+                        "focused": workspace.focused_window_idx == Some(i),
+                        "tab_group": workspace
+                            .tab_groups
+                            .iter()
+                            .position(|group| group.windows.contains(window)),
+                        "mapped": geometry.is_some(),
+                        "geometry": geometry.map(|g| json!({
+                            "x": g.loc.x,
+                            "y": g.loc.y,
+                            "width": g.size.w,
+                            "height": g.size.h,
+                        })),
+                    })
+                })
+                .collect();
+
+            json!({
+                "id": workspace.id,
+                "name": workspace.name,
+                "layout": format!("{:?}", workspace.layout_mode),
+                "active": workspace.id == active_id,
+                "windows": windows,
+            })
+        })
+        .collect();
+
+    json!({ "workspaces": workspaces })
+}
+
+fn outputs(compositor: &WebWMCompositor) -> Value {
+    let outputs: Vec<Value> = compositor
+        .space
+        .outputs()
+        .map(|output| {
+            let mode = output.current_mode();
+            let geometry = compositor.space.output_geometry(output);
+            json!({
+                "name": output.name(),
+                "make": output.physical_properties().make,
+                "model": output.physical_properties().model,
+                "mode": mode.map(|m| json!({
+                    "width": m.size.w,
+                    "height": m.size.h,
+                    "refresh_mhz": m.refresh,
+                })),
+                "scale": output.current_scale().fractional_scale(),
+                "geometry": geometry.map(|g| json!({
+                    "x": g.loc.x,
+                    "y": g.loc.y,
+                    "width": g.size.w,
+                    "height": g.size.h,
+                })),
+            })
+        })
+        .collect();
+
+    Value::Array(outputs)
+}
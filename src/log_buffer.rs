@@ -0,0 +1,329 @@
+// In-memory ring buffer of recent log records
+//
+// Wraps env_logger so RUST_LOG still controls what reaches stderr, while the
+// last few hundred info-or-higher records are always kept around. Queried by
+// `webwm-msg logs [--follow]` and packed into `webwm-msg debug-bundle`, which
+// matters when the compositor was started by a display manager and stderr
+// went nowhere.
+//
+// Most of the compositor prints rather than logs, so while it runs stdout
+// and stderr are pipes read back line by line: each line still goes where
+// it went before, and into the buffer as an info record from "stdout" or a
+// warning from "stderr". Programs started from the compositor inherit them,
+// so their output lands there too. smithay's tracing events arrive as log
+// records through tracing's `log` feature.
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    /// Increases by one per record, so clients can ask for what's new
+    pub seq: u64,
+    /// Wall clock time, milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    /// Milliseconds since the compositor started
+    pub uptime_ms: u64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    pub fn format(&self) -> String {
+        format!(
+            "[{:>6}.{:03} {:<5} {}] {}",
+            self.uptime_ms / 1000,
+            self.uptime_ms % 1000,
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+struct LogRing {
+    entries: VecDeque<LogEntry>,
+    next_seq: u64,
+}
+
+static RING: Mutex<LogRing> = Mutex::new(LogRing {
+    entries: VecDeque::new(),
+    next_seq: 0,
+});
+static START: OnceLock<Instant> = OnceLock::new();
+
+/// Written through the pipes to know when what came before has been read
+const FLUSH_MARKER: &[u8] = b"\0webwm-log-flush";
+
+/// stdout and stderr while they're captured
+struct Captured {
+    /// The original stdout and stderr, put back by `release_output`
+    originals: Vec<(RawFd, File)>,
+    /// Write ends of the pipes
+    pipes: Vec<File>,
+    /// A message per flush marker read
+    flushed: Receiver<()>,
+}
+
+static CAPTURED: Mutex<Option<Captured>> = Mutex::new(None);
+/// Where env_logger writes, so records don't come back through the pipe
+static ORIGINAL_STDERR: OnceLock<File> = OnceLock::new();
+
+/// stderr from before it was captured
+struct LogTarget;
+
+impl Write for LogTarget {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match ORIGINAL_STDERR.get() {
+            Some(mut file) => file.write(buf),
+            None => io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match ORIGINAL_STDERR.get() {
+            Some(mut file) => file.flush(),
+            None => io::stderr().flush(),
+        }
+    }
+}
+
+struct RingLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for RingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            push_record(record);
+        }
+
+        if self.inner.matches(record) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Install the logger. Replaces `env_logger::init()`.
+pub fn init() {
+    START.get_or_init(Instant::now);
+
+    let inner = env_logger::Builder::from_default_env()
+        .target(env_logger::Target::Pipe(Box::new(LogTarget)))
+        .build();
+    let max_level = inner.filter().max(LevelFilter::Info);
+
+    if log::set_boxed_logger(Box::new(RingLogger { inner })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+/// A copy of `fd` that isn't passed on to spawned programs
+fn duplicate(fd: RawFd) -> io::Result<File> {
+    // SAFETY: F_DUPFD_CLOEXEC only creates a new descriptor, which is ours
+    let copy = unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) };
+    if copy < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `copy` was just opened and nothing else owns it
+    Ok(unsafe { File::from_raw_fd(copy) })
+}
+
+/// Replace `fd` with a pipe whose lines are copied to where `fd` went and
+/// pushed as `level` records from `target`. Returns the original and the
+/// pipe's write end.
+fn capture(
+    fd: RawFd,
+    level: Level,
+    target: &'static str,
+    flushed: Sender<()>,
+) -> io::Result<(File, File)> {
+    let mut ends = [0; 2];
+    // SAFETY: `ends` has room for the two descriptors pipe2 writes
+    if unsafe { libc::pipe2(ends.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: both ends were just opened and nothing else owns them
+    let (reader, writer) = unsafe { (File::from_raw_fd(ends[0]), File::from_raw_fd(ends[1])) };
+
+    let original = duplicate(fd)?;
+    let mut echo = original.try_clone()?;
+    // SAFETY: dup2 only points `fd` at the pipe; `original` keeps what it was
+    if unsafe { libc::dup2(ends[1], fd) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    thread::Builder::new()
+        .name(format!("webwm-{}", target))
+        .spawn(move || {
+            for line in BufReader::new(reader).split(b'\n') {
+                let Ok(mut line) = line else {
+                    break;
+                };
+                if line == FLUSH_MARKER {
+                    let _ = flushed.send(());
+                    continue;
+                }
+                let message = String::from_utf8_lossy(&line).trim_end().to_string();
+                if !message.is_empty() {
+                    push(level, target, message);
+                }
+                line.push(b'\n');
+                let _ = echo.write_all(&line);
+            }
+        })?;
+    Ok((original, writer))
+}
+
+/// Capture stdout and stderr into the buffer until `release_output`.
+/// Only for the compositor itself: output printed just before exiting
+/// could be lost otherwise.
+pub fn capture_output() {
+    let (sender, flushed) = mpsc::channel();
+    let mut captured = Captured {
+        originals: Vec::new(),
+        pipes: Vec::new(),
+        flushed,
+    };
+    for (fd, level, target) in [
+        (libc::STDOUT_FILENO, Level::Info, "stdout"),
+        (libc::STDERR_FILENO, Level::Warn, "stderr"),
+    ] {
+        match capture(fd, level, target, sender.clone()) {
+            Ok((original, pipe)) => {
+                if fd == libc::STDERR_FILENO {
+                    if let Ok(copy) = original.try_clone() {
+                        let _ = ORIGINAL_STDERR.set(copy);
+                    }
+                }
+                captured.originals.push((fd, original));
+                captured.pipes.push(pipe);
+            }
+            Err(e) => eprintln!("⚠️  Can't keep {} in the log buffer: {}", target, e),
+        }
+    }
+    if let Ok(mut slot) = CAPTURED.lock() {
+        *slot = Some(captured);
+    }
+
+    // A panic's message should make it out before the process goes
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        flush_output();
+    }));
+}
+
+/// Wait until what was printed so far has been read from the pipes
+fn flush_output() {
+    let _ = io::stdout().flush();
+    let Ok(captured) = CAPTURED.lock() else {
+        return;
+    };
+    let Some(captured) = captured.as_ref() else {
+        return;
+    };
+    for mut pipe in &captured.pipes {
+        let _ = pipe.write_all(FLUSH_MARKER);
+        let _ = pipe.write_all(b"\n");
+    }
+    for _ in &captured.pipes {
+        if captured
+            .flushed
+            .recv_timeout(Duration::from_millis(500))
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+/// Flush the pipes and give stdout and stderr back, so what's printed
+/// on the way out isn't lost
+pub fn release_output() {
+    flush_output();
+    let Ok(mut captured) = CAPTURED.lock() else {
+        return;
+    };
+    for (fd, original) in captured.take().into_iter().flat_map(|c| c.originals) {
+        // SAFETY: points `fd` back at what it was before `capture`
+        unsafe { libc::dup2(original.as_raw_fd(), fd) };
+    }
+}
+
+fn push_record(record: &Record) {
+    push(record.level(), record.target(), record.args().to_string());
+}
+
+fn push(level: Level, target: &str, message: String) {
+    let timestamp_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let uptime_ms = START
+        .get()
+        .map(|start| start.elapsed().as_millis() as u64)
+        .unwrap_or(0);
+
+    if let Ok(mut ring) = RING.lock() {
+        let entry = LogEntry {
+            seq: ring.next_seq,
+            timestamp_ms,
+            uptime_ms,
+            level: level.to_string(),
+            target: target.to_string(),
+            message,
+        };
+
+        ring.next_seq += 1;
+        if ring.entries.len() == CAPACITY {
+            ring.entries.pop_front();
+        }
+        ring.entries.push_back(entry);
+    }
+}
+
+/// Buffered records at `min_level` or more severe, oldest first. With
+/// `since`, only records newer than that sequence number are returned.
+pub fn query(since: Option<u64>, min_level: Level) -> Vec<LogEntry> {
+    let Ok(ring) = RING.lock() else {
+        return Vec::new();
+    };
+
+    ring.entries
+        .iter()
+        .filter(|entry| since.is_none_or(|seq| entry.seq > seq))
+        .filter(|entry| match entry.level.parse::<Level>() {
+            Ok(level) => level <= min_level,
+            Err(_) => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Snapshot of all buffered records as text, oldest first
+pub fn recent_lines() -> Vec<String> {
+    query(None, Level::Trace)
+        .iter()
+        .map(LogEntry::format)
+        .collect()
+}
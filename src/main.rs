@@ -1,40 +1,149 @@
+mod bench;
 mod compositor;
 mod config;
+mod fake_client;
+mod ipc;
+mod log_buffer;
+mod session;
 mod state;
+mod storage;
+#[cfg(test)]
+mod test_support;
+mod watchdog;
+mod web_ui;
 
 use crate::compositor::backend::WebWMBackend;
 use crate::compositor::input::InputHandler;
+use crate::compositor::input_trace::{self, InputRecorder};
 use compositor::{ClientState, WebWMCompositor};
 
+use compositor::frame_stats::FrameScheduler;
+use compositor::gamma::NIGHT_LIGHT_INTERVAL;
+use compositor::unresponsive::PING_INTERVAL;
+use compositor::virtual_outputs::VirtualOutputSpec;
 use smithay::reexports::{
-    calloop::timer::{TimeoutAction, Timer},
+    calloop::{
+        generic::Generic,
+        timer::{TimeoutAction, Timer},
+        Interest, Mode, PostAction,
+    },
     wayland_server::Display,
 };
 use std::env;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
-    env_logger::init();
-
-    println!("===========================================");
-    println!("  WebWM - Web-Tech Wayland Compositor");
-    println!("  Version 0.2.0");
-    println!("===========================================\n");
+    // Initialize logging (also feeds the debug-bundle log buffer)
+    log_buffer::init();
 
     // Parse command line arguments
     let args: Vec<String> = env::args().collect();
 
+    // Schemas go to stdout to be redirected into a file, so no banner
+    if args.get(1).map(String::as_str) == Some("config")
+        && args.get(2).map(String::as_str) == Some("schema")
+    {
+        return run_schema_mode(args.get(3).map(String::as_str));
+    }
+
+    // Under a display manager nobody sees stdout, so skip the banner
+    if !args.iter().any(|arg| arg == "--session") {
+        println!("===========================================");
+        println!("  WebWM - Web-Tech Wayland Compositor");
+        println!("  Version 0.2.0");
+        println!("===========================================\n");
+    }
+
+    if args.get(1).map(String::as_str) == Some("config")
+        && args.get(2).map(String::as_str) == Some("migrate")
+    {
+        return run_migrate_mode(&args[3..]);
+    }
+
     // Check if running in config-only mode
     if args.len() > 1 && args[1] == "config" {
         return run_config_mode(&args);
     }
 
     // Normal compositor mode
-    run_compositor()?;
+    let options = parse_cli_options(&args);
+    log_buffer::capture_output();
+    let result = run_compositor(options);
+    log_buffer::release_output();
+    result
+}
 
-    Ok(())
+struct CliOptions {
+    config_dir: Option<String>,
+    /// Started by a display manager through `webwm-session`
+    session: bool,
+    bench: Option<bench::BenchOptions>,
+    record_input: Option<String>,
+    replay_input: Option<String>,
+    virtual_outputs: Vec<VirtualOutputSpec>,
+}
+
+fn parse_cli_options(args: &[String]) -> CliOptions {
+    let mut options = CliOptions {
+        config_dir: None,
+        session: false,
+        bench: None,
+        record_input: None,
+        replay_input: None,
+        virtual_outputs: Vec::new(),
+    };
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--session" => options.session = true,
+            "--bench" => {
+                let mut bench = bench::BenchOptions::default();
+                if let Some(windows) = args.get(i + 1).and_then(|n| n.parse().ok()) {
+                    bench.windows = windows;
+                    i += 1;
+                }
+                options.bench = Some(bench);
+            }
+            "--bench-iterations" => {
+                if let Some(bench) = options.bench.as_mut() {
+                    if let Some(n) = args.get(i + 1).and_then(|n| n.parse().ok()) {
+                        bench.iterations = n;
+                    }
+                }
+                i += 1;
+            }
+            "--bench-output" => {
+                if let Some(bench) = options.bench.as_mut() {
+                    bench.output = args.get(i + 1).cloned();
+                }
+                i += 1;
+            }
+            "--record-input" => {
+                options.record_input = args.get(i + 1).cloned();
+                i += 1;
+            }
+            "--replay-input" => {
+                options.replay_input = args.get(i + 1).cloned();
+                i += 1;
+            }
+            "--virtual-output" => {
+                let spec = args.get(i + 1).map(String::as_str).unwrap_or_default();
+                match VirtualOutputSpec::parse(spec) {
+                    Ok(spec) => options.virtual_outputs.push(spec),
+                    Err(e) => eprintln!("Ignoring --virtual-output: {}", e),
+                }
+                i += 1;
+            }
+            arg if !arg.starts_with("--") => options.config_dir = Some(arg.to_string()),
+            arg => eprintln!("Ignoring unknown option: {}", arg),
+        }
+        i += 1;
+    }
+
+    options
 }
 
 fn run_config_mode(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
@@ -42,7 +151,50 @@ fn run_config_mode(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
 
     let config_dir = args.get(2).map(|s| s.as_str()).unwrap_or("./config");
 
-    println!("Step 1: Loading Configuration");
+    println!("Step 1: Checking desktop.xml");
+    println!("-------------------------------------------");
+    let xml_path = Path::new(config_dir).join("desktop.xml");
+    // Problems in included files are reported where they are
+    let problems: Vec<String> = if xml_path.exists() {
+        match config::includes::expand_xml(&xml_path) {
+            Ok(xml) => config::xml_schema::validate_desktop_xml(&xml.text)
+                .into_iter()
+                .map(|problem| {
+                    let (file, line) = xml.locate(problem.line);
+                    format!(
+                        "{}:{}:{}: {}",
+                        file.display(),
+                        line,
+                        problem.column,
+                        problem.message
+                    )
+                })
+                .collect(),
+            Err(e) => vec![e.to_string()],
+        }
+    } else {
+        println!("No desktop.xml, skipping");
+        Vec::new()
+    };
+    for problem in &problems {
+        eprintln!("❌ {}", problem);
+    }
+    if problems.is_empty() && xml_path.exists() {
+        println!("✓ desktop.xml matches the schema");
+    }
+    let version = std::fs::read_to_string(&xml_path)
+        .map(|xml| config::migrate::config_version(&xml))
+        .unwrap_or(config::migrate::CONFIG_VERSION);
+    if version < config::migrate::CONFIG_VERSION {
+        println!(
+            "⚠️  The config is version {}, `webwm config migrate {}` upgrades it to {}",
+            version,
+            config_dir,
+            config::migrate::CONFIG_VERSION
+        );
+    }
+
+    println!("\nStep 2: Loading Configuration");
     println!("-------------------------------------------");
     let (config, js_runtime) = config::load_config(config_dir)?;
 
@@ -50,7 +202,10 @@ fn run_config_mode(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
     println!("  • Keybindings: {}", config.keybindings.len());
     println!("  • Window rules: {}", config.window_rules.len());
     println!("  • Layout mode: {}", config.layout.default_mode);
-    println!("  • Gaps: {}px", config.layout.gaps);
+    println!(
+        "  • Gaps: {}px inner, {}px outer",
+        config.layout.gaps_inner, config.layout.gaps_outer
+    );
     println!("  • Border width: {}px", config.layout.border_width);
 
     if let Some(ref desktop) = config.desktop {
@@ -79,7 +234,7 @@ fn run_config_mode(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
     }
 
     println!("\n-------------------------------------------");
-    println!("Step 2: Display Keybindings");
+    println!("Step 3: Display Keybindings");
     println!("-------------------------------------------");
     for (i, kb) in config.keybindings.iter().enumerate() {
         let modifiers = if kb.modifiers.is_empty() {
@@ -97,18 +252,75 @@ fn run_config_mode(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
                 format!("switch to workspace {}", workspace)
             }
             config::Action::ToggleFloating => "toggle floating".to_string(),
+            config::Action::MergeWindow { direction } => {
+                format!("merge into tab group {}", direction)
+            }
+            config::Action::SplitWindow => "split out of tab group".to_string(),
+            config::Action::SelectRegion { command } => format!("select region for '{}'", command),
+            config::Action::ForceClose => "force close window".to_string(),
+            config::Action::ExecAndFocus { command, .. } => {
+                format!("spawn and focus '{}'", command)
+            }
+            config::Action::RunOrRaise { app_id, command } => {
+                format!("focus '{}' or spawn '{}'", app_id, command)
+            }
+            config::Action::TogglePresentationMode => "toggle presentation mode".to_string(),
+            config::Action::PromoteToMaster => "promote to master".to_string(),
+            config::Action::AdjustMasterRatio { delta } => {
+                format!("adjust master ratio by {:+}", delta)
+            }
+            config::Action::AdjustSplitRatio { delta } => {
+                format!("adjust split ratio by {:+}", delta)
+            }
+            config::Action::RotateSplit => "rotate split".to_string(),
+            config::Action::SplitHorizontal => "split horizontally".to_string(),
+            config::Action::SplitVertical => "split vertically".to_string(),
+            config::Action::ReopenLast => "reopen last closed window".to_string(),
+            config::Action::ToggleOutputZoom => "toggle zoom to window".to_string(),
+            config::Action::ResizeTiled { direction, amount } => {
+                format!("resize tile {} by {:+}", direction, amount)
+            }
+            config::Action::ToggleBar { output } => match output {
+                Some(output) => format!("toggle bar on {}", output),
+                None => "toggle bar".to_string(),
+            },
+            config::Action::Swap { direction } => format!("swap window {}", direction),
+            config::Action::CenterWindow => "center window".to_string(),
+            config::Action::WindowMenu => "window menu".to_string(),
+            config::Action::WindowHints => "window hints".to_string(),
+            config::Action::FocusOutput { direction } => format!("focus output {}", direction),
+            config::Action::Raise => "raise window".to_string(),
+            config::Action::Lower => "lower window".to_string(),
+            config::Action::RaiseOrLower => "raise or lower window".to_string(),
+            config::Action::AdjustGaps { inner, outer } => {
+                format!("adjust gaps by {:+}px inner, {:+}px outer", inner, outer)
+            }
+            config::Action::Reload => "reload config".to_string(),
+            config::Action::EnterMode { name } => format!("enter mode '{}'", name),
+            config::Action::ExitMode => "exit mode".to_string(),
             config::Action::Custom { js } => format!("execute JS: {}", js),
         };
 
         println!("  {}. {}{} → {}", i + 1, modifiers, kb.key, action);
     }
 
+    let mut modes: Vec<_> = config.modes.iter().collect();
+    modes.sort_by_key(|(name, _)| name.as_str());
+    for (name, bindings) in modes {
+        println!("  Mode '{}': {} keybindings", name, bindings.len());
+    }
+
     if args.iter().any(|arg| arg == "--save-json") {
         let json_path = format!("{}/parsed_config.json", config_dir);
         config::save_config_json(&config, &json_path)?;
         println!("\n✓ Saved parsed configuration to: {}", json_path);
     }
 
+    // Fails CI jobs running `webwm config`
+    if !problems.is_empty() {
+        return Err(format!("desktop.xml has {} problem(s), see above", problems.len()).into());
+    }
+
     println!("\n===========================================");
     println!("Configuration is valid!");
     println!("===========================================\n");
@@ -116,21 +328,116 @@ fn run_config_mode(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn run_compositor() -> Result<(), Box<dyn std::error::Error>> {
+/// Upgrade a config directory to the current config version:
+/// `webwm config migrate [DIR] [--dry-run]`
+fn run_migrate_mode(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let config_dir = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .map(String::as_str)
+        .unwrap_or("./config");
+    let dir = Path::new(config_dir);
+
+    let migration = config::migrate::plan(dir)?;
+    if migration.from >= config::migrate::CONFIG_VERSION {
+        println!(
+            "✓ {} is already at version {}, nothing to do",
+            dir.display(),
+            migration.from
+        );
+        return Ok(());
+    }
+
+    println!(
+        "Migrating {} from version {} to {}",
+        dir.display(),
+        migration.from,
+        config::migrate::CONFIG_VERSION
+    );
+    for change in &migration.changes {
+        println!("  • {}", change);
+    }
+    println!(
+        "  • desktop.xml: marked as version {}",
+        config::migrate::CONFIG_VERSION
+    );
+
+    if dry_run {
+        println!("\nDry run, nothing written");
+        return Ok(());
+    }
+    config::migrate::write(dir, &migration)?;
+    let files: Vec<&str> = migration.files.iter().map(|(file, _)| *file).collect();
+    println!(
+        "\n✓ Rewrote {}, the old versions are in *.bak",
+        files.join(" and ")
+    );
+    Ok(())
+}
+
+/// Print the JSON Schema of config.json or of the IPC messages, for
+/// editors and external tools
+fn run_schema_mode(target: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let schema = match target.unwrap_or("config") {
+        "config" => schemars::schema_for!(config::Config),
+        "ipc-request" => schemars::schema_for!(ipc::IpcRequest),
+        "ipc-response" => schemars::schema_for!(ipc::IpcResponse),
+        other => {
+            return Err(format!(
+                "Unknown schema '{}', expected config, ipc-request or ipc-response",
+                other
+            )
+            .into())
+        }
+    };
+
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+fn run_compositor(options: CliOptions) -> Result<(), Box<dyn std::error::Error>> {
     println!("Starting compositor...\n");
 
-    // Get config directory
-    let config_dir = env::args().nth(1).unwrap_or_else(|| "./config".to_string());
+    // Benchmarks and replays never touch real hardware
+    if options.bench.is_some() || options.replay_input.is_some() {
+        env::set_var("WEBWM_BACKEND", "headless");
+    }
+
+    if options.session {
+        session::prepare_environment();
+    }
+
+    // Get config directory; sessions read the user's config
+    let config_dir = match options.config_dir {
+        Some(dir) => dir,
+        None if options.session => config::user_config_dir()
+            .filter(|dir| dir.exists())
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "./config".to_string()),
+        None => "./config".to_string(),
+    };
 
     // Load configuration
     println!("Loading configuration from: {}", config_dir);
-    let (config, js_runtime) = config::load_config(&config_dir)?;
+    // A broken config shouldn't lock the user out of their session
+    let (config, js_runtime, safe_mode) = match config::load_config(&config_dir) {
+        Ok((config, js_runtime)) => (config, js_runtime, None),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            eprintln!("⚠️  Starting in safe mode");
+            (config::safe_mode_config(), None, Some(e))
+        }
+    };
 
     println!("✓ Configuration loaded");
     println!("  • {} keybindings", config.keybindings.len());
     println!("  • {} window rules", config.window_rules.len());
     println!("  • Layout: {}", config.layout.default_mode);
-    println!("  • Gaps: {}px", config.layout.gaps);
+    println!(
+        "  • Gaps: {}px inner, {}px outer",
+        config.layout.gaps_inner, config.layout.gaps_outer
+    );
     println!();
 
     // Create event loop first
@@ -143,8 +450,23 @@ fn run_compositor() -> Result<(), Box<dyn std::error::Error>> {
     println!("Initializing compositor...");
     let mut compositor =
         WebWMCompositor::new(&mut display, event_loop.handle(), config, js_runtime);
+    compositor.safe_mode = safe_mode;
     println!("✓ Compositor initialized");
 
+    // Warn about event loop stages that take too long
+    compositor.watchdog = watchdog::Watchdog::start(Duration::from_millis(
+        compositor.config.performance.watchdog_threshold_ms as u64,
+    ));
+    if let Some(ref mut js_runtime) = compositor.js_runtime {
+        js_runtime.set_watchdog(compositor.watchdog.clone());
+    }
+    compositor.config_dir = Some(PathBuf::from(&config_dir));
+
+    if let Some(ref path) = options.record_input {
+        compositor.input_handler.recorder = Some(InputRecorder::create(path)?);
+        println!("✓ Recording input to: {}", path);
+    }
+
     // Initialize backend with event handler
     println!("Initializing backend...");
     let mut backend = WebWMBackend::new(&event_loop)?;
@@ -156,6 +478,10 @@ fn run_compositor() -> Result<(), Box<dyn std::error::Error>> {
     if let Some(output) = backend.get_output() {
         compositor.space.map_output(output, (0, 0));
     }
+    backend.add_virtual_outputs(&mut compositor, &options.virtual_outputs);
+    backend.configure_outputs(&mut compositor);
+    compositor.apply_output_scales();
+    compositor.outputs_changed();
 
     // Get the Wayland socket name
     let socket = smithay::wayland::socket::ListeningSocketSource::new_auto()?;
@@ -168,6 +494,216 @@ fn run_compositor() -> Result<(), Box<dyn std::error::Error>> {
     // Also set DISPLAY for XWayland compatibility (empty for pure Wayland)
     env::set_var("DISPLAY", "");
 
+    // IPC socket for webwm-msg
+    match ipc::init_ipc(&event_loop.handle(), &socket_name) {
+        Ok(path) => println!("✓ IPC socket created: {}", path.display()),
+        Err(e) => eprintln!("⚠️  Failed to create IPC socket: {}", e),
+    }
+
+    // Settings page, if enabled in desktop.xml
+    if let Some(settings) = compositor
+        .config
+        .desktop
+        .as_ref()
+        .and_then(|desktop| desktop.web_ui.clone())
+    {
+        match web_ui::init_web_ui(&event_loop.handle(), settings.port) {
+            Ok(url) => println!("✓ Settings page at {}", url),
+            Err(e) => eprintln!("⚠️  Failed to start the settings page: {}", e),
+        }
+    }
+
+    // Keep the cache directory under its size limit
+    let cache_size_mb = compositor
+        .config
+        .desktop
+        .as_ref()
+        .map(|desktop| desktop.storage.cache_size_mb)
+        .unwrap_or_default();
+    if cache_size_mb > 0 {
+        storage::prune_cache_dir(cache_size_mb);
+    }
+
+    // Prometheus metrics, if enabled in desktop.xml
+    if let Some(port) = compositor.config.performance.metrics_port {
+        match web_ui::init_metrics_endpoint(&event_loop.handle(), port) {
+            Ok(address) => println!("✓ Metrics at http://{}/metrics", address),
+            Err(e) => eprintln!("⚠️  Failed to start the metrics endpoint: {}", e),
+        }
+    }
+
+    // Speaker badges for windows playing audio
+    if let Err(e) = compositor::audio::init_audio_monitor(&event_loop.handle()) {
+        eprintln!("⚠️  Failed to start the audio monitor: {}", e);
+    }
+
+    // `power` events for charger and battery level changes
+    if let Err(e) = compositor::power::init_power_monitor(&event_loop.handle()) {
+        eprintln!("⚠️  Failed to start the power monitor: {}", e);
+    }
+
+    // Reload the configuration when its files are saved
+    // In safe mode, the broken file is watched too
+    let watched_files: Vec<PathBuf> = compositor
+        .config
+        .files
+        .iter()
+        .chain(compositor.safe_mode.as_ref().map(|e| &e.file))
+        .cloned()
+        .collect();
+    match compositor::config_reload::init_config_watcher(
+        &event_loop.handle(),
+        Path::new(&config_dir),
+        &watched_files,
+    ) {
+        Ok(watch) => {
+            compositor.config_watch = Some(watch);
+            println!("✓ Watching {} for changes", config_dir);
+        }
+        Err(e) => eprintln!("⚠️  Config hot reload disabled: {}", e),
+    }
+
+    if options.session {
+        session::import_environment();
+        log::info!(
+            "WebWM {} running on {} (backend: {}, config: {})",
+            env!("CARGO_PKG_VERSION"),
+            socket_name,
+            env::var("WEBWM_BACKEND").unwrap_or_else(|_| "winit".to_string()),
+            config_dir
+        );
+    } else {
+        print_running_banner(&socket_name, &compositor.config);
+    }
+
+    // Insert socket into event loop
+    event_loop.handle().insert_source(socket, {
+        let mut dh = display.handle();
+        move |client_stream, _, _| {
+            if let Err(e) = dh.insert_client(
+                client_stream,
+                Arc::new(ClientState {
+                    compositor_state: Default::default(),
+                }),
+            ) {
+                eprintln!("Error accepting client: {}", e);
+            }
+        }
+    })?;
+
+    if let Some(bench_options) = options.bench {
+        bench::start(
+            &event_loop.handle(),
+            event_loop.get_signal(),
+            &socket_name,
+            bench_options,
+        )?;
+    }
+
+    if let Some(ref path) = options.replay_input {
+        let events = input_trace::load_trace(path)?;
+        input_trace::start_replay(&event_loop.handle(), events)?;
+    }
+
+    // Client requests are dispatched as soon as they arrive, like input,
+    // instead of waiting for the next frame
+    event_loop.handle().insert_source(
+        Generic::new(display, Interest::READ, Mode::Level),
+        |_, display, compositor| {
+            let _stage = compositor.watchdog.enter("wayland_clients");
+            let start = Instant::now();
+            // Safety: the display is never dropped while the source is alive
+            unsafe {
+                display.get_mut().dispatch_clients(compositor)?;
+            }
+            compositor
+                .profiler
+                .record_dispatch("wayland_clients", start);
+            Ok(PostAction::Continue)
+        },
+    )?;
+
+    // Rendering runs on its own schedule, so input sources never wait behind a
+    // frame. All outputs are drawn together, so the fastest cap wins. Without
+    // a configured cap an output renders at its refresh rate.
+    let performance = &compositor.config.performance;
+    let max_fps = backend
+        .outputs()
+        .iter()
+        .map(|output| {
+            performance.max_fps_for(&output.name()).unwrap_or_else(|| {
+                let refresh = output.current_mode().map(|m| m.refresh).unwrap_or(60_000);
+                ((refresh + 500) / 1000).max(1) as u32
+            })
+        })
+        .reduce(|a, b| if a == 0 || b == 0 { 0 } else { a.max(b) })
+        .unwrap_or_else(|| performance.max_fps.unwrap_or(60));
+    let mut scheduler = FrameScheduler::new(max_fps, performance.idle_fps, Instant::now());
+    compositor.frame_pacing.set_max_fps(max_fps);
+    let loop_handle = event_loop.handle();
+    let mut armed_frame: Option<Instant> = None;
+
+    // Render the first frame straight away
+    loop_handle.insert_source(Timer::immediate(), |_, _, _| TimeoutAction::Drop)?;
+
+    // Night light follows the time of day
+    compositor.update_night_light();
+    loop_handle.insert_source(
+        Timer::from_duration(NIGHT_LIGHT_INTERVAL),
+        |_, _, compositor| {
+            compositor.update_night_light();
+            TimeoutAction::ToDuration(NIGHT_LIGHT_INTERVAL)
+        },
+    )?;
+
+    // Ping clients so hung ones can be marked instead of looking frozen
+    loop_handle.insert_source(Timer::from_duration(PING_INTERVAL), |_, _, compositor| {
+        compositor.ping_clients(Instant::now());
+        TimeoutAction::ToDuration(PING_INTERVAL)
+    })?;
+
+    // onStartup() handlers, once per session rather than per config load
+    compositor.emit_event("startup", serde_json::json!({}));
+
+    // Run event loop, blocking until input, a client or the next frame wakes it
+    event_loop.run(None::<Duration>, &mut compositor, |compositor| {
+        // Send replies to input and client requests right away
+        if let Err(e) = compositor.display_handle.flush_clients() {
+            eprintln!("Failed to flush clients: {}", e);
+        }
+
+        let idle = compositor.is_idle();
+        if !idle {
+            scheduler.wake(Instant::now());
+        }
+
+        if scheduler.is_due(Instant::now()) {
+            let _stage = compositor.watchdog.enter("render");
+            if let Err(e) = backend.render(compositor) {
+                eprintln!("Render error: {:?}", e);
+            }
+            scheduler.frame_done(Instant::now(), idle);
+            compositor.frame_pacing.frame_done(idle);
+        }
+
+        // One-shot wakeup for the next frame. A wakeup left over from a later
+        // deadline just runs an iteration with nothing due, so it isn't removed.
+        let now = Instant::now();
+        let deadline = scheduler.next_frame();
+        if armed_frame.is_none_or(|at| at <= now || deadline < at) {
+            match loop_handle.insert_source(Timer::from_deadline(deadline), |_, _, _| {
+                TimeoutAction::Drop
+            }) {
+                Ok(_) => armed_frame = Some(deadline),
+                Err(e) => eprintln!("Failed to schedule frame: {}", e),
+            }
+        }
+    })?;
+
+    Ok(())
+}
+
+fn print_running_banner(socket_name: &str, config: &config::Config) {
     println!("\n===========================================");
     println!("  WebWM is running!");
     println!("===========================================");
@@ -180,6 +716,9 @@ fn run_compositor() -> Result<(), Box<dyn std::error::Error>> {
             println!("Mode: Standalone (DRM backend)");
             println!("Note: This is basic DRM mode with placeholder rendering");
         }
+        "headless" => {
+            println!("Mode: Headless (no display output)");
+        }
         _ => {
             println!("Mode: Nested (winit backend)");
         }
@@ -189,7 +728,7 @@ fn run_compositor() -> Result<(), Box<dyn std::error::Error>> {
     println!("  WAYLAND_DISPLAY={} kitty", socket_name);
     println!("  WAYLAND_DISPLAY={} weston-terminal", socket_name);
     println!("\nKeybindings active:");
-    for kb in compositor.config.keybindings.iter().take(5) {
+    for kb in config.keybindings.iter().take(5) {
         let mods = if kb.modifiers.is_empty() {
             String::new()
         } else {
@@ -202,41 +741,6 @@ fn run_compositor() -> Result<(), Box<dyn std::error::Error>> {
     }
     println!("\nPress Ctrl+C to exit");
     println!("===========================================\n");
-
-    // Insert socket into event loop
-    event_loop.handle().insert_source(socket, {
-        let mut dh = display.handle();
-        move |client_stream, _, _| {
-            if let Err(e) = dh.insert_client(
-                client_stream,
-                Arc::new(ClientState {
-                    compositor_state: Default::default(),
-                }),
-            ) {
-                eprintln!("Error accepting client: {}", e);
-            }
-        }
-    })?;
-
-    // Add periodic rendering
-    let timer = Timer::from_duration(Duration::from_millis(16)); // ~60 FPS
-    event_loop.handle().insert_source(timer, |_, _, _| {
-        TimeoutAction::ToDuration(Duration::from_millis(16))
-    })?;
-
-    // Run event loop
-    event_loop.run(Duration::from_millis(16), &mut compositor, |compositor| {
-        // Dispatch Wayland events
-        display.dispatch_clients(compositor).unwrap();
-        display.flush_clients().unwrap();
-
-        // Render frame
-        if let Err(e) = backend.render(compositor) {
-            eprintln!("Render error: {:?}", e);
-        }
-    })?;
-
-    Ok(())
 }
 
 impl WebWMCompositor {
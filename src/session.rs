@@ -0,0 +1,70 @@
+// Display manager session support (`webwm --session`)
+//
+// Started through `webwm-session`, stdout is not a terminal anyone reads, so
+// the startup banners are replaced by log records (which end up in the
+// journal via systemd-cat) and the environment is pushed to systemd and
+// D-Bus so activated services and portals can find the compositor.
+
+use std::env;
+use std::process::{Command, Stdio};
+
+/// Variables other session services need to reach us
+const SESSION_VARIABLES: &[&str] = &[
+    "WAYLAND_DISPLAY",
+    "DISPLAY",
+    "XDG_CURRENT_DESKTOP",
+    "XDG_SESSION_TYPE",
+    "WEBWM_SOCK",
+];
+
+/// Fill in what `webwm-session` would have set, in case the binary is
+/// started directly with `--session`
+pub fn prepare_environment() {
+    for (key, value) in [
+        ("XDG_SESSION_TYPE", "wayland"),
+        ("XDG_CURRENT_DESKTOP", "WebWM"),
+        ("XDG_SESSION_DESKTOP", "webwm"),
+    ] {
+        if env::var_os(key).is_none() {
+            env::set_var(key, value);
+        }
+    }
+
+    // There is no parent compositor to nest in
+    if env::var_os("WEBWM_BACKEND").is_none() {
+        env::set_var("WEBWM_BACKEND", "drm-full");
+    }
+}
+
+/// Export the session variables to the systemd user manager and the D-Bus
+/// activation environment
+pub fn import_environment() {
+    let variables: Vec<&str> = SESSION_VARIABLES
+        .iter()
+        .copied()
+        .filter(|name| env::var_os(name).is_some())
+        .collect();
+
+    run_helper(
+        "systemctl",
+        &[&["--user", "import-environment"], &variables[..]].concat(),
+    );
+    run_helper(
+        "dbus-update-activation-environment",
+        &[&["--systemd"], &variables[..]].concat(),
+    );
+}
+
+fn run_helper(program: &str, args: &[&str]) {
+    match Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()
+    {
+        Ok(status) if status.success() => log::info!("{} {}", program, args.join(" ")),
+        Ok(status) => log::warn!("{} exited with {}", program, status),
+        // Not every system has systemd or the D-Bus tools
+        Err(e) => log::info!("Skipping {}: {}", program, e),
+    }
+}
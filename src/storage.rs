@@ -0,0 +1,187 @@
+// State and cache directories
+//
+// Config files are written by the user; everything WebWM writes for itself
+// goes into one of two directories instead:
+//
+//   $XDG_STATE_HOME/webwm (~/.local/state/webwm)  things worth keeping,
+//       like session restore files and the JS `storage` object
+//   $XDG_CACHE_HOME/webwm (~/.cache/webwm)  things that can be rebuilt,
+//       like icons/ and thumbnails/
+//
+// The cache is kept under `<storage cache-size="256MB" />` by dropping the
+// least recently written files at startup, and `webwm-msg clear-cache`
+// empties it.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Kinds of cached files, each in its own subdirectory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheKind {
+    Icons,
+    Thumbnails,
+}
+
+impl CacheKind {
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            CacheKind::Icons => "icons",
+            CacheKind::Thumbnails => "thumbnails",
+        }
+    }
+}
+
+fn xdg_dir(variable: &str, fallback: &str) -> Option<PathBuf> {
+    let base = std::env::var_os(variable)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))?;
+    Some(base.join("webwm"))
+}
+
+/// `$XDG_STATE_HOME/webwm`, falling back to `~/.local/state/webwm`
+pub fn state_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_STATE_HOME", ".local/state")
+}
+
+/// `$XDG_CACHE_HOME/webwm`, falling back to `~/.cache/webwm`
+pub fn cache_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CACHE_HOME", ".cache")
+}
+
+/// Contents of a state file, None if it was never written
+pub fn read_state(name: &str) -> Option<String> {
+    fs::read_to_string(state_dir()?.join(name)).ok()
+}
+
+/// Replace a state file. Written to a temporary file first, so a crash
+/// never leaves half of it behind.
+pub fn write_state(name: &str, contents: &str) -> Result<(), String> {
+    let dir = state_dir().ok_or("Neither XDG_STATE_HOME nor HOME is set")?;
+    write_atomic(&dir.join(name), contents)
+}
+
+fn write_atomic(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, contents)
+        .and_then(|_| fs::rename(&temporary, path))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Path for the cached file `key` of `kind`, creating its directory. Keys
+/// are file names; path separators are replaced.
+pub fn cache_path(kind: CacheKind, key: &str) -> Option<PathBuf> {
+    let dir = cache_dir()?.join(kind.dir_name());
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(key.replace(['/', '\\'], "_")))
+}
+
+/// Every file under `dir` with its size and modification time
+fn cached_files(dir: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    let mut files = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            files.extend(cached_files(&entry.path()));
+        } else {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((entry.path(), metadata.len(), modified));
+        }
+    }
+    files
+}
+
+/// Total size of the files under `dir`, in bytes
+pub fn cache_usage(dir: &Path) -> u64 {
+    cached_files(dir).iter().map(|(_, size, _)| size).sum()
+}
+
+/// Delete the least recently written files under `dir` until it holds at
+/// most `limit` bytes. Returns the number of bytes freed.
+pub fn prune_cache(dir: &Path, limit: u64) -> u64 {
+    let mut files = cached_files(dir);
+    let mut usage: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+
+    let mut freed = 0;
+    for (path, size, _) in files {
+        if usage <= limit {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            usage -= size;
+            freed += size;
+        }
+    }
+    freed
+}
+
+/// Delete everything under `dir`. Returns the number of bytes freed.
+pub fn clear_cache(dir: &Path) -> io::Result<u64> {
+    let usage = cache_usage(dir);
+    match fs::remove_dir_all(dir) {
+        Ok(()) => Ok(usage),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+/// Bring the cache under its size limit, at startup
+pub fn prune_cache_dir(limit_mb: u64) {
+    let Some(dir) = cache_dir() else {
+        return;
+    };
+    let freed = prune_cache(&dir, limit_mb * 1024 * 1024);
+    if freed > 0 {
+        println!(
+            "🧹 Pruned {} KiB from {} (limit {}MB)",
+            freed / 1024,
+            dir.display(),
+            limit_mb
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_prune_cache() {
+        let dir = std::env::temp_dir().join(format!("webwm-cache-{}", std::process::id()));
+        let icons = dir.join("icons");
+        fs::create_dir_all(&icons).unwrap();
+
+        // Oldest first: a, b, c, 100 bytes each
+        let now = SystemTime::now();
+        for (i, name) in ["a", "b", "c"].iter().enumerate() {
+            let path = icons.join(name);
+            fs::write(&path, [0u8; 100]).unwrap();
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(now - Duration::from_secs(60 * (3 - i as u64)))
+                .unwrap();
+        }
+        assert_eq!(cache_usage(&dir), 300);
+
+        assert_eq!(prune_cache(&dir, 250), 100);
+        assert!(!icons.join("a").exists());
+        assert!(icons.join("b").exists());
+        assert_eq!(prune_cache(&dir, 250), 0);
+
+        assert_eq!(clear_cache(&dir).unwrap(), 200);
+        assert!(!dir.exists());
+        assert_eq!(clear_cache(&dir).unwrap(), 0);
+    }
+}
@@ -0,0 +1,3164 @@
+// Integration test harness
+//
+// Runs a real compositor on the headless backend behind a private Wayland
+// socket and connects scripted fake clients to it. Client calls block on
+// their own thread while the test thread keeps dispatching the compositor,
+// so tests read as straight-line scripts.
+
+use smithay::reexports::{calloop::EventLoop, wayland_server::Display};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::compositor::bar::BarElement;
+use crate::compositor::headless_backend::HeadlessBackend;
+use crate::compositor::{window_app_id, ClientState, WebWMCompositor};
+use crate::config;
+use crate::fake_client::{FakeClient, FakeClientOptions};
+
+const WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct TestCompositor {
+    event_loop: EventLoop<'static, WebWMCompositor>,
+    display: Display<WebWMCompositor>,
+    pub compositor: WebWMCompositor,
+    pub backend: HeadlessBackend,
+    socket_name: String,
+}
+
+impl TestCompositor {
+    /// Start a compositor using the configuration shipped in `config/`
+    pub fn new() -> Self {
+        let config_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/config");
+        let (config, js_runtime) =
+            config::load_config(config_dir).expect("Failed to load test configuration");
+        Self::with_config(config, js_runtime)
+    }
+
+    pub fn with_config(config: config::Config, js_runtime: Option<config::JSRuntime>) -> Self {
+        if std::env::var_os("XDG_RUNTIME_DIR").is_none() {
+            std::env::set_var("XDG_RUNTIME_DIR", std::env::temp_dir());
+        }
+
+        let event_loop = EventLoop::try_new().expect("Failed to create event loop");
+        let mut display = Display::<WebWMCompositor>::new().expect("Failed to create display");
+        let mut compositor =
+            WebWMCompositor::new(&mut display, event_loop.handle(), config, js_runtime);
+
+        let backend = HeadlessBackend::new(1920, 1080);
+        compositor.space.map_output(&backend.output, (0, 0));
+        compositor.apply_output_scales();
+
+        let socket = smithay::wayland::socket::ListeningSocketSource::new_auto()
+            .expect("Failed to create Wayland socket");
+        let socket_name = socket.socket_name().to_string_lossy().into_owned();
+
+        event_loop
+            .handle()
+            .insert_source(socket, {
+                let mut dh = display.handle();
+                move |client_stream, _, _| {
+                    if let Err(e) = dh.insert_client(
+                        client_stream,
+                        Arc::new(ClientState {
+                            compositor_state: Default::default(),
+                        }),
+                    ) {
+                        eprintln!("Error accepting client: {}", e);
+                    }
+                }
+            })
+            .expect("Failed to insert socket source");
+
+        Self {
+            event_loop,
+            display,
+            compositor,
+            backend,
+            socket_name,
+        }
+    }
+
+    /// Run one iteration of the compositor loop and render a frame
+    pub fn dispatch(&mut self) {
+        self.event_loop
+            .dispatch(Some(Duration::from_millis(1)), &mut self.compositor)
+            .expect("Event loop dispatch failed");
+        self.display
+            .dispatch_clients(&mut self.compositor)
+            .expect("Failed to dispatch clients");
+        self.display
+            .flush_clients()
+            .expect("Failed to flush clients");
+        self.backend
+            .render_frame(&mut self.compositor)
+            .expect("Headless render failed");
+    }
+
+    /// Run a blocking client operation while the compositor keeps going
+    pub fn run<T: Send>(&mut self, f: impl FnOnce() -> T + Send) -> T {
+        thread::scope(|scope| {
+            let handle = scope.spawn(f);
+            while !handle.is_finished() {
+                self.dispatch();
+            }
+            handle.join().expect("Client thread panicked")
+        })
+    }
+
+    /// Connect a new client with the given app_id and wait until it has
+    /// committed a buffer for its first configure
+    pub fn connect(&mut self, app_id: &str) -> FakeClient {
+        let options = FakeClientOptions {
+            app_id: app_id.to_string(),
+            title: app_id.to_string(),
+            ..Default::default()
+        };
+        let socket_name = self.socket_name.clone();
+
+        let mut client = self
+            .run(move || FakeClient::connect(&socket_name, options))
+            .expect("Failed to connect fake client");
+        self.roundtrip(&mut client);
+        client
+    }
+
+    /// Deliver everything the compositor has sent to `client` and back
+    pub fn roundtrip(&mut self, client: &mut FakeClient) {
+        self.run(|| client.roundtrip())
+            .expect("Client roundtrip failed");
+    }
+
+    pub fn disconnect(&mut self, client: FakeClient) {
+        self.run(move || client.close())
+            .expect("Failed to close fake client");
+    }
+
+    /// Keep dispatching until `condition` holds, panicking after a timeout
+    pub fn wait_until(&mut self, mut condition: impl FnMut(&WebWMCompositor) -> bool) {
+        let deadline = Instant::now() + WAIT_TIMEOUT;
+        while !condition(&self.compositor) {
+            assert!(
+                Instant::now() < deadline,
+                "Timed out waiting for compositor"
+            );
+            self.dispatch();
+        }
+    }
+
+    /// App ids of the windows on a workspace, in layout order
+    pub fn workspace_app_ids(&self, workspace_id: u32) -> Vec<String> {
+        self.compositor
+            .workspace_manager
+            .get_workspace_windows(workspace_id)
+            .into_iter()
+            .map(window_app_id)
+            .collect()
+    }
+
+    pub fn focused_app_id(&self) -> Option<String> {
+        self.compositor
+            .workspace_manager
+            .focused_window()
+            .map(window_app_id)
+    }
+
+    /// All text currently drawn on the bar
+    pub fn bar_texts(&self) -> Vec<String> {
+        self.compositor
+            .render_bar_elements()
+            .into_iter()
+            .filter_map(|element| match element {
+                BarElement::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compositor::audio::AudioStream;
+
+    /// Size every tiled window should get with `count` windows on screen
+    fn tiled_size(harness: &TestCompositor, count: i32) -> (i32, i32) {
+        let inner = harness.compositor.config.layout.gaps_inner as i32;
+        let outer = harness.compositor.config.layout.gaps_outer as i32;
+        let bar_height = harness.compositor.bar_height();
+        (
+            (1920 - outer * 2 - inner * (count - 1)) / count,
+            1080 - bar_height - outer * 2,
+        )
+    }
+
+    #[test]
+    fn test_tiling_configure_sizes() {
+        let mut harness = TestCompositor::new();
+
+        let mut first = harness.connect("alpha");
+        assert_eq!(first.state.configured_size, tiled_size(&harness, 1));
+        assert_eq!(first.state.committed_size, Some(tiled_size(&harness, 1)));
+
+        let mut second = harness.connect("beta");
+        harness.roundtrip(&mut first);
+
+        let expected = tiled_size(&harness, 2);
+        assert_eq!(first.state.configured_size, expected);
+        assert_eq!(second.state.configured_size, expected);
+
+        harness.roundtrip(&mut second);
+        assert_eq!(second.state.committed_size, Some(expected));
+    }
+
+    #[test]
+    fn test_layout_transaction() {
+        let mut harness = TestCompositor::new();
+        if let Some(desktop) = harness.compositor.config.desktop.as_mut() {
+            desktop.layout.transaction_timeout_ms = 10_000;
+        }
+        let mut alpha = harness.connect("alpha");
+        let mut beta = harness.connect("beta");
+        harness.roundtrip(&mut alpha);
+        harness.roundtrip(&mut beta);
+        let beta_window = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let before = harness
+            .compositor
+            .space
+            .element_geometry(&beta_window)
+            .unwrap();
+
+        // beta takes the whole width once it has redrawn, not before
+        harness.disconnect(alpha);
+        assert!(harness.compositor.layout_transaction.is_some());
+        let waiting = harness
+            .compositor
+            .space
+            .element_geometry(&beta_window)
+            .unwrap();
+        assert_eq!(waiting.loc, before.loc);
+        harness.roundtrip(&mut beta);
+        assert!(harness.compositor.layout_transaction.is_none());
+        let outer = harness.compositor.get_outer_gaps();
+        let after = harness
+            .compositor
+            .space
+            .element_geometry(&beta_window)
+            .unwrap();
+        assert_eq!(after.loc.x, outer);
+        assert_eq!(after.size.w, tiled_size(&harness, 1).0);
+
+        // A client that doesn't redraw only holds the layout until the timeout
+        if let Some(desktop) = harness.compositor.config.desktop.as_mut() {
+            desktop.layout.transaction_timeout_ms = 20;
+        }
+        let _gamma = harness.connect("gamma");
+        harness.wait_until(|compositor| compositor.layout_transaction.is_none());
+    }
+
+    #[test]
+    fn test_adjust_gaps() {
+        let mut harness = TestCompositor::new();
+        let mut alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+
+        // Narrow gaps between the tiles, wide ones around them
+        let layout = &mut harness.compositor.config.layout;
+        layout.gaps_inner = 10;
+        layout.gaps_outer = 10;
+        harness.compositor.adjust_gaps(-6, 10);
+        harness.roundtrip(&mut alpha);
+        let bar_height = harness.compositor.bar_height();
+        assert_eq!(
+            alpha.state.configured_size,
+            ((1920 - 40 - 4) / 2, 1080 - bar_height - 40)
+        );
+
+        // Gaps don't go below zero
+        harness.compositor.adjust_gaps(-100, 0);
+        assert_eq!(harness.compositor.get_gaps(), 0);
+        assert_eq!(harness.compositor.get_outer_gaps(), 20);
+    }
+
+    #[test]
+    fn test_workspace_entries() {
+        use crate::compositor::workspace::LayoutMode;
+
+        let mut harness = TestCompositor::new();
+        let manager = &harness.compositor.workspace_manager;
+        assert_eq!(
+            manager.get_workspace(4).unwrap().layout_mode,
+            LayoutMode::Floating
+        );
+
+        // Assigned apps open on their workspace, without switching to it
+        let _alpha = harness.connect("alpha");
+        let _code = harness.connect("code");
+        assert_eq!(harness.workspace_app_ids(3), vec!["code"]);
+        assert_eq!(harness.workspace_app_ids(1), vec!["alpha"]);
+        assert_eq!(
+            harness.compositor.workspace_manager.active_workspace_id(),
+            1
+        );
+
+        // The workspace's gaps replace the layout's, and adjusting them
+        // there leaves the others alone
+        let layout = &mut harness.compositor.config.layout;
+        layout.gaps_inner = 10;
+        layout.gaps_outer = 10;
+        harness.compositor.workspace_manager.switch_to_workspace(3);
+        assert_eq!(harness.compositor.get_gaps(), 4);
+        assert_eq!(harness.compositor.get_outer_gaps(), 4);
+        harness.compositor.adjust_gaps(2, 0);
+        assert_eq!(harness.compositor.get_gaps(), 6);
+        harness.compositor.workspace_manager.switch_to_workspace(1);
+        assert_eq!(harness.compositor.get_gaps(), 10);
+    }
+
+    #[test]
+    fn test_master_stack_layout() {
+        use crate::compositor::workspace::LayoutMode;
+
+        let mut harness = TestCompositor::new();
+        let workspace = harness.compositor.workspace_manager.active_workspace_mut();
+        workspace.layout_mode = LayoutMode::MasterStack;
+        workspace.master_ratio = Some(0.5);
+        let gaps = harness.compositor.get_gaps();
+        let height = 1080 - harness.compositor.bar_height() - gaps * 2;
+
+        // Alone, the master gets the whole width
+        let mut alpha = harness.connect("alpha");
+        assert_eq!(alpha.state.configured_size, (1920 - gaps * 2, height));
+
+        let mut beta = harness.connect("beta");
+        let mut gamma = harness.connect("gamma");
+        harness.roundtrip(&mut alpha);
+        harness.roundtrip(&mut beta);
+        let master_width = (1920 - gaps * 3) / 2;
+        let stacked = (1920 - gaps * 3 - master_width, (height - gaps) / 2);
+        assert_eq!(alpha.state.configured_size, (master_width, height));
+        assert_eq!(beta.state.configured_size, stacked);
+        assert_eq!(gamma.state.configured_size, stacked);
+
+        // The focused window (gamma, the newest) swaps into the master tile
+        harness.compositor.promote_to_master();
+        harness.roundtrip(&mut alpha);
+        harness.roundtrip(&mut gamma);
+        assert_eq!(gamma.state.configured_size, (master_width, height));
+        assert_eq!(alpha.state.configured_size, stacked);
+
+        // Ratio adjustments stay within bounds
+        harness.compositor.adjust_master_ratio(0.7);
+        assert_eq!(harness.compositor.master_ratio(), 0.9);
+        harness.roundtrip(&mut gamma);
+        let wide = ((1920 - gaps * 3) as f32 * 0.9).round() as i32;
+        assert_eq!(gamma.state.configured_size, (wide, height));
+    }
+
+    #[test]
+    fn test_scroll_split_resize() {
+        use crate::compositor::workspace::LayoutMode;
+
+        let mut harness = TestCompositor::new();
+        let workspace = harness.compositor.workspace_manager.active_workspace_mut();
+        workspace.layout_mode = LayoutMode::MasterStack;
+        workspace.master_ratio = Some(0.5);
+        let gaps = harness.compositor.get_gaps();
+        let columns_width = 1920 - gaps * 3;
+
+        let mut alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        harness.roundtrip(&mut alpha);
+        let master_width = alpha.state.configured_size.0;
+
+        // Scrolling on a tile isn't a resize, scrolling on the gap is
+        let on_gap = ((gaps + master_width + gaps / 2) as f64, 500.0).into();
+        assert!(!harness
+            .compositor
+            .scroll_split((100.0, 500.0).into(), 0.0, 50.0, false));
+        assert!(harness.compositor.split_indicator.is_none());
+        assert!(harness
+            .compositor
+            .scroll_split(on_gap, 0.0, columns_width as f64 / 10.0, false));
+        assert!((harness.compositor.master_ratio() - 0.6).abs() < 0.001);
+        harness.roundtrip(&mut alpha);
+        let wider = (columns_width as f32 * harness.compositor.master_ratio()).round() as i32;
+        assert_eq!(alpha.state.configured_size.0, wider);
+        assert!(harness
+            .compositor
+            .render_split_indicator()
+            .iter()
+            .any(|element| matches!(element, BarElement::Text { text, .. } if text == "60%")));
+
+        // Super catches the gap from a little way off
+        let near_gap = ((gaps + wider - 10) as f64, 500.0).into();
+        assert!(!harness.compositor.scroll_split(near_gap, 0.0, -20.0, false));
+        assert!(harness.compositor.scroll_split(near_gap, 0.0, -20.0, true));
+    }
+
+    #[test]
+    fn test_dwindle_layout() {
+        use crate::compositor::workspace::LayoutMode;
+
+        let mut harness = TestCompositor::new();
+        let workspace = harness.compositor.workspace_manager.active_workspace_mut();
+        workspace.layout_mode = LayoutMode::Dwindle;
+        workspace.dwindle.default_ratio = 0.5;
+        let gaps = harness.compositor.get_gaps();
+        let width = 1920 - gaps * 2;
+        let height = 1080 - harness.compositor.bar_height() - gaps * 2;
+
+        let mut alpha = harness.connect("alpha");
+        assert_eq!(alpha.state.configured_size, (width, height));
+
+        // Beta splits alpha side by side, gamma splits beta (narrower than
+        // tall) one above the other
+        let mut beta = harness.connect("beta");
+        let mut gamma = harness.connect("gamma");
+        harness.roundtrip(&mut alpha);
+        harness.roundtrip(&mut beta);
+        let left = ((width - gaps) as f32 * 0.5).round() as i32;
+        let right = width - gaps - left;
+        let top = ((height - gaps) as f32 * 0.5).round() as i32;
+        assert_eq!(alpha.state.configured_size, (left, height));
+        assert_eq!(beta.state.configured_size, (right, top));
+        assert_eq!(gamma.state.configured_size, (right, height - gaps - top));
+
+        // Rotating gamma's split puts it beside beta
+        harness.compositor.rotate_split();
+        harness.roundtrip(&mut gamma);
+        let half = ((right - gaps) as f32 * 0.5).round() as i32;
+        assert_eq!(gamma.state.configured_size, (right - gaps - half, height));
+
+        // Closing beta gives gamma its area; alpha doesn't move
+        beta.close().unwrap();
+        harness.wait_until(|c| c.workspace_manager.active_workspace().len() == 2);
+        harness.roundtrip(&mut gamma);
+        harness.roundtrip(&mut alpha);
+        assert_eq!(gamma.state.configured_size, (right, height));
+        assert_eq!(alpha.state.configured_size, (left, height));
+    }
+
+    #[test]
+    fn test_split_direction() {
+        use crate::compositor::dwindle::SplitAxis;
+        use crate::compositor::workspace::LayoutMode;
+
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .workspace_manager
+            .active_workspace_mut()
+            .dwindle
+            .default_ratio = 0.5;
+        let gaps = harness.compositor.get_gaps();
+        let width = 1920 - gaps * 2;
+        let height = 1080 - harness.compositor.bar_height() - gaps * 2;
+        let mut alpha = harness.connect("alpha");
+
+        // Picking a direction turns the workspace into a tree, and the
+        // wide tile is cut top to bottom as asked
+        harness.compositor.choose_split(SplitAxis::Vertical);
+        assert_eq!(
+            harness
+                .compositor
+                .workspace_manager
+                .active_workspace()
+                .layout_mode,
+            LayoutMode::Dwindle
+        );
+        let beta = harness.connect("beta");
+        harness.roundtrip(&mut alpha);
+        let top = ((height - gaps) as f32 * 0.5).round() as i32;
+        assert_eq!(alpha.state.configured_size, (width, top));
+        assert_eq!(beta.state.configured_size, (width, height - gaps - top));
+    }
+
+    #[test]
+    fn test_confirm_close() {
+        use smithay::input::keyboard::keysyms;
+
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .config
+            .window_rules
+            .push(config::WindowRule {
+                app_id: "render".to_string(),
+                confirm_close: Some(true),
+                ..Default::default()
+            });
+        let mut render = harness.connect("render");
+
+        // Asked first; Escape keeps the window
+        harness.compositor.close_focused();
+        assert!(!harness.compositor.render_close_confirm().is_empty());
+        assert!(harness.compositor.close_confirm_key(keysyms::KEY_q));
+        assert!(harness.compositor.close_confirm_key(keysyms::KEY_Escape));
+        assert!(harness.compositor.close_confirm.is_none());
+        harness.roundtrip(&mut render);
+        assert!(!render.state.closed);
+
+        // Enter closes it
+        harness.compositor.close_focused();
+        assert!(harness.compositor.close_confirm_key(keysyms::KEY_Return));
+        harness.roundtrip(&mut render);
+        assert!(render.state.closed);
+        assert!(!harness.compositor.close_confirm_key(keysyms::KEY_Return));
+
+        // Other apps close right away
+        let mut shell = harness.connect("shell");
+        harness.compositor.close_focused();
+        assert!(harness.compositor.close_confirm.is_none());
+        harness.roundtrip(&mut shell);
+        assert!(shell.state.closed);
+    }
+
+    #[test]
+    fn test_tabbed_layout() {
+        use crate::compositor::tabs::TAB_BAR_HEIGHT;
+        use crate::compositor::workspace::LayoutMode;
+
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .workspace_manager
+            .active_workspace_mut()
+            .layout_mode = LayoutMode::Tabbed;
+        let gaps = harness.compositor.get_gaps();
+        let bar_height = harness.compositor.bar_height();
+        let full = (1920 - gaps * 2, 1080 - bar_height - gaps * 2);
+
+        // Alone, no strip
+        let mut alpha = harness.connect("alpha");
+        assert_eq!(alpha.state.configured_size, full);
+
+        // Every tab gets the whole area below the strip, only one is shown
+        let beta = harness.connect("beta");
+        harness.roundtrip(&mut alpha);
+        let tab_size = (full.0, full.1 - TAB_BAR_HEIGHT);
+        assert_eq!(alpha.state.configured_size, tab_size);
+        assert_eq!(beta.state.configured_size, tab_size);
+        let find = |compositor: &WebWMCompositor, app_id: &str| {
+            compositor
+                .workspace_manager
+                .active_workspace()
+                .windows
+                .iter()
+                .find(|window| window_app_id(window) == app_id)
+                .cloned()
+                .unwrap()
+        };
+        let (alpha_window, beta_window) = (
+            find(&harness.compositor, "alpha"),
+            find(&harness.compositor, "beta"),
+        );
+        assert!(harness
+            .compositor
+            .space
+            .element_geometry(&alpha_window)
+            .is_none());
+        assert!(harness
+            .compositor
+            .space
+            .element_geometry(&beta_window)
+            .is_some());
+        assert_eq!(harness.compositor.render_tab_bars().len(), 4);
+
+        // Clicking alpha's title brings it to the front
+        let title = (gaps as f64 + 10.0, (bar_height + gaps) as f64 + 5.0);
+        assert!(harness.compositor.tab_click(title.into()));
+        assert!(harness
+            .compositor
+            .space
+            .element_geometry(&alpha_window)
+            .is_some());
+        assert!(harness
+            .compositor
+            .space
+            .element_geometry(&beta_window)
+            .is_none());
+        assert_eq!(
+            harness.compositor.workspace_manager.focused_window(),
+            Some(&alpha_window)
+        );
+    }
+
+    #[test]
+    fn test_workspace_style_overrides() {
+        let mut harness = TestCompositor::new();
+        let overrides =
+            config::parse_css("workspace[name='1'] { gap: 0; outer-gap: 0; border-width: 0 }")
+                .unwrap();
+        harness
+            .compositor
+            .stylesheet
+            .as_mut()
+            .unwrap()
+            .rules
+            .extend(overrides.rules);
+
+        // Workspace 1 tiles edge to edge, without borders
+        let alpha = harness.connect("alpha");
+        let bar_height = harness.compositor.bar_height();
+        assert_eq!(alpha.state.configured_size, (1920, 1080 - bar_height));
+        assert_eq!(harness.compositor.get_border_width(), 0);
+
+        // Other workspaces keep the global settings
+        harness.compositor.workspace_manager.switch_to_workspace(2);
+        assert_eq!(
+            harness.compositor.get_gaps(),
+            harness.compositor.config.layout.gaps_inner as i32
+        );
+        assert_ne!(harness.compositor.get_border_width(), 0);
+    }
+
+    #[test]
+    fn test_grid_layout() {
+        let mut harness = TestCompositor::new();
+        let grid = config::parse_css(
+            "workspace[name='1'] { display: grid; grid-template-columns: 2fr 1fr; grid-template-areas: 'main side' 'main log' }",
+        )
+        .unwrap();
+        harness
+            .compositor
+            .stylesheet
+            .as_mut()
+            .unwrap()
+            .rules
+            .extend(grid.rules);
+        harness
+            .compositor
+            .config
+            .window_rules
+            .push(config::WindowRule {
+                app_id: "editor".to_string(),
+                grid_area: Some("main".to_string()),
+                ..Default::default()
+            });
+        let gaps = harness.compositor.get_gaps();
+        let width = 1920 - gaps * 2;
+        let height = 1080 - harness.compositor.bar_height() - gaps * 2;
+
+        // The editor gets the tall main area even though it opens last
+        let mut terminal = harness.connect("terminal");
+        let editor = harness.connect("editor");
+        harness.roundtrip(&mut terminal);
+        let main = ((width - gaps) as f32 * 2.0 / 3.0).floor() as i32;
+        let side = ((width - gaps) as f32 / 3.0).floor() as i32;
+        assert_eq!(editor.state.configured_size, (main, height));
+        assert_eq!(terminal.state.configured_size, (side, (height - gaps) / 2));
+
+        // Other workspaces keep tiling side by side
+        harness.compositor.workspace_manager.switch_to_workspace(2);
+        assert!(harness.compositor.workspace_grid().is_none());
+    }
+
+    #[test]
+    fn test_scaled_output_layout() {
+        let mut harness = TestCompositor::new();
+        harness.backend.output.change_current_state(
+            None,
+            None,
+            Some(smithay::output::Scale::Fractional(2.0)),
+            None,
+        );
+        harness.compositor.relayout();
+
+        // 1920x1080 pixels at 2x lay out as 960x540
+        let gaps = harness.compositor.config.layout.gaps_outer as i32;
+        let bar_height = harness.compositor.bar_height();
+        let alpha = harness.connect("alpha");
+        assert_eq!(
+            alpha.state.configured_size,
+            (960 - gaps * 2, 540 - bar_height - gaps * 2)
+        );
+        let window = harness.compositor.space.elements().next().unwrap();
+        assert_eq!(harness.compositor.window_scale(window), 2.0);
+    }
+
+    #[test]
+    fn test_focus_transitions() {
+        let mut harness = TestCompositor::new();
+
+        let _alpha = harness.connect("alpha");
+        assert_eq!(harness.focused_app_id().as_deref(), Some("alpha"));
+
+        // New windows take focus
+        let beta = harness.connect("beta");
+        assert_eq!(harness.focused_app_id().as_deref(), Some("beta"));
+
+        harness.compositor.workspace_manager.focus_next_window();
+        assert_eq!(harness.focused_app_id().as_deref(), Some("alpha"));
+
+        harness.compositor.workspace_manager.focus_next_window();
+        harness.disconnect(beta);
+        harness.wait_until(|c| c.workspace_manager.active_workspace().len() == 1);
+        assert_eq!(harness.focused_app_id().as_deref(), Some("alpha"));
+    }
+
+    #[test]
+    fn test_per_app_workspaces() {
+        let mut harness = TestCompositor::new();
+        if let Some(desktop) = harness.compositor.config.desktop.as_mut() {
+            desktop.workspace_per_app = true;
+        }
+        harness
+            .compositor
+            .js_runtime
+            .as_ref()
+            .unwrap()
+            .evaluate(
+                "setWorkspaceGrouper((w) => w.appId.startsWith('term') ? 'terminals' : null);",
+            )
+            .unwrap();
+        let workspace_of = |harness: &TestCompositor, app_id: &str| {
+            let manager = &harness.compositor.workspace_manager;
+            let workspace = manager
+                .all_workspaces()
+                .into_iter()
+                .find(|ws| ws.windows.iter().any(|w| window_app_id(w) == app_id))
+                .unwrap();
+            (workspace.id, workspace.name.clone())
+        };
+
+        // A new workspace per app, named after it and shown right away
+        let _alpha = harness.connect("alpha");
+        let (alpha_ws, name) = workspace_of(&harness, "alpha");
+        assert_eq!(name, "alpha");
+        assert_eq!(
+            harness.compositor.workspace_manager.active_workspace().id,
+            alpha_ws
+        );
+
+        // The grouper puts both terminals together
+        let _term_a = harness.connect("term-a");
+        let _term_b = harness.connect("term-b");
+        let (terminals_ws, name) = workspace_of(&harness, "term-a");
+        assert_eq!(name, "terminals");
+        assert_eq!(workspace_of(&harness, "term-b").0, terminals_ws);
+        assert_ne!(terminals_ws, alpha_ws);
+
+        // A second alpha window joins the first
+        let _alpha2 = harness.connect("alpha");
+        let manager = &harness.compositor.workspace_manager;
+        assert_eq!(manager.get_workspace(alpha_ws).unwrap().len(), 2);
+        assert_eq!(manager.active_workspace().id, alpha_ws);
+    }
+
+    #[test]
+    fn test_recently_closed() {
+        let mut harness = TestCompositor::new();
+        let alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        let workspace = harness.compositor.workspace_manager.active_workspace().id;
+
+        alpha.close().unwrap();
+        harness.wait_until(|c| !c.recently_closed.is_empty());
+        let closed = harness.compositor.recently_closed.last().unwrap();
+        assert_eq!(closed.app_id, "alpha");
+        assert_eq!((closed.workspace, closed.position), (workspace, 0));
+        // The fake client runs in the test process, whose command line is known
+        assert!(closed.command.is_some());
+    }
+
+    #[test]
+    fn test_warp_target() {
+        let mut harness = TestCompositor::new();
+        let _alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        assert_eq!(harness.compositor.warp_target(), None);
+
+        if let Some(desktop) = harness.compositor.config.desktop.as_mut() {
+            desktop.pointer.warp_pointer = true;
+        }
+        let window = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap();
+        let geometry = harness.compositor.space.element_geometry(window).unwrap();
+        let center = harness.compositor.warp_target().unwrap();
+        assert_eq!(
+            (center.x as i32, center.y as i32),
+            (
+                geometry.loc.x + geometry.size.w / 2,
+                geometry.loc.y + geometry.size.h / 2
+            )
+        );
+        assert!(center.x > 960.0, "beta is the right-hand tile");
+    }
+
+    #[test]
+    fn test_workspace_switching() {
+        let mut harness = TestCompositor::new();
+
+        let _alpha = harness.connect("alpha");
+        harness.compositor.workspace_manager.switch_to_workspace(2);
+        let _beta = harness.connect("beta");
+
+        assert_eq!(harness.workspace_app_ids(1), vec!["alpha"]);
+        assert_eq!(harness.workspace_app_ids(2), vec!["beta"]);
+        assert_eq!(harness.focused_app_id().as_deref(), Some("beta"));
+
+        harness.compositor.workspace_manager.switch_to_workspace(1);
+        assert_eq!(harness.focused_app_id().as_deref(), Some("alpha"));
+    }
+
+    #[test]
+    fn test_drag_window_to_workspace() {
+        let mut harness = TestCompositor::new();
+        let _alpha = harness.connect("alpha");
+
+        // Find workspace 3's button along the bar
+        let y = harness.compositor.bar_height() / 2;
+        let bar = harness.compositor.bar_renderer.as_ref().unwrap();
+        let button = (0..1920)
+            .find(|x| {
+                bar.workspace_at((*x, y), None, &harness.compositor.workspace_manager, None)
+                    .is_some_and(|(id, _)| id == 3)
+            })
+            .unwrap();
+
+        assert!(harness.compositor.begin_window_drag((960.0, 540.0).into()));
+        harness
+            .compositor
+            .window_drag_motion((button as f64 + 4.0, y as f64).into());
+        let drag = harness.compositor.window_drag.as_ref().unwrap();
+        assert_eq!(drag.target.map(|(id, _)| id), Some(3));
+        assert!(harness.bar_texts().iter().any(|t| t == "alpha"));
+
+        harness.compositor.end_window_drag();
+        assert!(harness.compositor.window_drag.is_none());
+        assert!(harness.workspace_app_ids(1).is_empty());
+        assert_eq!(harness.workspace_app_ids(3), vec!["alpha"]);
+    }
+
+    #[test]
+    fn test_exec_and_focus() {
+        let mut harness = TestCompositor::new();
+
+        // The window is recognised by app_id, since the fake client isn't
+        // the spawned process
+        harness
+            .compositor
+            .exec_and_focus("true", Some("alpha".to_string()), Some(3));
+        let _alpha = harness.connect("alpha");
+        assert_eq!(harness.workspace_app_ids(3), vec!["alpha"]);
+        assert_eq!(
+            harness.compositor.workspace_manager.active_workspace_id(),
+            3
+        );
+        assert_eq!(harness.focused_app_id().as_deref(), Some("alpha"));
+
+        // Once open, it is focused instead of launched again
+        harness.compositor.workspace_manager.switch_to_workspace(1);
+        harness
+            .compositor
+            .exec_and_focus("false", Some("alpha".to_string()), None);
+        assert_eq!(
+            harness.compositor.workspace_manager.active_workspace_id(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_run_or_raise_cycles() {
+        let mut harness = TestCompositor::new();
+        let _first = harness.connect("alpha");
+        harness.compositor.workspace_manager.switch_to_workspace(2);
+        let _second = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+
+        harness.compositor.run_or_raise("alpha", "false");
+        assert_eq!(harness.focused_app_id().as_deref(), Some("alpha"));
+        assert_eq!(
+            harness.compositor.workspace_manager.active_workspace_id(),
+            1
+        );
+
+        // Raising again moves on to the other alpha window
+        harness.compositor.run_or_raise("alpha", "false");
+        assert_eq!(
+            harness.compositor.workspace_manager.active_workspace_id(),
+            2
+        );
+        harness.compositor.run_or_raise("alpha", "false");
+        assert_eq!(
+            harness.compositor.workspace_manager.active_workspace_id(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_apply_template() {
+        use crate::compositor::layout_templates::{LayoutTemplate, TemplateSlot};
+
+        let slot = |app_id: &str| TemplateSlot {
+            app_id: app_id.to_string(),
+            command: "true".to_string(),
+        };
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .layout_templates
+            .templates
+            .push(LayoutTemplate {
+                name: "work".to_string(),
+                layout: "tiling".to_string(),
+                tiles: vec![vec![slot("beta")], vec![slot("alpha"), slot("gamma")]],
+            });
+
+        let _busy = harness.connect("delta");
+        assert!(harness.compositor.apply_template("work", Some(1)).is_err());
+        assert_eq!(harness.compositor.apply_template("work", None), Ok(2));
+
+        // Windows arrive in any order but end up in their slots
+        let _alpha = harness.connect("alpha");
+        let _gamma = harness.connect("gamma");
+        let _beta = harness.connect("beta");
+        assert_eq!(harness.workspace_app_ids(2), vec!["beta", "alpha", "gamma"]);
+        let ws = harness
+            .compositor
+            .workspace_manager
+            .get_workspace(2)
+            .unwrap();
+        assert_eq!(ws.tiles().len(), 2);
+        assert_eq!(harness.focused_app_id().as_deref(), Some("beta"));
+    }
+
+    #[test]
+    fn test_input_replay() {
+        use crate::compositor::input_trace::parse_trace;
+
+        let trace = r#"
+{"time_ms":0,"type":"pointer_motion","dx":100.0,"dy":50.0}
+{"time_ms":5,"type":"pointer_motion","dx":-300.0,"dy":20.0}
+{"time_ms":9,"type":"pointer_button","button":272,"pressed":true}
+{"time_ms":12,"type":"pointer_button","button":272,"pressed":false}
+{"time_ms":20,"type":"pointer_motion","dx":4000.0,"dy":0.0}
+"#;
+
+        let mut harness = TestCompositor::new();
+        let _alpha = harness.connect("alpha");
+
+        for timed in parse_trace(trace.as_bytes()).unwrap() {
+            harness.compositor.handle_input(timed.event);
+            harness.dispatch();
+        }
+
+        // Motion is clamped to the output's last pixel, so replays land on
+        // the same spot
+        let location = harness.compositor.pointer_location();
+        assert_eq!((location.x, location.y), (1919.0, 70.0));
+    }
+
+    #[test]
+    fn test_bar_contents() {
+        let mut harness = TestCompositor::new();
+
+        // Workspace names from desktop.xml (long names fall back to the id)
+        let texts = harness.bar_texts();
+        assert!(texts.iter().any(|t| t == "1"));
+        assert!(texts.iter().any(|t| t == "web"));
+
+        let _alpha = harness.connect("alpha");
+        harness.compositor.workspace_manager.switch_to_workspace(2);
+
+        // Occupied but inactive workspaces get an indicator dot
+        let has_dot = harness
+            .compositor
+            .render_bar_elements()
+            .iter()
+            .any(|element| matches!(element, BarElement::Circle { .. }));
+        assert!(has_dot);
+    }
+
+    #[test]
+    fn test_presentation_mode() {
+        let mut harness = TestCompositor::new();
+        let mut alpha = harness.connect("alpha");
+        let with_bar = tiled_size(&harness, 1);
+        assert!(harness.compositor.bar_height() > 0);
+
+        // desktop.xml hides the bar, so the window grows into its space
+        harness.compositor.toggle_presentation_mode();
+        harness.roundtrip(&mut alpha);
+        assert_eq!(harness.compositor.bar_height(), 0);
+        assert_eq!(alpha.state.configured_size, tiled_size(&harness, 1));
+        assert!(harness.compositor.idle_notifier_state.is_inhibited());
+
+        let texts = harness.bar_texts();
+        assert!(texts.iter().any(|t| t == "PRESENTING"));
+        assert!(!texts.iter().any(|t| t == "web"));
+
+        harness.compositor.toggle_presentation_mode();
+        harness.roundtrip(&mut alpha);
+        assert_eq!(alpha.state.configured_size, with_bar);
+        assert!(!harness.compositor.idle_notifier_state.is_inhibited());
+        assert!(!harness.bar_texts().iter().any(|t| t == "PRESENTING"));
+    }
+
+    #[test]
+    fn test_bar_visible_per_output() {
+        use crate::ipc::{dispatch, IpcRequest};
+
+        let mut harness = TestCompositor::new();
+        let mut alpha = harness.connect("alpha");
+        let with_bar = tiled_size(&harness, 1);
+        let request = |args: &[&str]| IpcRequest {
+            command: "bar-visible".to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        };
+
+        // Hiding the bar on the only output gives windows its room
+        let response = dispatch(&mut harness.compositor, &request(&["HEADLESS-1", "off"]));
+        assert!(response.success);
+        harness.roundtrip(&mut alpha);
+        assert_eq!(harness.compositor.bar_height(), 0);
+        assert!(!harness.bar_texts().iter().any(|t| t == "web"));
+        assert_eq!(alpha.state.configured_size, tiled_size(&harness, 1));
+
+        assert!(!dispatch(&mut harness.compositor, &request(&["HDMI-A-1"])).success);
+        harness.compositor.toggle_bar(None).unwrap();
+        harness.roundtrip(&mut alpha);
+        assert!(harness.bar_texts().iter().any(|t| t == "web"));
+        assert_eq!(alpha.state.configured_size, with_bar);
+    }
+
+    #[test]
+    fn test_focus_output() {
+        use smithay::output::{Mode, Output, PhysicalProperties, Subpixel};
+
+        let mut harness = TestCompositor::new();
+        let second = Output::new(
+            "HEADLESS-2".into(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: "WebWM".into(),
+                model: "Headless".into(),
+                serial_number: String::new(),
+            },
+        );
+        let mode = Mode {
+            size: (1280, 1024).into(),
+            refresh: 60_000,
+        };
+        second.change_current_state(Some(mode), None, None, None);
+        harness.compositor.space.map_output(&second, (1920, 0));
+
+        // Without warp-to-output only focus moves
+        if let Some(desktop) = harness.compositor.config.desktop.as_mut() {
+            desktop.pointer.warp_to_output = false;
+        }
+        assert_eq!(harness.compositor.focus_output("right"), None);
+        assert_eq!(
+            harness.compositor.focused_output_name().as_deref(),
+            Some("HEADLESS-2")
+        );
+        assert_eq!(harness.compositor.focus_output("right"), None);
+
+        // With it the pointer goes to the middle of the output
+        if let Some(desktop) = harness.compositor.config.desktop.as_mut() {
+            desktop.pointer.warp_to_output = true;
+        }
+        assert_eq!(
+            harness.compositor.focus_output("left"),
+            Some((960.0, 540.0).into())
+        );
+        assert_eq!(
+            harness.compositor.focused_output_name().as_deref(),
+            Some("HEADLESS-1")
+        );
+    }
+
+    #[test]
+    fn test_tab_merge_and_split() {
+        let mut harness = TestCompositor::new();
+
+        let _alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        let _gamma = harness.connect("gamma");
+
+        // gamma is focused; merge it into beta's tile
+        harness.compositor.merge_focused_window("left");
+        let workspace = harness.compositor.workspace_manager.active_workspace();
+        assert_eq!(workspace.tiles().len(), 2);
+        let group = workspace.tab_groups[0].clone();
+        let app_ids: Vec<String> = group.windows.iter().map(window_app_id).collect();
+        assert_eq!(app_ids, vec!["beta", "gamma"]);
+
+        // Only the focused tab is mapped
+        assert!(harness
+            .compositor
+            .space
+            .element_geometry(&group.windows[0])
+            .is_none());
+        assert!(harness
+            .compositor
+            .space
+            .element_geometry(&group.windows[1])
+            .is_some());
+
+        harness.compositor.split_focused_window();
+        let workspace = harness.compositor.workspace_manager.active_workspace();
+        assert!(workspace.tab_groups.is_empty());
+        assert_eq!(workspace.tiles().len(), 3);
+    }
+
+    #[test]
+    fn test_audio_badge() {
+        let mut harness = TestCompositor::new();
+        let _alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        harness.compositor.merge_focused_window("left");
+        let tab_elements = harness.compositor.render_tab_bars().len();
+
+        // Fake clients run in this process, so a stream of it plays for them
+        let beta = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .cloned()
+            .unwrap();
+        assert_eq!(harness.compositor.window_audio(&beta), None);
+        harness.compositor.audio.streams.push(AudioStream {
+            node_id: 71,
+            pids: vec![std::process::id(), 1000],
+            muted: true,
+        });
+        assert_eq!(harness.compositor.window_audio(&beta), Some(true));
+
+        // Both tabs get a muted speaker, without sound waves
+        assert_eq!(harness.compositor.render_tab_bars().len(), tab_elements + 4);
+    }
+
+    #[test]
+    fn test_fullscreen_modes() {
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .config
+            .window_rules
+            .push(config::WindowRule {
+                app_id: "beta".to_string(),
+                workspace: None,
+                floating: None,
+                css_class: None,
+                fullscreen: Some("container".to_string()),
+                ..Default::default()
+            });
+
+        let mut alpha = harness.connect("alpha");
+        let mut beta = harness.connect("beta");
+
+        // Container fullscreen keeps the tile size
+        beta.toplevel.set_fullscreen(None);
+        harness.roundtrip(&mut beta);
+        assert_eq!(beta.state.configured_size, tiled_size(&harness, 2));
+
+        alpha.toplevel.set_fullscreen(None);
+        harness.roundtrip(&mut alpha);
+        assert_eq!(alpha.state.configured_size, (1920, 1080));
+
+        alpha.toplevel.unset_fullscreen();
+        harness.roundtrip(&mut alpha);
+        assert_eq!(alpha.state.configured_size, tiled_size(&harness, 2));
+    }
+
+    #[test]
+    fn test_maximize_requests() {
+        let mut harness = TestCompositor::new();
+        let mut alpha = harness.connect("alpha");
+        let mut beta = harness.connect("beta");
+        let bar_height = harness.compositor.bar_height();
+
+        // Below the bar, without gaps
+        alpha.toplevel.set_maximized();
+        harness.roundtrip(&mut alpha);
+        assert_eq!(alpha.state.configured_size, (1920, 1080 - bar_height));
+
+        // Fullscreen covers the bar and goes back to maximized afterwards
+        alpha.toplevel.set_fullscreen(None);
+        harness.roundtrip(&mut alpha);
+        assert_eq!(alpha.state.configured_size, (1920, 1080));
+        alpha.toplevel.unset_fullscreen();
+        harness.roundtrip(&mut alpha);
+        assert_eq!(alpha.state.configured_size, (1920, 1080 - bar_height));
+
+        // Maximizing beta gives alpha its tile back
+        beta.toplevel.set_maximized();
+        harness.roundtrip(&mut beta);
+        harness.roundtrip(&mut alpha);
+        assert_eq!(beta.state.configured_size, (1920, 1080 - bar_height));
+        assert_eq!(alpha.state.configured_size, tiled_size(&harness, 2));
+
+        beta.toplevel.unset_maximized();
+        harness.roundtrip(&mut beta);
+        assert_eq!(beta.state.configured_size, tiled_size(&harness, 2));
+    }
+
+    #[test]
+    fn test_content_type_rules() {
+        use crate::compositor::content_type::window_content_type;
+        use wayland_protocols::wp::content_type::v1::client::wp_content_type_v1::Type;
+
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .config
+            .window_rules
+            .push(config::WindowRule {
+                content_type: Some("game".to_string()),
+                direct_scanout: Some(true),
+                ..Default::default()
+            });
+
+        let mut game = harness.connect("game");
+        game.toplevel.set_fullscreen(None);
+        harness.roundtrip(&mut game);
+
+        // Not a game until it says so
+        let window = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        assert_eq!(window_content_type(&window), None);
+        assert!(harness.compositor.direct_scanout_window().is_none());
+
+        game.set_content_type(Type::Game).unwrap();
+        harness.roundtrip(&mut game);
+        assert_eq!(window_content_type(&window), Some("game"));
+        assert_eq!(
+            harness.compositor.direct_scanout_window(),
+            Some(window.clone())
+        );
+
+        // Only while it is fullscreen
+        game.toplevel.unset_fullscreen();
+        harness.roundtrip(&mut game);
+        assert!(harness.compositor.direct_scanout_window().is_none());
+    }
+
+    #[test]
+    fn test_tearing_control() {
+        use wayland_protocols::wp::tearing_control::v1::client::wp_tearing_control_v1::PresentationHint;
+
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .config
+            .window_rules
+            .push(config::WindowRule {
+                app_id: "game".to_string(),
+                allow_tearing: Some(true),
+                ..Default::default()
+            });
+
+        let mut game = harness.connect("game");
+        let mut other = harness.connect("other");
+        game.set_presentation_hint(PresentationHint::Async).unwrap();
+        harness.roundtrip(&mut game);
+        other
+            .set_presentation_hint(PresentationHint::Async)
+            .unwrap();
+        harness.roundtrip(&mut other);
+
+        // Tiled windows never tear
+        assert!(harness.compositor.tearing_window().is_none());
+
+        game.toplevel.set_fullscreen(None);
+        harness.roundtrip(&mut game);
+        let tearing = harness.compositor.tearing_window().unwrap();
+        assert_eq!(window_app_id(&tearing), "game");
+
+        // Without a rule allowing it the hint is ignored
+        game.toplevel.unset_fullscreen();
+        harness.roundtrip(&mut game);
+        other.toplevel.set_fullscreen(None);
+        harness.roundtrip(&mut other);
+        assert!(harness.compositor.tearing_window().is_none());
+    }
+
+    #[test]
+    fn test_input_inhibit() {
+        use crate::compositor::input_trace::InputTraceEvent;
+        use smithay::reexports::wayland_server::Resource;
+
+        let mut harness = TestCompositor::new();
+        let _locker = harness.connect("locker");
+        let _beta = harness.connect("beta");
+
+        let windows = harness
+            .compositor
+            .workspace_manager
+            .get_workspace_windows(1);
+        let locker = windows[0].toplevel().unwrap().wl_surface().clone();
+        let beta = windows[1].toplevel().unwrap().wl_surface().clone();
+        let keyboard = harness.compositor.seat.get_keyboard().unwrap();
+        let pointer = harness.compositor.seat.get_pointer().unwrap();
+        assert_eq!(keyboard.current_focus(), Some(beta.clone()));
+
+        harness
+            .compositor
+            .begin_input_inhibit(locker.client().unwrap().id());
+        assert_eq!(keyboard.current_focus(), Some(locker.clone()));
+        assert!(!harness.compositor.input_allowed(&beta));
+
+        // The pointer passes over beta, which is on the right, without
+        // giving it focus
+        let location = harness.compositor.pointer_location();
+        harness
+            .compositor
+            .handle_input(InputTraceEvent::PointerMotion {
+                dx: 1500.0 - location.x,
+                dy: 540.0 - location.y,
+            });
+        assert_eq!(pointer.current_focus(), None);
+
+        harness.compositor.end_input_inhibit();
+        harness
+            .compositor
+            .handle_input(InputTraceEvent::PointerMotion { dx: 1.0, dy: 0.0 });
+        assert_eq!(pointer.current_focus(), Some(beta));
+    }
+
+    #[test]
+    fn test_region_select() {
+        use crate::compositor::input_trace::InputTraceEvent;
+        use crate::compositor::region_select::format_geometry;
+
+        let mut harness = TestCompositor::new();
+        let location = harness.compositor.pointer_location();
+        harness
+            .compositor
+            .begin_region_select(String::new(), location);
+
+        let events = [
+            InputTraceEvent::PointerMotion {
+                dx: 100.0,
+                dy: 100.0,
+            },
+            InputTraceEvent::PointerButton {
+                button: 272,
+                pressed: true,
+            },
+            InputTraceEvent::PointerMotion {
+                dx: 200.0,
+                dy: 50.0,
+            },
+        ];
+        for event in events {
+            harness.compositor.handle_input(event);
+        }
+
+        let selector = harness.compositor.region_selector.as_ref().unwrap();
+        assert_eq!(
+            selector.selection().map(|rect| format_geometry(&rect)),
+            Some("100,100 201x51".to_string())
+        );
+        assert!(harness
+            .compositor
+            .render_bar_elements()
+            .iter()
+            .any(|element| matches!(element, BarElement::Text { text, .. } if text == "201×51")));
+
+        harness
+            .compositor
+            .handle_input(InputTraceEvent::PointerButton {
+                button: 272,
+                pressed: false,
+            });
+        assert!(harness.compositor.region_selector.is_none());
+        assert!(harness.compositor.render_region_select().is_empty());
+    }
+
+    #[test]
+    fn test_selection_follows_keyboard_focus() {
+        use smithay::utils::SERIAL_COUNTER;
+
+        let mut harness = TestCompositor::new();
+        let mut alpha = harness.connect("alpha");
+        let mut beta = harness.connect("beta");
+
+        let focus = |harness: &mut TestCompositor, index: usize| {
+            let window = harness
+                .compositor
+                .workspace_manager
+                .get_workspace_windows(1)[index]
+                .clone();
+            let surface = window.toplevel().unwrap().wl_surface().clone();
+            let keyboard = harness.compositor.seat.get_keyboard().unwrap();
+            keyboard.set_focus(
+                &mut harness.compositor,
+                Some(surface),
+                SERIAL_COUNTER.next_serial(),
+            );
+        };
+
+        // Copy in alpha; beta isn't focused, so it isn't told
+        focus(&mut harness, 0);
+        alpha.set_selection("text/plain");
+        harness.roundtrip(&mut alpha);
+        harness.roundtrip(&mut beta);
+        assert_eq!(beta.state.selection, None);
+
+        // Focusing beta offers it the selection
+        focus(&mut harness, 1);
+        harness.roundtrip(&mut beta);
+        assert_eq!(beta.state.selection, Some(vec!["text/plain".to_string()]));
+    }
+
+    #[test]
+    fn test_urgency_until_focused() {
+        let mut harness = TestCompositor::new();
+
+        let _alpha = harness.connect("alpha");
+        harness.compositor.workspace_manager.switch_to_workspace(2);
+        let _beta = harness.connect("beta");
+
+        let alpha = harness
+            .compositor
+            .workspace_manager
+            .get_workspace_windows(1)[0]
+            .clone();
+        harness.compositor.set_window_urgent(&alpha, true);
+        assert!(harness.compositor.urgent_workspaces().contains_key(&1));
+        assert!(!harness.compositor.urgent_workspaces().contains_key(&2));
+
+        let surface = alpha.toplevel().unwrap().wl_surface().clone();
+        harness.compositor.clear_urgency_for_surface(&surface);
+        assert!(harness.compositor.urgent_workspaces().is_empty());
+    }
+
+    #[test]
+    fn test_unresponsive_client() {
+        use crate::compositor::unresponsive::PING_TIMEOUT;
+
+        let mut harness = TestCompositor::new();
+        let mut alpha = harness.connect("alpha");
+        let window = harness
+            .compositor
+            .workspace_manager
+            .get_workspace_windows(1)[0]
+            .clone();
+
+        // The client doesn't read the ping before the timeout passes
+        let start = Instant::now();
+        harness.compositor.ping_clients(start);
+        harness.compositor.ping_clients(start + PING_TIMEOUT);
+        assert!(harness.compositor.is_window_unresponsive(&window));
+        assert!(harness.bar_texts().iter().any(|t| t == "Not responding"));
+
+        // Answering late clears the mark
+        harness.roundtrip(&mut alpha);
+        harness.wait_until(|c| !c.is_window_unresponsive(&window));
+
+        // Force close doesn't wait for the client
+        harness.compositor.force_close_focused();
+        harness.wait_until(|c| c.workspace_manager.get_workspace_windows(1).is_empty());
+    }
+
+    #[test]
+    fn test_client_resource_report() {
+        let mut harness = TestCompositor::new();
+        let alpha = harness.connect("alpha");
+
+        let report = harness.compositor.client_report();
+        let entry = report
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|client| client["app_ids"] == serde_json::json!(["alpha"]))
+            .expect("alpha missing from the report")
+            .clone();
+        assert_eq!(entry["surfaces"], 1);
+        assert!(entry["buffers"].as_u64().unwrap() >= 1);
+        assert!(entry["buffer_kib"].as_u64().unwrap() > 0);
+        assert_eq!(entry["leaking"], false);
+
+        // Everything is released with the connection
+        harness.disconnect(alpha);
+        harness.wait_until(|c| {
+            c.client_report()
+                .as_array()
+                .is_some_and(|clients| clients.is_empty())
+        });
+    }
+
+    #[test]
+    fn test_foreign_dialog_parenting() {
+        use crate::compositor::workspace::LayoutMode;
+
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .workspace_manager
+            .active_workspace_mut()
+            .layout_mode = LayoutMode::Floating;
+        let location = |compositor: &WebWMCompositor, app_id: &str| {
+            compositor
+                .space
+                .elements()
+                .find(|window| window_app_id(window) == app_id)
+                .and_then(|window| compositor.space.element_location(window))
+        };
+
+        let mut app = harness.connect("app");
+        let mut portal = harness.connect("portal");
+        assert_ne!(
+            location(&harness.compositor, "portal"),
+            location(&harness.compositor, "app")
+        );
+
+        app.export_toplevel().unwrap();
+        harness.roundtrip(&mut app);
+        let handle = app
+            .state
+            .exported_handle
+            .clone()
+            .expect("No handle exported");
+        portal.set_parent_handle(&handle).unwrap();
+        harness.roundtrip(&mut portal);
+
+        let portal_window = harness
+            .compositor
+            .space
+            .elements()
+            .find(|window| window_app_id(window) == "portal")
+            .cloned()
+            .unwrap();
+        assert_eq!(
+            window_app_id(&harness.compositor.window_parent(&portal_window).unwrap()),
+            "app"
+        );
+        // Same size as its parent, so centered right on top of it
+        harness.wait_until(|c| location(c, "portal") == location(c, "app"));
+    }
+
+    #[test]
+    fn test_dialogs_follow_parent() {
+        use crate::compositor::workspace::WindowKind;
+
+        let mut harness = TestCompositor::new();
+        let mut app = harness.connect("app");
+        harness.compositor.workspace_manager.switch_to_workspace(2);
+        let mut dialog = harness.connect("dialog");
+        let find = |compositor: &WebWMCompositor, app_id: &str| {
+            compositor
+                .space
+                .elements()
+                .find(|window| window_app_id(window) == app_id)
+                .cloned()
+                .unwrap()
+        };
+        let app_window = find(&harness.compositor, "app");
+        let dialog_window = find(&harness.compositor, "dialog");
+
+        app.export_toplevel().unwrap();
+        harness.roundtrip(&mut app);
+        let handle = app.state.exported_handle.clone().unwrap();
+        dialog.set_parent_handle(&handle).unwrap();
+        harness.roundtrip(&mut dialog);
+
+        // Pulled over to its parent's workspace
+        let manager = &harness.compositor.workspace_manager;
+        assert_eq!(manager.find_window_workspace(&dialog_window), Some(1));
+        let workspace = manager.get_workspace(1).unwrap();
+        assert_eq!(workspace.window_kind(&dialog_window), WindowKind::Dialog);
+        assert_eq!(
+            workspace.dialogs_of(&app_window),
+            vec![dialog_window.clone()]
+        );
+
+        // And moved along with it
+        harness
+            .compositor
+            .workspace_manager
+            .move_window_to_workspace(app_window.clone(), 3);
+        let manager = &harness.compositor.workspace_manager;
+        assert_eq!(manager.find_window_workspace(&dialog_window), Some(3));
+        assert_eq!(
+            manager
+                .get_workspace(3)
+                .unwrap()
+                .dialog_parent(&dialog_window),
+            Some(&app_window)
+        );
+    }
+
+    #[test]
+    fn test_modal_dialog() {
+        use smithay::utils::{Logical, Rectangle};
+
+        let mut harness = TestCompositor::new();
+        let mut app = harness.connect("app");
+        let mut other = harness.connect("other");
+        let mut dialog = harness.connect("dialog");
+
+        app.export_toplevel().unwrap();
+        harness.roundtrip(&mut app);
+        let handle = app.state.exported_handle.clone().unwrap();
+        dialog.set_parent_handle(&handle).unwrap();
+        dialog.set_modal().unwrap();
+        harness.roundtrip(&mut dialog);
+        harness.roundtrip(&mut dialog);
+        harness.roundtrip(&mut other);
+
+        let find = |compositor: &WebWMCompositor, app_id: &str| {
+            compositor
+                .space
+                .elements()
+                .find(|window| window_app_id(window) == app_id)
+                .cloned()
+                .unwrap()
+        };
+        let app_window = find(&harness.compositor, "app");
+        let dialog_window = find(&harness.compositor, "dialog");
+
+        // Not a tile of its own: centered over its parent even when tiling
+        assert_eq!(other.state.configured_size, tiled_size(&harness, 2));
+        let parent = harness
+            .compositor
+            .space
+            .element_geometry(&app_window)
+            .unwrap();
+        let geometry = harness
+            .compositor
+            .space
+            .element_geometry(&dialog_window)
+            .unwrap();
+        assert!(parent.contains_rect(geometry));
+        let center = |r: Rectangle<i32, Logical>| (r.loc.x + r.size.w / 2, r.loc.y + r.size.h / 2);
+        let (parent_center, dialog_center) = (center(parent), center(geometry));
+        assert!((parent_center.0 - dialog_center.0).abs() <= 1);
+        assert!((parent_center.1 - dialog_center.1).abs() <= 1);
+
+        // The parent is dimmed around the dialog, and clicks on it go to
+        // the dialog
+        assert_eq!(
+            harness.compositor.modal_dialog_of(&app_window),
+            Some(dialog_window.clone())
+        );
+        assert_eq!(harness.compositor.focus_target(&app_window), dialog_window);
+        let dims = harness.compositor.render_modal_dims();
+        assert!(!dims.is_empty());
+        for dim in dims {
+            let BarElement::Rectangle { geometry: dim, .. } = dim else {
+                panic!("Expected a rectangle");
+            };
+            assert!(parent.contains_rect(dim));
+            assert!(!dim.overlaps(geometry));
+        }
+
+        // Opacity scales the scrim color's alpha
+        let scrim =
+            config::parse_css(".modal-parent { background-color: #202020; opacity: 0.5 }").unwrap();
+        harness
+            .compositor
+            .stylesheet
+            .as_mut()
+            .unwrap()
+            .rules
+            .extend(scrim.rules);
+        let BarElement::Rectangle { color, .. } = harness.compositor.render_modal_dims()[0] else {
+            panic!("Expected a rectangle");
+        };
+        assert_eq!(color[3], 0.5);
+
+        dialog.close().unwrap();
+        harness.wait_until(|c| c.modal_dialog_of(&app_window).is_none());
+        assert!(harness.compositor.render_modal_dims().is_empty());
+    }
+
+    #[test]
+    fn test_popup_positioning() {
+        let mut harness = TestCompositor::new();
+        let mut app = harness.connect("app");
+        let gaps = harness.compositor.config.layout.gaps_outer as i32;
+        let (width, _) = tiled_size(&harness, 1);
+
+        // A menu opened at the window's right edge slides back on screen
+        app.open_popup((width - 10, 10, 1, 1), (300, 200));
+        harness.roundtrip(&mut app);
+        harness.roundtrip(&mut app);
+        assert_eq!(
+            app.state.popup_geometry,
+            Some((1920 - gaps - 300, 11, 300, 200))
+        );
+
+        let window = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let popups = harness.compositor.popup_geometries(&window);
+        assert_eq!(popups.len(), 1);
+        assert_eq!(popups[0].loc.x + popups[0].size.w, 1920);
+        assert!(!app.state.popup_done);
+    }
+
+    #[test]
+    fn test_popup_grab_focus() {
+        let mut harness = TestCompositor::new();
+        let mut app = harness.connect("app");
+        let window = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let surface = window.toplevel().unwrap().wl_surface().clone();
+        let keyboard = harness.compositor.seat.get_keyboard().unwrap();
+
+        // A grabbing menu takes the keyboard, Escape hands it back
+        app.open_popup((10, 10, 1, 1), (200, 100));
+        harness.roundtrip(&mut app);
+        app.grab_popup();
+        harness.roundtrip(&mut app);
+        assert_eq!(harness.compositor.popup_grab_root, Some(surface.clone()));
+        assert_ne!(keyboard.current_focus(), Some(surface.clone()));
+
+        assert!(harness.compositor.dismiss_innermost_popup());
+        harness.roundtrip(&mut app);
+        assert!(app.state.popup_done);
+        assert_eq!(harness.compositor.popup_grab_root, None);
+        assert_eq!(keyboard.current_focus(), Some(surface.clone()));
+        assert!(!harness.compositor.dismiss_innermost_popup());
+
+        // Clicking outside the menu dismisses it
+        app.open_popup((10, 10, 1, 1), (200, 100));
+        harness.roundtrip(&mut app);
+        app.grab_popup();
+        harness.roundtrip(&mut app);
+        let menu = harness.compositor.popup_geometries(&window)[0];
+        assert!(harness.compositor.over_grabbed_popup(menu.loc.to_f64()));
+        assert!(!harness
+            .compositor
+            .over_grabbed_popup((menu.loc.x as f64 - 5.0, menu.loc.y as f64).into()));
+        assert!(harness.compositor.dismiss_popups());
+        harness.roundtrip(&mut app);
+        assert!(app.state.popup_done);
+        assert_eq!(keyboard.current_focus(), Some(surface));
+    }
+
+    #[test]
+    fn test_output_zoom() {
+        let mut harness = TestCompositor::new();
+        let _alpha = harness.connect("alpha");
+        let beta = harness.connect("beta");
+        let window = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let geometry = harness.compositor.space.element_geometry(&window).unwrap();
+
+        // The focused (tall) window fills the output's height, centered
+        harness.compositor.toggle_output_zoom();
+        let zoom = harness.compositor.output_zoom().unwrap();
+        let zoomed = zoom.apply(geometry);
+        assert_eq!(zoomed.loc.y, 0);
+        assert_eq!(zoomed.size.h, 1080);
+        assert!((zoomed.loc.x - (1920 - zoomed.size.w) / 2).abs() <= 1);
+
+        harness.compositor.toggle_output_zoom();
+        assert!(harness.compositor.output_zoom().is_none());
+
+        // Closing the window ends the zoom
+        harness.compositor.toggle_output_zoom();
+        beta.close().unwrap();
+        harness.wait_until(|c| c.zoomed_window.is_none());
+    }
+
+    #[test]
+    fn test_resize_tiled() {
+        let mut harness = TestCompositor::new();
+        let _alpha = harness.connect("alpha");
+        let mut beta = harness.connect("beta");
+        harness.roundtrip(&mut beta);
+        let before = beta.state.configured_size.0;
+
+        // The focused tile on the right grows to the left, and stays that
+        // wide across relayouts
+        harness.compositor.resize_tiled("left", 0.1);
+        harness.roundtrip(&mut beta);
+        let after = beta.state.configured_size.0;
+        assert!((after - before - 192).abs() <= 2);
+        harness.compositor.relayout();
+        harness.roundtrip(&mut beta);
+        assert_eq!(beta.state.configured_size.0, after);
+
+        // Tiles side by side have no edge to move up or down
+        harness.compositor.resize_tiled("up", 0.1);
+        harness.roundtrip(&mut beta);
+        assert_eq!(beta.state.configured_size.0, after);
+    }
+
+    #[test]
+    fn test_swap_window() {
+        let mut harness = TestCompositor::new();
+        let _alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        let windows: Vec<_> = harness.compositor.space.elements().cloned().collect();
+        let focused = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let other = windows.iter().find(|w| **w != focused).unwrap().clone();
+        let right = harness.compositor.space.element_location(&focused).unwrap();
+        let left = harness.compositor.space.element_location(&other).unwrap();
+        assert!(left.x < right.x);
+
+        // The focused window (beta, on the right) trades places with alpha
+        harness.compositor.swap_window("left");
+        assert_eq!(
+            harness.compositor.space.element_location(&focused),
+            Some(left)
+        );
+        assert_eq!(
+            harness.compositor.space.element_location(&other),
+            Some(right)
+        );
+        assert_eq!(
+            harness.compositor.workspace_manager.focused_window(),
+            Some(&focused)
+        );
+
+        // There's nothing further left to swap with
+        harness.compositor.swap_window("left");
+        assert_eq!(
+            harness.compositor.space.element_location(&focused),
+            Some(left)
+        );
+    }
+
+    #[test]
+    fn test_directional_focus() {
+        let mut harness = TestCompositor::new();
+        let _alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        let focused = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let other = harness
+            .compositor
+            .space
+            .elements()
+            .find(|w| **w != focused)
+            .unwrap()
+            .clone();
+        assert!(!harness.compositor.focus_by_order());
+
+        // Beta is on the right: alpha is to its left, nothing else around
+        assert_eq!(
+            harness.compositor.window_in_direction("left"),
+            Some(other.clone())
+        );
+        assert_eq!(harness.compositor.window_in_direction("right"), None);
+        assert_eq!(harness.compositor.window_in_direction("up"), None);
+
+        harness
+            .compositor
+            .workspace_manager
+            .active_workspace_mut()
+            .focus_window(&other);
+        assert_eq!(
+            harness.compositor.window_in_direction("right"),
+            Some(focused)
+        );
+    }
+
+    #[test]
+    fn test_floating_drag() {
+        use crate::compositor::workspace::LayoutMode;
+
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .workspace_manager
+            .active_workspace_mut()
+            .layout_mode = LayoutMode::Floating;
+        let mut alpha = harness.connect("alpha");
+        harness.roundtrip(&mut alpha);
+        let window = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let before = harness.compositor.space.element_geometry(&window).unwrap();
+
+        // Super+left-drag moves the window, and it stays there
+        let center = before.loc.to_f64() + before.size.to_f64().downscale(2.0).to_point();
+        assert!(harness.compositor.begin_floating_grab(center, false));
+        harness
+            .compositor
+            .floating_grab_motion(center + (100.0, 50.0).into());
+        harness.compositor.end_floating_grab();
+        let moved = harness.compositor.space.element_location(&window).unwrap();
+        assert_eq!(moved, before.loc + (100, 50).into());
+        harness.compositor.relayout();
+        assert_eq!(
+            harness.compositor.space.element_location(&window),
+            Some(moved)
+        );
+
+        // Super+right-drag near the bottom-right corner resizes from there
+        let corner = (moved + before.size.to_point()).to_f64() - (10.0, 10.0).into();
+        assert!(harness.compositor.begin_floating_grab(corner, true));
+        harness
+            .compositor
+            .floating_grab_motion(corner + (40.0, 30.0).into());
+        harness.compositor.end_floating_grab();
+        harness.roundtrip(&mut alpha);
+        assert_eq!(
+            alpha.state.configured_size,
+            (before.size.w + 40, before.size.h + 30)
+        );
+        assert_eq!(
+            harness.compositor.space.element_location(&window),
+            Some(moved)
+        );
+    }
+
+    #[test]
+    fn test_floating_stays_visible() {
+        use crate::compositor::workspace::LayoutMode;
+
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .workspace_manager
+            .active_workspace_mut()
+            .layout_mode = LayoutMode::Floating;
+        let mut alpha = harness.connect("alpha");
+        harness.roundtrip(&mut alpha);
+        let window = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let before = harness.compositor.space.element_geometry(&window).unwrap();
+
+        // Dragged far off to the left, a strip of the window stays
+        let center = before.loc.to_f64() + before.size.to_f64().downscale(2.0).to_point();
+        assert!(harness.compositor.begin_floating_grab(center, false));
+        harness
+            .compositor
+            .floating_grab_motion(center - (5000.0, 0.0).into());
+        harness.compositor.end_floating_grab();
+        let moved = harness.compositor.space.element_location(&window).unwrap();
+        assert_eq!(moved.x, 48 - before.size.w);
+
+        // Centering brings it back to the middle
+        harness.compositor.center_window();
+        let bar_height = harness.compositor.bar_height();
+        assert_eq!(
+            harness.compositor.space.element_location(&window),
+            Some(
+                (
+                    (1920 - before.size.w) / 2,
+                    bar_height + (1080 - bar_height - before.size.h) / 2
+                )
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_floating_placement() {
+        use crate::compositor::workspace::LayoutMode;
+
+        let mut harness = TestCompositor::new();
+        if let Some(desktop) = harness.compositor.config.desktop.as_mut() {
+            desktop.layout.floating_default_width = 640;
+            desktop.layout.floating_default_height = 480;
+            desktop.layout.center_new_windows = false;
+        }
+        harness
+            .compositor
+            .workspace_manager
+            .active_workspace_mut()
+            .layout_mode = LayoutMode::Floating;
+        let bar_height = harness.compositor.bar_height();
+
+        let alpha = harness.connect("alpha");
+        let mut beta = harness.connect("beta");
+        let geometries = |harness: &TestCompositor| {
+            harness
+                .compositor
+                .workspace_manager
+                .active_workspace()
+                .windows
+                .iter()
+                .map(|w| harness.compositor.space.element_geometry(w).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        // Cascaded from the top left with the configured size
+        let placed = geometries(&harness);
+        assert_eq!(placed[0].loc, (0, bar_height).into());
+        assert_eq!(placed[1].loc, (30, bar_height + 30).into());
+        harness.roundtrip(&mut beta);
+        assert_eq!(beta.state.configured_size, (640, 480));
+
+        // Closing the first window leaves the second where it was
+        harness.disconnect(alpha);
+        harness.wait_until(|compositor| compositor.workspace_manager.active_workspace().len() == 1);
+        harness.compositor.relayout();
+        assert_eq!(geometries(&harness), vec![placed[1]]);
+
+        // Centered when configured
+        if let Some(desktop) = harness.compositor.config.desktop.as_mut() {
+            desktop.layout.center_new_windows = true;
+        }
+        let _gamma = harness.connect("gamma");
+        let usable_height = 1080 - bar_height;
+        assert_eq!(
+            geometries(&harness)[1].loc,
+            ((1920 - 640) / 2, bar_height + (usable_height - 480) / 2).into()
+        );
+    }
+
+    #[test]
+    fn test_window_hints() {
+        use smithay::input::keyboard::keysyms;
+
+        let mut harness = TestCompositor::new();
+        let _alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        assert_eq!(harness.focused_app_id().as_deref(), Some("beta"));
+
+        // Labels go left to right; typing one focuses its window
+        harness.compositor.open_window_hints();
+        let hints = harness.compositor.window_hints.clone().unwrap();
+        let labels: Vec<(String, String)> = hints
+            .hints
+            .iter()
+            .map(|(label, window)| (label.clone(), window_app_id(window)))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("a".to_string(), "alpha".to_string()),
+                ("s".to_string(), "beta".to_string())
+            ]
+        );
+        assert!(harness.compositor.overlay_key(keysyms::KEY_a));
+        assert!(harness.compositor.window_hints.is_none());
+        assert_eq!(harness.focused_app_id().as_deref(), Some("alpha"));
+
+        // A letter no label starts with gives up
+        harness.compositor.open_window_hints();
+        assert!(harness.compositor.overlay_key(keysyms::KEY_l));
+        assert!(harness.compositor.window_hints.is_none());
+        assert_eq!(harness.focused_app_id().as_deref(), Some("alpha"));
+        assert!(!harness.compositor.overlay_key(keysyms::KEY_s));
+    }
+
+    #[test]
+    fn test_window_menu() {
+        use smithay::input::keyboard::keysyms;
+
+        let mut harness = TestCompositor::new();
+        let _alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        let beta = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let first = harness.compositor.workspace_manager.active_workspace_id();
+        let second = harness.compositor.workspace_manager.all_workspaces()[1].id;
+
+        // Always on top is the fourth item
+        harness.compositor.open_focused_window_menu();
+        assert!(harness.compositor.window_menu.is_some());
+        for _ in 0..4 {
+            assert!(harness.compositor.window_menu_key(keysyms::KEY_Down));
+        }
+        harness.compositor.window_menu_key(keysyms::KEY_Return);
+        assert!(harness.compositor.window_menu.is_none());
+        assert!(harness
+            .compositor
+            .workspace_manager
+            .active_workspace()
+            .is_on_top(&beta));
+        assert_eq!(
+            harness.compositor.stacked_elements().last().copied(),
+            Some(&beta)
+        );
+
+        // Clicking elsewhere closes the menu and lets the click through
+        harness.compositor.open_focused_window_menu();
+        assert!(!harness.compositor.window_menu_click((5.0, 1070.0).into()));
+        assert!(harness.compositor.window_menu.is_none());
+        assert!(!harness.compositor.window_menu_key(keysyms::KEY_Escape));
+
+        // The submenu moves the window to the second workspace, where it
+        // stays on top
+        harness.compositor.open_focused_window_menu();
+        for key in [keysyms::KEY_Down, keysyms::KEY_Right, keysyms::KEY_Down] {
+            harness.compositor.window_menu_key(key);
+        }
+        harness.compositor.window_menu_key(keysyms::KEY_Return);
+        let workspace_manager = &harness.compositor.workspace_manager;
+        assert_eq!(workspace_manager.find_window_workspace(&beta), Some(second));
+        assert_ne!(first, second);
+        assert!(workspace_manager
+            .get_workspace(second)
+            .is_some_and(|ws| ws.is_on_top(&beta)));
+    }
+
+    #[test]
+    fn test_floating_stack() {
+        use crate::compositor::workspace::LayoutMode;
+
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .workspace_manager
+            .active_workspace_mut()
+            .layout_mode = LayoutMode::Floating;
+        let _alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        let beta = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let alpha = harness
+            .compositor
+            .workspace_manager
+            .active_workspace()
+            .windows
+            .iter()
+            .find(|w| **w != beta)
+            .unwrap()
+            .clone();
+        let top = |harness: &TestCompositor| {
+            harness
+                .compositor
+                .stacked_elements()
+                .last()
+                .map(|w| (*w).clone())
+        };
+        // The windows cascade, so they overlap here
+        let overlap = harness
+            .compositor
+            .space
+            .element_geometry(&beta)
+            .unwrap()
+            .loc
+            .to_f64()
+            + (10.0, 10.0).into();
+
+        // New windows open on top
+        assert_eq!(top(&harness), Some(beta.clone()));
+
+        // A raised window is drawn and clicked on top
+        harness.compositor.raise_window(&alpha);
+        assert_eq!(top(&harness), Some(alpha.clone()));
+        assert_eq!(
+            harness
+                .compositor
+                .space
+                .element_under(overlap)
+                .map(|(w, _)| w.clone()),
+            Some(alpha.clone())
+        );
+        harness.compositor.relayout();
+        assert_eq!(top(&harness), Some(alpha.clone()));
+
+        // Raise-or-lower raises the focused window, then lowers it
+        harness.compositor.raise_or_lower_focused();
+        assert_eq!(top(&harness), Some(beta.clone()));
+        harness.compositor.raise_or_lower_focused();
+        assert_eq!(top(&harness), Some(alpha));
+    }
+
+    #[test]
+    fn test_floating_windows() {
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .config
+            .window_rules
+            .push(config::WindowRule {
+                app_id: "gamma".to_string(),
+                floating: Some(true),
+                ..Default::default()
+            });
+        let mut alpha = harness.connect("alpha");
+        let mut beta = harness.connect("beta");
+        let beta_window = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let top = |harness: &TestCompositor| {
+            harness
+                .compositor
+                .stacked_elements()
+                .last()
+                .map(|w| (*w).clone())
+        };
+
+        // Floating beta leaves the whole tiled area to alpha, with beta
+        // above it
+        harness.compositor.toggle_focused_floating();
+        harness.roundtrip(&mut alpha);
+        harness.roundtrip(&mut beta);
+        assert_eq!(alpha.state.configured_size, tiled_size(&harness, 1));
+        let workspace = harness.compositor.workspace_manager.active_workspace();
+        assert!(workspace.is_floating(&beta_window));
+        assert_eq!(workspace.tiles().len(), 1);
+        assert_eq!(top(&harness), Some(beta_window.clone()));
+        let floating_size = beta.state.configured_size;
+        assert_ne!(floating_size, tiled_size(&harness, 2));
+
+        // A Super+drag moves it, and it stays there
+        let geometry = harness
+            .compositor
+            .space
+            .element_geometry(&beta_window)
+            .unwrap();
+        let center = geometry.loc.to_f64() + (20.0, 20.0).into();
+        assert!(harness.compositor.begin_floating_grab(center, false));
+        harness
+            .compositor
+            .floating_grab_motion(center + (30.0, 40.0).into());
+        harness.compositor.end_floating_grab();
+        assert_eq!(
+            harness.compositor.space.element_location(&beta_window),
+            Some(geometry.loc + (30, 40).into())
+        );
+
+        // Toggled back, it's a tile again
+        harness.compositor.toggle_focused_floating();
+        harness.roundtrip(&mut alpha);
+        harness.roundtrip(&mut beta);
+        assert_eq!(alpha.state.configured_size, tiled_size(&harness, 2));
+        assert_eq!(beta.state.configured_size, tiled_size(&harness, 2));
+
+        // Rules float windows as they open
+        let mut gamma = harness.connect("gamma");
+        harness.roundtrip(&mut gamma);
+        harness.roundtrip(&mut alpha);
+        assert_eq!(alpha.state.configured_size, tiled_size(&harness, 2));
+        assert_eq!(gamma.state.configured_size, floating_size);
+        assert_eq!(
+            harness
+                .compositor
+                .workspace_manager
+                .active_workspace()
+                .floating_windows
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_wireframe_drag() {
+        use crate::compositor::workspace::LayoutMode;
+
+        let mut harness = TestCompositor::new();
+        if let Some(desktop) = harness.compositor.config.desktop.as_mut() {
+            desktop.layout.drag_mode = "wireframe".to_string();
+        }
+        harness
+            .compositor
+            .workspace_manager
+            .active_workspace_mut()
+            .layout_mode = LayoutMode::Floating;
+        let mut alpha = harness.connect("alpha");
+        harness.roundtrip(&mut alpha);
+        let window = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let before = harness.compositor.space.element_geometry(&window).unwrap();
+
+        // During the drag only the outline follows the pointer
+        let corner = (before.loc + before.size.to_point()).to_f64() - (10.0, 10.0).into();
+        assert!(harness.compositor.begin_floating_grab(corner, true));
+        harness
+            .compositor
+            .floating_grab_motion(corner + (40.0, 30.0).into());
+        harness.roundtrip(&mut alpha);
+        assert_eq!(alpha.state.configured_size, (before.size.w, before.size.h));
+        assert!(!harness.compositor.render_drag_outline().is_empty());
+
+        // Letting go sends the window its one new size
+        harness.compositor.end_floating_grab();
+        harness.roundtrip(&mut alpha);
+        assert_eq!(
+            alpha.state.configured_size,
+            (before.size.w + 40, before.size.h + 30)
+        );
+        assert!(harness.compositor.render_drag_outline().is_empty());
+    }
+
+    #[test]
+    fn test_metrics() {
+        let mut harness = TestCompositor::new();
+        let _alpha = harness.connect("alpha");
+        harness.compositor.workspace_manager.switch_to_workspace(2);
+        harness.compositor.workspace_manager.switch_to_workspace(2);
+        harness.compositor.render_bar_elements();
+
+        let metrics = harness.compositor.prometheus_metrics();
+        assert!(metrics.contains("webwm_clients 1\n"));
+        assert!(metrics.contains("webwm_windows 1\n"));
+        assert!(metrics.contains("webwm_workspace_switches_total 1\n"));
+        assert!(metrics.contains("webwm_bar_widget_updates_total{widget=\"clock\"} 1\n"));
+        assert!(metrics.contains("# TYPE webwm_output_dropped_frames_total counter\n"));
+    }
+
+    #[test]
+    fn test_live_config() {
+        use crate::ipc::{dispatch, IpcRequest};
+
+        let mut harness = TestCompositor::new();
+        let request = |command: &str, args: &[&str]| IpcRequest {
+            command: command.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        };
+
+        // Theme variables restyle every rule using them
+        let response = dispatch(
+            &mut harness.compositor,
+            &request("theme-set", &["--border-focus", "#ff0000"]),
+        );
+        assert!(response.success);
+        let stylesheet = harness.compositor.stylesheet.as_ref().unwrap();
+        let color = stylesheet
+            .get_color("window:focus", "border-color")
+            .unwrap();
+        assert_eq!(color.to_hex(), "#ff0000");
+        assert!(
+            !dispatch(
+                &mut harness.compositor,
+                &request("theme-set", &["--no-such-variable", "#ff0000"])
+            )
+            .success
+        );
+
+        // Keybindings can be replaced, added and removed
+        let action = r#"{"type": "Spawn", "command": "foot"}"#;
+        assert!(
+            dispatch(
+                &mut harness.compositor,
+                &request("keybind-set", &["Super+Return", action])
+            )
+            .success
+        );
+        assert!(
+            dispatch(
+                &mut harness.compositor,
+                &request("keybind-set", &["Super+Shift+y", action])
+            )
+            .success
+        );
+        let list = dispatch(&mut harness.compositor, &request("keybind-list", &[])).data;
+        let bound = |combo: &str| {
+            list["keybindings"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|binding| binding["combo"] == combo)
+                .map(|binding| binding["action"]["command"].clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(bound("Super+Return"), vec![serde_json::json!("foot")]);
+        assert_eq!(bound("Super+Shift+y"), vec![serde_json::json!("foot")]);
+
+        assert!(
+            dispatch(
+                &mut harness.compositor,
+                &request("keybind-remove", &["shift+super+y"])
+            )
+            .success
+        );
+        assert!(
+            !dispatch(
+                &mut harness.compositor,
+                &request("keybind-remove", &["Super+Shift+y"])
+            )
+            .success
+        );
+    }
+
+    #[test]
+    fn test_web_config_pipeline() {
+        let config_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/config");
+        let (config, js_runtime) = config::load_config(config_dir).unwrap();
+        assert!(js_runtime.is_some());
+        assert!(config.desktop.is_some() && config.stylesheet.is_some());
+        assert!(!config.keybindings.is_empty());
+        assert_eq!(config.layout.default_mode, "tiling");
+        // From --border-width in style.css
+        assert_eq!(config.layout.border_width, 2);
+
+        // config.js rules follow desktop.xml's
+        let first_js_rule = config
+            .window_rules
+            .iter()
+            .position(|rule| rule.app_id == "pavucontrol")
+            .unwrap();
+        assert_eq!(config.window_rules[first_js_rule].floating, Some(true));
+        let last_xml_rule = config
+            .window_rules
+            .iter()
+            .rposition(|rule| rule.app_id == "spotify")
+            .unwrap();
+        assert!(last_xml_rule < first_js_rule);
+    }
+
+    #[test]
+    fn test_config_reload() {
+        let dir = std::env::temp_dir().join(format!("webwm-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let shipped = concat!(env!("CARGO_MANIFEST_DIR"), "/config");
+        for file in ["desktop.xml", "style.css", "config.js"] {
+            std::fs::copy(format!("{}/{}", shipped, file), dir.join(file)).unwrap();
+        }
+        let (config, js_runtime) = config::load_config(&dir.to_string_lossy()).unwrap();
+        let mut harness = TestCompositor::with_config(config, js_runtime);
+        harness.compositor.config_dir = Some(dir.clone());
+        let keybindings = harness.compositor.config.keybindings.len();
+
+        // Workspaces take new names and gaps, keybindings come from the
+        // new config.js
+        let xml = std::fs::read_to_string(dir.join("desktop.xml")).unwrap();
+        std::fs::write(
+            dir.join("desktop.xml"),
+            xml.replace(r#"name="web""#, r#"name="browser""#),
+        )
+        .unwrap();
+        let js = std::fs::read_to_string(dir.join("config.js")).unwrap();
+        std::fs::write(
+            dir.join("config.js"),
+            format!("{}\nkeybind('Super+F12', () => wm.reload());\n", js),
+        )
+        .unwrap();
+        harness.compositor.reload_config().unwrap();
+        let workspace = harness.compositor.workspace_manager.get_workspace(2);
+        assert_eq!(workspace.unwrap().name, "browser");
+        assert_eq!(harness.compositor.config.keybindings.len(), keybindings + 1);
+
+        // A broken file keeps the running configuration
+        std::fs::write(dir.join("desktop.xml"), "<desktop>").unwrap();
+        assert!(harness.compositor.reload_config().is_err());
+        assert_eq!(harness.compositor.config.keybindings.len(), keybindings + 1);
+
+        // and says why, until it's clicked away
+        let error = harness.compositor.reload_error.clone().unwrap();
+        assert_eq!(error.file, dir.join("desktop.xml"));
+        assert!(error.line.is_some());
+        let texts = harness.bar_texts();
+        assert!(texts
+            .iter()
+            .any(|text| text.starts_with("Config reload failed")));
+        assert!(texts.iter().any(|text| text.contains("desktop.xml:")));
+        let corner = (1920.0 - 20.0, harness.compositor.bar_height() as f64 + 20.0);
+        assert!(harness.compositor.overlay_click(corner.into()));
+        assert!(harness.compositor.reload_error.is_none());
+
+        // A good reload clears it
+        std::fs::write(
+            dir.join("desktop.xml"),
+            xml.replace("<desktop>", "<desktop"),
+        )
+        .unwrap();
+        assert!(harness.compositor.reload_config().is_err());
+        assert!(harness.compositor.reload_error.is_some());
+        std::fs::write(dir.join("desktop.xml"), &xml).unwrap();
+        harness.compositor.reload_config().unwrap();
+        assert!(harness.compositor.reload_error.is_none());
+
+        // wm.reload() from a handler schedules a reload
+        harness
+            .compositor
+            .js_runtime
+            .as_ref()
+            .unwrap()
+            .evaluate("on('test-reload', () => wm.reload());")
+            .unwrap();
+        harness
+            .compositor
+            .emit_event("test-reload", serde_json::json!({}));
+        assert!(harness.compositor.config_reload_pending);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_safe_mode() {
+        let dir = std::env::temp_dir().join(format!("webwm-safe-mode-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let shipped = concat!(env!("CARGO_MANIFEST_DIR"), "/config");
+        for file in ["desktop.xml", "style.css"] {
+            std::fs::copy(format!("{}/{}", shipped, file), dir.join(file)).unwrap();
+        }
+        std::fs::write(
+            dir.join("config.js"),
+            "keybind('Super+a', () => {});\n\nundefinedThing();\n",
+        )
+        .unwrap();
+
+        // The error names the file and line
+        let error = config::load_config(&dir.to_string_lossy()).unwrap_err();
+        assert_eq!(error.file, dir.join("config.js"));
+        assert_eq!(error.line, Some(3));
+        assert!(error.message.contains("undefinedThing"));
+
+        let mut harness = TestCompositor::with_config(config::safe_mode_config(), None);
+        harness.compositor.config_dir = Some(dir.clone());
+        harness.compositor.safe_mode = Some(error);
+
+        // A banner says so, and reloading is a key away
+        let texts = harness.bar_texts();
+        assert!(texts.iter().any(|text| text.starts_with("Safe mode")));
+        assert!(texts.iter().any(|text| text.contains("config.js:3:")));
+        assert_eq!(
+            harness.compositor.bar_height(),
+            crate::compositor::safe_mode::BANNER_HEIGHT
+        );
+        assert!(harness
+            .compositor
+            .config
+            .keybindings
+            .iter()
+            .any(|kb| matches!(kb.action, config::Action::Reload)));
+
+        // Still broken: the banner shows the new error
+        std::fs::write(
+            dir.join("config.js"),
+            "keybind('Super+a', () => {});\nnope();\n",
+        )
+        .unwrap();
+        assert!(harness.compositor.reload_config().is_err());
+        assert_eq!(harness.compositor.safe_mode.as_ref().unwrap().line, Some(2));
+        assert!(harness.compositor.reload_error.is_none());
+
+        // Fixed: back to the real configuration
+        std::fs::write(dir.join("config.js"), "keybind('Super+a', () => {});\n").unwrap();
+        harness.compositor.reload_config().unwrap();
+        assert!(harness.compositor.safe_mode.is_none());
+        assert!(harness.compositor.js_runtime.is_some());
+        assert!(!harness
+            .bar_texts()
+            .iter()
+            .any(|text| text.starts_with("Safe mode")));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_binding_modes() {
+        use crate::compositor::input_trace::InputTraceEvent;
+        use smithay::input::keyboard::keysyms;
+
+        let mut harness = TestCompositor::new();
+        let press = |harness: &mut TestCompositor, keysym: u32| {
+            harness.compositor.handle_input(InputTraceEvent::Key {
+                keycode: keysym,
+                pressed: true,
+            });
+        };
+        let gaps_inner = |harness: &TestCompositor| {
+            let workspace = harness.compositor.workspace_manager.active_workspace();
+            workspace
+                .gaps_inner
+                .unwrap_or(harness.compositor.config.layout.gaps_inner)
+        };
+
+        // config.js declares a resize mode; give it a key to check with
+        let modes = &mut harness.compositor.config.modes;
+        modes.get_mut("resize").unwrap().push(config::Keybinding {
+            key: "g".to_string(),
+            modifiers: vec![],
+            sequence: vec![],
+            action: config::Action::AdjustGaps { inner: 5, outer: 0 },
+        });
+        harness
+            .compositor
+            .set_keybinding(
+                "F1",
+                config::Action::EnterMode {
+                    name: "resize".to_string(),
+                },
+            )
+            .unwrap();
+
+        press(&mut harness, keysyms::KEY_F1);
+        assert_eq!(
+            harness.compositor.input_handler.binding_mode(),
+            Some("resize")
+        );
+        assert!(harness.bar_texts().contains(&"resize".to_string()));
+
+        // The mode's keys do its thing, and stay in the mode
+        let gaps = gaps_inner(&harness);
+        press(&mut harness, keysyms::KEY_g);
+        press(&mut harness, keysyms::KEY_g);
+        assert_eq!(gaps_inner(&harness), gaps + 10);
+        assert_eq!(
+            harness.compositor.input_handler.binding_mode(),
+            Some("resize")
+        );
+
+        // The default bindings are away, Escape brings them back
+        press(&mut harness, keysyms::KEY_F1);
+        press(&mut harness, keysyms::KEY_Escape);
+        assert_eq!(harness.compositor.input_handler.binding_mode(), None);
+        assert!(!harness.bar_texts().contains(&"resize".to_string()));
+
+        // config.js callbacks enter and leave modes too
+        press(&mut harness, keysyms::KEY_F1);
+        press(&mut harness, keysyms::KEY_Return);
+        assert_eq!(harness.compositor.input_handler.binding_mode(), None);
+
+        // Unknown modes aren't entered
+        harness
+            .compositor
+            .set_keybinding(
+                "F2",
+                config::Action::EnterMode {
+                    name: "launch".to_string(),
+                },
+            )
+            .unwrap();
+        press(&mut harness, keysyms::KEY_F2);
+        assert_eq!(harness.compositor.input_handler.binding_mode(), None);
+    }
+
+    #[test]
+    fn test_key_chords() {
+        use crate::compositor::input_trace::InputTraceEvent;
+        use smithay::input::keyboard::keysyms;
+
+        let mut harness = TestCompositor::new();
+        let press = |harness: &mut TestCompositor, keysym: u32| {
+            harness.compositor.handle_input(InputTraceEvent::Key {
+                keycode: keysym,
+                pressed: true,
+            });
+        };
+        let gaps_inner = |harness: &TestCompositor| {
+            let workspace = harness.compositor.workspace_manager.active_workspace();
+            workspace
+                .gaps_inner
+                .unwrap_or(harness.compositor.config.layout.gaps_inner)
+        };
+        let pending = |harness: &TestCompositor| harness.compositor.input_handler.chord.is_some();
+
+        let compositor = &mut harness.compositor;
+        compositor
+            .set_keybinding("F1, g", config::Action::AdjustGaps { inner: 5, outer: 0 })
+            .unwrap();
+        compositor
+            .set_keybinding(
+                "F1, x, g",
+                config::Action::AdjustGaps {
+                    inner: -5,
+                    outer: 0,
+                },
+            )
+            .unwrap();
+        compositor
+            .set_keybinding("F1", config::Action::AdjustGaps { inner: 1, outer: 0 })
+            .unwrap();
+
+        // The first key waits for the rest, listing them
+        let gaps = gaps_inner(&harness);
+        press(&mut harness, keysyms::KEY_F1);
+        assert!(pending(&harness));
+        assert_eq!(gaps_inner(&harness), gaps);
+        let texts = harness.bar_texts();
+        assert!(texts.contains(&"F1, …".to_string()));
+        assert!(texts.contains(&"g".to_string()));
+        assert!(texts.contains(&"x, g".to_string()));
+
+        press(&mut harness, keysyms::KEY_g);
+        assert!(!pending(&harness));
+        assert_eq!(gaps_inner(&harness), gaps + 5);
+
+        press(&mut harness, keysyms::KEY_F1);
+        press(&mut harness, keysyms::KEY_x);
+        assert!(pending(&harness));
+        press(&mut harness, keysyms::KEY_g);
+        assert_eq!(gaps_inner(&harness), gaps);
+
+        // A key that continues nothing drops the chord, doing nothing
+        press(&mut harness, keysyms::KEY_F1);
+        press(&mut harness, keysyms::KEY_q);
+        assert!(!pending(&harness));
+        assert_eq!(gaps_inner(&harness), gaps);
+
+        // Timing out runs the binding of the keys so far
+        press(&mut harness, keysyms::KEY_F1);
+        let mut handler = std::mem::take(&mut harness.compositor.input_handler);
+        handler.chord_timed_out(&mut harness.compositor);
+        harness.compositor.input_handler = handler;
+        assert!(!pending(&harness));
+        assert_eq!(gaps_inner(&harness), gaps + 1);
+        assert!(!harness.bar_texts().contains(&"F1, …".to_string()));
+    }
+
+    #[test]
+    fn test_virtual_outputs() {
+        use crate::compositor::ui::rect;
+        use crate::compositor::virtual_outputs::VirtualOutputSpec;
+
+        let mut harness = TestCompositor::new();
+        let compositor = &mut harness.compositor;
+        let hidpi =
+            compositor.add_virtual_output(&VirtualOutputSpec::parse("2560x1440@2").unwrap());
+        let below =
+            compositor.add_virtual_output(&VirtualOutputSpec::parse("1280x1024+0+1080").unwrap());
+        let next = compositor.add_virtual_output(&VirtualOutputSpec::parse("800x600").unwrap());
+
+        // Each goes right of the ones before it unless placed, in logical
+        // coordinates
+        let geometry = |output| compositor.space.output_geometry(output).unwrap();
+        assert_eq!(hidpi.name(), "VIRTUAL-1");
+        assert_eq!(geometry(&hidpi), rect(1920, 0, 1280, 720));
+        assert_eq!(below.name(), "VIRTUAL-2");
+        assert_eq!(geometry(&below), rect(0, 1080, 1280, 1024));
+        assert_eq!(next.name(), "VIRTUAL-3");
+        assert_eq!(geometry(&next), rect(3200, 0, 800, 600));
+
+        // Multi-monitor logic sees them like real outputs
+        if let Some(desktop) = compositor.config.desktop.as_mut() {
+            desktop.pointer.warp_to_output = false;
+        }
+        compositor.focus_output("right");
+        assert_eq!(
+            compositor.focused_output_name().as_deref(),
+            Some("VIRTUAL-1")
+        );
+        compositor.focus_output("left");
+        compositor.focus_output("down");
+        assert_eq!(
+            compositor.focused_output_name().as_deref(),
+            Some("VIRTUAL-2")
+        );
+    }
+
+    #[test]
+    fn test_config_includes() {
+        let dir = std::env::temp_dir().join(format!("webwm-includes-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("keys")).unwrap();
+        let shipped = concat!(env!("CARGO_MANIFEST_DIR"), "/config");
+        std::fs::copy(format!("{}/style.css", shipped), dir.join("style.css")).unwrap();
+        std::fs::write(
+            dir.join("desktop.xml"),
+            "<desktop>\n  <include path=\"bars.xml\" />\n  <workspaces>\n    <workspace id=\"1\" name=\"main\" />\n  </workspaces>\n</desktop>\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("bars.xml"),
+            "<desktop>\n  <bar id=\"top\" position=\"top\" height=\"30\" />\n</desktop>\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("config.js"),
+            "const MOD = 'Super';\n@import \"keys/launchers.js\";\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("keys/launchers.js"),
+            "keybind(`${MOD}+Return`, () => wm.spawn('alacritty'));\n",
+        )
+        .unwrap();
+
+        let (config, _) = config::load_config(&dir.to_string_lossy()).unwrap();
+        let desktop = config.desktop.unwrap();
+        assert_eq!(desktop.bars.len(), 1);
+        assert_eq!(desktop.workspaces.len(), 1);
+        assert_eq!(config.keybindings.len(), 1);
+        assert_eq!(config.keybindings[0].key, "Return");
+
+        // Errors are in the imported file, on its own line numbers
+        std::fs::write(
+            dir.join("keys/launchers.js"),
+            "keybind('Super+Return', () => {});\nundefinedThing();\n",
+        )
+        .unwrap();
+        let error = config::load_config(&dir.to_string_lossy()).unwrap_err();
+        assert_eq!(error.file, dir.join("keys/launchers.js"));
+        assert_eq!(error.line, Some(2));
+
+        std::fs::write(
+            dir.join("bars.xml"),
+            "<desktop><include path=\"desktop.xml\" /></desktop>",
+        )
+        .unwrap();
+        let error = config::load_config(&dir.to_string_lossy()).unwrap_err();
+        assert_eq!(error.file, dir.join("bars.xml"));
+        assert!(error.message.starts_with("Include cycle"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_keybind_callbacks() {
+        use crate::compositor::input_trace::InputTraceEvent;
+        use smithay::input::keyboard::keysyms;
+        // Modifier state comes from xkb, which goes by real keycodes: 133
+        // is the left Super key
+        const SUPER_KEYCODE: u32 = 133;
+
+        let dir = std::env::temp_dir().join(format!("webwm-keybinds-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let shipped = concat!(env!("CARGO_MANIFEST_DIR"), "/config");
+        for file in ["desktop.xml", "style.css"] {
+            std::fs::copy(format!("{}/{}", shipped, file), dir.join(file)).unwrap();
+        }
+        // Combos the shipped config.js uses for other things
+        std::fs::write(
+            dir.join("config.js"),
+            "keybind('Super+b', () => wm.adjustGaps(7, 0));\nkeybind('Super+minus', () => wm.adjustGaps(0, 3));\n",
+        )
+        .unwrap();
+        let (config, js_runtime) = config::load_config(&dir.to_string_lossy()).unwrap();
+        let mut harness = TestCompositor::with_config(config, js_runtime);
+        let press = |harness: &mut TestCompositor, keycode: u32| {
+            harness.compositor.handle_input(InputTraceEvent::Key {
+                keycode,
+                pressed: true,
+            });
+        };
+        let gaps = |harness: &TestCompositor| {
+            let compositor = &harness.compositor;
+            let workspace = compositor.workspace_manager.active_workspace();
+            (
+                workspace
+                    .gaps_inner
+                    .unwrap_or(compositor.config.layout.gaps_inner),
+                workspace
+                    .gaps_outer
+                    .unwrap_or(compositor.config.layout.gaps_outer),
+            )
+        };
+
+        let (inner, outer) = gaps(&harness);
+        press(&mut harness, SUPER_KEYCODE);
+        press(&mut harness, keysyms::KEY_b);
+        assert_eq!(gaps(&harness), (inner + 7, outer));
+        press(&mut harness, keysyms::KEY_minus);
+        assert_eq!(gaps(&harness), (inner + 7, outer + 3));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_toggle_bar_key() {
+        use crate::compositor::input_trace::InputTraceEvent;
+        use crate::compositor::virtual_outputs::VirtualOutputSpec;
+        use smithay::input::keyboard::keysyms;
+
+        let mut harness = TestCompositor::new();
+        let compositor = &mut harness.compositor;
+        compositor.add_virtual_output(&VirtualOutputSpec::parse("1280x1024").unwrap());
+        compositor
+            .set_keybinding("F6", config::Action::ToggleBar { output: None })
+            .unwrap();
+
+        // The key toggles the bar of the output under the pointer, which
+        // actions see while the key is being handled
+        compositor.pointer_location = (2500.0, 300.0).into();
+        compositor.handle_input(InputTraceEvent::Key {
+            keycode: keysyms::KEY_F6,
+            pressed: true,
+        });
+        assert!(!compositor.bar_visible_on(Some("VIRTUAL-1")));
+        assert!(compositor.bar_visible_on(Some("HEADLESS-1")));
+    }
+
+    #[test]
+    fn test_focus_output_key() {
+        use crate::compositor::input_trace::InputTraceEvent;
+        use crate::compositor::virtual_outputs::VirtualOutputSpec;
+        use smithay::input::keyboard::keysyms;
+
+        let mut harness = TestCompositor::new();
+        let compositor = &mut harness.compositor;
+        compositor.add_virtual_output(&VirtualOutputSpec::parse("1280x1024").unwrap());
+        compositor
+            .set_keybinding(
+                "F7",
+                config::Action::FocusOutput {
+                    direction: "left".into(),
+                },
+            )
+            .unwrap();
+
+        // Nothing picked from the keyboard yet, so the key goes left of the
+        // output under the pointer
+        compositor.pointer_location = (2500.0, 300.0).into();
+        compositor.focused_output = None;
+        compositor.handle_input(InputTraceEvent::Key {
+            keycode: keysyms::KEY_F7,
+            pressed: true,
+        });
+        assert_eq!(compositor.focused_output.as_deref(), Some("HEADLESS-1"));
+    }
+
+    #[test]
+    fn test_debug_overlay_position() {
+        use crate::compositor::virtual_outputs::VirtualOutputSpec;
+
+        let mut harness = TestCompositor::new();
+        let compositor = &mut harness.compositor;
+        compositor.add_virtual_output(&VirtualOutputSpec::parse("1280x1024").unwrap());
+        compositor.debug_overlay = true;
+
+        // Its background is the only translucent black rectangle
+        let overlay_x = |compositor: &WebWMCompositor| {
+            compositor
+                .render_bar_elements()
+                .into_iter()
+                .find_map(|element| match element {
+                    BarElement::Rectangle { geometry, color } if color == [0.0, 0.0, 0.0, 0.7] => {
+                        Some(geometry.loc.x)
+                    }
+                    _ => None,
+                })
+                .unwrap()
+        };
+        compositor.focused_output = Some("HEADLESS-1".into());
+        assert_eq!(overlay_x(compositor), 1920 - 268);
+        compositor.focused_output = Some("VIRTUAL-1".into());
+        assert_eq!(overlay_x(compositor), 1920 + 1280 - 268);
+    }
+
+    #[test]
+    fn test_shipped_gap_keys() {
+        use crate::compositor::input_trace::InputTraceEvent;
+        use smithay::input::keyboard::keysyms;
+        // xkb keycodes of the left Super and Shift keys
+        const SUPER_KEYCODE: u32 = 133;
+        const SHIFT_KEYCODE: u32 = 50;
+
+        let mut harness = TestCompositor::new();
+        let mut press = |keycode: u32| {
+            harness.compositor.handle_input(InputTraceEvent::Key {
+                keycode,
+                pressed: true,
+            });
+            let compositor = &harness.compositor;
+            let workspace = compositor.workspace_manager.active_workspace();
+            (
+                workspace
+                    .gaps_inner
+                    .unwrap_or(compositor.config.layout.gaps_inner),
+                workspace
+                    .gaps_outer
+                    .unwrap_or(compositor.config.layout.gaps_outer),
+            )
+        };
+
+        let (inner, outer) = press(SUPER_KEYCODE);
+        assert_eq!(press(keysyms::KEY_minus), (inner - 5, outer - 5));
+        assert_eq!(press(keysyms::KEY_equal), (inner, outer));
+        press(SHIFT_KEYCODE);
+        assert_eq!(press(keysyms::KEY_minus), (inner, outer - 5));
+    }
+
+    #[test]
+    fn test_shipped_stacking_keys() {
+        use crate::compositor::input_trace::InputTraceEvent;
+        use crate::compositor::workspace::LayoutMode;
+        use smithay::input::keyboard::keysyms;
+        // xkb keycode of the left Super key
+        const SUPER_KEYCODE: u32 = 133;
+
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .workspace_manager
+            .active_workspace_mut()
+            .layout_mode = LayoutMode::Floating;
+        let _alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        let beta = harness
+            .compositor
+            .workspace_manager
+            .focused_window()
+            .unwrap()
+            .clone();
+        let beta_on_top = |harness: &TestCompositor| {
+            harness.compositor.stacked_elements().last().copied() == Some(&beta)
+        };
+        let press = |harness: &mut TestCompositor, keycode: u32| {
+            harness.compositor.handle_input(InputTraceEvent::Key {
+                keycode,
+                pressed: true,
+            });
+        };
+        assert!(beta_on_top(&harness));
+
+        press(&mut harness, SUPER_KEYCODE);
+        press(&mut harness, keysyms::KEY_Page_Down);
+        assert!(!beta_on_top(&harness));
+        press(&mut harness, keysyms::KEY_Page_Up);
+        assert!(beta_on_top(&harness));
+    }
+
+    #[test]
+    fn test_shipped_focus_output_keys() {
+        use crate::compositor::input_trace::InputTraceEvent;
+        use crate::compositor::virtual_outputs::VirtualOutputSpec;
+        use smithay::input::keyboard::keysyms;
+        // xkb keycode of the left Super key
+        const SUPER_KEYCODE: u32 = 133;
+
+        let mut harness = TestCompositor::new();
+        let compositor = &mut harness.compositor;
+        compositor.add_virtual_output(&VirtualOutputSpec::parse("1280x1024").unwrap());
+        if let Some(desktop) = compositor.config.desktop.as_mut() {
+            desktop.pointer.warp_to_output = false;
+        }
+        let mut press = |keycode: u32| {
+            compositor.handle_input(InputTraceEvent::Key {
+                keycode,
+                pressed: true,
+            });
+            compositor.focused_output.clone()
+        };
+
+        press(SUPER_KEYCODE);
+        assert_eq!(press(keysyms::KEY_period).as_deref(), Some("VIRTUAL-1"));
+        assert_eq!(press(keysyms::KEY_comma).as_deref(), Some("HEADLESS-1"));
+    }
+
+    #[test]
+    fn test_shipped_master_ratio_keys() {
+        use crate::compositor::input_trace::InputTraceEvent;
+        use smithay::input::keyboard::keysyms;
+        // xkb keycode of the left Super key
+        const SUPER_KEYCODE: u32 = 133;
+
+        let mut harness = TestCompositor::new();
+        let compositor = &mut harness.compositor;
+        let ratio = compositor.master_ratio();
+        let mut press = |keycode: u32| {
+            compositor.handle_input(InputTraceEvent::Key {
+                keycode,
+                pressed: true,
+            });
+            compositor.master_ratio()
+        };
+
+        press(SUPER_KEYCODE);
+        assert!((press(keysyms::KEY_bracketleft) - (ratio - 0.05)).abs() < 0.001);
+        assert!((press(keysyms::KEY_bracketright) - ratio).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_shipped_split_ratio_keys() {
+        use crate::compositor::input_trace::InputTraceEvent;
+        use crate::compositor::workspace::LayoutMode;
+        use smithay::input::keyboard::keysyms;
+        // xkb keycodes of the left Super and Control keys
+        const SUPER_KEYCODE: u32 = 133;
+        const CONTROL_KEYCODE: u32 = 37;
+
+        let mut harness = TestCompositor::new();
+        let workspace = harness.compositor.workspace_manager.active_workspace_mut();
+        workspace.layout_mode = LayoutMode::Dwindle;
+        workspace.dwindle.default_ratio = 0.5;
+        let mut alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        harness.roundtrip(&mut alpha);
+        let (half, _) = alpha.state.configured_size;
+
+        for keycode in [SUPER_KEYCODE, CONTROL_KEYCODE, keysyms::KEY_bracketright] {
+            harness.compositor.handle_input(InputTraceEvent::Key {
+                keycode,
+                pressed: true,
+            });
+        }
+        harness.roundtrip(&mut alpha);
+        assert_ne!(alpha.state.configured_size.0, half);
+    }
+
+    #[test]
+    fn test_fullscreen_hides_bars() {
+        use crate::compositor::virtual_outputs::VirtualOutputSpec;
+
+        let mut harness = TestCompositor::new();
+        harness
+            .compositor
+            .add_virtual_output(&VirtualOutputSpec::parse("1280x1024").unwrap());
+        let mut alpha = harness.connect("alpha");
+        let _beta = harness.connect("beta");
+        assert!(!harness
+            .compositor
+            .render_output_bars(Some("HEADLESS-1"))
+            .is_empty());
+
+        // Nothing is drawn over the window, but the other output keeps its bar
+        alpha.toplevel.set_fullscreen(None);
+        harness.roundtrip(&mut alpha);
+        assert_eq!(alpha.state.configured_size, (1920, 1080));
+        assert!(harness
+            .compositor
+            .render_output_bars(Some("HEADLESS-1"))
+            .is_empty());
+        assert!(harness.compositor.render_tab_bars().is_empty());
+        assert!(!harness
+            .compositor
+            .render_output_bars(Some("VIRTUAL-1"))
+            .is_empty());
+
+        alpha.toplevel.unset_fullscreen();
+        harness.roundtrip(&mut alpha);
+        assert!(!harness
+            .compositor
+            .render_output_bars(Some("HEADLESS-1"))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_close_window_on_inactive_workspace() {
+        let mut harness = TestCompositor::new();
+        let alpha = harness.connect("alpha");
+        let workspace_manager = &mut harness.compositor.workspace_manager;
+        assert_eq!(workspace_manager.get_workspace(1).unwrap().len(), 1);
+        workspace_manager.switch_to_workspace(2);
+
+        harness.disconnect(alpha);
+        harness.wait_until(|compositor| {
+            compositor
+                .workspace_manager
+                .get_workspace(1)
+                .is_some_and(|workspace| workspace.is_empty())
+        });
+    }
+}
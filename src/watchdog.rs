@@ -0,0 +1,158 @@
+// Event loop watchdog
+//
+// Everything runs on the event loop, so one slow iteration, like a JS
+// callback stuck in a loop or a widget taking ages, freezes input and
+// rendering along with it. The loop marks the stages it goes through
+// (client requests, input, IPC, rendering, JS), and a watchdog thread
+// checks on them: once an iteration has been in a stage longer than
+// `<watchdog threshold>` in `<performance>`, it logs a warning naming the
+// stage, and when the loop moves on, how long it was stuck. Runaway JS
+// doesn't need the watchdog to end it: the runtime stops callbacks at
+// their time limit itself.
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Stage JS callbacks run in
+pub const JS_STAGE: &str = "js";
+
+#[derive(Debug, Default)]
+struct Shared {
+    /// Stages the loop is in, outermost first, with when each started
+    stages: Mutex<Vec<(&'static str, Instant)>>,
+    /// Start of the stuck iteration a warning was logged for
+    warned: Mutex<Option<Instant>>,
+}
+
+impl Shared {
+    /// The warning to log if the iteration has been stuck longer than
+    /// `threshold`, once per iteration
+    fn check(&self, threshold: Duration, now: Instant) -> Option<String> {
+        let stages = self.stages.lock().ok()?;
+        let &(_, started) = stages.first()?;
+
+        let stuck = now.saturating_duration_since(started);
+        let mut warned = self.warned.lock().ok()?;
+        if stuck < threshold || *warned == Some(started) {
+            return None;
+        }
+        *warned = Some(started);
+        let path: Vec<&str> = stages.iter().map(|(stage, _)| *stage).collect();
+        Some(format!(
+            "⚠️  Event loop stuck for {}ms in {}",
+            stuck.as_millis(),
+            path.join(" → ")
+        ))
+    }
+}
+
+/// Handle to the watchdog, cloned into whatever marks stages. The default
+/// one marks stages without a thread watching them.
+#[derive(Debug, Clone, Default)]
+pub struct Watchdog {
+    shared: Arc<Shared>,
+}
+
+/// Leaves its stage when dropped
+#[must_use]
+pub struct Stage {
+    shared: Arc<Shared>,
+}
+
+impl Watchdog {
+    /// Watch for stages running longer than `threshold`. A zero threshold
+    /// starts no thread.
+    pub fn start(threshold: Duration) -> Self {
+        let watchdog = Self::default();
+        if threshold.is_zero() {
+            return watchdog;
+        }
+
+        let shared = watchdog.shared.clone();
+        let interval = threshold / 4;
+        let spawned = thread::Builder::new()
+            .name("webwm-watchdog".to_string())
+            .spawn(move || loop {
+                thread::sleep(interval);
+                if let Some(warning) = shared.check(threshold, Instant::now()) {
+                    eprintln!("{}", warning);
+                }
+            });
+        match spawned {
+            Ok(_) => println!("✓ Watchdog on, warning after {}ms", threshold.as_millis()),
+            Err(e) => eprintln!("⚠️  Failed to start the watchdog: {}", e),
+        }
+        watchdog
+    }
+
+    /// Mark the loop as in `stage` until the returned guard is dropped
+    pub fn enter(&self, stage: &'static str) -> Stage {
+        if let Ok(mut stages) = self.shared.stages.lock() {
+            stages.push((stage, Instant::now()));
+        }
+        Stage {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Drop for Stage {
+    fn drop(&mut self) {
+        let Ok(mut stages) = self.shared.stages.lock() else {
+            return;
+        };
+        let Some((stage, started)) = stages.pop() else {
+            return;
+        };
+        if !stages.is_empty() {
+            return;
+        }
+        let Ok(mut warned) = self.shared.warned.lock() else {
+            return;
+        };
+        if *warned == Some(started) {
+            *warned = None;
+            eprintln!(
+                "⏱️  Event loop moved on after {}ms in {}",
+                started.elapsed().as_millis(),
+                stage
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_check() {
+        let watchdog = Watchdog::default();
+        let threshold = Duration::from_millis(500);
+        let now = Instant::now();
+
+        // Nothing running, nothing to say
+        assert_eq!(watchdog.shared.check(threshold, now), None);
+
+        let input = watchdog.enter("input");
+        let js = watchdog.enter(JS_STAGE);
+        let later = |ms: u64| Instant::now() + Duration::from_millis(ms);
+        assert_eq!(watchdog.shared.check(threshold, later(100)), None);
+
+        // One warning per stuck iteration, naming the stage
+        let warning = watchdog.shared.check(threshold, later(600));
+        assert!(warning.is_some_and(|w| w.ends_with("in input → js")));
+        assert_eq!(watchdog.shared.check(threshold, later(700)), None);
+
+        drop(js);
+        drop(input);
+        assert!(watchdog.shared.stages.lock().unwrap().is_empty());
+        assert_eq!(*watchdog.shared.warned.lock().unwrap(), None);
+
+        // The next iteration starts over
+        let _render = watchdog.enter("render");
+        let warning = watchdog.shared.check(threshold, later(600));
+        assert!(warning.is_some_and(|w| w.ends_with("in render")));
+    }
+}
@@ -0,0 +1,417 @@
+// Web settings page
+//
+// With `<web-ui port="8377" />` in desktop.xml the compositor serves a small
+// settings page at http://127.0.0.1:8377 for editing keybindings, theme
+// variables and bar widgets. The page posts IPC requests to /api, which are
+// dispatched exactly like webwm-msg's, so changes apply live; like the IPC
+// commands, they are not saved to the config files.
+//
+// The server only listens on loopback, but any web page open in a browser
+// can reach loopback too. So requests must name this server in Host and,
+// if they have one, Origin (which stops DNS rebinding and cross-site
+// posts), /api only takes application/json (which browsers won't send
+// cross-origin without a preflight, and preflights are refused), and only
+// the settings commands are accepted. Other local users and processes can
+// reach loopback without a browser, and keybind-set can bind a command, so
+// /api also wants the secret made up at startup in an X-WebWM-Token
+// header. The page takes it from its URL's fragment: the link printed at
+// startup and written, readable only by the user, to
+// `$XDG_RUNTIME_DIR/webwm-settings-url`.
+//
+// `<metrics port="9477" />` under `<performance>` starts a second server
+// the same way, answering only GET /metrics with Prometheus metrics.
+
+use smithay::reexports::calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::compositor::WebWMCompositor;
+use crate::ipc::{self, IpcRequest, IpcResponse};
+
+const INDEX_HTML: &str = include_str!("web_ui.html");
+
+/// IPC commands the page may send
+const ALLOWED_COMMANDS: &[&str] = &[
+    "version",
+    "keybind-list",
+    "keybind-set",
+    "keybind-remove",
+    "theme",
+    "theme-set",
+    "bar-list",
+    "bar-widgets",
+];
+
+/// Requests are small JSON objects; anything bigger is refused
+const MAX_BODY: usize = 64 * 1024;
+
+/// Secret /api requests must carry, made up when the page is first served
+static TOKEN: OnceLock<String> = OnceLock::new();
+
+#[derive(Debug, Default, PartialEq)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    /// Header names are lowercase
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Read one HTTP/1.1 request: request line, headers and a
+/// Content-Length body
+pub fn read_request(reader: &mut impl BufRead) -> Result<HttpRequest, String> {
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read request: {}", e))?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err("Malformed request line".to_string());
+    };
+    let mut request = HttpRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        ..Default::default()
+    };
+
+    loop {
+        line.clear();
+        reader
+            .read_line(&mut line)
+            .map_err(|e| format!("Failed to read headers: {}", e))?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| format!("Malformed header '{}'", header))?;
+        request
+            .headers
+            .push((name.trim().to_lowercase(), value.trim().to_string()));
+    }
+
+    let length: usize = request
+        .header("content-length")
+        .map(|length| length.parse().map_err(|_| "Invalid Content-Length"))
+        .transpose()?
+        .unwrap_or(0);
+    if length > MAX_BODY {
+        return Err("Request body too large".to_string());
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| format!("Failed to read body: {}", e))?;
+    request.body = String::from_utf8(body).map_err(|_| "Body is not UTF-8")?;
+
+    Ok(request)
+}
+
+/// Refuse requests that weren't made by a page of this server
+pub fn check_origin(request: &HttpRequest, port: u16) -> Result<(), String> {
+    let hosts = [format!("127.0.0.1:{}", port), format!("localhost:{}", port)];
+
+    let host = request.header("host").unwrap_or_default();
+    if !hosts.iter().any(|h| h == host) {
+        return Err(format!("Unexpected Host '{}'", host));
+    }
+    if let Some(origin) = request.header("origin") {
+        if !hosts.iter().any(|h| origin == format!("http://{}", h)) {
+            return Err(format!("Unexpected Origin '{}'", origin));
+        }
+    }
+    Ok(())
+}
+
+/// Refuse /api requests without the session's token
+pub fn check_token(request: &HttpRequest, token: &str) -> Result<(), String> {
+    let given = request.header("x-webwm-token").unwrap_or_default();
+    // Compared in full, so the time taken says nothing about it
+    let differs = given
+        .bytes()
+        .zip(token.bytes())
+        .fold(0, |acc, (a, b)| acc | (a ^ b));
+    if token.is_empty() || given.len() != token.len() || differs != 0 {
+        return Err("Missing or wrong X-WebWM-Token".to_string());
+    }
+    Ok(())
+}
+
+/// 32 random hex digits
+fn make_token() -> std::io::Result<String> {
+    let mut bytes = [0u8; 16];
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Where the page's link is written for the user to open
+fn url_file() -> PathBuf {
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(runtime_dir).join("webwm-settings-url")
+}
+
+/// Answers one request that passed `check_origin`
+type Handler = fn(&HttpRequest, &TcpStream, &mut WebWMCompositor);
+
+/// Serve the settings page and its API. Returns the page's link, with the
+/// token in it.
+pub fn init_web_ui(
+    loop_handle: &LoopHandle<'static, WebWMCompositor>,
+    port: u16,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let token = TOKEN.get_or_init(|| make_token().unwrap_or_default());
+    if token.is_empty() {
+        return Err("Failed to make up a token for the settings page".into());
+    }
+    let address = listen(loop_handle, port, handle_settings)?;
+    let url = format!("http://{}/#token={}", address, token);
+
+    // Only the user may read it
+    let path = url_file();
+    fs::remove_file(&path).ok();
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", url))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(url)
+}
+
+/// Serve Prometheus metrics at /metrics
+pub fn init_metrics_endpoint(
+    loop_handle: &LoopHandle<'static, WebWMCompositor>,
+    port: u16,
+) -> Result<SocketAddr, Box<dyn std::error::Error>> {
+    listen(loop_handle, port, handle_metrics)
+}
+
+/// Listen on loopback and serve requests from the event loop
+fn listen(
+    loop_handle: &LoopHandle<'static, WebWMCompositor>,
+    port: u16,
+    handler: Handler,
+) -> Result<SocketAddr, Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    listener.set_nonblocking(true)?;
+    let address = listener.local_addr()?;
+
+    loop_handle.insert_source(
+        Generic::new(listener, Interest::READ, Mode::Level),
+        move |_, listener, compositor| {
+            loop {
+                match listener.accept() {
+                    Ok((stream, _)) => handle_connection(stream, compositor, port, handler),
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        eprintln!("Web UI accept error: {}", e);
+                        break;
+                    }
+                }
+            }
+            Ok(PostAction::Continue)
+        },
+    )?;
+
+    Ok(address)
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    compositor: &mut WebWMCompositor,
+    port: u16,
+    handler: Handler,
+) {
+    // Like IPC clients, a slow browser must not stall the event loop
+    stream.set_nonblocking(false).ok();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .ok();
+    stream
+        .set_write_timeout(Some(Duration::from_millis(100)))
+        .ok();
+
+    let request = match read_request(&mut BufReader::new(&stream)) {
+        Ok(request) => request,
+        Err(e) => return respond(&stream, "400 Bad Request", "text/plain", &e),
+    };
+    if let Err(e) = check_origin(&request, port) {
+        eprintln!("⚠️  Refused HTTP request on port {}: {}", port, e);
+        return respond(&stream, "403 Forbidden", "text/plain", &e);
+    }
+
+    handler(&request, &stream, compositor);
+}
+
+fn handle_settings(request: &HttpRequest, stream: &TcpStream, compositor: &mut WebWMCompositor) {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => respond(stream, "200 OK", "text/html; charset=utf-8", INDEX_HTML),
+        ("POST", "/api") => {
+            if let Err(e) = check_token(request, TOKEN.get().map_or("", String::as_str)) {
+                eprintln!("⚠️  Refused settings request: {}", e);
+                return respond(stream, "403 Forbidden", "text/plain", &e);
+            }
+            let json = request
+                .header("content-type")
+                .is_some_and(|t| t.starts_with("application/json"));
+            if !json {
+                return respond(
+                    stream,
+                    "415 Unsupported Media Type",
+                    "text/plain",
+                    "Expected application/json",
+                );
+            }
+
+            let response = match serde_json::from_str::<IpcRequest>(&request.body) {
+                Ok(request) if ALLOWED_COMMANDS.contains(&request.command.as_str()) => {
+                    ipc::dispatch(compositor, &request)
+                }
+                Ok(request) => IpcResponse::error(format!(
+                    "'{}' is not available from the web UI",
+                    request.command
+                )),
+                Err(e) => IpcResponse::error(format!("Invalid request: {}", e)),
+            };
+            let body = serde_json::to_string(&response).unwrap_or_default();
+            respond(stream, "200 OK", "application/json", &body)
+        }
+        (_, "/") | (_, "/api") => respond(
+            stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed",
+        ),
+        _ => respond(stream, "404 Not Found", "text/plain", "Not found"),
+    }
+}
+
+fn handle_metrics(request: &HttpRequest, stream: &TcpStream, compositor: &mut WebWMCompositor) {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/metrics") => respond(
+            stream,
+            "200 OK",
+            "text/plain; version=0.0.4",
+            &compositor.prometheus_metrics(),
+        ),
+        (_, "/metrics") => respond(
+            stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed",
+        ),
+        _ => respond(stream, "404 Not Found", "text/plain", "Not found"),
+    }
+}
+
+fn respond(mut stream: &TcpStream, status: &str, content_type: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         X-Frame-Options: DENY\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    if let Err(e) = stream.write_all(response.as_bytes()) {
+        eprintln!("Web UI write error: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_request() {
+        let raw = "POST /api HTTP/1.1\r\nHost: 127.0.0.1:8377\r\n\
+                   Content-Type: application/json\r\nContent-Length: 21\r\n\r\n\
+                   {\"command\":\"theme\"}\r\n";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/api");
+        assert_eq!(request.header("content-type"), Some("application/json"));
+        assert_eq!(request.body, "{\"command\":\"theme\"}\r\n");
+
+        let oversized = format!(
+            "POST /api HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY + 1
+        );
+        assert!(read_request(&mut oversized.as_bytes()).is_err());
+        assert!(read_request(&mut "\r\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_check_origin() {
+        let request = |headers: &[(&str, &str)]| HttpRequest {
+            method: "POST".to_string(),
+            path: "/api".to_string(),
+            headers: headers
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect(),
+            body: String::new(),
+        };
+
+        assert!(check_origin(&request(&[("host", "localhost:8377")]), 8377).is_ok());
+        assert!(check_origin(
+            &request(&[
+                ("host", "127.0.0.1:8377"),
+                ("origin", "http://127.0.0.1:8377")
+            ]),
+            8377
+        )
+        .is_ok());
+
+        // DNS rebinding and cross-site requests
+        assert!(check_origin(&request(&[("host", "evil.example:8377")]), 8377).is_err());
+        assert!(check_origin(
+            &request(&[
+                ("host", "127.0.0.1:8377"),
+                ("origin", "https://evil.example")
+            ]),
+            8377
+        )
+        .is_err());
+        assert!(check_origin(&request(&[]), 8377).is_err());
+    }
+
+    #[test]
+    fn test_check_token() {
+        let request = |token: Option<&str>| HttpRequest {
+            method: "POST".to_string(),
+            path: "/api".to_string(),
+            headers: token
+                .map(|token| ("x-webwm-token".to_string(), token.to_string()))
+                .into_iter()
+                .collect(),
+            body: String::new(),
+        };
+        let token = make_token().unwrap();
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, make_token().unwrap());
+
+        assert!(check_token(&request(Some(&token)), &token).is_ok());
+        assert!(check_token(&request(None), &token).is_err());
+        assert!(check_token(&request(Some(&token[..31])), &token).is_err());
+        assert!(check_token(&request(Some("0".repeat(32).as_str())), &token).is_err());
+        // Never with no token made up
+        assert!(check_token(&request(Some("")), "").is_err());
+    }
+}